@@ -0,0 +1,34 @@
+//! Reading asset files from the assets of the APK on Android.
+
+use std::{ffi::CString, io::Read, path::Path, sync::OnceLock};
+
+pub use ndk::asset::AssetManager;
+
+static ASSET_MANAGER: OnceLock<AssetManager> = OnceLock::new();
+
+/// Sets the asset manager of the activity to read relative asset files from.
+///
+/// The engine does this on startup, available using `AndroidApp::asset_manager`.
+pub fn set_asset_manager(manager: AssetManager) {
+    let _ = ASSET_MANAGER.set(manager);
+}
+
+/// Reads a file from the assets directory of the APK.
+pub(crate) fn read(path: &Path) -> std::io::Result<Vec<u8>> {
+    let manager = ASSET_MANAGER.get().ok_or_else(|| {
+        std::io::Error::other("The asset manager of the Android activity is not set.")
+    })?;
+    let name = CString::new(path.to_string_lossy().as_bytes()).map_err(std::io::Error::other)?;
+    let mut asset = manager.open(&name).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!(
+                "The APK does not contain the asset file {}.",
+                path.display()
+            ),
+        )
+    })?;
+    let mut data = Vec::with_capacity(asset.length());
+    asset.read_to_end(&mut data)?;
+    Ok(data)
+}
@@ -96,16 +96,40 @@
 //! `asset("sounds/monsters/zombie/growl.oga")`
 //!
 //! and get the data to be used in the game engine.
+//!
+//! ## Patches and DLC
+//!
+//! Additional asset files made the same way can be mounted at runtime using [`mount_pack`].
+//! Assets in a mounted pack override the assets with the same path of packs with a lower priority.
+//! The assets packed at build time have a priority of 0.
+//!
+//! `mount_pack("dlc/winter.assets", 10).await?` makes `asset("textures/environment/stone.png")` return the snowy
+//! stone of the DLC, while all other assets still come from the base game.
+//!
+//! ## Mods
+//!
+//! Players can put mods with their own packs into a mods directory loaded using [`mods::load_mods`].
+//! See the [`mods`] module for the layout of the directory.
+//!
+//! ## Android
+//!
+//! On Android the asset files get read from the assets directory of the APK, so the packed files have to be copied there
+//! when building the package. Relative paths given to [`mount_pack`] point into the APK as well.
+
+#[cfg(target_os = "android")]
+pub mod android;
+pub mod mods;
 
 #[allow(unused_imports)]
 use std::{
     io::{Read, Write},
+    path::{Path, PathBuf},
     sync::{Arc, LazyLock},
 };
 
 use smol::fs;
 
-use ahash::HashMap;
+use ahash::{HashMap, HashSet};
 
 use anyhow::{anyhow, Result};
 use parking_lot::RwLock;
@@ -157,6 +181,35 @@ pub enum Compression {
 }
 
 impl Compression {
+    /// Guesses the compression of an asset file from its first bytes.
+    ///
+    /// Returns an error in case the file is compressed using an algorithm whose feature is not enabled.
+    pub fn detect(buffer: &[u8]) -> Result<Self> {
+        let name = match buffer {
+            [0x1f, 0x8b, ..] => "deflate",
+            [b'B', b'Z', b'h', ..] => "bzip2",
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => "zstd",
+            [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => "lzma",
+            [0x04, 0x22, 0x4d, 0x18, ..] => "lz4",
+            _ => return Ok(Self::None),
+        };
+        match name {
+            #[cfg(feature = "deflate")]
+            "deflate" => Ok(Self::Deflate),
+            #[cfg(feature = "bzip2")]
+            "bzip2" => Ok(Self::Bwt),
+            #[cfg(feature = "zstd")]
+            "zstd" => Ok(Self::Zstd),
+            #[cfg(feature = "lzma")]
+            "lzma" => Ok(Self::Lzma),
+            #[cfg(feature = "lz4")]
+            "lz4" => Ok(Self::Lz4),
+            name => Err(anyhow!(
+                "The `{name}` feature is required to read this asset file."
+            )),
+        }
+    }
+
     /// Decompresses the given buffer using the compression algorithm of self.
     pub fn decompress(&self, buffer: &[u8]) -> Result<Vec<u8>> {
         #[allow(unused_assignments)]
@@ -227,6 +280,99 @@ pub fn asset_blocking(path: &str) -> Result<Arc<[u8]>, AssetError> {
     smol::block_on(async { CACHE.get_or_load(path).await })
 }
 
+/// Returns the contents of a file outside of the asset packs, like a file dropped into the window, caching it like an
+/// asset.
+///
+/// Relative paths are relative to the working directory. The file gets read once and then returned from the cache until
+/// [forget_file] or [clear_cache] gets called.
+pub async fn file(path: impl AsRef<Path>) -> Result<Arc<[u8]>, AssetError> {
+    let key = file_key(path.as_ref())?;
+    if let Some(data) = CACHE.map.read().get(&key) {
+        return Ok(data.clone());
+    }
+    let data: Arc<[u8]> = fs::read(path.as_ref())
+        .await
+        .map_err(AssetError::Io)?
+        .into();
+    Ok(CACHE.map.write().entry(key).or_insert(data).clone())
+}
+
+/// Exactly the same as [file] but not async and blocking.
+pub fn file_blocking(path: impl AsRef<Path>) -> Result<Arc<[u8]>, AssetError> {
+    smol::block_on(file(path))
+}
+
+/// Removes a file loaded using [file] from the cache, so the next access reads it from disk again.
+pub fn forget_file(path: impl AsRef<Path>) -> Result<(), AssetError> {
+    let key = file_key(path.as_ref())?;
+    CACHE.map.write().remove(&key);
+    Ok(())
+}
+
+/// The cache key of a file outside of the asset packs, which can not collide with the keys of assets.
+fn file_key(path: &Path) -> Result<String, AssetError> {
+    let path = std::path::absolute(path).map_err(AssetError::Io)?;
+    Ok(format!("file://{}", path.display()))
+}
+
+/// Mounts an asset pack file, making its assets override the assets of packs with a lower priority.
+///
+/// The assets packed at build time have a priority of 0, so a patch or DLC should use a higher one.
+/// Packs with a negative priority only provide assets missing everywhere else.
+/// Of packs with the same priority the one mounted last wins.
+///
+/// Relative paths are relative to the directory of the binary like the packs made at build time.
+/// Mounting a path again replaces the previous mount.
+///
+/// Assets overridden by the pack get removed from the cache, so the next access returns the new version.
+pub async fn mount_pack(path: impl AsRef<Path>, priority: i32) -> Result<(), AssetError> {
+    let path = pack_path(path.as_ref())?;
+    let map = read_pack(&path, None).await?;
+    let pack = MountedPack {
+        keys: map.into_keys().collect(),
+        path,
+        priority,
+    };
+
+    let mut packs = PACKS.write();
+    packs.retain(|mounted| mounted.path != pack.path);
+    CACHE.invalidate(&pack.keys);
+    let index = packs
+        .iter()
+        .position(|mounted| mounted.priority <= priority)
+        .unwrap_or(packs.len());
+    packs.insert(index, pack);
+    Ok(())
+}
+
+/// Exactly the same as [mount_pack] but not async and blocking.
+pub fn mount_pack_blocking(path: impl AsRef<Path>, priority: i32) -> Result<(), AssetError> {
+    smol::block_on(mount_pack(path, priority))
+}
+
+/// Unmounts a pack mounted using [mount_pack], making the assets it overrode available again.
+///
+/// Returns false in case the pack was not mounted.
+pub fn unmount_pack(path: impl AsRef<Path>) -> Result<bool, AssetError> {
+    let path = pack_path(path.as_ref())?;
+    let mut packs = PACKS.write();
+    let Some(index) = packs.iter().position(|mounted| mounted.path == path) else {
+        return Ok(false);
+    };
+    let pack = packs.remove(index);
+    CACHE.invalidate(&pack.keys);
+    Ok(true)
+}
+
+/// Returns the paths and priorities of all mounted packs, starting with the highest priority.
+pub fn mounted_packs() -> Vec<(PathBuf, i32)> {
+    PACKS
+        .read()
+        .iter()
+        .map(|pack| (pack.path.clone(), pack.priority))
+        .collect()
+}
+
 /// Clears the asset cache for unused keys and removes them. When calling the `asset` function for an unloaded asset it takes the same time
 /// as it did first again.
 pub fn clear_cache() {
@@ -250,33 +396,21 @@ impl Cache {
         // else load it into the cache.
 
         // Error when the key does not exist,
-        let Some((file_path, compression)) = MAP.get(key) else {
+        let Some((asset_path, compression)) = source(key)? else {
             return Err(AssetError::NotListed);
         };
 
-        // Path where the key data is stored:
-        let asset_path = {
-            let application_path = std::env::current_exe().map_err(AssetError::Io)?;
-            let application_path = application_path.parent().unwrap();
-            application_path.join(file_path)
-        };
-
         // Decompressed and deserialized HashMap of keys and data
-        let map: HashMap<String, Vec<u8>> = {
-            // Read from disk,
-            let data = fs::read(asset_path).await.map_err(AssetError::Io)?;
-            // Uncompress if it has compression or return an error if it does not have a supported format.
-            let data = compression
-                .decompress(&data)
-                .map_err(AssetError::UnsupportedFormat)?;
-
-            bincode::deserialize(&data).map_err(|x| AssetError::UnsupportedFormat(x.into()))?
-        };
+        let map = read_pack(&asset_path, compression).await?;
 
         let mut result: Option<Arc<[u8]>> = None;
 
         // Load to cache in a way quickly accessable.
         for (key2, value) in map {
+            // Assets overridden by another pack must not end up in the cache.
+            if source(&key2)?.map(|(path, _)| path).as_ref() != Some(&asset_path) {
+                continue;
+            }
             let mut write = self.map.write();
 
             let data: Arc<[u8]> = value.into();
@@ -297,6 +431,11 @@ impl Cache {
         }
     }
 
+    /// Removes the given keys from the cache.
+    fn invalidate(&self, keys: &HashSet<String>) {
+        self.map.write().retain(|key, _| !keys.contains(key));
+    }
+
     /// Removes all the resources that are not used.
     pub fn clear(&self) {
         let mut map = self.map.write();
@@ -316,3 +455,84 @@ impl Default for Cache {
 
 /// The cache holding each asset.
 static CACHE: LazyLock<Cache> = LazyLock::new(Cache::default);
+
+/// An asset file mounted at runtime.
+#[derive(Debug)]
+struct MountedPack {
+    path: PathBuf,
+    priority: i32,
+    keys: HashSet<String>,
+}
+
+/// The mounted packs ordered from the highest to the lowest priority.
+static PACKS: LazyLock<RwLock<Vec<MountedPack>>> = LazyLock::new(RwLock::default);
+
+/// The directory of the binary all asset files are relative to.
+static APPLICATION_DIR: LazyLock<Option<PathBuf>> = LazyLock::new(|| {
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+});
+
+/// Returns the directory of the binary.
+fn application_dir() -> Result<PathBuf, AssetError> {
+    APPLICATION_DIR.clone().ok_or_else(|| {
+        AssetError::Io(std::io::Error::other(
+            "The directory of the binary could not be found.",
+        ))
+    })
+}
+
+/// Returns the path of an asset file, relative paths being relative to the directory of the binary.
+///
+/// On Android relative paths stay relative, pointing into the assets of the APK.
+fn pack_path(path: &Path) -> Result<PathBuf, AssetError> {
+    #[cfg(target_os = "android")]
+    if path.is_relative() {
+        return Ok(path.to_path_buf());
+    }
+    Ok(application_dir()?.join(path))
+}
+
+/// Returns the path of the file the asset with the given key gets loaded from,
+/// along with its compression in case it is one of the files packed at build time.
+fn source(key: &str) -> Result<Option<(PathBuf, Option<Compression>)>, AssetError> {
+    let packs = PACKS.read();
+    let pack = packs.iter().find(|pack| pack.keys.contains(key));
+    Ok(match (pack, MAP.get(key)) {
+        (Some(pack), Some(_)) if pack.priority >= 0 => Some((pack.path.clone(), None)),
+        (_, Some((file_path, compression))) => Some((pack_path(file_path)?, Some(*compression))),
+        (Some(pack), None) => Some((pack.path.clone(), None)),
+        (None, None) => None,
+    })
+}
+
+/// Reads, decompresses and deserializes an asset file.
+///
+/// The compression gets detected in case it is not known.
+async fn read_pack(
+    path: &Path,
+    compression: Option<Compression>,
+) -> Result<HashMap<String, Vec<u8>>, AssetError> {
+    // Read from disk,
+    #[cfg(not(target_os = "android"))]
+    let data = fs::read(path).await.map_err(AssetError::Io)?;
+    // or from the APK.
+    #[cfg(target_os = "android")]
+    let data = if path.is_relative() {
+        android::read(path)
+    } else {
+        fs::read(path).await
+    }
+    .map_err(AssetError::Io)?;
+    // Uncompress if it has compression or return an error if it does not have a supported format.
+    let compression = match compression {
+        Some(compression) => compression,
+        None => Compression::detect(&data).map_err(AssetError::UnsupportedFormat)?,
+    };
+    let data = compression
+        .decompress(&data)
+        .map_err(AssetError::UnsupportedFormat)?;
+
+    bincode::deserialize(&data).map_err(|x| AssetError::UnsupportedFormat(x.into()))
+}
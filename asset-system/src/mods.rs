@@ -0,0 +1,370 @@
+//! Loading mods from a directory, each mounting its own asset packs over the assets of the game.
+//!
+//! ## Mods directory layout
+//!
+//! mods/
+//! - mods.toml (written by the engine, lists disabled mods)
+//! - better-trees
+//!   - mod.toml
+//!   - trees.pack
+//! - hd-leaves
+//!   - mod.toml
+//!   - leaves.pack
+//!
+//! The name of the directory of a mod is its id, which other mods use to depend on it.
+//!
+//! ## mod.toml
+//!
+//! ```toml
+//! name = "Better Trees"
+//! version = "1.2.0"
+//! description = "Replaces every tree of the game."
+//! authors = ["someone"]
+//! # Mods getting loaded before this one.
+//! dependencies = ["hd-leaves"]
+//! # Asset packs made by the build script, relative to the mod directory.
+//! packs = ["trees.pack"]
+//! # Files for the game to run, as the engine does not run scripts itself.
+//! scripts = ["scripts/trees.lua"]
+//! ```
+//!
+//! Only `name` and `version` are required.
+//!
+//! ## Load order
+//!
+//! Mods get loaded after their dependencies and otherwise in alphabetical order of their ids.
+//! Every mod overrides the assets of the game and of the mods loaded before it.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::{mount_pack, unmount_pack, AssetError, PACKS};
+
+/// The name of the manifest file inside of the directory of each mod.
+pub const MANIFEST_FILE: &str = "mod.toml";
+/// The name of the file in the mods directory storing which mods are disabled.
+pub const SETTINGS_FILE: &str = "mods.toml";
+/// The pack priority of the first mod in the load order. Each following mod gets one more.
+pub const BASE_PRIORITY: i32 = 100;
+
+/// The description of a mod read from its `mod.toml`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModManifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// The ids of the mods this mod requires.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// The asset packs of the mod relative to its directory.
+    #[serde(default)]
+    pub packs: Vec<PathBuf>,
+    /// Scripts of the mod relative to its directory, for the game to run.
+    #[serde(default)]
+    pub scripts: Vec<PathBuf>,
+}
+
+/// A mod found in the mods directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mod {
+    /// The name of the directory of the mod.
+    pub id: String,
+    pub directory: PathBuf,
+    pub manifest: ModManifest,
+    /// Whether the player enabled the mod. Enabled mods may still fail to load, see [ModReport::errors].
+    pub enabled: bool,
+}
+
+/// An asset contained in multiple loaded mods.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModConflict {
+    /// The key of the asset.
+    pub asset: String,
+    /// The ids of the mods containing the asset in load order. The last one wins.
+    pub mods: Vec<String>,
+}
+
+/// An error that can occur loading mods.
+#[derive(thiserror::Error, Debug)]
+pub enum ModError {
+    /// The mods directory or a manifest could not be read.
+    #[error("There was a problem reading the mods directory: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The manifest of the mod `{id}` is invalid: {error}")]
+    Manifest { id: String, error: toml::de::Error },
+    #[error("The mod `{id}` depends on `{dependency}`, which is missing or disabled.")]
+    MissingDependency { id: String, dependency: String },
+    #[error("The mods {0:?} depend on each other in a cycle.")]
+    DependencyCycle(Vec<String>),
+    #[error("A pack of the mod `{id}` could not be mounted: {error}")]
+    Pack { id: String, error: AssetError },
+    #[error("The mod list could not be saved: {0}")]
+    Settings(#[from] toml::ser::Error),
+    #[error(transparent)]
+    Asset(#[from] AssetError),
+    /// [set_mod_enabled] got called before loading mods using [load_mods].
+    #[error("No mods directory has been loaded.")]
+    NotLoaded,
+}
+
+/// The outcome of loading the mods directory.
+#[derive(Debug, Default)]
+pub struct ModReport {
+    /// The ids of all loaded mods in the order they got mounted.
+    pub load_order: Vec<String>,
+    /// Assets replaced by more than one mod.
+    pub conflicts: Vec<ModConflict>,
+    /// Mods that could not be loaded, along with the reason.
+    pub errors: Vec<ModError>,
+}
+
+/// The list of disabled mods stored in the mods directory.
+#[derive(Default, Serialize, Deserialize)]
+struct ModSettings {
+    #[serde(default)]
+    disabled: Vec<String>,
+}
+
+#[derive(Default)]
+struct ModState {
+    directory: Option<PathBuf>,
+    mods: Vec<Mod>,
+    /// The packs mounted for mods, unmounted again before reloading.
+    mounted: Vec<PathBuf>,
+    report: Arc<ModReport>,
+}
+
+static MODS: LazyLock<RwLock<ModState>> = LazyLock::new(RwLock::default);
+
+/// Scans the mods directory and mounts the packs of every enabled mod, replacing the mods loaded before.
+///
+/// Relative paths are relative to the directory of the binary. A missing directory counts as having no mods.
+///
+/// Mods with missing dependencies or dependency cycles get skipped and listed in the errors of the report,
+/// the same way as packs that could not be mounted.
+pub async fn load_mods(directory: impl AsRef<Path>) -> Result<Arc<ModReport>, ModError> {
+    let directory = crate::application_dir()?.join(directory);
+
+    let previous = std::mem::take(&mut MODS.write().mounted);
+    for pack in previous {
+        let _ = unmount_pack(pack);
+    }
+
+    let settings = read_settings(&directory)?;
+    let mut report = ModReport::default();
+    let mut mods = scan(&directory, &settings, &mut report)?;
+    let order = load_order(&mods, &mut report);
+
+    let mut mounted = vec![];
+    for (index, id) in order.iter().enumerate() {
+        let Some(entry) = mods.iter().find(|entry| &entry.id == id) else {
+            continue;
+        };
+        let priority = BASE_PRIORITY + index as i32;
+        let mut failed = false;
+        for pack in &entry.manifest.packs {
+            let path = entry.directory.join(pack);
+            match mount_pack(&path, priority).await {
+                Ok(()) => mounted.push(path),
+                Err(error) => {
+                    report.errors.push(ModError::Pack {
+                        id: id.clone(),
+                        error,
+                    });
+                    failed = true;
+                }
+            }
+        }
+        if !failed {
+            report.load_order.push(id.clone());
+        }
+    }
+    report.conflicts = conflicts(&mods, &mounted);
+
+    // Disabled mods come after the loaded ones, so menus can list them in load order.
+    mods.sort_by_key(|entry| {
+        order
+            .iter()
+            .position(|id| *id == entry.id)
+            .unwrap_or(usize::MAX)
+    });
+
+    let report = Arc::new(report);
+    *MODS.write() = ModState {
+        directory: Some(directory),
+        mods,
+        mounted,
+        report: report.clone(),
+    };
+    Ok(report)
+}
+
+/// Exactly the same as [load_mods] but not async and blocking.
+pub fn load_mods_blocking(directory: impl AsRef<Path>) -> Result<Arc<ModReport>, ModError> {
+    smol::block_on(load_mods(directory))
+}
+
+/// Enables or disables a mod, stores the choice in the mods directory and loads all mods again.
+pub async fn set_mod_enabled(id: &str, enabled: bool) -> Result<Arc<ModReport>, ModError> {
+    let directory = MODS.read().directory.clone().ok_or(ModError::NotLoaded)?;
+    let mut settings = read_settings(&directory)?;
+    settings.disabled.retain(|disabled| disabled != id);
+    if !enabled {
+        settings.disabled.push(id.to_string());
+    }
+    std::fs::write(
+        directory.join(SETTINGS_FILE),
+        toml::to_string_pretty(&settings)?,
+    )?;
+    load_mods(directory).await
+}
+
+/// Returns all mods found by the last [load_mods] call, the loaded ones first in load order.
+pub fn mods() -> Vec<Mod> {
+    MODS.read().mods.clone()
+}
+
+/// Returns the report of the last [load_mods] call.
+pub fn mod_report() -> Arc<ModReport> {
+    MODS.read().report.clone()
+}
+
+fn read_settings(directory: &Path) -> Result<ModSettings, ModError> {
+    match std::fs::read_to_string(directory.join(SETTINGS_FILE)) {
+        // A broken settings file should not lock players out of their mods.
+        Ok(settings) => Ok(toml::from_str(&settings).unwrap_or_default()),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(ModSettings::default()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Reads the manifests of all mods in the directory, sorted by id.
+fn scan(
+    directory: &Path,
+    settings: &ModSettings,
+    report: &mut ModReport,
+) -> Result<Vec<Mod>, ModError> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error.into()),
+    };
+
+    let mut mods = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        let manifest_path = path.join(MANIFEST_FILE);
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let id = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let manifest = match std::fs::read_to_string(&manifest_path) {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                report.errors.push(error.into());
+                continue;
+            }
+        };
+        match toml::from_str(&manifest) {
+            Ok(manifest) => mods.push(Mod {
+                enabled: !settings.disabled.contains(&id),
+                id,
+                directory: path,
+                manifest,
+            }),
+            Err(error) => report.errors.push(ModError::Manifest { id, error }),
+        }
+    }
+    mods.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(mods)
+}
+
+/// Orders the enabled mods after their dependencies, leaving out mods with missing dependencies or cycles.
+fn load_order(mods: &[Mod], report: &mut ModReport) -> Vec<String> {
+    let enabled: BTreeMap<&str, &Mod> = mods
+        .iter()
+        .filter(|entry| entry.enabled)
+        .map(|entry| (entry.id.as_str(), entry))
+        .collect();
+
+    let mut order: Vec<String> = vec![];
+    let mut skipped: Vec<String> = vec![];
+    let mut remaining: Vec<&Mod> = enabled.values().copied().collect();
+    while !remaining.is_empty() {
+        let before = remaining.len();
+        remaining.retain(|entry| {
+            let dependencies = &entry.manifest.dependencies;
+            if let Some(dependency) = dependencies.iter().find(|dependency| {
+                !enabled.contains_key(dependency.as_str()) || skipped.contains(dependency)
+            }) {
+                report.errors.push(ModError::MissingDependency {
+                    id: entry.id.clone(),
+                    dependency: dependency.clone(),
+                });
+                skipped.push(entry.id.clone());
+                return false;
+            }
+            if dependencies
+                .iter()
+                .all(|dependency| order.contains(dependency))
+            {
+                order.push(entry.id.clone());
+                return false;
+            }
+            true
+        });
+        if remaining.len() == before {
+            // Nothing left can be loaded, so the remaining mods wait on each other.
+            report.errors.push(ModError::DependencyCycle(
+                remaining.iter().map(|entry| entry.id.clone()).collect(),
+            ));
+            break;
+        }
+    }
+    order
+}
+
+/// Finds the assets contained in the packs of more than one loaded mod.
+fn conflicts(mods: &[Mod], mounted: &[PathBuf]) -> Vec<ModConflict> {
+    let packs = PACKS.read();
+    let mut assets: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    // The packs are ordered from the highest priority down, so iterate in reverse to get the load order.
+    for pack in packs
+        .iter()
+        .rev()
+        .filter(|pack| mounted.contains(&pack.path))
+    {
+        let Some(owner) = mods
+            .iter()
+            .find(|entry| pack.path.starts_with(&entry.directory))
+        else {
+            continue;
+        };
+        for key in &pack.keys {
+            let owners = assets.entry(key).or_default();
+            if !owners.contains(&owner.id) {
+                owners.push(owner.id.clone());
+            }
+        }
+    }
+    assets
+        .into_iter()
+        .filter(|(_, mods)| mods.len() > 1)
+        .map(|(asset, mods)| ModConflict {
+            asset: asset.to_string(),
+            mods,
+        })
+        .collect()
+}
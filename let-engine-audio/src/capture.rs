@@ -0,0 +1,366 @@
+//! Recording audio from microphones and other input devices.
+
+use std::sync::Arc;
+
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    FromSample, SampleFormat, SizedSample, StreamConfig,
+};
+use crossbeam::channel::{bounded, Receiver};
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::Frame;
+
+/// The number of recorded chunks kept for [`Capture::frames`] before newer ones get dropped.
+const MAX_PENDING_CHUNKS: usize = 256;
+
+/// Errors that occur opening input devices and capturing from them.
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    /// There is no input device to capture from.
+    #[error("There is no audio input device available.")]
+    NoDevice,
+    #[error("Failed to list the audio input devices: {0}")]
+    Devices(#[from] cpal::DevicesError),
+    #[error("Failed to get the configuration of the audio input device: {0}")]
+    Config(#[from] cpal::DefaultStreamConfigError),
+    #[error("Failed to open the audio input stream: {0}")]
+    Build(#[from] cpal::BuildStreamError),
+    #[error("Failed to start the audio input stream: {0}")]
+    Play(#[from] cpal::PlayStreamError),
+    #[error("Failed to pause the audio input stream: {0}")]
+    Pause(#[from] cpal::PauseStreamError),
+    /// The device delivers samples in a format not supported by the engine.
+    #[error("The sample format {0} of this input device is not supported.")]
+    UnsupportedFormat(SampleFormat),
+    /// The device stopped working while capturing, for example by getting unplugged.
+    #[error("The audio input stream failed: {0}")]
+    Stream(#[from] cpal::StreamError),
+    /// Frames can not be resampled from or to a sample rate of 0.
+    #[error("Can not resample from {from} Hz to {to} Hz.")]
+    SampleRate { from: u32, to: u32 },
+}
+
+/// A device audio can be recorded from, like a microphone.
+#[derive(Clone)]
+pub struct InputDevice {
+    name: String,
+    device: cpal::Device,
+}
+
+impl std::fmt::Debug for InputDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputDevice")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+impl InputDevice {
+    fn new(device: cpal::Device) -> Self {
+        Self {
+            name: device.name().unwrap_or_default(),
+            device,
+        }
+    }
+
+    /// Returns the name of the device given by the system.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the sample rate the device records with by default.
+    pub fn sample_rate(&self) -> Result<u32, CaptureError> {
+        Ok(self.device.default_input_config()?.sample_rate().0)
+    }
+}
+
+/// Returns all input devices of the system.
+pub fn input_devices() -> Result<Vec<InputDevice>, CaptureError> {
+    Ok(cpal::default_host()
+        .input_devices()?
+        .map(InputDevice::new)
+        .collect())
+}
+
+/// Returns the input device selected as default by the system.
+pub fn default_input_device() -> Option<InputDevice> {
+    cpal::default_host()
+        .default_input_device()
+        .map(InputDevice::new)
+}
+
+/// A running recording of an input device.
+///
+/// Recording stops when this gets dropped.
+///
+/// # Usage
+/// ```ignore
+/// let device = default_input_device().ok_or(CaptureError::NoDevice)?;
+/// let capture = Capture::start(&device, Some(48000))?;
+///
+/// // every frame
+/// let frames = capture.frames();
+/// let loudness = frames.iter().map(|frame| frame.left.abs()).fold(0.0, f32::max);
+/// ```
+pub struct Capture {
+    stream: cpal::Stream,
+    frames: Option<Receiver<Vec<Frame>>>,
+    sample_rate: u32,
+    error: Arc<Mutex<Option<cpal::StreamError>>>,
+}
+
+impl Capture {
+    /// Starts recording the device, collecting the frames to be taken using [`frames`](Capture::frames).
+    ///
+    /// The frames get resampled to the given sample rate, or keep the rate of the device on `None`.
+    ///
+    /// Frames not taken in time get dropped once a few seconds of them pile up.
+    pub fn start(device: &InputDevice, sample_rate: Option<u32>) -> Result<Self, CaptureError> {
+        let (send, recv) = bounded(MAX_PENDING_CHUNKS);
+        let mut capture = Self::start_with_callback(device, sample_rate, move |frames| {
+            // The audio thread must not wait for the game to take the frames.
+            let _ = send.try_send(frames.to_vec());
+        })?;
+        capture.frames = Some(recv);
+        Ok(capture)
+    }
+
+    /// Starts recording the device, calling the callback with every chunk of recorded frames.
+    ///
+    /// The callback runs on the audio thread, so it should return quickly.
+    pub fn start_with_callback(
+        device: &InputDevice,
+        sample_rate: Option<u32>,
+        callback: impl FnMut(&[Frame]) + Send + 'static,
+    ) -> Result<Self, CaptureError> {
+        let config = device.device.default_input_config()?;
+        let device_rate = config.sample_rate().0;
+        let sample_rate = sample_rate.unwrap_or(device_rate);
+        let error = Arc::new(Mutex::new(None));
+
+        let format = config.sample_format();
+        let config: StreamConfig = config.into();
+        let stream = match format {
+            SampleFormat::F32 => {
+                build::<f32>(device, &config, sample_rate, callback, error.clone())?
+            }
+            SampleFormat::I16 => {
+                build::<i16>(device, &config, sample_rate, callback, error.clone())?
+            }
+            SampleFormat::U16 => {
+                build::<u16>(device, &config, sample_rate, callback, error.clone())?
+            }
+            SampleFormat::I32 => {
+                build::<i32>(device, &config, sample_rate, callback, error.clone())?
+            }
+            format => return Err(CaptureError::UnsupportedFormat(format)),
+        };
+        stream.play()?;
+
+        Ok(Self {
+            stream,
+            frames: None,
+            sample_rate,
+            error,
+        })
+    }
+
+    /// Returns all frames recorded since the last call.
+    ///
+    /// Always empty for captures started with a callback.
+    pub fn frames(&self) -> Vec<Frame> {
+        self.frames
+            .as_ref()
+            .map(|frames| frames.try_iter().flatten().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the sample rate of the recorded frames.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Pauses the recording.
+    pub fn pause(&self) -> Result<(), CaptureError> {
+        Ok(self.stream.pause()?)
+    }
+
+    /// Continues the recording after pausing it.
+    pub fn resume(&self) -> Result<(), CaptureError> {
+        Ok(self.stream.play()?)
+    }
+
+    /// Returns the error that stopped the recording, if there is one.
+    pub fn error(&self) -> Option<CaptureError> {
+        self.error.lock().take().map(CaptureError::from)
+    }
+}
+
+/// Opens an input stream turning samples of the type `T` into resampled frames.
+fn build<T>(
+    device: &InputDevice,
+    config: &StreamConfig,
+    sample_rate: u32,
+    mut callback: impl FnMut(&[Frame]) + Send + 'static,
+    error: Arc<Mutex<Option<cpal::StreamError>>>,
+) -> Result<cpal::Stream, CaptureError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let mut resampler = Resampler::new(config.sample_rate.0, sample_rate)?;
+    let mut frames = vec![];
+    let mut resampled = vec![];
+
+    Ok(device.device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            frames.clear();
+            frames.extend(data.chunks(channels).map(|samples| {
+                let left = f32::from_sample(samples[0]);
+                let right = samples
+                    .get(1)
+                    .map_or(left, |sample| f32::from_sample(*sample));
+                Frame { left, right }
+            }));
+            resampled.clear();
+            resampler.process(&frames, &mut resampled);
+            callback(&resampled);
+        },
+        move |stream_error| {
+            *error.lock() = Some(stream_error);
+        },
+        None,
+    )?)
+}
+
+/// Converts a continuous stream of frames from one sample rate to another using linear interpolation.
+#[derive(Clone, Debug)]
+pub struct Resampler {
+    from: u32,
+    to: u32,
+    /// The position of the next output frame, where 0 is the last frame of the previous chunk.
+    position: f64,
+    last: Frame,
+}
+
+impl Resampler {
+    /// Creates a resampler converting from the first to the second sample rate.
+    ///
+    /// Fails in case one of the sample rates is 0.
+    pub fn new(from: u32, to: u32) -> Result<Self, CaptureError> {
+        if from == 0 || to == 0 {
+            return Err(CaptureError::SampleRate { from, to });
+        }
+        Ok(Self {
+            from,
+            to,
+            position: 1.0,
+            last: Frame::ZERO,
+        })
+    }
+
+    /// Resamples the next chunk of the stream, appending the result to the output.
+    pub fn process(&mut self, input: &[Frame], output: &mut Vec<Frame>) {
+        if self.from == self.to {
+            output.extend_from_slice(input);
+            return;
+        }
+        let Some(last) = input.last() else {
+            return;
+        };
+
+        let step = self.from as f64 / self.to as f64;
+        let frame = |index: usize| {
+            if index == 0 {
+                self.last
+            } else {
+                input[index - 1]
+            }
+        };
+        while (self.position as usize) < input.len() {
+            let index = self.position as usize;
+            let t = self.position.fract() as f32;
+            let (a, b) = (frame(index), frame(index + 1));
+            output.push(Frame {
+                left: a.left + (b.left - a.left) * t,
+                right: a.right + (b.right - a.right) * t,
+            });
+            self.position += step;
+        }
+        self.position -= input.len() as f64;
+        self.last = *last;
+    }
+}
+
+/// Resamples a whole recording from one sample rate to another.
+///
+/// Fails in case one of the sample rates is 0.
+pub fn resample(frames: &[Frame], from: u32, to: u32) -> Result<Vec<Frame>, CaptureError> {
+    let mut resampler = Resampler::new(from, to)?;
+    let mut output = Vec::with_capacity(frames.len() * to as usize / from as usize + 1);
+    resampler.process(frames, &mut output);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ramp(len: usize) -> Vec<Frame> {
+        (0..len)
+            .map(|index| Frame {
+                left: index as f32,
+                right: -(index as f32),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_sample_rates_get_rejected() {
+        assert!(Resampler::new(0, 48000).is_err());
+        assert!(Resampler::new(48000, 0).is_err());
+        assert!(resample(&ramp(4), 0, 0).is_err());
+    }
+
+    #[test]
+    fn same_rate_keeps_the_frames() {
+        let frames = ramp(10);
+        assert_eq!(resample(&frames, 44100, 44100).unwrap(), frames);
+    }
+
+    #[test]
+    fn upsampling_interpolates() {
+        let output = resample(&ramp(4), 1, 2).unwrap();
+        let left: Vec<f32> = output.iter().map(|frame| frame.left).collect();
+        // The first output frame lies on the first input frame, the ones between get interpolated.
+        assert_eq!(left, [0.0, 0.5, 1.0, 1.5, 2.0, 2.5]);
+        assert_eq!(output[3].right, -1.5);
+    }
+
+    #[test]
+    fn downsampling_skips_frames() {
+        let output = resample(&ramp(8), 2, 1).unwrap();
+        let left: Vec<f32> = output.iter().map(|frame| frame.left).collect();
+        assert_eq!(left, [0.0, 2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn chunks_continue_the_stream() {
+        let frames = ramp(100);
+        let whole = resample(&frames, 44100, 48000).unwrap();
+
+        let mut resampler = Resampler::new(44100, 48000).unwrap();
+        let mut chunked = vec![];
+        for chunk in frames.chunks(7) {
+            resampler.process(chunk, &mut chunked);
+        }
+        assert_eq!(chunked.len(), whole.len());
+        for (a, b) in chunked.iter().zip(&whole) {
+            assert!((a.left - b.left).abs() < 1e-3);
+        }
+    }
+}
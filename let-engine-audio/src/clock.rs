@@ -0,0 +1,98 @@
+//! Clocks on the audio thread for starting sounds exactly on time.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use crossbeam::channel::unbounded;
+use kira::clock::ClockHandle;
+use parking_lot::Mutex;
+
+pub use kira::clock::{ClockSpeed, ClockTime};
+
+use crate::{AudioUpdate, NoAudioServerError, Tween, AUDIO_SERVER};
+
+/// A clock ticking on the audio thread, for rhythm games and music stingers landing on the beat.
+///
+/// Sounds played using [`Sound::play_at`](crate::Sound::play_at) start on the exact sample the clock reaches
+/// the given tick, no matter when the command arrives on the audio thread, as long as it arrives before.
+///
+/// Clocks keep ticking when the audio settings or the output device change.
+/// The number of clocks is limited by the clock capacity of the [`AudioSettings`](crate::AudioSettings).
+///
+/// # Usage
+/// ```ignore
+/// let mut clock = AudioClock::new(ClockSpeed::TicksPerMinute(120.0))?;
+/// clock.start();
+///
+/// // Starts the stinger on the next bar of four beats.
+/// let time = clock.time();
+/// stinger.play_at(time + (4 - time.ticks % 4))?;
+/// ```
+pub struct AudioClock {
+    slot: Arc<Mutex<ClockSlot>>,
+}
+
+/// A clock of the game, replaced by one on the new audio manager when the audio server restarts.
+pub(crate) struct ClockSlot {
+    pub(crate) handle: ClockHandle,
+    pub(crate) speed: ClockSpeed,
+    /// The ticks the clocks replaced by restarts reached, added to the ticks of the current clock.
+    pub(crate) offset: u64,
+}
+
+impl AudioClock {
+    /// Creates a stopped clock with the given speed.
+    pub fn new(speed: ClockSpeed) -> Result<Self> {
+        let (sender, recv) = unbounded();
+        AUDIO_SERVER
+            .send(AudioUpdate::AddClock(speed, sender))
+            .ok()
+            .ok_or(NoAudioServerError)?;
+        Ok(Self {
+            slot: recv.recv()??,
+        })
+    }
+
+    /// Creates a stopped clock ticking once per beat of the given tempo.
+    pub fn from_bpm(beats_per_minute: f64) -> Result<Self> {
+        Self::new(ClockSpeed::TicksPerMinute(beats_per_minute))
+    }
+
+    /// Starts or resumes the clock.
+    pub fn start(&mut self) {
+        self.slot.lock().handle.start();
+    }
+
+    /// Pauses the clock, keeping the current tick.
+    pub fn pause(&mut self) {
+        self.slot.lock().handle.pause();
+    }
+
+    /// Stops the clock and resets it to tick 0.
+    pub fn stop(&mut self) {
+        let mut slot = self.slot.lock();
+        slot.handle.stop();
+        slot.offset = 0;
+    }
+
+    /// Changes the speed of the clock.
+    pub fn set_speed(&mut self, speed: ClockSpeed, tween: Tween) {
+        let mut slot = self.slot.lock();
+        slot.handle.set_speed(speed, tween.into());
+        slot.speed = speed;
+    }
+
+    /// Returns true if the clock is running.
+    pub fn is_ticking(&self) -> bool {
+        self.slot.lock().handle.ticking()
+    }
+
+    /// Returns the current tick of the clock.
+    ///
+    /// Adding ticks to the returned time makes a time in the future, to be used with
+    /// [`Sound::play_at`](crate::Sound::play_at).
+    pub fn time(&self) -> ClockTime {
+        let slot = self.slot.lock();
+        slot.handle.time() + slot.offset
+    }
+}
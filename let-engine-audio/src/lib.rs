@@ -4,16 +4,20 @@ use std::{
     f64::consts::PI,
     io::Cursor,
     path::Path,
-    sync::{Arc, LazyLock, OnceLock},
+    sync::{Arc, LazyLock, OnceLock, Weak},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
+use cpal::traits::{DeviceTrait, HostTrait};
 use crossbeam::channel::{unbounded, Sender};
 use glam::{Quat, Vec3};
 use kira::{
-    manager::{backend::DefaultBackend, AudioManager, AudioManagerSettings, Capacities},
+    manager::{
+        backend::{cpal::CpalBackendSettings, Backend, DefaultBackend},
+        AudioManager, AudioManagerSettings, Capacities,
+    },
     sound::{
         static_sound::{StaticSoundData, StaticSoundHandle, StaticSoundSettings},
         FromFileError,
@@ -21,12 +25,91 @@ use kira::{
     spatial::{
         emitter::{EmitterHandle, EmitterSettings},
         listener::{ListenerHandle, ListenerSettings},
-        scene::SpatialSceneSettings,
+        scene::{SpatialSceneHandle, SpatialSceneSettings},
     },
     tween::Value,
 };
 
 static AUDIO_SERVER: LazyLock<Sender<AudioUpdate>> = LazyLock::new(audio_server);
+static AUDIO_STATS: Mutex<AudioStats> = Mutex::new(AudioStats {
+    sounds: 0,
+    sound_capacity: 0,
+    emitters: 0,
+    emitter_capacity: 0,
+    listeners: 0,
+    listener_capacity: 0,
+    spatial_scenes: 0,
+    spatial_scene_capacity: 0,
+    clocks: 0,
+    clock_capacity: 0,
+    failed_plays: 0,
+    failed_emitters: 0,
+    busy_time: Duration::ZERO,
+    tracks: Vec::new(),
+});
+
+/// How often the audio server refreshes its statistics while idle.
+const STATS_INTERVAL: Duration = Duration::from_millis(250);
+/// How often the audio server checks whether the selected output device is connected.
+const DEVICE_INTERVAL: Duration = Duration::from_secs(2);
+/// The name of the output device the audio server plays on, `None` for the default device of the system.
+static ACTIVE_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Counters of the audio server for tuning the capacities of the [`AudioSettings`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AudioStats {
+    /// The number of sounds currently existing on the audio server, including paused ones.
+    pub sounds: u16,
+    /// The maximum number of sounds set in the audio settings.
+    pub sound_capacity: u16,
+    /// The number of sounds bound to objects.
+    pub emitters: u16,
+    /// The maximum number of sounds bound to objects set in the audio settings.
+    pub emitter_capacity: u16,
+    /// The number of listeners of all spatial scenes.
+    pub listeners: u16,
+    /// The maximum number of listeners of each spatial scene set in the audio settings.
+    pub listener_capacity: u16,
+    /// The number of spatial scenes, including the one shared by layers without their own.
+    pub spatial_scenes: u16,
+    /// The maximum number of spatial scenes set in the audio settings.
+    pub spatial_scene_capacity: u16,
+    /// The number of audio clocks, including stopped ones.
+    pub clocks: u16,
+    /// The maximum number of audio clocks set in the audio settings.
+    pub clock_capacity: u16,
+    /// The number of sounds that failed to play since the start of the game, most likely due to a full sound capacity.
+    pub failed_plays: u64,
+    /// The number of sounds that played without being bound to their object due to a full emitter capacity.
+    pub failed_emitters: u64,
+    /// The total time the audio server spent handling requests since the start of the game.
+    pub busy_time: Duration,
+    /// The voice usage of each track, starting with the main track and the shared spatial scene.
+    pub tracks: Vec<TrackStats>,
+}
+
+/// A destination the sounds of the audio server play on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AudioTrack {
+    /// The main output, playing all sounds not bound to an object.
+    Main,
+    /// The spatial scene shared by all layers without their own.
+    SharedScene,
+    /// The own spatial scene of the layer with the given [ID](Layer::id).
+    Layer(u32),
+}
+
+/// The voice usage of a single track.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TrackStats {
+    pub track: AudioTrack,
+    /// The number of sounds playing or paused on this track.
+    pub voices: u16,
+    /// The number of emitters of the spatial scene, always 0 for the main track.
+    pub emitters: u16,
+    /// The number of listeners of the spatial scene, always 0 for the main track.
+    pub listeners: u16,
+}
 
 /// The audio server has not started.
 #[derive(Clone, Copy, Debug, Error)]
@@ -38,82 +121,494 @@ fn audio_server() -> Sender<AudioUpdate> {
     thread::spawn(|| {
         let recv = recv;
 
-        let (manager_settings, scene_settings) = (
-            AudioManagerSettings::default(),
-            SpatialSceneSettings::default(),
-        ); //SETTINGS.audio.get().make();
-
-        let mut audio_manager = AudioManager::<DefaultBackend>::new(manager_settings);
-        if let Ok(audio_manager) = audio_manager.as_mut() {
-            let mut spacial_scene = audio_manager
-                .add_spatial_scene(scene_settings)
-                .expect("impossible");
-            loop {
-                match recv.recv() {
-                    Ok(AudioUpdate::Play(sound)) => {
-                        let mut emitter = sound.emitter.lock();
-                        let mut settings: StaticSoundSettings = sound.settings.into();
-                        if let Some(spatial_emitter) = emitter.get() {
-                            // remove the emitter in case the object was removed.
-                            if sound.object.is_none() {
-                                emitter.take();
-                            } else {
-                                settings = settings.output_destination(spatial_emitter);
-                            };
-                        }
-                        // if the sound contains an object then add a spatial emitter
-                        if let (None, Some(object)) = (emitter.get(), &sound.object) {
-                            if let Ok(spatial_emitter) = spacial_scene.add_emitter(
-                                object.transform.position.extend(0.0),
-                                sound.spatial_settings().into(),
-                            ) {
-                                settings = settings.output_destination(&spatial_emitter);
-                                let _ = emitter.set(spatial_emitter);
-                            }
-                        }
-                        let handle = audio_manager.play(StaticSoundData {
-                            sample_rate: sound.data.sample_rate,
-                            frames: sound.data.frames,
-                            settings,
-                            slice: sound.data.slice,
-                        });
-                        sound.handle.lock().take();
-                        let _ = sound.handle.lock().set(handle.map_err(|x| x.into()));
-                    }
-                    Ok(AudioUpdate::NewListener(sender)) => {
-                        if let Ok(listener) = spacial_scene.add_listener(
-                            Vec3::ZERO,
-                            Quat::IDENTITY,
-                            ListenerSettings::default(),
-                        ) {
-                            let _ = sender.send(listener);
-                        };
-                    }
-                    Ok(AudioUpdate::SettingsChange(settings)) => {
-                        let (manager_settings, scene_settings) = settings.make();
-                        if let Ok(mut manager) =
-                            AudioManager::<DefaultBackend>::new(manager_settings)
-                        {
-                            spacial_scene = manager
-                                .add_spatial_scene(scene_settings)
-                                .expect("unreachable");
-                            *audio_manager = manager;
-                        } else {
-                            break;
-                        };
+        let mut capacities = AudioSettings::default();
+        let Ok((mut audio_manager, mut scenes)) = create_manager(&capacities) else {
+            return;
+        };
+        let mut handles = Handles::default();
+        let mut last_device_check = Instant::now();
+        loop {
+            let update = recv.recv_timeout(STATS_INTERVAL);
+            let start = Instant::now();
+            scenes.prune();
+            handles.prune();
+            {
+                let mut stats = AUDIO_STATS.lock();
+                stats.sounds = audio_manager.num_sounds();
+                stats.sound_capacity = capacities.sound_capacity;
+                stats.emitters = scenes.num_emitters();
+                stats.emitter_capacity = capacities.object_bound_sound_capacity;
+                stats.listeners = scenes.num_listeners();
+                stats.listener_capacity = capacities.listener_capacity;
+                stats.spatial_scenes = scenes.len();
+                stats.spatial_scene_capacity = capacities.spatial_scene_capacity;
+                stats.clocks = audio_manager.num_clocks();
+                stats.clock_capacity = capacities.clock_capacity;
+                stats.tracks = scenes.tracks(&handles.sounds);
+            }
+            match update {
+                Ok(AudioUpdate::Play(sound)) => {
+                    play_sound(&mut audio_manager, &mut scenes, &sound, None, None);
+                    handles.add_sound(sound);
+                }
+                Ok(AudioUpdate::PlayAt(sound, time)) => {
+                    let time = handles.clock_time(time);
+                    play_sound(&mut audio_manager, &mut scenes, &sound, Some(time), None);
+                    handles.add_sound(sound);
+                }
+                Ok(AudioUpdate::NewListener(layer, sender)) => {
+                    if let Ok(handle) = scenes.get(&layer).add_listener(
+                        Vec3::ZERO,
+                        Quat::IDENTITY,
+                        ListenerSettings::default(),
+                    ) {
+                        let listener = Arc::new(Mutex::new(ListenerSlot {
+                            handle,
+                            layer: Arc::downgrade(&layer),
+                            position: Vec3::ZERO,
+                            orientation: Quat::IDENTITY,
+                        }));
+                        handles.listeners.push(Arc::downgrade(&listener));
+                        let _ = sender.send(listener);
+                    };
+                }
+                Ok(AudioUpdate::AddSpatialScene(layer, sender)) => {
+                    let _ = sender.send(scenes.add(&mut audio_manager, layer, &capacities));
+                }
+                Ok(AudioUpdate::RemoveSpatialScene(layer)) => {
+                    scenes.remove(&layer);
+                }
+                Ok(AudioUpdate::SettingsChange(settings)) => {
+                    if restart(&mut audio_manager, &mut scenes, &settings, &handles).is_err() {
+                        break;
                     }
-                    _ => (),
-                };
+                    capacities = settings;
+                }
+                Ok(AudioUpdate::AddClock(speed, sender)) => {
+                    let clock = audio_manager.add_clock(speed).map(|handle| {
+                        let clock = Arc::new(Mutex::new(ClockSlot {
+                            handle,
+                            speed,
+                            offset: 0,
+                        }));
+                        handles.clocks.push(Arc::downgrade(&clock));
+                        clock
+                    });
+                    let _ = sender.send(clock.map_err(|x| x.into()));
+                }
+                _ => (),
+            };
+
+            // Falls back to the default device when the selected one gets unplugged and returns once it is back.
+            if let Some(name) = capacities
+                .output_device
+                .as_deref()
+                .filter(|_| last_device_check.elapsed() >= DEVICE_INTERVAL)
+            {
+                last_device_check = Instant::now();
+                let connected = find_output_device(name).is_some();
+                if connected != ACTIVE_DEVICE.lock().is_some() {
+                    let _ = restart(&mut audio_manager, &mut scenes, &capacities, &handles);
+                }
             }
+            AUDIO_STATS.lock().busy_time += start.elapsed();
         }
     });
     send
 }
 
-pub enum AudioUpdate {
+/// The spatial scenes of the audio server.
+struct SpatialScenes {
+    /// The scene of all layers without their own.
+    shared: SpatialSceneHandle,
+    layers: Vec<(Weak<Layer>, SpatialSceneHandle)>,
+}
+
+impl SpatialScenes {
+    /// Returns the scene the sounds and listeners of objects in the layer belong to.
+    fn get(&mut self, layer: &Arc<Layer>) -> &mut SpatialSceneHandle {
+        if let Some((_, scene)) = self
+            .layers
+            .iter_mut()
+            .find(|(scene_layer, _)| scene_layer.as_ptr() == Arc::as_ptr(layer))
+        {
+            return scene;
+        }
+        &mut self.shared
+    }
+
+    /// Gives the layer its own scene in case it does not have one yet.
+    fn add<B: Backend>(
+        &mut self,
+        audio_manager: &mut AudioManager<B>,
+        layer: Weak<Layer>,
+        settings: &AudioSettings,
+    ) -> Result<()> {
+        if !self.layers.iter().any(|(other, _)| other.ptr_eq(&layer)) {
+            let scene = audio_manager.add_spatial_scene(settings.scene_settings())?;
+            self.layers.push((layer, scene));
+        }
+        Ok(())
+    }
+
+    /// Drops the own scene of the layer, which removes all of its emitters and listeners.
+    fn remove(&mut self, layer: &Weak<Layer>) {
+        self.layers.retain(|(other, _)| !other.ptr_eq(layer));
+    }
+
+    /// Drops the scenes of layers that do not exist anymore.
+    fn prune(&mut self) {
+        self.layers.retain(|(layer, _)| layer.strong_count() > 0);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &SpatialSceneHandle> {
+        std::iter::once(&self.shared).chain(self.layers.iter().map(|(_, scene)| scene))
+    }
+
+    fn len(&self) -> u16 {
+        self.layers.len() as u16 + 1
+    }
+
+    fn num_emitters(&self) -> u16 {
+        self.iter().map(SpatialSceneHandle::num_emitters).sum()
+    }
+
+    fn num_listeners(&self) -> u16 {
+        self.iter().map(SpatialSceneHandle::num_listeners).sum()
+    }
+
+    /// Counts the voices of the main track and every scene.
+    ///
+    /// Sounds bound to an emitter play in the scene of their object, all others on the main track.
+    fn tracks(&self, sounds: &[Sound]) -> Vec<TrackStats> {
+        let scene_stats = |track, scene: &SpatialSceneHandle| TrackStats {
+            track,
+            voices: 0,
+            emitters: scene.num_emitters(),
+            listeners: scene.num_listeners(),
+        };
+        let mut tracks = vec![
+            TrackStats {
+                track: AudioTrack::Main,
+                voices: 0,
+                emitters: 0,
+                listeners: 0,
+            },
+            scene_stats(AudioTrack::SharedScene, &self.shared),
+        ];
+        tracks.extend(self.layers.iter().filter_map(|(layer, scene)| {
+            Some(scene_stats(AudioTrack::Layer(layer.upgrade()?.id()), scene))
+        }));
+
+        for sound in sounds {
+            let track = match &sound.object {
+                Some(object) if sound.emitter.lock().get().is_some() => self
+                    .layers
+                    .iter()
+                    .find(|(layer, _)| layer.as_ptr() == Arc::as_ptr(object.layer()))
+                    .map_or(AudioTrack::SharedScene, |_| {
+                        AudioTrack::Layer(object.layer().id())
+                    }),
+                _ => AudioTrack::Main,
+            };
+            if let Some(stats) = tracks.iter_mut().find(|stats| stats.track == track) {
+                stats.voices += 1;
+            }
+        }
+        tracks
+    }
+}
+
+/// Creates an audio manager and the shared spatial scene playing on the output device of the settings.
+fn create_manager(
+    settings: &AudioSettings,
+) -> Result<(AudioManager<DefaultBackend>, SpatialScenes)> {
+    let manager_settings = settings.make();
+    *ACTIVE_DEVICE.lock() = manager_settings
+        .backend_settings
+        .device
+        .as_ref()
+        .and_then(|device| device.name().ok());
+    let mut audio_manager = AudioManager::<DefaultBackend>::new(manager_settings)?;
+    let shared = audio_manager.add_spatial_scene(settings.scene_settings())?;
+    Ok((
+        audio_manager,
+        SpatialScenes {
+            shared,
+            layers: vec![],
+        },
+    ))
+}
+
+/// Replaces the audio manager with one using the given settings, continuing the playing and paused sounds.
+fn restart(
+    audio_manager: &mut AudioManager<DefaultBackend>,
+    scenes: &mut SpatialScenes,
+    settings: &AudioSettings,
+    handles: &Handles,
+) -> Result<()> {
+    let (manager, new_scenes) = create_manager(settings)?;
+    replace_manager(
+        audio_manager,
+        scenes,
+        (manager, new_scenes),
+        settings,
+        handles,
+    );
+    Ok(())
+}
+
+/// Moves the sounds, listeners and clocks of the game over to the new audio manager.
+///
+/// The scenes of layers get recreated and the listeners get added to them again at their last position.
+/// Clocks keep their speed and continue counting from the tick they were at.
+/// Sounds still waiting for a clock start right away.
+fn replace_manager<B: Backend>(
+    audio_manager: &mut AudioManager<B>,
+    scenes: &mut SpatialScenes,
+    (manager, new_scenes): (AudioManager<B>, SpatialScenes),
+    settings: &AudioSettings,
+    handles: &Handles,
+) {
+    let sounds = &handles.sounds;
+    // The sounds stop as soon as the old manager gets dropped.
+    let states: Vec<(PlaybackState, f64)> = sounds
+        .iter()
+        .map(|sound| (sound.state(), sound.position()))
+        .collect();
+    let clocks: Vec<_> = handles.clocks.iter().filter_map(Weak::upgrade).collect();
+    let clock_states: Vec<(bool, u64)> = clocks
+        .iter()
+        .map(|clock| {
+            let clock = clock.lock();
+            (
+                clock.handle.ticking(),
+                clock.handle.time().ticks + clock.offset,
+            )
+        })
+        .collect();
+    let layers: Vec<Weak<Layer>> = scenes
+        .layers
+        .iter()
+        .map(|(layer, _)| layer.clone())
+        .collect();
+    *audio_manager = manager;
+    *scenes = new_scenes;
+    // Layers left over by a lowered scene capacity fall back to the shared scene.
+    for layer in layers {
+        let _ = scenes.add(audio_manager, layer, settings);
+    }
+
+    for (sound, (state, position)) in sounds.iter().zip(states) {
+        sound.emitter.lock().take();
+        play_sound(audio_manager, scenes, sound, None, Some(position));
+        if state == PlaybackState::Paused {
+            if let Some(Ok(handle)) = sound.handle.lock().get_mut() {
+                handle.pause(kira::tween::Tween {
+                    duration: Duration::ZERO,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    for listener in handles.listeners.iter().filter_map(Weak::upgrade) {
+        let mut listener = listener.lock();
+        let Some(layer) = listener.layer.upgrade() else {
+            continue;
+        };
+        if let Ok(handle) = scenes.get(&layer).add_listener(
+            listener.position,
+            listener.orientation,
+            ListenerSettings::default(),
+        ) {
+            listener.handle = handle;
+        }
+    }
+
+    for (clock, (ticking, ticks)) in clocks.iter().zip(clock_states) {
+        let mut clock = clock.lock();
+        if let Ok(mut handle) = audio_manager.add_clock(clock.speed) {
+            if ticking {
+                handle.start();
+            }
+            clock.handle = handle;
+            clock.offset = ticks;
+        }
+    }
+}
+
+/// Plays the sound on the audio manager, binding it to an emitter in the scene of its object in case it has one.
+///
+/// The sound waits for the clock time in case one is given.
+fn play_sound<B: Backend>(
+    audio_manager: &mut AudioManager<B>,
+    scenes: &mut SpatialScenes,
+    sound: &Sound,
+    start_time: Option<ClockTime>,
+    start_position: Option<f64>,
+) {
+    let mut emitter = sound.emitter.lock();
+    let mut settings: StaticSoundSettings = sound.settings.into();
+    if let Some(time) = start_time {
+        settings = settings.start_time(kira::StartTime::ClockTime(time));
+    }
+    if let Some(position) = start_position {
+        settings = settings.start_position(position);
+    }
+    if let Some(spatial_emitter) = emitter.get() {
+        // remove the emitter in case the object was removed.
+        if sound.object.is_none() {
+            emitter.take();
+        } else {
+            settings = settings.output_destination(spatial_emitter);
+        };
+    }
+    // if the sound contains an object then add a spatial emitter
+    if let (None, Some(object)) = (emitter.get(), &sound.object) {
+        if let Ok(spatial_emitter) = scenes.get(object.layer()).add_emitter(
+            object.transform.position.extend(0.0),
+            sound.spatial_settings().into(),
+        ) {
+            settings = settings.output_destination(&spatial_emitter);
+            let _ = emitter.set(spatial_emitter);
+        } else {
+            AUDIO_STATS.lock().failed_emitters += 1;
+        }
+    }
+    #[cfg(feature = "physics")]
+    if sound.occluded {
+        settings = settings.volume(sound.output_volume());
+    }
+    let data = StaticSoundData {
+        sample_rate: sound.data.sample_rate,
+        frames: sound.data.frames.clone(),
+        settings,
+        slice: sound.data.slice,
+    };
+    // Only sounds checking for occlusion pay for the filter.
+    #[cfg(feature = "physics")]
+    let handle = if sound.occlusion.is_some() {
+        audio_manager.play(occlusion::FilteredSoundData {
+            data,
+            cutoff: sound.cutoff.clone(),
+        })
+    } else {
+        audio_manager.play(data)
+    };
+    #[cfg(not(feature = "physics"))]
+    let handle = audio_manager.play(data);
+    if handle.is_err() {
+        AUDIO_STATS.lock().failed_plays += 1;
+    }
+    sound.handle.lock().take();
+    let _ = sound.handle.lock().set(handle.map_err(|x| x.into()));
+}
+
+/// Returns the output device with the given name.
+fn find_output_device(name: &str) -> Option<cpal::Device> {
+    cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| device.name().is_ok_and(|device| device == name))
+}
+
+/// Returns the names of all audio output devices of the system.
+pub fn output_devices() -> Result<Vec<String>> {
+    Ok(cpal::default_host()
+        .output_devices()?
+        .filter_map(|device| device.name().ok())
+        .collect())
+}
+
+pub(crate) enum AudioUpdate {
     Play(Sound),
-    NewListener(Sender<ListenerHandle>),
+    PlayAt(Sound, ClockTime),
+    NewListener(Arc<Layer>, Sender<Arc<Mutex<ListenerSlot>>>),
+    AddSpatialScene(Weak<Layer>, Sender<Result<()>>),
+    RemoveSpatialScene(Weak<Layer>),
     SettingsChange(AudioSettings),
+    AddClock(ClockSpeed, Sender<Result<Arc<Mutex<ClockSlot>>>>),
+}
+
+/// The sounds, listeners and clocks of the game, moved over to the new audio manager when the audio server restarts.
+#[derive(Default)]
+struct Handles {
+    /// Sounds started by the server that did not stop yet.
+    sounds: Vec<Sound>,
+    listeners: Vec<Weak<Mutex<ListenerSlot>>>,
+    clocks: Vec<Weak<Mutex<ClockSlot>>>,
+}
+
+impl Handles {
+    fn add_sound(&mut self, sound: Sound) {
+        // A sound played again replaces its previous playback.
+        self.sounds
+            .retain(|played| !Arc::ptr_eq(&played.handle, &sound.handle));
+        self.sounds.push(sound);
+    }
+
+    /// Forgets stopped sounds and dropped listeners and clocks.
+    fn prune(&mut self) {
+        self.sounds
+            .retain(|sound| sound.state() != PlaybackState::Stopped);
+        self.listeners
+            .retain(|listener| listener.strong_count() > 0);
+        self.clocks.retain(|clock| clock.strong_count() > 0);
+    }
+
+    /// Converts a time of an [`AudioClock`] to the ticks of the clock on the current audio manager.
+    fn clock_time(&self, time: ClockTime) -> ClockTime {
+        let offset = self
+            .clocks
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find_map(|clock| {
+                let clock = clock.lock();
+                (clock.handle.id() == time.clock).then_some(clock.offset)
+            })
+            .unwrap_or(0);
+        ClockTime {
+            ticks: time.ticks.saturating_sub(offset),
+            ..time
+        }
+    }
+}
+
+/// A listener of the game, added to the scene of its layer again when the audio server restarts.
+pub(crate) struct ListenerSlot {
+    handle: ListenerHandle,
+    layer: Weak<Layer>,
+    position: Vec3,
+    orientation: Quat,
+}
+
+/// Gives the layer its own spatial scene, so sounds bound to its objects are only heard by its listeners.
+///
+/// Layers without their own scene share one, which lets split screen players or separate world instances hear each
+/// other. The number of scenes is limited by the spatial scene capacity of the [`AudioSettings`].
+///
+/// Sounds and listeners created before keep their previous scene until they get played or created again.
+pub fn enable_spatial_audio(layer: &Arc<Layer>) -> Result<()> {
+    let (sender, recv) = unbounded();
+    AUDIO_SERVER
+        .send(AudioUpdate::AddSpatialScene(Arc::downgrade(layer), sender))
+        .ok()
+        .ok_or(NoAudioServerError)?;
+    recv.recv()?
+}
+
+/// Removes the own spatial scene of the layer, moving it back to the shared one.
+///
+/// Sounds bound to objects of the layer go silent and its listeners stop working,
+/// so play the sounds and create the listeners again.
+///
+/// The scene also gets removed once the layer gets dropped.
+pub fn disable_spatial_audio(layer: &Arc<Layer>) -> Result<(), NoAudioServerError> {
+    AUDIO_SERVER
+        .send(AudioUpdate::RemoveSpatialScene(Arc::downgrade(layer)))
+        .ok()
+        .ok_or(NoAudioServerError)
 }
 
 pub use kira::{
@@ -161,14 +656,22 @@ impl From<Tween> for kira::tween::Tween {
 }
 
 /// The global audio settings that should be used throughout the game.
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct AudioSettings {
     /// The limit of how many sounds can exist at the same time.
     pub sound_capacity: u16,
     /// The limit of how many sounds can be bound to objects to make them spatial.
     pub object_bound_sound_capacity: u16,
-    /// The limit of how many scenes can play spatial sounds.
+    /// The limit of how many scenes can play spatial sounds, including the one shared by layers without their own.
     pub spatial_scene_capacity: u16,
+    /// The limit of how many listeners each spatial scene can have.
+    pub listener_capacity: u16,
+    /// The limit of how many [`AudioClock`]s can exist at the same time.
+    pub clock_capacity: u16,
+    /// The name of the output device to play on, as returned by [`output_devices`].
+    ///
+    /// The default device of the system gets used on `None` or while the device is not connected.
+    pub output_device: Option<String>,
 }
 
 impl AudioSettings {
@@ -210,23 +713,62 @@ impl AudioSettings {
         self
     }
 
+    /// Sets the maximum amount of listeners of each spatial scene.
+    pub fn set_listener_capacity(&mut self, listener_capacity: u16) {
+        self.listener_capacity = listener_capacity;
+    }
+
+    /// Sets the maximum amount of listeners of each spatial scene and returns self.
+    pub fn listener_capacity(mut self, listener_capacity: u16) -> Self {
+        self.listener_capacity = listener_capacity;
+        self
+    }
+
+    /// Sets the maximum amount of audio clocks.
+    pub fn set_clock_capacity(&mut self, clock_capacity: u16) {
+        self.clock_capacity = clock_capacity;
+    }
+
+    /// Sets the maximum amount of audio clocks and returns self.
+    pub fn clock_capacity(mut self, clock_capacity: u16) -> Self {
+        self.clock_capacity = clock_capacity;
+        self
+    }
+
+    /// Sets the name of the output device to play on.
+    pub fn set_output_device(&mut self, output_device: Option<String>) {
+        self.output_device = output_device;
+    }
+
+    /// Sets the name of the output device to play on and returns self.
+    pub fn output_device(mut self, output_device: Option<String>) -> Self {
+        self.output_device = output_device;
+        self
+    }
+
     /// Converts these audio settings to the kira settings to be used when making or editing the settings.
-    pub(crate) fn make(&self) -> (AudioManagerSettings<DefaultBackend>, SpatialSceneSettings) {
-        let manager_settings = AudioManagerSettings {
+    pub(crate) fn make(&self) -> AudioManagerSettings<DefaultBackend> {
+        AudioManagerSettings {
             capacities: Capacities {
                 command_capacity: 256,
                 sound_capacity: self.sound_capacity,
-                clock_capacity: 1,
+                clock_capacity: self.clock_capacity,
                 spatial_scene_capacity: self.spatial_scene_capacity,
                 ..Default::default()
             },
+            backend_settings: CpalBackendSettings {
+                device: self.output_device.as_deref().and_then(find_output_device),
+                ..Default::default()
+            },
             ..Default::default()
-        };
-        let scene_settings = SpatialSceneSettings::new()
-            .emitter_capacity(self.object_bound_sound_capacity)
-            .listener_capacity(self.spatial_scene_capacity);
+        }
+    }
 
-        (manager_settings, scene_settings)
+    /// Returns the kira settings of every spatial scene.
+    pub(crate) fn scene_settings(&self) -> SpatialSceneSettings {
+        SpatialSceneSettings::new()
+            .emitter_capacity(self.object_bound_sound_capacity)
+            .listener_capacity(self.listener_capacity)
     }
 }
 
@@ -236,6 +778,9 @@ impl Default for AudioSettings {
             sound_capacity: 256,
             object_bound_sound_capacity: 256,
             spatial_scene_capacity: 8,
+            listener_capacity: 8,
+            clock_capacity: 8,
+            output_device: None,
         }
     }
 }
@@ -243,7 +788,22 @@ impl Default for AudioSettings {
 use parking_lot::Mutex;
 use thiserror::Error;
 
-use let_engine_core::objects::Object;
+pub mod capture;
+mod clock;
+mod metronome;
+mod music;
+#[cfg(feature = "physics")]
+mod occlusion;
+mod synth;
+use clock::ClockSlot;
+pub use clock::*;
+pub use metronome::*;
+pub use music::*;
+#[cfg(feature = "physics")]
+pub use occlusion::OcclusionSettings;
+pub use synth::*;
+
+use let_engine_core::objects::{scenes::Layer, Object};
 
 /// The shared loaded data of a sound, clone friendly and thread safe.
 #[derive(Clone, Debug, PartialEq)]
@@ -394,6 +954,13 @@ pub struct Sound {
     emitter: Arc<Mutex<OnceLock<EmitterHandle>>>,
     handle: Arc<Mutex<OnceLock<Result<StaticSoundHandle>>>>,
     object: Option<Object>,
+    #[cfg(feature = "physics")]
+    occlusion: Option<OcclusionSettings>,
+    #[cfg(feature = "physics")]
+    occluded: bool,
+    /// The bits of the cutoff frequency of the occlusion filter.
+    #[cfg(feature = "physics")]
+    cutoff: Arc<std::sync::atomic::AtomicU32>,
 }
 
 impl Sound {
@@ -406,6 +973,12 @@ impl Sound {
             emitter: Arc::new(Mutex::new(OnceLock::new())),
             handle: Arc::new(Mutex::new(OnceLock::new())),
             object: None,
+            #[cfg(feature = "physics")]
+            occlusion: None,
+            #[cfg(feature = "physics")]
+            occluded: false,
+            #[cfg(feature = "physics")]
+            cutoff: Arc::new(occlusion::OPEN_CUTOFF.to_bits().into()),
         }
     }
 
@@ -431,6 +1004,38 @@ impl Sound {
         self.spatial_settings
     }
 
+    /// Makes the sound quieter and muffled while colliders are between its object and the listener,
+    /// or stops doing so on `None`.
+    ///
+    /// Takes effect the next time the sound gets played.
+    #[cfg(feature = "physics")]
+    pub fn set_occlusion(&mut self, occlusion: Option<OcclusionSettings>) {
+        self.occlusion = occlusion;
+    }
+
+    /// Returns the occlusion settings of this sound.
+    #[cfg(feature = "physics")]
+    pub fn occlusion(&self) -> Option<&OcclusionSettings> {
+        self.occlusion.as_ref()
+    }
+
+    /// Returns true if colliders were between the object of the sound and the listener on the last update.
+    #[cfg(feature = "physics")]
+    pub fn is_occluded(&self) -> bool {
+        self.occluded
+    }
+
+    /// Returns the volume of the sound including the reduction of occlusion.
+    #[cfg(feature = "physics")]
+    fn output_volume(&self) -> Volume {
+        match &self.occlusion {
+            Some(occlusion) if self.occluded => {
+                Volume::Amplitude(self.settings.volume.as_amplitude() * occlusion.volume)
+            }
+            _ => self.settings.volume,
+        }
+    }
+
     /// Returns the data behind the sound.
     pub fn data(&self) -> &SoundData {
         &self.data
@@ -458,9 +1063,11 @@ impl Sound {
     ///
     /// Returns an error in case the command queue is full.
     pub fn set_volume(&mut self, volume: impl Into<Volume>, tween: Tween) {
-        let volume = volume.into();
-        let value_volume = Value::Fixed(volume);
-        self.settings.volume = volume;
+        self.settings.volume = volume.into();
+        #[cfg(feature = "physics")]
+        let value_volume = Value::Fixed(self.output_volume());
+        #[cfg(not(feature = "physics"))]
+        let value_volume = Value::Fixed(self.settings.volume);
         if let Some(Ok(handle)) = self.handle.lock().get_mut() {
             handle.set_volume(value_volume, tween.into());
         }
@@ -510,15 +1117,50 @@ impl Sound {
 
     /// Updates the position of the sound.
     ///
+    /// Sounds with occlusion settings also check whether colliders are between them and the listener.
+    ///
     /// Returns an error in case the command queue is full.
     pub fn update(&mut self, tween: Tween) -> Result<()> {
         if let (Some(emitter), Some(object)) = (self.emitter.lock().get_mut(), &mut self.object) {
             object.update()?;
             emitter.set_position(object.transform.position.extend(0.0), tween.into())
         }
+        #[cfg(feature = "physics")]
+        self.update_occlusion();
         Ok(())
     }
 
+    #[cfg(feature = "physics")]
+    fn update_occlusion(&mut self) {
+        let (Some(settings), Some(object)) = (&self.occlusion, &self.object) else {
+            return;
+        };
+        let occluded = occlusion::is_occluded(
+            object.layer(),
+            object.public_transform().position,
+            object.handle(),
+            settings,
+        );
+        if occluded == self.occluded {
+            return;
+        }
+        let (cutoff, tween) = (settings.cutoff, settings.tween);
+        self.occluded = occluded;
+        self.cutoff.store(
+            if occluded {
+                cutoff
+            } else {
+                occlusion::OPEN_CUTOFF
+            }
+            .to_bits(),
+            std::sync::atomic::Ordering::Relaxed,
+        );
+        let volume = self.output_volume();
+        if let Some(Ok(handle)) = self.handle.lock().get_mut() {
+            handle.set_volume(Value::Fixed(volume), tween.into());
+        }
+    }
+
     /// Plays this sound.
     pub fn play(&mut self) -> Result<()> {
         if self.state() != PlaybackState::Playing {
@@ -530,6 +1172,19 @@ impl Sound {
         Ok(())
     }
 
+    /// Plays this sound starting on the exact sample the clock of the time reaches it.
+    ///
+    /// Times that already passed start the sound right away.
+    pub fn play_at(&mut self, time: ClockTime) -> Result<()> {
+        if self.state() != PlaybackState::Playing {
+            AUDIO_SERVER
+                .send(AudioUpdate::PlayAt(self.clone(), time))
+                .ok()
+                .ok_or(NoAudioServerError)?;
+        }
+        Ok(())
+    }
+
     /// Pauses this sound.
     ///
     /// Returns an error in case the command queue is full.
@@ -687,34 +1342,54 @@ pub struct Audio {
 impl Audio {
     /// Returns the audio settings.
     pub fn get(&self) -> AudioSettings {
-        *self.audio_settings.lock()
+        self.audio_settings.lock().clone()
     }
     /// Sets and applies the audio settings and therefore refreshes the engine side audio server to use them.
+    ///
+    /// Playing and paused sounds, listeners and clocks continue where they were.
     pub fn set(&self, settings: AudioSettings) -> Result<(), NoAudioServerError> {
-        *self.audio_settings.lock() = settings;
+        *self.audio_settings.lock() = settings.clone();
         AUDIO_SERVER
             .send(AudioUpdate::SettingsChange(settings))
             .ok()
             .ok_or(NoAudioServerError)
     }
+
+    /// Returns the latest statistics of the audio server.
+    ///
+    /// The counters get refreshed a few times per second.
+    pub fn stats(&self) -> AudioStats {
+        AUDIO_STATS.lock().clone()
+    }
+
+    /// Returns the name of the output device sounds currently play on, `None` for the default device of the system.
+    pub fn active_output_device(&self) -> Option<String> {
+        ACTIVE_DEVICE.lock().clone()
+    }
 }
 
 /// Your "ears". The object this is bound to represents the position and orientation of where the sound is to be heard.
 ///
 /// Just the existence of this object is enough for you to be able to hear sounds directionally from the position of this listener.
+///
+/// Listeners only hear sounds of the spatial scene of their layer, see [`enable_spatial_audio`].
 pub struct Listener {
-    listener: ListenerHandle,
+    listener: Arc<Mutex<ListenerSlot>>,
     object: Object,
+    #[cfg(feature = "physics")]
+    position: Arc<occlusion::ListenerPosition>,
 }
 
 impl Listener {
     /// Creates a new Listener using the given object as ears.
     pub fn new(object: &Object) -> Result<Self> {
         let (sender, recv) = unbounded();
-        AUDIO_SERVER.send(AudioUpdate::NewListener(sender))?;
+        AUDIO_SERVER.send(AudioUpdate::NewListener(object.layer().clone(), sender))?;
         Ok(Self {
             object: object.clone(),
             listener: recv.recv()?,
+            #[cfg(feature = "physics")]
+            position: occlusion::ListenerPosition::register(object),
         })
     }
 
@@ -726,12 +1401,95 @@ impl Listener {
     /// Updates the listener to the object it is bound to.
     pub fn update(&mut self, tween: Tween) -> Result<()> {
         self.object.update()?;
-        self.listener
-            .set_position(self.object.transform.position.extend(0.0), tween.into());
-        self.listener.set_orientation(
-            Quat::from_rotation_z(self.object.transform.rotation),
-            tween.into(),
-        );
+        let mut listener = self.listener.lock();
+        listener.position = self.object.transform.position.extend(0.0);
+        listener.orientation = Quat::from_rotation_z(self.object.transform.rotation);
+        let ListenerSlot {
+            handle,
+            position,
+            orientation,
+            ..
+        } = &mut *listener;
+        handle.set_position(*position, tween.into());
+        handle.set_orientation(*orientation, tween.into());
+        #[cfg(feature = "physics")]
+        self.position.set(self.object.public_transform().position);
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use kira::manager::backend::mock::MockBackend;
+    use let_engine_core::objects::scenes::SCENE;
+
+    use super::*;
+
+    fn mock_manager() -> (AudioManager<MockBackend>, SpatialScenes) {
+        let mut audio_manager =
+            AudioManager::<MockBackend>::new(AudioManagerSettings::default()).unwrap();
+        let shared = audio_manager
+            .add_spatial_scene(SpatialSceneSettings::default())
+            .unwrap();
+        (
+            audio_manager,
+            SpatialScenes {
+                shared,
+                layers: vec![],
+            },
+        )
+    }
+
+    #[test]
+    fn restart_keeps_listeners_and_clocks() {
+        let settings = AudioSettings::default();
+        let (mut audio_manager, mut scenes) = mock_manager();
+        let layer = SCENE.new_layer();
+        scenes
+            .add(&mut audio_manager, Arc::downgrade(&layer), &settings)
+            .unwrap();
+
+        let handle = scenes
+            .get(&layer)
+            .add_listener(Vec3::ZERO, Quat::IDENTITY, ListenerSettings::default())
+            .unwrap();
+        let listener = Arc::new(Mutex::new(ListenerSlot {
+            handle,
+            layer: Arc::downgrade(&layer),
+            position: Vec3::new(1.0, 2.0, 0.0),
+            orientation: Quat::IDENTITY,
+        }));
+        let speed = ClockSpeed::TicksPerSecond(10.0);
+        let clock = Arc::new(Mutex::new(ClockSlot {
+            handle: audio_manager.add_clock(speed).unwrap(),
+            speed,
+            offset: 5,
+        }));
+        let handles = Handles {
+            sounds: vec![],
+            listeners: vec![Arc::downgrade(&listener)],
+            clocks: vec![Arc::downgrade(&clock)],
+        };
+
+        replace_manager(
+            &mut audio_manager,
+            &mut scenes,
+            mock_manager(),
+            &settings,
+            &handles,
+        );
+
+        // The listener got added to the new scene of its layer.
+        assert_eq!(scenes.get(&layer).num_listeners(), 1);
+        assert_eq!(scenes.shared.num_listeners(), 0);
+
+        // The clock got replaced and continues from the tick it was at.
+        assert_eq!(audio_manager.num_clocks(), 1);
+        let time = {
+            let clock = clock.lock();
+            assert_eq!(clock.offset, 5);
+            clock.handle.time() + 7
+        };
+        assert_eq!(handles.clock_time(time).ticks, 2);
+    }
+}
@@ -0,0 +1,406 @@
+//! Beat and bar events following the tempo of a music track.
+
+use std::{
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use thiserror::Error;
+
+use crate::{
+    AudioClock, AudioUpdate, ClockSpeed, ClockTime, Easing, NoAudioServerError, PlaybackState,
+    Sound, Tween, AUDIO_SERVER,
+};
+
+/// The resolution of the clock of a metronome, as used by most MIDI sequencers.
+///
+/// The offset of a track gets rounded to a tick, which is about 5 milliseconds at 120 beats per minute.
+const TICKS_PER_BEAT: u64 = 96;
+
+static METRONOMES: Mutex<Vec<Weak<Mutex<State>>>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The tempo of a music track.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tempo {
+    /// The beats per minute.
+    pub bpm: f64,
+    /// The position in seconds of the first beat of the first bar in the track.
+    pub offset: f64,
+    /// The number of beats in each bar.
+    pub beats_per_bar: u32,
+}
+
+impl Tempo {
+    /// Creates a tempo starting at the beginning of the track with four beats per bar.
+    pub fn new(bpm: f64) -> Self {
+        Self {
+            bpm,
+            offset: 0.0,
+            beats_per_bar: 4,
+        }
+    }
+
+    /// Sets the position of the first beat in seconds and returns self.
+    #[inline]
+    pub fn offset(mut self, offset: f64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets the number of beats in each bar and returns self.
+    #[inline]
+    pub fn beats_per_bar(mut self, beats_per_bar: u32) -> Self {
+        self.beats_per_bar = beats_per_bar;
+        self
+    }
+
+    /// Returns an error in case the beats per minute are not a positive finite number.
+    pub fn validate(&self) -> Result<(), InvalidTempoError> {
+        if self.bpm.is_finite() && self.bpm > 0.0 {
+            Ok(())
+        } else {
+            Err(InvalidTempoError(self.bpm))
+        }
+    }
+
+    fn ticks_per_second(&self) -> f64 {
+        self.bpm / 60.0 * TICKS_PER_BEAT as f64
+    }
+
+    /// Returns the offset in clock ticks.
+    fn offset_ticks(&self) -> u64 {
+        (self.offset.max(0.0) * self.ticks_per_second()).round() as u64
+    }
+}
+
+/// The beats per minute of a [`Tempo`] are not a positive finite number.
+#[derive(Clone, Copy, Debug, Error)]
+#[error("The tempo needs a positive number of beats per minute, not {0}.")]
+pub struct InvalidTempoError(pub f64);
+
+/// A beat of a track played by a [`Metronome`], sent to the game as `Event::Beat`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeatEvent {
+    /// The ID of the metronome playing the track.
+    pub metronome: usize,
+    /// The number of the beat since the offset of the track, starting at 0.
+    pub beat: u64,
+    /// The number of the bar the beat is in, starting at 0.
+    pub bar: u64,
+    /// The place of the beat in its bar, 0 being the downbeat.
+    pub beat_in_bar: u32,
+    /// The clock time of the beat, to start sounds right on it using [`Sound::play_at`].
+    pub time: ClockTime,
+    /// The time left until the beat gets heard, which is about the look-ahead of the metronome.
+    pub time_until: Duration,
+}
+
+impl BeatEvent {
+    /// Returns true if this is the first beat of a bar.
+    pub fn is_downbeat(&self) -> bool {
+        self.beat_in_bar == 0
+    }
+}
+
+struct State {
+    id: usize,
+    tempo: Tempo,
+    lookahead: Duration,
+    clock: Option<AudioClock>,
+    track: Option<Sound>,
+    /// The clock tick the track started on.
+    start: u64,
+    /// The next beat to send an event for.
+    next: u64,
+}
+
+impl State {
+    /// Returns the clock tick of the given beat.
+    fn beat_tick(&self, beat: u64) -> u64 {
+        self.start + self.tempo.offset_ticks() + beat * TICKS_PER_BEAT
+    }
+
+    fn clock_time(&self, tick: u64) -> Option<ClockTime> {
+        let now = self.clock.as_ref()?.time();
+        Some(now + tick.saturating_sub(now.ticks))
+    }
+
+    fn poll(&mut self, events: &mut Vec<BeatEvent>) {
+        let (Some(clock), Some(track)) = (&self.clock, &self.track) else {
+            return;
+        };
+        if track.state() == PlaybackState::Stopped {
+            return;
+        }
+        let now = clock.time().ticks;
+        let beats_per_bar = self.tempo.beats_per_bar.max(1) as u64;
+        for beat in self.due_beats(now) {
+            let tick = self.beat_tick(beat);
+            if let Some(time) = self.clock_time(tick) {
+                events.push(BeatEvent {
+                    metronome: self.id,
+                    beat,
+                    bar: beat / beats_per_bar,
+                    beat_in_bar: (beat % beats_per_bar) as u32,
+                    time,
+                    time_until: Duration::from_secs_f64(
+                        tick.saturating_sub(now) as f64 / self.tempo.ticks_per_second(),
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Returns the beats to send events for at the given clock tick and moves on to the beat after them.
+    ///
+    /// Of the beats that already passed, only the latest one gets sent, so a hitch does not cause a burst of late
+    /// beats in a single frame.
+    fn due_beats(&mut self, now: u64) -> Range<u64> {
+        let first = self.beat_tick(0);
+        if now >= first {
+            self.next = self.next.max((now - first) / TICKS_PER_BEAT);
+        }
+        let ahead = now + (self.lookahead.as_secs_f64() * self.tempo.ticks_per_second()) as u64;
+        let start = self.next;
+        while self.beat_tick(self.next) <= ahead {
+            self.next += 1;
+        }
+        start..self.next
+    }
+}
+
+/// Plays music tracks on an audio clock and sends an `Event::Beat` to the game on every beat.
+///
+/// The events get sent the look-ahead time before the beat can be heard, so gameplay can prepare for it,
+/// while sounds started with [`Sound::play_at`] using the time of the event or [`beat_time`](Metronome::beat_time)
+/// land right on the beat.
+///
+/// # Usage
+/// ```ignore
+/// let mut metronome = Metronome::new().lookahead(Duration::from_millis(200));
+/// metronome.play(&mut track, Tempo::new(128.0).offset(0.42))?;
+///
+/// // in the event function of the game
+/// if let Event::Beat(beat) = event {
+///     if beat.is_downbeat() {
+///         cymbal.play_at(beat.time)?;
+///     }
+/// }
+/// ```
+pub struct Metronome {
+    state: Arc<Mutex<State>>,
+}
+
+impl Default for Metronome {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metronome {
+    /// Creates a metronome without a track.
+    pub fn new() -> Self {
+        let state = Arc::new(Mutex::new(State {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            tempo: Tempo::new(120.0),
+            lookahead: Duration::ZERO,
+            clock: None,
+            track: None,
+            start: 0,
+            next: 0,
+        }));
+        let mut metronomes = METRONOMES.lock();
+        metronomes.retain(|metronome| metronome.strong_count() > 0);
+        metronomes.push(Arc::downgrade(&state));
+        Self { state }
+    }
+
+    /// Sets how long before the beats their events get sent and returns self.
+    #[inline]
+    pub fn lookahead(self, lookahead: Duration) -> Self {
+        self.state.lock().lookahead = lookahead;
+        self
+    }
+
+    /// Sets how long before the beats their events get sent.
+    pub fn set_lookahead(&mut self, lookahead: Duration) {
+        self.state.lock().lookahead = lookahead;
+    }
+
+    /// Returns the ID of this metronome, which the beat events of its tracks carry.
+    pub fn id(&self) -> usize {
+        self.state.lock().id
+    }
+
+    /// Returns the tempo of the current track.
+    pub fn tempo(&self) -> Option<Tempo> {
+        let state = self.state.lock();
+        state.track.as_ref().map(|_| state.tempo)
+    }
+
+    /// Plays the track with the given tempo from the beginning on a new clock, stopping the clock of the previous track.
+    ///
+    /// The track starts one beat later, giving the audio server time to schedule it.
+    ///
+    /// Fails with an [`InvalidTempoError`] in case the beats per minute are not a positive finite number.
+    pub fn play(&mut self, track: &mut Sound, tempo: Tempo) -> Result<()> {
+        tempo.validate()?;
+        let mut state = self.state.lock();
+        if let Some(mut clock) = state.clock.take() {
+            clock.stop();
+        }
+        let mut clock = AudioClock::new(ClockSpeed::TicksPerMinute(
+            tempo.bpm * TICKS_PER_BEAT as f64,
+        ))?;
+        clock.start();
+        let start = clock.time() + TICKS_PER_BEAT;
+        // Played directly, as the track still counts as playing right after getting stopped.
+        track.stop(INSTANT);
+        AUDIO_SERVER
+            .send(AudioUpdate::PlayAt(track.clone(), start))
+            .ok()
+            .ok_or(NoAudioServerError)?;
+
+        state.tempo = tempo;
+        state.start = start.ticks;
+        state.next = 0;
+        state.clock = Some(clock);
+        state.track = Some(track.clone());
+        Ok(())
+    }
+
+    /// Pauses the track along with the beats.
+    pub fn pause(&mut self) {
+        let mut state = self.state.lock();
+        let State { clock, track, .. } = &mut *state;
+        if let (Some(clock), Some(track)) = (clock, track) {
+            track.pause(INSTANT);
+            clock.pause();
+        }
+    }
+
+    /// Resumes the track along with the beats.
+    pub fn resume(&mut self) {
+        let mut state = self.state.lock();
+        let State { clock, track, .. } = &mut *state;
+        if let (Some(clock), Some(track)) = (clock, track) {
+            track.resume(INSTANT);
+            clock.start();
+        }
+    }
+
+    /// Stops the track and the beats.
+    pub fn stop(&mut self, tween: Tween) {
+        let mut state = self.state.lock();
+        if let Some(mut track) = state.track.take() {
+            track.stop(tween);
+        }
+        if let Some(mut clock) = state.clock.take() {
+            clock.stop();
+        }
+    }
+
+    /// Returns the current position in beats, negative before the offset.
+    ///
+    /// Returns `None` in case no track was played.
+    pub fn beat(&self) -> Option<f64> {
+        let state = self.state.lock();
+        let now = state.clock.as_ref()?.time().ticks;
+        Some((now as f64 - state.beat_tick(0) as f64) / TICKS_PER_BEAT as f64)
+    }
+
+    /// Returns the next beat to be heard.
+    pub fn next_beat(&self) -> Option<u64> {
+        let beat = self.beat()?;
+        Some(if beat < 0.0 { 0 } else { beat as u64 + 1 })
+    }
+
+    /// Returns the first beat of the next bar to be heard.
+    pub fn next_downbeat(&self) -> Option<u64> {
+        let beats_per_bar = self.state.lock().tempo.beats_per_bar.max(1) as u64;
+        Some(self.next_beat()?.div_ceil(beats_per_bar) * beats_per_bar)
+    }
+
+    /// Returns the clock time of the given beat, to start sounds right on it using [`Sound::play_at`].
+    pub fn beat_time(&self, beat: u64) -> Option<ClockTime> {
+        let state = self.state.lock();
+        state.clock_time(state.beat_tick(beat))
+    }
+}
+
+const INSTANT: Tween = Tween {
+    duration: Duration::ZERO,
+    easing: Easing::Linear,
+};
+
+/// Returns the beats of all metronomes that are due.
+///
+/// Gets called by the engine every frame to send the beats as events to the game.
+pub fn poll_beats() -> Vec<BeatEvent> {
+    let mut events = vec![];
+    let metronomes: Vec<_> = METRONOMES.lock().iter().filter_map(Weak::upgrade).collect();
+    for metronome in metronomes {
+        metronome.lock().poll(&mut events);
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tempo_needs_positive_bpm() {
+        assert!(Tempo::new(120.0).validate().is_ok());
+        for bpm in [0.0, -60.0, f64::NAN, f64::INFINITY] {
+            assert!(Tempo::new(bpm).validate().is_err());
+        }
+    }
+
+    fn state(lookahead: Duration) -> State {
+        State {
+            id: 0,
+            tempo: Tempo::new(120.0),
+            lookahead,
+            clock: None,
+            track: None,
+            start: 0,
+            next: 0,
+        }
+    }
+
+    #[test]
+    fn hitch_sends_only_the_latest_beat() {
+        let mut state = state(Duration::ZERO);
+        assert_eq!(state.due_beats(0), 0..1);
+        assert_eq!(state.due_beats(TICKS_PER_BEAT / 2), 1..1);
+
+        // A frame hitch of ten beats.
+        assert_eq!(state.due_beats(TICKS_PER_BEAT * 21 / 2), 10..11);
+        assert_eq!(state.due_beats(TICKS_PER_BEAT * 21 / 2), 11..11);
+        assert_eq!(state.due_beats(TICKS_PER_BEAT * 11), 11..12);
+    }
+
+    #[test]
+    fn lookahead_sends_beats_early() {
+        // Half a beat at 120 beats per minute.
+        let mut state = state(Duration::from_millis(250));
+        assert_eq!(state.due_beats(0), 0..1);
+        assert_eq!(state.due_beats(TICKS_PER_BEAT / 2), 1..2);
+        assert_eq!(state.due_beats(TICKS_PER_BEAT / 2 + 1), 2..2);
+    }
+
+    #[test]
+    fn offset_rounds_to_ticks() {
+        let tempo = Tempo::new(120.0).offset(0.5);
+        assert_eq!(tempo.offset_ticks(), TICKS_PER_BEAT);
+        assert_eq!(tempo.offset(-1.0).offset_ticks(), 0);
+    }
+}
@@ -0,0 +1,357 @@
+//! Background music with a queue of tracks, crossfades and ducking under voice lines.
+
+use anyhow::Result;
+use rand::seq::SliceRandom;
+
+use crate::{PlaybackState, Sound, SoundData, SoundSettings, Tween, Volume};
+
+/// What happens after the last track of the queue or the current track ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Repeat {
+    /// Stops after the last track.
+    #[default]
+    Off,
+    /// Plays the current track again.
+    Track,
+    /// Starts the queue from the beginning.
+    Queue,
+}
+
+/// Plays a queue of music tracks, fading from one track to the next.
+///
+/// The player does not run on its own. Call [`update`](MusicPlayer::update) every frame.
+///
+/// # Usage
+/// ```ignore
+/// let mut music = MusicPlayer::new()
+///     .crossfade(Tween { duration: Duration::from_secs(3), easing: Easing::Linear })
+///     .repeat(Repeat::Queue)
+///     .shuffle(true);
+/// music.queue(SoundData::from_file("music/forest.ogg")?);
+/// music.queue(SoundData::from_file("music/cave.ogg")?);
+/// music.play()?;
+///
+/// // Lowers the music while the voice line plays.
+/// music.play_voice(Sound::new(SoundData::from_file("voice/intro.ogg")?, SoundSettings::new()))?;
+/// ```
+pub struct MusicPlayer {
+    tracks: Vec<SoundData>,
+    /// The indices of the tracks in the order they get played.
+    order: Vec<usize>,
+    position: usize,
+    current: Option<Sound>,
+    /// Whether the current track was seen playing, as it only starts once the audio server got to it.
+    started: bool,
+    paused: bool,
+    /// Voice lines ducking the music along with whether they were seen playing.
+    voices: Vec<(Sound, bool)>,
+    crossfade: Tween,
+    volume: f64,
+    duck_volume: f64,
+    duck_tween: Tween,
+    shuffle: bool,
+    repeat: Repeat,
+}
+
+impl Default for MusicPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MusicPlayer {
+    /// Creates a player with an empty queue.
+    pub fn new() -> Self {
+        Self {
+            tracks: vec![],
+            order: vec![],
+            position: 0,
+            current: None,
+            started: false,
+            paused: false,
+            voices: vec![],
+            crossfade: Tween::default(),
+            volume: 1.0,
+            duck_volume: 0.3,
+            duck_tween: Tween::default(),
+            shuffle: false,
+            repeat: Repeat::Off,
+        }
+    }
+
+    /// Sets the fade between tracks and returns self.
+    pub fn crossfade(mut self, crossfade: Tween) -> Self {
+        self.crossfade = crossfade;
+        self
+    }
+
+    /// Sets the volume of the music as amplitude and returns self.
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Sets the volume of the music relative to its normal volume while a voice line plays and returns self.
+    ///
+    /// ## Default configuration
+    ///
+    /// 0.3
+    pub fn duck_volume(mut self, duck_volume: f64) -> Self {
+        self.duck_volume = duck_volume;
+        self
+    }
+
+    /// Sets the fade of the music volume when ducking and returns self.
+    pub fn duck_tween(mut self, duck_tween: Tween) -> Self {
+        self.duck_tween = duck_tween;
+        self
+    }
+
+    /// Sets whether the queue plays in random order and returns self.
+    pub fn shuffle(mut self, shuffle: bool) -> Self {
+        self.set_shuffle(shuffle);
+        self
+    }
+
+    /// Sets what happens when a track ends and returns self.
+    pub fn repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Sets the fade between tracks.
+    pub fn set_crossfade(&mut self, crossfade: Tween) {
+        self.crossfade = crossfade;
+    }
+
+    /// Changes the volume of the music as amplitude, fading over the given tween.
+    pub fn set_volume(&mut self, volume: f64, tween: Tween) {
+        self.volume = volume;
+        self.apply_volume(tween);
+    }
+
+    /// Returns the volume of the music as amplitude.
+    pub fn get_volume(&self) -> f64 {
+        self.volume
+    }
+
+    /// Sets the volume of the music relative to its normal volume while a voice line plays.
+    pub fn set_duck_volume(&mut self, duck_volume: f64) {
+        self.duck_volume = duck_volume;
+        self.apply_volume(self.duck_tween);
+    }
+
+    /// Sets the fade of the music volume when ducking.
+    pub fn set_duck_tween(&mut self, duck_tween: Tween) {
+        self.duck_tween = duck_tween;
+    }
+
+    /// Sets whether the queue plays in random order.
+    ///
+    /// The current track keeps playing at its place in the queue.
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+        let current = self.current_track();
+        self.order = (0..self.tracks.len()).collect();
+        if shuffle {
+            self.order.shuffle(&mut rand::thread_rng());
+        }
+        if let Some(current) = current {
+            let index = self
+                .order
+                .iter()
+                .position(|track| *track == current)
+                .expect("every track is in the order");
+            self.order.swap(index, self.position);
+        }
+    }
+
+    /// Sets what happens when a track ends.
+    pub fn set_repeat(&mut self, repeat: Repeat) {
+        self.repeat = repeat;
+    }
+
+    /// Adds a track to the end of the queue and returns its index.
+    pub fn queue(&mut self, track: SoundData) -> usize {
+        self.tracks.push(track);
+        let index = self.tracks.len() - 1;
+        self.order.push(index);
+        index
+    }
+
+    /// Stops the music and removes all tracks from the queue.
+    pub fn clear(&mut self, tween: Tween) {
+        self.stop(tween);
+        self.tracks.clear();
+        self.order.clear();
+        self.position = 0;
+    }
+
+    /// Returns all tracks in the order they got queued.
+    pub fn tracks(&self) -> &[SoundData] {
+        &self.tracks
+    }
+
+    /// Returns the index of the track currently playing.
+    pub fn current_track(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.order[self.position])
+    }
+
+    /// Returns true if music is playing.
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some() && !self.paused
+    }
+
+    /// Starts playing the queue, or resumes the music in case it is paused.
+    pub fn play(&mut self) -> Result<()> {
+        if self.paused {
+            self.resume(self.crossfade);
+        } else if self.current.is_none() && !self.order.is_empty() {
+            self.start(self.position)?;
+        }
+        Ok(())
+    }
+
+    /// Fades over to the track with the given index.
+    pub fn play_track(&mut self, track: usize) -> Result<()> {
+        if let Some(position) = self.order.iter().position(|index| *index == track) {
+            self.start(position)?;
+        }
+        Ok(())
+    }
+
+    /// Fades over to the next track of the queue, ignoring [`Repeat::Track`].
+    pub fn next(&mut self) -> Result<()> {
+        if self.order.is_empty() {
+            return Ok(());
+        }
+        self.start((self.position + 1) % self.order.len())
+    }
+
+    /// Fades over to the previous track of the queue.
+    pub fn previous(&mut self) -> Result<()> {
+        if self.order.is_empty() {
+            return Ok(());
+        }
+        self.start((self.position + self.order.len() - 1) % self.order.len())
+    }
+
+    /// Pauses the music.
+    pub fn pause(&mut self, tween: Tween) {
+        if let Some(current) = self.current.as_mut() {
+            current.pause(tween);
+            self.paused = true;
+        }
+    }
+
+    /// Resumes the music after pausing it.
+    pub fn resume(&mut self, tween: Tween) {
+        if let Some(current) = self.current.as_mut() {
+            current.resume(tween);
+        }
+        self.paused = false;
+    }
+
+    /// Stops the music. Playing again starts the current track from the beginning.
+    pub fn stop(&mut self, tween: Tween) {
+        if let Some(mut current) = self.current.take() {
+            current.stop(tween);
+        }
+        self.paused = false;
+    }
+
+    /// Plays a voice line, lowering the volume of the music until it and all other voice lines are over.
+    pub fn play_voice(&mut self, mut voice: Sound) -> Result<()> {
+        voice.play()?;
+        self.voices.push((voice, false));
+        self.apply_volume(self.duck_tween);
+        Ok(())
+    }
+
+    /// Starts the next track when the current one is about to end and restores the volume after voice lines.
+    pub fn update(&mut self) -> Result<()> {
+        let voices = self.voices.len();
+        // Voice lines are stopped until the audio server starts them, so only drop them once they were playing.
+        self.voices.retain_mut(|(voice, started)| {
+            let state = voice.state();
+            *started |= state == PlaybackState::Playing;
+            !*started || state != PlaybackState::Stopped
+        });
+        if self.voices.len() != voices {
+            self.apply_volume(self.duck_tween);
+        }
+
+        if self.paused {
+            return Ok(());
+        }
+        let Some(current) = self.current.as_ref() else {
+            return Ok(());
+        };
+
+        let state = current.state();
+        if state == PlaybackState::Playing {
+            self.started = true;
+        }
+        if !self.started {
+            return Ok(());
+        }
+
+        let remaining = current.data().duration().as_secs_f64() - current.position();
+        if state == PlaybackState::Stopped || remaining <= self.crossfade.duration.as_secs_f64() {
+            match self.following() {
+                Some(position) => self.start(position)?,
+                None => self.stop(self.crossfade),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the position in the order of the track playing after the current one ends.
+    fn following(&mut self) -> Option<usize> {
+        match self.repeat {
+            Repeat::Track => Some(self.position),
+            _ if self.position + 1 < self.order.len() => Some(self.position + 1),
+            Repeat::Queue => {
+                if self.shuffle {
+                    self.order.shuffle(&mut rand::thread_rng());
+                }
+                Some(0)
+            }
+            Repeat::Off => None,
+        }
+    }
+
+    /// Fades out the current track and fades in the track at the given position of the order.
+    fn start(&mut self, position: usize) -> Result<()> {
+        if let Some(mut previous) = self.current.take() {
+            previous.stop(self.crossfade);
+        }
+        self.position = position;
+        self.started = false;
+        self.paused = false;
+
+        let settings = SoundSettings::new()
+            .volume(Volume::Amplitude(self.target_volume()))
+            .fade_in_tween(Some(self.crossfade));
+        let mut sound = Sound::new(self.tracks[self.order[position]].clone(), settings);
+        sound.play()?;
+        self.current = Some(sound);
+        Ok(())
+    }
+
+    /// The volume the music should have right now.
+    fn target_volume(&self) -> f64 {
+        if self.voices.is_empty() {
+            self.volume
+        } else {
+            self.volume * self.duck_volume
+        }
+    }
+
+    fn apply_volume(&mut self, tween: Tween) {
+        let volume = self.target_volume();
+        if let Some(current) = self.current.as_mut() {
+            current.set_volume(Volume::Amplitude(volume), tween);
+        }
+    }
+}
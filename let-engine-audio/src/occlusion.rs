@@ -0,0 +1,226 @@
+//! Muffling sounds with colliders between them and the listener.
+
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use crossbeam::atomic::AtomicCell;
+use glam::Vec2;
+use kira::{
+    clock::clock_info::ClockInfoProvider,
+    modulator::value_provider::ModulatorValueProvider,
+    sound::{
+        static_sound::{StaticSoundData, StaticSoundHandle},
+        Sound, SoundData,
+    },
+    OutputDestination,
+};
+use let_engine_core::objects::{physics::CastFilter, scenes::Layer, Object, ObjectHandle};
+use parking_lot::Mutex;
+
+use crate::{Easing, Frame, Tween};
+
+/// The cutoff frequency at which the filter gets bypassed, as people do not hear much higher.
+pub(crate) const OPEN_CUTOFF: f32 = 20_000.0;
+/// The time in seconds the cutoff of the filter takes to mostly reach a new value, so it does not click.
+const CUTOFF_SMOOTHING: f32 = 0.05;
+
+/// How a sound bound to an object changes while colliders are between it and the listener.
+///
+/// The check is a ray from the closest listener in the layer of the object to the object,
+/// done every time the sound gets updated.
+#[derive(Clone, Debug)]
+pub struct OcclusionSettings {
+    /// The factor of the volume while occluded.
+    pub volume: f64,
+    /// The cutoff frequency in hertz of the low pass filter while occluded. Lower values muffle more.
+    pub cutoff: f32,
+    /// Decides which colliders block the sound. The objects of the sound and the listener never do.
+    pub filter: CastFilter,
+    /// The transition of the volume when the sound gets occluded or free again.
+    pub tween: Tween,
+}
+
+impl Default for OcclusionSettings {
+    fn default() -> Self {
+        Self {
+            volume: 0.4,
+            cutoff: 800.0,
+            filter: CastFilter::new().exclude_sensors(),
+            tween: Tween {
+                duration: Duration::from_millis(150),
+                easing: Easing::Linear,
+            },
+        }
+    }
+}
+
+impl OcclusionSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the factor of the volume while occluded and returns self.
+    #[inline]
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Sets the cutoff frequency of the low pass filter while occluded and returns self.
+    #[inline]
+    pub fn cutoff(mut self, cutoff: f32) -> Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    /// Sets the filter deciding which colliders block the sound and returns self.
+    #[inline]
+    pub fn filter(mut self, filter: CastFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Sets the transition of the volume and returns self.
+    #[inline]
+    pub fn tween(mut self, tween: Tween) -> Self {
+        self.tween = tween;
+        self
+    }
+}
+
+/// The place of a listener, shared with the sounds checking whether they are occluded.
+pub(crate) struct ListenerPosition {
+    layer: Weak<Layer>,
+    object: ObjectHandle,
+    position: AtomicCell<Vec2>,
+}
+
+static LISTENERS: Mutex<Vec<Weak<ListenerPosition>>> = Mutex::new(Vec::new());
+
+impl ListenerPosition {
+    /// Registers the object of a new listener. It stays registered until the returned position gets dropped.
+    pub fn register(object: &Object) -> Arc<Self> {
+        let position = Arc::new(Self {
+            layer: Arc::downgrade(object.layer()),
+            object: object.handle(),
+            position: AtomicCell::new(object.public_transform().position),
+        });
+        let mut listeners = LISTENERS.lock();
+        listeners.retain(|listener| listener.strong_count() > 0);
+        listeners.push(Arc::downgrade(&position));
+        position
+    }
+
+    pub fn set(&self, position: Vec2) {
+        self.position.store(position);
+    }
+}
+
+/// Returns true if a collider is between the given point and the closest listener of the layer.
+///
+/// Sounds without a listener in their layer are never occluded.
+pub(crate) fn is_occluded(
+    layer: &Arc<Layer>,
+    point: Vec2,
+    object: ObjectHandle,
+    settings: &OcclusionSettings,
+) -> bool {
+    let closest = LISTENERS
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .filter(|listener| listener.layer.as_ptr() == Arc::as_ptr(layer))
+        .map(|listener| (listener.position.load(), listener.object))
+        .min_by(|(a, _), (b, _)| {
+            a.distance_squared(point)
+                .total_cmp(&b.distance_squared(point))
+        });
+    let Some((listener, listener_object)) = closest else {
+        return false;
+    };
+
+    let mut filter = settings.filter.clone();
+    filter.exclude.extend([object, listener_object]);
+    layer
+        .cast_ray(listener, point - listener, 1.0, &filter)
+        .is_some()
+}
+
+/// Sound data played through a low pass filter, whose cutoff frequency can change while it plays.
+pub(crate) struct FilteredSoundData {
+    pub data: StaticSoundData,
+    /// The bits of the target cutoff frequency in hertz.
+    pub cutoff: Arc<AtomicU32>,
+}
+
+impl SoundData for FilteredSoundData {
+    type Error = <StaticSoundData as SoundData>::Error;
+    type Handle = StaticSoundHandle;
+
+    fn into_sound(self) -> Result<(Box<dyn Sound>, Self::Handle), Self::Error> {
+        let (sound, handle) = self.data.into_sound()?;
+        let cutoff = f32::from_bits(self.cutoff.load(Ordering::Relaxed));
+        Ok((
+            Box::new(FilteredSound {
+                sound,
+                target: self.cutoff,
+                cutoff,
+                previous: Frame::ZERO,
+            }),
+            handle,
+        ))
+    }
+}
+
+struct FilteredSound {
+    sound: Box<dyn Sound>,
+    target: Arc<AtomicU32>,
+    cutoff: f32,
+    previous: Frame,
+}
+
+impl Sound for FilteredSound {
+    fn output_destination(&mut self) -> OutputDestination {
+        self.sound.output_destination()
+    }
+
+    fn on_start_processing(&mut self) {
+        self.sound.on_start_processing();
+    }
+
+    fn process(
+        &mut self,
+        dt: f64,
+        clock_info_provider: &ClockInfoProvider,
+        modulator_value_provider: &ModulatorValueProvider,
+    ) -> Frame {
+        let input = self
+            .sound
+            .process(dt, clock_info_provider, modulator_value_provider);
+        let dt = dt as f32;
+
+        let target = f32::from_bits(self.target.load(Ordering::Relaxed));
+        self.cutoff += (target - self.cutoff) * (dt / CUTOFF_SMOOTHING).min(1.0);
+        if (target - self.cutoff).abs() < 1.0 {
+            self.cutoff = target;
+        }
+        if self.cutoff >= OPEN_CUTOFF {
+            self.previous = input;
+            return input;
+        }
+
+        // A one pole low pass filter.
+        let alpha = 1.0 - (-std::f32::consts::TAU * self.cutoff * dt).exp();
+        self.previous = self.previous + (input - self.previous) * alpha;
+        self.previous
+    }
+
+    fn finished(&self) -> bool {
+        self.sound.finished()
+    }
+}
@@ -0,0 +1,226 @@
+//! Generating and combining sounds at runtime, for retro sound effects without shipping samples.
+//!
+//! # Usage
+//! ```ignore
+//! // A laser shot falling in pitch.
+//! let laser = SoundData::gen_sweep(Waveform::Square, 1200.0, 200.0, 0.25)
+//!     .envelope(Envelope::new(0.01, 0.05, 0.6, 0.1))
+//!     .amplify(0.4);
+//! let hit = SoundData::gen_noise(0.1).envelope(Envelope::new(0.0, 0.1, 0.0, 0.0));
+//! let sound = Sound::new(laser.concat(&hit), SoundSettings::new());
+//! ```
+
+use std::{f32::consts::TAU, sync::Arc};
+
+use crate::{capture::resample, Frame, SoundData};
+
+/// The sample rate of generated sounds.
+const SAMPLE_RATE: u32 = 44100;
+
+/// The shape of a generated wave.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Triangle,
+    Sawtooth,
+    /// White noise, ignoring the frequency.
+    Noise,
+}
+
+impl Waveform {
+    /// Returns the value of the wave at the given phase from 0 to 1.
+    pub fn sample(&self, phase: f32) -> f32 {
+        match self {
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Sawtooth => 2.0 * phase - 1.0,
+            Waveform::Noise => rand::random::<f32>() * 2.0 - 1.0,
+        }
+    }
+}
+
+/// An attack, decay, sustain and release envelope shaping the volume of a sound over time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Envelope {
+    /// Seconds to rise from silence to full volume.
+    pub attack: f32,
+    /// Seconds to fall from full volume to the sustain level.
+    pub decay: f32,
+    /// The volume held after the decay from 0 to 1.
+    pub sustain: f32,
+    /// Seconds to fade out to silence at the end of the sound.
+    pub release: f32,
+}
+
+impl Envelope {
+    pub fn new(attack: f32, decay: f32, sustain: f32, release: f32) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+
+    /// Returns the volume at the given time of a sound with the given length in seconds.
+    pub fn volume(&self, time: f32, length: f32) -> f32 {
+        let level = if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        };
+        let release_start = length - self.release;
+        if time > release_start && self.release > 0.0 {
+            level * (1.0 - (time - release_start) / self.release).max(0.0)
+        } else {
+            level
+        }
+    }
+}
+
+/// Synthesis
+impl SoundData {
+    /// Generates a wave of the given shape with the frequency in hertz and the length in seconds.
+    pub fn gen_wave(waveform: Waveform, frequency: f64, length: f64) -> Self {
+        Self::gen_sweep(waveform, frequency, frequency, length)
+    }
+
+    /// Generates triangle wave sound data with length as seconds.
+    pub fn gen_triangle_wave(frequency: f64, length: f64) -> Self {
+        Self::gen_wave(Waveform::Triangle, frequency, length)
+    }
+
+    /// Generates sawtooth wave sound data with length as seconds.
+    pub fn gen_sawtooth_wave(frequency: f64, length: f64) -> Self {
+        Self::gen_wave(Waveform::Sawtooth, frequency, length)
+    }
+
+    /// Generates white noise with length as seconds.
+    pub fn gen_noise(length: f64) -> Self {
+        Self::gen_wave(Waveform::Noise, 0.0, length)
+    }
+
+    /// Generates a wave gliding from one frequency to another over the length in seconds.
+    pub fn gen_sweep(waveform: Waveform, from: f64, to: f64, length: f64) -> Self {
+        let num_samples = (SAMPLE_RATE as f64 * length) as usize;
+        let mut phase = 0.0f64;
+        let frames: Vec<Frame> = (0..num_samples)
+            .map(|i| {
+                let progress = i as f64 / num_samples as f64;
+                let value = waveform.sample(phase as f32);
+                phase = (phase + (from + (to - from) * progress) / SAMPLE_RATE as f64).fract();
+                Frame {
+                    left: value,
+                    right: value,
+                }
+            })
+            .collect();
+        Self::from_frames(frames, SAMPLE_RATE)
+    }
+
+    /// Creates sound data from raw frames.
+    pub fn from_frames(frames: impl Into<Arc<[Frame]>>, sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            frames: frames.into(),
+            slice: None,
+        }
+    }
+
+    /// Returns the frames of the sound, limited to the slice in case it has one.
+    pub fn sliced_frames(&self) -> &[Frame] {
+        match self.slice {
+            Some((start, end)) => &self.frames[start..end],
+            None => &self.frames,
+        }
+    }
+
+    /// Shapes the volume of the sound using the envelope.
+    pub fn envelope(&self, envelope: Envelope) -> Self {
+        let sample_rate = self.sample_rate as f32;
+        let length = self.sliced_frames().len() as f32 / sample_rate;
+        self.map(|index, frame| {
+            let volume = envelope.volume(index as f32 / sample_rate, length);
+            Frame {
+                left: frame.left * volume,
+                right: frame.right * volume,
+            }
+        })
+    }
+
+    /// Multiplies the amplitude of the sound.
+    pub fn amplify(&self, amplitude: f32) -> Self {
+        self.map(|_, frame| Frame {
+            left: frame.left * amplitude,
+            right: frame.right * amplitude,
+        })
+    }
+
+    /// Plays both sounds at the same time, returning a sound as long as the longer one.
+    ///
+    /// The other sound gets resampled in case its sample rate differs.
+    pub fn mix(&self, other: &SoundData) -> Self {
+        let other = other.resampled(self.sample_rate);
+        let (a, b) = (self.sliced_frames(), other.sliced_frames());
+        let frames: Vec<Frame> = (0..a.len().max(b.len()))
+            .map(|i| {
+                let (a, b) = (
+                    a.get(i).copied().unwrap_or(Frame::ZERO),
+                    b.get(i).copied().unwrap_or(Frame::ZERO),
+                );
+                Frame {
+                    left: a.left + b.left,
+                    right: a.right + b.right,
+                }
+            })
+            .collect();
+        Self::from_frames(frames, self.sample_rate)
+    }
+
+    /// Plays the other sound after this one.
+    ///
+    /// The other sound gets resampled in case its sample rate differs.
+    pub fn concat(&self, other: &SoundData) -> Self {
+        let other = other.resampled(self.sample_rate);
+        let frames: Vec<Frame> = self
+            .sliced_frames()
+            .iter()
+            .chain(other.sliced_frames())
+            .copied()
+            .collect();
+        Self::from_frames(frames, self.sample_rate)
+    }
+
+    /// Converts the sound to the given sample rate.
+    ///
+    /// Gives an empty sound in case one of the sample rates is 0.
+    pub fn resampled(&self, sample_rate: u32) -> Self {
+        if self.sample_rate == sample_rate {
+            return self.clone();
+        }
+        Self::from_frames(
+            resample(self.sliced_frames(), self.sample_rate, sample_rate).unwrap_or_default(),
+            sample_rate,
+        )
+    }
+
+    fn map(&self, mut f: impl FnMut(usize, Frame) -> Frame) -> Self {
+        let frames: Vec<Frame> = self
+            .sliced_frames()
+            .iter()
+            .enumerate()
+            .map(|(index, frame)| f(index, *frame))
+            .collect();
+        Self::from_frames(frames, self.sample_rate)
+    }
+}
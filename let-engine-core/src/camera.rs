@@ -67,6 +67,9 @@ impl CameraScaling {
 }
 
 /// Settings that determine your camera vision.
+///
+/// Make them using the builder methods or a struct literal ending in `..Default::default()`,
+/// so new fields do not break the code.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy)]
 pub struct CameraSettings {
@@ -74,12 +77,18 @@ pub struct CameraSettings {
     pub zoom: f32,
     /// The scaling mode. Default is `Stretch`.
     pub mode: CameraScaling,
+    /// The area of the window the camera is allowed to draw to. Default is `None`, which is the whole viewport.
+    ///
+    /// Unlike the viewport it cuts the view off without changing the projection,
+    /// for example for split-screen borders or a view sliding in from the side.
+    pub scissor: Option<Viewport>,
 }
 impl Default for CameraSettings {
     fn default() -> Self {
         Self {
             zoom: 1.0,
             mode: CameraScaling::Stretch,
+            scissor: None,
         }
     }
 }
@@ -96,4 +105,51 @@ impl CameraSettings {
         self.mode = mode;
         self
     }
+    /// Sets the area of the window the camera is allowed to draw to and returns self.
+    #[inline]
+    pub fn scissor(mut self, scissor: Option<Viewport>) -> Self {
+        self.scissor = scissor;
+        self
+    }
+}
+
+/// The area of the window a camera draws to, where `(0, 0)` is the top left and `(1, 1)` the bottom right corner.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Default for Viewport {
+    /// The whole window.
+    fn default() -> Self {
+        Self {
+            min: Vec2::ZERO,
+            max: Vec2::ONE,
+        }
+    }
+}
+
+impl Viewport {
+    /// Creates a new viewport from the top left and bottom right corner.
+    pub const fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// The left half of the window, for two player split-screen.
+    pub const LEFT: Self = Self::new(vec2(0.0, 0.0), vec2(0.5, 1.0));
+    /// The right half of the window, for two player split-screen.
+    pub const RIGHT: Self = Self::new(vec2(0.5, 0.0), vec2(1.0, 1.0));
+    /// The upper half of the window, for two player split-screen.
+    pub const TOP: Self = Self::new(vec2(0.0, 0.0), vec2(1.0, 0.5));
+    /// The lower half of the window, for two player split-screen.
+    pub const BOTTOM: Self = Self::new(vec2(0.0, 0.5), vec2(1.0, 1.0));
+
+    /// Returns the offset and size of this viewport in pixels for the given window size.
+    pub fn pixels(&self, dimensions: Vec2) -> (Vec2, Vec2) {
+        let min = self.min.clamp(Vec2::ZERO, Vec2::ONE) * dimensions;
+        let max = self.max.clamp(Vec2::ZERO, Vec2::ONE) * dimensions;
+        (min, (max - min).max(Vec2::ZERO))
+    }
 }
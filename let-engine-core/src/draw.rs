@@ -1,18 +1,31 @@
 use anyhow::Result;
 use parking_lot::{Mutex, RwLock};
 use std::{
+    collections::{HashMap, VecDeque},
     sync::{atomic::AtomicBool, Arc, OnceLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use vulkano::{
+    buffer::{BufferContents, Subbuffer},
     command_buffer::{
         CommandBuffer, CommandBufferBeginInfo, CommandBufferInheritanceInfo, CommandBufferLevel,
         CommandBufferUsage, RecordingCommandBuffer, RenderPassBeginInfo, SubpassBeginInfo,
-        SubpassContents,
+        SubpassContents, SubpassEndInfo,
     },
-    descriptor_set::{DescriptorSet, WriteDescriptorSet},
-    pipeline::{graphics::viewport::Viewport, Pipeline},
-    render_pass::Framebuffer,
+    descriptor_set::{layout::DescriptorSetLayout, DescriptorSet, WriteDescriptorSet},
+    format::{ClearValue, Format},
+    image::{
+        sampler::{Sampler, SamplerCreateInfo},
+        view::ImageView,
+    },
+    pipeline::{
+        graphics::{
+            depth_stencil::StencilFaces,
+            viewport::{Scissor, Viewport},
+        },
+        GraphicsPipeline, Pipeline,
+    },
+    render_pass::{Framebuffer, Subpass},
     swapchain::{
         acquire_next_image, Surface, Swapchain, SwapchainAcquireFuture, SwapchainCreateInfo,
         SwapchainPresentInfo,
@@ -22,25 +35,33 @@ use vulkano::{
 };
 use winit::event_loop::EventLoop;
 
+pub use crate::gpu_timings::{GpuPass, GpuTimings};
 use crate::{
     camera::CameraSettings,
-    objects::{scenes::SCENE, Instance, Node, Object, VisualObject},
+    gpu_timings::{GpuTimer, Timestamp},
+    objects::{
+        scenes::{FrozenFrame, Layer, SCENE},
+        AntiAliasing, Appearance, Color, Instance, Light, Object, StencilPass, VisualObject,
+    },
+    render_callbacks::{LayerView, RenderCallbackId, RenderCallbacks, RenderContext, RenderStage},
     resources::{
-        data::{InstanceData, ModelViewProj, ObjectFrag},
+        data::{EngineConstants, InstanceData, LightsFrag, ModelViewProj, ObjectFrag},
+        materials::{DistanceField, Material, LIGHTS_SET, OBJECT_SET},
         resources,
+        textures::Texture,
         vulkan::{
-            swapchain::create_swapchain_and_images, window::create_window,
-            window_size_dependent_setup,
+            frozen_layer_setup, swapchain::create_swapchain_and_images, window::create_window,
+            window_size_dependent_setup, BINDLESS_TEXTURES,
         },
-        Loader, Model,
+        Loader, Model, ModelData,
     },
-    utils::ortho_maker,
+    utils::view_proj,
     window::{Window, WindowBuilder},
 };
 
 //use cgmath::{Deg, Matrix3, Matrix4, Ortho, Point3, Rad, Vector3};
 use glam::{
-    f32::{Mat4, Quat, Vec3},
+    f32::{Mat4, Quat, Vec2, Vec3, Vec4},
     vec2,
 };
 
@@ -50,15 +71,87 @@ pub static VIEWPORT: RwLock<Viewport> = RwLock::new(Viewport {
     depth_range: 0.0..=1.0,
 });
 
+/// The seconds after which the time in the engine constants starts over.
+const SHADER_TIME_PERIOD: f64 = 3600.0;
+
+/// Set by writes to the contents of textures and buffers, which damage tracking can not see.
+static CONTENTS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+/// Makes sure the next frame gets drawn after the contents of a texture or buffer changed.
+pub(crate) fn contents_changed() {
+    CONTENTS_CHANGED.store(true, std::sync::atomic::Ordering::Release);
+}
+
 /// Responsible for drawing on the surface.
 pub struct Draw {
     pub surface: Arc<Surface>,
     pub window: Arc<Window>,
-    pub swapchain: Arc<Swapchain>,
+    /// `None` only while the device gets rebuilt after it got lost.
+    swapchain: Option<Arc<Swapchain>>,
     pub framebuffers: Vec<Arc<Framebuffer>>,
     pub previous_frame_end: Option<Box<dyn GpuFuture>>,
+    /// The frames submitted to the GPU with their ID in the frame buffer of the loader,
+    /// along with a function returning true once they finished.
+    frames_in_flight: VecDeque<(u64, Box<dyn Fn() -> Result<bool, VulkanoError>>)>,
+    /// `None` in case the GPU does not support timestamps.
+    gpu_timer: Option<GpuTimer>,
     graphics: Arc<Graphics>,
     dimensions: [u32; 2],
+    /// The time the engine started drawing.
+    started: Instant,
+    /// The time the current frame started drawing.
+    frame_start: Instant,
+    /// The seconds between the starts of the previous and the current frame.
+    delta_time: f32,
+    /// What the last drawn frame showed, kept while damage tracking is enabled.
+    drawn: Option<DrawnFrame>,
+    /// The time to check for changes again after the latest frame got skipped by damage tracking.
+    idle_until: Option<Instant>,
+    /// True while the application is suspended and the surface can not be drawn on.
+    suspended: bool,
+    /// The images of the frozen layers, drawn in their place instead of their objects.
+    frozen_images: Vec<FrozenImage>,
+    /// The textures of the texture array, kept between frames so it only gets written to when they change.
+    textures: Mutex<TextureTable>,
+}
+
+/// Everything deciding the image of a frame, except for the contents of textures and uniform buffers.
+struct DrawnFrame {
+    layers: Vec<DrawnLayer>,
+    clear_color: Color,
+    hdr: HdrSettings,
+    dimensions: [u32; 2],
+}
+
+struct DrawnLayer {
+    layer: Arc<Layer>,
+    frame: Arc<FrozenFrame>,
+    frozen: bool,
+    lights: Vec<Light>,
+    ambient_light: Color,
+}
+
+/// The image a frozen layer got drawn to at the frame it got frozen at.
+struct FrozenImage {
+    frame: Arc<FrozenFrame>,
+    framebuffer: Arc<Framebuffer>,
+    /// Reads the image when drawing it over the scene.
+    set: Arc<DescriptorSet>,
+}
+
+impl DrawnFrame {
+    fn same_image(&self, other: &Self) -> bool {
+        self.clear_color == other.clear_color
+            && self.hdr == other.hdr
+            && self.dimensions == other.dimensions
+            && self.layers.len() == other.layers.len()
+            && self.layers.iter().zip(&other.layers).all(|(a, b)| {
+                Arc::ptr_eq(&a.layer, &b.layer)
+                    && a.lights == b.lights
+                    && a.ambient_light == b.ambient_light
+                    && (Arc::ptr_eq(&a.frame, &b.frame) || a.frame.same_image(&b.frame))
+            })
+    }
 }
 
 impl Draw {
@@ -67,12 +160,17 @@ impl Draw {
         event_loop: &EventLoop<()>,
         graphics: Arc<Graphics>,
     ) -> Result<Self> {
-        let vulkan = resources()?.vulkan().clone();
+        let vulkan = resources()?.vulkan();
         let loader = resources()?.loader().lock();
         let (surface, window) =
             create_window(event_loop, &resources()?.vulkan().instance, window_builder)?;
 
-        let (swapchain, images) = create_swapchain_and_images(&vulkan.device, &surface, &graphics)?;
+        let (swapchain, images) = create_swapchain_and_images(
+            &vulkan.device,
+            &surface,
+            &graphics,
+            vulkan.surface_format,
+        )?;
 
         let mut viewport = Viewport {
             offset: [0.0; 2],
@@ -80,8 +178,12 @@ impl Draw {
             depth_range: 0.0..=1.0,
         };
 
-        let framebuffers =
-            window_size_dependent_setup(&images, vulkan.render_pass.clone(), &mut viewport)?;
+        let framebuffers = window_size_dependent_setup(
+            &images,
+            vulkan.render_pass.clone(),
+            &mut viewport,
+            &loader.memory_allocator,
+        )?;
 
         *VIEWPORT.write() = viewport;
 
@@ -102,11 +204,21 @@ impl Draw {
         Ok(Self {
             surface,
             window,
-            swapchain,
+            swapchain: Some(swapchain),
             framebuffers,
             previous_frame_end,
+            frames_in_flight: VecDeque::new(),
+            gpu_timer: GpuTimer::new(&vulkan)?,
             graphics,
             dimensions,
+            started: Instant::now(),
+            frame_start: Instant::now(),
+            delta_time: 0.0,
+            drawn: None,
+            idle_until: None,
+            suspended: false,
+            frozen_images: vec![],
+            textures: Mutex::default(),
         })
     }
 
@@ -114,6 +226,11 @@ impl Draw {
         &self.window
     }
 
+    /// Returns the swapchain the frames get presented to.
+    pub fn swapchain(&self) -> Option<&Arc<Swapchain>> {
+        self.swapchain.as_ref()
+    }
+
     /// Recreates the swapchain in case it is out of date if someone for example changed the scene size or window dimensions.
     fn recreate_swapchain(&mut self, loader: &mut Loader) -> Result<()> {
         if self
@@ -121,10 +238,11 @@ impl Draw {
             .recreate_swapchain
             .load(std::sync::atomic::Ordering::Acquire)
         {
-            let (new_swapchain, new_images) = match self.swapchain.recreate(SwapchainCreateInfo {
+            let swapchain = self.swapchain.as_ref().ok_or(VulkanError::DeviceLost)?;
+            let (new_swapchain, new_images) = match swapchain.recreate(SwapchainCreateInfo {
                 image_extent: self.dimensions,
                 present_mode: self.graphics.present_mode().into(),
-                ..self.swapchain.create_info()
+                ..swapchain.create_info()
             }) {
                 Ok(r) => r,
                 Err(e) => {
@@ -132,11 +250,13 @@ impl Draw {
                 }
             };
 
-            self.swapchain = new_swapchain;
+            self.swapchain = Some(new_swapchain);
+            self.graphics.stats.lock().swapchain_recreations += 1;
             self.framebuffers = window_size_dependent_setup(
                 &new_images,
                 resources()?.vulkan().render_pass.clone(),
                 &mut VIEWPORT.write(),
+                &loader.memory_allocator,
             )
             .map_err(VulkanError::Other)?;
             loader.pipelines.clear();
@@ -149,15 +269,15 @@ impl Draw {
 
     /// Makes a primary and secondary command buffer already inside a render pass.
     fn make_command_buffer(
-        &self,
+        &mut self,
         image_num: usize,
         clear_color: [f32; 4],
-        loader: &Loader,
+        loader: &mut Loader,
+        drawn: &DrawnFrame,
     ) -> Result<(RecordingCommandBuffer, RecordingCommandBuffer), VulkanError> {
         let vulkan = resources()
             .map_err(|e| VulkanError::Other(e.into()))?
-            .vulkan()
-            .clone();
+            .vulkan();
         let mut builder = RecordingCommandBuffer::new(
             loader.command_buffer_allocator.clone(),
             vulkan.queue.queue_family_index(),
@@ -170,11 +290,46 @@ impl Draw {
         .map_err(Validated::unwrap)
         .map_err(VulkanError::Validated)?;
 
+        if let Some(timer) = &mut self.gpu_timer {
+            // The timings of a frame get read a few frames later, once the GPU is done with it.
+            if let Some(timings) = timer
+                .begin_frame(&mut builder)
+                .map_err(VulkanError::Other)?
+            {
+                *self.graphics.gpu_timings.lock() = Some(timings);
+            }
+        }
+        self.timestamp(&mut builder, Timestamp::FrameStart)?;
+
+        self.render_callbacks(
+            RenderStage::BeforePass,
+            &mut builder,
+            loader,
+            (VIEWPORT.read().clone(), self.window_scissor()),
+            None,
+        )
+        .map_err(VulkanError::Other)?;
+        self.draw_frozen_layers(&mut builder, loader, drawn)
+            .map_err(VulkanError::Other)?;
+        self.timestamp(&mut builder, Timestamp::PassStart)?;
+
         // Makes a commandbuffer that takes multiple secondary buffers.
         builder
             .begin_render_pass(
                 RenderPassBeginInfo {
-                    clear_values: vec![Some(clear_color.into())],
+                    // The scene gets drawn to an HDR image first in case the window uses an HDR color space.
+                    clear_values: if vulkan.tonemap.is_some() {
+                        vec![
+                            Some(clear_color.into()),
+                            None,
+                            Some(ClearValue::DepthStencil((1.0, 0))),
+                        ]
+                    } else {
+                        vec![
+                            Some(clear_color.into()),
+                            Some(ClearValue::DepthStencil((1.0, 0))),
+                        ]
+                    },
                     ..RenderPassBeginInfo::framebuffer(self.framebuffers[image_num].clone())
                 },
                 SubpassBeginInfo {
@@ -201,11 +356,71 @@ impl Draw {
         .map_err(VulkanError::Validated)?;
         secondary_builder
             .set_viewport(0, [VIEWPORT.read().clone()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_scissor(0, [self.window_scissor()].into_iter().collect())
             .map_err(|e| VulkanError::Other(e.into()))?;
+        self.timestamp(&mut secondary_builder, Timestamp::SceneStart)?;
 
         Ok((builder, secondary_builder))
     }
 
+    /// Writes a timestamp of the GPU timings, in case the GPU supports them.
+    fn timestamp(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        timestamp: Timestamp,
+    ) -> Result<(), VulkanError> {
+        match &self.gpu_timer {
+            Some(timer) => timer
+                .write(command_buffer, timestamp)
+                .map_err(VulkanError::Other),
+            None => Ok(()),
+        }
+    }
+
+    /// Calls the render callbacks of the given stage, recording to the given command buffer.
+    fn render_callbacks(
+        &self,
+        stage: RenderStage,
+        command_buffer: &mut RecordingCommandBuffer,
+        loader: &Loader,
+        (viewport, scissor): (Viewport, Scissor),
+        layer: Option<LayerView>,
+    ) -> Result<()> {
+        let callbacks = &self.graphics.render_callbacks;
+        if !callbacks.has(stage) {
+            return Ok(());
+        }
+        callbacks.run(&mut RenderContext {
+            command_buffer,
+            loader,
+            stage,
+            subpass: resources()?.vulkan().subpass.clone(),
+            viewport,
+            scissor,
+            layer,
+        });
+        Ok(())
+    }
+
+    /// Returns the scissor of the given area in pixels, rounded to whole pixels.
+    fn scissor(offset: Vec2, extent: Vec2) -> Scissor {
+        let min = offset.round();
+        let max = (offset + extent).round();
+        Scissor {
+            offset: [min.x as u32, min.y as u32],
+            extent: [(max.x - min.x) as u32, (max.y - min.y) as u32],
+        }
+    }
+
+    /// Returns the scissor covering the whole window.
+    fn window_scissor(&self) -> Scissor {
+        Scissor {
+            offset: [0, 0],
+            extent: self.dimensions,
+        }
+    }
+
     fn make_mvp_matrix(
         object: &VisualObject,
         dimensions: [u32; 2],
@@ -220,74 +435,239 @@ impl Draw {
         // Model matrix
         let model = Mat4::from_scale_rotation_translation(scaling, rotation, translation);
 
-        // View matrix
-        let rotation = Mat4::from_rotation_z(camera.transform.rotation);
-
-        let zoom = 1.0 / camera_settings.zoom;
-
-        // Projection matrix
-        let proj = ortho_maker(
-            camera_settings.mode,
-            camera.transform.position,
-            zoom,
+        let (view, proj) = view_proj(
+            camera.transform,
+            camera_settings,
             vec2(dimensions[0] as f32, dimensions[1] as f32),
         );
-
-        let view = Mat4::look_at_rh(
-            Vec3::from([
-                camera.transform.position[0],
-                camera.transform.position[1],
-                1.0,
-            ]),
-            Vec3::from([
-                camera.transform.position[0],
-                camera.transform.position[1],
-                0.0,
-            ]),
-            Vec3::Y,
-        ) * rotation;
         (model, view, proj)
     }
 
+    /// Returns the fragment shader data of a non instanced object.
+    fn object_frag(appearance: &Appearance) -> ObjectFrag {
+        let mut frag = ObjectFrag {
+            color: (*appearance.get_color()).into(),
+            texture_id: if let Some(material) = appearance.get_material() {
+                material.layer()
+            } else {
+                0
+            },
+            distance_range: 0.0,
+            outline_width: 0.0,
+            glow_width: 0.0,
+            outline_color: Vec4::ZERO,
+            glow_color: Vec4::ZERO,
+        };
+        match *appearance.get_anti_aliasing() {
+            AntiAliasing::None => (),
+            AntiAliasing::Msdf { distance_range } => frag.distance_range = distance_range,
+            AntiAliasing::Sdf {
+                distance_range,
+                outline_width,
+                outline_color,
+                glow_width,
+                glow_color,
+            } => {
+                frag.distance_range = distance_range;
+                frag.outline_width = outline_width;
+                frag.glow_width = glow_width;
+                frag.outline_color = outline_color.into();
+                frag.glow_color = glow_color.into();
+            }
+        }
+        frag
+    }
+
+    /// Returns true if the bounds of a model transformed by the given matrix lie completely outside of the screen.
+    fn outside_view([min, max]: [Vec2; 2], mvp: Mat4) -> bool {
+        let corners = [
+            vec2(min.x, min.y),
+            vec2(max.x, min.y),
+            vec2(min.x, max.y),
+            vec2(max.x, max.y),
+        ]
+        .map(|corner| mvp.project_point3(corner.extend(0.0)));
+
+        corners.iter().all(|corner| corner.x < -1.0)
+            || corners.iter().all(|corner| corner.x > 1.0)
+            || corners.iter().all(|corner| corner.y < -1.0)
+            || corners.iter().all(|corner| corner.y > 1.0)
+    }
+
     /// Draws the Game Scene on the given command buffer.
+    /// Draws the given layers, returning the statistics of drawing them.
     fn write_secondary_command_buffer(
         &self,
         command_buffer: &mut RecordingCommandBuffer,
         loader: &mut Loader,
-    ) -> Result<()> {
-        for layer in SCENE.layers().iter() {
-            let mut order: Vec<VisualObject> = Vec::with_capacity(layer.objects_map.lock().len());
-            let mut instances: Vec<Instance> = vec![];
+        layers: &[DrawnLayer],
+    ) -> Result<RenderStats> {
+        let bindless = self.graphics.bindless_textures();
+        let mut textures = self.textures.lock();
+        let mut frame_stats = RenderStats::default();
+        let mut last_pipeline: Option<Arc<GraphicsPipeline>> = None;
+        let mut count_pipeline = |pipeline: &Arc<GraphicsPipeline>, stats: &mut RenderStats| {
+            if !last_pipeline
+                .as_ref()
+                .is_some_and(|last| Arc::ptr_eq(last, pipeline))
+            {
+                stats.pipeline_switches += 1;
+                last_pipeline = Some(pipeline.clone());
+            }
+        };
+        let window_size = vec2(self.dimensions[0] as f32, self.dimensions[1] as f32);
+        for DrawnLayer { layer, frame, .. } in layers {
+            // Frozen layers already got drawn to their own image.
+            if let Some(image) = self
+                .frozen_images
+                .iter()
+                .find(|image| Arc::ptr_eq(&image.frame, frame))
+            {
+                self.draw_frozen_image(command_buffer, image)?;
+                frame_stats.draw_calls += 1;
+                continue;
+            }
+            let culling = layer.culling();
+            // Uploaded once the first lit object of the layer gets drawn.
+            let mut lights: Option<Subbuffer<LightsFrag>> = None;
+
+            // Every camera draws the whole layer to its own part of the window.
+            for view in frame.views.iter() {
+                let (offset, extent) = view.viewport.pixels(window_size);
+                if extent.x < 1.0 || extent.y < 1.0 {
+                    continue;
+                }
+                let viewport = Viewport {
+                    offset: offset.into(),
+                    extent: extent.into(),
+                    depth_range: 0.0..=1.0,
+                };
+                // Without a scissor set the camera draws to its whole viewport.
+                let scissor = match view.settings.scissor {
+                    Some(scissor) => {
+                        let (offset, extent) = scissor.pixels(window_size);
+                        Self::scissor(offset, extent)
+                    }
+                    None => Self::scissor(offset, extent),
+                };
+                if scissor.extent.contains(&0) {
+                    continue;
+                }
+                let layer_view = || {
+                    let (view, proj) = view_proj(view.camera.transform, view.settings, extent);
+                    LayerView { layer, view, proj }
+                };
+                self.render_callbacks(
+                    RenderStage::BeforeLayer,
+                    command_buffer,
+                    loader,
+                    (viewport.clone(), scissor),
+                    Some(layer_view()),
+                )?;
+                command_buffer
+                    .set_viewport(0, [viewport.clone()].into_iter().collect())
+                    .map_err(|e| VulkanError::Other(e.into()))?
+                    .set_scissor(0, [scissor].into_iter().collect())
+                    .map_err(|e| VulkanError::Other(e.into()))?
+                    .set_stencil_reference(StencilFaces::FrontAndBack, 0)
+                    .map_err(|e| VulkanError::Other(e.into()))?;
+                let dimensions = [extent.x as u32, extent.y as u32];
+                let constants = {
+                    let (view, proj) = view_proj(view.camera.transform, view.settings, extent);
+                    EngineConstants {
+                        camera: (proj * view).to_cols_array_2d(),
+                        resolution: extent.into(),
+                        time: self.shader_time(),
+                        delta_time: self.delta_time,
+                        object_id: 0,
+                    }
+                };
+                // The stencil value objects get drawn at, which is the number of masks they are in.
+                let mut reference = 0;
 
-            Node::order_position(&mut order, &layer.root.lock());
+                let mut instances: Vec<Instance> = vec![];
+                let mut batch: Option<Batch> = None;
 
-            for object in order {
-                let appearance = &object.appearance;
+                for object in frame.objects.iter() {
+                    let appearance = &object.appearance;
 
-                let Some(model) = appearance.get_model() else {
-                    continue;
-                };
+                    let Some(model) = appearance.get_model() else {
+                        continue;
+                    };
 
-                let vulkan = resources()?.vulkan();
-                let shapes = resources()?.shapes().clone();
+                    let vulkan = resources()?.vulkan();
+                    let shapes = resources()?.shapes().clone();
 
-                let model_data = match model {
-                    Model::Custom(data) => data,
-                    Model::Square => &shapes.square,
-                    Model::Triangle => &shapes.triangle,
-                };
+                    let model_data = match model {
+                        Model::Custom(data) => data,
+                        Model::Square => &shapes.square,
+                        Model::Triangle => &shapes.triangle,
+                    };
+
+                    let (model, view, proj) =
+                        Self::make_mvp_matrix(object, dimensions, &view.camera, view.settings);
+
+                    // Skip objects completely outside of the view of the camera.
+                    if culling && Self::outside_view(model_data.bounds(), proj * view * model) {
+                        frame_stats.objects_culled += 1;
+                        continue;
+                    }
+
+                    // Materials holding objects of a lost device wait for the game to make them again.
+                    if appearance
+                        .get_material()
+                        .is_some_and(|material| material.is_lost(&vulkan))
+                    {
+                        continue;
+                    }
+
+                    // Masks change the stencil buffer, so the pending batch has to be drawn before.
+                    if object.stencil != StencilPass::Draw || object.clip != reference {
+                        if let Some(batch) = batch.take() {
+                            let pipeline = self.draw_batch(
+                                command_buffer,
+                                loader,
+                                batch,
+                                &mut textures,
+                                constants,
+                                &mut frame_stats,
+                            )?;
+                            count_pipeline(&pipeline, &mut frame_stats);
+                        }
+                    }
+                    match object.stencil {
+                        StencilPass::Draw => (),
+                        StencilPass::Mask => {
+                            reference = object.clip - 1;
+                            Self::draw_mask(
+                                command_buffer,
+                                &vulkan.mask,
+                                model_data,
+                                proj * view * model,
+                                reference,
+                            )?;
+                            frame_stats.draw_calls += 1;
+                        }
+                        StencilPass::Unmask => {
+                            reference = object.clip;
+                            Self::draw_mask(
+                                command_buffer,
+                                &vulkan.unmask,
+                                model_data,
+                                proj * view * model,
+                                reference,
+                            )?;
+                            frame_stats.draw_calls += 1;
+                            continue;
+                        }
+                    }
+                    if object.clip != reference {
+                        reference = object.clip;
+                        command_buffer
+                            .set_stencil_reference(StencilFaces::FrontAndBack, reference)
+                            .map_err(|e| VulkanError::Other(e.into()))?;
+                    }
 
-                // Skip drawing the object if the object is not marked visible or has no vertices.
-                if appearance.is_instanced() {
-                    // appearance.instance.drawing.
-                    appearance.instance.draw(&mut instances);
-                    let mut data = appearance.instance.instance_data.lock();
-                    let (model, view, proj) = Self::make_mvp_matrix(
-                        &object,
-                        self.dimensions,
-                        &layer.camera.lock().lock().object,
-                        layer.camera_settings(),
-                    );
                     let instance_data = InstanceData {
                         model,
                         view,
@@ -295,168 +675,810 @@ impl Draw {
                         color: (*appearance.get_color()).into(),
                         layer: appearance.layer().unwrap_or(0),
                     };
-                    data.push(instance_data);
-                    continue;
-                };
 
-                let mut descriptors = vec![];
+                    // Skip drawing the object if the object is not marked visible or has no vertices.
+                    if appearance.is_instanced() {
+                        // appearance.instance.drawing.
+                        appearance.instance.draw(&mut instances);
+                        appearance.instance.instance_data.lock().push(instance_data);
+                        continue;
+                    };
 
-                // The pipeline of the current object. Takes the default one if there is none.
-                let pipeline = if let Some(material) = appearance.get_material() {
-                    if let Some(texture) = material.texture() {
-                        descriptors.push(texture.set().clone());
+                    // Consecutive objects with the same model and a default material get drawn in one instanced call.
+                    if matches!(appearance.get_anti_aliasing(), AntiAliasing::None) {
+                        let material = appearance.get_material();
+                        // Default textured objects with different textures share a batch using the texture array.
+                        let texture = match material {
+                            Some(material) if bindless => material.bindless_texture()?,
+                            _ => None,
+                        };
+                        if let (Some(texture), Some(instanced)) =
+                            (texture, &vulkan.bindless_instance_material)
+                        {
+                            let index = match textures.index(texture) {
+                                Some(index) => index,
+                                None => {
+                                    // The array is full, so the pending batch has to be drawn before starting over.
+                                    if let Some(batch) = batch.take() {
+                                        let pipeline = self.draw_batch(
+                                            command_buffer,
+                                            loader,
+                                            batch,
+                                            &mut textures,
+                                            constants,
+                                            &mut frame_stats,
+                                        )?;
+                                        count_pipeline(&pipeline, &mut frame_stats);
+                                    }
+                                    textures.clear();
+                                    textures
+                                        .index(texture)
+                                        .expect("The texture array is empty after clearing it.")
+                                }
+                            };
+                            let instance_data = InstanceData {
+                                layer: index,
+                                ..instance_data
+                            };
+                            if let Some(batch) = batch.as_mut().filter(|batch| {
+                                batch.bindless && batch.model.same_buffers(model_data)
+                            }) {
+                                batch.data.push(instance_data);
+                                continue;
+                            }
+                            if let Some(batch) = batch.take() {
+                                let pipeline = self.draw_batch(
+                                    command_buffer,
+                                    loader,
+                                    batch,
+                                    &mut textures,
+                                    constants,
+                                    &mut frame_stats,
+                                )?;
+                                count_pipeline(&pipeline, &mut frame_stats);
+                            }
+                            batch = Some(Batch {
+                                material: None,
+                                instanced: instanced.clone(),
+                                model: model_data.clone(),
+                                data: vec![instance_data],
+                                bindless: true,
+                            });
+                            continue;
+                        }
+                        if let Some(batch) = batch
+                            .as_mut()
+                            .filter(|batch| batch.accepts(material, model_data))
+                        {
+                            batch.data.push(instance_data);
+                            continue;
+                        }
+                        let instanced = match material {
+                            Some(material) => material.default_instanced()?,
+                            None => Some(vulkan.default_instance_material.clone()),
+                        };
+                        if let Some(instanced) = instanced {
+                            if let Some(batch) = batch.take() {
+                                let pipeline = self.draw_batch(
+                                    command_buffer,
+                                    loader,
+                                    batch,
+                                    &mut textures,
+                                    constants,
+                                    &mut frame_stats,
+                                )?;
+                                count_pipeline(&pipeline, &mut frame_stats);
+                            }
+                            batch = Some(Batch {
+                                material: material.cloned(),
+                                instanced,
+                                model: model_data.clone(),
+                                data: vec![instance_data],
+                                bindless: false,
+                            });
+                            continue;
+                        }
                     }
-                    if let Some(descriptor) = &material.descriptor {
-                        descriptors.push(descriptor.clone());
+
+                    // Keep the draw order by drawing the pending batch before this object.
+                    if let Some(batch) = batch.take() {
+                        let pipeline = self.draw_batch(
+                            command_buffer,
+                            loader,
+                            batch,
+                            &mut textures,
+                            constants,
+                            &mut frame_stats,
+                        )?;
+                        count_pipeline(&pipeline, &mut frame_stats);
                     }
-                    material
-                        .get_pipeline_or_recreate(loader)
-                        .map_err(VulkanError::Other)?
-                } else {
-                    vulkan
-                        .default_material
-                        .get_pipeline_or_recreate(loader)
-                        .map_err(VulkanError::Other)?
-                };
 
-                // MVP matrix for the object
-                let objectvert_sub_buffer = loader
-                    .object_buffer_allocator
-                    .allocate_sized()
-                    .map_err(|error| VulkanError::Other(error.into()))?;
-                // Simple color and texture data for the fragment shader.
-                let objectfrag_sub_buffer = loader
-                    .object_buffer_allocator
-                    .allocate_sized()
-                    .map_err(|error| VulkanError::Other(error.into()))?;
-
-                let (model, view, proj) = Self::make_mvp_matrix(
-                    &object,
-                    self.dimensions,
-                    &layer.camera.lock().lock().object,
-                    layer.camera_settings(),
-                );
-
-                *objectvert_sub_buffer
-                    .write()
-                    .map_err(|error| VulkanError::Other(error.into()))? =
-                    ModelViewProj { model, view, proj };
-                *objectfrag_sub_buffer
-                    .write()
-                    .map_err(|error| VulkanError::Other(error.into()))? = ObjectFrag {
-                    color: (*appearance.get_color()).into(),
-                    texture_id: if let Some(material) = appearance.get_material() {
-                        material.layer()
+                    // The sets of the engine in order, the object set getting inserted in front of them below.
+                    // Normal maps only get used together with a texture, so the lights end up at their set.
+                    let mut descriptors = vec![];
+                    // True if the material has a normal map, putting the lights of the layer after it.
+                    let mut lit = false;
+                    // The descriptor written to the material along with the set it goes to.
+                    let mut material_descriptor = None;
+
+                    // The pipeline of the current object. Takes the default one if there is none.
+                    let pipeline = if let Some(material) = appearance.get_material() {
+                        let texture = material.texture();
+                        if let Some(texture) = &texture {
+                            descriptors.push(texture.set());
+                        }
+                        // Distance field shading replaces the fragment shader, which would use the normal map.
+                        if let Some(normal_map) = material.normal_map().filter(|_| {
+                            material.lit()
+                                && matches!(appearance.get_anti_aliasing(), AntiAliasing::None)
+                        }) {
+                            descriptors.push(normal_map.set());
+                            lit = true;
+                        }
+                        // Anti aliased textured shapes use the distance field variant of their material,
+                        // which keeps its vertex shader and settings.
+                        let distance_field = match (appearance.get_anti_aliasing(), &texture) {
+                            (AntiAliasing::Msdf { .. }, Some(texture)) if texture.layers() == 1 => {
+                                Some(DistanceField::Msdf)
+                            }
+                            (AntiAliasing::Sdf { .. }, Some(texture)) if texture.layers() == 1 => {
+                                Some(DistanceField::Sdf)
+                            }
+                            _ => None,
+                        };
+                        let pipeline = match distance_field {
+                            Some(distance_field) => material
+                                .get_distance_field_pipeline_or_recreate(distance_field, loader),
+                            None => material.get_pipeline_or_recreate(loader),
+                        }
+                        .map_err(VulkanError::Other)?;
+                        if let Some(descriptor) = &material.descriptor {
+                            // The engine fragment shader does not use the descriptor, so it may not be in the layout.
+                            let index = material.descriptor_set_index();
+                            if pipeline.layout().set_layouts().len() > index {
+                                material_descriptor = Some((index as u32, descriptor.clone()));
+                            }
+                        }
+                        pipeline
                     } else {
-                        0
-                    },
-                };
+                        vulkan
+                            .default_material
+                            .get_pipeline_or_recreate(loader)
+                            .map_err(VulkanError::Other)?
+                    };
 
-                descriptors.insert(
-                    0,
-                    DescriptorSet::new(
-                        loader.descriptor_set_allocator.clone(),
-                        pipeline
-                            .layout()
-                            .set_layouts()
-                            .first()
-                            .ok_or(VulkanError::ShaderError)?
-                            .clone(),
-                        [
-                            WriteDescriptorSet::buffer(0, objectvert_sub_buffer.clone()),
-                            WriteDescriptorSet::buffer(1, objectfrag_sub_buffer.clone()),
-                        ],
-                        [],
-                    )
-                    .map_err(Validated::unwrap)
-                    .map_err(VulkanError::Validated)?,
-                );
+                    if lit {
+                        let buffer = match &lights {
+                            Some(buffer) => buffer.clone(),
+                            None => {
+                                let buffer = loader
+                                    .frame_buffer
+                                    .allocate_sized()
+                                    .map_err(VulkanError::Other)?;
+                                *buffer
+                                    .write()
+                                    .map_err(|error| VulkanError::Other(error.into()))? =
+                                    LightsFrag::new(layer.ambient_light(), &layer.lights());
+                                lights.insert(buffer).clone()
+                            }
+                        };
+                        descriptors.push(
+                            DescriptorSet::new(
+                                loader.descriptor_set_allocator.clone(),
+                                pipeline
+                                    .layout()
+                                    .set_layouts()
+                                    .get(LIGHTS_SET)
+                                    .ok_or(VulkanError::ShaderError)?
+                                    .clone(),
+                                [WriteDescriptorSet::buffer(0, buffer)],
+                                [],
+                            )
+                            .map_err(Validated::unwrap)
+                            .map_err(VulkanError::Validated)?,
+                        );
+                    }
 
-                let command_buffer = command_buffer
-                    .bind_pipeline_graphics(pipeline.clone())
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_descriptor_sets(
-                        vulkano::pipeline::PipelineBindPoint::Graphics,
-                        pipeline.layout().clone(),
-                        0,
-                        descriptors,
-                    )
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_vertex_buffers(0, model_data.vertex_buffer())
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_index_buffer(model_data.index_buffer())
-                    .map_err(|e| VulkanError::Other(e.into()))?;
-                unsafe {
+                    // MVP matrix for the object
+                    let objectvert_sub_buffer = loader
+                        .frame_buffer
+                        .allocate_sized()
+                        .map_err(VulkanError::Other)?;
+                    // Simple color and texture data for the fragment shader.
+                    let objectfrag_sub_buffer = loader
+                        .frame_buffer
+                        .allocate_sized()
+                        .map_err(VulkanError::Other)?;
+
+                    *objectvert_sub_buffer
+                        .write()
+                        .map_err(|error| VulkanError::Other(error.into()))? =
+                        ModelViewProj { model, view, proj };
+                    *objectfrag_sub_buffer
+                        .write()
+                        .map_err(|error| VulkanError::Other(error.into()))? =
+                        Self::object_frag(appearance);
+
+                    descriptors.insert(
+                        OBJECT_SET,
+                        DescriptorSet::new(
+                            loader.descriptor_set_allocator.clone(),
+                            pipeline
+                                .layout()
+                                .set_layouts()
+                                .get(OBJECT_SET)
+                                .ok_or(VulkanError::ShaderError)?
+                                .clone(),
+                            [
+                                WriteDescriptorSet::buffer(0, objectvert_sub_buffer.clone()),
+                                WriteDescriptorSet::buffer(1, objectfrag_sub_buffer.clone()),
+                            ],
+                            [],
+                        )
+                        .map_err(Validated::unwrap)
+                        .map_err(VulkanError::Validated)?,
+                    );
+
+                    command_buffer
+                        .bind_pipeline_graphics(pipeline.clone())
+                        .map_err(|e| VulkanError::Other(e.into()))?;
+                    if appearance
+                        .get_material()
+                        .is_some_and(|material| material.engine_constants())
+                    {
+                        Self::push_engine_constants(
+                            command_buffer,
+                            &pipeline,
+                            EngineConstants {
+                                object_id: object.id as u32,
+                                ..constants
+                            },
+                        )?;
+                    }
+                    let command_buffer = command_buffer
+                        .bind_descriptor_sets(
+                            vulkano::pipeline::PipelineBindPoint::Graphics,
+                            pipeline.layout().clone(),
+                            0,
+                            descriptors,
+                        )
+                        .map_err(|e| VulkanError::Other(e.into()))?;
+                    if let Some((index, descriptor)) = material_descriptor {
+                        command_buffer
+                            .bind_descriptor_sets(
+                                vulkano::pipeline::PipelineBindPoint::Graphics,
+                                pipeline.layout().clone(),
+                                index,
+                                descriptor,
+                            )
+                            .map_err(|e| VulkanError::Other(e.into()))?;
+                    }
+                    let command_buffer = command_buffer
+                        .bind_vertex_buffers(0, model_data.vertex_buffer())
+                        .map_err(|e| VulkanError::Other(e.into()))?
+                        .bind_index_buffer(model_data.index_buffer())
+                        .map_err(|e| VulkanError::Other(e.into()))?;
+                    unsafe {
+                        command_buffer
+                            .draw_indexed(model_data.size() as u32, 1, 0, 0, 0)
+                            .map_err(|e| VulkanError::Other(e.into()))?;
+                    }
+                    count_pipeline(&pipeline, &mut frame_stats);
+                    frame_stats.draw_calls += 1;
+                    frame_stats.objects += 1;
+                }
+                if let Some(batch) = batch.take() {
+                    let pipeline = self.draw_batch(
+                        command_buffer,
+                        loader,
+                        batch,
+                        &mut textures,
+                        constants,
+                        &mut frame_stats,
+                    )?;
+                    count_pipeline(&pipeline, &mut frame_stats);
+                }
+                // Instanced appearances get drawn after all masks got removed again, so they do not get clipped.
+                if reference != 0 {
                     command_buffer
-                        .draw_indexed(model_data.size() as u32, 1, 0, 0, 0)
+                        .set_stencil_reference(StencilFaces::FrontAndBack, 0)
                         .map_err(|e| VulkanError::Other(e.into()))?;
                 }
+                for instance in instances {
+                    let Some(model) = instance.model.as_ref() else {
+                        continue;
+                    };
+
+                    let vulkan = resources()?.vulkan();
+                    let shapes = resources()?.shapes().clone();
+                    let model = match model {
+                        Model::Custom(data) => data,
+                        Model::Square => &shapes.square,
+                        Model::Triangle => &shapes.triangle,
+                    };
+                    let material = instance
+                        .material
+                        .as_ref()
+                        .unwrap_or(&vulkan.default_instance_material);
+                    if material.is_lost(&vulkan) {
+                        continue;
+                    }
+
+                    let mut data = instance.instance_data.lock();
+                    let pipeline = self.draw_instances(
+                        command_buffer,
+                        loader,
+                        material,
+                        model,
+                        &data,
+                        None,
+                        constants,
+                        &mut frame_stats,
+                    )?;
+                    count_pipeline(&pipeline, &mut frame_stats);
+                    instance.finish_drawing();
+                    data.clear();
+                }
+                self.render_callbacks(
+                    RenderStage::AfterLayer,
+                    command_buffer,
+                    loader,
+                    (viewport, scissor),
+                    Some(layer_view()),
+                )?;
             }
-            for instance in instances {
-                let Some(model) = instance.model.as_ref() else {
-                    continue;
-                };
+        }
+        Ok(frame_stats)
+    }
 
-                let mut data = instance.instance_data.lock();
-                let instance_buffer = loader
-                    .instance_buffer_allocator
-                    .allocate_slice::<InstanceData>(data.len() as u64)
+    /// Draws the layers frozen since the last frame to their own images,
+    /// which get drawn in place of their objects until they get unfrozen.
+    fn draw_frozen_layers(
+        &mut self,
+        builder: &mut RecordingCommandBuffer,
+        loader: &mut Loader,
+        drawn: &DrawnFrame,
+    ) -> Result<()> {
+        let vulkan = resources()?.vulkan();
+        let dimensions = self.dimensions;
+        // The images of unfrozen layers get dropped, and the ones of a different size drawn again.
+        self.frozen_images.retain(|image| {
+            image.framebuffer.extent() == dimensions
+                && Arc::ptr_eq(image.framebuffer.render_pass(), &vulkan.freeze_render_pass)
+                && drawn
+                    .layers
+                    .iter()
+                    .any(|layer| layer.frozen && Arc::ptr_eq(&layer.frame, &image.frame))
+        });
+        for layer in drawn.layers.iter().filter(|layer| layer.frozen) {
+            if self
+                .frozen_images
+                .iter()
+                .any(|image| Arc::ptr_eq(&image.frame, &layer.frame))
+            {
+                continue;
+            }
+            let (framebuffer, view) = frozen_layer_setup(
+                vulkan.freeze_render_pass.clone(),
+                dimensions,
+                &loader.memory_allocator,
+            )?;
+            // Cleared to transparent, so the layers below show through the image.
+            let mut clear_values = vec![Some(ClearValue::Float([0.0; 4]))];
+            if vulkan.tonemap.is_some() {
+                clear_values.push(None);
+            }
+            clear_values.push(Some(ClearValue::DepthStencil((1.0, 0))));
+            builder
+                .begin_render_pass(
+                    RenderPassBeginInfo {
+                        clear_values,
+                        ..RenderPassBeginInfo::framebuffer(framebuffer.clone())
+                    },
+                    SubpassBeginInfo {
+                        contents: SubpassContents::SecondaryCommandBuffers,
+                        ..Default::default()
+                    },
+                )
+                .map_err(|e| VulkanError::Other(e.into()))?;
+
+            let mut secondary_builder = RecordingCommandBuffer::new(
+                loader.command_buffer_allocator.clone(),
+                vulkan.queue.queue_family_index(),
+                CommandBufferLevel::Secondary,
+                CommandBufferBeginInfo {
+                    usage: CommandBufferUsage::OneTimeSubmit,
+                    inheritance_info: Some(CommandBufferInheritanceInfo {
+                        render_pass: Some(
+                            Subpass::from(vulkan.freeze_render_pass.clone(), 0)
+                                .ok_or(VulkanError::ShaderError)?
+                                .into(),
+                        ),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            )?;
+            secondary_builder
+                .set_viewport(0, [VIEWPORT.read().clone()].into_iter().collect())
+                .map_err(|e| VulkanError::Other(e.into()))?
+                .set_scissor(0, [self.window_scissor()].into_iter().collect())
+                .map_err(|e| VulkanError::Other(e.into()))?;
+            self.write_secondary_command_buffer(
+                &mut secondary_builder,
+                loader,
+                std::slice::from_ref(layer),
+            )?;
+            builder
+                .execute_commands(secondary_builder.end()?)
+                .map_err(|e| VulkanError::Other(e.into()))?;
+            // The tonemapping and overlay subpasses stay empty, the image gets tonemapped with the scene.
+            for _ in 1..vulkan.freeze_render_pass.subpasses().len() {
+                builder
+                    .next_subpass(
+                        SubpassEndInfo::default(),
+                        SubpassBeginInfo {
+                            contents: SubpassContents::Inline,
+                            ..Default::default()
+                        },
+                    )
                     .map_err(|e| VulkanError::Other(e.into()))?;
-                instance_buffer
-                    .write()
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .copy_from_slice(&data);
+            }
+            builder
+                .end_render_pass(Default::default())
+                .map_err(|e| VulkanError::Other(e.into()))?;
 
-                let mut descriptors = vec![];
-                let vulkan = resources()?.vulkan();
+            let set = DescriptorSet::new(
+                loader.descriptor_set_allocator.clone(),
+                vulkan
+                    .composite
+                    .layout()
+                    .set_layouts()
+                    .first()
+                    .ok_or(VulkanError::ShaderError)?
+                    .clone(),
+                [WriteDescriptorSet::image_view_sampler(
+                    0,
+                    view,
+                    Sampler::new(vulkan.device.clone(), SamplerCreateInfo::default())?,
+                )],
+                [],
+            )?;
+            self.frozen_images.push(FrozenImage {
+                frame: layer.frame.clone(),
+                framebuffer,
+                set,
+            });
+        }
+        Ok(())
+    }
 
-                // The pipeline of the current object. Takes the default one if there is none.
-                let pipeline = if let Some(material) = &instance.material {
-                    if let Some(texture) = material.texture() {
-                        descriptors.push(texture.set().clone());
-                    }
-                    if let Some(descriptor) = &material.descriptor {
-                        descriptors.push(descriptor.clone());
-                    }
-                    material
-                        .get_pipeline_or_recreate(loader)
-                        .map_err(VulkanError::Other)?
-                } else {
-                    vulkan
-                        .default_instance_material
-                        .get_pipeline_or_recreate(loader)
-                        .map_err(VulkanError::Other)?
-                };
+    /// Draws the image of a frozen layer over the whole window.
+    fn draw_frozen_image(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        image: &FrozenImage,
+    ) -> Result<()> {
+        let pipeline = resources()?.vulkan().composite.clone();
+        command_buffer
+            .bind_pipeline_graphics(pipeline.clone())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_viewport(0, [VIEWPORT.read().clone()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_scissor(0, [self.window_scissor()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_stencil_reference(StencilFaces::FrontAndBack, 0)
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_descriptor_sets(
+                vulkano::pipeline::PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                image.set.clone(),
+            )
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        // A single triangle covering the whole window, made up in the vertex shader.
+        unsafe {
+            command_buffer
+                .draw(3, 1, 0, 0)
+                .map_err(|e| VulkanError::Other(e.into()))?;
+        }
+        Ok(())
+    }
+
+    /// Draws the model only to the stencil buffer, using the mask or unmask pipeline
+    /// with the stencil value the model has to be inside of.
+    fn draw_mask(
+        command_buffer: &mut RecordingCommandBuffer,
+        pipeline: &Arc<GraphicsPipeline>,
+        model: &ModelData,
+        mvp: Mat4,
+        reference: u32,
+    ) -> Result<()> {
+        let command_buffer = command_buffer
+            .bind_pipeline_graphics(pipeline.clone())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_stencil_reference(StencilFaces::FrontAndBack, reference)
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .push_constants(pipeline.layout().clone(), 0, MaskParameters { mvp })
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_vertex_buffers(0, model.vertex_buffer())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_index_buffer(model.index_buffer())
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        unsafe {
+            command_buffer
+                .draw_indexed(model.size() as u32, 1, 0, 0, 0)
+                .map_err(|e| VulkanError::Other(e.into()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the time given to shaders in the engine constants.
+    ///
+    /// It starts over every hour, because a float of the seconds since the start loses precision in long sessions.
+    fn shader_time(&self) -> f32 {
+        let seconds = self.frame_start.duration_since(self.started).as_secs_f64();
+        (seconds % SHADER_TIME_PERIOD) as f32
+    }
+
+    /// Pushes the engine constants to a material that enabled them, in case the shaders of the pipeline declare them.
+    ///
+    /// The distance field variants of a material only declare them in case its vertex shader does.
+    fn push_engine_constants(
+        command_buffer: &mut RecordingCommandBuffer,
+        pipeline: &Arc<GraphicsPipeline>,
+        constants: EngineConstants,
+    ) -> Result<()> {
+        let declared = pipeline
+            .layout()
+            .push_constant_ranges()
+            .iter()
+            .any(|range| {
+                range.offset == 0 && range.size as usize == std::mem::size_of::<EngineConstants>()
+            });
+        if declared {
+            command_buffer
+                .push_constants(pipeline.layout().clone(), 0, constants)
+                .map_err(|e| VulkanError::Other(e.into()))?;
+        }
+        Ok(())
+    }
+
+    /// Draws the custom cursor of the window over the scene at the position of the system cursor.
+    fn draw_cursor(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        loader: &mut Loader,
+        stats: &mut RenderStats,
+    ) -> Result<()> {
+        let (Some(cursor), Some(position)) =
+            (self.window.custom_cursor(), self.window.cursor_position())
+        else {
+            return Ok(());
+        };
+        if !self.window.cursor_visible() {
+            return Ok(());
+        }
+        let window_size = vec2(self.dimensions[0] as f32, self.dimensions[1] as f32);
+        let (width, height) = cursor.texture().dimensions();
+        let size = vec2(width as f32, height as f32);
+        // Maps pixels from the top left corner of the window to normalized device coordinates.
+        let proj = Mat4::from_translation(Vec3::new(-1.0, -1.0, 0.0))
+            * Mat4::from_scale(Vec3::new(2.0 / window_size.x, 2.0 / window_size.y, 1.0));
+        let model = Mat4::from_scale_rotation_translation(
+            (size * 0.5).extend(1.0),
+            Quat::IDENTITY,
+            (position - cursor.hotspot() + size * 0.5).extend(0.0),
+        );
+
+        command_buffer
+            .set_viewport(0, [VIEWPORT.read().clone()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_scissor(0, [self.window_scissor()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_stencil_reference(StencilFaces::FrontAndBack, 0)
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        let shapes = resources()?.shapes().clone();
+        self.draw_instances(
+            command_buffer,
+            loader,
+            cursor.material(),
+            &shapes.square,
+            &[InstanceData {
+                color: Vec4::ONE,
+                layer: 0,
+                model,
+                view: Mat4::IDENTITY,
+                proj,
+            }],
+            None,
+            EngineConstants {
+                camera: proj.to_cols_array_2d(),
+                resolution: window_size.into(),
+                time: self.shader_time(),
+                delta_time: self.delta_time,
+                object_id: 0,
+            },
+            stats,
+        )?;
+        Ok(())
+    }
+
+    /// Draws a pending batch, binding the texture array of the frame in case it is a bindless one.
+    ///
+    /// Returns the pipeline that got bound.
+    fn draw_batch(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        loader: &mut Loader,
+        batch: Batch,
+        textures: &mut TextureTable,
+        constants: EngineConstants,
+        stats: &mut RenderStats,
+    ) -> Result<Arc<GraphicsPipeline>> {
+        let set = if batch.bindless {
+            let layout = batch
+                .instanced
+                .get_pipeline_or_recreate(loader)
+                .map_err(VulkanError::Other)?
+                .layout()
+                .set_layouts()
+                .first()
+                .ok_or(VulkanError::ShaderError)?
+                .clone();
+            Some(textures.set(loader, layout)?)
+        } else {
+            None
+        };
+        self.draw_instances(
+            command_buffer,
+            loader,
+            &batch.instanced,
+            &batch.model,
+            &batch.data,
+            set,
+            constants,
+            stats,
+        )
+    }
+
+    /// Draws the given instance data with the instanced material and model in one draw call.
+    ///
+    /// The given texture set gets bound in place of the texture of the material.
+    /// Returns the pipeline that got bound.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_instances(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        loader: &mut Loader,
+        material: &Material,
+        model: &ModelData,
+        data: &[InstanceData],
+        textures: Option<Arc<DescriptorSet>>,
+        constants: EngineConstants,
+        stats: &mut RenderStats,
+    ) -> Result<Arc<GraphicsPipeline>> {
+        let instance_buffer = loader
+            .frame_buffer
+            .allocate_slice::<InstanceData>(data.len() as u64)
+            .map_err(VulkanError::Other)?;
+        instance_buffer
+            .write()
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .copy_from_slice(data);
+
+        let mut descriptors = vec![];
+        if let Some(set) = textures.or_else(|| Some(material.texture()?.set())) {
+            descriptors.push(set);
+        }
+        // The writes of instanced materials go right after their texture.
+        if let Some(descriptor) = &material.descriptor {
+            descriptors.push(descriptor.clone());
+        }
+        let pipeline = material
+            .get_pipeline_or_recreate(loader)
+            .map_err(VulkanError::Other)?;
+
+        command_buffer
+            .bind_pipeline_graphics(pipeline.clone())
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        if material.engine_constants() {
+            Self::push_engine_constants(command_buffer, &pipeline, constants)?;
+        }
+        let command_buffer = command_buffer
+            .bind_descriptor_sets(
+                vulkano::pipeline::PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                descriptors,
+            )
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_vertex_buffers(0, (model.vertex_buffer(), instance_buffer))
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_index_buffer(model.index_buffer())
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        unsafe {
+            command_buffer
+                .draw_indexed(model.size() as u32, data.len() as u32, 0, 0, 0)
+                .map_err(|e| VulkanError::Other(e.into()))?;
+        }
+        stats.draw_calls += 1;
+        stats.objects += data.len() as u32;
+        Ok(pipeline)
+    }
+
+    /// Maps the HDR image of the scene to the window in the second subpass, in case the window uses an HDR color space.
+    fn tonemap(
+        &self,
+        builder: &mut RecordingCommandBuffer,
+        image_num: usize,
+        loader: &Loader,
+    ) -> Result<(), VulkanError> {
+        let vulkan = resources()
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .vulkan();
+        let Some(pipeline) = &vulkan.tonemap else {
+            return Ok(());
+        };
+        let scene = self.framebuffers[image_num].attachments()[0].clone();
+        let set = DescriptorSet::new(
+            loader.descriptor_set_allocator.clone(),
+            pipeline
+                .layout()
+                .set_layouts()
+                .first()
+                .ok_or(VulkanError::ShaderError)?
+                .clone(),
+            [WriteDescriptorSet::image_view(0, scene)],
+            [],
+        )
+        .map_err(Validated::unwrap)
+        .map_err(VulkanError::Validated)?;
 
-                let shapes = resources()?.shapes().clone();
-                let model = match &model {
-                    Model::Custom(data) => data,
-                    Model::Square => &shapes.square,
-                    Model::Triangle => &shapes.triangle,
-                };
+        let hdr = self.graphics.hdr_settings();
+        let parameters = TonemapParameters {
+            output: match self.graphics.color_space() {
+                ColorSpace::Hdr10 => 2,
+                _ => 1,
+            },
+            curve: match hdr.tonemapping {
+                Tonemapping::None => 0,
+                Tonemapping::Reinhard => 1,
+                Tonemapping::Aces => 2,
+            },
+            paper_white: hdr.paper_white.max(1.0),
+            max_luminance: hdr.max_luminance.max(hdr.paper_white).max(1.0),
+        };
 
-                let command_buffer = command_buffer
-                    .bind_pipeline_graphics(pipeline.clone())
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_descriptor_sets(
-                        vulkano::pipeline::PipelineBindPoint::Graphics,
-                        pipeline.layout().clone(),
-                        0,
-                        descriptors,
-                    )
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_vertex_buffers(0, (model.vertex_buffer(), instance_buffer))
-                    .map_err(|e| VulkanError::Other(e.into()))?
-                    .bind_index_buffer(model.index_buffer())
-                    .map_err(|e| VulkanError::Other(e.into()))?;
-                unsafe {
-                    command_buffer
-                        .draw_indexed(model.size() as u32, data.len() as u32, 0, 0, 0)
-                        .map_err(|e| VulkanError::Other(e.into()))?;
-                }
-                instance.finish_drawing();
-                data.clear();
-            }
+        builder
+            .next_subpass(
+                SubpassEndInfo::default(),
+                SubpassBeginInfo {
+                    contents: SubpassContents::Inline,
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_pipeline_graphics(pipeline.clone())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_viewport(0, [VIEWPORT.read().clone()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .set_scissor(0, [self.window_scissor()].into_iter().collect())
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .bind_descriptor_sets(
+                vulkano::pipeline::PipelineBindPoint::Graphics,
+                pipeline.layout().clone(),
+                0,
+                set,
+            )
+            .map_err(|e| VulkanError::Other(e.into()))?
+            .push_constants(pipeline.layout().clone(), 0, parameters)
+            .map_err(|e| VulkanError::Other(e.into()))?;
+        // A single triangle covering the whole window, made up in the vertex shader.
+        unsafe {
+            builder
+                .draw(3, 1, 0, 0)
+                .map_err(|e| VulkanError::Other(e.into()))?;
         }
         Ok(())
     }
@@ -467,31 +1489,46 @@ impl Draw {
         command_buffer: Arc<CommandBuffer>,
         acquire_future: SwapchainAcquireFuture,
         image_num: u32,
+        loader: &mut Loader,
     ) -> Result<()> {
-        let vulkan = resources()?.vulkan().clone();
-        let future = self
-            .previous_frame_end
-            .take()
-            .ok_or(VulkanError::FlushFutureError(
-                "Failed to obtain previous frame".to_string(),
-            ))?
+        let vulkan = resources()?.vulkan();
+        let swapchain = self.swapchain.clone().ok_or(VulkanError::DeviceLost)?;
+        let mut previous_frame_end =
+            self.previous_frame_end
+                .take()
+                .ok_or(VulkanError::FlushFutureError(
+                    "Failed to obtain previous frame".to_string(),
+                ))?;
+        // Waits for the semaphores of the uploads submitted since the last frame.
+        for upload in loader.take_uploads() {
+            previous_frame_end = previous_frame_end.join(upload).boxed();
+        }
+        let future = previous_frame_end
             .join(acquire_future)
             .then_execute(vulkan.queue.clone(), command_buffer)
             .map_err(|e| VulkanError::Other(e.into()))?
             .then_swapchain_present(
                 vulkan.queue.clone(),
-                SwapchainPresentInfo::swapchain_image_index(self.swapchain.clone(), image_num),
+                SwapchainPresentInfo::swapchain_image_index(swapchain, image_num),
             )
             .then_signal_fence_and_flush();
 
         match future.map_err(Validated::unwrap) {
             Ok(future) => {
+                // Frames failing to submit keep their data in the next frame, whose fence comes after them.
+                let future = Arc::new(future);
+                let fence = future.clone();
+                self.frames_in_flight.push_back((
+                    loader.frame_buffer.end_frame(),
+                    Box::new(move || fence.is_signaled()),
+                ));
                 self.previous_frame_end = Some(future.boxed());
             }
             Err(VulkanoError::OutOfDate) => {
                 self.mark_swapchain_outdated();
                 self.previous_frame_end = Some(sync::now(vulkan.device.clone()).boxed());
             }
+            Err(VulkanoError::DeviceLost) => return Err(VulkanError::DeviceLost.into()),
             Err(e) => {
                 self.previous_frame_end = Some(sync::now(vulkan.device.clone()).boxed());
                 return Err(VulkanError::FlushFutureError(e.to_string()).into());
@@ -500,6 +1537,109 @@ impl Draw {
         Ok(())
     }
 
+    /// Stops drawing until [`resume`](Draw::resume) gets called.
+    ///
+    /// Some platforms like Android destroy the surface of the window while the application is in the background.
+    pub fn suspend(&mut self) {
+        if let Some(previous_frame_end) = self.previous_frame_end.as_mut() {
+            previous_frame_end.cleanup_finished();
+        }
+        self.suspended = true;
+    }
+
+    /// Creates a new surface and swapchain for the window after the application got suspended and continues drawing.
+    pub fn resume(&mut self) -> Result<()> {
+        if !self.suspended {
+            return Ok(());
+        }
+        let vulkan = resources()?.vulkan();
+        let mut loader = resources()?.loader().lock();
+
+        let surface = Surface::from_window(vulkan.instance.clone(), self.window.handle().clone())?;
+        let (swapchain, images) = create_swapchain_and_images(
+            &vulkan.device,
+            &surface,
+            &self.graphics,
+            vulkan.surface_format,
+        )?;
+        self.framebuffers = window_size_dependent_setup(
+            &images,
+            vulkan.render_pass.clone(),
+            &mut VIEWPORT.write(),
+            &loader.memory_allocator,
+        )?;
+        self.surface = surface;
+        self.swapchain = Some(swapchain);
+        self.graphics.stats.lock().swapchain_recreations += 1;
+        loader.pipelines.clear();
+        self.drawn = None;
+        self.previous_frame_end = Some(sync::now(vulkan.device.clone()).boxed());
+        self.graphics
+            .recreate_swapchain
+            .store(false, std::sync::atomic::Ordering::Release);
+        self.suspended = false;
+        Ok(())
+    }
+
+    /// Rebuilds the device after it got lost and continues drawing on the new one.
+    ///
+    /// Textures and models get reloaded, everything else made using the lost device has to be made again.
+    /// See [`Resources::rebuild`](crate::resources::Resources::rebuild).
+    pub fn recover(&mut self) -> Result<()> {
+        // The window can only have one swapchain, so the old one has to be destroyed before making the new one.
+        drop_lost((
+            self.previous_frame_end.take(),
+            std::mem::take(&mut self.frames_in_flight),
+            std::mem::take(&mut self.framebuffers),
+            self.swapchain.take(),
+            self.gpu_timer.take(),
+            std::mem::take(&mut self.frozen_images),
+            std::mem::take(self.textures.get_mut()),
+        ));
+        self.drawn = None;
+
+        resources()?.rebuild(&self.surface, &self.graphics)?;
+
+        let vulkan = resources()?.vulkan();
+        let loader = resources()?.loader().lock();
+        let (swapchain, images) = create_swapchain_and_images(
+            &vulkan.device,
+            &self.surface,
+            &self.graphics,
+            vulkan.surface_format,
+        )?;
+        self.framebuffers = window_size_dependent_setup(
+            &images,
+            vulkan.render_pass.clone(),
+            &mut VIEWPORT.write(),
+            &loader.memory_allocator,
+        )?;
+        self.swapchain = Some(swapchain);
+        self.gpu_timer = GpuTimer::new(&vulkan)?;
+        self.graphics.stats.lock().swapchain_recreations += 1;
+        self.previous_frame_end = Some(sync::now(vulkan.device.clone()).boxed());
+        self.graphics
+            .recreate_swapchain
+            .store(false, std::sync::atomic::Ordering::Release);
+        Ok(())
+    }
+
+    /// Returns true while drawing is suspended.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+
+    /// Returns the time to check for changes again in case damage tracking skipped the latest frame,
+    /// so the event loop can wait instead of drawing as fast as possible.
+    pub fn idle_until(&self) -> Option<Instant> {
+        let redraw = self
+            .graphics
+            .redraw_requested
+            .load(std::sync::atomic::Ordering::Acquire)
+            || CONTENTS_CHANGED.load(std::sync::atomic::Ordering::Acquire);
+        self.idle_until.filter(|_| !redraw)
+    }
+
     pub fn mark_swapchain_outdated(&self) {
         self.graphics
             .recreate_swapchain
@@ -511,6 +1651,9 @@ impl Draw {
         &mut self,
         #[cfg(feature = "egui")] gui: &mut egui_winit_vulkano::Gui,
     ) -> Result<(), VulkanError> {
+        if self.suspended {
+            return Ok(());
+        }
         let mut loader = resources()
             .map_err(|e| VulkanError::Other(e.into()))?
             .loader()
@@ -519,6 +1662,17 @@ impl Draw {
         let dimensions = self.window.inner_size();
         self.dimensions = [dimensions.x as u32, dimensions.y as u32];
 
+        // Frees the frame data of finished frames before their futures unlock the buffers they used.
+        while let Some((frame, finished)) = self.frames_in_flight.front() {
+            // A lost device has to be recovered before its frames can be freed.
+            if !finished().map_err(|error| VulkanError::from(Validated::Error(error)))? {
+                break;
+            }
+            loader.frame_buffer.retire(*frame);
+            self.frames_in_flight.pop_front();
+        }
+        loader.free_unused_pipelines();
+
         self.previous_frame_end
             .as_mut()
             .ok_or(VulkanError::Other(anyhow::Error::msg(
@@ -530,15 +1684,33 @@ impl Draw {
             return Ok(());
         }
 
+        #[cfg(feature = "egui")]
+        if gui.context().has_requested_repaint() {
+            self.graphics.request_redraw();
+        }
+        let frame = self.capture();
+        if self.skip(&frame, &loader) {
+            // Ends the frame egui began in `immediate_ui`, so the next one does not continue it.
+            #[cfg(feature = "egui")]
+            drop(gui.draw_on_subpass_image(self.dimensions));
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        self.delta_time = now.duration_since(self.frame_start).as_secs_f32();
+        self.frame_start = now;
+
         Self::recreate_swapchain(self, &mut loader).map_err(VulkanError::Other)?;
 
+        let swapchain = self.swapchain.clone().ok_or(VulkanError::DeviceLost)?;
         let (image_num, suboptimal, acquire_future) =
-            match acquire_next_image(self.swapchain.clone(), None).map_err(Validated::unwrap) {
+            match acquire_next_image(swapchain, None).map_err(Validated::unwrap) {
                 Ok(r) => r,
                 Err(VulkanoError::OutOfDate) => {
                     self.mark_swapchain_outdated();
                     return Err(VulkanError::SwapchainOutOfDate);
                 }
+                Err(VulkanoError::DeviceLost) => return Err(VulkanError::DeviceLost),
                 Err(e) => {
                     return Err(VulkanError::Validated(e));
                 }
@@ -548,23 +1720,54 @@ impl Draw {
             self.mark_swapchain_outdated();
         }
 
-        let (mut builder, mut secondary_builder) = Self::make_command_buffer(
+        self.textures.get_mut().begin_frame();
+        let clear_color = self.window.clear_color().rgba();
+        let (mut builder, mut secondary_builder) =
+            Self::make_command_buffer(self, image_num as usize, clear_color, &mut loader, &frame)?;
+
+        let mut frame_stats = Self::write_secondary_command_buffer(
             self,
-            image_num as usize,
-            self.window.clear_color().rgba(),
+            &mut secondary_builder,
+            &mut loader,
+            &frame.layers,
+        )
+        .map_err(VulkanError::Other)?;
+        self.render_callbacks(
+            RenderStage::AfterScene,
+            &mut secondary_builder,
             &loader,
-        )?;
-
-        Self::write_secondary_command_buffer(self, &mut secondary_builder, &mut loader)
+            (VIEWPORT.read().clone(), self.window_scissor()),
+            None,
+        )
+        .map_err(VulkanError::Other)?;
+        self.draw_cursor(&mut secondary_builder, &mut loader, &mut frame_stats)
             .map_err(VulkanError::Other)?;
+        {
+            let mut stats = self.graphics.stats.lock();
+            frame_stats.swapchain_recreations = stats.swapchain_recreations;
+            frame_stats.skipped_frames = stats.skipped_frames;
+            *stats = frame_stats;
+        }
+        self.timestamp(&mut secondary_builder, Timestamp::SceneEnd)?;
 
         builder
             .execute_commands(secondary_builder.end()?)
             .map_err(|e| VulkanError::Other(e.into()))?;
 
+        Self::tonemap(self, &mut builder, image_num as usize, &loader)?;
+
+        // Egui gets drawn over the final image, without the stencil buffer of the scene.
+        builder
+            .next_subpass(
+                SubpassEndInfo::default(),
+                SubpassBeginInfo {
+                    contents: SubpassContents::SecondaryCommandBuffers,
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| VulkanError::Other(e.into()))?;
         #[cfg(feature = "egui")]
         {
-            // Creates and draws the second command buffer in case of egui.
             let cb = gui.draw_on_subpass_image(self.dimensions);
             builder
                 .execute_commands(cb)
@@ -573,12 +1776,257 @@ impl Draw {
         builder
             .end_render_pass(Default::default())
             .map_err(|e| VulkanError::Other(e.into()))?;
+        self.timestamp(&mut builder, Timestamp::PassEnd)?;
+        self.render_callbacks(
+            RenderStage::AfterPass,
+            &mut builder,
+            &loader,
+            (VIEWPORT.read().clone(), self.window_scissor()),
+            None,
+        )
+        .map_err(VulkanError::Other)?;
+        self.timestamp(&mut builder, Timestamp::FrameEnd)?;
         let command_buffer = builder.end()?;
 
-        Self::execute_command_buffer(self, command_buffer, acquire_future, image_num)
-            .map_err(VulkanError::Other)?;
+        Self::execute_command_buffer(self, command_buffer, acquire_future, image_num, &mut loader)
+            .map_err(|error| match error.downcast::<VulkanError>() {
+                Ok(error) => error,
+                Err(error) => VulkanError::Other(error),
+            })?;
+        if self.graphics.damage_tracking() {
+            self.drawn = Some(frame);
+        }
         Ok(())
     }
+
+    /// Captures the draw lists and cameras of all layers along with everything else deciding the image.
+    fn capture(&self) -> DrawnFrame {
+        let frozen = self.graphics.frozen();
+        DrawnFrame {
+            layers: SCENE
+                .layers()
+                .into_iter()
+                .map(|layer| {
+                    let frozen = frozen || layer.frozen();
+                    DrawnLayer {
+                        frame: layer.frame(frozen),
+                        frozen,
+                        lights: layer.lights(),
+                        ambient_light: layer.ambient_light(),
+                        layer,
+                    }
+                })
+                .collect(),
+            clear_color: self.window.clear_color(),
+            hdr: self.graphics.hdr_settings(),
+            dimensions: self.dimensions,
+        }
+    }
+
+    /// Returns true if damage tracking is enabled and the frame would look the same as the last drawn one.
+    ///
+    /// Skipped frames wait for a refresh of the monitor instead of the presentation,
+    /// so the loop does not spin while nothing changes.
+    fn skip(&mut self, frame: &DrawnFrame, loader: &Loader) -> bool {
+        let redraw = self
+            .graphics
+            .redraw_requested
+            .swap(false, std::sync::atomic::Ordering::AcqRel)
+            | CONTENTS_CHANGED.swap(false, std::sync::atomic::Ordering::AcqRel);
+        if !self.graphics.damage_tracking() {
+            self.drawn = None;
+            self.idle_until = None;
+            return false;
+        }
+        let unchanged = !redraw
+            && !self
+                .graphics
+                .recreate_swapchain
+                .load(std::sync::atomic::Ordering::Acquire)
+            // Uploads get waited for by the next drawn frame.
+            && !loader.has_uploads()
+            // Callbacks may draw anything at any time.
+            && self.graphics.render_callbacks.is_empty()
+            && self
+                .drawn
+                .as_ref()
+                .is_some_and(|drawn| drawn.same_image(frame));
+        if !unchanged {
+            self.idle_until = None;
+            return false;
+        }
+
+        let refresh = self
+            .window
+            .current_monitor()
+            .and_then(|monitor| monitor.refresh_rate())
+            .filter(|millihertz| *millihertz > 0)
+            .map_or(Duration::from_millis(16), |millihertz| {
+                Duration::from_secs_f64(1000.0 / millihertz as f64)
+            });
+        self.idle_until = Some(Instant::now() + refresh);
+        self.graphics.stats.lock().skipped_frames += 1;
+        true
+    }
+}
+
+/// Drops objects of a lost device.
+///
+/// Waiting for the fences of a lost device can fail, which vulkano panics on when dropping the futures of frames.
+fn drop_lost<T>(value: T) {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || drop(value)));
+    std::panic::set_hook(hook);
+}
+
+/// How the engine spaces out the frames it draws.
+///
+/// Frames get scheduled at a fixed target time instead of waiting after each frame, so the time between frames stays
+/// even and late frames do not delay the following ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FramePacing {
+    /// Draws frames as fast as the present mode allows.
+    #[default]
+    Unlimited,
+    /// Draws a frame every given duration.
+    Limit(Duration),
+    /// Draws a frame for every refresh of the monitor the window is on, even with present modes without vsync.
+    ///
+    /// Does not wait on top of the `Fifo` present mode, as it already syncs to the display.
+    Adaptive,
+}
+
+/// When the event loop of the engine runs and frames get drawn.
+///
+/// Games redraw all the time, while tools and other applications built on the engine only need new frames
+/// once something happens, leaving the CPU and GPU idle in between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Draws frames continuously, spaced out by the [`FramePacing`].
+    #[default]
+    Poll,
+    /// Sleeps until something happens and only draws a frame after window and input events,
+    /// a call to [`Window::request_redraw`](crate::window::Window::request_redraw), a repaint requested by egui
+    /// or a tick, where damage tracking skips the frame in case the tick did not change anything.
+    Wait,
+    /// Same as `Wait`, but also draws a frame after the given time passed without one,
+    /// for example to update a clock or poll a connection.
+    WaitUntil(Duration),
+}
+
+/// The color space the window gets presented in.
+///
+/// In HDR color spaces the scene gets drawn to a 16 bit floating point image first, where colors count as linear light
+/// with 1.0 being the paper white of the [`HdrSettings`]. Colors above 1.0 get shown brighter on the display
+/// instead of getting clipped, going through the [`Tonemapping`] on the way to the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Standard dynamic range, supported everywhere.
+    #[default]
+    Srgb,
+    /// Linear extended sRGB, also known as scRGB, using 16 bit floating point colors.
+    ExtendedSrgb,
+    /// HDR10 with the BT.2020 primaries and the ST 2084 transfer function.
+    Hdr10,
+}
+
+impl ColorSpace {
+    /// Returns the surface format and color space of the swapchain, `None` for the first format of the surface.
+    pub(crate) fn surface_format(self) -> Option<(Format, vulkano::swapchain::ColorSpace)> {
+        match self {
+            ColorSpace::Srgb => None,
+            ColorSpace::ExtendedSrgb => Some((
+                Format::R16G16B16A16_SFLOAT,
+                vulkano::swapchain::ColorSpace::ExtendedSrgbLinear,
+            )),
+            ColorSpace::Hdr10 => Some((
+                Format::A2B10G10R10_UNORM_PACK32,
+                vulkano::swapchain::ColorSpace::Hdr10St2084,
+            )),
+        }
+    }
+
+    /// Returns true for the color spaces with a high dynamic range.
+    pub fn is_hdr(self) -> bool {
+        self != ColorSpace::Srgb
+    }
+}
+
+/// The curve compressing colors brighter than the display can show in HDR color spaces.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Tonemapping {
+    /// Clips colors at the maximum brightness.
+    None,
+    /// Rolls off bright colors smoothly, keeping the look of the colors below paper white.
+    Reinhard,
+    /// The filmic curve of the ACES reference, with more contrast.
+    #[default]
+    Aces,
+}
+
+/// How colors get mapped to the brightness of HDR displays.
+///
+/// Only used with HDR [color spaces](ColorSpace).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrSettings {
+    pub tonemapping: Tonemapping,
+    /// The brightness of the color 1.0 in nits.
+    pub paper_white: f32,
+    /// The highest brightness of the display in nits.
+    pub max_luminance: f32,
+}
+
+impl Default for HdrSettings {
+    fn default() -> Self {
+        Self {
+            tonemapping: Tonemapping::default(),
+            paper_white: 200.0,
+            max_luminance: 1000.0,
+        }
+    }
+}
+
+impl HdrSettings {
+    /// Sets the tonemapping curve and returns self.
+    #[inline]
+    pub fn tonemapping(mut self, tonemapping: Tonemapping) -> Self {
+        self.tonemapping = tonemapping;
+        self
+    }
+
+    /// Sets the brightness of the color 1.0 in nits and returns self.
+    #[inline]
+    pub fn paper_white(mut self, paper_white: f32) -> Self {
+        self.paper_white = paper_white;
+        self
+    }
+
+    /// Sets the highest brightness of the display in nits and returns self.
+    #[inline]
+    pub fn max_luminance(mut self, max_luminance: f32) -> Self {
+        self.max_luminance = max_luminance;
+        self
+    }
+}
+
+/// The push constants of the mask shader.
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct MaskParameters {
+    mvp: Mat4,
+}
+
+/// The push constants of the tonemapping shader.
+#[derive(BufferContents, Clone, Copy)]
+#[repr(C)]
+struct TonemapParameters {
+    /// 1 for extended sRGB, 2 for HDR10.
+    output: u32,
+    /// 0 for none, 1 for Reinhard, 2 for ACES.
+    curve: u32,
+    paper_white: f32,
+    max_luminance: f32,
 }
 
 /// Engine wide Graphics settings.
@@ -589,17 +2037,30 @@ impl Draw {
 /// 2. `Immediate`
 /// 3. `Fifo`
 ///
-/// The framerate limit is `None`, so off.
+/// Frame pacing is [`FramePacing::Unlimited`], so off.
 ///
 /// Only alter settings after the game engine has been initialized. The initialisation of the game engine also
 /// initializes the settings.
 pub struct Graphics {
     /// An option that determines something called "VSync".
     pub(crate) present_mode: Mutex<PresentMode>,
-    /// Time waited before each frame.
-    framerate_limit: Mutex<Duration>,
+    /// How the frames get spaced out.
+    frame_pacing: Mutex<FramePacing>,
+    control_flow: Mutex<ControlFlow>,
     pub(crate) available_present_modes: OnceLock<Vec<PresentMode>>,
+    preferred_present_mode: Mutex<Option<PresentMode>>,
+    pub(crate) color_space: Mutex<ColorSpace>,
+    pub(crate) available_color_spaces: OnceLock<Vec<ColorSpace>>,
+    preferred_color_space: Mutex<ColorSpace>,
+    hdr: Mutex<HdrSettings>,
+    render_callbacks: RenderCallbacks,
     pub(crate) recreate_swapchain: AtomicBool,
+    frozen: AtomicBool,
+    bindless_textures: AtomicBool,
+    damage_tracking: AtomicBool,
+    redraw_requested: AtomicBool,
+    stats: Mutex<RenderStats>,
+    gpu_timings: Mutex<Option<GpuTimings>>,
 }
 
 impl Graphics {
@@ -607,9 +2068,22 @@ impl Graphics {
     pub fn new(present_mode: PresentMode) -> Self {
         Self {
             present_mode: Mutex::new(present_mode),
-            framerate_limit: Mutex::new(Duration::from_secs(0)),
+            frame_pacing: Mutex::new(FramePacing::Unlimited),
+            control_flow: Mutex::new(ControlFlow::Poll),
             available_present_modes: OnceLock::new(),
+            preferred_present_mode: Mutex::new(None),
+            color_space: Mutex::new(ColorSpace::Srgb),
+            available_color_spaces: OnceLock::new(),
+            preferred_color_space: Mutex::new(ColorSpace::Srgb),
+            hdr: Mutex::new(HdrSettings::default()),
+            render_callbacks: RenderCallbacks::default(),
             recreate_swapchain: false.into(),
+            frozen: false.into(),
+            bindless_textures: true.into(),
+            damage_tracking: false.into(),
+            redraw_requested: false.into(),
+            stats: Mutex::new(RenderStats::default()),
+            gpu_timings: Mutex::new(None),
         }
     }
 
@@ -637,18 +2111,61 @@ impl Graphics {
         }
     }
 
-    /// Returns waiting time between frames to wait.
+    /// Returns the present mode requested for the creation of the window.
+    pub fn preferred_present_mode(&self) -> Option<PresentMode> {
+        *self.preferred_present_mode.lock()
+    }
+
+    /// Requests a present mode to be used when the window gets created.
+    ///
+    /// Unlike [set_present_mode](Graphics::set_present_mode) this can be called before the engine is initialized.
+    /// In case the device does not support the mode, the default order gets used instead.
+    pub fn set_preferred_present_mode(&self, mode: Option<PresentMode>) {
+        *self.preferred_present_mode.lock() = mode;
+    }
+
+    /// Returns how the frames get spaced out.
+    pub fn frame_pacing(&self) -> FramePacing {
+        *self.frame_pacing.lock()
+    }
+
+    /// Sets how the frames get spaced out.
+    pub fn set_frame_pacing(&self, pacing: FramePacing) {
+        *self.frame_pacing.lock() = pacing;
+    }
+
+    /// Returns when the event loop runs and frames get drawn.
+    pub fn control_flow(&self) -> ControlFlow {
+        *self.control_flow.lock()
+    }
+
+    /// Sets when the event loop runs and frames get drawn, taking effect once the current events are handled.
+    ///
+    /// Pair a waiting control flow with the `idle_timeout` of the tick settings to also suspend the tick system
+    /// while nothing happens.
+    pub fn set_control_flow(&self, control_flow: ControlFlow) {
+        *self.control_flow.lock() = control_flow;
+    }
+
+    /// Returns the time between frames of the framerate limit, or zero if frames are not limited to a fixed time.
     pub fn framerate_limit(&self) -> Duration {
-        *self.framerate_limit.lock()
+        match self.frame_pacing() {
+            FramePacing::Limit(limit) => limit,
+            _ => Duration::ZERO,
+        }
     }
 
-    /// Sets the framerate limit as waiting time between frames.
+    /// Sets the framerate limit as time between frames.
     ///
     /// This should be able to be changed by the user in case they have a device with limited power capacity like a laptop with a battery.
     ///
     /// Setting the duration to no wait time at all will turn off the limit.
     pub fn set_framerate_limit(&self, limit: Duration) {
-        *self.framerate_limit.lock() = limit;
+        self.set_frame_pacing(if limit.is_zero() {
+            FramePacing::Unlimited
+        } else {
+            FramePacing::Limit(limit)
+        });
     }
 
     /// Sets the cap for the max frames per second the game should be able to output.
@@ -664,6 +2181,89 @@ impl Graphics {
         self.set_framerate_limit(Duration::from_secs_f64(1.0 / cap as f64));
     }
 
+    /// Returns true if the rendering of all layers is frozen.
+    pub fn frozen(&self) -> bool {
+        self.frozen.load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Freezes or unfreezes the rendering of all layers at once.
+    ///
+    /// While frozen every layer keeps showing the frame it was frozen at, the same way as
+    /// [`Layer::set_frozen`](crate::objects::scenes::Layer::set_frozen) does for a single layer.
+    /// Egui still gets drawn on top, which makes this useful for pause menus.
+    pub fn set_frozen(&self, frozen: bool) {
+        self.frozen
+            .store(frozen, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Returns true if objects with default textured materials get drawn out of one texture array per frame.
+    pub fn bindless_textures(&self) -> bool {
+        self.bindless_textures
+            .load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Enables or disables drawing objects with default textured materials out of one texture array per frame,
+    /// which is enabled by default.
+    ///
+    /// This way consecutive objects with the same model get drawn in one instanced call, even if their textures
+    /// differ, instead of switching the texture between every call. It only works for default textured materials
+    /// of single layer [`Flat`](crate::resources::textures::TextureKind::Flat) textures on objects without
+    /// [anti aliasing](crate::objects::AntiAliasing) and does nothing on GPUs that can not index textures per instance.
+    ///
+    /// The array is kept between frames and only the textures added to it get written,
+    /// so it gets cheaper the less the set of drawn textures changes from frame to frame.
+    pub fn set_bindless_textures(&self, enabled: bool) {
+        self.bindless_textures
+            .store(enabled, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Returns true if frames looking the same as the last drawn one get skipped.
+    pub fn damage_tracking(&self) -> bool {
+        self.damage_tracking
+            .load(std::sync::atomic::Ordering::Acquire)
+    }
+
+    /// Enables or disables skipping frames that would look the same as the last drawn one, which is disabled by default.
+    ///
+    /// Every frame the draw lists, cameras and lights of all layers get compared to the ones of the last drawn frame.
+    /// In case nothing changed, the frame neither gets drawn nor presented, which saves a lot of power
+    /// in tools and mostly static games. Writes to textures using `write_region` and to materials using `write`
+    /// draw the next frame, but buffers written to directly can not be seen, so call
+    /// [`request_redraw`](Graphics::request_redraw) after writing to them.
+    /// While frames get skipped the event loop waits until the next refresh of the monitor instead of spinning.
+    /// Materials animated using the time of the engine constants stop moving while frames get skipped.
+    /// Frames always get drawn while render callbacks are added.
+    pub fn set_damage_tracking(&self, enabled: bool) {
+        self.damage_tracking
+            .store(enabled, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Makes sure the next frame gets drawn even if [damage tracking](Graphics::set_damage_tracking)
+    /// sees no changes, for example after writing to a texture or uniform buffer.
+    pub fn request_redraw(&self) {
+        self.redraw_requested
+            .store(true, std::sync::atomic::Ordering::Release);
+    }
+
+    /// Loads all textures and models of the batch to the GPU in a single submission.
+    ///
+    /// See [`LoadBatch`](crate::resources::LoadBatch).
+    pub fn load_batch(
+        &self,
+        batch: crate::resources::LoadBatch,
+    ) -> anyhow::Result<crate::resources::LoadedBatch> {
+        batch.load()
+    }
+
+    /// Returns the statistics of the last drawn frame.
+    pub fn stats(&self) -> RenderStats {
+        let mut stats = *self.stats.lock();
+        stats.texture_memory = crate::resources::memory::texture_memory();
+        stats.buffer_memory = crate::resources::memory::buffer_memory();
+        stats.gpu_timings = *self.gpu_timings.lock();
+        stats
+    }
+
     /// Returns all the present modes this device supports.
     ///
     /// If the vec is empty the engine has not been initialized and the settings should not be changed at this state.
@@ -673,6 +2273,243 @@ impl Graphics {
             .cloned()
             .unwrap_or(vec![])
     }
+
+    /// Returns the color space the window gets presented in.
+    pub fn color_space(&self) -> ColorSpace {
+        *self.color_space.lock()
+    }
+
+    /// Returns the color space requested for the creation of the window.
+    pub fn preferred_color_space(&self) -> ColorSpace {
+        *self.preferred_color_space.lock()
+    }
+
+    /// Requests a color space to be used when the engine gets initialized.
+    ///
+    /// The color space can not change afterwards. In case the display does not support it, sRGB gets used.
+    pub fn set_preferred_color_space(&self, color_space: ColorSpace) {
+        *self.preferred_color_space.lock() = color_space;
+    }
+
+    /// Returns all the color spaces the display supports.
+    ///
+    /// If the vec is empty the engine has not been initialized yet.
+    pub fn supported_color_spaces(&self) -> Vec<ColorSpace> {
+        self.available_color_spaces
+            .get()
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns how colors get mapped to the brightness of HDR displays.
+    pub fn hdr_settings(&self) -> HdrSettings {
+        *self.hdr.lock()
+    }
+
+    /// Sets how colors get mapped to the brightness of HDR displays. Takes effect the next frame.
+    pub fn set_hdr_settings(&self, settings: HdrSettings) {
+        *self.hdr.lock() = settings;
+    }
+
+    /// Adds a function recording own commands into every frame at the given stage.
+    ///
+    /// Callbacks of the same stage get called in the order they got added. An error returned by a callback gets
+    /// logged without stopping the frame, but the commands it recorded until then stay in the command buffer.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// SETTINGS.graphics.add_render_callback(RenderStage::AfterScene, move |context| {
+    ///     let viewport = context.viewport().clone();
+    ///     context
+    ///         .command_buffer()
+    ///         .set_viewport(0, [viewport].into_iter().collect())?
+    ///         .bind_pipeline_graphics(vignette.clone())?;
+    ///     unsafe { context.command_buffer().draw(3, 1, 0, 0)? };
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn add_render_callback(
+        &self,
+        stage: RenderStage,
+        callback: impl FnMut(&mut RenderContext) -> Result<()> + Send + 'static,
+    ) -> RenderCallbackId {
+        self.render_callbacks.add(stage, Box::new(callback))
+    }
+
+    /// Removes the render callback with the given ID, returning false in case it was removed already.
+    pub fn remove_render_callback(&self, id: RenderCallbackId) -> bool {
+        self.render_callbacks.remove(id)
+    }
+}
+
+/// Consecutive objects of a layer sharing the same model and default material, drawn in one instanced call.
+struct Batch {
+    material: Option<Material>,
+    instanced: Material,
+    model: ModelData,
+    data: Vec<InstanceData>,
+    /// True if the objects get drawn using the texture array of the frame, where the materials may differ.
+    bindless: bool,
+}
+
+impl Batch {
+    /// Returns true if an object with the given material and model can be drawn with this batch.
+    fn accepts(&self, material: Option<&Material>, model: &ModelData) -> bool {
+        !self.bindless
+            && self.model.same_buffers(model)
+            && match (&self.material, material) {
+                (Some(batched), Some(material)) => batched.same_resources(material),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+/// The maximum number of descriptor sets of the texture array kept for reuse.
+const TEXTURE_ARRAY_SETS: usize = 8;
+
+/// A descriptor set of the texture array and the number of textures of the table written to it.
+struct TextureArraySet {
+    set: Arc<DescriptorSet>,
+    written: usize,
+}
+
+/// The textures drawn using the bindless instance material, each one at its own index of the array.
+///
+/// Only default textured objects without anti aliasing get drawn using the array,
+/// all other objects bind the descriptor set of their own texture.
+#[derive(Default)]
+struct TextureTable {
+    indices: HashMap<*const ImageView, u32>,
+    textures: Vec<(Arc<ImageView>, Arc<Sampler>)>,
+    /// Whether each texture got drawn in the current frame.
+    used: Vec<bool>,
+    /// The descriptor sets of the array, each holding the first `written` textures of the table.
+    sets: Vec<TextureArraySet>,
+}
+
+impl TextureTable {
+    /// Starts a new frame, starting over in case some textures of the last frame did not get drawn,
+    /// so the array does not keep them loaded.
+    fn begin_frame(&mut self) {
+        if self.used.contains(&false) {
+            *self = Self::default();
+        }
+        self.used.fill(false);
+    }
+
+    /// Returns the index of the texture in the array, adding it in case it is not in there yet.
+    ///
+    /// Returns `None` in case the array is full.
+    fn index(&mut self, texture: &Texture) -> Option<u32> {
+        let view = texture.view();
+        let key = Arc::as_ptr(&view);
+        if let Some(&index) = self.indices.get(&key) {
+            self.used[index as usize] = true;
+            return Some(index);
+        }
+        let index = self.textures.len() as u32;
+        if index == BINDLESS_TEXTURES {
+            return None;
+        }
+        self.indices.insert(key, index);
+        self.textures.push((view, texture.sampler()));
+        self.used.push(true);
+        Some(index)
+    }
+
+    /// Empties the array, so the sets get overwritten starting at the first element.
+    fn clear(&mut self) {
+        self.indices.clear();
+        self.textures.clear();
+        self.used.clear();
+        for array in &mut self.sets {
+            array.written = 0;
+        }
+    }
+
+    /// Returns a descriptor set holding all textures of the array.
+    ///
+    /// A set the GPU is done with only gets the textures added since it was last written to.
+    /// A new set only gets made in case all of them are in use, where every element of the array has to be valid,
+    /// so the rest of it gets filled with the first texture.
+    fn set(
+        &mut self,
+        loader: &Loader,
+        layout: Arc<DescriptorSetLayout>,
+    ) -> Result<Arc<DescriptorSet>> {
+        let len = self.textures.len();
+        if let Some(array) = self.sets.iter().find(|array| array.written == len) {
+            return Ok(array.set.clone());
+        }
+        // Sets bound in command buffers that did not finish yet are shared with them and can not be written to.
+        for array in &mut self.sets {
+            if let Some(set) = Arc::get_mut(&mut array.set) {
+                set.update(
+                    [WriteDescriptorSet::image_view_sampler_array(
+                        0,
+                        array.written as u32,
+                        self.textures[array.written..].iter().cloned(),
+                    )],
+                    [],
+                )
+                .map_err(|e| VulkanError::Other(e.into()))?;
+                array.written = len;
+                return Ok(array.set.clone());
+            }
+        }
+
+        let elements = self
+            .textures
+            .iter()
+            .chain(self.textures.first().into_iter().cycle())
+            .take(BINDLESS_TEXTURES as usize)
+            .cloned();
+        let set = DescriptorSet::new(
+            loader.descriptor_set_allocator.clone(),
+            layout,
+            [WriteDescriptorSet::image_view_sampler_array(0, 0, elements)],
+            [],
+        )
+        .map_err(Validated::unwrap)
+        .map_err(VulkanError::Validated)?;
+        if self.sets.len() == TEXTURE_ARRAY_SETS {
+            self.sets.remove(0);
+        }
+        self.sets.push(TextureArraySet {
+            set: set.clone(),
+            written: len,
+        });
+        Ok(set)
+    }
+}
+
+/// Statistics of the renderer for debug overlays and catching performance regressions.
+///
+/// The counters without a note are for the last drawn frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of draw calls, where each batch of instances is one call.
+    pub draw_calls: u32,
+    /// The number of objects drawn, including instances.
+    pub objects: u32,
+    /// The number of objects that were not drawn, because they are outside of the view.
+    pub objects_culled: u32,
+    /// The number of times a different pipeline got bound than the one before.
+    pub pipeline_switches: u32,
+    /// The number of times the swapchain got recreated since the start of the game.
+    pub swapchain_recreations: u64,
+    /// The number of frames skipped by [damage tracking](Graphics::set_damage_tracking) since the start of the game.
+    pub skipped_frames: u64,
+    /// The bytes of GPU memory used by textures.
+    pub texture_memory: u64,
+    /// The bytes of GPU memory used by the vertex and index buffers of custom models.
+    pub buffer_memory: u64,
+    /// The time the GPU spent on each pass of the newest frame it finished, which is a few frames behind.
+    ///
+    /// Comparing it to the time the CPU takes per frame tells whether a scene is CPU or GPU bound.
+    /// `None` in case the GPU does not support timestamps or no frame finished yet.
+    pub gpu_timings: Option<GpuTimings>,
 }
 
 /// The presentation action to take when presenting images to the window.
@@ -752,13 +2589,21 @@ pub enum VulkanError {
     Validated(VulkanoError),
     #[error("An unexpected error with the shaders occured.")]
     ShaderError,
+    /// The GPU stopped working, for example after a driver crash, reset or update.
+    ///
+    /// Every resource made on the device is unusable afterwards, until [`Draw::recover`] rebuilds the device.
+    #[error("The GPU device got lost.")]
+    DeviceLost,
     #[error("An unexpected error occured: {0}")]
     Other(anyhow::Error),
 }
 
 impl From<Validated<VulkanoError>> for VulkanError {
     fn from(value: Validated<VulkanoError>) -> Self {
-        Self::Validated(value.unwrap())
+        match value.unwrap() {
+            VulkanoError::DeviceLost => Self::DeviceLost,
+            error => Self::Validated(error),
+        }
     }
 }
 
@@ -771,6 +2616,16 @@ pub enum ShaderError {
     InvalidSpirV,
     #[error("Something happened and the shader can not be made.: {0:?}")]
     Other(VulkanError),
+    #[error("Failed to read the shader file: {0}")]
+    Io(#[from] std::io::Error),
+    /// The GLSL compiler could not be started.
+    #[cfg(feature = "glsl")]
+    #[error("The GLSL compiler is not available.")]
+    CompilerUnavailable,
+    /// The GLSL source has errors.
+    #[cfg(feature = "glsl")]
+    #[error("Failed to compile the shader:\n{}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("\n"))]
+    Compile(Vec<crate::resources::glsl::GlslError>),
 }
 
 impl From<Validated<VulkanoError>> for ShaderError {
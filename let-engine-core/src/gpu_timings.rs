@@ -0,0 +1,201 @@
+//! Measuring the time the GPU spends on each part of a frame using timestamp queries.
+
+use std::{ops::Range, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use vulkano::{
+    command_buffer::RecordingCommandBuffer,
+    query::{QueryPool, QueryPoolCreateInfo, QueryResultFlags, QueryType},
+    sync::PipelineStage,
+};
+
+use crate::resources::vulkan::Vulkan;
+
+/// The number of frames whose timestamps can be in flight at once before the oldest gets overwritten.
+const FRAMES: u32 = 4;
+
+/// A part of the frame measured on the GPU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GpuPass {
+    /// The commands of [`RenderStage::BeforePass`](crate::render_callbacks::RenderStage::BeforePass) callbacks.
+    BeforePass,
+    /// Drawing the layers of the scene.
+    Scene,
+    /// Drawing egui and mapping the scene to the window, until the end of the render pass.
+    Composite,
+    /// The commands of [`RenderStage::AfterPass`](crate::render_callbacks::RenderStage::AfterPass) callbacks.
+    AfterPass,
+}
+
+impl GpuPass {
+    /// Every pass in the order they run.
+    pub const ALL: [GpuPass; 4] = [
+        GpuPass::BeforePass,
+        GpuPass::Scene,
+        GpuPass::Composite,
+        GpuPass::AfterPass,
+    ];
+
+    /// Returns the name of the pass.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GpuPass::BeforePass => "before pass",
+            GpuPass::Scene => "scene",
+            GpuPass::Composite => "composite",
+            GpuPass::AfterPass => "after pass",
+        }
+    }
+
+    /// Returns the timestamps the pass starts and ends at.
+    fn timestamps(&self) -> (Timestamp, Timestamp) {
+        match self {
+            GpuPass::BeforePass => (Timestamp::FrameStart, Timestamp::PassStart),
+            GpuPass::Scene => (Timestamp::SceneStart, Timestamp::SceneEnd),
+            GpuPass::Composite => (Timestamp::SceneEnd, Timestamp::PassEnd),
+            GpuPass::AfterPass => (Timestamp::PassEnd, Timestamp::FrameEnd),
+        }
+    }
+}
+
+/// The time the GPU spent on each pass of a frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GpuTimings {
+    passes: [Duration; GpuPass::ALL.len()],
+    total: Duration,
+}
+
+impl GpuTimings {
+    /// Returns the time spent on the given pass.
+    pub fn get(&self, pass: GpuPass) -> Duration {
+        self.passes[pass as usize]
+    }
+
+    /// Returns the time from the start to the end of the frame on the GPU.
+    pub fn total(&self) -> Duration {
+        self.total
+    }
+}
+
+/// The points of a frame a timestamp gets written at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Timestamp {
+    FrameStart,
+    /// Right before the render pass of the scene begins.
+    PassStart,
+    /// At the start of the secondary command buffer of the scene.
+    SceneStart,
+    /// At the end of the secondary command buffer of the scene.
+    SceneEnd,
+    /// Right after the render pass of the scene ended.
+    PassEnd,
+    FrameEnd,
+}
+
+impl Timestamp {
+    const COUNT: u32 = 6;
+}
+
+/// Writes the timestamps of the frames and reads them back once the GPU got to them.
+pub(crate) struct GpuTimer {
+    pool: Arc<QueryPool>,
+    /// The nanoseconds per timestamp tick.
+    period: f64,
+    /// The bits of the timestamps that are valid.
+    mask: u64,
+    /// The slot of the queries of the current frame.
+    frame: u32,
+    /// True for slots written by a frame and not read yet.
+    written: [bool; FRAMES as usize],
+}
+
+impl GpuTimer {
+    /// Creates the timer, `None` in case the graphics queue does not support timestamps.
+    pub fn new(vulkan: &Vulkan) -> Result<Option<Self>> {
+        let physical_device = vulkan.device.physical_device();
+        let Some(bits) = physical_device.queue_family_properties()
+            [vulkan.queue.queue_family_index() as usize]
+            .timestamp_valid_bits
+        else {
+            return Ok(None);
+        };
+        let pool = QueryPool::new(
+            vulkan.device.clone(),
+            QueryPoolCreateInfo {
+                query_count: FRAMES * Timestamp::COUNT,
+                ..QueryPoolCreateInfo::query_type(QueryType::Timestamp)
+            },
+        )?;
+        Ok(Some(Self {
+            pool,
+            period: physical_device.properties().timestamp_period as f64,
+            mask: u64::MAX.checked_shr(64 - bits).unwrap_or(0),
+            frame: 0,
+            written: [false; FRAMES as usize],
+        }))
+    }
+
+    fn queries(&self) -> Range<u32> {
+        let first = self.frame * Timestamp::COUNT;
+        first..first + Timestamp::COUNT
+    }
+
+    /// Moves on to the slot of the next frame, returning the timings written to it a few frames ago
+    /// in case the GPU finished them, and resets its queries.
+    ///
+    /// Has to be recorded outside of a render pass, before any timestamp of the frame.
+    pub fn begin_frame(
+        &mut self,
+        command_buffer: &mut RecordingCommandBuffer,
+    ) -> Result<Option<GpuTimings>> {
+        self.frame = (self.frame + 1) % FRAMES;
+        let timings = if self.written[self.frame as usize] {
+            self.read()?
+        } else {
+            None
+        };
+        // Safety: the reset runs after the frame that wrote the queries last in the queue.
+        unsafe { command_buffer.reset_query_pool(self.pool.clone(), self.queries())? };
+        self.written[self.frame as usize] = true;
+        Ok(timings)
+    }
+
+    /// Returns the timings of the current slot, `None` in case the GPU did not write all of them yet.
+    fn read(&self) -> Result<Option<GpuTimings>> {
+        let mut ticks = [0u64; Timestamp::COUNT as usize];
+        if !self
+            .pool
+            .get_results(self.queries(), &mut ticks, QueryResultFlags::empty())?
+        {
+            return Ok(None);
+        }
+        let duration = |(start, end): (Timestamp, Timestamp)| {
+            let ticks = ticks[end as usize].wrapping_sub(ticks[start as usize]) & self.mask;
+            Duration::from_nanos((ticks as f64 * self.period) as u64)
+        };
+        let mut timings = GpuTimings {
+            total: duration((Timestamp::FrameStart, Timestamp::FrameEnd)),
+            ..Default::default()
+        };
+        for pass in GpuPass::ALL {
+            timings.passes[pass as usize] = duration(pass.timestamps());
+        }
+        Ok(Some(timings))
+    }
+
+    /// Writes the given timestamp of the current frame once all commands before it finished.
+    pub fn write(
+        &self,
+        command_buffer: &mut RecordingCommandBuffer,
+        timestamp: Timestamp,
+    ) -> Result<()> {
+        // Safety: the query got reset at the beginning of the frame and gets written once.
+        unsafe {
+            command_buffer.write_timestamp(
+                self.pool.clone(),
+                self.queries().start + timestamp as u32,
+                PipelineStage::BottomOfPipe,
+            )?
+        };
+        Ok(())
+    }
+}
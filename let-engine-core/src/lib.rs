@@ -1,8 +1,12 @@
 pub mod camera;
 #[cfg(feature = "client")]
 pub mod draw;
+#[cfg(feature = "client")]
+mod gpu_timings;
 pub mod objects;
 #[cfg(feature = "client")]
+pub mod render_callbacks;
+#[cfg(feature = "client")]
 pub mod resources;
 pub mod utils;
 #[cfg(feature = "client")]
@@ -10,6 +14,14 @@ pub mod window;
 
 use thiserror::Error;
 
+#[cfg(feature = "determinism")]
+mod check_feature_conflicts {
+    #[cfg(feature = "simd")]
+    compile_error!("`determinism` can not be used together with `simd`. Disable the default features of the engine.");
+    #[cfg(feature = "fast-math")]
+    compile_error!("`determinism` can not be used together with `fast-math`.");
+}
+
 /// The game engine failed to start for the following reasons:
 #[derive(Debug, Error)]
 pub enum EngineError {
@@ -0,0 +1,325 @@
+//! Keeping objects at a place of their parent or the window.
+
+use glam::{Mat2, Mat4, Vec2};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::Transform;
+
+/// A point of a rectangle, where `(-1, -1)` is the top left and `(1, 1)` the bottom right corner.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AnchorPoint {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// Any point of the rectangle, going from -1 to 1 on both axes.
+    Custom(Vec2),
+}
+
+impl AnchorPoint {
+    /// Returns the position of the point in a rectangle going from -1 to 1.
+    pub fn position(&self) -> Vec2 {
+        match self {
+            Self::TopLeft => Vec2::new(-1.0, -1.0),
+            Self::Top => Vec2::new(0.0, -1.0),
+            Self::TopRight => Vec2::new(1.0, -1.0),
+            Self::Left => Vec2::new(-1.0, 0.0),
+            Self::Center => Vec2::ZERO,
+            Self::Right => Vec2::new(1.0, 0.0),
+            Self::BottomLeft => Vec2::new(-1.0, 1.0),
+            Self::Bottom => Vec2::new(0.0, 1.0),
+            Self::BottomRight => Vec2::new(1.0, 1.0),
+            Self::Custom(position) => *position,
+        }
+    }
+}
+
+/// The distance of an anchored object from its anchor point.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AnchorOffset {
+    /// An offset in pixels of the window, right and down being positive.
+    Pixels(Vec2),
+    /// An offset in fractions of the full width and height of the parent or the window.
+    Fraction(Vec2),
+}
+
+impl Default for AnchorOffset {
+    fn default() -> Self {
+        Self::Pixels(Vec2::ZERO)
+    }
+}
+
+/// What an object gets anchored to.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnchorTarget {
+    /// The rectangle of the parent object, or the window for objects at the root of their layer.
+    #[default]
+    Parent,
+    /// The window, as seen through the camera of the layer.
+    Window,
+}
+
+/// Places an object at a point of its parent or the window, replacing the position of its transform.
+///
+/// The position gets recomputed when the object gets initialized or synced and for all objects of the scene
+/// when the window gets resized. Changing the size of the parent requires calling
+/// [`Layer::update_anchors`](super::scenes::Layer::update_anchors) for its anchored children to follow.
+///
+/// # Usage
+/// ```ignore
+/// // a health bar 20 pixels away from the top left corner of the window
+/// let health = NewObjectBuilder::default()
+///     .anchor(Anchor::window(AnchorPoint::TopLeft).pivot(AnchorPoint::TopLeft).pixels(vec2(20.0, 20.0)))
+///     .build()?
+///     .init(&hud)?;
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Anchor {
+    /// The point of the target the object gets placed at.
+    pub point: AnchorPoint,
+    /// The point of the object placed at the anchor point. Default is the center.
+    pub pivot: AnchorPoint,
+    pub target: AnchorTarget,
+    pub offset: AnchorOffset,
+}
+
+impl Anchor {
+    /// Anchors the object to the given point of its parent.
+    pub fn new(point: AnchorPoint) -> Self {
+        Self {
+            point,
+            ..Default::default()
+        }
+    }
+
+    /// Anchors the object to the given point of the window.
+    pub fn window(point: AnchorPoint) -> Self {
+        Self {
+            point,
+            target: AnchorTarget::Window,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the point of the object placed at the anchor point and returns self.
+    #[inline]
+    pub fn pivot(mut self, pivot: AnchorPoint) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Sets the target and returns self.
+    #[inline]
+    pub fn target(mut self, target: AnchorTarget) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets the offset and returns self.
+    #[inline]
+    pub fn offset(mut self, offset: AnchorOffset) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Sets an offset in pixels and returns self.
+    #[inline]
+    pub fn pixels(self, pixels: Vec2) -> Self {
+        self.offset(AnchorOffset::Pixels(pixels))
+    }
+
+    /// Sets an offset in fractions of the target size and returns self.
+    #[inline]
+    pub fn fraction(self, fraction: Vec2) -> Self {
+        self.offset(AnchorOffset::Fraction(fraction))
+    }
+
+    /// Returns the local position of an object with the given size anchored in the given space,
+    /// `parent` being the public transform of the parent or `None` at the root of the layer.
+    pub(crate) fn resolve(
+        &self,
+        space: &AnchorSpace,
+        size: Vec2,
+        parent: Option<Transform>,
+    ) -> Vec2 {
+        let parent_size = parent.map_or(Vec2::ONE, |parent| parent.size);
+        let pivot = self.pivot.position() * size * parent_size;
+        match (self.target, parent) {
+            (AnchorTarget::Parent, Some(parent)) => {
+                let offset = match self.offset {
+                    AnchorOffset::Pixels(pixels) => pixels * space.pixel,
+                    AnchorOffset::Fraction(fraction) => fraction * parent.size * 2.0,
+                };
+                self.point.position() * parent.size + offset - pivot
+            }
+            (_, parent) => {
+                let offset = match self.offset {
+                    AnchorOffset::Pixels(pixels) => pixels,
+                    AnchorOffset::Fraction(fraction) => fraction * space.window,
+                };
+                let screen = (self.point.position() * 0.5 + 0.5) * space.window + offset;
+                let world = space.projection.to_world(screen);
+                let local = parent.map_or(world, |parent| {
+                    Mat2::from_angle(-parent.rotation) * (world - parent.position)
+                });
+                local - pivot
+            }
+        }
+    }
+}
+
+/// The view projection of the main camera of a layer along with its viewport in pixels.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ScreenProjection {
+    pub view_proj: Mat4,
+    /// The top left corner of the viewport in pixels.
+    pub min: Vec2,
+    /// The size of the viewport in pixels.
+    pub size: Vec2,
+}
+
+impl ScreenProjection {
+    /// Converts a position in pixels from the top left corner of the window to world space.
+    pub fn to_world(&self, position: Vec2) -> Vec2 {
+        let ndc = (position - self.min) / self.size * 2.0 - 1.0;
+        self.view_proj
+            .inverse()
+            .project_point3(ndc.extend(0.0))
+            .truncate()
+    }
+
+    /// Converts a position in world space to pixels from the top left corner of the window.
+    pub fn to_screen(&self, position: Vec2) -> Vec2 {
+        let ndc = self
+            .view_proj
+            .project_point3(position.extend(0.0))
+            .truncate();
+        self.min + (ndc + 1.0) * 0.5 * self.size
+    }
+}
+
+/// The window and camera of a layer at the moment anchors get resolved.
+pub(crate) struct AnchorSpace {
+    /// The size of the window in pixels.
+    pub window: Vec2,
+    /// The projection of the camera, the same [`Layer::screen_to_world`](super::scenes::Layer::screen_to_world) uses.
+    pub projection: ScreenProjection,
+    /// The size of a pixel in world space.
+    pub pixel: Vec2,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec2;
+
+    /// A 200 by 100 pixel window showing the world from -1 to 1 on both axes, making a pixel 0.01 by 0.02 units.
+    fn space() -> AnchorSpace {
+        let window = vec2(200.0, 100.0);
+        AnchorSpace {
+            window,
+            projection: ScreenProjection {
+                view_proj: Mat4::IDENTITY,
+                min: Vec2::ZERO,
+                size: window,
+            },
+            pixel: vec2(0.01, 0.02),
+        }
+    }
+
+    fn transform(position: Vec2, size: Vec2, rotation: f32) -> Transform {
+        Transform {
+            position,
+            size,
+            rotation,
+        }
+    }
+
+    fn assert_near(a: Vec2, b: Vec2) {
+        assert!(a.abs_diff_eq(b, 1e-4), "{a} != {b}");
+    }
+
+    #[test]
+    fn parent_points_and_offsets() {
+        let space = space();
+        let parent = Some(transform(vec2(5.0, 5.0), vec2(2.0, 1.0), 0.0));
+
+        let anchor = Anchor::new(AnchorPoint::TopRight);
+        assert_near(anchor.resolve(&space, Vec2::ONE, parent), vec2(2.0, -1.0));
+        // Pixel offsets get converted to world units.
+        let anchor = anchor.pixels(vec2(-10.0, 10.0));
+        assert_near(anchor.resolve(&space, Vec2::ONE, parent), vec2(1.9, -0.8));
+        // Fractions are relative to the full size of the parent.
+        let anchor = Anchor::new(AnchorPoint::Center).fraction(vec2(0.25, 0.5));
+        assert_near(anchor.resolve(&space, Vec2::ONE, parent), vec2(1.0, 1.0));
+    }
+
+    #[test]
+    fn pivot_moves_the_object_inside() {
+        let space = space();
+        let parent = Some(transform(Vec2::ZERO, vec2(2.0, 2.0), 0.0));
+        let anchor = Anchor::new(AnchorPoint::BottomRight).pivot(AnchorPoint::BottomRight);
+        assert_near(
+            anchor.resolve(&space, vec2(0.5, 0.25), parent),
+            vec2(1.0, 1.5),
+        );
+    }
+
+    #[test]
+    fn window_points() {
+        let space = space();
+        let anchor = Anchor::window(AnchorPoint::TopLeft);
+        assert_near(anchor.resolve(&space, Vec2::ONE, None), vec2(-1.0, -1.0));
+        let anchor = Anchor::window(AnchorPoint::Center).pixels(vec2(50.0, 25.0));
+        assert_near(anchor.resolve(&space, Vec2::ONE, None), vec2(0.5, 0.5));
+        let anchor = Anchor::window(AnchorPoint::TopLeft)
+            .pivot(AnchorPoint::TopLeft)
+            .fraction(vec2(0.5, 0.5));
+        assert_near(anchor.resolve(&space, vec2(0.1, 0.1), None), vec2(0.1, 0.1));
+        // Objects at the root anchored to their parent follow the window.
+        let anchor = Anchor::new(AnchorPoint::BottomRight);
+        assert_near(anchor.resolve(&space, Vec2::ONE, None), vec2(1.0, 1.0));
+    }
+
+    #[test]
+    fn window_anchor_inside_a_rotated_parent() {
+        let space = space();
+        let parent = Some(transform(
+            vec2(1.0, 0.0),
+            Vec2::ONE,
+            std::f32::consts::FRAC_PI_2,
+        ));
+        // The top right corner of the window is at (1, -1), one unit above the parent.
+        let anchor = Anchor::window(AnchorPoint::TopRight);
+        assert_near(anchor.resolve(&space, Vec2::ONE, parent), vec2(-1.0, 0.0));
+    }
+
+    #[test]
+    fn projection_round_trip() {
+        let projection = ScreenProjection {
+            view_proj: Mat4::from_scale(glam::vec3(0.5, 0.25, 1.0))
+                * Mat4::from_translation(glam::vec3(-3.0, 2.0, 0.0)),
+            min: vec2(10.0, 20.0),
+            size: vec2(300.0, 200.0),
+        };
+        for position in [vec2(10.0, 20.0), vec2(160.0, 120.0), vec2(-40.0, 180.0)] {
+            assert_near(
+                projection.to_screen(projection.to_world(position)),
+                position,
+            );
+        }
+        assert_near(projection.to_world(vec2(160.0, 120.0)), vec2(3.0, -2.0));
+    }
+}
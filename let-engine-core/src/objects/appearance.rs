@@ -11,11 +11,18 @@ use glam::vec2;
 
 /// Holds everything about the appearance of objects like
 /// textures, vetex/index data, color and material.
+///
+/// Appearances marked as mask clip all descendants of their object to the shape of their model, which is useful
+/// for scrollable lists or round minimaps. Masks can be nested up to 255 deep. The mask itself gets drawn as usual,
+/// so a transparent color makes an invisible mask. Textures do not change the clipped shape, a mask without a model
+/// hides its descendants and instanced appearances do not get clipped.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Appearance {
     visible: bool,
     transform: Transform,
     color: Color,
+    anti_aliasing: AntiAliasing,
+    mask: bool,
 
     instanced: bool,
     pub(crate) instance: Instance,
@@ -111,6 +118,8 @@ impl Appearance {
     getters_and_setters!(visible, "the visibility", bool);
     getters_and_setters!(transform, "the transform", Transform);
     getters_and_setters!(color, "the color", Color);
+    getters_and_setters!(anti_aliasing, "the edge anti aliasing", AntiAliasing);
+    getters_and_setters!(mask, "the mask flag", bool);
 
     /// Returns the model of the appearance.
     pub fn get_model(&self) -> Option<&Model> {
@@ -215,12 +224,50 @@ impl Default for Appearance {
             visible: true,
             transform: Transform::default(),
             color: Color::WHITE,
+            anti_aliasing: AntiAliasing::default(),
+            mask: false,
             instanced: false,
             instance: Instance::default(),
         }
     }
 }
 
+/// The way the edges of a shape get smoothed without the need of multisampling.
+///
+/// The distance field modes replace the fragment shader of the material with the one of the engine,
+/// keeping its vertex shader, topology and line width.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AntiAliasing {
+    /// No anti aliasing. The edges of the model are drawn as they are rasterized.
+    #[default]
+    None,
+    /// Treats the texture of the material as a multi-channel signed distance field of the shape
+    /// and draws it with smooth edges at any scale using the color of the appearance.
+    ///
+    /// The texture should be loaded without SRGB. Only applies to non instanced appearances with a single layer texture.
+    Msdf {
+        /// The distance range in texture pixels the field was generated with.
+        distance_range: f32,
+    },
+    /// Treats the red channel of the texture as a single channel signed distance field,
+    /// like the glyph atlas of SDF labels, and draws it with smooth edges at any scale.
+    ///
+    /// The outline and glow get drawn outside of the shape and have to fit in half the distance range.
+    /// Only applies to non instanced appearances with a single layer texture.
+    Sdf {
+        /// The distance range in texture pixels the field was generated with.
+        distance_range: f32,
+        /// The width of the outline in texture pixels.
+        outline_width: f32,
+        /// The color of the outline.
+        outline_color: Color,
+        /// The width of the glow outside of the outline in texture pixels.
+        glow_width: f32,
+        /// The color of the glow, fading out with distance.
+        glow_color: Color,
+    },
+}
+
 /// An instance that can be reused many times without performance impact.
 #[derive(Clone, Debug, Default)]
 pub(crate) struct Instance {
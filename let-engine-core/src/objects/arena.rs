@@ -0,0 +1,222 @@
+//! The storage of the nodes of a layer.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The ID given to the next layer, starting at 1 so default handles never resolve.
+static NEXT_LAYER: AtomicU32 = AtomicU32::new(1);
+
+/// A lightweight reference to an object in its layer, checked to still point to the same object.
+///
+/// Once the object gets removed, the handle stays invalid even after its slot gets reused by a new object.
+/// Handles only resolve in the layer they were made by.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectHandle {
+    layer: u32,
+    index: u32,
+    generation: u32,
+}
+
+impl ObjectHandle {
+    /// Returns the ID of the layer the object is in.
+    pub fn layer(&self) -> u32 {
+        self.layer
+    }
+
+    /// Returns the index of the slot of the object in its layer.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns how many times the slot got reused before this object.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Packs the handle into the user data of colliders and rigid bodies.
+    #[cfg(feature = "physics")]
+    pub(crate) fn to_bits(self) -> u128 {
+        ((self.layer as u128) << 64) | ((self.generation as u128) << 32) | self.index as u128
+    }
+
+    /// Unpacks a handle from the user data of a collider or rigid body.
+    #[cfg(feature = "physics")]
+    pub(crate) fn from_bits(bits: u128) -> Self {
+        Self {
+            layer: (bits >> 64) as u32,
+            index: bits as u32,
+            generation: (bits >> 32) as u32,
+        }
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// A generational arena holding the nodes of a layer next to each other,
+/// reusing the slots of removed objects.
+pub(crate) struct Arena<T> {
+    layer: u32,
+    slots: Vec<Slot<T>>,
+    /// The indices of the empty slots.
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Arena<T> {
+    /// Creates an empty arena with a new layer ID.
+    pub fn new() -> Self {
+        Self {
+            layer: NEXT_LAYER.fetch_add(1, Ordering::Relaxed),
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+
+    /// Makes space for the given number of objects to be inserted without growing.
+    pub fn reserve(&mut self, additional: usize) {
+        self.slots
+            .reserve(additional.saturating_sub(self.free.len()));
+    }
+
+    /// Returns the number of objects that fit without growing.
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Returns the handle the next inserted value is going to get.
+    pub fn next_handle(&self) -> ObjectHandle {
+        match self.free.last() {
+            Some(&index) => ObjectHandle {
+                layer: self.layer,
+                index,
+                generation: self.slots[index as usize].generation,
+            },
+            None => ObjectHandle {
+                layer: self.layer,
+                index: self.slots.len() as u32,
+                generation: 0,
+            },
+        }
+    }
+
+    /// Inserts a value, returning its handle.
+    pub fn insert(&mut self, value: T) -> ObjectHandle {
+        let handle = self.next_handle();
+        if self.free.pop().is_some() {
+            self.slots[handle.index as usize].value = Some(value);
+        } else {
+            self.slots.push(Slot {
+                generation: 0,
+                value: Some(value),
+            });
+        }
+        self.len += 1;
+        handle
+    }
+
+    fn slot(&self, handle: ObjectHandle) -> Option<&Slot<T>> {
+        if handle.layer != self.layer {
+            return None;
+        }
+        self.slots
+            .get(handle.index as usize)
+            .filter(|slot| slot.generation == handle.generation)
+    }
+
+    /// Returns the value of the handle in case the object still exists.
+    pub fn get(&self, handle: ObjectHandle) -> Option<&T> {
+        self.slot(handle)?.value.as_ref()
+    }
+
+    /// Returns the value of the handle mutably in case the object still exists.
+    pub fn get_mut(&mut self, handle: ObjectHandle) -> Option<&mut T> {
+        self.slot(handle)?;
+        self.slots[handle.index as usize].value.as_mut()
+    }
+
+    /// Returns true if the object of the handle still exists.
+    pub fn contains(&self, handle: ObjectHandle) -> bool {
+        self.get(handle).is_some()
+    }
+
+    /// Removes the object of the handle, invalidating the handle.
+    pub fn remove(&mut self, handle: ObjectHandle) -> Option<T> {
+        self.slot(handle)?;
+        let slot = &mut self.slots[handle.index as usize];
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(handle.index);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Returns the number of objects.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Iterates over the values in the order of their slots.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_handles_stay_invalid() {
+        let mut arena = Arena::new();
+        let first = arena.insert("first");
+        assert_eq!(arena.remove(first), Some("first"));
+        let second = arena.insert("second");
+
+        assert_eq!(first.index(), second.index());
+        assert_eq!(arena.get(first), None);
+        assert_eq!(arena.remove(first), None);
+        assert_eq!(arena.get(second), Some(&"second"));
+        assert_eq!(arena.len(), 1);
+    }
+
+    #[test]
+    fn handles_only_resolve_in_their_layer() {
+        let mut first = Arena::new();
+        let mut second = Arena::new();
+        let handle = first.insert(1);
+        let other = second.insert(2);
+
+        assert_ne!(handle.layer(), other.layer());
+        assert_eq!(second.get(handle), None);
+        assert_eq!(ObjectHandle::default().layer(), 0);
+        assert_eq!(first.get(ObjectHandle::default()), None);
+    }
+
+    #[test]
+    fn next_handle_matches_insert() {
+        let mut arena = Arena::new();
+        let first = arena.insert(1);
+        arena.insert(2);
+        arena.remove(first);
+
+        let next = arena.next_handle();
+        assert_eq!(arena.insert(3), next);
+        assert_eq!(arena.next_handle().index(), 2);
+        assert_eq!(arena.values().copied().collect::<Vec<_>>(), [3, 2]);
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn handle_bits_round_trip() {
+        let handle = ObjectHandle {
+            layer: 7,
+            index: u32::MAX,
+            generation: 3,
+        };
+        assert_eq!(ObjectHandle::from_bits(handle.to_bits()), handle);
+    }
+}
@@ -0,0 +1,602 @@
+//! Changing scenes through commands that can be undone, for editors and tools.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem::discriminant,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+
+#[cfg(feature = "client")]
+use super::Appearance;
+use super::{scenes::Layer, NewObject, Object, ObjectError, ObjectHandle, Transform};
+
+/// Changes of the same kind to the same object made within this time get undone together.
+const MERGE_TIME: Duration = Duration::from_millis(500);
+
+/// A change to the scene executed by a [`SceneHistory`].
+#[derive(Clone)]
+pub enum SceneCommand {
+    /// Spawns an object into a layer under the given parent, or at the root of the layer without one.
+    Spawn {
+        layer: Arc<Layer>,
+        parent: Option<Object>,
+        object: NewObject,
+    },
+    /// Removes an object together with its children.
+    Remove(Object),
+    /// Spawns a removed object with its children again at the place it got removed from.
+    Restore(RemovedObject),
+    SetTransform(Object, Transform),
+    #[cfg(feature = "client")]
+    SetAppearance(Object, Appearance),
+    SetName(Object, Option<String>),
+    SetTags(Object, Vec<String>),
+    /// Moves an object to the given index in the children order of its parent.
+    MoveTo(Object, usize),
+}
+
+impl SceneCommand {
+    /// Returns the object this command changes, which is the parent for spawns and restores.
+    pub fn object(&self) -> Option<&Object> {
+        match self {
+            Self::Spawn { parent, .. } => parent.as_ref(),
+            Self::Restore(removed) => removed.parent.as_ref(),
+            Self::Remove(object) | Self::SetTransform(object, _) => Some(object),
+            #[cfg(feature = "client")]
+            Self::SetAppearance(object, _) => Some(object),
+            Self::SetName(object, _) | Self::SetTags(object, _) | Self::MoveTo(object, _) => {
+                Some(object)
+            }
+        }
+    }
+
+    /// Applies the command to the scene without recording it in a history,
+    /// returning the object it spawned, removed or changed.
+    pub fn apply(self) -> Result<Object> {
+        Ok(History::new(0).apply(self)?.object)
+    }
+
+    /// Returns true if executing the other command right after this one can be undone together with it.
+    fn merges_with(&self, other: &SceneCommand) -> bool {
+        let mergeable = match self {
+            Self::SetTransform(..) | Self::SetName(..) | Self::SetTags(..) => true,
+            #[cfg(feature = "client")]
+            Self::SetAppearance(..) => true,
+            _ => false,
+        };
+        mergeable
+            && discriminant(self) == discriminant(other)
+            && self.object().map(Object::handle) == other.object().map(Object::handle)
+    }
+}
+
+/// An object removed by a [`SceneCommand::Remove`], keeping everything needed to restore it with its children.
+///
+/// Data attached to the objects using [`Object::insert`] does not get restored.
+#[derive(Clone)]
+pub struct RemovedObject {
+    layer: Arc<Layer>,
+    parent: Option<Object>,
+    index: usize,
+    tree: RemovedNode,
+}
+
+impl RemovedObject {
+    /// Returns the layer the object got removed from.
+    pub fn layer(&self) -> &Arc<Layer> {
+        &self.layer
+    }
+
+    /// Returns the removed object without its children.
+    pub fn object(&self) -> &NewObject {
+        &self.tree.object
+    }
+}
+
+#[derive(Clone)]
+struct RemovedNode {
+    handle: ObjectHandle,
+    object: NewObject,
+    children: Vec<RemovedNode>,
+}
+
+impl RemovedNode {
+    fn new(object: &Object) -> Self {
+        #[allow(unused_mut)]
+        let mut new = object.to_new();
+        // The bodies get removed along with the object, so restoring it has to add new ones.
+        #[cfg(feature = "physics")]
+        {
+            new.physics.collider_handle = None;
+            new.physics.rigid_body_handle = None;
+        }
+        Self {
+            handle: object.handle(),
+            object: new,
+            children: object.children().iter().map(Self::new).collect(),
+        }
+    }
+}
+
+/// Whether a change came from executing a new command or from going through the history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HistoryAction {
+    Execute,
+    Undo,
+    Redo,
+}
+
+/// A change made by a [`SceneHistory`], given to its change listeners.
+#[derive(Clone)]
+pub struct SceneChange {
+    pub action: HistoryAction,
+    /// The command that got applied, which is the inverse of the original one when undoing.
+    pub command: SceneCommand,
+    /// The object that got spawned, removed, restored or changed.
+    pub object: Object,
+}
+
+type Listener = Arc<dyn Fn(&SceneChange) + Send + Sync>;
+
+struct Entry {
+    undo: SceneCommand,
+    redo: SceneCommand,
+    /// The time the step got executed, or none for steps that do not merge.
+    time: Option<Instant>,
+}
+
+/// A command applied to the scene along with the command undoing it.
+struct Applied {
+    command: SceneCommand,
+    inverse: SceneCommand,
+    object: Object,
+}
+
+struct History {
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    limit: usize,
+    /// Removed objects pointing to the objects they got restored as.
+    ///
+    /// Handles contain the [ID of their layer](Layer::id), so objects of different layers never collide.
+    restored: HashMap<ObjectHandle, Object>,
+}
+
+impl History {
+    fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            limit,
+            restored: HashMap::new(),
+        }
+    }
+
+    fn resolve(&self, object: &Object) -> Object {
+        let mut object = object;
+        while let Some(restored) = self.restored.get(&object.handle()) {
+            object = restored;
+        }
+        object.clone()
+    }
+
+    /// Forgets the restored objects no step refers to anymore, following the chains of objects
+    /// that got removed and restored multiple times.
+    fn prune(&mut self) {
+        let referenced: Vec<ObjectHandle> = self
+            .undo
+            .iter()
+            .chain(self.redo.iter())
+            .flat_map(|entry| [entry.undo.object(), entry.redo.object()])
+            .flatten()
+            .map(Object::handle)
+            .collect();
+        let mut keep = HashSet::new();
+        for mut handle in referenced {
+            while let Some(restored) = self.restored.get(&handle) {
+                if !keep.insert(handle) {
+                    break;
+                }
+                handle = restored.handle();
+            }
+        }
+        self.restored.retain(|handle, _| keep.contains(handle));
+    }
+
+    /// Returns the current state of the object in its layer.
+    fn live(&self, object: &Object) -> Result<Object, ObjectError> {
+        self.resolve(object).current()
+    }
+
+    fn apply(&mut self, command: SceneCommand) -> Result<Applied> {
+        Ok(match command {
+            SceneCommand::Spawn {
+                layer,
+                parent,
+                object,
+            } => {
+                let parent = parent.map(|parent| self.resolve(&parent));
+                let spawned = object
+                    .clone()
+                    .init_with_optional_parent(&layer, parent.as_ref())?;
+                Applied {
+                    command: SceneCommand::Spawn {
+                        layer,
+                        parent,
+                        object,
+                    },
+                    inverse: SceneCommand::Remove(spawned.clone()),
+                    object: spawned,
+                }
+            }
+            SceneCommand::Remove(object) => {
+                let object = self.live(&object)?;
+                let layer = object.layer().clone();
+                let index = layer.child_index(&object)?;
+                let parent = object.parent();
+                let tree = RemovedNode::new(&object);
+                object.clone().remove()?;
+                Applied {
+                    command: SceneCommand::Remove(object.clone()),
+                    inverse: SceneCommand::Restore(RemovedObject {
+                        layer,
+                        parent,
+                        index,
+                        tree,
+                    }),
+                    object,
+                }
+            }
+            SceneCommand::Restore(mut removed) => {
+                removed.parent = removed.parent.map(|parent| self.resolve(&parent));
+                let restored =
+                    self.restore(&removed.layer, removed.parent.as_ref(), &removed.tree)?;
+                // Stays at the end in case siblings got removed in the meantime.
+                restored.move_to(removed.index).ok();
+                Applied {
+                    command: SceneCommand::Restore(removed),
+                    inverse: SceneCommand::Remove(restored.clone()),
+                    object: restored,
+                }
+            }
+            SceneCommand::SetTransform(object, transform) => self.set(
+                &object,
+                transform,
+                |object| &mut object.transform,
+                SceneCommand::SetTransform,
+            )?,
+            #[cfg(feature = "client")]
+            SceneCommand::SetAppearance(object, appearance) => self.set(
+                &object,
+                appearance,
+                |object| &mut object.appearance,
+                SceneCommand::SetAppearance,
+            )?,
+            SceneCommand::SetName(object, name) => self.set(
+                &object,
+                name,
+                |object| &mut object.name,
+                SceneCommand::SetName,
+            )?,
+            SceneCommand::SetTags(object, tags) => self.set(
+                &object,
+                tags,
+                |object| &mut object.tags,
+                SceneCommand::SetTags,
+            )?,
+            SceneCommand::MoveTo(object, index) => {
+                let object = self.live(&object)?;
+                let old = object.layer().child_index(&object)?;
+                object.move_to(index)?;
+                Applied {
+                    command: SceneCommand::MoveTo(object.clone(), index),
+                    inverse: SceneCommand::MoveTo(object.clone(), old),
+                    object,
+                }
+            }
+        })
+    }
+
+    /// Replaces a field of the object and syncs it.
+    fn set<T: Clone>(
+        &self,
+        object: &Object,
+        value: T,
+        field: fn(&mut Object) -> &mut T,
+        command: fn(Object, T) -> SceneCommand,
+    ) -> Result<Applied> {
+        let mut object = self.live(object)?;
+        let old = std::mem::replace(field(&mut object), value.clone());
+        object.sync()?;
+        Ok(Applied {
+            command: command(object.clone(), value),
+            inverse: command(object.clone(), old),
+            object,
+        })
+    }
+
+    fn restore(
+        &mut self,
+        layer: &Arc<Layer>,
+        parent: Option<&Object>,
+        node: &RemovedNode,
+    ) -> Result<Object> {
+        let object = node
+            .object
+            .clone()
+            .init_with_optional_parent(layer, parent)?;
+        self.restored.insert(node.handle, object.clone());
+        for child in node.children.iter() {
+            self.restore(layer, Some(&object), child)?;
+        }
+        Ok(object)
+    }
+}
+
+/// Modifies scenes using [`SceneCommand`]s, keeping undo and redo stacks and telling listeners about every change.
+///
+/// Commands get applied one at a time, so editor tools and the game can change the scene from different threads
+/// while it is running. Changes the game makes to objects directly are not part of the history, so undoing
+/// a change to an object the game removed in the meantime fails and drops that step.
+///
+/// Undoing a removal spawns the objects again with new IDs. The history keeps track of them, so commands holding
+/// the removed objects still apply to the restored ones and [`resolve`](SceneHistory::resolve) returns their new handles.
+///
+/// Changes of the same kind to the same object following each other closely, like dragging a value in an editor,
+/// get undone together.
+///
+/// # Usage
+/// ```ignore
+/// let history = SceneHistory::new();
+/// history.on_change(|change| println!("{:?}", change.action));
+///
+/// let object = history.execute(SceneCommand::Spawn {
+///     layer: layer.clone(),
+///     parent: None,
+///     object: NewObject::new(),
+/// })?;
+/// history.execute(SceneCommand::SetTransform(object, Transform::default().size(vec2(2.0, 2.0))))?;
+/// history.undo()?;
+/// ```
+pub struct SceneHistory {
+    history: Mutex<History>,
+    listeners: Mutex<Vec<Listener>>,
+}
+
+impl Default for SceneHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneHistory {
+    /// Creates an empty history keeping up to 256 steps.
+    pub fn new() -> Self {
+        Self {
+            history: Mutex::new(History::new(256)),
+            listeners: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns the maximum number of steps that can be undone.
+    pub fn limit(&self) -> usize {
+        self.history.lock().limit
+    }
+
+    /// Sets the maximum number of steps that can be undone, forgetting the oldest ones above it.
+    pub fn set_limit(&self, limit: usize) {
+        let mut history = self.history.lock();
+        history.limit = limit;
+        let len = history.undo.len();
+        history.undo.drain(..len.saturating_sub(limit));
+        history.prune();
+    }
+
+    /// Applies the command to the scene and returns the object it spawned, removed or changed.
+    ///
+    /// Clears the steps that could be redone.
+    pub fn execute(&self, command: SceneCommand) -> Result<Object> {
+        let mut history = self.history.lock();
+        let applied = history.apply(command)?;
+        history.redo.clear();
+
+        let merges = history.undo.back().is_some_and(|last| {
+            last.time.is_some_and(|time| time.elapsed() < MERGE_TIME)
+                && last.redo.merges_with(&applied.command)
+        });
+        if merges {
+            let last = history.undo.back_mut().unwrap();
+            last.redo = applied.command.clone();
+            last.time = Some(Instant::now());
+        } else {
+            history.undo.push_back(Entry {
+                undo: applied.inverse,
+                redo: applied.command.clone(),
+                time: Some(Instant::now()),
+            });
+            if history.undo.len() > history.limit {
+                history.undo.pop_front();
+            }
+        }
+        history.prune();
+        drop(history);
+
+        self.notify(
+            HistoryAction::Execute,
+            applied.command,
+            applied.object.clone(),
+        );
+        Ok(applied.object)
+    }
+
+    /// Undoes the last step, returning false if there is none.
+    pub fn undo(&self) -> Result<bool> {
+        let mut history = self.history.lock();
+        let Some(entry) = history.undo.pop_back() else {
+            return Ok(false);
+        };
+        let applied = history.apply(entry.undo)?;
+        history.redo.push(Entry {
+            undo: applied.command.clone(),
+            redo: applied.inverse,
+            time: None,
+        });
+        history.prune();
+        drop(history);
+
+        self.notify(HistoryAction::Undo, applied.command, applied.object);
+        Ok(true)
+    }
+
+    /// Redoes the last undone step, returning false if there is none.
+    pub fn redo(&self) -> Result<bool> {
+        let mut history = self.history.lock();
+        let Some(entry) = history.redo.pop() else {
+            return Ok(false);
+        };
+        let applied = history.apply(entry.redo)?;
+        history.undo.push_back(Entry {
+            undo: applied.inverse,
+            redo: applied.command.clone(),
+            time: None,
+        });
+        history.prune();
+        drop(history);
+
+        self.notify(HistoryAction::Redo, applied.command, applied.object);
+        Ok(true)
+    }
+
+    /// Returns true if there is a step to undo.
+    pub fn can_undo(&self) -> bool {
+        !self.history.lock().undo.is_empty()
+    }
+
+    /// Returns true if there is an undone step to redo.
+    pub fn can_redo(&self) -> bool {
+        !self.history.lock().redo.is_empty()
+    }
+
+    /// Forgets all steps without changing the scene.
+    pub fn clear(&self) {
+        let mut history = self.history.lock();
+        history.undo.clear();
+        history.redo.clear();
+        history.restored.clear();
+    }
+
+    /// Returns the handle of the given object, or of the object it got restored as after undoing its removal.
+    ///
+    /// Restored objects are only remembered as long as a step of the history refers to them.
+    pub fn resolve(&self, object: &Object) -> Object {
+        self.history.lock().resolve(object)
+    }
+
+    /// Adds a function getting called after every change made by this history.
+    pub fn on_change(&self, listener: impl Fn(&SceneChange) + Send + Sync + 'static) {
+        self.listeners.lock().push(Arc::new(listener));
+    }
+
+    /// Removes all change listeners.
+    pub fn clear_listeners(&self) {
+        self.listeners.lock().clear();
+    }
+
+    /// Calls the listeners without holding a lock, so they can use the history themselves.
+    fn notify(&self, action: HistoryAction, command: SceneCommand, object: Object) {
+        let listeners = self.listeners.lock().clone();
+        let change = SceneChange {
+            action,
+            command,
+            object,
+        };
+        for listener in listeners.iter() {
+            listener(&change);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(history: &SceneHistory, layer: &Arc<Layer>, parent: Option<&Object>) -> Object {
+        history
+            .execute(SceneCommand::Spawn {
+                layer: layer.clone(),
+                parent: parent.cloned(),
+                object: NewObject::new(),
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn removed_objects_resolve_to_restored_ones() {
+        let layer = Layer::new().unwrap();
+        let history = SceneHistory::new();
+        let object = spawn(&history, &layer, None);
+        let child = spawn(&history, &layer, Some(&object));
+
+        history
+            .execute(SceneCommand::Remove(object.clone()))
+            .unwrap();
+        assert!(layer.object(object.handle()).is_none());
+        history.undo().unwrap();
+
+        let restored = history.resolve(&object);
+        assert_ne!(restored.handle(), object.handle());
+        assert!(layer.object(restored.handle()).is_some());
+        let parent = history
+            .resolve(&child)
+            .parent()
+            .map(|parent| parent.handle());
+        assert_eq!(parent, Some(restored.handle()));
+
+        // Undoing the spawn of the child removes the child it got restored as.
+        let restored_child = history.resolve(&child);
+        history.undo().unwrap();
+        assert!(layer.object(restored_child.handle()).is_none());
+    }
+
+    #[test]
+    fn restored_objects_stay_in_their_layer() {
+        let first = Layer::new().unwrap();
+        let second = Layer::new().unwrap();
+        let history = SceneHistory::new();
+        let object = spawn(&history, &first, None);
+        let other = spawn(&history, &second, None);
+        assert_eq!(object.handle().index(), other.handle().index());
+
+        history.execute(SceneCommand::Remove(object)).unwrap();
+        history.undo().unwrap();
+        assert_eq!(history.resolve(&other).handle(), other.handle());
+    }
+
+    #[test]
+    fn forgotten_steps_prune_restored_objects() {
+        let layer = Layer::new().unwrap();
+        let history = SceneHistory::new();
+        let object = spawn(&history, &layer, None);
+        history
+            .execute(SceneCommand::Remove(object.clone()))
+            .unwrap();
+        history.undo().unwrap();
+        assert_eq!(history.history.lock().restored.len(), 1);
+
+        // Only the undone spawn referred to the removed object.
+        history.set_limit(0);
+        assert!(history.history.lock().restored.is_empty());
+        assert_eq!(history.resolve(&object).handle(), object.handle());
+
+        history.set_limit(16);
+        let object = spawn(&history, &layer, None);
+        history.execute(SceneCommand::Remove(object)).unwrap();
+        history.undo().unwrap();
+        history.clear();
+        assert!(history.history.lock().restored.is_empty());
+    }
+}
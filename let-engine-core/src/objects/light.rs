@@ -0,0 +1,62 @@
+//! Point lights shining on the normal mapped objects of a layer.
+
+use glam::Vec2;
+
+use super::Color;
+
+/// The maximum number of lights per layer. Lights past this number do not get drawn.
+pub const MAX_LIGHTS: usize = 32;
+
+/// A point light lighting objects drawn with a lit material.
+///
+/// Lights belong to a layer and get set using [Layer::set_lights](super::scenes::Layer::set_lights).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    /// The position of the light in the world space of its layer.
+    pub position: Vec2,
+    /// How far the light is in front of the layer, making light hit surfaces at a flatter angle the lower it is.
+    pub height: f32,
+    /// The distance at which the light fades out completely.
+    pub radius: f32,
+    pub color: Color,
+    /// The brightness of the light, multiplied with the color.
+    pub intensity: f32,
+}
+
+impl Light {
+    /// Creates a white light at the given position with the given radius.
+    pub fn new(position: Vec2, radius: f32) -> Self {
+        Self {
+            position,
+            radius,
+            ..Default::default()
+        }
+    }
+
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            height: 0.5,
+            radius: 5.0,
+            color: Color::WHITE,
+            intensity: 1.0,
+        }
+    }
+}
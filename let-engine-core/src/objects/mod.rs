@@ -1,39 +1,67 @@
 //! Objects to be drawn to the screen.
 
+#[cfg(feature = "client")]
+mod anchor;
 #[cfg(feature = "client")]
 mod appearance;
 #[cfg(feature = "client")]
 mod color;
 #[cfg(feature = "client")]
+pub mod light;
+#[cfg(feature = "client")]
+pub use anchor::{Anchor, AnchorOffset, AnchorPoint, AnchorTarget};
+#[cfg(feature = "client")]
+use anchor::{AnchorSpace, ScreenProjection};
+#[cfg(feature = "client")]
 pub use appearance::*;
 #[cfg(feature = "client")]
 pub use color::Color;
+#[cfg(feature = "client")]
+pub use light::Light;
 
 #[cfg(feature = "physics")]
 pub mod physics;
 #[cfg(feature = "physics")]
 use physics::*;
 
+mod arena;
+mod history;
+mod pool;
+mod queue;
 pub mod scenes;
+mod time_effects;
+use arena::Arena;
+pub use arena::ObjectHandle;
+pub use history::{HistoryAction, RemovedObject, SceneChange, SceneCommand, SceneHistory};
+use pool::PoolInner;
+pub use pool::{ObjectPool, ObjectStats};
+pub use queue::SceneQueue;
 use scenes::Layer;
 
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Result};
 
 use derive_builder::Builder;
-use parking_lot::Mutex;
+#[cfg(feature = "client")]
+use rayon::prelude::*;
 
+#[cfg(feature = "physics")]
+use std::collections::HashSet;
 use std::{
+    any::{Any, TypeId},
     collections::HashMap,
-    sync::{Arc, Weak},
+    sync::Arc,
 };
 
 use glam::{vec2, Vec2};
 
 #[cfg(feature = "physics")]
-type RigidBodyParent = Option<Option<Weak<Mutex<Node<Object>>>>>;
-type ObjectsMap = HashMap<usize, NObject>;
-pub(crate) type NObject = Arc<Mutex<Node<Object>>>;
-type WeakObject = Weak<Mutex<Node<Object>>>;
+type RigidBodyParent = Option<Option<ObjectHandle>>;
+/// The nodes of a layer.
+pub(crate) type Nodes = Arena<Node>;
+
+/// The number of children a node needs for its subtrees to get collected in parallel.
+#[cfg(feature = "client")]
+const PARALLEL_TRAVERSAL_THRESHOLD: usize = 64;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -86,6 +114,19 @@ impl Transform {
         self.rotation = rotation;
         self
     }
+
+    /// Linearly interpolates between this and another transform by `t`, where 0 returns self and 1 returns `other`.
+    ///
+    /// The rotation takes the shortest way around.
+    pub fn lerp(self, other: Self, t: f32) -> Self {
+        use std::f32::consts::{PI, TAU};
+        let rotation_difference = (other.rotation - self.rotation + PI).rem_euclid(TAU) - PI;
+        Transform {
+            position: self.position.lerp(other.position, t),
+            size: self.size.lerp(other.size, t),
+            rotation: self.rotation + rotation_difference * t,
+        }
+    }
 }
 
 impl From<(Vec2, f32)> for Transform {
@@ -117,107 +158,269 @@ impl Default for Transform {
 #[derive(Clone)]
 #[cfg(feature = "client")]
 pub(crate) struct VisualObject {
+    pub id: usize,
     pub transform: Transform,
     pub appearance: Appearance,
+    /// The stencil value the object gets drawn at, which is the number of masks it is in.
+    pub clip: u32,
+    pub stencil: StencilPass,
 }
 #[cfg(feature = "client")]
 impl VisualObject {
     /// Combines the object position data.
-    pub fn combined(object: &Object, parent: &Object) -> Self {
+    pub fn combined(object: &Object, parent: &Object, clip: u32) -> Self {
         let transform = object.transform.combine(parent.public_transform());
         let appearance = object.appearance().clone();
         Self {
+            id: object.id,
             transform,
             appearance,
+            clip,
+            stencil: StencilPass::Draw,
         }
     }
 }
 
+/// What drawing a visual object does with the stencil buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg(feature = "client")]
+pub(crate) enum StencilPass {
+    /// Gets clipped by the masks it is in.
+    Draw,
+    /// Adds the shape of the mask to the stencil buffer before drawing it.
+    Mask,
+    /// Removes the shape of the mask from the stencil buffer again after all children got drawn.
+    Unmask,
+}
+
+/// The deepest masks can be nested in an 8 bit stencil buffer.
+#[cfg(feature = "client")]
+const MAX_CLIP: u32 = u8::MAX as u32;
+
 /// Node structure for the layer.
-pub(crate) struct Node<T> {
-    pub object: T,
-    // pub parent: Option<Weak<Mutex<Node<T>>>>,
+pub(crate) struct Node {
+    pub object: Object,
+    /// The object this one is inside of, which is none for the root of the layer.
+    pub parent: Option<ObjectHandle>,
     #[cfg(feature = "physics")]
     pub rigid_body_parent: RigidBodyParent,
-    pub children: Vec<Arc<Mutex<Node<T>>>>,
+    /// The number of times the object got synced, so a physics step running meanwhile does not undo the changes.
+    #[cfg(feature = "physics")]
+    pub changes: u64,
+    pub children: Vec<ObjectHandle>,
+    /// User data attached to the object, one value per type.
+    pub data: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
 }
-impl PartialEq for Node<Object> {
+impl PartialEq for Node {
     fn eq(&self, other: &Self) -> bool {
         self.object == other.object
     }
 }
 
-impl Node<Object> {
-    /// Takes a vector of every object transform and appearance and fills it with the right client order based on the root node inserted.
+impl Node {
+    /// Takes a vector of every object transform and appearance and fills it with the right client order based on the node inserted.
+    ///
+    /// Nodes with many children get their subtrees collected in parallel and appended in order.
+    /// Masks get pushed a second time after their descendants to remove them from the stencil buffer again.
+    #[cfg(feature = "client")]
+    pub(crate) fn order_position(
+        order: &mut Vec<VisualObject>,
+        nodes: &Nodes,
+        node: &Self,
+        clip: u32,
+    ) {
+        if node.children.len() < PARALLEL_TRAVERSAL_THRESHOLD {
+            for child in node.children.iter() {
+                Self::order_child(order, nodes, *child, &node.object, clip);
+            }
+            return;
+        }
+        let subtrees: Vec<Vec<VisualObject>> = node
+            .children
+            .par_iter()
+            .map(|child| {
+                let mut subtree = vec![];
+                Self::order_child(&mut subtree, nodes, *child, &node.object, clip);
+                subtree
+            })
+            .collect();
+        order.reserve(subtrees.iter().map(Vec::len).sum());
+        for subtree in subtrees {
+            order.extend(subtree);
+        }
+    }
+
+    /// Pushes the given child and its descendants in client order.
     #[cfg(feature = "client")]
-    pub(crate) fn order_position(order: &mut Vec<VisualObject>, objects: &Self) {
-        for child in objects.children.iter() {
-            let child = child.lock();
+    fn order_child(
+        order: &mut Vec<VisualObject>,
+        nodes: &Nodes,
+        child: ObjectHandle,
+        parent: &Object,
+        clip: u32,
+    ) {
+        let Some(child) = nodes.get(child) else {
+            return;
+        };
+        if !child.object.appearance.get_visible() {
+            return;
+        }
+        let object = VisualObject::combined(&child.object, parent, clip);
+        let inner = Self::push_visual(order, object.clone());
+        for child in child.children.iter().filter_map(|child| nodes.get(*child)) {
             if !child.object.appearance.get_visible() {
                 continue;
             }
-            let object = VisualObject::combined(&child.object, &objects.object);
-            order.push(object.clone());
-            for child in child.children.iter() {
-                let child = child.lock();
-                if !child.object.appearance.get_visible() {
-                    continue;
-                }
-                order.push(VisualObject {
-                    transform: child.object.transform.combine(object.transform),
-                    appearance: child.object.appearance().clone(),
-                });
-                Self::order_position(order, &child);
-            }
+            let visual = VisualObject {
+                id: child.object.id,
+                transform: child.object.transform.combine(object.transform),
+                appearance: child.object.appearance().clone(),
+                clip: inner,
+                stencil: StencilPass::Draw,
+            };
+            let clip = Self::push_visual(order, visual.clone());
+            Self::order_position(order, nodes, child, clip);
+            Self::pop_mask(order, visual);
+        }
+        Self::pop_mask(order, object);
+    }
+
+    /// Pushes the object, returning the stencil value its children get drawn at.
+    #[cfg(feature = "client")]
+    fn push_visual(order: &mut Vec<VisualObject>, mut object: VisualObject) -> u32 {
+        if object.appearance.get_mask() && object.clip < MAX_CLIP {
+            object.clip += 1;
+            object.stencil = StencilPass::Mask;
+        }
+        let clip = object.clip;
+        order.push(object);
+        clip
+    }
+
+    /// Pushes the removal of the given object from the stencil buffer in case it is a mask.
+    #[cfg(feature = "client")]
+    fn pop_mask(order: &mut Vec<VisualObject>, mut object: VisualObject) {
+        if object.appearance.get_mask() && object.clip < MAX_CLIP {
+            object.clip += 1;
+            object.stencil = StencilPass::Unmask;
+            order.push(object);
+        }
+    }
+
+    /// Collects the combined transform of every child of this node recursively into the given map.
+    pub(crate) fn collect_transforms(
+        &self,
+        nodes: &Nodes,
+        parent: Transform,
+        transforms: &mut HashMap<usize, Transform>,
+    ) {
+        for child in self.children.iter().filter_map(|child| nodes.get(*child)) {
+            let transform = child.object.transform.combine(parent);
+            transforms.insert(child.object.id, transform);
+            child.collect_transforms(nodes, transform, transforms);
         }
     }
 
     /// Iterates to the last child to update all public position held by the Node.
-    pub fn update_children_position(&mut self, parent_pos: Transform) {
-        self.object.set_parent_transform(parent_pos);
-        for child in self.children.iter() {
-            child
-                .lock()
-                .update_children_position(self.object.public_transform());
+    pub fn update_children_position(
+        nodes: &mut Nodes,
+        handle: ObjectHandle,
+        parent_pos: Transform,
+    ) {
+        let Some(node) = nodes.get_mut(handle) else {
+            return;
+        };
+        node.object.set_parent_transform(parent_pos);
+        let public_transform = node.object.public_transform();
+        for index in 0.. {
+            let Some(&child) = nodes.get(handle).and_then(|node| node.children.get(index)) else {
+                break;
+            };
+            Self::update_children_position(nodes, child, public_transform);
         }
     }
 
-    /// Searches for the given object to be removed from the list of children.
-    ///
-    /// In case there is no child it will return an error.
-    pub fn remove_child(&mut self, object: &NObject) -> Result<()> {
-        let index = self
-            .children
-            .clone()
-            .into_iter()
-            .position(|x| Arc::ptr_eq(&x, object))
-            .ok_or(Error::msg("No child found"))?;
-        self.children.remove(index);
-        Ok(())
+    /// Moves the anchored children of the node recursively to their anchors, updating the parent transforms on the way.
+    #[cfg(feature = "client")]
+    pub(crate) fn update_anchors(
+        nodes: &mut Nodes,
+        handle: ObjectHandle,
+        space: &AnchorSpace,
+        root: bool,
+        #[cfg(feature = "physics")] physics: &mut Physics,
+    ) {
+        let Some(node) = nodes.get(handle) else {
+            return;
+        };
+        let parent = node.object.public_transform();
+        #[cfg(feature = "physics")]
+        let parent_local = node.object.transform;
+        for index in 0.. {
+            let Some(&child) = nodes.get(handle).and_then(|node| node.children.get(index)) else {
+                break;
+            };
+            let Some(node) = nodes.get_mut(child) else {
+                continue;
+            };
+            node.object.set_parent_transform(parent);
+            if let Some(anchor) = node.object.anchor {
+                node.object.transform.position =
+                    anchor.resolve(space, node.object.transform.size, (!root).then_some(parent));
+                // Moves the collider and rigid body along, the same way syncing the object does.
+                #[cfg(feature = "physics")]
+                {
+                    let object = &mut node.object;
+                    object.physics.update(
+                        &object.transform,
+                        parent_local,
+                        &mut node.rigid_body_parent,
+                        child.to_bits(),
+                        physics,
+                    );
+                }
+            }
+            Self::update_anchors(
+                nodes,
+                child,
+                space,
+                false,
+                #[cfg(feature = "physics")]
+                physics,
+            );
+        }
     }
 
-    /// Removes all children and their children recursively from the layer.
-    pub fn remove_children(
-        &mut self,
-        objects: &mut ObjectsMap,
-        #[cfg(feature = "physics")] rigid_bodies: &mut ObjectsMap,
+    /// Removes the node of the handle and all its children recursively from the layer and its physics,
+    /// moving them into the given list.
+    ///
+    /// The removed nodes should get dropped after unlocking the layer, as dropping the data attached to them can access it.
+    #[allow(unused_mut)]
+    pub fn remove_tree(
+        nodes: &mut Nodes,
+        handle: ObjectHandle,
+        #[cfg(feature = "physics")] rigid_bodies: &mut HashSet<ObjectHandle>,
+        removed: &mut Vec<Node>,
     ) {
+        let Some(mut node) = nodes.remove(handle) else {
+            return;
+        };
         #[cfg(feature = "physics")]
         {
-            let layer = self.object.layer().clone();
-            self.object.physics.remove(layer.physics());
+            if let Some(layer) = node.object.layer.clone() {
+                node.object.physics.remove(layer.physics());
+            }
+            rigid_bodies.remove(&handle);
         }
-        for child in self.children.iter() {
-            child.clone().lock().remove_children(
-                objects,
+        for child in node.children.iter() {
+            Self::remove_tree(
+                nodes,
+                *child,
                 #[cfg(feature = "physics")]
                 rigid_bodies,
+                removed,
             );
         }
-        objects.remove(self.object.id());
-        #[cfg(feature = "physics")]
-        rigid_bodies.remove(self.object.id());
-        self.children = vec![];
+        removed.push(node);
     }
 }
 
@@ -227,9 +430,19 @@ impl Node<Object> {
 pub struct NewObject {
     #[builder(setter(into), default)]
     pub transform: Transform,
+    /// An optional name to find this object with using [`Layer::find_by_name`].
+    #[builder(setter(into, strip_option), default)]
+    pub name: Option<String>,
+    /// Tags to find this object with using [`Layer::find_by_tag`].
+    #[builder(setter(into), default)]
+    pub tags: Vec<String>,
     #[builder(setter(into))]
     #[cfg(feature = "client")]
     pub appearance: Appearance,
+    /// Keeps the object at a point of its parent or the window, replacing the position of the transform.
+    #[builder(setter(into, strip_option), default)]
+    #[cfg(feature = "client")]
+    pub anchor: Option<Anchor>,
     #[builder(setter(skip))]
     #[cfg(feature = "physics")]
     pub(crate) physics: ObjectPhysics,
@@ -240,11 +453,14 @@ pub struct NewObject {
 pub struct Object {
     pub transform: Transform,
     parent_transform: Transform,
+    pub name: Option<String>,
+    pub tags: Vec<String>,
     #[cfg(feature = "client")]
     pub appearance: Appearance,
+    #[cfg(feature = "client")]
+    pub anchor: Option<Anchor>,
     id: usize,
-    node: WeakObject,
-    parent_node: Option<WeakObject>,
+    handle: ObjectHandle,
     #[cfg(feature = "physics")]
     pub(crate) physics: ObjectPhysics,
     layer: Option<Arc<Layer>>,
@@ -253,6 +469,7 @@ impl std::fmt::Debug for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Object")
             .field("id", &self.id)
+            .field("name", &self.name)
             .field("transform", &self.transform)
             .field("parent_transform", &self.parent_transform)
             .finish()
@@ -308,81 +525,78 @@ impl NewObject {
     ) -> Result<Object> {
         // Init ID of this object.
         let id = layer.increment_id();
+        #[cfg(feature = "client")]
+        let space = self.anchor.map(|_| layer.anchor_space());
 
-        #[cfg(feature = "physics")]
-        let mut rigid_body_parent;
-        let parent: NObject = if let Some(parent) = parent {
-            let Ok(parent) = parent.as_node() else {
-                return Err(anyhow!("Parent uninitialized"));
-            };
-            #[cfg(feature = "physics")]
-            {
-                rigid_body_parent = parent.lock().rigid_body_parent.clone();
-            }
-            parent.clone()
-        } else {
-            #[cfg(feature = "physics")]
-            {
-                rigid_body_parent = None;
-            }
-            layer.root.clone()
+        let mut nodes = layer.nodes.lock();
+        let parent = match parent {
+            Some(parent) if nodes.contains(parent.handle) => parent.handle,
+            Some(_) => return Err(anyhow!("Parent uninitialized")),
+            None => layer.root,
         };
+        let handle = nodes.next_handle();
+        let parent_node = nodes.get(parent).unwrap();
+
+        #[cfg(feature = "client")]
+        if let (Some(anchor), Some(space)) = (self.anchor, space) {
+            let parent_transform =
+                (parent != layer.root).then(|| parent_node.object.public_transform());
+            self.transform.position = anchor.resolve(&space, self.transform.size, parent_transform);
+        }
         // Updates the physics side and returns the parent position.
         #[cfg(feature = "physics")]
+        let mut rigid_body_parent = parent_node.rigid_body_parent;
+        #[cfg(feature = "physics")]
         let parent_transform = self
             .physics
             .update(
                 &self.transform,
-                &mut parent.lock(),
+                parent_node.object.transform,
                 &mut rigid_body_parent,
-                id as u128,
+                handle.to_bits(),
                 &mut layer.physics().lock(),
             )
-            .ok_or(Error::msg(
-                "Could not update the physics side of this object.",
-            ))?;
+            .ok_or(anyhow!("Could not update the physics side of this object."))?;
         #[cfg(not(feature = "physics"))]
-        let parent_transform = parent.lock().object.public_transform();
+        let parent_transform = parent_node.object.public_transform();
 
         // Make yourself to a node.
-        let node: NObject = std::sync::Arc::new_cyclic(|weak| {
-            let parent = Some(std::sync::Arc::downgrade(&parent));
-            let object = Object {
-                transform: self.transform,
-                parent_transform,
-                #[cfg(feature = "client")]
-                appearance: self.appearance,
-                id,
-                node: weak.clone(),
-                parent_node: parent.clone(),
-                #[cfg(feature = "physics")]
-                physics: self.physics,
-                layer: Some(layer.clone()),
-            };
-            Mutex::new(Node {
-                object,
-                // parent: parent.clone(),
-                #[cfg(feature = "physics")]
-                rigid_body_parent: rigid_body_parent.clone(),
-                children: vec![],
-            })
-        });
-
-        let object = node.lock().object.clone();
+        let object = Object {
+            transform: self.transform,
+            parent_transform,
+            name: self.name,
+            tags: self.tags,
+            #[cfg(feature = "client")]
+            appearance: self.appearance,
+            #[cfg(feature = "client")]
+            anchor: self.anchor,
+            id,
+            handle,
+            #[cfg(feature = "physics")]
+            physics: self.physics,
+            layer: Some(layer.clone()),
+        };
 
         // In case there is no rigid body roots make yourself one.
         #[cfg(feature = "physics")]
-        if let Some(value) = &rigid_body_parent {
-            if value.is_none() && object.physics.rigid_body.is_some() {
-                layer.rigid_body_roots().lock().insert(id, node.clone());
-            }
+        if rigid_body_parent == Some(None) && object.physics.rigid_body.is_some() {
+            layer.rigid_body_roots().lock().insert(handle);
         }
 
-        // Add yourself to the objects map.
-        layer.add_object(id, &node);
-
-        // Add yourself to the list of children of the parent.
-        parent.lock().children.push(node.clone());
+        // Add yourself to the objects of the layer and the list of children of the parent.
+        nodes.insert(Node {
+            object: object.clone(),
+            parent: Some(parent),
+            #[cfg(feature = "physics")]
+            rigid_body_parent,
+            #[cfg(feature = "physics")]
+            changes: 0,
+            children: vec![],
+            data: HashMap::new(),
+        });
+        nodes.get_mut(parent).unwrap().children.push(handle);
+        drop(nodes);
+        layer.count_allocated();
         Ok(object)
     }
 }
@@ -447,15 +661,18 @@ impl NewObject {
 }
 
 impl Object {
-    pub(crate) fn root(node: WeakObject) -> Self {
+    pub(crate) fn root() -> Self {
         Self {
             transform: Transform::default(),
             parent_transform: Transform::default(),
+            name: None,
+            tags: vec![],
             #[cfg(feature = "client")]
             appearance: Appearance::default(),
+            #[cfg(feature = "client")]
+            anchor: None,
             id: 0,
-            node,
-            parent_node: None,
+            handle: ObjectHandle::default(),
             #[cfg(feature = "physics")]
             physics: ObjectPhysics::default(),
             layer: None,
@@ -466,47 +683,99 @@ impl Object {
         self.layer.as_ref().unwrap()
     }
 
-    pub(crate) fn parent_node(&self) -> NObject {
-        self.parent_node.as_ref().unwrap().upgrade().unwrap()
+    /// Returns a copy of the object this one is inside of, or none in case it is at the root of its layer.
+    pub fn parent(&self) -> Option<Object> {
+        let layer = self.layer.as_ref()?;
+        let nodes = layer.nodes.lock();
+        let parent = nodes.get(self.handle)?.parent?;
+        (parent != layer.root).then(|| nodes.get(parent).unwrap().object.clone())
     }
 
     /// Returns false if the `remove` function was called on another instance of this object before.
     pub fn is_initialized(&self) -> bool {
-        self.as_node().is_ok()
+        self.layer
+            .as_ref()
+            .is_some_and(|layer| layer.nodes.lock().contains(self.handle))
+    }
+
+    /// Returns copies of the direct children of this object in drawing order.
+    ///
+    /// Returns an empty list in case the object is not initialized.
+    pub fn children(&self) -> Vec<Object> {
+        self.with_node(|node, nodes| {
+            node.children
+                .iter()
+                .filter_map(|child| Some(nodes.get(*child)?.object.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+    }
+
+    /// Returns a copy of the current state of this object in its layer.
+    pub(crate) fn current(&self) -> Result<Object, ObjectError> {
+        self.with_node(|node, _| node.object.clone())
+    }
+
+    /// Runs the closure with the node of this object and the nodes of its layer.
+    fn with_node<R>(&self, f: impl FnOnce(&Node, &Nodes) -> R) -> Result<R, ObjectError> {
+        let layer = self.layer.as_ref().ok_or(ObjectError::Uninit)?;
+        let nodes = layer.nodes.lock();
+        let node = nodes.get(self.handle).ok_or(ObjectError::Uninit)?;
+        Ok(f(node, &nodes))
+    }
+
+    /// Runs the closure with mutable access to the node of this object.
+    fn with_node_mut<R>(&self, f: impl FnOnce(&mut Node) -> R) -> Result<R, ObjectError> {
+        let layer = self.layer.as_ref().ok_or(ObjectError::Uninit)?;
+        let mut nodes = layer.nodes.lock();
+        let node = nodes.get_mut(self.handle).ok_or(ObjectError::Uninit)?;
+        Ok(f(node))
     }
 
     /// Removes the object from it's layer in case it is still initialized.
-    #[allow(unused_mut)]
-    pub fn remove(mut self) -> Result<NewObject> {
-        let layer = self.layer.as_ref().unwrap();
-        let mut map = layer.objects_map.lock();
+    pub fn remove(self) -> Result<NewObject> {
+        let layer = self.layer.as_ref().ok_or(ObjectError::Uninit)?;
+        let mut nodes = layer.nodes.lock();
         #[cfg(feature = "physics")]
         let mut rigid_bodies = layer.rigid_body_roots().lock();
-        let node = map.remove(&self.id).ok_or(ObjectError::Uninit)?;
-
-        #[cfg(feature = "physics")]
-        {
-            rigid_bodies.remove(&self.id);
-            // Remove self from the physics side.
-            self.physics.remove(layer.physics());
-        }
-
-        let mut object = node.lock();
-        object.remove_children(
-            &mut map,
+        let parent = nodes
+            .get(self.handle)
+            .ok_or(ObjectError::Uninit)?
+            .parent
+            .ok_or(ObjectError::NoParent)?;
+
+        // Removes self and the children from the layer and the physics side.
+        let mut removed = vec![];
+        Node::remove_tree(
+            &mut nodes,
+            self.handle,
             #[cfg(feature = "physics")]
             &mut rigid_bodies,
+            &mut removed,
         );
-
-        let mut parent_node = self.parent_node();
-        parent_node.lock().remove_child(&node)?;
+        if let Some(parent) = nodes.get_mut(parent) {
+            parent.children.retain(|child| *child != self.handle);
+        }
+        #[cfg(feature = "physics")]
+        drop(rigid_bodies);
+        drop(nodes);
+        layer.count_removed(removed.len());
+        drop(removed);
 
         Ok(NewObject {
             transform: self.transform,
+            name: self.name,
+            tags: self.tags,
             #[cfg(feature = "client")]
             appearance: self.appearance,
+            #[cfg(feature = "client")]
+            anchor: self.anchor,
             #[cfg(feature = "physics")]
-            physics: self.physics,
+            physics: ObjectPhysics {
+                collider_handle: None,
+                rigid_body_handle: None,
+                ..self.physics
+            },
         })
     }
 
@@ -514,8 +783,12 @@ impl Object {
     pub fn to_new(&self) -> NewObject {
         NewObject {
             transform: self.transform,
+            name: self.name.clone(),
+            tags: self.tags.clone(),
             #[cfg(feature = "client")]
             appearance: self.appearance.clone(),
+            #[cfg(feature = "client")]
+            anchor: self.anchor,
             #[cfg(feature = "physics")]
             physics: self.physics.clone(),
         }
@@ -524,6 +797,8 @@ impl Object {
     /// Copies the data from a `NewObject` into itself.
     pub fn copy_new(&mut self, object: NewObject) {
         self.transform = object.transform;
+        self.name = object.name;
+        self.tags = object.tags;
         #[cfg(feature = "physics")]
         {
             self.physics = object.physics;
@@ -531,6 +806,7 @@ impl Object {
         #[cfg(feature = "client")]
         {
             self.appearance = object.appearance;
+            self.anchor = object.anchor;
         }
     }
 
@@ -540,6 +816,11 @@ impl Object {
         self.transform.rotation = rotation;
     }
 
+    /// Returns true if this object has the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own == tag)
+    }
+
     /// Returns the public position where the object is going to be rendered.
     pub fn public_transform(&self) -> Transform {
         self.transform.combine(self.parent_transform)
@@ -562,19 +843,19 @@ impl Object {
         &self.id
     }
 
-    pub(crate) fn as_node(&self) -> Result<NObject, ObjectError> {
-        self.node.upgrade().ok_or(ObjectError::Uninit)
+    /// Returns the handle of the object in its layer, which stops resolving once the object gets removed.
+    pub fn handle(&self) -> ObjectHandle {
+        self.handle
     }
 
     /// Updates the object to match the object information located inside the system of the layer. Useful when having physics.
     pub fn update(&mut self) -> Result<(), ObjectError> {
         // receive
-        let node = self.as_node()?;
-        let object = &node.lock().object;
+        let object = self.current()?;
         self.transform = object.transform;
         #[cfg(feature = "client")]
         {
-            self.appearance = object.appearance().clone();
+            self.appearance = object.appearance;
         }
         Ok(())
     }
@@ -582,32 +863,86 @@ impl Object {
     /// Updates the object inside the layer system to match with this one. Useful when doing anything to the object and submitting it with this function.
     pub fn sync(&mut self) -> Result<(), ObjectError> {
         // send
-        // update public position of all children recursively
-        let node = self.as_node()?.clone();
+        let layer = self.layer.clone().ok_or(ObjectError::Uninit)?;
+        #[cfg(feature = "client")]
+        let space = self.anchor.map(|_| layer.anchor_space());
+        let mut nodes = layer.nodes.lock();
+        #[allow(unused_variables)]
+        let parent = nodes
+            .get(self.handle)
+            .ok_or(ObjectError::Uninit)?
+            .parent
+            .ok_or(ObjectError::NoParent)?;
+        #[cfg(feature = "client")]
+        if let (Some(anchor), Some(space)) = (self.anchor, space) {
+            let parent_transform = (parent != layer.root).then_some(self.parent_transform);
+            self.transform.position = anchor.resolve(&space, self.transform.size, parent_transform);
+        }
         #[cfg(feature = "physics")]
         {
-            let layer = self.layer().clone();
-            let parent_node = self.parent_node();
-            let mut parent = parent_node.lock();
-            let mut node = node.lock();
+            let parent_transform = nodes.get(parent).unwrap().object.transform;
+            let node = nodes.get_mut(self.handle).unwrap();
+            node.changes += 1;
             let mut physics = layer.physics().lock();
             self.parent_transform = self
                 .physics
                 .update(
                     &self.transform,
-                    &mut parent,
+                    parent_transform,
                     &mut node.rigid_body_parent,
-                    self.id as u128,
+                    self.handle.to_bits(),
                     &mut physics,
                 )
                 .unwrap();
         }
-        let mut node = node.lock();
-        node.update_children_position(self.parent_transform);
-        node.object = self.clone();
+        // update public position of all children recursively
+        nodes.get_mut(self.handle).unwrap().object = self.clone();
+        Node::update_children_position(&mut nodes, self.handle, self.parent_transform);
         Ok(())
     }
 
+    /// Attaches the given data to this object, replacing and returning the previous data of the same type.
+    ///
+    /// The data lives in the layer together with the object and gets dropped once the object gets removed.
+    pub fn insert<T: Any + Send + Sync>(&self, data: T) -> Result<Option<T>, ObjectError> {
+        let previous =
+            self.with_node_mut(|node| node.data.insert(TypeId::of::<T>(), Box::new(data)))?;
+        Ok(previous.and_then(|previous| previous.downcast().ok().map(|previous| *previous)))
+    }
+
+    /// Returns a copy of the data of the given type attached to this object.
+    pub fn get<T: Any + Send + Sync + Clone>(&self) -> Option<T> {
+        self.with(|data: &mut T| data.clone())
+    }
+
+    /// Runs the given closure with mutable access to the data of the given type attached to this object.
+    ///
+    /// Returns `None` in case the object has no data of this type or is not initialized.
+    ///
+    /// The objects of the layer are locked while the closure runs, so it must not access them.
+    pub fn with<T: Any + Send + Sync, R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.with_node_mut(|node| {
+            let data = node.data.get_mut(&TypeId::of::<T>())?.downcast_mut::<T>()?;
+            Some(f(data))
+        })
+        .ok()
+        .flatten()
+    }
+
+    /// Returns true if data of the given type is attached to this object.
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.with_node(|node, _| node.data.contains_key(&TypeId::of::<T>()))
+            .unwrap_or(false)
+    }
+
+    /// Removes the data of the given type from this object and returns it.
+    pub fn take<T: Any + Send + Sync>(&self) -> Option<T> {
+        let data = self
+            .with_node_mut(|node| node.data.remove(&TypeId::of::<T>()))
+            .ok()??;
+        data.downcast().ok().map(|data| *data)
+    }
+
     /// Moves an object to the given index in the children order of the object it is inside right now.
     ///
     /// It returns an error in case the given index is not covered.
@@ -682,6 +1017,135 @@ impl Object {
     pub fn set_local_collider_position(&mut self, pos: Vec2) {
         self.physics.local_collider_position = pos;
     }
+
+    /// Runs the closure on the rigid body inside the physics of the layer and keeps the local copy
+    /// of this object up to date, so the next sync does not revert the change.
+    fn modify_rigid_body<R>(
+        &mut self,
+        f: impl FnOnce(&mut RigidBody) -> R,
+    ) -> Result<R, ObjectError> {
+        let handle = self
+            .physics
+            .rigid_body_handle
+            .ok_or(ObjectError::NoRigidBody)?;
+        let layer = self.layer.clone().ok_or(ObjectError::Uninit)?;
+        let mut physics = layer.physics().lock();
+        let body = physics
+            .rigid_body_set
+            .get_mut(handle)
+            .ok_or(ObjectError::Uninit)?;
+        let mut rigid_body = RigidBody(body.clone());
+        let result = f(&mut rigid_body);
+        *body = rigid_body.0.clone();
+        self.physics.rigid_body = Some(rigid_body);
+        Ok(result)
+    }
+
+    /// Reads from the rigid body inside the physics of the layer.
+    fn read_rigid_body<R>(
+        &self,
+        f: impl FnOnce(&rapier2d::dynamics::RigidBody) -> R,
+    ) -> Result<R, ObjectError> {
+        let handle = self
+            .physics
+            .rigid_body_handle
+            .ok_or(ObjectError::NoRigidBody)?;
+        let layer = self.layer.as_ref().ok_or(ObjectError::Uninit)?;
+        let physics = layer.physics().lock();
+        physics
+            .rigid_body_set
+            .get(handle)
+            .map(f)
+            .ok_or(ObjectError::Uninit)
+    }
+
+    /// Moves the object to the given local position and rotation right away without sweeping through the space between.
+    ///
+    /// Unlike setting the transform and syncing, this only moves the rigid body or collider in the layer physics
+    /// and keeps its velocity and other state, so contacts get recalculated at the new position on the next step.
+    pub fn teleport(&mut self, position: Vec2, rotation: f32) -> Result<(), ObjectError> {
+        let layer = self.layer.clone().ok_or(ObjectError::Uninit)?;
+        let mut nodes = layer.nodes.lock();
+        if !nodes.contains(self.handle) {
+            return Err(ObjectError::Uninit);
+        }
+        self.transform.position = position;
+        self.transform.rotation = rotation;
+        let iso = self.public_transform().into();
+
+        let body = layer.physics().lock().teleport(
+            self.physics.rigid_body_handle,
+            self.physics.collider_handle,
+            iso,
+        );
+        if let Some(body) = body {
+            self.physics.rigid_body = Some(RigidBody(body));
+        }
+        let node = nodes.get_mut(self.handle).unwrap();
+        node.object.transform = self.transform;
+        node.object.physics.rigid_body = self.physics.rigid_body.clone();
+        Node::update_children_position(&mut nodes, self.handle, self.parent_transform);
+        Ok(())
+    }
+
+    /// Applies an impulse at the center of mass, changing the velocity right away.
+    pub fn apply_impulse(&mut self, impulse: Vec2) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.apply_impulse(impulse, true))
+    }
+
+    /// Applies an impulse at the given point in world space, changing the linear and angular velocity right away.
+    pub fn apply_impulse_at_point(
+        &mut self,
+        impulse: Vec2,
+        point: Vec2,
+    ) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.apply_impulse_at_point(impulse, point, true))
+    }
+
+    /// Applies an angular impulse, changing the angular velocity right away.
+    pub fn apply_torque_impulse(&mut self, torque_impulse: f32) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.apply_torque_impulse(torque_impulse, true))
+    }
+
+    /// Adds a force at the center of mass.
+    ///
+    /// Forces keep getting applied every physics step until they get removed with [`reset_forces`](Self::reset_forces).
+    pub fn apply_force(&mut self, force: Vec2) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.add_force(force, true))
+    }
+
+    /// Adds a torque, which keeps getting applied every physics step until [`reset_forces`](Self::reset_forces) gets called.
+    pub fn apply_torque(&mut self, torque: f32) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.add_torque(torque, true))
+    }
+
+    /// Removes all forces and torques added to this object.
+    pub fn reset_forces(&mut self) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| {
+            body.reset_forces(true);
+            body.reset_torques(true);
+        })
+    }
+
+    /// Returns the current linear velocity of this object.
+    pub fn velocity(&self) -> Result<Vec2, ObjectError> {
+        self.read_rigid_body(|body| vec2(body.linvel().x, body.linvel().y))
+    }
+
+    /// Sets the linear velocity of this object.
+    pub fn set_linear_velocity(&mut self, velocity: Vec2) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.set_linvel(velocity, true))
+    }
+
+    /// Returns the current angular velocity of this object.
+    pub fn angular_velocity(&self) -> Result<f32, ObjectError> {
+        self.read_rigid_body(|body| body.angvel())
+    }
+
+    /// Sets the angular velocity of this object.
+    pub fn set_angular_velocity(&mut self, velocity: f32) -> Result<(), ObjectError> {
+        self.modify_rigid_body(|body| body.set_angvel(velocity, true))
+    }
 }
 
 // Object based errors.
@@ -706,4 +1170,12 @@ pub enum ObjectError {
     /// The object you are trying to access is not initialized anymore.
     #[error("This object was removed from the objects list.")]
     Uninit,
+    /// The object does not have a rigid body in the physics of its layer.
+    #[cfg(feature = "physics")]
+    #[error("This object does not have a rigid body.")]
+    NoRigidBody,
+    /// The object does not have a collider in the physics of its layer.
+    #[cfg(feature = "physics")]
+    #[error("This object does not have a collider.")]
+    NoCollider,
 }
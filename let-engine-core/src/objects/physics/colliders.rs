@@ -1,8 +1,10 @@
 //! Wrapping of Rapiers colliders to be used with Let Engine and Glam.
 
+use super::AlphaOutline;
 use crate::objects::Transform;
 use glam::Vec2;
 use rapier2d::{parry::transformation::vhacd::VHACDParameters, prelude::*};
+use thiserror::Error;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -130,6 +132,138 @@ impl Collider {
     pub fn contact_force_event_threshold(&self) -> Real {
         self.0.contact_force_event_threshold()
     }
+
+    /// Returns true if the contact hooks of the layer get called for contacts of this collider.
+    pub fn contact_hooks(&self) -> bool {
+        self.0
+            .active_hooks()
+            .contains(ActiveHooks::MODIFY_SOLVER_CONTACTS)
+    }
+
+    /// Enables or disables calling the contact hooks of the layer for contacts of this collider.
+    ///
+    /// Required for one-way platforms and the contact hook of the layer.
+    pub fn set_contact_hooks(&mut self, enabled: bool) {
+        let mut hooks = self.0.active_hooks();
+        hooks.set(ActiveHooks::MODIFY_SOLVER_CONTACTS, enabled);
+        self.0.set_active_hooks(hooks);
+    }
+
+    /// Returns true if collision events get reported for this collider.
+    pub fn collision_events(&self) -> bool {
+        self.0
+            .active_events()
+            .contains(ActiveEvents::COLLISION_EVENTS)
+    }
+
+    /// Enables or disables reporting collision events of this collider to the
+    /// [collision event queue](crate::objects::scenes::Layer::drain_collision_events) of the layer.
+    pub fn set_collision_events(&mut self, enabled: bool) {
+        let mut events = self.0.active_events();
+        events.set(ActiveEvents::COLLISION_EVENTS, enabled);
+        self.0.set_active_events(events);
+    }
+
+    /// Returns the groups deciding which other colliders this collider interacts with.
+    pub fn collision_groups(&self) -> InteractionGroups {
+        self.0.collision_groups()
+    }
+
+    /// Sets the groups deciding which other colliders this collider interacts with.
+    pub fn set_collision_groups(&mut self, groups: InteractionGroups) {
+        self.0.set_collision_groups(groups)
+    }
+
+    /// Returns the shape of this collider.
+    pub fn shape(&self) -> Shape {
+        Shape(self.0.shared_shape().clone())
+    }
+
+    /// Sets the shape of this collider.
+    pub fn set_shape(&mut self, shape: Shape) {
+        self.0.set_shape(shape.0)
+    }
+
+    /// Returns the parts of this collider with their offsets relative to the collider.
+    ///
+    /// A collider without a compound shape consists of a single part without offset.
+    pub fn parts(&self) -> Vec<(Transform, Shape)> {
+        match self.0.shape().as_compound() {
+            Some(compound) => compound
+                .shapes()
+                .iter()
+                .map(|(iso, shape)| (iso_to_transform(iso), Shape(shape.clone())))
+                .collect(),
+            None => vec![(Transform::default(), self.shape())],
+        }
+    }
+
+    /// Adds a part with the given offset to this collider, turning its shape into a compound shape.
+    ///
+    /// Only the position and rotation of the transform are used. Returns the index of the new part.
+    pub fn add_part(&mut self, offset: Transform, shape: Shape) -> Result<usize, CompoundError> {
+        let mut parts = self.parts();
+        parts.push((offset, shape));
+        let index = parts.len() - 1;
+        self.set_parts(parts)?;
+        Ok(index)
+    }
+
+    /// Removes the part at the given index and returns it.
+    ///
+    /// Returns `None` in case the index is out of bounds or the part is the last one left,
+    /// as a collider always needs a shape.
+    pub fn remove_part(&mut self, index: usize) -> Option<(Transform, Shape)> {
+        let mut parts = self.parts();
+        if parts.len() <= 1 || index >= parts.len() {
+            return None;
+        }
+        let part = parts.remove(index);
+        // The remaining parts were part of a valid compound already.
+        self.set_parts(parts).ok()?;
+        Some(part)
+    }
+
+    /// Replaces all parts of this collider.
+    ///
+    /// A single part without offset gets used as plain shape instead of a compound shape.
+    pub fn set_parts(&mut self, parts: Vec<(Transform, Shape)>) -> Result<(), CompoundError> {
+        match parts.as_slice() {
+            [] => return Err(CompoundError::Empty),
+            [(offset, shape)] if offset.position == Vec2::ZERO && offset.rotation == 0.0 => {
+                self.0.set_shape(shape.0.clone());
+                return Ok(());
+            }
+            _ => (),
+        }
+        if parts
+            .iter()
+            .any(|(_, shape)| shape.0.as_composite_shape().is_some())
+        {
+            return Err(CompoundError::Composite);
+        }
+        self.0.set_shape(Shape::compound(parts).0);
+        Ok(())
+    }
+}
+
+/// Errors when editing the parts of a compound collider.
+#[derive(Error, Debug)]
+pub enum CompoundError {
+    /// Compound shapes need at least one part.
+    #[error("A compound collider needs at least one part.")]
+    Empty,
+    /// Compound shapes can not contain triangle meshes, polylines, heightfields or other compound shapes.
+    #[error("Compound colliders can not be made of composite shapes like compounds, triangle meshes or polylines.")]
+    Composite,
+}
+
+fn iso_to_transform(iso: &Isometry<Real>) -> Transform {
+    Transform {
+        position: Vec2::new(iso.translation.x, iso.translation.y),
+        rotation: iso.rotation.angle(),
+        ..Default::default()
+    }
 }
 
 pub struct ColliderBuilder {
@@ -347,6 +481,48 @@ impl ColliderBuilder {
         Self::new(Shape::from_shared_shape(shape))
     }
 
+    /// Initializes a collider builder following the outline of the opaque pixels of the given RGBA8 image.
+    ///
+    /// Returns `None` in case the image has no opaque pixels.
+    pub fn from_alpha(
+        pixels: &[u8],
+        dimensions: (u32, u32),
+        threshold: u8,
+        tolerance: Real,
+    ) -> Option<Self> {
+        Shape::from_alpha(pixels, dimensions, threshold, tolerance).map(Self::new)
+    }
+
+    /// Initializes a collider builder with the convex hull of the opaque pixels of the given RGBA8 image.
+    pub fn convex_hull_from_alpha(
+        pixels: &[u8],
+        dimensions: (u32, u32),
+        threshold: u8,
+        tolerance: Real,
+    ) -> Option<Self> {
+        Shape::convex_hull_from_alpha(pixels, dimensions, threshold, tolerance).map(Self::new)
+    }
+
+    /// Enables calling the contact hooks of the layer for contacts of the collider this builder will build.
+    pub fn contact_hooks(mut self, enabled: bool) -> Self {
+        self.active_hooks
+            .set(ActiveHooks::MODIFY_SOLVER_CONTACTS, enabled);
+        self
+    }
+
+    /// Enables reporting collision events of the collider this builder will build.
+    pub fn collision_events(mut self, enabled: bool) -> Self {
+        self.active_events
+            .set(ActiveEvents::COLLISION_EVENTS, enabled);
+        self
+    }
+
+    /// Sets the groups deciding which other colliders the collider this builder will build interacts with.
+    pub fn collision_groups(mut self, groups: InteractionGroups) -> Self {
+        self.collision_groups = groups;
+        self
+    }
+
     /// Sets whether or not the collider built by this builder is a sensor.
     pub fn sensor(mut self, is_sensor: bool) -> Self {
         self.is_sensor = is_sensor;
@@ -421,6 +597,7 @@ impl ColliderBuilder {
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone)]
 pub struct Shape(pub(crate) SharedShape);
 
 impl Shape {
@@ -661,6 +838,44 @@ impl Shape {
     pub fn from_shared_shape(shape: SharedShape) -> Self {
         Self(shape)
     }
+
+    /// Initializes a compound shape of convex parts following the outline of the opaque pixels of the given RGBA8 image.
+    ///
+    /// Pixels with an alpha value above `threshold` count as opaque. The outline gets simplified
+    /// with the given `tolerance` in pixels. The image is mapped to -1.0 to 1.0 on both axes like the square model.
+    ///
+    /// Returns `None` in case the image has no opaque pixels.
+    pub fn from_alpha(
+        pixels: &[u8],
+        dimensions: (u32, u32),
+        threshold: u8,
+        tolerance: Real,
+    ) -> Option<Self> {
+        let outline = AlphaOutline::from_rgba8(pixels, dimensions, threshold, tolerance);
+        (!outline.indices.is_empty())
+            .then(|| Self::convex_decomposition(&outline.vertices, &outline.indices))
+    }
+
+    /// Initializes a convex polygon shape enclosing the opaque pixels of the given RGBA8 image.
+    pub fn convex_hull_from_alpha(
+        pixels: &[u8],
+        dimensions: (u32, u32),
+        threshold: u8,
+        tolerance: Real,
+    ) -> Option<Self> {
+        let outline = AlphaOutline::from_rgba8(pixels, dimensions, threshold, tolerance);
+        Self::convex_hull(&outline.vertices)
+    }
+
+    /// Returns the minimum and maximum corner of the axis aligned box enclosing this shape at the given position and rotation.
+    pub fn aabb(&self, position: Vec2, rotation: Real) -> (Vec2, Vec2) {
+        let iso = Isometry::new(vector![position.x, position.y], rotation);
+        let aabb = self.0.compute_aabb(&iso);
+        (
+            Vec2::new(aabb.mins.x, aabb.mins.y),
+            Vec2::new(aabb.maxs.x, aabb.maxs.y),
+        )
+    }
 }
 
 impl Default for ColliderBuilder {
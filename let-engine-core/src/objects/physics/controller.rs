@@ -0,0 +1,209 @@
+//! Kinematic character controller moving objects through the physics of their layer.
+
+use glam::{vec2, Vec2};
+use rapier2d::control::{
+    CharacterAutostep, CharacterLength, EffectiveCharacterMovement, KinematicCharacterController,
+};
+use rapier2d::prelude::*;
+
+use super::CastFilter;
+use crate::objects::{Object, ObjectError, ObjectHandle};
+
+/// Settings for climbing over small obstacles like stairs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepOffset {
+    /// The maximum height of a step the character can climb.
+    pub max_height: Real,
+    /// The minimum free width required on top of a step to climb it.
+    pub min_width: Real,
+    /// Also climbs on top of dynamic bodies if true.
+    pub include_dynamic_bodies: bool,
+}
+
+impl Default for StepOffset {
+    fn default() -> Self {
+        Self {
+            max_height: 0.25,
+            min_width: 0.5,
+            include_dynamic_bodies: true,
+        }
+    }
+}
+
+/// The result of a single character controller movement.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CharacterMovement {
+    /// The translation that got applied to the object after resolving collisions.
+    pub translation: Vec2,
+    /// True if the character is standing on the ground after the movement.
+    pub grounded: bool,
+    /// True if the character is sliding down a slope too steep to climb.
+    pub sliding_down_slope: bool,
+    /// The handles of the objects the character ran into while moving.
+    pub collisions: Vec<ObjectHandle>,
+}
+
+/// A kinematic character controller implementing move-and-slide for objects with a collider.
+///
+/// Instead of getting pushed by the physics solver, the character gets moved by the desired velocity
+/// each tick, sliding along walls, climbing slopes and steps and snapping to the ground.
+/// Works best with a kinematic position based rigid body, which lets dynamic bodies react to the character.
+///
+/// # Usage
+/// ```ignore
+/// let mut controller = CharacterController::new();
+///
+/// // every tick, running at 60 ticks per second
+/// let velocity = vec2(input_x * speed, fall_speed);
+/// let movement = controller.move_object(&mut player, velocity, 1.0 / 60.0)?;
+/// if movement.grounded { /* allow jumping */ }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CharacterController {
+    /// The up direction of the character. Defaults to negative y, opposite of the default gravity.
+    pub up: Vec2,
+    /// The gap kept between the character and obstacles to avoid numerical issues.
+    pub offset: Real,
+    /// Slides along obstacles instead of stopping at them.
+    pub slide: bool,
+    /// The steepest slope in radians the character can walk up.
+    pub max_slope_climb_angle: Real,
+    /// The flattest slope in radians the character automatically slides down.
+    pub min_slope_slide_angle: Real,
+    /// Climbs steps if set.
+    pub step_offset: Option<StepOffset>,
+    /// Keeps the character on the ground when walking down slopes and steps up to this distance.
+    pub snap_to_ground: Option<Real>,
+    /// Decides which colliders block the character. The object itself is always ignored.
+    pub filter: CastFilter,
+    grounded: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            up: vec2(0.0, -1.0),
+            offset: 0.01,
+            slide: true,
+            max_slope_climb_angle: std::f32::consts::FRAC_PI_4,
+            min_slope_slide_angle: std::f32::consts::FRAC_PI_4,
+            step_offset: Some(StepOffset::default()),
+            snap_to_ground: Some(0.2),
+            filter: CastFilter::default(),
+            grounded: false,
+        }
+    }
+}
+
+impl CharacterController {
+    /// Creates a new character controller with default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if the character was standing on the ground after the last movement.
+    pub fn is_grounded(&self) -> bool {
+        self.grounded
+    }
+
+    /// Moves the object with the given velocity for `delta` seconds, resolving collisions with the other colliders of its layer.
+    ///
+    /// The object requires a collider. In case it has a kinematic position based rigid body,
+    /// the body gets moved to the new position on the next physics step. Otherwise it gets teleported there right away.
+    ///
+    /// The velocity is in world space, so child objects should not be rotated or scaled by their parents.
+    pub fn move_object(
+        &mut self,
+        object: &mut Object,
+        velocity: Vec2,
+        delta: f32,
+    ) -> Result<CharacterMovement, ObjectError> {
+        let collider_handle = object
+            .physics
+            .collider_handle
+            .ok_or(ObjectError::NoCollider)?;
+        let layer = object.layer.clone().ok_or(ObjectError::Uninit)?;
+
+        let mut collisions = vec![];
+        let movement = {
+            let mut physics = layer.physics().lock();
+            physics.update_query_pipeline();
+            let physics = &*physics;
+            let collider = physics
+                .collider_set
+                .get(collider_handle)
+                .ok_or(ObjectError::Uninit)?;
+
+            let mut filter_collisions = |collision: rapier2d::control::CharacterCollision| {
+                if let Some(other) = physics.collider_set.get(collision.handle) {
+                    let handle = ObjectHandle::from_bits(other.user_data);
+                    if !collisions.contains(&handle) {
+                        collisions.push(handle);
+                    }
+                }
+            };
+            self.filter.with_query_filter(|filter| {
+                let mut filter = filter.exclude_collider(collider_handle);
+                if let Some(body) = object.physics.rigid_body_handle {
+                    filter = filter.exclude_rigid_body(body);
+                }
+                self.rapier_controller().move_shape(
+                    delta,
+                    &physics.rigid_body_set,
+                    &physics.collider_set,
+                    &physics.query_pipeline,
+                    collider.shape(),
+                    collider.position(),
+                    vector![velocity.x, velocity.y] * delta,
+                    filter,
+                    &mut filter_collisions,
+                )
+            })
+        };
+        let EffectiveCharacterMovement {
+            translation,
+            grounded,
+            is_sliding_down_slope,
+        } = movement;
+        let translation = vec2(translation.x, translation.y);
+        self.grounded = grounded;
+
+        let position = object.transform.position + translation;
+        let kinematic = object
+            .read_rigid_body(|body| body.body_type() == RigidBodyType::KinematicPositionBased)
+            .unwrap_or(false);
+        if kinematic {
+            let mut transform = object.transform;
+            transform.position = position;
+            let next = transform.combine(object.parent_transform).into();
+            object.modify_rigid_body(|body| body.0.set_next_kinematic_position(next))?;
+            object.transform.position = position;
+        } else {
+            object.teleport(position, object.transform.rotation)?;
+        }
+
+        Ok(CharacterMovement {
+            translation,
+            grounded,
+            sliding_down_slope: is_sliding_down_slope,
+            collisions,
+        })
+    }
+
+    fn rapier_controller(&self) -> KinematicCharacterController {
+        KinematicCharacterController {
+            up: UnitVector::new_normalize(vector![self.up.x, self.up.y]),
+            offset: CharacterLength::Absolute(self.offset),
+            slide: self.slide,
+            autostep: self.step_offset.map(|step| CharacterAutostep {
+                max_height: CharacterLength::Absolute(step.max_height),
+                min_width: CharacterLength::Absolute(step.min_width),
+                include_dynamic_bodies: step.include_dynamic_bodies,
+            }),
+            max_slope_climb_angle: self.max_slope_climb_angle,
+            min_slope_slide_angle: self.min_slope_slide_angle,
+            snap_to_ground: self.snap_to_ground.map(CharacterLength::Absolute),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Collision and intersection events of a layer.
+
+use glam::{vec2, Vec2};
+use parking_lot::Mutex;
+use rapier2d::prelude::*;
+
+use crate::objects::ObjectHandle;
+
+/// Whether two objects started or stopped touching.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CollisionEventKind {
+    Started,
+    Stopped,
+}
+
+/// A collision or sensor intersection between two objects of a layer.
+///
+/// Only gets reported for colliders with collision events enabled,
+/// which collide according to their collision groups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub kind: CollisionEventKind,
+    /// The handles of the two objects, resolving with [`Layer::object`](crate::objects::scenes::Layer::object).
+    pub objects: (ObjectHandle, ObjectHandle),
+    /// True if one of the colliders is a sensor, making this an intersection without contact response.
+    pub sensor: bool,
+    /// The contact points in world space. Empty for stopped events and sensors.
+    pub points: Vec<Vec2>,
+    /// The contact normal pointing from the first object to the second one.
+    pub normal: Vec2,
+    /// The sum of the impulses applied at the contact points.
+    pub impulse: Real,
+}
+
+/// Collects the collision events of the physics steps of a layer.
+#[derive(Default)]
+pub(crate) struct EventCollector {
+    pub events: Mutex<Vec<CollisionEvent>>,
+}
+
+impl EventHandler for EventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        event: rapier2d::geometry::CollisionEvent,
+        contact_pair: Option<&ContactPair>,
+    ) {
+        let object = |handle| {
+            colliders
+                .get(handle)
+                .map_or_else(ObjectHandle::default, |collider| {
+                    ObjectHandle::from_bits(collider.user_data)
+                })
+        };
+        let kind = if event.started() {
+            CollisionEventKind::Started
+        } else {
+            CollisionEventKind::Stopped
+        };
+
+        let mut points = vec![];
+        let mut normal = Vec2::ZERO;
+        let mut impulse = 0.0;
+        if let Some(pair) = contact_pair {
+            for manifold in pair.manifolds.iter() {
+                normal = vec2(manifold.data.normal.x, manifold.data.normal.y);
+                points.extend(
+                    manifold
+                        .data
+                        .solver_contacts
+                        .iter()
+                        .map(|contact| vec2(contact.point.x, contact.point.y)),
+                );
+                impulse += manifold
+                    .points
+                    .iter()
+                    .map(|point| point.data.impulse)
+                    .sum::<Real>();
+            }
+        }
+
+        self.events.lock().push(CollisionEvent {
+            kind,
+            objects: (object(event.collider1()), object(event.collider2())),
+            sensor: event.sensor(),
+            points,
+            normal,
+            impulse,
+        });
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
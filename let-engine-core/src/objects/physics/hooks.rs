@@ -0,0 +1,112 @@
+//! Contact modification hooks of a layer.
+
+use std::{collections::HashMap, sync::Arc};
+
+use glam::{vec2, Vec2};
+use rapier2d::prelude::*;
+
+use crate::objects::ObjectHandle;
+
+/// The maximum angle in radians between a contact normal and the solid side of a one-way platform
+/// for the contact to count.
+const ONE_WAY_ALLOWED_ANGLE: Real = 0.1;
+
+pub(crate) type ContactHook = Arc<dyn Fn(&mut ContactContext) + Send + Sync>;
+
+/// The physics hooks of a layer, getting called for contacts of colliders with contact hooks enabled.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    /// The local normals of the solid sides of one-way platforms by the bits of the object handle.
+    pub one_way_platforms: HashMap<u128, Vec2>,
+    pub contact_hook: Option<ContactHook>,
+}
+
+impl PhysicsHooks for Hooks {
+    fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+        let user_data = |handle| {
+            context
+                .colliders
+                .get(handle)
+                .map_or(0, |collider: &rapier2d::geometry::Collider| {
+                    collider.user_data
+                })
+        };
+        let (object1, object2) = (user_data(context.collider1), user_data(context.collider2));
+
+        if let Some(normal) = self.one_way_platforms.get(&object1) {
+            context.update_as_oneway_platform(&vector![normal.x, normal.y], ONE_WAY_ALLOWED_ANGLE);
+        } else if let Some(normal) = self.one_way_platforms.get(&object2) {
+            context
+                .update_as_oneway_platform(&vector![-normal.x, -normal.y], ONE_WAY_ALLOWED_ANGLE);
+        }
+
+        if let Some(hook) = &self.contact_hook {
+            hook(&mut ContactContext {
+                object1: ObjectHandle::from_bits(object1),
+                object2: ObjectHandle::from_bits(object2),
+                context,
+            });
+        }
+    }
+}
+
+/// A contact between two objects that is about to be resolved by the physics solver.
+///
+/// Gets passed to the contact hook set with [`Layer::set_contact_hook`](crate::objects::scenes::Layer::set_contact_hook).
+pub struct ContactContext<'a, 'b> {
+    object1: ObjectHandle,
+    object2: ObjectHandle,
+    context: &'a mut ContactModificationContext<'b>,
+}
+
+impl ContactContext<'_, '_> {
+    /// Returns the handles of the two objects in contact.
+    pub fn objects(&self) -> (ObjectHandle, ObjectHandle) {
+        (self.object1, self.object2)
+    }
+
+    /// Returns the contact normal pointing from the first object towards the second one.
+    pub fn normal(&self) -> Vec2 {
+        vec2(self.context.normal.x, self.context.normal.y)
+    }
+
+    /// Returns the contact points in world space.
+    pub fn points(&self) -> Vec<Vec2> {
+        self.context
+            .solver_contacts
+            .iter()
+            .map(|contact| vec2(contact.point.x, contact.point.y))
+            .collect()
+    }
+
+    /// Returns true if there are contact points left that the solver is going to resolve.
+    pub fn is_enabled(&self) -> bool {
+        !self.context.solver_contacts.is_empty()
+    }
+
+    /// Ignores this contact for the current step, letting the objects pass through each other.
+    pub fn disable(&mut self) {
+        self.context.solver_contacts.clear();
+    }
+
+    /// Overrides the friction coefficient of all contact points.
+    pub fn set_friction(&mut self, friction: Real) {
+        for contact in self.context.solver_contacts.iter_mut() {
+            contact.friction = friction;
+        }
+    }
+
+    /// Overrides the restitution coefficient of all contact points.
+    pub fn set_restitution(&mut self, restitution: Real) {
+        for contact in self.context.solver_contacts.iter_mut() {
+            contact.restitution = restitution;
+        }
+    }
+
+    /// Sets the velocity the surface of the first object moves at relative to the second, like a conveyor belt.
+    pub fn set_tangent_velocity(&mut self, velocity: Vec2) {
+        for contact in self.context.solver_contacts.iter_mut() {
+            contact.tangent_velocity = vector![velocity.x, velocity.y];
+        }
+    }
+}
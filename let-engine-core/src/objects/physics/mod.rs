@@ -6,19 +6,154 @@ use nalgebra::Isometry2;
 use parking_lot::Mutex;
 pub use rapier2d::parry::transformation::vhacd::VHACDParameters;
 use rapier2d::prelude::*;
+use std::num::NonZeroUsize;
 
 mod colliders;
+mod controller;
+mod events;
+mod hooks;
 pub mod joints;
+mod outline;
 mod rigid_bodies;
-pub use colliders::{Collider, ColliderBuilder, Shape};
+mod triggers;
+pub use colliders::{Collider, ColliderBuilder, CompoundError, Shape};
+pub use controller::{CharacterController, CharacterMovement, StepOffset};
+pub(crate) use events::EventCollector;
+pub use events::{CollisionEvent, CollisionEventKind};
+pub use hooks::ContactContext;
+pub(crate) use hooks::{ContactHook, Hooks};
+pub use outline::AlphaOutline;
 pub use rigid_bodies::{NoRigidBodyError, RigidBody, RigidBodyBuilder};
+pub use triggers::{TriggerEvent, TriggerZone};
+
+pub use rapier2d::geometry::{Group, InteractionGroups};
 
 pub use rapier2d::dynamics::{
     CoefficientCombineRule, ImpulseJointHandle, IntegrationParameters, LockedAxes,
     RigidBodyActivation, RigidBodyType,
 };
 
-use super::{Node, Object};
+use super::{Object, ObjectHandle};
+
+/// Options deciding which colliders a ray or shape cast can hit.
+#[derive(Clone, Debug)]
+pub struct CastFilter {
+    /// Only hit colliders interacting with these groups.
+    pub groups: Option<InteractionGroups>,
+    /// Handles of objects to ignore, like the object doing the cast.
+    pub exclude: Vec<ObjectHandle>,
+    /// Ignores sensor colliders.
+    pub exclude_sensors: bool,
+    /// Hits a collider right at the origin in case the cast starts inside of it.
+    /// Otherwise the cast hits the boundary of the collider from the inside.
+    pub solid: bool,
+}
+
+impl Default for CastFilter {
+    fn default() -> Self {
+        Self {
+            groups: None,
+            exclude: vec![],
+            exclude_sensors: false,
+            solid: true,
+        }
+    }
+}
+
+impl CastFilter {
+    /// Returns a filter hitting every collider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ignores the given object.
+    pub fn exclude(mut self, object: &Object) -> Self {
+        self.exclude.push(object.handle());
+        self
+    }
+
+    /// Only hits colliders interacting with the given groups.
+    pub fn groups(mut self, groups: InteractionGroups) -> Self {
+        self.groups = Some(groups);
+        self
+    }
+
+    /// Ignores sensor colliders.
+    pub fn exclude_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+
+    /// Sets whether casts starting inside a collider hit it right at the origin.
+    pub fn solid(mut self, solid: bool) -> Self {
+        self.solid = solid;
+        self
+    }
+
+    /// Calls the given closure with the equivalent rapier query filter.
+    pub(crate) fn with_query_filter<R>(&self, f: impl FnOnce(QueryFilter) -> R) -> R {
+        let predicate = |_, collider: &rapier2d::geometry::Collider| {
+            !self
+                .exclude
+                .contains(&ObjectHandle::from_bits(collider.user_data))
+        };
+        let mut filter = QueryFilter::new().predicate(&predicate);
+        if let Some(groups) = self.groups {
+            filter = filter.groups(groups);
+        }
+        if self.exclude_sensors {
+            filter = filter.exclude_sensors();
+        }
+        f(filter)
+    }
+}
+
+/// The first hit of a ray or shape cast.
+#[derive(Clone, Debug)]
+pub struct CastHit {
+    /// The object that got hit.
+    pub object: Object,
+    /// The point of impact in world space.
+    pub point: Vec2,
+    /// The surface normal of the hit object at the point of impact.
+    pub normal: Vec2,
+    /// The distance travelled along the cast in multiples of the direction vector.
+    pub time_of_impact: Real,
+}
+
+/// The simulation settings of the physics of a layer, adjustable at runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicsSettings {
+    /// The gravity applied to all dynamic bodies.
+    pub gravity: Vec2,
+    /// The time in seconds simulated by a single physics step.
+    ///
+    /// Defaults to 1/60 seconds and should match the tick rate for the simulation to run in real time.
+    pub timestep: Real,
+    /// The maximum number of continuous collision detection substeps per step.
+    ///
+    /// Bodies need CCD enabled for this to prevent them from tunneling through thin colliders at high speeds.
+    /// 0 disables continuous collision detection for the whole layer.
+    pub ccd_substeps: usize,
+    /// The number of solver iterations per step. Higher values make stacks and joints more stable.
+    pub solver_iterations: NonZeroUsize,
+    /// The number of internal iterations done within each solver iteration.
+    pub internal_solver_iterations: usize,
+}
+
+impl PhysicsSettings {
+    /// Whether the physics of every layer produce bit for bit the same results on every platform.
+    ///
+    /// This is only the case with the `determinism` feature, which games using lockstep networking should enable.
+    /// On the same platform the physics are always deterministic, as long as objects get added in the same order.
+    pub const DETERMINISTIC: bool = cfg!(feature = "determinism");
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Physics::new().settings()
+    }
+}
 
 /// Physics stuff.
 pub(crate) struct Physics {
@@ -36,6 +171,40 @@ pub(crate) struct Physics {
 
     pub query_pipeline: QueryPipeline,
     pub query_pipeline_out_of_date: bool,
+
+    pub stats: PhysicsStats,
+    pub hooks: Hooks,
+    pub events: EventCollector,
+}
+
+/// Counters of the latest physics step of a layer for tuning physics heavy scenes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PhysicsStats {
+    /// The amount of rigid bodies in the layer.
+    pub bodies: usize,
+    /// The amount of dynamic and kinematic rigid bodies that are not sleeping.
+    pub active_bodies: usize,
+    /// The amount of colliders in the layer.
+    pub colliders: usize,
+    /// The amount of collider pairs with touching contacts.
+    pub contacts: usize,
+    /// The amount of groups of awake bodies touching each other.
+    pub islands: usize,
+    /// The time the latest step took.
+    pub step_time: std::time::Duration,
+}
+
+impl std::iter::Sum for PhysicsStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |total, stats| Self {
+            bodies: total.bodies + stats.bodies,
+            active_bodies: total.active_bodies + stats.active_bodies,
+            colliders: total.colliders + stats.colliders,
+            contacts: total.contacts + stats.contacts,
+            islands: total.islands + stats.islands,
+            step_time: total.step_time + stats.step_time,
+        })
+    }
 }
 
 impl Default for Physics {
@@ -59,10 +228,34 @@ impl Physics {
             ccd_solver: CCDSolver::new(),
             query_pipeline: QueryPipeline::new(),
             query_pipeline_out_of_date: false,
+            stats: PhysicsStats::default(),
+            hooks: Hooks::default(),
+            events: EventCollector::default(),
+        }
+    }
+    pub fn settings(&self) -> PhysicsSettings {
+        let parameters = &self.integration_parameters;
+        PhysicsSettings {
+            gravity: Vec2::new(self.gravity.x, self.gravity.y),
+            timestep: parameters.dt,
+            ccd_substeps: parameters.max_ccd_substeps,
+            solver_iterations: parameters.num_solver_iterations,
+            internal_solver_iterations: parameters.num_internal_pgs_iterations,
         }
     }
+
+    pub fn set_settings(&mut self, settings: PhysicsSettings) {
+        self.gravity = vector![settings.gravity.x, settings.gravity.y];
+        let parameters = &mut self.integration_parameters;
+        parameters.dt = settings.timestep;
+        parameters.max_ccd_substeps = settings.ccd_substeps;
+        parameters.num_solver_iterations = settings.solver_iterations;
+        parameters.num_internal_pgs_iterations = settings.internal_solver_iterations;
+    }
+
     /// Physics iteration.
     pub fn step(&mut self, physics_pipeline: &mut PhysicsPipeline) {
+        let start = std::time::Instant::now();
         physics_pipeline.step(
             &self.gravity,
             &self.integration_parameters,
@@ -75,13 +268,132 @@ impl Physics {
             &mut self.multibody_joint_set,
             &mut self.ccd_solver,
             None, // Doesn't update that well with the query pipeline in here.
-            &(),
-            &(),
+            &self.hooks,
+            &self.events,
         );
         // So it updates here.
         self.query_pipeline.update(&self.collider_set);
         self.query_pipeline_out_of_date = false;
+        self.update_stats(start.elapsed());
     }
+
+    /// Counts the bodies, contacts and islands after a step.
+    fn update_stats(&mut self, step_time: std::time::Duration) {
+        let active: Vec<RigidBodyHandle> = self
+            .island_manager
+            .active_dynamic_bodies()
+            .iter()
+            .chain(self.island_manager.active_kinematic_bodies())
+            .copied()
+            .collect();
+
+        // Union the awake bodies touching each other to count the islands.
+        let mut islands: std::collections::HashMap<RigidBodyHandle, RigidBodyHandle> =
+            active.iter().map(|handle| (*handle, *handle)).collect();
+        fn find(
+            islands: &mut std::collections::HashMap<RigidBodyHandle, RigidBodyHandle>,
+            handle: RigidBodyHandle,
+        ) -> RigidBodyHandle {
+            let parent = islands[&handle];
+            if parent == handle {
+                return handle;
+            }
+            let root = find(islands, parent);
+            islands.insert(handle, root);
+            root
+        }
+
+        let mut contacts = 0;
+        for pair in self.narrow_phase.contact_pairs() {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+            contacts += 1;
+            let parents = (
+                self.collider_set
+                    .get(pair.collider1)
+                    .and_then(|collider| collider.parent()),
+                self.collider_set
+                    .get(pair.collider2)
+                    .and_then(|collider| collider.parent()),
+            );
+            if let (Some(a), Some(b)) = parents {
+                if islands.contains_key(&a) && islands.contains_key(&b) {
+                    let (a, b) = (find(&mut islands, a), find(&mut islands, b));
+                    islands.insert(a, b);
+                }
+            }
+        }
+        let island_count = active
+            .iter()
+            .filter(|handle| find(&mut islands, **handle) == **handle)
+            .count();
+
+        self.stats = PhysicsStats {
+            bodies: self.rigid_body_set.len(),
+            active_bodies: active.len(),
+            colliders: self.collider_set.len(),
+            contacts,
+            islands: island_count,
+            step_time,
+        };
+    }
+    /// Moves a rigid body or a parentless collider to the given position right away, including attached colliders.
+    ///
+    /// Returns the moved rigid body in case there is one.
+    pub fn teleport(
+        &mut self,
+        rigid_body_handle: Option<RigidBodyHandle>,
+        collider_handle: Option<ColliderHandle>,
+        position: Isometry<Real>,
+    ) -> Option<rapier2d::dynamics::RigidBody> {
+        self.query_pipeline_out_of_date = true;
+        if let Some(body) = rigid_body_handle.and_then(|handle| self.rigid_body_set.get_mut(handle))
+        {
+            body.set_position(position, true);
+            // Move the colliders along, so queries see the new position before the next step.
+            for handle in body.colliders() {
+                if let Some(collider) = self.collider_set.get_mut(*handle) {
+                    let local = collider
+                        .position_wrt_parent()
+                        .copied()
+                        .unwrap_or_else(Isometry::identity);
+                    collider.set_position(position * local);
+                }
+            }
+            return Some(body.clone());
+        }
+        if let Some(collider) = collider_handle.and_then(|handle| self.collider_set.get_mut(handle))
+        {
+            collider.set_position(position);
+        }
+        None
+    }
+
+    /// Enables or disables the simulation of all non fixed rigid bodies matching the given position predicate.
+    ///
+    /// Returns the number of bodies that changed their state.
+    pub fn set_bodies_enabled(&mut self, filter: impl Fn(Vec2) -> Option<bool>) -> usize {
+        let mut changed = 0;
+        for (_, body) in self.rigid_body_set.iter_mut() {
+            if body.is_fixed() {
+                continue;
+            }
+            let position = body.translation();
+            let Some(enabled) = filter(Vec2::new(position.x, position.y)) else {
+                continue;
+            };
+            if body.is_enabled() != enabled {
+                body.set_enabled(enabled);
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.query_pipeline_out_of_date = true;
+        }
+        changed
+    }
+
     /// Updates the query pipeline if it requires one after someone manually moved a collider.
     pub fn update_query_pipeline(&mut self) {
         if self.query_pipeline_out_of_date {
@@ -168,12 +480,11 @@ impl ObjectPhysics {
     pub fn update(
         &mut self,
         transform: &Transform,
-        parent: &mut Node<Object>,
+        parent_transform: Transform,
         rigid_body_object: &mut crate::objects::RigidBodyParent,
         id: u128,
         physics: &mut Physics,
     ) -> Option<Transform> {
-        let parent_transform = parent.object.transform;
         let public_transform = transform.combine(parent_transform);
 
         physics.query_pipeline_out_of_date = true;
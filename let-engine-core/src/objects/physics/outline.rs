@@ -0,0 +1,185 @@
+//! Outline tracing of sprite alpha channels for collider generation.
+
+use std::collections::HashMap;
+
+use glam::{vec2, Vec2};
+
+/// The outline of the opaque parts of an image as closed polylines.
+///
+/// The vertices are in object space, where the image covers -1.0 to 1.0 on both axes
+/// like the default square model.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlphaOutline {
+    pub vertices: Vec<Vec2>,
+    pub indices: Vec<[u32; 2]>,
+}
+
+impl AlphaOutline {
+    /// Traces the outlines of all pixels with an alpha value above `threshold` in the given RGBA8 pixel data.
+    ///
+    /// The traced outlines get simplified so no removed pixel corner is further than `tolerance`
+    /// pixels away from the resulting lines. A tolerance around 1.0 to 2.0 usually works well for sprites.
+    pub fn from_rgba8(
+        pixels: &[u8],
+        dimensions: (u32, u32),
+        threshold: u8,
+        tolerance: f32,
+    ) -> Self {
+        let (width, height) = (dimensions.0 as i32, dimensions.1 as i32);
+        let solid = |x: i32, y: i32| {
+            x >= 0
+                && y >= 0
+                && x < width
+                && y < height
+                && pixels
+                    .get(((y * width + x) * 4 + 3) as usize)
+                    .is_some_and(|alpha| *alpha > threshold)
+        };
+
+        // Collect the pixel borders between solid and empty pixels, oriented with the solid side on the same side.
+        let mut edges: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+        let mut edge_count = 0;
+        for y in 0..height {
+            for x in 0..width {
+                if !solid(x, y) {
+                    continue;
+                }
+                let mut add = |from: (i32, i32), to: (i32, i32)| {
+                    edges.entry(from).or_default().push(to);
+                    edge_count += 1;
+                };
+                if !solid(x, y - 1) {
+                    add((x, y), (x + 1, y));
+                }
+                if !solid(x + 1, y) {
+                    add((x + 1, y), (x + 1, y + 1));
+                }
+                if !solid(x, y + 1) {
+                    add((x + 1, y + 1), (x, y + 1));
+                }
+                if !solid(x - 1, y) {
+                    add((x, y + 1), (x, y));
+                }
+            }
+        }
+
+        // Chain the borders to closed loops.
+        let mut loops: Vec<Vec<Vec2>> = vec![];
+        while edge_count > 0 {
+            let Some(&start) = edges.keys().next() else {
+                break;
+            };
+            let mut contour = vec![];
+            let mut current = start;
+            while let Some(next) = edges.get_mut(&current).and_then(|targets| targets.pop()) {
+                edge_count -= 1;
+                if edges
+                    .get(&current)
+                    .is_some_and(|targets| targets.is_empty())
+                {
+                    edges.remove(&current);
+                }
+                contour.push(vec2(current.0 as f32, current.1 as f32));
+                current = next;
+                if current == start {
+                    break;
+                }
+            }
+            edges.retain(|_, targets| !targets.is_empty());
+            let contour = simplify_loop(&remove_collinear(contour), tolerance);
+            if contour.len() >= 3 {
+                loops.push(contour);
+            }
+        }
+
+        // Map pixel corners to object space.
+        let size = vec2(width as f32, height as f32);
+        let mut outline = Self::default();
+        for contour in loops {
+            let offset = outline.vertices.len() as u32;
+            let count = contour.len() as u32;
+            outline
+                .vertices
+                .extend(contour.into_iter().map(|point| point / size * 2.0 - 1.0));
+            outline
+                .indices
+                .extend((0..count).map(|i| [offset + i, offset + (i + 1) % count]));
+        }
+        outline
+    }
+}
+
+/// Removes the points in the middle of straight lines of a closed loop.
+fn remove_collinear(contour: Vec<Vec2>) -> Vec<Vec2> {
+    let len = contour.len();
+    if len < 3 {
+        return contour;
+    }
+    (0..len)
+        .filter(|&i| {
+            let previous = contour[(i + len - 1) % len];
+            let next = contour[(i + 1) % len];
+            (contour[i] - previous).perp_dot(next - contour[i]) != 0.0
+        })
+        .map(|i| contour[i])
+        .collect()
+}
+
+/// Simplifies a closed loop using the Ramer Douglas Peucker algorithm.
+fn simplify_loop(contour: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    if contour.len() < 4 || tolerance <= 0.0 {
+        return contour.to_vec();
+    }
+    // Split the loop at the point furthest away from the first one.
+    let far = (1..contour.len())
+        .max_by(|a, b| {
+            contour[*a]
+                .distance_squared(contour[0])
+                .total_cmp(&contour[*b].distance_squared(contour[0]))
+        })
+        .unwrap_or(1);
+    let mut first = contour[..=far].to_vec();
+    let mut second = contour[far..].to_vec();
+    second.push(contour[0]);
+
+    first = simplify(&first, tolerance);
+    second = simplify(&second, tolerance);
+    // Drop the duplicated split points.
+    first.pop();
+    second.pop();
+    first.extend(second);
+    first
+}
+
+fn simplify(points: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    let (Some(&start), Some(&end)) = (points.first(), points.last()) else {
+        return vec![];
+    };
+    let direction = end - start;
+    let length = direction.length();
+    let distance = |point: Vec2| {
+        if length == 0.0 {
+            point.distance(start)
+        } else {
+            direction.perp_dot(point - start).abs() / length
+        }
+    };
+    let furthest = points
+        .iter()
+        .enumerate()
+        .skip(1)
+        .take(points.len().saturating_sub(2))
+        .map(|(i, point)| (i, distance(*point)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+    match furthest {
+        Some((index, max)) if max > tolerance => {
+            let mut left = simplify(&points[..=index], tolerance);
+            let right = simplify(&points[index..], tolerance);
+            left.pop();
+            left.extend(right);
+            left
+        }
+        _ => vec![start, end],
+    }
+}
@@ -0,0 +1,119 @@
+//! Sensor areas keeping track of the objects inside of them.
+
+use rapier2d::prelude::*;
+
+use crate::objects::{Object, ObjectError, ObjectHandle};
+
+/// An object entering or leaving a trigger zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TriggerEvent {
+    /// The object with this handle entered the zone.
+    Entered(ObjectHandle),
+    /// The object with this handle left the zone or got removed.
+    Exited(ObjectHandle),
+}
+
+/// A sensor collider keeping track of the objects inside of it, like doors, pickups or checkpoints.
+///
+/// The zone does not need a rigid body and detects every collider of its layer,
+/// including fixed and kinematic ones, according to its collision groups.
+///
+/// # Usage
+/// ```ignore
+/// let mut checkpoint = TriggerZone::new(checkpoint_object)?;
+///
+/// // every tick
+/// for event in checkpoint.update()? {
+///     if let TriggerEvent::Entered(handle) = event {
+///         // save the game
+///     }
+/// }
+/// ```
+pub struct TriggerZone {
+    object: Object,
+    inside: Vec<ObjectHandle>,
+}
+
+impl TriggerZone {
+    /// Turns the collider of the given initialized object into a sensor tracking the objects inside of it.
+    pub fn new(mut object: Object) -> Result<Self, ObjectError> {
+        let collider = object.collider_mut().ok_or(ObjectError::NoCollider)?;
+        collider.set_sensor(true);
+        collider
+            .0
+            .set_active_collision_types(ActiveCollisionTypes::all());
+        object.sync()?;
+        Ok(Self {
+            object,
+            inside: vec![],
+        })
+    }
+
+    /// Returns the object of this zone.
+    pub fn object(&self) -> &Object {
+        &self.object
+    }
+
+    /// Returns a mutable reference to the object of this zone, for example to move it around and sync it.
+    pub fn object_mut(&mut self) -> &mut Object {
+        &mut self.object
+    }
+
+    /// Returns the handles of the objects inside this zone as of the last update, sorted.
+    pub fn objects(&self) -> &[ObjectHandle] {
+        &self.inside
+    }
+
+    /// Returns true if the object with the given handle was inside this zone on the last update.
+    pub fn contains(&self, handle: ObjectHandle) -> bool {
+        self.inside.binary_search(&handle).is_ok()
+    }
+
+    /// Compares the objects inside this zone after the latest physics step to the ones of the last update
+    /// and returns which ones entered and left since.
+    pub fn update(&mut self) -> Result<Vec<TriggerEvent>, ObjectError> {
+        let handle = self
+            .object
+            .physics
+            .collider_handle
+            .ok_or(ObjectError::NoCollider)?;
+        let layer = self.object.layer.clone().ok_or(ObjectError::Uninit)?;
+
+        let mut current: Vec<ObjectHandle> = {
+            let physics = layer.physics().lock();
+            physics
+                .narrow_phase
+                .intersection_pairs_with(handle)
+                .filter(|(_, _, intersecting)| *intersecting)
+                .filter_map(|(collider1, collider2, _)| {
+                    let other = if collider1 == handle {
+                        collider2
+                    } else {
+                        collider1
+                    };
+                    physics
+                        .collider_set
+                        .get(other)
+                        .map(|collider| ObjectHandle::from_bits(collider.user_data))
+                })
+                .collect()
+        };
+        current.sort_unstable();
+        current.dedup();
+
+        let mut events: Vec<TriggerEvent> = self
+            .inside
+            .iter()
+            .filter(|handle| current.binary_search(handle).is_err())
+            .map(|handle| TriggerEvent::Exited(*handle))
+            .collect();
+        events.extend(
+            current
+                .iter()
+                .filter(|handle| self.inside.binary_search(handle).is_err())
+                .map(|handle| TriggerEvent::Entered(*handle)),
+        );
+        self.inside = current;
+        Ok(events)
+    }
+}
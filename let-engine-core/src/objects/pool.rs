@@ -0,0 +1,204 @@
+//! Recycling objects that get spawned and removed all the time.
+
+use std::{
+    any::Any,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+
+use super::{scenes::Layer, NewObject, NoLayerError, Object, ObjectError};
+
+/// Marks an object resting in a pool.
+struct Idle;
+
+pub(crate) struct PoolInner {
+    layer: Weak<Layer>,
+    capacity: AtomicUsize,
+    idle: Mutex<Vec<Object>>,
+}
+
+impl PoolInner {
+    pub fn new(layer: &Arc<Layer>, capacity: usize) -> Self {
+        Self {
+            layer: Arc::downgrade(layer),
+            capacity: AtomicUsize::new(capacity),
+            idle: Mutex::new(Vec::with_capacity(capacity)),
+        }
+    }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        self.capacity.store(capacity, Ordering::Release);
+    }
+
+    pub fn len(&self) -> usize {
+        self.idle.lock().len()
+    }
+
+    /// Drops the resting objects, which keep their layer alive.
+    pub fn drain(&self) {
+        self.idle.lock().clear();
+    }
+}
+
+/// A pool of objects in a layer, keeping removed objects around to spawn them again,
+/// so their nodes and physics handles do not get allocated again.
+///
+/// Every object spawned by the pool carries data of the type `T`, which also tells apart the pools of a layer.
+///
+/// Objects released back into the pool stay in the layer, invisible and with disabled physics,
+/// until they get spawned again or the pool is full. Their children stay as they are.
+/// Removing the layer from the scene empties its pools.
+///
+/// # Usage
+/// ```ignore
+/// struct Bullet { damage: u32 }
+///
+/// let bullets = layer.pool::<Bullet>(512);
+/// let bullet = bullets.spawn(bullet_template.clone(), Bullet { damage: 3 })?;
+///
+/// // on hit
+/// bullets.release(bullet)?;
+/// ```
+pub struct ObjectPool<T> {
+    inner: Arc<PoolInner>,
+    _data: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for ObjectPool<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _data: PhantomData,
+        }
+    }
+}
+
+impl<T: Any + Send + Sync> ObjectPool<T> {
+    pub(crate) fn new(inner: Arc<PoolInner>) -> Self {
+        Self {
+            inner,
+            _data: PhantomData,
+        }
+    }
+
+    /// Returns the maximum number of objects waiting in this pool.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of objects waiting in this pool to get spawned again.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns true if no objects are waiting in this pool.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fills the pool with released copies of the given object up to the given count,
+    /// moving the allocations to a moment like a loading screen.
+    pub fn prewarm(&self, count: usize, object: &NewObject) -> Result<()> {
+        let layer = self.inner.layer.upgrade().ok_or(NoLayerError)?;
+        let count = count.min(self.capacity());
+        while self.len() < count {
+            let object = object.clone().init(&layer)?;
+            self.rest(object)?;
+        }
+        Ok(())
+    }
+
+    /// Spawns the given object with the given data at the root of the layer of this pool,
+    /// reusing a released object in case there is one.
+    pub fn spawn(&self, object: NewObject, data: T) -> Result<Object> {
+        let Some(mut reused) = self.inner.idle.lock().pop() else {
+            let layer = self.inner.layer.upgrade().ok_or(NoLayerError)?;
+            let spawned = object.init(&layer)?;
+            spawned.insert(data)?;
+            return Ok(spawned);
+        };
+        #[cfg(feature = "physics")]
+        let handles = (
+            reused.physics.collider_handle,
+            reused.physics.rigid_body_handle,
+        );
+        reused.copy_new(object);
+        // Keeping the handles updates the bodies in place instead of adding new ones.
+        #[cfg(feature = "physics")]
+        {
+            (
+                reused.physics.collider_handle,
+                reused.physics.rigid_body_handle,
+            ) = handles;
+        }
+        reused.sync()?;
+        reused.take::<Idle>();
+        reused.insert(data)?;
+        reused.layer().count_recycled();
+        Ok(reused)
+    }
+
+    /// Releases the object back into the pool, returning its data.
+    ///
+    /// The object gets removed from its layer in case the pool is full.
+    pub fn release(&self, mut object: Object) -> Result<Option<T>> {
+        let data = object.take::<T>();
+        if object.contains::<Idle>() {
+            return Ok(data);
+        }
+        if self.len() >= self.capacity() {
+            object.remove()?;
+            return Ok(data);
+        }
+        object.update()?;
+        self.rest(object)?;
+        Ok(data)
+    }
+
+    /// Hides the object, disables its physics and puts it into the pool.
+    fn rest(&self, mut object: Object) -> Result<(), ObjectError> {
+        #[cfg(feature = "client")]
+        object.appearance.set_visible(false);
+        #[cfg(feature = "physics")]
+        {
+            if let Some(collider) = object.collider_mut() {
+                collider.set_enabled(false);
+            }
+            if let Some(rigid_body) = object.rigid_body_mut() {
+                rigid_body.set_enabled(false);
+            }
+        }
+        object.sync()?;
+        object.insert(Idle)?;
+        self.inner.idle.lock().push(object);
+        Ok(())
+    }
+
+    /// Removes all objects waiting in this pool from their layer.
+    pub fn clear(&self) {
+        for object in self.inner.idle.lock().drain(..) {
+            let _ = object.remove();
+        }
+    }
+}
+
+/// Counters of the objects of a layer, for checking how much pooling saves.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ObjectStats {
+    /// The number of objects in the layer, including the ones resting in pools.
+    pub objects: usize,
+    /// The number of objects resting in the pools of the layer.
+    pub pooled: usize,
+    /// The number of object nodes allocated since the layer got created.
+    pub allocated: u64,
+    /// The number of objects removed since the layer got created, including their children.
+    pub removed: u64,
+    /// The number of objects spawned from a pool without allocating since the layer got created.
+    pub recycled: u64,
+}
@@ -0,0 +1,120 @@
+//! Deferring changes to the scene from any thread to a defined point of the frame.
+
+use std::mem;
+
+use parking_lot::Mutex;
+
+#[cfg(feature = "client")]
+use super::Appearance;
+use super::{Object, SceneCommand, Transform};
+
+type Modify = Box<dyn FnOnce(&mut Object) + Send>;
+
+enum Deferred {
+    Command(SceneCommand),
+    Modify(Object, Modify),
+}
+
+/// A queue of changes to the scene, which gets applied all at once by the engine.
+///
+/// Systems running on other threads, like the tick or own worker threads, push changes here instead of locking
+/// the objects themselves, so they never wait for the scene while it gets drawn or stepped. The engine applies
+/// the queue right before every frame gets drawn and after the game logic of every tick, in the order the changes
+/// got pushed. Changes pushed while the queue gets applied wait for the next time.
+/// Only one thread applies the queue at a time, so changes never get applied out of order.
+///
+/// Changes apply to the state of the object at the time of applying, so a queued transform does not undo
+/// changes to the appearance made in the meantime. Changes that fail, for example because the object got removed,
+/// get logged and skipped.
+///
+/// # Usage
+/// ```ignore
+/// // on any thread
+/// SCENE_QUEUE.set_transform(&object, Transform::default().position(vec2(1.0, 0.0)));
+/// SCENE_QUEUE.modify(&object, |object| object.appearance.set_visible(false));
+/// ```
+#[derive(Default)]
+pub struct SceneQueue {
+    queue: Mutex<Vec<Deferred>>,
+    /// Held while applying, so a second thread waits for the earlier changes to be applied first.
+    applying: Mutex<()>,
+}
+
+impl SceneQueue {
+    /// Creates an empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a scene command, which does not get recorded in any history.
+    pub fn push(&self, command: SceneCommand) {
+        self.queue.lock().push(Deferred::Command(command));
+    }
+
+    /// Queues replacing the transform of the object.
+    pub fn set_transform(&self, object: &Object, transform: Transform) {
+        self.push(SceneCommand::SetTransform(object.clone(), transform));
+    }
+
+    /// Queues replacing the appearance of the object.
+    #[cfg(feature = "client")]
+    pub fn set_appearance(&self, object: &Object, appearance: Appearance) {
+        self.push(SceneCommand::SetAppearance(object.clone(), appearance));
+    }
+
+    /// Queues removing the object together with its children.
+    pub fn remove(&self, object: &Object) {
+        self.push(SceneCommand::Remove(object.clone()));
+    }
+
+    /// Queues a function changing the object, which gets called with its current state and synced afterwards.
+    pub fn modify(&self, object: &Object, f: impl FnOnce(&mut Object) + Send + 'static) {
+        self.queue
+            .lock()
+            .push(Deferred::Modify(object.clone(), Box::new(f)));
+    }
+
+    /// Returns the number of queued changes.
+    pub fn len(&self) -> usize {
+        self.queue.lock().len()
+    }
+
+    /// Returns true if no changes are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().is_empty()
+    }
+
+    /// Drops all queued changes without applying them.
+    pub fn clear(&self) {
+        self.queue.lock().clear();
+    }
+
+    /// Applies all queued changes in the order they got pushed, returning the number of changes that succeeded.
+    ///
+    /// Gets called by the engine, but can also be called to apply the changes earlier.
+    /// Waits in case another thread is applying the queue at the moment.
+    pub fn apply(&self) -> usize {
+        let _applying = self.applying.lock();
+        // The lock is only held for taking the queue, so pushing never waits for changes being applied.
+        let queue = mem::take(&mut *self.queue.lock());
+        let mut applied = 0;
+        for deferred in queue {
+            let result = match deferred {
+                Deferred::Command(command) => command.apply().map(|_| ()),
+                Deferred::Modify(object, f) => modify(&object, f),
+            };
+            match result {
+                Ok(()) => applied += 1,
+                Err(error) => log::warn!("A queued scene change failed: {error}"),
+            }
+        }
+        applied
+    }
+}
+
+fn modify(object: &Object, f: Modify) -> anyhow::Result<()> {
+    let mut object = object.current()?;
+    f(&mut object);
+    object.sync()?;
+    Ok(())
+}
@@ -5,12 +5,15 @@ use crossbeam::atomic::AtomicCell;
 use indexmap::{indexset, IndexSet};
 
 use parking_lot::Mutex;
+#[cfg(feature = "physics")]
+use std::collections::HashSet;
 use std::{
     collections::HashMap,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc, LazyLock,
     },
+    time::{Duration, Instant},
 };
 
 /// The engine wide scene holding all objects in layers.
@@ -20,25 +23,140 @@ pub static SCENE: LazyLock<crate::objects::scenes::Scene> =
 /// The whole scene seen with all it's layers.
 pub struct Scene {
     layers: Mutex<IndexSet<Arc<Layer>>>,
+    /// Spare physics pipelines, one for every layer stepped at the same time.
+    #[cfg(feature = "physics")]
+    physics_pipelines: Mutex<Vec<PhysicsPipeline>>,
+    /// Finishes once the physics step running on the worker threads is done.
     #[cfg(feature = "physics")]
-    physics_pipeline: Mutex<PhysicsPipeline>,
+    physics_step: Mutex<Option<crossbeam::channel::Receiver<()>>>,
+    tick_timing: AtomicCell<Option<TickTiming>>,
+}
+
+/// The time the latest tick has finished at and the interval to the tick before it.
+#[derive(Clone, Copy)]
+struct TickTiming {
+    instant: Instant,
+    interval: Duration,
 }
 
 impl Scene {
     /// Updates the scene physics and layers.
     #[cfg(feature = "physics")]
     pub fn update(&self, physics: bool) -> Result<()> {
+        self.sync_physics();
         let layers = self.layers.lock();
 
-        let mut pipeline = self.physics_pipeline.lock();
+        let mut pipeline = self.take_pipeline();
         if physics {
             for layer in layers.iter() {
                 layer.step_physics(&mut pipeline);
             }
         }
+        self.physics_pipelines.lock().push(pipeline);
         Ok(())
     }
 
+    /// Starts stepping the physics of all layers at the same time on the worker threads and returns right away.
+    ///
+    /// The objects keep their transforms until the next [`sync_physics`](Self::sync_physics),
+    /// which applies the outcome of the step, so the game and the renderer do not see half stepped layers.
+    /// Objects synced by the game while the step runs keep their new transforms.
+    /// Changing or querying the physics of a layer waits for its step to finish.
+    #[cfg(feature = "physics")]
+    pub fn update_threaded(&'static self) {
+        self.sync_physics();
+        let layers: Vec<Arc<Layer>> = self.layers.lock().iter().cloned().collect();
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        *self.physics_step.lock() = Some(receiver);
+        rayon::spawn(move || {
+            use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+            layers.par_iter().for_each(|layer| {
+                let mut pipeline = self.take_pipeline();
+                layer.step_rapier(&mut pipeline);
+                self.physics_pipelines.lock().push(pipeline);
+            });
+            let _ = sender.send(());
+        });
+    }
+
+    /// Waits for the physics step started by [`update_threaded`](Self::update_threaded) to finish
+    /// and applies its outcome to the objects.
+    ///
+    /// Gets called by the tick system before every tick. Call this before reading the transforms of physics objects
+    /// in case they have to be the ones of the latest step.
+    #[cfg(feature = "physics")]
+    pub fn sync_physics(&self) {
+        let Some(step) = self.physics_step.lock().take() else {
+            return;
+        };
+        let _ = step.recv();
+        for layer in self.layers.lock().iter() {
+            layer.apply_physics_transforms();
+        }
+    }
+
+    /// Returns true if a physics step is running on the worker threads.
+    #[cfg(feature = "physics")]
+    pub fn physics_stepping(&self) -> bool {
+        self.physics_step
+            .lock()
+            .as_ref()
+            .is_some_and(|step| step.is_empty())
+    }
+
+    /// Returns the counters of the latest physics step of every layer summed up.
+    ///
+    /// The step time is the time all layers took together.
+    #[cfg(feature = "physics")]
+    pub fn physics_stats(&self) -> physics::PhysicsStats {
+        self.layers
+            .lock()
+            .iter()
+            .map(|layer| layer.physics_stats())
+            .sum()
+    }
+
+    #[cfg(feature = "physics")]
+    fn take_pipeline(&self) -> PhysicsPipeline {
+        self.physics_pipelines
+            .lock()
+            .pop()
+            .unwrap_or_else(PhysicsPipeline::new)
+    }
+
+    /// Marks the end of a tick and records the transforms of all objects in layers with interpolation enabled.
+    ///
+    /// Gets called by the tick system after every tick.
+    pub fn finish_tick(&self) {
+        let now = Instant::now();
+        let interval = self
+            .tick_timing
+            .load()
+            .map(|timing| now.duration_since(timing.instant))
+            .unwrap_or_default();
+        self.tick_timing.store(Some(TickTiming {
+            instant: now,
+            interval,
+        }));
+
+        for layer in self.layers.lock().iter() {
+            layer.record_tick_transforms();
+        }
+    }
+
+    /// Returns how far the time has progressed from the latest tick to the next one, ranging from 0 to 1.
+    ///
+    /// This is the factor used to blend between the previous and current tick transforms of interpolated layers.
+    pub fn interpolation_alpha(&self) -> f32 {
+        let Some(timing) = self.tick_timing.load() else {
+            return 1.0;
+        };
+        if timing.interval.is_zero() {
+            return 1.0;
+        }
+        (timing.instant.elapsed().as_secs_f32() / timing.interval.as_secs_f32()).clamp(0.0, 1.0)
+    }
+
     /// Initializes a new layer into the scene.
     pub fn new_layer(&self) -> Arc<Layer> {
         let layer = Layer::new().unwrap();
@@ -48,27 +166,68 @@ impl Scene {
     }
 
     /// Removes a layer from the scene.
-    pub fn remove_layer(&self, layer: &mut Layer) -> Result<(), NoLayerError> {
-        let node: NObject;
+    pub fn remove_layer(&self, layer: &Layer) -> Result<(), NoLayerError> {
         let mut layers = self.layers.lock();
-        if layers.shift_remove(layer) {
-            node = layer.root.clone();
-        } else {
+        if !layers.shift_remove(layer) {
             return Err(NoLayerError);
         }
-        let mut objectguard = node.lock();
 
         //delete all the children of the layer too.
-        objectguard.remove_children(
-            &mut layer.objects_map.lock(),
-            #[cfg(feature = "physics")]
-            &mut layer.rigid_body_roots.lock(),
-        );
-        layers.shift_remove(layer);
+        let mut nodes = layer.nodes.lock();
+        #[cfg(feature = "physics")]
+        let mut rigid_bodies = layer.rigid_body_roots.lock();
+        let children = std::mem::take(&mut nodes.get_mut(layer.root).unwrap().children);
+        let mut removed = vec![];
+        for child in children {
+            Node::remove_tree(
+                &mut nodes,
+                child,
+                #[cfg(feature = "physics")]
+                &mut rigid_bodies,
+                &mut removed,
+            );
+        }
+        #[cfg(feature = "physics")]
+        drop(rigid_bodies);
+        drop(nodes);
+        drop(removed);
+
+        // Pooled objects hold the layer, which holds the pools, so they have to go for the layer to be freed.
+        for (_, pool) in layer.pools.lock().drain() {
+            pool.drain();
+        }
 
         Ok(())
     }
 
+    /// Moves the layer to the end of the layer list, so it gets drawn above all other layers.
+    pub fn move_layer_to_top(&self, layer: &Arc<Layer>) -> Result<(), NoLayerError> {
+        let mut layers = self.layers.lock();
+        let index = layers.get_index_of(layer).ok_or(NoLayerError)?;
+        let last = layers.len() - 1;
+        layers.move_index(index, last);
+        Ok(())
+    }
+
+    /// Returns a checksum over the [checksums](Layer::checksum) of all layers in order.
+    pub fn checksum(&self) -> u64 {
+        use std::hash::Hasher;
+
+        let mut hasher = crate::utils::StableHasher::default();
+        for layer in self.layers.lock().iter() {
+            hasher.write_u64(layer.checksum());
+        }
+        hasher.finish()
+    }
+
+    /// Moves the anchored objects of all layers to their anchors.
+    #[cfg(feature = "client")]
+    pub fn update_anchors(&self) {
+        for layer in self.layers.lock().iter() {
+            layer.update_anchors();
+        }
+    }
+
     /// Returns an IndexSet of all layers.
     pub fn layers(&self) -> IndexSet<Arc<Layer>> {
         self.layers.lock().clone()
@@ -87,51 +246,155 @@ impl Default for Scene {
         Self {
             layers: Mutex::new(indexset![]),
             #[cfg(feature = "physics")]
-            physics_pipeline: Mutex::new(PhysicsPipeline::new()),
+            physics_pipelines: Mutex::new(vec![]),
+            #[cfg(feature = "physics")]
+            physics_step: Mutex::new(None),
+            tick_timing: AtomicCell::new(None),
         }
     }
 }
 
 /// A layer struct holding it's own object hierarchy, camera and physics iteration.
 pub struct Layer {
-    pub(crate) root: NObject,
-    pub(crate) camera: Mutex<NObject>,
+    /// The handle of the invisible object all objects at the root of this layer are inside of.
+    pub(crate) root: ObjectHandle,
+    camera: AtomicCell<ObjectHandle>,
     camera_settings: AtomicCell<CameraSettings>,
-    pub(crate) objects_map: Mutex<ObjectsMap>,
+    camera_offset: AtomicCell<Vec2>,
+    viewport: AtomicCell<Viewport>,
+    extra_cameras: Mutex<Vec<ExtraCamera>>,
+    pub(crate) nodes: Mutex<Nodes>,
+    /// The objects with rigid bodies that are not inside of another rigid body.
     #[cfg(feature = "physics")]
-    rigid_body_roots: Mutex<ObjectsMap>,
+    rigid_body_roots: Mutex<HashSet<ObjectHandle>>,
     latest_object: AtomicU64,
     #[cfg(feature = "physics")]
     physics: Mutex<Physics>,
     #[cfg(feature = "physics")]
-    physics_enabled: std::sync::atomic::AtomicBool,
+    physics_enabled: AtomicBool,
+    /// The transforms of the bodies moved by the latest physics step, waiting to get applied to their objects.
+    #[cfg(feature = "physics")]
+    stepped_transforms: Mutex<Vec<(ObjectHandle, u64, Vec2, f32)>>,
+    interpolation: AtomicBool,
+    tick_transforms: Mutex<HashMap<usize, (Transform, Transform)>>,
+    frozen: AtomicBool,
+    #[cfg(feature = "client")]
+    frozen_frame: Mutex<Option<Arc<FrozenFrame>>>,
+    time_scale: AtomicCell<f64>,
+    paused: AtomicBool,
+    time_effects: Mutex<super::time_effects::TimeEffects>,
+    /// The object pools of this layer by the type of the data of their objects.
+    pools: Mutex<HashMap<TypeId, Arc<PoolInner>>>,
+    allocated_objects: AtomicU64,
+    removed_objects: AtomicU64,
+    recycled_objects: AtomicU64,
+    #[cfg(feature = "client")]
+    culling: AtomicBool,
+    #[cfg(feature = "client")]
+    lights: Mutex<Vec<Light>>,
+    #[cfg(feature = "client")]
+    ambient_light: AtomicCell<Color>,
+}
+
+/// A camera drawing its layer to another part of the window next to the main camera.
+struct ExtraCamera {
+    camera: ObjectHandle,
+    settings: CameraSettings,
+    viewport: Viewport,
+}
+
+/// The draw list and cameras of a layer captured at the moment it got frozen.
+#[cfg(feature = "client")]
+pub(crate) struct FrozenFrame {
+    pub objects: Vec<VisualObject>,
+    pub views: Vec<FrameView>,
+}
+
+#[cfg(feature = "client")]
+impl FrozenFrame {
+    /// Returns true if drawing both frames results in the same image,
+    /// not counting changes to the contents of textures and uniform buffers.
+    pub fn same_image(&self, other: &Self) -> bool {
+        self.objects.len() == other.objects.len()
+            && self.views.len() == other.views.len()
+            && self.objects.iter().zip(&other.objects).all(|(a, b)| {
+                a.id == b.id
+                    && a.transform == b.transform
+                    && a.clip == b.clip
+                    && a.stencil == b.stencil
+                    && a.appearance == b.appearance
+                    // Materials compare equal no matter which layer of their texture they show.
+                    && a.appearance.layer() == b.appearance.layer()
+            })
+            && self.views.iter().zip(&other.views).all(|(a, b)| {
+                a.camera.transform == b.camera.transform
+                    && a.settings.zoom == b.settings.zoom
+                    && a.settings.mode == b.settings.mode
+                    && a.settings.scissor == b.settings.scissor
+                    && a.viewport == b.viewport
+            })
+    }
+}
+
+/// A camera of a captured frame along with the area of the window it draws to.
+#[cfg(feature = "client")]
+pub(crate) struct FrameView {
+    pub camera: Object,
+    pub settings: CameraSettings,
+    pub viewport: Viewport,
 }
 
 impl Layer {
     /// Creates a new layer with the given root.
     pub(crate) fn new() -> Result<Arc<Self>> {
-        let root = Arc::new_cyclic(|weak| {
-            Mutex::new(Node {
-                object: Object::root(weak.clone()),
-                #[cfg(feature = "physics")]
-                rigid_body_parent: None,
-                children: vec![],
-            })
+        let mut nodes = Nodes::new();
+        let mut root = Object::root();
+        root.handle = nodes.next_handle();
+        let root = nodes.insert(Node {
+            object: root,
+            parent: None,
+            #[cfg(feature = "physics")]
+            rigid_body_parent: None,
+            #[cfg(feature = "physics")]
+            changes: 0,
+            children: vec![],
+            data: HashMap::new(),
         });
-        let mut objects_map = HashMap::new();
-        objects_map.insert(0, root.clone());
         Ok(Arc::new(Self {
-            root: root.clone(),
-            camera: Mutex::new(root),
+            root,
+            camera: AtomicCell::new(root),
             camera_settings: AtomicCell::new(CameraSettings::default()),
-            objects_map: Mutex::new(objects_map),
+            camera_offset: AtomicCell::new(Vec2::ZERO),
+            viewport: AtomicCell::new(Viewport::default()),
+            extra_cameras: Mutex::new(vec![]),
+            nodes: Mutex::new(nodes),
             #[cfg(feature = "physics")]
-            rigid_body_roots: Mutex::new(HashMap::new()),
+            rigid_body_roots: Mutex::new(HashSet::new()),
             latest_object: AtomicU64::new(1),
             #[cfg(feature = "physics")]
             physics: Mutex::new(Physics::new()),
             #[cfg(feature = "physics")]
-            physics_enabled: std::sync::atomic::AtomicBool::new(true),
+            physics_enabled: AtomicBool::new(true),
+            #[cfg(feature = "physics")]
+            stepped_transforms: Mutex::new(vec![]),
+            interpolation: AtomicBool::new(false),
+            tick_transforms: Mutex::new(HashMap::new()),
+            frozen: AtomicBool::new(false),
+            #[cfg(feature = "client")]
+            frozen_frame: Mutex::new(None),
+            time_scale: AtomicCell::new(1.0),
+            paused: AtomicBool::new(false),
+            time_effects: Mutex::default(),
+            pools: Mutex::new(HashMap::new()),
+            allocated_objects: AtomicU64::new(0),
+            removed_objects: AtomicU64::new(0),
+            recycled_objects: AtomicU64::new(0),
+            #[cfg(feature = "client")]
+            culling: AtomicBool::new(true),
+            #[cfg(feature = "client")]
+            lights: Mutex::new(vec![]),
+            #[cfg(feature = "client")]
+            ambient_light: AtomicCell::new(Color::from_rgb(0.3, 0.3, 0.3)),
         }))
     }
     /// Used by the proc macro to initialize the physics for an object.
@@ -140,18 +403,33 @@ impl Layer {
         &self.physics
     }
     #[cfg(feature = "physics")]
-    pub(crate) fn rigid_body_roots(&self) -> &Mutex<ObjectsMap> {
+    pub(crate) fn rigid_body_roots(&self) -> &Mutex<HashSet<ObjectHandle>> {
         &self.rigid_body_roots
     }
-    /// Sets the camera of this layer.
+    /// Sets the camera of this layer to an object inside of it.
     pub fn set_camera(&self, camera: &Object) -> Result<(), ObjectError> {
-        *self.camera.lock() = camera.as_node()?;
+        self.camera.store(self.handle_in_layer(camera)?);
         Ok(())
     }
 
     /// Returns the position of the camera object.
+    ///
+    /// Returns the default transform in case the camera object got removed.
     pub fn camera_transform(&self) -> Transform {
-        self.camera.lock().lock().object.transform
+        self.nodes
+            .lock()
+            .get(self.camera.load())
+            .map_or_else(Transform::default, |node| node.object.transform)
+    }
+
+    /// Returns the handle of the given object in case it is inside this layer.
+    fn handle_in_layer(&self, object: &Object) -> Result<ObjectHandle, ObjectError> {
+        let handle = object.handle();
+        if self.nodes.lock().contains(handle) {
+            Ok(handle)
+        } else {
+            Err(ObjectError::Uninit)
+        }
     }
 
     /// Returns the scaling of the camera settings.
@@ -179,6 +457,71 @@ impl Layer {
         self.camera_settings.load()
     }
 
+    /// Returns the offset the camera gets drawn at relative to its object.
+    pub fn camera_offset(&self) -> Vec2 {
+        self.camera_offset.load()
+    }
+
+    /// Offsets the view of the camera from its object without moving the object itself.
+    ///
+    /// Useful for effects like screen shake that should not interfere with the game logic moving the camera.
+    pub fn set_camera_offset(&self, offset: Vec2) {
+        self.camera_offset.store(offset)
+    }
+
+    /// Returns the area of the window the main camera draws to.
+    pub fn viewport(&self) -> Viewport {
+        self.viewport.load()
+    }
+
+    /// Sets the area of the window the main camera draws to.
+    ///
+    /// ## Default configuration
+    ///
+    /// The whole window.
+    pub fn set_viewport(&self, viewport: Viewport) {
+        self.viewport.store(viewport)
+    }
+
+    /// Adds another camera drawing this layer to the given area of the window, for example for split-screen.
+    ///
+    /// Returns the index of the added camera.
+    pub fn add_camera(
+        &self,
+        camera: &Object,
+        settings: CameraSettings,
+        viewport: Viewport,
+    ) -> Result<usize, ObjectError> {
+        let mut cameras = self.extra_cameras.lock();
+        cameras.push(ExtraCamera {
+            camera: self.handle_in_layer(camera)?,
+            settings,
+            viewport,
+        });
+        Ok(cameras.len() - 1)
+    }
+
+    /// Changes the settings and viewport of an added camera.
+    pub fn set_added_camera(&self, index: usize, settings: CameraSettings, viewport: Viewport) {
+        if let Some(camera) = self.extra_cameras.lock().get_mut(index) {
+            camera.settings = settings;
+            camera.viewport = viewport;
+        }
+    }
+
+    /// Removes the added camera with the given index. The indices of the cameras added after it go down by one.
+    pub fn remove_camera(&self, index: usize) {
+        let mut cameras = self.extra_cameras.lock();
+        if index < cameras.len() {
+            cameras.remove(index);
+        }
+    }
+
+    /// Returns the number of cameras added besides the main camera.
+    pub fn added_cameras(&self) -> usize {
+        self.extra_cameras.lock().len()
+    }
+
     /// Returns the position of a given side with given window dimensions to world space.
     ///
     /// x -1.0 to 1.0 for left to right
@@ -203,9 +546,427 @@ impl Layer {
         )
     }
 
-    /// Checks if the layer contains this object.
-    pub fn contains_object(&self, object_id: &usize) -> bool {
-        self.objects_map.lock().contains_key(object_id)
+    /// Returns the window and camera of this layer to resolve anchors in.
+    #[cfg(feature = "client")]
+    pub(crate) fn anchor_space(&self) -> AnchorSpace {
+        use crate::window::WINDOW;
+        let window = WINDOW
+            .get()
+            .map_or(vec2(1000.0, 1000.0), |window| window.inner_size());
+        let projection = self.screen_projection();
+        let settings = self.camera_settings();
+        AnchorSpace {
+            window,
+            projection,
+            pixel: settings.mode.scale(projection.size) * 2.0 / settings.zoom / projection.size,
+        }
+    }
+
+    /// Moves all anchored objects of this layer to their anchors.
+    ///
+    /// Gets called for every layer when the window gets resized.
+    /// Call it after changing the camera or the size of a parent with anchored children.
+    #[cfg(feature = "client")]
+    pub fn update_anchors(&self) {
+        let space = self.anchor_space();
+        let mut nodes = self.nodes.lock();
+        Node::update_anchors(
+            &mut nodes,
+            self.root,
+            &space,
+            true,
+            #[cfg(feature = "physics")]
+            &mut self.physics.lock(),
+        );
+    }
+
+    /// Returns the view projection matrix of the main camera along with its viewport in pixels.
+    #[cfg(feature = "client")]
+    fn screen_projection(&self) -> ScreenProjection {
+        use crate::window::WINDOW;
+        let window_size = WINDOW
+            .get()
+            .map_or(vec2(1000.0, 1000.0), |window| window.inner_size());
+        let (min, size) = self.viewport().pixels(window_size);
+        let mut camera = self.camera_transform();
+        camera.position += self.camera_offset();
+        let (view, proj) = crate::utils::view_proj(camera, self.camera_settings(), size);
+        ScreenProjection {
+            view_proj: proj * view,
+            min,
+            size: size.max(Vec2::ONE),
+        }
+    }
+
+    /// Converts a position in pixels from the top left corner of the window to the world space of this layer.
+    ///
+    /// Takes the position, rotation and offset of the camera, the zoom, the scaling mode, the viewport and the window size into account.
+    #[cfg(feature = "client")]
+    pub fn screen_to_world(&self, position: Vec2) -> Vec2 {
+        self.screen_projection().to_world(position)
+    }
+
+    /// Converts a position in the world space of this layer to pixels from the top left corner of the window.
+    #[cfg(feature = "client")]
+    pub fn world_to_screen(&self, position: Vec2) -> Vec2 {
+        self.screen_projection().to_screen(position)
+    }
+
+    /// Returns true if the transforms of objects in this layer get interpolated between ticks.
+    pub fn interpolation(&self) -> bool {
+        self.interpolation.load(Ordering::Acquire)
+    }
+
+    /// Enables or disables the interpolation of object transforms between ticks.
+    ///
+    /// When enabled objects get drawn blended between their last two tick transforms
+    /// instead of snapping from one tick position to the next, at the cost of being
+    /// one tick behind.
+    pub fn set_interpolation(&self, interpolation: bool) {
+        self.interpolation.store(interpolation, Ordering::Release);
+        if !interpolation {
+            self.tick_transforms.lock().clear();
+        }
+    }
+
+    /// Returns the lights shining on the objects of this layer drawn with a lit material.
+    #[cfg(feature = "client")]
+    pub fn lights(&self) -> Vec<Light> {
+        self.lights.lock().clone()
+    }
+
+    /// Replaces the lights of this layer. Only the first [MAX_LIGHTS](super::light::MAX_LIGHTS) lights get drawn.
+    ///
+    /// Lights following objects have to be moved along with them by setting the lights again.
+    #[cfg(feature = "client")]
+    pub fn set_lights(&self, lights: Vec<Light>) {
+        *self.lights.lock() = lights;
+    }
+
+    /// Returns the light reaching every part of lit objects regardless of the lights.
+    #[cfg(feature = "client")]
+    pub fn ambient_light(&self) -> Color {
+        self.ambient_light.load()
+    }
+
+    /// Sets the light reaching every part of lit objects regardless of the lights.
+    ///
+    /// ## Default configuration
+    ///
+    /// A dark gray of 0.3.
+    #[cfg(feature = "client")]
+    pub fn set_ambient_light(&self, color: Color) {
+        self.ambient_light.store(color)
+    }
+
+    /// Returns true if objects outside of the view of the camera get skipped while drawing.
+    #[cfg(feature = "client")]
+    pub fn culling(&self) -> bool {
+        self.culling.load(Ordering::Acquire)
+    }
+
+    /// Enables or disables skipping objects outside of the view of the camera while drawing.
+    ///
+    /// The bounds of an object are calculated from its model and transform.
+    /// Disable this for layers with materials that move vertices outside of the model in the vertex shader.
+    ///
+    /// ## Default configuration
+    ///
+    /// true
+    #[cfg(feature = "client")]
+    pub fn set_culling(&self, culling: bool) {
+        self.culling.store(culling, Ordering::Release);
+    }
+
+    /// Stores the current transforms of all objects as the newest tick transforms in case interpolation is enabled.
+    pub(crate) fn record_tick_transforms(&self) {
+        if !self.interpolation() {
+            return;
+        }
+        let mut transforms = HashMap::new();
+        let nodes = self.nodes.lock();
+        let root = nodes.get(self.root).unwrap();
+        root.collect_transforms(&nodes, root.object.public_transform(), &mut transforms);
+        drop(nodes);
+
+        let mut tick_transforms = self.tick_transforms.lock();
+        // Drop the removed objects.
+        tick_transforms.retain(|id, _| transforms.contains_key(id));
+        for (id, transform) in transforms {
+            tick_transforms
+                .entry(id)
+                .and_modify(|(previous, current)| {
+                    *previous = *current;
+                    *current = transform;
+                })
+                .or_insert((transform, transform));
+        }
+    }
+
+    /// Returns the transform of the given object blended between the previous and the latest tick by `alpha`.
+    ///
+    /// Returns `None` in case interpolation is disabled or the object was not present at the latest tick.
+    pub(crate) fn interpolated_transform(&self, id: usize, alpha: f32) -> Option<Transform> {
+        if !self.interpolation() {
+            return None;
+        }
+        self.tick_transforms
+            .lock()
+            .get(&id)
+            .map(|(previous, current)| previous.lerp(*current, alpha))
+    }
+
+    /// Returns true if the rendering of this layer is frozen.
+    pub fn frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    /// Freezes or unfreezes the rendering of this layer.
+    ///
+    /// A frozen layer keeps showing the image of the frame it got frozen at.
+    /// Its objects can still be changed and simulated, but the renderer draws them once to an image
+    /// the size of the window and only draws that image in their place until the layer gets unfrozen again.
+    /// The image gets drawn again from the objects as they were when the window size changes.
+    pub fn set_frozen(&self, frozen: bool) {
+        self.frozen.store(frozen, Ordering::Release);
+        #[cfg(feature = "client")]
+        if !frozen {
+            self.frozen_frame.lock().take();
+        }
+    }
+
+    /// Returns the time scale of this layer.
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale.load()
+    }
+
+    /// Sets the time scale of this layer, which stacks on top of the time scale of the game.
+    ///
+    /// It scales the physics steps, the animations and the layer timers of the scheduler,
+    /// so the game world can go into slow motion while a menu on another layer keeps its pace.
+    ///
+    /// Panics if the given time scale is negative, infinite or NaN.
+    pub fn set_time_scale(&self, time_scale: f64) {
+        if !(time_scale.is_finite() && time_scale >= 0.0) {
+            panic!("The time scale {time_scale} is not a finite positive number.");
+        }
+        self.time_scale.store(time_scale);
+    }
+
+    /// Returns true if the time of this layer is paused.
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Pauses or resumes the time of this layer.
+    ///
+    /// A paused layer does not step its physics, animations or layer timers while keeping its time scale,
+    /// but still gets drawn, unlike a [frozen](Self::set_frozen) one which still gets simulated.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Release);
+    }
+
+    /// Returns the time scale of this layer including its time effects, 0 while it is paused.
+    pub fn effective_time_scale(&self) -> f64 {
+        if self.paused() {
+            0.0
+        } else {
+            self.time_scale() * self.time_effects.lock().factor(Instant::now())
+        }
+    }
+
+    /// Freezes the time of this layer for the given real time duration.
+    ///
+    /// Calling this during an active hit stop extends it in case the new one lasts longer.
+    pub fn hit_stop(&self, duration: Duration) {
+        self.time_effects.lock().hit_stop(Instant::now() + duration);
+    }
+
+    /// Slows the time of this layer down to `scale` times its time scale.
+    ///
+    /// The time gets eased towards the slow motion scale for `ramp_in`, stays there for `hold` and
+    /// returns back to normal for `ramp_out`. Replaces the current slow motion effect of this layer.
+    pub fn slow_motion(&self, scale: f64, ramp_in: Duration, hold: Duration, ramp_out: Duration) {
+        self.time_effects
+            .lock()
+            .slow_motion(Instant::now(), scale, ramp_in, hold, ramp_out);
+    }
+
+    /// Stops the hit stop and slow motion of this layer.
+    pub fn clear_time_effects(&self) {
+        self.time_effects.lock().clear();
+    }
+
+    /// Collects the objects of this layer in draw order together with the current camera.
+    #[cfg(feature = "client")]
+    fn capture_frame(&self) -> FrozenFrame {
+        let nodes = self.nodes.lock();
+        let mut objects: Vec<VisualObject> = Vec::with_capacity(nodes.len());
+        Node::order_position(&mut objects, &nodes, nodes.get(self.root).unwrap(), 0);
+
+        // Blend the transforms between the last two ticks.
+        if self.interpolation() {
+            let alpha = SCENE.interpolation_alpha();
+            for object in objects.iter_mut() {
+                if let Some(transform) = self.interpolated_transform(object.id, alpha) {
+                    object.transform = transform;
+                }
+            }
+        }
+
+        let offset = self.camera_offset();
+        let view = |camera: ObjectHandle, settings, viewport| {
+            let mut camera = nodes
+                .get(camera)
+                .map_or_else(Object::root, |node| node.object.clone());
+            camera.transform.position += offset;
+            FrameView {
+                camera,
+                settings,
+                viewport,
+            }
+        };
+        let mut views = vec![view(
+            self.camera.load(),
+            self.camera_settings(),
+            self.viewport(),
+        )];
+        views.extend(
+            self.extra_cameras
+                .lock()
+                .iter()
+                .map(|extra| view(extra.camera, extra.settings, extra.viewport)),
+        );
+
+        FrozenFrame { objects, views }
+    }
+
+    /// Returns the frame to draw for this layer.
+    ///
+    /// While frozen the frame captured at the first frozen draw gets returned again.
+    #[cfg(feature = "client")]
+    pub(crate) fn frame(&self, frozen: bool) -> Arc<FrozenFrame> {
+        let mut frozen_frame = self.frozen_frame.lock();
+        if frozen {
+            frozen_frame
+                .get_or_insert_with(|| Arc::new(self.capture_frame()))
+                .clone()
+        } else {
+            frozen_frame.take();
+            Arc::new(self.capture_frame())
+        }
+    }
+
+    /// Returns copies of the objects at the root of this layer in drawing order.
+    pub fn children(&self) -> Vec<Object> {
+        let nodes = self.nodes.lock();
+        nodes
+            .get(self.root)
+            .unwrap()
+            .children
+            .iter()
+            .filter_map(|child| Some(nodes.get(*child)?.object.clone()))
+            .collect()
+    }
+
+    /// Returns the object with the given name.
+    ///
+    /// In case multiple objects share the name the one initialized first gets returned.
+    pub fn find_by_name(&self, name: &str) -> Option<Object> {
+        self.find_objects(|object| object.name.as_deref() == Some(name))
+            .into_iter()
+            .next()
+    }
+
+    /// Returns all objects with the given tag in the order they were initialized.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<Object> {
+        self.find_objects(|object| object.has_tag(tag))
+    }
+
+    /// Returns all objects matching the predicate sorted by their ID.
+    fn find_objects(&self, predicate: impl Fn(&Object) -> bool) -> Vec<Object> {
+        let mut objects: Vec<Object> = self
+            .nodes
+            .lock()
+            .values()
+            .filter(|node| node.parent.is_some() && predicate(&node.object))
+            .map(|node| node.object.clone())
+            .collect();
+        objects.sort_unstable_by_key(|object| *object.id());
+        objects
+    }
+
+    /// Returns a checksum of the IDs and transforms of all objects in this layer,
+    /// along with the velocities of their rigid bodies.
+    ///
+    /// The checksum is the same on every machine running the same simulation,
+    /// so peers of lockstep games can compare it every few ticks to detect desyncs.
+    pub fn checksum(&self) -> u64 {
+        use std::hash::Hasher;
+
+        let nodes = self.nodes.lock();
+        let mut sorted: Vec<&Node> = nodes.values().collect();
+        sorted.sort_unstable_by_key(|node| node.object.id);
+
+        #[cfg(feature = "physics")]
+        let physics = self.physics.lock();
+        let mut hasher = crate::utils::StableHasher::default();
+        for node in sorted {
+            let id = node.object.id;
+            let transform = node.object.transform;
+            hasher.write_usize(id);
+            for value in [
+                transform.position.x,
+                transform.position.y,
+                transform.size.x,
+                transform.size.y,
+                transform.rotation,
+            ] {
+                hasher.write_u32(value.to_bits());
+            }
+            #[cfg(feature = "physics")]
+            if let Some(rigid_body) = node
+                .object
+                .rigidbody_handle()
+                .and_then(|handle| physics.rigid_body_set.get(handle))
+            {
+                let velocity = rigid_body.linvel();
+                for value in [velocity.x, velocity.y, rigid_body.angvel()] {
+                    hasher.write_u32(value.to_bits());
+                }
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Returns the ID of this layer, which is unique for the whole run of the program.
+    ///
+    /// It is the same as the [`layer`](ObjectHandle::layer) of the handles of the objects in this layer.
+    pub fn id(&self) -> u32 {
+        self.root.layer()
+    }
+
+    /// Returns a copy of the object the handle points to, `None` in case it got removed or is in another layer.
+    pub fn object(&self, handle: ObjectHandle) -> Option<Object> {
+        if handle == self.root {
+            return None;
+        }
+        Some(self.nodes.lock().get(handle)?.object.clone())
+    }
+
+    /// Returns true if the handle still points to an object of this layer.
+    pub fn contains_handle(&self, handle: ObjectHandle) -> bool {
+        handle != self.root && self.nodes.lock().contains(handle)
+    }
+
+    /// Makes space for the given number of additional objects, so spawning them does not grow the storage of the layer.
+    pub fn reserve_objects(&self, additional: usize) {
+        self.nodes.lock().reserve(additional);
+    }
+
+    /// Returns the number of objects this layer holds without growing its storage.
+    pub fn object_capacity(&self) -> usize {
+        self.nodes.lock().capacity()
     }
     //TODO FIX FIXME
     // #[cfg(feature = "audio")]
@@ -233,132 +994,127 @@ impl Layer {
         self.latest_object.fetch_add(1, Ordering::AcqRel) as usize
     }
 
-    pub(crate) fn add_object(&self, id: usize, object: &NObject) {
-        self.objects_map.lock().insert(id, object.clone());
+    pub(crate) fn count_allocated(&self) {
+        self.allocated_objects.fetch_add(1, Ordering::Relaxed);
     }
 
-    /// Moves an object on the given index in it's parents children order.
-    pub(crate) fn move_to(&self, object: &Object, index: usize) -> Result<(), ObjectError> {
-        let node = object.as_node()?;
-        let count = Self::count_children(&node).ok_or(ObjectError::NoParent)?;
+    pub(crate) fn count_removed(&self, count: usize) {
+        self.removed_objects
+            .fetch_add(count as u64, Ordering::Relaxed);
+    }
 
-        if count < index {
-            return Err(ObjectError::Move(format!(
-                "This object can not be moved to {index}. You can not go above {count}"
-            )));
-        } else {
-            Self::move_object_to(node, index);
+    pub(crate) fn count_recycled(&self) {
+        self.recycled_objects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the object pool of this layer for objects carrying data of the type `T`,
+    /// creating it in case it does not exist yet.
+    ///
+    /// The capacity is the maximum number of released objects the pool keeps, replacing the previous capacity.
+    pub fn pool<T: Any + Send + Sync>(self: &Arc<Self>, capacity: usize) -> ObjectPool<T> {
+        let mut pools = self.pools.lock();
+        let inner = pools
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Arc::new(PoolInner::new(self, capacity)));
+        inner.set_capacity(capacity);
+        ObjectPool::new(inner.clone())
+    }
+
+    /// Returns the object counters of this layer.
+    pub fn object_stats(&self) -> ObjectStats {
+        ObjectStats {
+            objects: self.nodes.lock().len() - 1,
+            pooled: self.pools.lock().values().map(|pool| pool.len()).sum(),
+            allocated: self.allocated_objects.load(Ordering::Relaxed),
+            removed: self.removed_objects.load(Ordering::Relaxed),
+            recycled: self.recycled_objects.load(Ordering::Relaxed),
         }
-        Ok(())
+    }
+
+    /// Moves an object on the given index in it's parents children order.
+    pub(crate) fn move_to(&self, object: &Object, index: usize) -> Result<(), ObjectError> {
+        self.with_siblings(object, |siblings, current| {
+            let count = siblings.len();
+            if count <= index {
+                return Err(ObjectError::Move(format!(
+                    "This object can not be moved to {index}. You can not go above {}",
+                    count - 1
+                )));
+            }
+            let element = siblings.remove(current);
+            siblings.insert(index, element);
+            Ok(())
+        })
     }
 
     /// Moves an object one up in it's parents children order.
     pub(crate) fn move_up(&self, object: &Object) -> Result<(), ObjectError> {
-        let node = object.as_node()?;
-        if Arc::ptr_eq(&node, &self.root) {
-            return Err(ObjectError::NoParent);
-        }
-        let parent = node.lock().object.parent_node();
-        let index = Self::find_child_index(&parent, &node).ok_or(ObjectError::NoParent)?;
-        if index == 0 {
-            return Err(ObjectError::Move(
-                "Object already on the top of the current layer.".to_string(),
-            ));
-        } else {
-            let dst_node = parent.lock().children.get(index - 1).unwrap().clone();
-            Self::swap_objects(node, dst_node);
-        }
-        Ok(())
+        self.with_siblings(object, |siblings, index| {
+            if index == 0 {
+                return Err(ObjectError::Move(
+                    "Object already on the top of the current layer.".to_string(),
+                ));
+            }
+            siblings.swap(index, index - 1);
+            Ok(())
+        })
     }
 
     /// Moves an object one down in it's parents children order.
     pub(crate) fn move_down(&self, object: &Object) -> Result<(), ObjectError> {
-        let node = object.as_node()?;
-        if Arc::ptr_eq(&node, &self.root) {
-            return Err(ObjectError::NoParent);
-        }
-        let parent = node.lock().object.parent_node();
-        let index = Self::find_child_index(&parent, &node).ok_or(ObjectError::NoParent)?;
-
-        let dst_node = parent
-            .lock()
-            .children
-            .get(index + 1)
-            .ok_or(ObjectError::Move(format!(
-                "Object already at the bottom of the layer: {index}"
-            )))?
-            .clone();
-        Self::swap_objects(node, dst_node);
-        Ok(())
+        self.with_siblings(object, |siblings, index| {
+            if index + 1 >= siblings.len() {
+                return Err(ObjectError::Move(format!(
+                    "Object already at the bottom of the layer: {index}"
+                )));
+            }
+            siblings.swap(index, index + 1);
+            Ok(())
+        })
     }
 
     /// Moves an object all the way to the top of it's parents children list.
     pub(crate) fn move_to_top(&self, object: &Object) -> Result<(), ObjectError> {
-        let node = object.as_node()?;
-        Self::move_object_to(node, 0);
-        Ok(())
+        self.with_siblings(object, |siblings, index| {
+            let element = siblings.remove(index);
+            siblings.insert(0, element);
+            Ok(())
+        })
     }
 
     /// Moves an object all the way to the bottom of it's parents children list.
     pub(crate) fn move_to_bottom(&self, object: &Object) -> Result<(), ObjectError> {
-        let node = object.as_node()?;
-        let count = Self::count_children(&node).ok_or(ObjectError::NoParent)? - 1;
-        Self::move_object_to(node, count);
-        Ok(())
-    }
-
-    /// Finds the index of the child in the parents children list.
-    ///
-    /// Returns `None` in case the child is not present in the object.
-    fn find_child_index(parent: &NObject, object: &NObject) -> Option<usize> {
-        let parent = parent.lock();
-        parent
-            .children
-            .clone()
-            .into_iter()
-            .position(|x| Arc::ptr_eq(&x, object))
-    }
-
-    /// Counts the amount of children the parent of the given object has.
-    ///
-    /// Returns none in case there is no parent.
-    fn count_children(object: &NObject) -> Option<usize> {
-        let parent = object.lock().object.parent_node();
-        let parent = parent.lock();
-        Some(parent.children.len())
+        self.with_siblings(object, |siblings, index| {
+            let element = siblings.remove(index);
+            siblings.push(element);
+            Ok(())
+        })
     }
 
-    /// Moves an object on the given index in it's parents children order.
-    fn move_object_to(src: NObject, dst: usize) {
-        let parent = src.lock().object.parent_node();
-        let mut parent = parent.lock();
-        let index = parent
-            .children
-            .clone()
-            .into_iter()
-            .position(|x| Arc::ptr_eq(&x, &src))
-            .unwrap();
-        let element = parent.children.remove(index);
-        parent.children.insert(dst, element);
+    /// Returns the index of the object in the children list of its parent.
+    pub(crate) fn child_index(&self, object: &Object) -> Result<usize, ObjectError> {
+        self.with_siblings(object, |_, index| Ok(index))
     }
 
-    fn swap_objects(src: NObject, dst: NObject) {
-        let parent = src.lock().object.parent_node();
-        let mut parent = parent.lock();
-        let src_index = parent
-            .children
-            .clone()
-            .into_iter()
-            .position(|x| Arc::ptr_eq(&x, &src))
-            .unwrap();
-        let dst_index = parent
-            .children
-            .clone()
-            .into_iter()
-            .position(|x| Arc::ptr_eq(&x, &dst))
-            .unwrap();
-
-        parent.children.swap(src_index, dst_index);
+    /// Runs the closure with the children list of the parent of the object and the index of the object in it.
+    fn with_siblings<R>(
+        &self,
+        object: &Object,
+        f: impl FnOnce(&mut Vec<ObjectHandle>, usize) -> Result<R, ObjectError>,
+    ) -> Result<R, ObjectError> {
+        let handle = object.handle();
+        let mut nodes = self.nodes.lock();
+        let parent = nodes
+            .get(handle)
+            .ok_or(ObjectError::Uninit)?
+            .parent
+            .ok_or(ObjectError::NoParent)?;
+        let siblings = &mut nodes.get_mut(parent).ok_or(ObjectError::NoParent)?.children;
+        let index = siblings
+            .iter()
+            .position(|child| *child == handle)
+            .ok_or(ObjectError::NoParent)?;
+        f(siblings, index)
     }
 }
 
@@ -369,8 +1125,8 @@ use rapier2d::prelude::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "physics")))]
 #[cfg(feature = "physics")]
 impl Layer {
-    /// Returns the nearest collider id from a specific location.
-    pub fn query_nearest_collider_at(&self, position: Vec2) -> Option<usize> {
+    /// Returns the handle of the object with the nearest collider from a specific location.
+    pub fn query_nearest_collider_at(&self, position: Vec2) -> Option<ObjectHandle> {
         let mut physics = self.physics.lock();
         physics.update_query_pipeline();
 
@@ -384,48 +1140,117 @@ impl Layer {
         );
 
         if let Some((handle, _)) = result {
-            Some(physics.collider_set.get(handle).unwrap().user_data as usize)
+            Some(ObjectHandle::from_bits(
+                physics.collider_set.get(handle).unwrap().user_data,
+            ))
         } else {
             None
         }
     }
 
-    /// Returns id of the first collider intersecting with given ray.
+    /// Casts a ray and returns the first object hit with the point and normal of the impact.
+    ///
+    /// `max_time_of_impact` limits the length of the ray in multiples of `direction`.
     pub fn cast_ray(
         &self,
-        position: Vec2,
+        origin: Vec2,
         direction: Vec2,
-        time_of_impact: Real,
-        solid: bool,
-    ) -> Option<usize> {
-        let mut physics = self.physics.lock();
-        physics.update_query_pipeline();
+        max_time_of_impact: Real,
+        filter: &physics::CastFilter,
+    ) -> Option<physics::CastHit> {
+        let (handle, point, normal, time_of_impact) = {
+            let mut physics = self.physics.lock();
+            physics.update_query_pipeline();
 
-        let point = mint::Point2::from(position);
-        let direction = mint::Vector2::from(direction);
-        let result = physics.query_pipeline.cast_ray(
-            &physics.rigid_body_set,
-            &physics.collider_set,
-            &Ray::new(point.into(), direction.into()),
+            let point = mint::Point2::from(origin);
+            let mint_direction = mint::Vector2::from(direction);
+            let (handle, intersection) = filter.with_query_filter(|query_filter| {
+                physics.query_pipeline.cast_ray_and_get_normal(
+                    &physics.rigid_body_set,
+                    &physics.collider_set,
+                    &Ray::new(point.into(), mint_direction.into()),
+                    max_time_of_impact,
+                    filter.solid,
+                    query_filter,
+                )
+            })?;
+            (
+                ObjectHandle::from_bits(physics.collider_set.get(handle)?.user_data),
+                origin + direction * intersection.time_of_impact,
+                vec2(intersection.normal.x, intersection.normal.y),
+                intersection.time_of_impact,
+            )
+        };
+
+        Some(physics::CastHit {
+            object: self.object(handle)?,
+            point,
+            normal,
             time_of_impact,
-            solid,
-            QueryFilter::default(),
-        );
+        })
+    }
 
-        if let Some((handle, _)) = result {
-            Some(physics.collider_set.get(handle).unwrap().user_data as usize)
-        } else {
-            None
-        }
+    /// Moves a shape from the given position and rotation along the velocity and returns the first object hit
+    /// with the point and normal of the impact.
+    ///
+    /// `max_time_of_impact` limits the distance in multiples of `velocity`.
+    pub fn cast_shape(
+        &self,
+        shape: &physics::Shape,
+        position: (Vec2, f32),
+        velocity: Vec2,
+        max_time_of_impact: Real,
+        filter: &physics::CastFilter,
+    ) -> Option<physics::CastHit> {
+        let (handle, point, normal, time_of_impact) = {
+            let mut physics = self.physics.lock();
+            physics.update_query_pipeline();
+
+            let vec = mint::Vector2::from(position.0);
+            let iso = nalgebra::Isometry2::new(vec.into(), position.1);
+            let mint_velocity = mint::Vector2::from(velocity);
+            let options = ShapeCastOptions {
+                max_time_of_impact,
+                stop_at_penetration: filter.solid,
+                ..Default::default()
+            };
+            let (handle, hit) = filter.with_query_filter(|query_filter| {
+                physics.query_pipeline.cast_shape(
+                    &physics.rigid_body_set,
+                    &physics.collider_set,
+                    &iso,
+                    &mint_velocity.into(),
+                    &*shape.0,
+                    options,
+                    query_filter,
+                )
+            })?;
+            let collider = physics.collider_set.get(handle)?;
+            let point = collider.position() * hit.witness1;
+            let normal = collider.position() * hit.normal1;
+            (
+                ObjectHandle::from_bits(collider.user_data),
+                vec2(point.x, point.y),
+                vec2(normal.x, normal.y),
+                hit.time_of_impact,
+            )
+        };
+
+        Some(physics::CastHit {
+            object: self.object(handle)?,
+            point,
+            normal,
+            time_of_impact,
+        })
     }
-    /// Returns id of the first collider intersecting with given ray and returns a normal.
+
     pub fn cast_ray_and_get_normal(
         &self,
         position: Vec2,
         direction: Vec2,
         time_of_impact: Real,
         solid: bool,
-    ) -> Option<(usize, Vec2)> {
+    ) -> Option<(ObjectHandle, Vec2)> {
         let mut physics = self.physics.lock();
         physics.update_query_pipeline();
 
@@ -443,7 +1268,7 @@ impl Layer {
         if let Some((handle, intersection)) = result {
             let inter = intersection.normal;
             Some((
-                physics.collider_set.get(handle).unwrap().user_data as usize,
+                ObjectHandle::from_bits(physics.collider_set.get(handle).unwrap().user_data),
                 vec2(inter.x, inter.y),
             ))
         } else {
@@ -451,14 +1276,14 @@ impl Layer {
         }
     }
 
-    /// Returns id of the first collider intersecting with given ray.
+    /// Returns the handles of the objects with colliders intersecting with given ray.
     pub fn intersections_with_ray(
         &self,
         position: Vec2,
         direction: Vec2,
         time_of_impact: Real,
         solid: bool,
-    ) -> Vec<usize> {
+    ) -> Vec<ObjectHandle> {
         let mut physics = self.physics.lock();
         physics.update_query_pipeline();
 
@@ -467,7 +1292,9 @@ impl Layer {
         let colliders = &physics.collider_set;
         let filter = QueryFilter::default();
         let mut callback = |handle| {
-            intersections.push(physics.collider_set.get(handle).unwrap().user_data as usize);
+            intersections.push(ObjectHandle::from_bits(
+                physics.collider_set.get(handle).unwrap().user_data,
+            ));
             true
         };
 
@@ -500,7 +1327,7 @@ impl Layer {
         &self,
         shape: physics::Shape,
         position: (Vec2, f32),
-    ) -> Option<usize> {
+    ) -> Option<ObjectHandle> {
         let mut physics = self.physics.lock();
         physics.update_query_pipeline();
 
@@ -513,7 +1340,9 @@ impl Layer {
             shape.0.as_ref(),
             QueryFilter::default(),
         );
-        result.map(|handle| physics.collider_set.get(handle).unwrap().user_data as usize)
+        result.map(|handle| {
+            ObjectHandle::from_bits(physics.collider_set.get(handle).unwrap().user_data)
+        })
     }
 
     /// Cast a shape and return the first collider intersecting with it.
@@ -521,13 +1350,15 @@ impl Layer {
         &self,
         shape: physics::Shape,
         position: (Vec2, f32),
-    ) -> Vec<usize> {
+    ) -> Vec<ObjectHandle> {
         let mut physics = self.physics.lock();
         physics.update_query_pipeline();
 
         let mut intersections = vec![];
         let callback = |handle| {
-            intersections.push(physics.collider_set.get(handle).unwrap().user_data as usize);
+            intersections.push(ObjectHandle::from_bits(
+                physics.collider_set.get(handle).unwrap().user_data,
+            ));
             true
         };
 
@@ -544,20 +1375,63 @@ impl Layer {
         intersections
     }
     pub(crate) fn step_physics(&self, physics_pipeline: &mut PhysicsPipeline) {
-        if self.physics_enabled.load(Ordering::Acquire) {
-            let mut map = self.rigid_body_roots.lock();
+        self.step_rapier(physics_pipeline);
+        self.apply_physics_transforms();
+    }
+
+    /// Steps the physics of this layer, keeping the new transforms of the bodies until they get applied.
+    fn step_rapier(&self, physics_pipeline: &mut PhysicsPipeline) {
+        let time_scale = self.effective_time_scale() as f32;
+        if self.physics_enabled.load(Ordering::Acquire) && time_scale > 0.0 {
+            // The changes counted when the step starts, to skip objects the game changes during the step.
+            let roots: Vec<(ObjectHandle, RigidBodyHandle, u64)> = {
+                let nodes = self.nodes.lock();
+                self.rigid_body_roots
+                    .lock()
+                    .iter()
+                    .filter_map(|handle| {
+                        let node = nodes.get(*handle)?;
+                        Some((*handle, node.object.rigidbody_handle()?, node.changes))
+                    })
+                    .collect()
+            };
 
             let mut physics = self.physics.lock();
+            // The timestep gets scaled for this step only, keeping the settings as they are.
+            let timestep = physics.integration_parameters.dt;
+            physics.integration_parameters.dt = timestep * time_scale;
             physics.step(physics_pipeline); // Rapier-side physics iteration run.
-            for (_, object) in map.iter_mut() {
-                let mut node = object.lock();
-                let rigid_body = physics
-                    .rigid_body_set
-                    .get(node.object.rigidbody_handle().unwrap())
-                    .unwrap();
+            physics.integration_parameters.dt = timestep;
+            let mut stepped = self.stepped_transforms.lock();
+            stepped.clear();
+            for (handle, rigid_body_handle, changes) in roots {
+                let Some(rigid_body) = physics.rigid_body_set.get(rigid_body_handle) else {
+                    continue;
+                };
+                // Deactivated bodies did not move, so their objects are still up to date.
+                if !rigid_body.is_enabled() {
+                    continue;
+                }
                 let pos = *rigid_body.translation();
-                node.object
-                    .set_isometry(vec2(pos.x, pos.y), rigid_body.rotation().angle());
+                stepped.push((
+                    handle,
+                    changes,
+                    vec2(pos.x, pos.y),
+                    rigid_body.rotation().angle(),
+                ));
+            }
+        }
+    }
+
+    /// Moves the objects to the transforms of their bodies after the latest physics step.
+    ///
+    /// Objects the game synced since the step started keep their new transform.
+    fn apply_physics_transforms(&self) {
+        let stepped = std::mem::take(&mut *self.stepped_transforms.lock());
+        let mut nodes = self.nodes.lock();
+        for (handle, changes, position, rotation) in stepped {
+            if let Some(node) = nodes.get_mut(handle).filter(|node| node.changes == changes) {
+                node.object.set_isometry(position, rotation);
             }
         }
     }
@@ -573,6 +1447,99 @@ impl Layer {
         let vec = mint::Vector2::from(gravity);
         self.physics.lock().gravity = vec.into();
     }
+    /// Turns the collider of the given object into a one-way platform or back into a regular collider.
+    ///
+    /// Other objects only collide with the side the given normal in local space of the object points to.
+    /// A platform that can be jumped through from below uses `vec2(0.0, -1.0)`.
+    ///
+    /// Enables the contact hooks of the collider and syncs the object.
+    pub fn set_one_way_platform(
+        &self,
+        object: &mut Object,
+        normal: Option<Vec2>,
+    ) -> Result<(), ObjectError> {
+        let id = object.handle().to_bits();
+        {
+            let hooks = &mut self.physics.lock().hooks;
+            match normal {
+                Some(normal) => hooks
+                    .one_way_platforms
+                    .insert(id, normal.normalize_or_zero()),
+                None => hooks.one_way_platforms.remove(&id),
+            };
+        }
+        if let (Some(collider), Some(_)) = (object.collider_mut(), normal) {
+            collider.set_contact_hooks(true);
+        }
+        object.sync()
+    }
+
+    /// Sets a hook that gets called for every contact of colliders with contact hooks enabled before it gets resolved.
+    ///
+    /// The hook can inspect and modify or disable the contact.
+    /// It runs during the physics step, so accessing the physics of this layer inside it deadlocks.
+    pub fn set_contact_hook(
+        &self,
+        hook: impl Fn(&mut physics::ContactContext) + Send + Sync + 'static,
+    ) {
+        self.physics.lock().hooks.contact_hook = Some(Arc::new(hook) as physics::ContactHook);
+    }
+
+    /// Removes the contact hook of this layer.
+    pub fn clear_contact_hook(&self) {
+        self.physics.lock().hooks.contact_hook = None;
+    }
+
+    /// Takes all collision events reported since the last call.
+    ///
+    /// Events only get reported for colliders with collision events enabled. They pile up
+    /// until they get drained, so drain them regularly, for example every tick.
+    pub fn drain_collision_events(&self) -> Vec<physics::CollisionEvent> {
+        std::mem::take(&mut *self.physics.lock().events.events.lock())
+    }
+
+    /// Deactivates the physics of all non fixed rigid bodies inside the given area, freezing them in place
+    /// without taking part in the simulation or collisions until they get activated again.
+    ///
+    /// Returns the number of deactivated bodies.
+    /// Note that syncing an object applies its local rigid body copy, which can activate it again.
+    pub fn deactivate_region(&self, min: Vec2, max: Vec2) -> usize {
+        self.physics.lock().set_bodies_enabled(|position| {
+            (position.cmpge(min).all() && position.cmple(max).all()).then_some(false)
+        })
+    }
+
+    /// Activates the physics of all rigid bodies inside the given area that were deactivated before.
+    ///
+    /// Returns the number of activated bodies.
+    pub fn activate_region(&self, min: Vec2, max: Vec2) -> usize {
+        self.physics.lock().set_bodies_enabled(|position| {
+            (position.cmpge(min).all() && position.cmple(max).all()).then_some(true)
+        })
+    }
+
+    /// Only keeps the physics of rigid bodies within `radius` around any of the given points active,
+    /// deactivating all others.
+    ///
+    /// Calling this every tick with the positions of the players keeps big worlds cheap,
+    /// waking the areas they approach and freezing the ones they leave.
+    ///
+    /// Returns the number of bodies that changed their state.
+    pub fn activate_around(&self, points: &[Vec2], radius: f32) -> usize {
+        let radius_squared = radius * radius;
+        self.physics.lock().set_bodies_enabled(|position| {
+            Some(
+                points
+                    .iter()
+                    .any(|point| point.distance_squared(position) <= radius_squared),
+            )
+        })
+    }
+
+    /// Returns the counters of the latest physics step of this layer.
+    pub fn physics_stats(&self) -> physics::PhysicsStats {
+        self.physics.lock().stats
+    }
     /// Returns if physics is enabled.
     pub fn physics_enabled(&self) -> bool {
         self.physics_enabled.load(Ordering::Acquire)
@@ -581,6 +1548,14 @@ impl Layer {
     pub fn set_physics_enabled(&self, enabled: bool) {
         self.physics_enabled.store(enabled, Ordering::Release)
     }
+    /// Returns the physics settings of this layer.
+    pub fn physics_settings(&self) -> physics::PhysicsSettings {
+        self.physics.lock().settings()
+    }
+    /// Sets the gravity, timestep, continuous collision detection and solver iterations of the physics of this layer.
+    pub fn set_physics_settings(&self, settings: physics::PhysicsSettings) {
+        self.physics.lock().set_settings(settings);
+    }
     /// Takes the physics simulation parameters.
     pub fn physics_parameters(&self) -> IntegrationParameters {
         self.physics.lock().integration_parameters
@@ -642,7 +1617,7 @@ impl Layer {
 
 impl PartialEq for Layer {
     fn eq(&self, other: &Self) -> bool {
-        *self.root.lock() == *other.root.lock()
+        self.root == other.root
     }
 }
 
@@ -650,6 +1625,38 @@ impl Eq for Layer {}
 
 impl std::hash::Hash for Layer {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        Arc::as_ptr(&self.root).hash(state);
+        self.root.hash(state);
+    }
+}
+
+#[cfg(all(test, feature = "physics"))]
+mod tests {
+    use super::*;
+    use crate::objects::physics::{ColliderBuilder, RigidBodyBuilder};
+
+    fn body(layer: &Arc<Layer>, position: Vec2) -> Object {
+        let mut object = NewObject::new();
+        object.set_isometry(position, 0.0);
+        object.set_collider(Some(ColliderBuilder::square(0.5, 0.5).build()));
+        object.set_rigid_body(Some(RigidBodyBuilder::dynamic().build()));
+        object.init(layer).unwrap()
+    }
+
+    #[test]
+    fn steps_do_not_undo_changes_made_meanwhile() {
+        let layer = Layer::new().unwrap();
+        let mut falling = body(&layer, Vec2::ZERO);
+        let mut teleported = body(&layer, vec2(5.0, 0.0));
+
+        layer.step_rapier(&mut PhysicsPipeline::new());
+        // The game moves the object while the step runs on another thread.
+        teleported.transform.position = vec2(10.0, 0.0);
+        teleported.sync().unwrap();
+        layer.apply_physics_transforms();
+
+        falling.update().unwrap();
+        teleported.update().unwrap();
+        assert_ne!(falling.transform.position, Vec2::ZERO);
+        assert_eq!(teleported.transform.position, vec2(10.0, 0.0));
     }
 }
@@ -0,0 +1,141 @@
+//! Hit stops and slow motion ramps scaling the time of a layer.
+
+use std::time::{Duration, Instant};
+
+/// A slow motion effect easing the time scale in and out.
+struct SlowMotion {
+    start: Instant,
+    scale: f64,
+    ramp_in: Duration,
+    hold: Duration,
+    ramp_out: Duration,
+}
+
+impl SlowMotion {
+    /// Returns the time scale factor at the given moment or `None` once the effect is over.
+    fn factor(&self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.start);
+        let ramp = |elapsed: Duration, ramp: Duration| {
+            if ramp.is_zero() {
+                1.0
+            } else {
+                (elapsed.as_secs_f64() / ramp.as_secs_f64()).min(1.0)
+            }
+        };
+        let hold_end = self.ramp_in + self.hold;
+        if elapsed < self.ramp_in {
+            Some(1.0 + (self.scale - 1.0) * ramp(elapsed, self.ramp_in))
+        } else if elapsed < hold_end {
+            Some(self.scale)
+        } else if elapsed < hold_end + self.ramp_out {
+            Some(self.scale + (1.0 - self.scale) * ramp(elapsed - hold_end, self.ramp_out))
+        } else {
+            None
+        }
+    }
+}
+
+/// The time effects of a layer, running on real time so they keep working while they slow the layer down.
+#[derive(Default)]
+pub(crate) struct TimeEffects {
+    hit_stop: Option<Instant>,
+    slow_motion: Option<SlowMotion>,
+}
+
+impl TimeEffects {
+    /// Freezes the time until the given moment, extending the current hit stop in case it ends earlier.
+    pub fn hit_stop(&mut self, end: Instant) {
+        if self.hit_stop.is_none_or(|current| current < end) {
+            self.hit_stop = Some(end);
+        }
+    }
+
+    /// Replaces the current slow motion effect.
+    pub fn slow_motion(
+        &mut self,
+        start: Instant,
+        scale: f64,
+        ramp_in: Duration,
+        hold: Duration,
+        ramp_out: Duration,
+    ) {
+        self.slow_motion = Some(SlowMotion {
+            start,
+            scale: scale.max(0.0),
+            ramp_in,
+            hold,
+            ramp_out,
+        });
+    }
+
+    /// Stops all effects.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the factor the time scale gets multiplied with at the given moment, dropping finished effects.
+    pub fn factor(&mut self, now: Instant) -> f64 {
+        if self.hit_stop.is_some_and(|end| end <= now) {
+            self.hit_stop = None;
+        }
+        let slow_motion = self
+            .slow_motion
+            .as_ref()
+            .and_then(|slow_motion| slow_motion.factor(now));
+        if slow_motion.is_none() {
+            self.slow_motion = None;
+        }
+
+        if self.hit_stop.is_some() {
+            0.0
+        } else {
+            slow_motion.unwrap_or(1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn hit_stop_freezes_until_it_ends() {
+        let start = Instant::now();
+        let mut effects = TimeEffects::default();
+        effects.hit_stop(start + 100 * MS);
+        // A shorter hit stop does not cut the current one short.
+        effects.hit_stop(start + 50 * MS);
+
+        assert_eq!(effects.factor(start + 60 * MS), 0.0);
+        assert_eq!(effects.factor(start + 100 * MS), 1.0);
+        assert!(effects.hit_stop.is_none());
+    }
+
+    #[test]
+    fn slow_motion_ramps_in_and_out() {
+        let start = Instant::now();
+        let mut effects = TimeEffects::default();
+        effects.slow_motion(start, 0.2, 100 * MS, 100 * MS, 100 * MS);
+
+        assert!((effects.factor(start + 50 * MS) - 0.6).abs() < 1e-9);
+        assert_eq!(effects.factor(start + 150 * MS), 0.2);
+        assert!((effects.factor(start + 250 * MS) - 0.6).abs() < 1e-9);
+        assert_eq!(effects.factor(start + 300 * MS), 1.0);
+        assert!(effects.slow_motion.is_none());
+    }
+
+    #[test]
+    fn hit_stop_wins_over_slow_motion() {
+        let start = Instant::now();
+        let mut effects = TimeEffects::default();
+        effects.slow_motion(start, 0.5, Duration::ZERO, 100 * MS, Duration::ZERO);
+        effects.hit_stop(start + 10 * MS);
+
+        assert_eq!(effects.factor(start), 0.0);
+        assert_eq!(effects.factor(start + 20 * MS), 0.5);
+        effects.clear();
+        assert_eq!(effects.factor(start + 20 * MS), 1.0);
+    }
+}
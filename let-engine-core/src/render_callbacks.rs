@@ -0,0 +1,177 @@
+//! Recording own commands into the frames of the engine.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use anyhow::Result;
+use glam::{Mat4, Vec2};
+use parking_lot::Mutex;
+use vulkano::{
+    command_buffer::RecordingCommandBuffer,
+    pipeline::graphics::viewport::{Scissor, Viewport},
+    render_pass::Subpass,
+};
+
+use crate::{objects::scenes::Layer, resources::Loader};
+
+/// The point of a frame a render callback gets called at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderStage {
+    /// Before the render pass of the scene begins, in the primary command buffer.
+    ///
+    /// The place for drawing to own images with own render passes, like 3D models rendered to a texture.
+    BeforePass,
+    /// Before every view of a layer gets drawn, inside the scene subpass.
+    BeforeLayer,
+    /// After every view of a layer got drawn, inside the scene subpass.
+    AfterLayer,
+    /// After all layers got drawn, inside the scene subpass. The place for full screen effects drawn over the scene.
+    AfterScene,
+    /// After the render pass of the scene ended, in the primary command buffer.
+    AfterPass,
+}
+
+impl RenderStage {
+    /// Returns true if commands of this stage get recorded inside the scene subpass.
+    pub fn in_subpass(self) -> bool {
+        matches!(
+            self,
+            RenderStage::BeforeLayer | RenderStage::AfterLayer | RenderStage::AfterScene
+        )
+    }
+}
+
+/// The layer and camera view of a [`RenderStage::BeforeLayer`] or [`RenderStage::AfterLayer`] callback.
+pub struct LayerView<'a> {
+    pub layer: &'a Arc<Layer>,
+    /// The view matrix of the camera.
+    pub view: Mat4,
+    /// The projection matrix of the camera.
+    pub proj: Mat4,
+}
+
+/// Access to the command buffer of the current frame given to render callbacks.
+///
+/// Inside the scene subpass, the pipelines need to be made for [`subpass`](RenderContext::subpass)
+/// and the viewport and scissor have to be set before drawing, since the engine changes them for every camera.
+/// The scene subpass has the stencil buffer of masks as its depth stencil attachment, so those pipelines need a
+/// `depth_stencil_state`, which can be the default one in case they do not care about masks.
+/// The pipelines of materials also test the stencil buffer of masks, so drawing with them needs the stencil
+/// reference to be set, which is 0 outside of masks.
+/// Bound pipelines and descriptor sets do not need to be restored, because the engine binds its own for every draw.
+pub struct RenderContext<'a> {
+    pub(crate) command_buffer: &'a mut RecordingCommandBuffer,
+    pub(crate) loader: &'a Loader,
+    pub(crate) stage: RenderStage,
+    pub(crate) subpass: Subpass,
+    pub(crate) viewport: Viewport,
+    pub(crate) scissor: Scissor,
+    pub(crate) layer: Option<LayerView<'a>>,
+}
+
+impl<'a> RenderContext<'a> {
+    /// Returns the command buffer to record commands to.
+    ///
+    /// It is a secondary command buffer inside the scene subpass for the stages where
+    /// [`RenderStage::in_subpass`] is true, and the primary one of the frame otherwise.
+    pub fn command_buffer(&mut self) -> &mut RecordingCommandBuffer {
+        self.command_buffer
+    }
+
+    /// Returns the loader holding the allocators of the engine.
+    pub fn loader(&self) -> &Loader {
+        self.loader
+    }
+
+    /// Returns the stage this callback got called at.
+    pub fn stage(&self) -> RenderStage {
+        self.stage
+    }
+
+    /// Returns the scene subpass, which pipelines drawing inside of it have to be made for.
+    pub fn subpass(&self) -> &Subpass {
+        &self.subpass
+    }
+
+    /// Returns the viewport of the camera for layer stages and of the whole window otherwise.
+    pub fn viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// Returns the scissor of the camera for layer stages and of the whole window otherwise.
+    pub fn scissor(&self) -> &Scissor {
+        &self.scissor
+    }
+
+    /// Returns the size of the viewport in pixels.
+    pub fn dimensions(&self) -> Vec2 {
+        Vec2::from(self.viewport.extent)
+    }
+
+    /// Returns the layer and camera being drawn in layer stages.
+    pub fn layer(&self) -> Option<&LayerView<'a>> {
+        self.layer.as_ref()
+    }
+}
+
+type Callback = Box<dyn FnMut(&mut RenderContext) -> Result<()> + Send>;
+/// A callback shared with the frames running it, so the list stays unlocked while it runs.
+type SharedCallback = Arc<Mutex<Callback>>;
+
+/// Identifies a render callback to remove it again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RenderCallbackId(u64);
+
+/// The render callbacks registered in the graphics settings.
+#[derive(Default)]
+pub(crate) struct RenderCallbacks {
+    next: AtomicU64,
+    callbacks: Mutex<Vec<(RenderCallbackId, RenderStage, SharedCallback)>>,
+}
+
+impl RenderCallbacks {
+    pub fn add(&self, stage: RenderStage, callback: Callback) -> RenderCallbackId {
+        let id = RenderCallbackId(self.next.fetch_add(1, Ordering::Relaxed));
+        self.callbacks
+            .lock()
+            .push((id, stage, Arc::new(Mutex::new(callback))));
+        id
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.callbacks.lock().is_empty()
+    }
+
+    pub fn remove(&self, id: RenderCallbackId) -> bool {
+        let mut callbacks = self.callbacks.lock();
+        let len = callbacks.len();
+        callbacks.retain(|(callback, ..)| *callback != id);
+        callbacks.len() != len
+    }
+
+    /// Calls all callbacks of the stage of the given context in the order they got added.
+    ///
+    /// Failing callbacks get logged, so they do not stop the frame.
+    /// Callbacks can add and remove callbacks, which takes effect at the next stage they get run at.
+    pub fn run(&self, context: &mut RenderContext) {
+        let callbacks: Vec<SharedCallback> = self
+            .callbacks
+            .lock()
+            .iter()
+            .filter(|(_, stage, _)| *stage == context.stage)
+            .map(|(.., callback)| callback.clone())
+            .collect();
+        for callback in callbacks {
+            if let Err(error) = (callback.lock())(context) {
+                log::error!("A render callback at {:?} failed: {error}", context.stage);
+            }
+        }
+    }
+
+    /// Returns true if a callback is registered for the given stage.
+    pub fn has(&self, stage: RenderStage) -> bool {
+        self.callbacks.lock().iter().any(|(_, s, _)| *s == stage)
+    }
+}
@@ -0,0 +1,277 @@
+//! Buffers for the data of a single frame.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use anyhow::{Error, Result};
+use vulkano::{
+    buffer::{Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
+    device::DeviceOwned,
+    memory::allocator::{
+        AllocationCreateInfo, DeviceLayout, MemoryTypeFilter, StandardMemoryAllocator,
+    },
+    DeviceSize,
+};
+
+/// The size of the ring buffer before anything had to grow it.
+const INITIAL_CAPACITY: DeviceSize = 1 << 20;
+
+/// A persistently mapped buffer handing out the uniform and instance data of the frames,
+/// wrapping around to the beginning once the frames in flight using it finished.
+///
+/// Allocations go into the current frame until [`end_frame`](RingBuffer::end_frame) gets called after submitting it.
+/// Its part of the buffer gets reused after [`retire`](RingBuffer::retire) got called once the fence of the frame
+/// got signaled. In case a frame does not fit in the space left, the buffer grows to a new one twice the size,
+/// leaving the old one to the frames still using it.
+pub struct RingBuffer {
+    allocator: Arc<StandardMemoryAllocator>,
+    buffer: Subbuffer<[u8]>,
+    /// The alignment of every allocation, fitting uniform buffers and flushing non coherent memory.
+    alignment: DeviceSize,
+    ring: Ring,
+}
+
+impl RingBuffer {
+    pub(crate) fn new(allocator: Arc<StandardMemoryAllocator>) -> Result<Self> {
+        let properties = allocator.device().physical_device().properties();
+        let alignment = properties
+            .min_uniform_buffer_offset_alignment
+            .max(properties.non_coherent_atom_size)
+            .as_devicesize();
+        Ok(Self {
+            buffer: Self::create_buffer(&allocator, INITIAL_CAPACITY)?,
+            allocator,
+            alignment,
+            ring: Ring::new(INITIAL_CAPACITY),
+        })
+    }
+
+    fn create_buffer(
+        allocator: &Arc<StandardMemoryAllocator>,
+        size: DeviceSize,
+    ) -> Result<Subbuffer<[u8]>> {
+        Ok(Buffer::new_slice(
+            allocator.clone(),
+            BufferCreateInfo {
+                usage: BufferUsage::UNIFORM_BUFFER | BufferUsage::VERTEX_BUFFER,
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                ..Default::default()
+            },
+            size,
+        )?)
+    }
+
+    /// Allocates space for a value in the current frame.
+    pub fn allocate_sized<T: BufferContents>(&mut self) -> Result<Subbuffer<T>> {
+        let layout = T::LAYOUT.unwrap_sized();
+        Ok(self.allocate(layout)?.reinterpret())
+    }
+
+    /// Allocates space for the given number of values in the current frame.
+    pub fn allocate_slice<T: BufferContents>(&mut self, len: DeviceSize) -> Result<Subbuffer<[T]>> {
+        let layout = <[T]>::LAYOUT
+            .layout_for_len(len)
+            .ok_or(Error::msg("Can not allocate an empty slice."))?;
+        Ok(self.allocate(layout)?.reinterpret())
+    }
+
+    fn allocate(&mut self, layout: DeviceLayout) -> Result<Subbuffer<[u8]>> {
+        let size = layout.size();
+        let alignment = layout.alignment().as_devicesize().max(self.alignment);
+        let offset = match self.ring.allocate(size, alignment) {
+            Some(offset) => offset,
+            None => {
+                self.grow(size + alignment)?;
+                self.ring.allocate(size, alignment).ok_or(Error::msg(
+                    "The frame buffer could not grow to fit the data.",
+                ))?
+            }
+        };
+        Ok(self.buffer.clone().slice(offset..offset + size))
+    }
+
+    /// Replaces the buffer with a new one at least twice the size fitting the given size.
+    ///
+    /// The frames in flight keep the old buffer alive until they finished.
+    fn grow(&mut self, size: DeviceSize) -> Result<()> {
+        let capacity = (self.buffer.size() * 2).max(size.next_power_of_two());
+        self.buffer = Self::create_buffer(&self.allocator, capacity)?;
+        self.ring.reset(capacity);
+        Ok(())
+    }
+
+    /// Closes the allocations of the current frame after it got submitted, returning the ID to retire it with.
+    pub fn end_frame(&mut self) -> u64 {
+        self.ring.end_frame()
+    }
+
+    /// Frees the data of the frame with the given ID and all frames before it,
+    /// which have to be finished on the GPU.
+    pub fn retire(&mut self, frame: u64) {
+        self.ring.retire(frame);
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn capacity(&self) -> DeviceSize {
+        self.buffer.size()
+    }
+}
+
+/// The ranges of a ring buffer in use by the frames, without the buffer itself.
+struct Ring {
+    size: DeviceSize,
+    /// Where the next allocation starts.
+    head: DeviceSize,
+    /// Where the data of the oldest frame in flight starts.
+    tail: DeviceSize,
+    /// Where the data of the current frame starts.
+    frame_start: DeviceSize,
+    /// The ID and end of the data of every submitted frame not retired yet, oldest first.
+    frames: VecDeque<(u64, DeviceSize)>,
+    next_frame: u64,
+}
+
+impl Ring {
+    fn new(size: DeviceSize) -> Self {
+        Self {
+            size,
+            head: 0,
+            tail: 0,
+            frame_start: 0,
+            frames: VecDeque::new(),
+            next_frame: 0,
+        }
+    }
+
+    /// Returns the offset of a free range of the given size and takes it,
+    /// wrapping around in case the end of the buffer is reached.
+    fn allocate(&mut self, size: DeviceSize, alignment: DeviceSize) -> Option<DeviceSize> {
+        let offset = self.fit(size, alignment)?;
+        self.head = offset + size;
+        Some(offset)
+    }
+
+    fn fit(&self, size: DeviceSize, alignment: DeviceSize) -> Option<DeviceSize> {
+        let start = self.head.next_multiple_of(alignment);
+        if self.head >= self.tail {
+            // The free space is behind the head and in front of the tail.
+            if start + size <= self.size {
+                return Some(start);
+            }
+            // The head must not reach the tail, because that is how an empty buffer looks.
+            (size < self.tail).then_some(0)
+        } else {
+            (start + size < self.tail).then_some(start)
+        }
+    }
+
+    /// Starts over with an empty buffer of the given size, forgetting the frames using the previous one.
+    fn reset(&mut self, size: DeviceSize) {
+        self.size = size;
+        self.head = 0;
+        self.tail = 0;
+        self.frame_start = 0;
+        self.frames.clear();
+    }
+
+    fn end_frame(&mut self) -> u64 {
+        let frame = self.next_frame;
+        self.next_frame += 1;
+        self.frames.push_back((frame, self.head));
+        self.frame_start = self.head;
+        frame
+    }
+
+    fn retire(&mut self, frame: u64) {
+        while let Some(&(id, end)) = self.frames.front() {
+            if id > frame {
+                break;
+            }
+            self.tail = end;
+            self.frames.pop_front();
+        }
+        // Starts at the beginning again once nothing is in use.
+        if self.frames.is_empty() && self.head == self.frame_start {
+            self.head = 0;
+            self.tail = 0;
+            self.frame_start = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allocations_get_aligned() {
+        let mut ring = Ring::new(1024);
+        assert_eq!(ring.allocate(10, 64), Some(0));
+        assert_eq!(ring.allocate(10, 64), Some(64));
+        assert_eq!(ring.allocate(100, 256), Some(256));
+        assert_eq!(ring.head, 356);
+    }
+
+    #[test]
+    fn frames_in_flight_do_not_get_overwritten() {
+        let mut ring = Ring::new(256);
+        ring.allocate(200, 1).unwrap();
+        let first = ring.end_frame();
+
+        // The end is full and the start is used by the first frame.
+        assert_eq!(ring.allocate(100, 1), None);
+
+        ring.retire(first);
+        assert_eq!(ring.allocate(100, 1), Some(0));
+    }
+
+    #[test]
+    fn wraps_around_behind_retired_frames() {
+        let mut ring = Ring::new(256);
+        ring.allocate(100, 1).unwrap();
+        let first = ring.end_frame();
+        ring.allocate(100, 1).unwrap();
+        let second = ring.end_frame();
+        ring.retire(first);
+
+        // Does not fit behind the second frame, but in front of it where the first one was.
+        assert_eq!(ring.allocate(80, 1), Some(0));
+        // The head must not reach the tail.
+        assert_eq!(ring.allocate(20, 1), None);
+        assert_eq!(ring.allocate(19, 1), Some(80));
+
+        ring.end_frame();
+        ring.retire(second);
+        assert_eq!(ring.tail, 200);
+        assert_eq!(ring.allocate(100, 1), Some(99));
+    }
+
+    #[test]
+    fn retiring_everything_starts_over() {
+        let mut ring = Ring::new(256);
+        ring.allocate(100, 1).unwrap();
+        ring.end_frame();
+        let last = ring.end_frame();
+        ring.retire(last);
+
+        assert!(ring.frames.is_empty());
+        assert_eq!((ring.head, ring.tail, ring.frame_start), (0, 0, 0));
+    }
+
+    #[test]
+    fn reset_forgets_old_frames() {
+        let mut ring = Ring::new(256);
+        ring.allocate(200, 1).unwrap();
+        let old = ring.end_frame();
+        ring.reset(512);
+
+        assert_eq!(ring.allocate(400, 1), Some(0));
+        // Frames of the old buffer do not move the tail of the new one.
+        ring.retire(old);
+        assert_eq!(ring.tail, 0);
+        assert_eq!(ring.allocate(100, 1), Some(400));
+    }
+}
@@ -6,6 +6,7 @@ use thiserror::Error;
 use vulkano::{buffer::BufferContents, pipeline::graphics::vertex_input::Vertex as VTX};
 
 use super::{Loader, ModelData};
+use crate::objects::{light::Light, Color};
 use parking_lot::Mutex;
 use std::sync::Arc;
 
@@ -54,6 +55,19 @@ pub(crate) struct ModelViewProj {
     pub proj: Mat4,
 }
 
+/// The push constants given to every material declaring them, laid out as described in [`Shaders`](super::materials::Shaders).
+///
+/// The camera is an array instead of a matrix, so the size matches the block declared in the shaders.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BufferContents)]
+pub(crate) struct EngineConstants {
+    pub camera: [[f32; 4]; 4],
+    pub resolution: [f32; 2],
+    pub time: f32,
+    pub delta_time: f32,
+    pub object_id: u32,
+}
+
 /// Default instance data.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, BufferContents, VTX)]
@@ -75,6 +89,11 @@ pub(crate) struct InstanceData {
 pub(crate) struct ObjectFrag {
     pub color: Vec4,
     pub texture_id: u32,
+    pub distance_range: f32,
+    pub outline_width: f32,
+    pub glow_width: f32,
+    pub outline_color: Vec4,
+    pub glow_color: Vec4,
 }
 
 impl Default for ObjectFrag {
@@ -82,8 +101,75 @@ impl Default for ObjectFrag {
         Self {
             color: Vec4::splat(0.0),
             texture_id: 0,
+            distance_range: 0.0,
+            outline_width: 0.0,
+            glow_width: 0.0,
+            outline_color: Vec4::splat(0.0),
+            glow_color: Vec4::splat(0.0),
+        }
+    }
+}
+
+/// A single light as read by the lit fragment shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, BufferContents)]
+pub(crate) struct LightFrag {
+    /// The position in `xy`, the height in `z` and the radius in `w`.
+    pub position: Vec4,
+    /// The color multiplied with the intensity.
+    pub color: Vec4,
+}
+
+/// The lights of a layer as read by the lit fragment shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BufferContents)]
+pub(crate) struct LightsFrag {
+    pub ambient: Vec4,
+    pub count: u32,
+    pub _padding: [u32; 3],
+    pub lights: [LightFrag; crate::objects::light::MAX_LIGHTS],
+}
+
+impl LightsFrag {
+    pub fn new(ambient: Color, lights: &[Light]) -> Self {
+        let mut frag = Self {
+            ambient: ambient.into(),
+            count: lights.len().min(crate::objects::light::MAX_LIGHTS) as u32,
+            _padding: [0; 3],
+            lights: [LightFrag::default(); crate::objects::light::MAX_LIGHTS],
+        };
+        for (frag, light) in frag.lights.iter_mut().zip(lights) {
+            frag.position = light.position.extend(light.height).extend(light.radius);
+            frag.color = Vec4::from(light.color) * light.intensity;
+        }
+        frag
+    }
+}
+
+/// The borders of a nine-slice texture in pixels, which keep their size when the model gets stretched.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Insets {
+    /// Creates new insets with the given borders.
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
         }
     }
+
+    /// Creates new insets with the same border on every side.
+    pub const fn uniform(border: f32) -> Self {
+        Self::new(border, border, border, border)
+    }
 }
 
 /// Vertex and index data for the appearance and shape of objects.
@@ -138,6 +224,53 @@ impl Data {
         }
     }
 
+    /// Returns the data of a square from `-1.0` to `1.0` split into 9 parts, which stretches the center of a texture
+    /// while keeping the borders at a fixed size.
+    ///
+    /// `size` is the size the model gets shown at and `texture_size` the size of the texture, both in pixels.
+    /// The insets are the borders of the texture in pixels. In case the borders do not fit into the size they get shrunk evenly.
+    pub fn nine_slice(size: Vec2, texture_size: Vec2, insets: Insets) -> Self {
+        // The model spans 2 units across the size.
+        let scale = 2.0 / size.max(Vec2::splat(f32::EPSILON));
+        let mut start = vec2(insets.left, insets.top) * scale;
+        let mut end = vec2(insets.right, insets.bottom) * scale;
+        let overflow = ((start + end) * 0.5).max(Vec2::ONE);
+        start /= overflow;
+        end /= overflow;
+
+        let texture_size = texture_size.max(Vec2::ONE);
+        let xs = [-1.0, -1.0 + start.x, 1.0 - end.x, 1.0];
+        let ys = [-1.0, -1.0 + start.y, 1.0 - end.y, 1.0];
+        let us = [
+            0.0,
+            insets.left / texture_size.x,
+            1.0 - insets.right / texture_size.x,
+            1.0,
+        ];
+        let vs = [
+            0.0,
+            insets.top / texture_size.y,
+            1.0 - insets.bottom / texture_size.y,
+            1.0,
+        ];
+
+        // Texture positions go from -1 to 1 like the positions, being mapped to the texture in the shaders.
+        let mut vertices = Vec::with_capacity(16);
+        for y in 0..4 {
+            for x in 0..4 {
+                vertices.push(tvert(xs[x], ys[y], us[x] * 2.0 - 1.0, vs[y] * 2.0 - 1.0));
+            }
+        }
+        let mut indices = Vec::with_capacity(54);
+        for row in 0..3 {
+            for column in 0..3 {
+                let i = row * 4 + column;
+                indices.extend_from_slice(&[i, i + 1, i + 4, i + 1, i + 4, i + 5]);
+            }
+        }
+        Data::Dynamic { vertices, indices }
+    }
+
     /// Returns if the data has an empty field.
     pub fn is_empty(&self) -> bool {
         match self {
@@ -172,8 +305,8 @@ pub struct BasicShapes {
 impl BasicShapes {
     pub fn new(loader: &Arc<Mutex<Loader>>) -> Result<Self> {
         Ok(Self {
-            square: ModelData::new_from_loader(Data::square(), loader)?,
-            triangle: ModelData::new_from_loader(Data::triangle(), loader)?,
+            square: ModelData::new_from_loader(Data::square(), Some("square"), loader)?,
+            triangle: ModelData::new_from_loader(Data::triangle(), Some("triangle"), loader)?,
         })
     }
 }
@@ -190,3 +323,21 @@ const SQUARE: [Vertex; 4] = [
     vert(1.0, 1.0),
 ];
 const SQUARE_ID: [u32; 6] = [0, 1, 2, 1, 2, 3];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nine_slice_corners_match_the_square() {
+        let data = Data::nine_slice(vec2(200.0, 100.0), vec2(64.0, 64.0), Insets::uniform(16.0));
+        let vertices = data.vertices();
+
+        for (corner, index) in SQUARE.iter().zip([0, 3, 12, 15]) {
+            assert_eq!(vertices[index], *corner);
+        }
+        // The inner corner of the top left border is the inset in the texture.
+        assert_eq!(vertices[5].tex_position, vec2(-0.5, -0.5));
+        assert_eq!(data.indices().len(), 54);
+    }
+}
@@ -0,0 +1,96 @@
+//! Compiling GLSL to SpirV at runtime.
+
+use std::fmt::Display;
+
+use shaderc::{CompileOptions, Compiler, ShaderKind};
+
+use crate::draw::ShaderError;
+
+/// The stage of a shader written in GLSL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GlslStage {
+    Vertex,
+    Fragment,
+}
+
+impl Display for GlslStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GlslStage::Vertex => f.write_str("vertex shader"),
+            GlslStage::Fragment => f.write_str("fragment shader"),
+        }
+    }
+}
+
+/// A problem the GLSL compiler found in a shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlslError {
+    pub stage: GlslStage,
+    /// The line in the source the problem is at, starting from 1.
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl Display for GlslError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {}: {}", self.stage, line, self.message),
+            None => write!(f, "{}: {}", self.stage, self.message),
+        }
+    }
+}
+
+/// Compiles the GLSL source of the given stage to SpirV words.
+pub(crate) fn compile(
+    source: &str,
+    stage: GlslStage,
+    entry_point: &str,
+) -> Result<Vec<u32>, ShaderError> {
+    let compiler = Compiler::new().ok_or(ShaderError::CompilerUnavailable)?;
+    let mut options = CompileOptions::new().ok_or(ShaderError::CompilerUnavailable)?;
+    options.add_macro_definition("EP", Some(entry_point));
+    let kind = match stage {
+        GlslStage::Vertex => ShaderKind::Vertex,
+        GlslStage::Fragment => ShaderKind::Fragment,
+    };
+    let name = match stage {
+        GlslStage::Vertex => "vertex",
+        GlslStage::Fragment => "fragment",
+    };
+
+    match compiler.compile_into_spirv(source, kind, name, entry_point, Some(&options)) {
+        Ok(artifact) => Ok(artifact.as_binary().to_vec()),
+        Err(shaderc::Error::CompilationError(_, messages)) => {
+            Err(ShaderError::Compile(parse_errors(&messages, stage)))
+        }
+        Err(error) => Err(ShaderError::Compile(vec![GlslError {
+            stage,
+            line: None,
+            message: error.to_string(),
+        }])),
+    }
+}
+
+/// Splits the output of the compiler looking like `vertex:12: error: 'x' : undeclared identifier` into errors.
+fn parse_errors(messages: &str, stage: GlslStage) -> Vec<GlslError> {
+    messages
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.ends_with("generated."))
+        .map(|line| {
+            let mut parts = line.splitn(3, ':');
+            let _name = parts.next();
+            match (parts.next().map(|line| line.trim().parse()), parts.next()) {
+                (Some(Ok(number)), Some(message)) => GlslError {
+                    stage,
+                    line: Some(number),
+                    message: message.trim().to_string(),
+                },
+                _ => GlslError {
+                    stage,
+                    line: None,
+                    message: line.trim().to_string(),
+                },
+            }
+        })
+        .collect()
+}
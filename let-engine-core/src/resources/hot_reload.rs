@@ -0,0 +1,138 @@
+//! Reloading the shaders of materials when their files change, so shaders can be iterated on without restarting the game.
+//!
+//! # Usage
+//! ```ignore
+//! let shaders = unsafe { Shaders::from_spirv_files("shaders/water.vert.spv", "shaders/water.frag.spv", "main")? };
+//! let material = Material::new_with_shaders(settings, None, &shaders, false, vec![])?;
+//! unsafe {
+//!     SHADER_WATCHER.watch(&material, "shaders/water.vert.spv", "shaders/water.frag.spv", "main");
+//! }
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+    time::SystemTime,
+};
+
+use parking_lot::Mutex;
+
+use super::materials::{Material, Shaders};
+use crate::draw::ShaderError;
+
+/// The watcher of all shader files registered for reloading.
+pub static SHADER_WATCHER: LazyLock<ShaderWatcher> = LazyLock::new(ShaderWatcher::new);
+
+/// The outcome of reloading the shaders of a watched material.
+#[derive(Clone, Debug)]
+pub struct ShaderReload {
+    /// The name of the material given in its settings.
+    pub name: Option<String>,
+    pub vertex: PathBuf,
+    pub fragment: PathBuf,
+    /// The reason the new shaders could not be used, in which case the material keeps its previous shaders.
+    pub error: Option<Arc<str>>,
+}
+
+struct Watched {
+    material: Material,
+    vertex: PathBuf,
+    fragment: PathBuf,
+    entry_point: String,
+    modified: [Option<SystemTime>; 2],
+}
+
+/// Checks the files of watched materials for changes and swaps their shaders.
+pub struct ShaderWatcher {
+    watched: Mutex<Vec<Watched>>,
+}
+
+impl ShaderWatcher {
+    fn new() -> Self {
+        Self {
+            watched: Mutex::new(vec![]),
+        }
+    }
+
+    /// Reloads the shaders of the material from the given files every time one of them changes.
+    ///
+    /// With the `glsl` feature files not ending with `.spv` get compiled as GLSL.
+    ///
+    /// # Safety
+    ///
+    /// Changed SpirV files get loaded using [`Shaders::from_spirv_files`] with the same requirements.
+    pub unsafe fn watch(
+        &self,
+        material: &Material,
+        vertex: impl Into<PathBuf>,
+        fragment: impl Into<PathBuf>,
+        entry_point: &str,
+    ) {
+        let vertex = vertex.into();
+        let fragment = fragment.into();
+        let modified = [modified(&vertex), modified(&fragment)];
+        self.watched.lock().push(Watched {
+            material: material.clone(),
+            vertex,
+            fragment,
+            entry_point: entry_point.to_string(),
+            modified,
+        });
+    }
+
+    /// Stops watching the files of the material.
+    pub fn unwatch(&self, material: &Material) {
+        self.watched
+            .lock()
+            .retain(|watched| watched.material != *material);
+    }
+
+    /// Reloads the shaders of every material whose files changed since the last poll.
+    ///
+    /// Gets called by the engine every frame.
+    pub fn poll(&self) -> Vec<ShaderReload> {
+        let mut reloads = vec![];
+        for watched in self.watched.lock().iter_mut() {
+            let current = [modified(&watched.vertex), modified(&watched.fragment)];
+            // Files in the middle of getting saved can be missing for a moment.
+            if current == watched.modified || current.contains(&None) {
+                continue;
+            }
+            watched.modified = current;
+
+            let result = unsafe { load(&watched.vertex, &watched.fragment, &watched.entry_point) }
+                .map_err(anyhow::Error::from)
+                .and_then(|shaders| watched.material.replace_shaders(shaders));
+
+            reloads.push(ShaderReload {
+                name: watched.material.name().map(str::to_string),
+                vertex: watched.vertex.clone(),
+                fragment: watched.fragment.clone(),
+                error: result.err().map(|error| format!("{error:#}").into()),
+            });
+        }
+        reloads
+    }
+}
+
+/// Loads SpirV files, or compiles GLSL sources in case they do not end with `.spv` and the `glsl` feature is enabled.
+unsafe fn load(vertex: &Path, fragment: &Path, entry_point: &str) -> Result<Shaders, ShaderError> {
+    #[cfg(feature = "glsl")]
+    if vertex
+        .extension()
+        .map_or(true, |extension| extension != "spv")
+    {
+        return Shaders::from_glsl(
+            &std::fs::read_to_string(vertex)?,
+            &std::fs::read_to_string(fragment)?,
+            entry_point,
+        );
+    }
+    unsafe { Shaders::from_spirv_files(vertex, fragment, entry_point) }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
@@ -1,3 +1,4 @@
+use super::buffer::RingBuffer;
 use super::Vulkan;
 use anyhow::{Error, Result};
 use std::sync::Arc;
@@ -5,27 +6,28 @@ use vulkano::{
     buffer::{allocator::*, Buffer, BufferContents, BufferCreateInfo, BufferUsage, Subbuffer},
     command_buffer::{
         allocator::{StandardCommandBufferAllocator, StandardCommandBufferAllocatorCreateInfo},
-        CommandBufferBeginInfo, CommandBufferUsage, CopyBufferToImageInfo, RecordingCommandBuffer,
+        BlitImageInfo, BufferImageCopy, CommandBufferBeginInfo, CommandBufferLevel,
+        CommandBufferUsage, CopyBufferInfo, CopyBufferToImageInfo, RecordingCommandBuffer,
     },
     descriptor_set::{
         allocator::{StandardDescriptorSetAllocator, StandardDescriptorSetAllocatorCreateInfo},
         DescriptorSet, WriteDescriptorSet,
     },
-    format::Format,
+    format::{Format, FormatFeatures},
     image::{
-        sampler::Sampler,
+        sampler::{Filter, Sampler},
         view::{ImageView, ImageViewCreateInfo, ImageViewType},
-        Image, ImageCreateInfo, ImageType, ImageUsage,
+        Image, ImageBlit, ImageCreateFlags, ImageCreateInfo, ImageSubresourceLayers, ImageType,
+        ImageUsage,
     },
     memory::allocator::{AllocationCreateInfo, MemoryTypeFilter, StandardMemoryAllocator},
-    pipeline::{
-        cache::{PipelineCache, PipelineCacheCreateInfo},
-        GraphicsPipeline, Pipeline,
-    },
+    pipeline::{cache::PipelineCache, GraphicsPipeline, Pipeline},
+    sync::{GpuFuture, Sharing},
     DeviceSize,
 };
 
-use super::textures::{Format as tFormat, TextureSettings};
+use super::data::{Data, Vertex};
+use super::textures::{Format as tFormat, Sampler as tSampler, TextureKind, TextureSettings};
 
 /// Loads thing to the gpu.
 pub struct Loader {
@@ -33,11 +35,80 @@ pub struct Loader {
     pub vertex_buffer_allocator: SubbufferAllocator,
     pub index_buffer_allocator: SubbufferAllocator,
     pub object_buffer_allocator: SubbufferAllocator,
-    pub instance_buffer_allocator: SubbufferAllocator,
+    pub staging_buffer_allocator: SubbufferAllocator,
+    /// The uniform and instance data of the frames.
+    pub frame_buffer: RingBuffer,
     pub descriptor_set_allocator: Arc<StandardDescriptorSetAllocator>,
     pub command_buffer_allocator: Arc<StandardCommandBufferAllocator>,
     pub pipeline_cache: Arc<PipelineCache>,
+    /// Keeps the pipelines of materials alive, which only hold weak pointers to them.
+    ///
+    /// Pipelines no material points to anymore get freed by [`free_unused_pipelines`](Loader::free_unused_pipelines),
+    /// unless they got pinned using [`pin_pipeline`](Loader::pin_pipeline).
     pub pipelines: Vec<Arc<GraphicsPipeline>>,
+    /// Pipelines managed by the game, kept alive until they get unpinned.
+    pinned_pipelines: Vec<Arc<GraphicsPipeline>>,
+    /// Submitted uploads the next frame has to wait for.
+    uploads: Vec<Box<dyn GpuFuture + Send + Sync>>,
+}
+
+/// The GPU side objects of a texture loaded using [`Loader::record_texture`].
+#[derive(Clone)]
+pub struct LoadedTexture {
+    /// The descriptor set binding the texture to the texture set of the default materials.
+    pub set: Arc<DescriptorSet>,
+    pub image: Arc<Image>,
+    pub view: Arc<ImageView>,
+    pub sampler: Arc<Sampler>,
+}
+
+/// Commands uploading resources to the GPU, started using [`Loader::begin_uploads`]
+/// and submitted using [`Loader::submit_uploads`].
+///
+/// Copies run on the transfer queue. Blits, like generating mipmaps, can only run on the graphics queue,
+/// so they get recorded into a second command buffer executed after the copies.
+pub struct Uploads {
+    transfer: RecordingCommandBuffer,
+    graphics: Option<RecordingCommandBuffer>,
+    graphics_family: u32,
+    /// False in case uploads run on the graphics queue, sharing its command buffer.
+    separate: bool,
+    allocator: Arc<StandardCommandBufferAllocator>,
+}
+
+impl Uploads {
+    /// Returns the command buffer for copies, executed on the transfer queue.
+    pub fn transfer(&mut self) -> &mut RecordingCommandBuffer {
+        &mut self.transfer
+    }
+
+    /// Returns the command buffer for the commands only the graphics queue can execute, like blits.
+    ///
+    /// It is the same as [`transfer`](Uploads::transfer) in case uploads run on the graphics queue.
+    pub fn graphics(&mut self) -> Result<&mut RecordingCommandBuffer> {
+        if !self.separate {
+            return Ok(&mut self.transfer);
+        }
+        if self.graphics.is_none() {
+            self.graphics = Some(begin(&self.allocator, self.graphics_family)?);
+        }
+        Ok(self.graphics.as_mut().unwrap())
+    }
+}
+
+fn begin(
+    allocator: &Arc<StandardCommandBufferAllocator>,
+    queue_family_index: u32,
+) -> Result<RecordingCommandBuffer> {
+    Ok(RecordingCommandBuffer::new(
+        allocator.clone(),
+        queue_family_index,
+        CommandBufferLevel::Primary,
+        CommandBufferBeginInfo {
+            usage: CommandBufferUsage::OneTimeSubmit,
+            ..Default::default()
+        },
+    )?)
 }
 
 impl Loader {
@@ -75,15 +146,18 @@ impl Loader {
             },
         );
 
-        let instance_buffer_allocator: SubbufferAllocator = SubbufferAllocator::new(
+        let staging_buffer_allocator: SubbufferAllocator = SubbufferAllocator::new(
             memory_allocator.clone(),
             SubbufferAllocatorCreateInfo {
-                buffer_usage: BufferUsage::VERTEX_BUFFER,
-                memory_type_filter: MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
+                buffer_usage: BufferUsage::TRANSFER_SRC,
+                memory_type_filter: MemoryTypeFilter::PREFER_HOST
+                    | MemoryTypeFilter::HOST_SEQUENTIAL_WRITE,
                 ..Default::default()
             },
         );
 
+        let frame_buffer = RingBuffer::new(memory_allocator.clone())?;
+
         let descriptor_set_allocator = StandardDescriptorSetAllocator::new(
             vulkan.device.clone(),
             StandardDescriptorSetAllocatorCreateInfo::default(),
@@ -99,24 +173,28 @@ impl Loader {
         )
         .into();
 
-        let pipeline_cache = unsafe {
-            PipelineCache::new(vulkan.device.clone(), PipelineCacheCreateInfo::default())?
-        };
+        let pipeline_cache = super::pipeline_cache::load(vulkan)?;
 
         Ok(Self {
             memory_allocator,
             vertex_buffer_allocator,
             index_buffer_allocator,
             object_buffer_allocator,
-            instance_buffer_allocator,
+            staging_buffer_allocator,
+            frame_buffer,
             descriptor_set_allocator,
             command_buffer_allocator,
             pipeline_cache,
             pipelines,
+            pinned_pipelines: vec![],
+            uploads: vec![],
         })
     }
 
     /// Loads a texture to the GPU.
+    ///
+    /// The optional name gets attached to the Vulkan objects of this texture for debugging purposes.
+    #[allow(clippy::too_many_arguments)]
     pub fn load_texture(
         &mut self,
         vulkan: &Vulkan,
@@ -125,22 +203,226 @@ impl Loader {
         layers: u32,
         format: tFormat,
         settings: TextureSettings,
-    ) -> Result<Arc<DescriptorSet>> {
-        if dimensions.0 * dimensions.1 * format as u32 > data.len() as u32 {
-            return Err(Error::msg(
-                "The size of the texture is smaller than the provided texture dimensions.",
-            ));
+        name: Option<&str>,
+    ) -> Result<LoadedTexture> {
+        let mut uploads = self.begin_uploads(vulkan)?;
+        let texture = self.record_texture(
+            vulkan,
+            &mut uploads,
+            data,
+            dimensions,
+            layers,
+            format,
+            settings,
+            name,
+        )?;
+
+        self.submit_uploads(vulkan, uploads)?;
+        Ok(texture)
+    }
+
+    /// Overwrites a region of one layer of a loaded texture with the given pixels.
+    ///
+    /// The pixels get copied to a staging buffer first, which gets reused once the copy is done.
+    pub fn write_texture_region(
+        &mut self,
+        vulkan: &Vulkan,
+        image: &Arc<Image>,
+        offset: [u32; 2],
+        extent: [u32; 2],
+        layer: u32,
+        data: &[u8],
+    ) -> Result<()> {
+        let size = image
+            .format()
+            .block_size()
+            .checked_mul(extent[0] as DeviceSize)
+            .and_then(|size| size.checked_mul(extent[1] as DeviceSize))
+            .ok_or_else(|| Error::msg(format!("The region {extent:?} is too large.")))?;
+        if data.len() as DeviceSize != size {
+            return Err(Error::msg(format!(
+                "The region needs {size} bytes, but {} were given.",
+                data.len()
+            )));
         }
 
-        let mut uploads = RecordingCommandBuffer::new(
-            self.command_buffer_allocator.clone(),
-            vulkan.queue.queue_family_index(),
-            vulkano::command_buffer::CommandBufferLevel::Primary,
-            CommandBufferBeginInfo {
-                usage: CommandBufferUsage::OneTimeSubmit,
+        let staging = self.staging_buffer_allocator.allocate_slice::<u8>(size)?;
+        staging.write()?.copy_from_slice(data);
+
+        let mut uploads = self.begin_uploads(vulkan)?;
+        uploads
+            .transfer()
+            .copy_buffer_to_image(CopyBufferToImageInfo {
+                regions: [BufferImageCopy {
+                    image_subresource: ImageSubresourceLayers {
+                        array_layers: layer..layer + 1,
+                        ..image.subresource_layers()
+                    },
+                    image_offset: [offset[0], offset[1], 0],
+                    image_extent: [extent[0], extent[1], 1],
+                    ..Default::default()
+                }]
+                .into(),
+                ..CopyBufferToImageInfo::buffer_image(staging, image.clone())
+            })?;
+        record_mipmaps(uploads.graphics()?, image)?;
+        self.submit_uploads(vulkan, uploads)
+    }
+
+    /// Starts command buffers for uploading resources to the GPU in a single submission.
+    pub fn begin_uploads(&self, vulkan: &Vulkan) -> Result<Uploads> {
+        Ok(Uploads {
+            transfer: begin(
+                &self.command_buffer_allocator,
+                vulkan.transfer_queue.queue_family_index(),
+            )?,
+            graphics: None,
+            graphics_family: vulkan.queue.queue_family_index(),
+            separate: vulkan.separate_transfer(),
+            allocator: self.command_buffer_allocator.clone(),
+        })
+    }
+
+    /// Submits the uploads without waiting for them to finish.
+    ///
+    /// The next frame waits for them using a semaphore, so big uploads do not stall the thread submitting them.
+    pub fn submit_uploads(&mut self, vulkan: &Vulkan, uploads: Uploads) -> Result<()> {
+        let transfer = uploads
+            .transfer
+            .end()?
+            .execute(vulkan.transfer_queue.clone())?;
+        let future = match uploads.graphics {
+            Some(graphics) => transfer
+                .then_signal_semaphore()
+                .then_execute(vulkan.queue.clone(), graphics.end()?)?
+                .then_signal_semaphore_and_flush()?
+                .boxed_send_sync(),
+            None => transfer
+                .then_signal_semaphore_and_flush()?
+                .boxed_send_sync(),
+        };
+        self.uploads.push(future);
+        Ok(())
+    }
+
+    /// Frees the pipelines of dropped materials.
+    ///
+    /// Frames still in flight keep the pipelines they use alive until they are finished.
+    pub(crate) fn free_unused_pipelines(&mut self) {
+        retain_used(&mut self.pipelines, &self.pinned_pipelines);
+    }
+
+    /// Keeps the pipeline alive until it gets unpinned, even without a material pointing to it.
+    ///
+    /// Meant for pipelines the game manages by hand instead of through materials.
+    pub fn pin_pipeline(&mut self, pipeline: &Arc<GraphicsPipeline>) {
+        if !self
+            .pinned_pipelines
+            .iter()
+            .any(|pinned| Arc::ptr_eq(pinned, pipeline))
+        {
+            self.pinned_pipelines.push(pipeline.clone());
+        }
+    }
+
+    /// Lets the pipeline get freed again once no material points to it anymore.
+    pub fn unpin_pipeline(&mut self, pipeline: &Arc<GraphicsPipeline>) {
+        self.pinned_pipelines
+            .retain(|pinned| !Arc::ptr_eq(pinned, pipeline));
+    }
+
+    /// Returns true if uploads got submitted since the last frame.
+    pub(crate) fn has_uploads(&self) -> bool {
+        !self.uploads.is_empty()
+    }
+
+    /// Returns the uploads submitted since the last call, for the next frame to wait for.
+    pub(crate) fn take_uploads(&mut self) -> Vec<Box<dyn GpuFuture + Send + Sync>> {
+        std::mem::take(&mut self.uploads)
+    }
+
+    /// Creates the device local vertex and index buffers of a model and records copying its data into them.
+    ///
+    /// The buffers can only be used after the uploads got submitted.
+    pub fn record_model(
+        &self,
+        vulkan: &Vulkan,
+        uploads: &mut Uploads,
+        data: &Data,
+    ) -> Result<(Subbuffer<[Vertex]>, Subbuffer<[u32]>)> {
+        Ok((
+            self.record_buffer(vulkan, uploads, data.vertices(), BufferUsage::VERTEX_BUFFER)?,
+            self.record_buffer(vulkan, uploads, data.indices(), BufferUsage::INDEX_BUFFER)?,
+        ))
+    }
+
+    /// Creates a device local buffer and records copying the data into it through a staging buffer.
+    fn record_buffer<T: BufferContents + Copy>(
+        &self,
+        vulkan: &Vulkan,
+        uploads: &mut Uploads,
+        data: &[T],
+        usage: BufferUsage,
+    ) -> Result<Subbuffer<[T]>> {
+        let staging = self
+            .staging_buffer_allocator
+            .allocate_slice::<T>(data.len() as DeviceSize)?;
+        staging.write()?.copy_from_slice(data);
+
+        let buffer = Buffer::new_slice::<T>(
+            self.memory_allocator.clone(),
+            BufferCreateInfo {
+                usage: usage | BufferUsage::TRANSFER_DST,
+                sharing: if vulkan.separate_transfer() {
+                    Sharing::Concurrent(
+                        [
+                            vulkan.queue.queue_family_index(),
+                            vulkan.transfer_queue.queue_family_index(),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    )
+                } else {
+                    Sharing::Exclusive
+                },
+                ..Default::default()
+            },
+            AllocationCreateInfo {
+                memory_type_filter: MemoryTypeFilter::PREFER_DEVICE,
                 ..Default::default()
             },
+            data.len() as DeviceSize,
         )?;
+        uploads
+            .transfer()
+            .copy_buffer(CopyBufferInfo::buffers(staging, buffer.clone()))?;
+        Ok(buffer)
+    }
+
+    /// Creates a texture and records its upload into the given uploads.
+    ///
+    /// The texture can only be used after the uploads got submitted.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record_texture(
+        &mut self,
+        vulkan: &Vulkan,
+        uploads: &mut Uploads,
+        data: Arc<[u8]>,
+        dimensions: (u32, u32),
+        layers: u32,
+        format: tFormat,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<LoadedTexture> {
+        if dimensions.0 * dimensions.1 * format as u32 > data.len() as u32 {
+            return Err(Error::msg(
+                "The size of the texture is smaller than the provided texture dimensions.",
+            ));
+        }
+        let cubemap = settings.kind == TextureKind::Cubemap;
+        if cubemap && (layers != 6 || dimensions.0 != dimensions.1) {
+            return Err(Error::msg("A cubemap needs 6 square layers."));
+        }
 
         let format = if settings.srgb {
             match format {
@@ -156,6 +438,26 @@ impl Loader {
             }
         };
 
+        let mip_levels = if settings.mipmaps {
+            let features = vulkan
+                .device
+                .physical_device()
+                .format_properties(format)?
+                .optimal_tiling_features;
+            if features.contains(
+                FormatFeatures::BLIT_SRC
+                    | FormatFeatures::BLIT_DST
+                    | FormatFeatures::SAMPLED_IMAGE_FILTER_LINEAR,
+            ) {
+                dimensions.0.max(dimensions.1).max(1).ilog2() + 1
+            } else {
+                log::warn!("Mipmaps can not be generated for textures of the format {format:?} on this GPU.");
+                1
+            }
+        } else {
+            1
+        };
+
         let upload_buffer: Subbuffer<[u8]> = Buffer::new_slice(
             self.memory_allocator.clone(),
             BufferCreateInfo {
@@ -175,77 +477,103 @@ impl Loader {
                 * layers as DeviceSize,
         )?;
         upload_buffer.write()?.copy_from_slice(&data);
+        vulkan.set_debug_name(upload_buffer.buffer(), name);
 
         let image = Image::new(
             self.memory_allocator.clone(),
             ImageCreateInfo {
+                flags: if cubemap {
+                    ImageCreateFlags::CUBE_COMPATIBLE
+                } else {
+                    ImageCreateFlags::empty()
+                },
                 image_type: ImageType::Dim2d,
                 format,
                 extent: [dimensions.0, dimensions.1, 1],
                 array_layers: layers,
-                usage: ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED,
+                mip_levels,
+                usage: if mip_levels > 1 {
+                    ImageUsage::TRANSFER_SRC | ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED
+                } else {
+                    ImageUsage::TRANSFER_DST | ImageUsage::SAMPLED
+                },
+                sharing: if vulkan.separate_transfer() {
+                    Sharing::Concurrent(
+                        [
+                            vulkan.queue.queue_family_index(),
+                            vulkan.transfer_queue.queue_family_index(),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    )
+                } else {
+                    Sharing::Exclusive
+                },
                 ..Default::default()
             },
             AllocationCreateInfo::default(),
         )?;
+        vulkan.set_debug_name(&image, name);
 
-        uploads.copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
-            upload_buffer,
-            image.clone(),
-        ))?;
+        uploads
+            .transfer()
+            .copy_buffer_to_image(CopyBufferToImageInfo::buffer_image(
+                upload_buffer,
+                image.clone(),
+            ))?;
+        if mip_levels > 1 {
+            record_mipmaps(uploads.graphics()?, &image)?;
+        }
 
-        let set_layout;
+        let (view_type, material) = match settings.kind {
+            TextureKind::Flat if layers <= 1 => (ImageViewType::Dim2d, &vulkan.textured_material),
+            TextureKind::Flat | TextureKind::Array => {
+                (ImageViewType::Dim2dArray, &vulkan.texture_array_material)
+            }
+            // Only the binding matters for the layout, so custom cubemap shaders can use the array layout.
+            TextureKind::Cubemap => (ImageViewType::Cube, &vulkan.texture_array_material),
+        };
+        let set_layout = material
+            .get_pipeline_or_recreate(self)?
+            .layout()
+            .set_layouts()
+            .get(1)
+            .ok_or(Error::msg(
+                "failed to get second set of the texture layout.",
+            ))?
+            .clone();
 
         let texture_view = ImageView::new(
             image.clone(),
             ImageViewCreateInfo {
-                view_type: if layers <= 1 {
-                    set_layout = vulkan
-                        .textured_material
-                        .get_pipeline_or_recreate(self)?
-                        .layout()
-                        .set_layouts()
-                        .get(1)
-                        .ok_or(Error::msg(
-                            "failed to get second set of the texture layout.",
-                        ))?
-                        .clone();
-                    ImageViewType::Dim2d
-                } else {
-                    set_layout = vulkan
-                        .texture_array_material
-                        .get_pipeline_or_recreate(self)?
-                        .layout()
-                        .set_layouts()
-                        .get(1)
-                        .ok_or(Error::msg(
-                            "failed to get second set of the texture array layout.",
-                        ))?
-                        .clone();
-                    ImageViewType::Dim2dArray
-                },
+                view_type,
                 ..ImageViewCreateInfo::from_image(&image)
             },
         )?;
 
-        let samplercreateinfo = settings.sampler.to_vulkano();
+        let sampler = create_sampler(vulkan, &settings.sampler)?;
 
-        let sampler = Sampler::new(vulkan.device.clone(), samplercreateinfo)?;
+        vulkan.set_debug_name(&texture_view, name);
+        vulkan.set_debug_name(&sampler, name);
 
         let set = DescriptorSet::new(
             self.descriptor_set_allocator.clone(),
             set_layout,
             [WriteDescriptorSet::image_view_sampler(
                 0,
-                texture_view,
-                sampler,
+                texture_view.clone(),
+                sampler.clone(),
             )],
             [],
         )?;
+        vulkan.set_debug_name(&set, name);
 
-        // Upload to gpu.
-        let _ = uploads.end()?.execute(vulkan.queue.clone())?;
-        Ok(set)
+        Ok(LoadedTexture {
+            set,
+            image,
+            view: texture_view,
+            sampler,
+        })
     }
     /// Makes a descriptor write.
     pub fn write_descriptor<T: BufferContents>(
@@ -258,3 +586,82 @@ impl Loader {
         Ok(WriteDescriptorSet::buffer(set, buf))
     }
 }
+
+/// Fills the mip levels of the image by repeatedly downscaling the level above.
+fn record_mipmaps(uploads: &mut RecordingCommandBuffer, image: &Arc<Image>) -> Result<()> {
+    let [width, height, _] = image.extent();
+    for level in 1..image.mip_levels() {
+        let source = [
+            (width >> (level - 1)).max(1),
+            (height >> (level - 1)).max(1),
+            1,
+        ];
+        let destination = [(width >> level).max(1), (height >> level).max(1), 1];
+        uploads.blit_image(BlitImageInfo {
+            regions: [ImageBlit {
+                src_subresource: ImageSubresourceLayers {
+                    mip_level: level - 1,
+                    ..image.subresource_layers()
+                },
+                src_offsets: [[0; 3], source],
+                dst_subresource: ImageSubresourceLayers {
+                    mip_level: level,
+                    ..image.subresource_layers()
+                },
+                dst_offsets: [[0; 3], destination],
+                ..Default::default()
+            }]
+            .into(),
+            filter: Filter::Linear,
+            ..BlitImageInfo::images(image.clone(), image.clone())
+        })?;
+    }
+    Ok(())
+}
+
+/// Creates the sampler with the given settings, fitting them into the limits of the device.
+fn create_sampler(vulkan: &Vulkan, settings: &tSampler) -> Result<Arc<Sampler>> {
+    let limits = vulkan.device.physical_device().properties();
+    let mut info = settings.to_vulkano();
+
+    info.anisotropy = match settings.anisotropy {
+        Some(_) if !vulkan.device.enabled_features().sampler_anisotropy => {
+            log::warn!("Anisotropic filtering is not supported by this GPU.");
+            None
+        }
+        Some(anisotropy) => Some(anisotropy.clamp(1.0, limits.max_sampler_anisotropy)),
+        None => None,
+    };
+    info.mip_lod_bias = settings
+        .lod_bias
+        .clamp(-limits.max_sampler_lod_bias, limits.max_sampler_lod_bias);
+
+    Ok(Sampler::new(vulkan.device.clone(), info)?)
+}
+
+/// Drops the pipelines that neither a material points to nor got pinned.
+fn retain_used<T>(pipelines: &mut Vec<Arc<T>>, pinned: &[Arc<T>]) {
+    pipelines.retain(|pipeline| {
+        Arc::weak_count(pipeline) > 0 || pinned.iter().any(|pinned| Arc::ptr_eq(pinned, pipeline))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_pipelines_survive() {
+        let used = Arc::new("used");
+        let material = Arc::downgrade(&used);
+        let pinned = Arc::new("pinned");
+        let mut pipelines = vec![used, pinned.clone(), Arc::new("unused")];
+
+        retain_used(&mut pipelines, std::slice::from_ref(&pinned));
+        assert_eq!(pipelines, [Arc::new("used"), Arc::new("pinned")]);
+
+        drop(material);
+        retain_used(&mut pipelines, &[]);
+        assert!(pipelines.is_empty());
+    }
+}
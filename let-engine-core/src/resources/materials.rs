@@ -11,10 +11,15 @@ use crate::{
 use anyhow::{anyhow, Error, Result};
 use derive_builder::Builder;
 use parking_lot::Mutex;
-use std::sync::{Arc, Weak};
+use std::{
+    path::Path,
+    sync::{Arc, Weak},
+};
 
 use vulkano::{
-    descriptor_set::{DescriptorSet, WriteDescriptorSet},
+    descriptor_set::{layout::DescriptorSetLayout, DescriptorSet, WriteDescriptorSet},
+    device::{Device, DeviceOwned},
+    image::view::ImageViewType,
     pipeline::{
         graphics::{
             input_assembly::{InputAssemblyState, PrimitiveTopology},
@@ -27,7 +32,12 @@ use vulkano::{
     shader::{spirv::bytes_to_words, ShaderModule, ShaderModuleCreateInfo},
 };
 
-use super::{resources, textures::TextureError, vulkan::pipeline::create_pipeline, Loader};
+use super::{
+    resources,
+    textures::TextureError,
+    vulkan::{pipeline::create_pipeline, Shader, Vulkan},
+    Loader,
+};
 // pub use vulkano::pipeline::graphics::rasterization::LineStipple;
 
 /// The way in which an object gets drawn using it's vertices and indices.
@@ -57,18 +67,35 @@ impl From<Topology> for PrimitiveTopology {
     }
 }
 
+/// The distance field fragment shaders of the engine a material can be drawn with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum DistanceField {
+    Msdf = 0,
+    Sdf = 1,
+}
+
+/// The descriptor set holding the data of the object for materials drawn one object at a time.
+pub(crate) const OBJECT_SET: usize = 0;
+/// The descriptor set of the texture for materials drawn one object at a time.
+pub(crate) const TEXTURE_SET: usize = 1;
+/// The descriptor set of the lights of the layer for lit materials, following the normal map.
+pub(crate) const LIGHTS_SET: usize = 3;
+
 /// A material holding the way an object should be drawn.
 ///
 /// It takes some time to make a new material.
 #[derive(Clone)]
 pub struct Material {
     pub(crate) pipeline: Arc<Mutex<Weak<GraphicsPipeline>>>,
+    /// The pipelines drawing this material with the engine's MSDF and SDF fragment shaders, made once needed.
+    distance_field_pipelines: Arc<Mutex<[Weak<GraphicsPipeline>; 2]>>,
     instanced: bool,
     pub(crate) descriptor: Option<Arc<DescriptorSet>>,
     texture: Option<Texture>,
+    normal_map: Option<Texture>,
     layer: u32,
     settings: MaterialSettings,
-    shaders: Shaders,
+    shaders: Arc<Mutex<Shaders>>,
 }
 
 impl PartialEq for Material {
@@ -82,6 +109,7 @@ impl std::fmt::Debug for Material {
         f.debug_struct("Material")
             .field("instanced", &self.instanced)
             .field("texture", &self.texture)
+            .field("normal_map", &self.normal_map)
             .field("layer", &self.layer)
             .finish()
     }
@@ -97,12 +125,14 @@ impl Material {
     ) -> Self {
         Self {
             pipeline: Arc::new(Mutex::new(Arc::downgrade(pipeline))),
+            distance_field_pipelines: Default::default(),
             instanced,
             descriptor: None,
             texture: None,
+            normal_map: None,
             layer: 0,
             settings: MaterialSettings::default(),
-            shaders,
+            shaders: Arc::new(Mutex::new(shaders)),
         }
     }
     /// Creates a new material using the given shaders, settings and write operations.
@@ -165,35 +195,42 @@ impl Material {
             Some(pipeline_cache),
         )
         .map_err(VulkanError::Other)?;
+        vulkan.set_debug_name(&pipeline, settings.name.as_deref());
 
         loader.pipelines.push(pipeline.clone());
 
-        let descriptor = if !writes.is_empty() {
-            Some(DescriptorSet::new(
-                loader.descriptor_set_allocator.clone(),
-                pipeline
-                    .layout()
-                    .set_layouts()
-                    .get(2) // on set 2
-                    .ok_or(VulkanError::Other(Error::msg(
-                        "Failed to get the second set of the pipeline layout.",
-                    )))?
-                    .clone(),
-                writes.clone(),
-                [],
-            )?)
-        } else {
-            None
-        };
-        Ok(Self {
+        let mut material = Self {
             pipeline: Arc::new(Mutex::new(Arc::downgrade(&pipeline))),
-            descriptor,
+            distance_field_pipelines: Default::default(),
+            descriptor: None,
             instanced,
             layer: settings.initial_layer,
             texture,
+            normal_map: None,
             settings,
-            shaders: shaders.clone(),
-        })
+            shaders: Arc::new(Mutex::new(shaders.clone())),
+        };
+        if !writes.is_empty() {
+            material.descriptor = Some(DescriptorSet::new(
+                loader.descriptor_set_allocator.clone(),
+                material
+                    .descriptor_layout(&pipeline)
+                    .map_err(VulkanError::Other)?,
+                writes,
+                [],
+            )?);
+        }
+        Ok(material)
+    }
+
+    /// Compiles the GLSL sources and makes a new material from them using the default settings.
+    ///
+    /// Compile errors get returned with the stage and line they are at.
+    #[cfg(feature = "glsl")]
+    pub fn from_glsl(vertex_source: &str, fragment_source: &str) -> Result<Material, ShaderError> {
+        let shaders = Shaders::from_glsl(vertex_source, fragment_source, "main")?;
+        Self::new_with_shaders(MaterialSettings::default(), None, &shaders, false, vec![])
+            .map_err(ShaderError::Other)
     }
 
     /// Makes a new default material.
@@ -204,8 +241,8 @@ impl Material {
         let shaders = resources()
             .map_err(|e| VulkanError::Other(e.into()))?
             .vulkan()
-            .clone()
-            .default_shaders;
+            .default_shaders
+            .clone();
         Self::new_with_shaders(settings, texture, &shaders, false, vec![])
     }
 
@@ -217,8 +254,8 @@ impl Material {
         let shaders = resources()
             .map_err(|e| VulkanError::Other(e.into()))?
             .vulkan()
-            .clone()
-            .default_instance_shaders;
+            .default_instance_shaders
+            .clone();
         Self::new_with_shaders(settings, texture, &shaders, true, vec![])
     }
 
@@ -251,6 +288,64 @@ impl Material {
         })
     }
 
+    /// Creates a material lit by the lights of the layer, shading the texture using the normal map.
+    ///
+    /// The normal map gets sampled at the same coordinates as the texture, so both should have the same layout.
+    /// Its red channel points right and its green channel up in the image.
+    pub fn new_lit(texture: &Texture, normal_map: &Texture) -> Result<Material> {
+        Ok(Material {
+            texture: Some(texture.clone()),
+            normal_map: Some(normal_map.clone()),
+            ..resources()?.vulkan().lit_material.clone()
+        })
+    }
+
+    /// Returns the instanced counterpart of this material in case it is one of the default non instanced materials.
+    pub(crate) fn default_instanced(&self) -> Result<Option<Material>> {
+        let vulkan = resources()?.vulkan();
+        if self.instanced || self.descriptor.is_some() {
+            return Ok(None);
+        }
+        if Arc::ptr_eq(&self.pipeline, &vulkan.default_material.pipeline) {
+            return Ok(Some(vulkan.default_instance_material.clone()));
+        }
+        let textured = Arc::ptr_eq(&self.pipeline, &vulkan.textured_material.pipeline)
+            || Arc::ptr_eq(&self.pipeline, &vulkan.texture_array_material.pipeline);
+        match &self.texture {
+            Some(texture) if textured => Ok(Some(Self::new_default_textured_instance(texture)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the texture in case this is a default textured material
+    /// that can be drawn using the bindless instance material.
+    pub(crate) fn bindless_texture(&self) -> Result<Option<&Texture>> {
+        let vulkan = resources()?.vulkan();
+        if self.instanced
+            || self.descriptor.is_some()
+            || vulkan.bindless_instance_material.is_none()
+            || !Arc::ptr_eq(&self.pipeline, &vulkan.textured_material.pipeline)
+        {
+            return Ok(None);
+        }
+        Ok(self
+            .texture
+            .as_ref()
+            .filter(|texture| texture.view().view_type() == ImageViewType::Dim2d))
+    }
+
+    /// Returns true if both materials use the same pipeline, descriptor, texture and normal map.
+    pub(crate) fn same_resources(&self, other: &Self) -> bool {
+        let same_texture = |a: &Option<Texture>, b: &Option<Texture>| match (a, b) {
+            (Some(a), Some(b)) => a.same_gpu(b),
+            (None, None) => true,
+            _ => false,
+        };
+        self == other
+            && same_texture(&self.texture, &other.texture)
+            && same_texture(&self.normal_map, &other.normal_map)
+    }
+
     /// Returns the graphics pipeline, but in case it is out of date reloads it from the beginning.
     ///
     /// ## How the system works
@@ -262,24 +357,72 @@ impl Material {
     /// somewhere. The loader struct contains a vec of arcs with all the graphics pipelines that get cleared on window resize
     /// making the weak pointer invalid and return a `None`. This function returns the Some if the weak pointer or remakes the
     /// whole pipeline returning it instead.
+    ///
+    /// After the device got lost the shaders get loaded to the new one from their SpirV.
     pub(crate) fn get_pipeline_or_recreate(
         &self,
         loader: &mut Loader,
     ) -> Result<Arc<GraphicsPipeline>> {
+        let vulkan = resources()?.vulkan();
         if let Some(pipeline) = self.pipeline.lock().upgrade() {
-            return Ok(pipeline);
+            if Arc::ptr_eq(pipeline.device(), &vulkan.device) {
+                return Ok(pipeline);
+            }
         }
+        let pipeline = self.create_pipeline(None, loader)?;
+        *self.pipeline.lock() = Arc::downgrade(&pipeline);
+        Ok(pipeline)
+    }
+
+    /// Returns the pipeline drawing this material with the given distance field fragment shader of the engine,
+    /// keeping the vertex shader and settings of the material. Gets made the first time it is needed.
+    pub(crate) fn get_distance_field_pipeline_or_recreate(
+        &self,
+        distance_field: DistanceField,
+        loader: &mut Loader,
+    ) -> Result<Arc<GraphicsPipeline>> {
         let vulkan = resources()?.vulkan();
-        let vertex = self
-            .shaders
+        let index = distance_field as usize;
+        if let Some(pipeline) = self.distance_field_pipelines.lock()[index].upgrade() {
+            if Arc::ptr_eq(pipeline.device(), &vulkan.device) {
+                return Ok(pipeline);
+            }
+        }
+        let fragment = match distance_field {
+            DistanceField::Msdf => vulkan.msdf_material.shaders(),
+            DistanceField::Sdf => vulkan.sdf_material.shaders(),
+        };
+        let pipeline = self.create_pipeline(Some(&fragment), loader)?;
+        self.distance_field_pipelines.lock()[index] = Arc::downgrade(&pipeline);
+        Ok(pipeline)
+    }
+
+    /// Makes the pipeline of this material, using the fragment shader of the given shaders instead of its own if given.
+    fn create_pipeline(
+        &self,
+        fragment_shaders: Option<&Shaders>,
+        loader: &mut Loader,
+    ) -> Result<Arc<GraphicsPipeline>> {
+        let vulkan = resources()?.vulkan();
+        let shaders = {
+            let mut shaders = self.shaders.lock();
+            *shaders = shaders.reload(&vulkan.device)?;
+            shaders.clone()
+        };
+        let vertex = shaders
             .vertex
-            .entry_point(&self.shaders.entry_point)
-            .ok_or(anyhow!("Entry point changed during runtime."))?;
-        let fragment = self
-            .shaders
-            .fragment
-            .entry_point(&self.shaders.entry_point)
+            .entry_point(&shaders.entry_point)
             .ok_or(anyhow!("Entry point changed during runtime."))?;
+        let fragment = match fragment_shaders {
+            Some(fragment_shaders) => fragment_shaders
+                .fragment
+                .entry_point(&fragment_shaders.entry_point)
+                .ok_or(anyhow!("The engine shaders have no main function."))?,
+            None => shaders
+                .fragment
+                .entry_point(&shaders.entry_point)
+                .ok_or(anyhow!("Entry point changed during runtime."))?,
+        };
 
         let subpass = Subpass::from(vulkan.render_pass.clone(), 0)
             .ok_or(anyhow!("Failed to create subpass from the render pass."))?;
@@ -310,35 +453,134 @@ impl Material {
             rasterisation_state,
             Some(loader.pipeline_cache.clone()),
         )?;
+        vulkan.set_debug_name(&pipeline, self.settings.name.as_deref());
 
         loader.pipelines.push(pipeline.clone());
-        *self.pipeline.lock() = Arc::downgrade(&pipeline);
         Ok(pipeline)
     }
+
+    /// Returns true if the material holds objects of a lost device that can not be made again,
+    /// like descriptors written to it or shaders made from modules, so it can not be drawn anymore.
+    pub(crate) fn is_lost(&self, vulkan: &Vulkan) -> bool {
+        let device = &vulkan.device;
+        self.descriptor
+            .as_ref()
+            .is_some_and(|descriptor| !Arc::ptr_eq(descriptor.layout().device(), device))
+            || [&self.texture, &self.normal_map]
+                .into_iter()
+                .flatten()
+                .any(|texture| !texture.is_on(vulkan))
+            || {
+                let shaders = self.shaders.lock();
+                shaders.code.is_none() && !Arc::ptr_eq(shaders.vertex.device(), device)
+            }
+    }
+
+    /// Moves the pipeline and shaders of the given material made on a new device into this one and its clones.
+    pub(crate) fn replace_gpu(&self, material: &Material) {
+        *self.pipeline.lock() = material.pipeline.lock().clone();
+        *self.distance_field_pipelines.lock() = material.distance_field_pipelines.lock().clone();
+        *self.shaders.lock() = material.shaders.lock().clone();
+    }
+
+    /// Returns the shaders of this material.
+    pub fn shaders(&self) -> Shaders {
+        self.shaders.lock().clone()
+    }
+
+    /// Swaps the shaders of this material and every clone of it, rebuilding the graphics pipeline right away.
+    ///
+    /// In case the pipeline can not be made with the new shaders the error gets returned and the previous shaders stay in use.
+    /// Descriptors written to the material have to stay compatible with the new shaders.
+    pub fn replace_shaders(&self, shaders: Shaders) -> Result<()> {
+        let mut loader = resources()?.loader().lock();
+        let previous_shaders = std::mem::replace(&mut *self.shaders.lock(), shaders);
+        let previous_pipeline = std::mem::take(&mut *self.pipeline.lock());
+        match self.get_pipeline_or_recreate(&mut loader) {
+            Ok(_) => {
+                // The distance field pipelines get made again with the new vertex shader.
+                std::mem::take(&mut *self.distance_field_pipelines.lock());
+                if let Some(previous) = previous_pipeline.upgrade() {
+                    loader
+                        .pipelines
+                        .retain(|pipeline| !Arc::ptr_eq(pipeline, &previous));
+                }
+                Ok(())
+            }
+            Err(error) => {
+                *self.shaders.lock() = previous_shaders;
+                *self.pipeline.lock() = previous_pipeline;
+                Err(error)
+            }
+        }
+    }
 }
 impl Material {
     /// Writes to the material changing the variables for the shaders.
     ///
+    /// The writes go to the descriptor set at [`descriptor_set_index`](Material::descriptor_set_index).
+    ///
     /// # Safety
     /// The program will crash in case in case the data input here is not as the shader wants it.
     pub unsafe fn write(&mut self, descriptor: Vec<WriteDescriptorSet>) -> Result<()> {
         let mut loader = resources()?.loader().lock();
+        let pipeline = self.get_pipeline_or_recreate(&mut loader)?;
         self.descriptor = Some(DescriptorSet::new(
             loader.descriptor_set_allocator.clone(),
-            self.get_pipeline_or_recreate(&mut loader)?
-                .layout()
-                .set_layouts()
-                .get(1)
-                .ok_or(Error::msg(
-                    "Could not obtain the second set layout of this write.",
-                ))?
-                .clone(),
+            self.descriptor_layout(&pipeline)?,
             descriptor,
             [],
         )?);
+        crate::draw::contents_changed();
         Ok(())
     }
 
+    /// Returns the index of the descriptor set the writes of this material go to,
+    /// which is the first one after the sets the engine binds.
+    ///
+    /// Instanced materials get their texture at set 0. The others get the data of the object at set 0,
+    /// the texture at set 1 and in case of a normal map next to the texture, the normal map at set 2 and the lights at set 3.
+    ///
+    /// Giving the material a texture or normal map after writing to it moves the set, so write to it again after that.
+    pub fn descriptor_set_index(&self) -> usize {
+        if self.instanced {
+            self.texture.is_some() as usize
+        } else if self.lit() {
+            LIGHTS_SET + 1
+        } else if self.texture.is_some() {
+            TEXTURE_SET + 1
+        } else {
+            TEXTURE_SET
+        }
+    }
+
+    /// Returns true if the material has a normal map along with the texture it shades.
+    pub(crate) fn lit(&self) -> bool {
+        !self.instanced && self.texture.is_some() && self.normal_map.is_some()
+    }
+
+    /// Returns the layout of the descriptor set the writes of this material go to.
+    pub(crate) fn descriptor_layout(
+        &self,
+        pipeline: &GraphicsPipeline,
+    ) -> Result<Arc<DescriptorSetLayout>> {
+        let index = self.descriptor_set_index();
+        pipeline
+            .layout()
+            .set_layouts()
+            .get(index)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!("The shaders of this material declare no descriptor set {index}.")
+            })
+    }
+
+    /// Replaces the descriptor set written to this material.
+    pub(crate) fn set_descriptor(&mut self, descriptor: Arc<DescriptorSet>) {
+        self.descriptor = Some(descriptor);
+        crate::draw::contents_changed();
+    }
+
     /// Sets the layer of the texture in case it has a texture with layers.
     pub fn set_layer(&mut self, id: u32) -> Result<(), TextureError> {
         if let Some(texture) = &self.texture {
@@ -395,6 +637,16 @@ impl Material {
         Ok(())
     }
 
+    /// Returns the name of this material given in the material settings.
+    pub fn name(&self) -> Option<&str> {
+        self.settings.name.as_deref()
+    }
+
+    /// Returns true if the engine constants get pushed to the shaders of this material.
+    pub fn engine_constants(&self) -> bool {
+        self.settings.engine_constants
+    }
+
     /// Returns the texture.
     pub fn texture(&self) -> Option<Texture> {
         self.texture.clone()
@@ -404,6 +656,19 @@ impl Material {
     pub fn set_texture(&mut self, texture: Option<Texture>) {
         self.texture = texture;
     }
+
+    /// Returns the normal map.
+    pub fn normal_map(&self) -> Option<Texture> {
+        self.normal_map.clone()
+    }
+
+    /// Sets the normal map, which has to be a texture with a single layer.
+    ///
+    /// The normal map only gets used together with a texture. It gets bound to set 2 and the lights of the layer to set 3,
+    /// moving [descriptor writes](Material::descriptor_set_index) to set 4.
+    pub fn set_normal_map(&mut self, normal_map: Option<Texture>) {
+        self.normal_map = normal_map;
+    }
 }
 
 /// Vertex and fragment shaders of a material
@@ -419,6 +684,16 @@ pub struct MaterialSettings {
     /// If the texture has multiple layers this is the layer it starts at.
     #[builder(setter(into), default = "0")]
     pub initial_layer: u32,
+    /// The name of this material used as the debug name of the graphics pipeline.
+    ///
+    /// Makes validation messages and graphics captures refer to this name instead of an opaque handle.
+    #[builder(setter(into, strip_option), default)]
+    pub name: Option<String>,
+    /// Pushes the [engine constants](Shaders#engine-constants) to the shaders of this material for every draw call.
+    ///
+    /// The shaders have to declare the `Engine` push constant block in that case.
+    #[builder(default = "false")]
+    pub engine_constants: bool,
 }
 
 impl Default for MaterialSettings {
@@ -427,16 +702,43 @@ impl Default for MaterialSettings {
             topology: Topology::TriangleList,
             line_width: 1.0,
             initial_layer: 0,
+            name: None,
+            engine_constants: false,
         }
     }
 }
 
 /// Holds compiled shaders in form of ShaderModules to use in a material.
+///
+/// # Engine constants
+///
+/// Materials with [`engine_constants`](MaterialSettings::engine_constants) enabled get the following push constant
+/// block filled by the engine for every draw call, so animated shaders do not need buffers updated by the game each frame.
+/// The block has to be declared whole and exactly like this.
+///
+/// ```glsl
+/// layout (push_constant) uniform Engine {
+///     // The projection and view matrix of the camera drawing the object.
+///     mat4 camera;
+///     // The size of the viewport of the camera in pixels.
+///     vec2 resolution;
+///     // The seconds since the engine started drawing, starting over at 0 every hour to stay precise.
+///     float time;
+///     // The seconds since the previous frame.
+///     float delta_time;
+///     // The ID of the object in its layer, 0 for instanced draws.
+///     uint object_id;
+/// } engine;
+/// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Shaders {
     pub(crate) vertex: Arc<ShaderModule>,
     pub(crate) fragment: Arc<ShaderModule>,
     entry_point: Box<str>,
+    /// The SpirV of the vertex and fragment shader, kept to load them again after the device got lost.
+    ///
+    /// `None` for shaders made from modules.
+    code: Option<[Arc<[u32]>; 2]>,
 }
 
 impl Shaders {
@@ -454,10 +756,10 @@ impl Shaders {
         let device = resources()
             .map_err(|e| ShaderError::Other(VulkanError::Other(e.into())))?
             .vulkan()
-            .clone()
-            .device;
-        let vertex_words = bytes_to_words(vertex_bytes)?;
-        let fragment_words = bytes_to_words(fragment_bytes)?;
+            .device
+            .clone();
+        let vertex_words: Arc<[u32]> = bytes_to_words(vertex_bytes)?.into();
+        let fragment_words: Arc<[u32]> = bytes_to_words(fragment_bytes)?.into();
         let vertex: Arc<ShaderModule> = unsafe {
             ShaderModule::new(device.clone(), ShaderModuleCreateInfo::new(&vertex_words))?
         };
@@ -474,8 +776,62 @@ impl Shaders {
             vertex,
             fragment,
             entry_point: entry_point.into(),
+            code: Some([vertex_words, fragment_words]),
         })
     }
+    /// Creates a shader from SpirV files.
+    ///
+    /// # Safety
+    ///
+    /// The same as [`from_bytes`](Shaders::from_bytes).
+    pub unsafe fn from_spirv_files(
+        vertex: impl AsRef<Path>,
+        fragment: impl AsRef<Path>,
+        entry_point: &str,
+    ) -> Result<Self, ShaderError> {
+        let vertex_bytes = std::fs::read(vertex)?;
+        let fragment_bytes = std::fs::read(fragment)?;
+        unsafe { Self::from_bytes(&vertex_bytes, &fragment_bytes, entry_point) }
+    }
+
+    /// Compiles the GLSL sources of the vertex and fragment shader.
+    ///
+    /// The entry point is also available to the sources as the `EP` macro.
+    #[cfg(feature = "glsl")]
+    pub fn from_glsl(
+        vertex_source: &str,
+        fragment_source: &str,
+        entry_point: &str,
+    ) -> Result<Self, ShaderError> {
+        use super::glsl::{compile, GlslStage};
+
+        let device = resources()
+            .map_err(|e| ShaderError::Other(VulkanError::Other(e.into())))?
+            .vulkan()
+            .device
+            .clone();
+        let vertex_words: Arc<[u32]> =
+            compile(vertex_source, GlslStage::Vertex, entry_point)?.into();
+        let fragment_words: Arc<[u32]> =
+            compile(fragment_source, GlslStage::Fragment, entry_point)?.into();
+        // The compiler only produces valid SpirV.
+        let vertex = unsafe {
+            ShaderModule::new(device.clone(), ShaderModuleCreateInfo::new(&vertex_words))?
+        };
+        let fragment =
+            unsafe { ShaderModule::new(device, ShaderModuleCreateInfo::new(&fragment_words))? };
+        Ok(Self {
+            vertex,
+            fragment,
+            entry_point: entry_point.into(),
+            code: Some([vertex_words, fragment_words]),
+        })
+    }
+
+    /// Uses already loaded shader modules.
+    ///
+    /// The engine does not know the SpirV of modules,
+    /// so materials using them can not be restored after the device got lost.
     pub fn from_modules(
         vertex: Arc<ShaderModule>,
         fragment: Arc<ShaderModule>,
@@ -485,6 +841,40 @@ impl Shaders {
             vertex,
             fragment,
             entry_point: entry_point.into(),
+            code: None,
         }
     }
+
+    /// Uses the default shaders of the engine with the `main` entry point.
+    pub(crate) fn from_default(vertex: &Shader, fragment: &Shader) -> Self {
+        Self {
+            vertex: vertex.module.clone(),
+            fragment: fragment.module.clone(),
+            entry_point: "main".into(),
+            code: Some([vertex.code.clone(), fragment.code.clone()]),
+        }
+    }
+
+    /// Returns these shaders loaded on the given device, loading them from their SpirV in case they are on a lost one.
+    pub(crate) fn reload(&self, device: &Arc<Device>) -> Result<Self> {
+        if Arc::ptr_eq(self.vertex.device(), device) {
+            return Ok(self.clone());
+        }
+        let [vertex, fragment] = self.code.as_ref().ok_or(anyhow!(
+            "The shaders were made from modules of a lost device and have to be made again."
+        ))?;
+        // The SpirV already got loaded once.
+        let (vertex_module, fragment_module) = unsafe {
+            (
+                ShaderModule::new(device.clone(), ShaderModuleCreateInfo::new(vertex))?,
+                ShaderModule::new(device.clone(), ShaderModuleCreateInfo::new(fragment))?,
+            )
+        };
+        Ok(Self {
+            vertex: vertex_module,
+            fragment: fragment_module,
+            entry_point: self.entry_point.clone(),
+            code: self.code.clone(),
+        })
+    }
 }
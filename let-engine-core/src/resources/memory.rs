@@ -0,0 +1,126 @@
+//! Bookkeeping of the GPU memory used by textures and models, which also loads them again after the device got lost.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Weak,
+};
+
+use anyhow::Result;
+use parking_lot::Mutex;
+
+use super::{vulkan::Vulkan, Loader};
+
+static TEXTURE_MEMORY: AtomicU64 = AtomicU64::new(0);
+static BUFFER_MEMORY: AtomicU64 = AtomicU64::new(0);
+/// Every living allocation, for listing them in reports.
+static ALLOCATIONS: Mutex<Vec<Weak<TrackedMemory>>> = Mutex::new(Vec::new());
+/// Every living resource that can be loaded to a new device, kept separately as clones share a single one.
+static RESIDENT: Mutex<Vec<Weak<dyn Reload>>> = Mutex::new(Vec::new());
+
+/// The kind of GPU memory of an allocation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MemoryKind {
+    /// The pixels of a texture.
+    Texture,
+    /// The vertex and index buffers of a model.
+    Buffer,
+}
+
+impl MemoryKind {
+    fn counter(&self) -> &'static AtomicU64 {
+        match self {
+            MemoryKind::Texture => &TEXTURE_MEMORY,
+            MemoryKind::Buffer => &BUFFER_MEMORY,
+        }
+    }
+}
+
+/// Counts its bytes as allocated for as long as it exists.
+///
+/// Shared between the clones of a resource using an `Arc`, so the bytes get released with the last clone.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TrackedMemory {
+    kind: MemoryKind,
+    bytes: u64,
+    label: Option<Arc<str>>,
+}
+
+impl TrackedMemory {
+    pub fn new(kind: MemoryKind, bytes: u64, label: Option<Arc<str>>) -> Arc<Self> {
+        kind.counter().fetch_add(bytes, Ordering::Relaxed);
+        let memory = Arc::new(Self { kind, bytes, label });
+        let mut allocations = ALLOCATIONS.lock();
+        allocations.retain(|allocation| allocation.strong_count() > 0);
+        allocations.push(Arc::downgrade(&memory));
+        memory
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+impl Drop for TrackedMemory {
+    fn drop(&mut self) {
+        self.kind.counter().fetch_sub(self.bytes, Ordering::Relaxed);
+    }
+}
+
+/// Returns the bytes of GPU memory currently used by textures.
+pub(crate) fn texture_memory() -> u64 {
+    TEXTURE_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Returns the bytes of GPU memory currently used by model vertex and index buffers.
+pub(crate) fn buffer_memory() -> u64 {
+    BUFFER_MEMORY.load(Ordering::Relaxed)
+}
+
+/// A living allocation of GPU memory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub kind: MemoryKind,
+    pub bytes: u64,
+    /// The name of the resource, like the path of a texture.
+    pub label: Option<Arc<str>>,
+}
+
+/// Returns all textures and models currently using GPU memory.
+pub fn allocations() -> Vec<Allocation> {
+    ALLOCATIONS
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .map(|memory| Allocation {
+            kind: memory.kind,
+            bytes: memory.bytes,
+            label: memory.label.clone(),
+        })
+        .collect()
+}
+
+/// The GPU side of a resource, which can be made again from the data it keeps on the CPU.
+pub(crate) trait Reload: Send + Sync {
+    /// Loads the resource to the current device, replacing the objects of the lost one.
+    fn reload(&self, vulkan: &Vulkan, loader: &mut Loader) -> Result<()>;
+}
+
+/// Remembers the resource to be loaded again in case the device gets lost.
+pub(crate) fn register<T: Reload + 'static>(resource: &Arc<T>) {
+    let resource: Weak<dyn Reload> = Arc::downgrade(resource) as Weak<dyn Reload>;
+    let mut resident = RESIDENT.lock();
+    resident.retain(|resource| resource.strong_count() > 0);
+    resident.push(resource);
+}
+
+/// Loads every living resource to the new device after the previous one got lost.
+///
+/// Resources failing to load get logged and keep the objects of the lost device.
+pub(crate) fn reload(vulkan: &Vulkan, loader: &mut Loader) {
+    let resident: Vec<_> = RESIDENT.lock().iter().filter_map(Weak::upgrade).collect();
+    for resource in resident {
+        if let Err(error) = resource.reload(vulkan, loader) {
+            log::error!("Failed to load a resource to the new device: {error:#}");
+        }
+    }
+}
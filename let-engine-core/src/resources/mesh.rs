@@ -0,0 +1,695 @@
+//! Building models and their colliders out of primitive shapes.
+
+use anyhow::Result;
+use glam::{vec2, Vec2};
+use thiserror::Error;
+
+use super::{
+    data::{vert, Data},
+    Model, ModelData,
+};
+#[cfg(feature = "physics")]
+use crate::objects::{
+    physics::{ColliderBuilder, Shape},
+    Transform,
+};
+
+/// The default amount of segments of a full circle.
+const DEFAULT_SEGMENTS: u32 = 32;
+
+/// An error that can occur adding polygons to a [MeshBuilder].
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum MeshError {
+    #[error("A polygon needs at least 3 points that are not all on one line.")]
+    TooFewPoints,
+    /// The outline crosses itself or a hole lies outside of the outline.
+    #[error("The polygon could not be triangulated, as its outline intersects itself.")]
+    SelfIntersecting,
+}
+
+/// Builds the data of a model out of primitive shapes, along with a collider of the same shape.
+///
+/// Every shape gets added to the mesh built so far. Overlapping shapes get drawn twice,
+/// so shapes using transparent colors should not overlap.
+///
+/// The texture coordinates are the same as the positions, so textures cover -1.0 to 1.0 like on the default square.
+///
+/// # Usage
+/// ```ignore
+/// let builder = MeshBuilder::new()
+///     .rect(vec2(0.0, -0.5), vec2(1.0, 0.1))
+///     .circle(vec2(0.0, 0.3), 0.4)
+///     .polygon(&[vec2(-0.2, 0.0), vec2(0.2, 0.0), vec2(0.0, -0.4)])?;
+///
+/// let (model, collider) = builder.model_and_collider()?;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshBuilder {
+    vertices: Vec<Vec2>,
+    indices: Vec<u32>,
+    /// Counter clockwise convex polygons covering the mesh, which become the parts of the collider.
+    parts: Vec<Vec<Vec2>>,
+    segments: u32,
+}
+
+impl Default for MeshBuilder {
+    fn default() -> Self {
+        Self {
+            vertices: vec![],
+            indices: vec![],
+            parts: vec![],
+            segments: DEFAULT_SEGMENTS,
+        }
+    }
+}
+
+impl MeshBuilder {
+    /// Creates an empty mesh.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the amount of segments of full circles added after this and returns the builder.
+    ///
+    /// Rounded corners use a quarter of the segments. The default is 32.
+    pub fn segments(mut self, segments: u32) -> Self {
+        self.segments = segments.max(3);
+        self
+    }
+
+    /// Adds a rectangle with the given center and half size.
+    pub fn rect(mut self, center: Vec2, half_size: Vec2) -> Self {
+        let half_size = half_size.abs();
+        self.add_convex(vec![
+            center - half_size,
+            center + vec2(half_size.x, -half_size.y),
+            center + half_size,
+            center + vec2(-half_size.x, half_size.y),
+        ]);
+        self
+    }
+
+    /// Adds a rectangle with corners rounded by the given radius.
+    pub fn rounded_rect(mut self, center: Vec2, half_size: Vec2, radius: f32) -> Self {
+        let half_size = half_size.abs();
+        let radius = radius.clamp(0.0, half_size.min_element());
+        if radius <= 0.0 {
+            return self.rect(center, half_size);
+        }
+        let inner = half_size - Vec2::splat(radius);
+        let corners = [
+            (vec2(inner.x, -inner.y), -0.25),
+            (inner, 0.0),
+            (vec2(-inner.x, inner.y), 0.25),
+            (-inner, 0.5),
+        ];
+        let steps = self.corner_segments();
+        let mut points = Vec::with_capacity(corners.len() * (steps as usize + 1));
+        for (corner, start) in corners {
+            points.extend(arc(center + corner, radius, start, 0.25, steps));
+        }
+        self.add_convex(points);
+        self
+    }
+
+    /// Adds a circle.
+    pub fn circle(mut self, center: Vec2, radius: f32) -> Self {
+        let segments = self.segments;
+        let mut points = arc(center, radius.abs(), 0.0, 1.0, segments);
+        // The last point of a full arc is the same as the first one.
+        points.pop();
+        self.add_convex(points);
+        self
+    }
+
+    /// Adds a capsule, which is a line from `a` to `b` with the given radius around it.
+    pub fn capsule(mut self, a: Vec2, b: Vec2, radius: f32) -> Self {
+        let radius = radius.abs();
+        if a.distance_squared(b) <= f32::EPSILON {
+            return self.circle(a, radius);
+        }
+        // The angle of the direction from a to b in turns.
+        let direction = b - a;
+        let direction = direction.y.atan2(direction.x) / std::f32::consts::TAU;
+        let steps = self.corner_segments() * 2;
+        let mut points = arc(b, radius, direction - 0.25, 0.5, steps);
+        points.extend(arc(a, radius, direction + 0.25, 0.5, steps));
+        self.add_convex(points);
+        self
+    }
+
+    /// Adds a ring between the inner and outer radius.
+    ///
+    /// An inner radius of zero adds a circle instead.
+    pub fn ring(mut self, center: Vec2, inner_radius: f32, outer_radius: f32) -> Self {
+        let (inner_radius, outer_radius) = (
+            inner_radius.abs().min(outer_radius.abs()),
+            inner_radius.abs().max(outer_radius.abs()),
+        );
+        if inner_radius <= 0.0 {
+            return self.circle(center, outer_radius);
+        }
+        let segments = self.segments;
+        let outer = arc(center, outer_radius, 0.0, 1.0, segments);
+        let inner = arc(center, inner_radius, 0.0, 1.0, segments);
+
+        let base = self.vertices.len() as u32;
+        for index in 0..segments as usize {
+            self.vertices.push(outer[index]);
+            self.vertices.push(inner[index]);
+        }
+        for index in 0..segments {
+            let next = (index + 1) % segments;
+            let (outer, inner) = (base + index * 2, base + index * 2 + 1);
+            let (next_outer, next_inner) = (base + next * 2, base + next * 2 + 1);
+            self.indices
+                .extend([outer, next_outer, next_inner, outer, next_inner, inner]);
+        }
+        // Every segment of a ring is convex on its own.
+        for index in 0..segments as usize {
+            self.parts.push(vec![
+                outer[index],
+                outer[index + 1],
+                inner[index + 1],
+                inner[index],
+            ]);
+        }
+        self
+    }
+
+    /// Adds a polygon with the given outline, which may be concave but must not cross itself.
+    ///
+    /// The points can be in clockwise or counter clockwise order.
+    pub fn polygon(self, outline: &[Vec2]) -> Result<Self, MeshError> {
+        self.polygon_with_holes(outline, &[])
+    }
+
+    /// Adds a polygon with holes cut out of it.
+    ///
+    /// The holes must be inside of the outline and must neither cross each other nor the outline.
+    pub fn polygon_with_holes(
+        mut self,
+        outline: &[Vec2],
+        holes: &[Vec<Vec2>],
+    ) -> Result<Self, MeshError> {
+        let outline = clean_outline(outline, true).ok_or(MeshError::TooFewPoints)?;
+        let holes: Vec<Vec<Vec2>> = holes
+            .iter()
+            .filter_map(|hole| clean_outline(hole, false))
+            .collect();
+        let points = bridge_holes(outline, holes)?;
+        let triangles = triangulate(&points).ok_or(MeshError::SelfIntersecting)?;
+
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(&points);
+        self.indices.extend(
+            triangles
+                .iter()
+                .flat_map(|triangle| triangle.map(|index| base + index as u32)),
+        );
+        self.parts.extend(convex_parts(&points, &triangles));
+        Ok(self)
+    }
+
+    /// Adds all shapes of another builder to this one.
+    pub fn merge(mut self, other: MeshBuilder) -> Self {
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(other.vertices);
+        self.indices
+            .extend(other.indices.into_iter().map(|index| base + index));
+        self.parts.extend(other.parts);
+        self
+    }
+
+    /// Moves all shapes added so far by the given offset.
+    pub fn translate(self, offset: Vec2) -> Self {
+        self.map(|point| point + offset)
+    }
+
+    /// Rotates all shapes added so far around the origin by the given angle in radians.
+    pub fn rotate(self, angle: f32) -> Self {
+        let rotation = Vec2::from_angle(angle);
+        self.map(|point| rotation.rotate(point))
+    }
+
+    /// Scales all shapes added so far from the origin.
+    pub fn scale(mut self, scale: Vec2) -> Self {
+        // Mirroring turns the parts clockwise.
+        if scale.x * scale.y < 0.0 {
+            for part in &mut self.parts {
+                part.reverse();
+            }
+        }
+        self.map(|point| point * scale)
+    }
+
+    /// Returns true if no shape was added yet.
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Returns the vertices and indices of the mesh.
+    pub fn data(&self) -> Data {
+        Data::Dynamic {
+            vertices: self
+                .vertices
+                .iter()
+                .map(|point| vert(point.x, point.y))
+                .collect(),
+            indices: self.indices.clone(),
+        }
+    }
+
+    /// Loads the mesh as a model.
+    ///
+    /// Returns an error in case the mesh is empty or the GPU memory is full.
+    pub fn model(&self) -> Result<Model> {
+        Ok(Model::Custom(ModelData::new(self.data())?))
+    }
+
+    /// Returns a shape matching the mesh, made of the convex parts of every added shape.
+    ///
+    /// Returns `None` in case the mesh is empty.
+    #[cfg(feature = "physics")]
+    pub fn shape(&self) -> Option<Shape> {
+        let mut shapes: Vec<Shape> = self
+            .parts
+            .iter()
+            .filter_map(|part| Shape::convex_polyline(part))
+            .collect();
+        match shapes.len() {
+            0 => None,
+            1 => shapes.pop(),
+            _ => Some(Shape::compound(
+                shapes
+                    .into_iter()
+                    .map(|shape| (Transform::default(), shape))
+                    .collect(),
+            )),
+        }
+    }
+
+    /// Returns a collider builder with a shape matching the mesh.
+    #[cfg(feature = "physics")]
+    pub fn collider(&self) -> Option<ColliderBuilder> {
+        self.shape().map(ColliderBuilder::new)
+    }
+
+    /// Loads the model of the mesh and creates a collider of the same shape in one call.
+    #[cfg(feature = "physics")]
+    pub fn model_and_collider(&self) -> Result<(Model, ColliderBuilder)> {
+        let model = self.model()?;
+        let collider = self.collider().ok_or(super::data::NoDataError)?;
+        Ok((model, collider))
+    }
+
+    fn corner_segments(&self) -> u32 {
+        (self.segments / 4).max(1)
+    }
+
+    /// Adds a convex polygon in counter clockwise order as a triangle fan.
+    fn add_convex(&mut self, mut points: Vec<Vec2>) {
+        // Corners with a radius as big as the rect meet at the same point.
+        points.dedup();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        let base = self.vertices.len() as u32;
+        self.vertices.extend(&points);
+        for index in 1..points.len().saturating_sub(1) as u32 {
+            self.indices.extend([base, base + index, base + index + 1]);
+        }
+        self.parts.push(points);
+    }
+
+    fn map(mut self, f: impl Fn(Vec2) -> Vec2) -> Self {
+        for point in self
+            .vertices
+            .iter_mut()
+            .chain(self.parts.iter_mut().flatten())
+        {
+            *point = f(*point);
+        }
+        self
+    }
+}
+
+/// Returns `steps + 1` points of an arc counter clockwise from `start` over the given length, both in turns.
+fn arc(center: Vec2, radius: f32, start: f32, length: f32, steps: u32) -> Vec<Vec2> {
+    (0..=steps)
+        .map(|step| {
+            let turns = start + length * step as f32 / steps as f32;
+            center + Vec2::from_angle(turns * std::f32::consts::TAU) * radius
+        })
+        .collect()
+}
+
+/// Two times the signed area of the polygon, positive for counter clockwise polygons.
+fn signed_area(points: &[Vec2]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum()
+}
+
+/// Returns true if `b` is a corner where the outline does not turn, relative to the length of the edges.
+fn is_straight(a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let cross = (b - a).perp_dot(c - b);
+    cross.abs() <= 1e-6 * (b - a).length() * (c - b).length()
+}
+
+/// Removes duplicate and straight points of an outline and orients it counter clockwise, or clockwise for holes.
+fn clean_outline(outline: &[Vec2], counter_clockwise: bool) -> Option<Vec<Vec2>> {
+    let mut points = outline.to_vec();
+    points.dedup_by(|b, a| a.distance_squared(*b) <= f32::EPSILON * f32::EPSILON);
+    while points.len() > 1 && points[0].distance_squared(points[points.len() - 1]) <= f32::EPSILON {
+        points.pop();
+    }
+    let mut index = 0;
+    while points.len() >= 3 && index < points.len() {
+        let len = points.len();
+        let (a, b, c) = (
+            points[(index + len - 1) % len],
+            points[index],
+            points[(index + 1) % len],
+        );
+        if is_straight(a, b, c) {
+            points.remove(index);
+            index = index.saturating_sub(1);
+        } else {
+            index += 1;
+        }
+    }
+    if points.len() < 3 {
+        return None;
+    }
+    if (signed_area(&points) > 0.0) != counter_clockwise {
+        points.reverse();
+    }
+    Some(points)
+}
+
+/// Returns true if the segments cross each other at a point that is not one of their ends.
+fn segments_cross(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let side = |p: Vec2, q: Vec2, r: Vec2| (q - p).perp_dot(r - p);
+    let (d1, d2) = (side(a, b, c), side(a, b, d));
+    let (d3, d4) = (side(c, d, a), side(c, d, b));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Connects the clockwise holes to the counter clockwise outline with cuts, making a single outline.
+fn bridge_holes(mut outline: Vec<Vec2>, mut holes: Vec<Vec<Vec2>>) -> Result<Vec<Vec2>, MeshError> {
+    let rightmost = |hole: &Vec<Vec2>| {
+        (0..hole.len())
+            .max_by(|a, b| hole[*a].x.total_cmp(&hole[*b].x))
+            .unwrap()
+    };
+    // Holes further right get connected first, so later cuts do not have to cross earlier holes.
+    holes.sort_by(|a, b| b[rightmost(b)].x.total_cmp(&a[rightmost(a)].x));
+
+    for (hole_index, hole) in holes.iter().enumerate() {
+        let start = rightmost(hole);
+        let from = hole[start];
+
+        let blocked = |to: Vec2| {
+            let edges = |points: &[Vec2]| -> Vec<(Vec2, Vec2)> {
+                points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .map(|(a, b)| (*a, *b))
+                    .collect()
+            };
+            edges(&outline)
+                .into_iter()
+                .chain(holes[hole_index..].iter().flat_map(|hole| edges(hole)))
+                .any(|(a, b)| segments_cross(from, to, a, b))
+        };
+
+        let mut candidates: Vec<usize> = (0..outline.len()).collect();
+        candidates.sort_by(|a, b| {
+            outline[*a]
+                .distance_squared(from)
+                .total_cmp(&outline[*b].distance_squared(from))
+        });
+        let target = candidates
+            .into_iter()
+            .find(|index| !blocked(outline[*index]))
+            .ok_or(MeshError::SelfIntersecting)?;
+
+        let mut bridged = Vec::with_capacity(outline.len() + hole.len() + 2);
+        bridged.extend(&outline[..=target]);
+        bridged.extend(hole[start..].iter().chain(&hole[..start]));
+        bridged.push(from);
+        bridged.push(outline[target]);
+        bridged.extend(&outline[target + 1..]);
+        outline = bridged;
+    }
+    Ok(outline)
+}
+
+/// Triangulates a counter clockwise outline by clipping ears.
+///
+/// Returns `None` in case the outline intersects itself.
+fn triangulate(points: &[Vec2]) -> Option<Vec<[usize; 3]>> {
+    // Clipping ears does not notice crossing edges in every case, like in a bow tie.
+    let edges = points.iter().zip(points.iter().cycle().skip(1));
+    if edges.clone().enumerate().any(|(index, (a, b))| {
+        edges
+            .clone()
+            .skip(index + 1)
+            .any(|(c, d)| segments_cross(*a, *b, *c, *d))
+    }) {
+        return None;
+    }
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len().saturating_sub(2));
+    let mut index = 0;
+    // The amount of corners checked since the last clipped one.
+    let mut checked = 0;
+
+    while remaining.len() > 3 {
+        let len = remaining.len();
+        index %= len;
+        let (a, b, c) = (
+            remaining[(index + len - 1) % len],
+            remaining[index],
+            remaining[(index + 1) % len],
+        );
+        let (pa, pb, pc) = (points[a], points[b], points[c]);
+
+        // Straight corners and the tips of the cuts to holes cover no area.
+        if is_straight(pa, pb, pc) {
+            remaining.remove(index);
+            checked = 0;
+            continue;
+        }
+
+        let convex = (pb - pa).perp_dot(pc - pb) > 0.0;
+        let ear = convex
+            && !remaining.iter().any(|&other| {
+                let point = points[other];
+                point != pa && point != pb && point != pc && in_triangle(point, pa, pb, pc)
+            });
+        if ear {
+            triangles.push([a, b, c]);
+            remaining.remove(index);
+            checked = 0;
+        } else {
+            index += 1;
+            checked += 1;
+            if checked > len {
+                return None;
+            }
+        }
+    }
+    if let [a, b, c] = remaining[..] {
+        if !is_straight(points[a], points[b], points[c]) {
+            triangles.push([a, b, c]);
+        }
+    }
+    Some(triangles)
+}
+
+/// Returns true if the point is inside of or on the edge of the counter clockwise triangle.
+fn in_triangle(point: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).perp_dot(point - a) >= 0.0
+        && (c - b).perp_dot(point - b) >= 0.0
+        && (a - c).perp_dot(point - c) >= 0.0
+}
+
+/// Merges neighbouring triangles to bigger convex polygons, as long as they stay convex.
+fn convex_parts(points: &[Vec2], triangles: &[[usize; 3]]) -> Vec<Vec<Vec2>> {
+    let mut parts: Vec<Vec<usize>> = triangles.iter().map(|triangle| triangle.to_vec()).collect();
+
+    'merge: loop {
+        for a in 0..parts.len() {
+            for b in a + 1..parts.len() {
+                if let Some(merged) = merge_convex(points, &parts[a], &parts[b]) {
+                    parts[a] = merged;
+                    parts.swap_remove(b);
+                    continue 'merge;
+                }
+            }
+        }
+        break;
+    }
+
+    parts
+        .into_iter()
+        .map(|part| part.into_iter().map(|index| points[index]).collect())
+        .collect()
+}
+
+/// Joins two counter clockwise polygons sharing an edge, in case the result is convex.
+fn merge_convex(points: &[Vec2], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (a_len, b_len) = (a.len(), b.len());
+    for i in 0..a_len {
+        let (from, to) = (a[i], a[(i + 1) % a_len]);
+        // The shared edge goes the other way around in the neighbour.
+        let Some(j) = (0..b_len).find(|j| b[*j] == to && b[(*j + 1) % b_len] == from) else {
+            continue;
+        };
+        let mut merged = Vec::with_capacity(a_len + b_len - 2);
+        merged.extend((1..=a_len).map(|k| a[(i + k) % a_len]));
+        merged.extend((2..b_len).map(|k| b[(j + k) % b_len]));
+
+        let len = merged.len();
+        let convex = (0..len).all(|k| {
+            let (p, q, r) = (
+                points[merged[(k + len - 1) % len]],
+                points[merged[k]],
+                points[merged[(k + 1) % len]],
+            );
+            (q - p).perp_dot(r - q) > 0.0
+        });
+        return convex.then_some(merged);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two times the area covered by the triangles, failing on triangles that are not counter clockwise.
+    fn triangles_area(points: &[Vec2], triangles: &[[usize; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let area = signed_area(&[points[a], points[b], points[c]]);
+                assert!(
+                    area > 0.0,
+                    "The triangle {a} {b} {c} is not counter clockwise."
+                );
+                area
+            })
+            .sum()
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // An L shape with one reflex corner.
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(2.0, 1.0),
+            vec2(1.0, 1.0),
+            vec2(1.0, 2.0),
+            vec2(0.0, 2.0),
+        ];
+        let triangles = triangulate(&points).unwrap();
+
+        assert_eq!(triangles.len(), points.len() - 2);
+        assert!((triangles_area(&points, &triangles) - signed_area(&points)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn collinear_points() {
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(0.0, 2.0),
+        ];
+        // Straight corners do not make triangles without area.
+        let triangles = triangulate(&points).unwrap();
+        assert!((triangles_area(&points, &triangles) - signed_area(&points)).abs() < 1e-5);
+
+        assert_eq!(clean_outline(&points, true).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn both_orientations() {
+        let counter_clockwise = [
+            vec2(0.0, 0.0),
+            vec2(2.0, 0.0),
+            vec2(1.0, 0.5),
+            vec2(2.0, 2.0),
+            vec2(0.0, 2.0),
+        ];
+        let mut clockwise = counter_clockwise;
+        clockwise.reverse();
+
+        for outline in [&counter_clockwise, &clockwise] {
+            let mesh = MeshBuilder::new().polygon(outline).unwrap();
+            let triangles: Vec<[usize; 3]> = mesh
+                .indices
+                .chunks(3)
+                .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+                .collect();
+            assert!(
+                (triangles_area(&mesh.vertices, &triangles) - signed_area(&counter_clockwise))
+                    .abs()
+                    < 1e-5
+            );
+        }
+    }
+
+    #[test]
+    fn polygon_with_hole() {
+        let outline = [
+            vec2(-2.0, -2.0),
+            vec2(2.0, -2.0),
+            vec2(2.0, 2.0),
+            vec2(-2.0, 2.0),
+        ];
+        let hole = vec![
+            vec2(-1.0, -1.0),
+            vec2(1.0, -1.0),
+            vec2(1.0, 1.0),
+            vec2(-1.0, 1.0),
+        ];
+        // The cut to the hole runs along itself, which does not count as crossing.
+        let mesh = MeshBuilder::new()
+            .polygon_with_holes(&outline, &[hole])
+            .unwrap();
+        let triangles: Vec<[usize; 3]> = mesh
+            .indices
+            .chunks(3)
+            .map(|triangle| [0, 1, 2].map(|corner| triangle[corner] as usize))
+            .collect();
+        assert!((triangles_area(&mesh.vertices, &triangles) - 24.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        let line = [vec2(0.0, 0.0), vec2(1.0, 1.0), vec2(2.0, 2.0)];
+        assert_eq!(clean_outline(&line, true), None);
+        assert_eq!(
+            MeshBuilder::new().polygon(&line),
+            Err(MeshError::TooFewPoints)
+        );
+
+        // A bow tie crossing itself in the middle.
+        let bow_tie = [
+            vec2(0.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(2.0, 0.0),
+            vec2(0.0, 2.0),
+        ];
+        assert_eq!(triangulate(&bow_tie), None);
+    }
+}
@@ -1,16 +1,35 @@
 //! Resources to be handled by the engine like textures, sounds and fonts.
 //!
 //! Panics the program in case the system is not capable of running the game engine.
+//!
+//! # Lifetime
+//!
+//! Textures, materials and models are handles sharing their GPU resources between clones.
+//! The resources get freed once the last clone is dropped and the frames still in flight that use them are finished,
+//! so there is nothing to remove by hand. The pipelines of dropped materials get freed when the next frame gets drawn.
+//! Pipelines the game manages by hand instead of through materials can be kept using `Loader::pin_pipeline`.
+//! Textures loaded using the `RESOURCE_CACHE` of `let-engine` stay in the cache until they get evicted.
+//!
+//! # Device loss
+//!
+//! In case the GPU device gets lost, for example after a driver crash or update, the engine makes a new one.
+//! Textures and models keep their data on the CPU and get loaded to the new device right away.
+//! Materials written to using descriptors, shaders made from modules and uniform buffers can not be restored,
+//! so objects using them do not get drawn until the game makes them again.
 
 use anyhow::{Context, Result};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use std::sync::{Arc, OnceLock};
 use vulkano::buffer::BufferContents;
 use vulkano::descriptor_set::WriteDescriptorSet;
 use vulkano::pipeline::cache::{PipelineCache, PipelineCacheCreateInfo};
+use vulkano::swapchain::Surface;
 use winit::event_loop::EventLoop;
 
+mod batch;
+pub(crate) mod buffer;
 mod loader;
+pub(crate) mod memory;
 pub(crate) mod vulkan;
 pub(crate) use loader::Loader;
 use vulkan::Vulkan;
@@ -18,12 +37,27 @@ use vulkan::Vulkan;
 pub mod textures;
 
 pub mod data;
+#[cfg(feature = "glsl")]
+pub mod glsl;
+#[cfg(feature = "hot_reload")]
+pub mod hot_reload;
 pub mod materials;
+pub mod mesh;
 mod model;
+mod pipeline_cache;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod uniforms;
 
+pub use batch::{LoadBatch, LoadedBatch};
+pub use memory::{allocations, Allocation, MemoryKind};
 pub use model::*;
+pub use pipeline_cache::{
+    clear_pipeline_cache, pipeline_cache_directory, save_pipeline_cache,
+    set_pipeline_cache_directory,
+};
 
-use crate::EngineError;
+use crate::{draw::Graphics, EngineError};
 
 use self::data::BasicShapes;
 
@@ -33,17 +67,18 @@ pub fn resources<'a>() -> Result<&'a Resources, EngineError> {
 }
 
 /// All the resources kept in the game engine like textures, fonts, sounds and models.
-#[derive(Clone)]
 pub struct Resources {
-    pub vulkan: Vulkan,
+    /// Replaced by a new one in case the device gets lost.
+    vulkan: RwLock<Arc<Vulkan>>,
     pub loader: Arc<Mutex<Loader>>,
     pub shapes: BasicShapes,
 }
 
 impl Resources {
-    pub fn new(event_loop: &EventLoop<()>) -> Result<Self, EngineError> {
-        let (materials, vulkan) =
-            Vulkan::init(event_loop).map_err(|e| EngineError::RequirementError(e.to_string()))?;
+    /// Initializes Vulkan, choosing the color space of the window from the given graphics settings.
+    pub fn new(event_loop: &EventLoop<()>, graphics: &Graphics) -> Result<Self, EngineError> {
+        let (materials, vulkan) = Vulkan::init(event_loop, graphics)
+            .map_err(|e| EngineError::RequirementError(e.to_string()))?;
 
         let loader = Arc::new(Mutex::new(
             Loader::init(&vulkan, materials)
@@ -54,14 +89,33 @@ impl Resources {
             .context("Failed to load default shapes into the GPU memory.")
             .map_err(EngineError::Other)?;
         Ok(Self {
-            vulkan,
+            vulkan: RwLock::new(Arc::new(vulkan)),
             loader,
             shapes,
         })
     }
 
-    pub fn vulkan(&self) -> &Vulkan {
-        &self.vulkan
+    /// Makes a new device drawing to the given surface after the previous one got lost,
+    /// loading the textures and models to it again.
+    pub(crate) fn rebuild(&self, surface: &Arc<Surface>, graphics: &Graphics) -> Result<()> {
+        let mut loader = self.loader.lock();
+        let previous = self.vulkan();
+        let (pipelines, mut vulkan) = Vulkan::new(previous.instance.clone(), surface, graphics)
+            .context("Failed to make a new device after the previous one got lost.")?;
+        vulkan.keep_materials(&previous);
+
+        // The uploads can never finish, and their futures would wait on the lost device when dropped.
+        std::mem::forget(loader.take_uploads());
+        *loader = Loader::init(&vulkan, pipelines)?;
+
+        let vulkan = Arc::new(vulkan);
+        *self.vulkan.write() = vulkan.clone();
+        memory::reload(&vulkan, &mut loader);
+        Ok(())
+    }
+
+    pub fn vulkan(&self) -> Arc<Vulkan> {
+        self.vulkan.read().clone()
     }
     pub fn loader(&self) -> &Arc<Mutex<Loader>> {
         &self.loader
@@ -73,6 +127,8 @@ impl Resources {
 
 /// Merges a pipeline cache into the resources potentially making the creation of materials faster.
 ///
+/// The engine already keeps the cache on disk between runs, see [`set_pipeline_cache_directory`].
+///
 /// # Safety
 ///
 /// Unsafe because vulkan blindly trusts that this data comes from the `get_pipeline_binary` function.
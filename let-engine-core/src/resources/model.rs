@@ -1,57 +1,132 @@
 use crate::resources::data::*;
 use anyhow::Result;
+use glam::Vec2;
 use parking_lot::Mutex;
 use std::sync::Arc;
 use vulkano::buffer::Subbuffer;
 
-use super::{resources, Loader};
+use super::{
+    memory::{self, MemoryKind, Reload, TrackedMemory},
+    resources,
+    vulkan::Vulkan,
+    Loader,
+};
 
 /// The custom model of an object made of vertices and indices.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct ModelData {
-    vertex_sub_buffer: Subbuffer<[Vertex]>,
-    index_sub_buffer: Subbuffer<[u32]>,
+    gpu: Arc<GpuModel>,
+    bounds: [Vec2; 2],
+    memory: Arc<TrackedMemory>,
+}
+
+/// The buffers of a model shared between its clones, along with the data to load them again.
+#[derive(Debug)]
+struct GpuModel {
+    buffers: Mutex<(Subbuffer<[Vertex]>, Subbuffer<[u32]>)>,
     data: Data,
 }
 
+impl Reload for GpuModel {
+    fn reload(&self, _vulkan: &Vulkan, loader: &mut Loader) -> Result<()> {
+        *self.buffers.lock() = upload(&self.data, loader)?;
+        Ok(())
+    }
+}
+
+/// Allocates the vertex and index buffers of the data and writes it to them.
+fn upload(data: &Data, loader: &Loader) -> Result<(Subbuffer<[Vertex]>, Subbuffer<[u32]>)> {
+    let vertex_sub_buffer = loader
+        .vertex_buffer_allocator
+        .allocate_slice(data.vertices().len() as _)?;
+    let index_sub_buffer = loader
+        .index_buffer_allocator
+        .allocate_slice(data.indices().len() as _)?;
+
+    vertex_sub_buffer.write()?.copy_from_slice(data.vertices());
+    index_sub_buffer.write()?.copy_from_slice(data.indices());
+    Ok((vertex_sub_buffer, index_sub_buffer))
+}
+
 impl ModelData {
     /// Makes a new model with given data.
     ///
     /// Can return an error in case the GPU memory is full.
     pub fn new(data: Data) -> Result<Self> {
-        Self::new_from_loader(data, resources()?.loader())
+        Self::new_from_loader(data, None, resources()?.loader())
     }
 
-    pub(crate) fn new_from_loader(data: Data, loader: &Arc<Mutex<Loader>>) -> Result<Self> {
+    /// Makes a new model with given data and a name to show up in memory reports.
+    ///
+    /// Can return an error in case the GPU memory is full.
+    pub fn new_named(data: Data, name: &str) -> Result<Self> {
+        Self::new_from_loader(data, Some(name), resources()?.loader())
+    }
+
+    pub(crate) fn new_from_loader(
+        data: Data,
+        name: Option<&str>,
+        loader: &Arc<Mutex<Loader>>,
+    ) -> Result<Self> {
         if data.is_empty() {
             return Err(NoDataError.into());
         }
-        let loader = loader.lock();
-        let vertex_sub_buffer = loader
-            .vertex_buffer_allocator
-            .allocate_slice(data.vertices().len() as _)?;
-        let index_sub_buffer = loader
-            .index_buffer_allocator
-            .allocate_slice(data.indices().len() as _)?;
-
-        vertex_sub_buffer.write()?.copy_from_slice(data.vertices());
-        index_sub_buffer.write()?.copy_from_slice(data.indices());
-
-        Ok(Self {
-            vertex_sub_buffer,
-            index_sub_buffer,
+        let buffers = upload(&data, &loader.lock())?;
+        Ok(Self::from_buffers(data, name, buffers))
+    }
+
+    /// Wraps a model already loaded to the GPU.
+    pub(crate) fn from_buffers(
+        data: Data,
+        name: Option<&str>,
+        (vertex_sub_buffer, index_sub_buffer): (Subbuffer<[Vertex]>, Subbuffer<[u32]>),
+    ) -> Self {
+        let memory = TrackedMemory::new(
+            MemoryKind::Buffer,
+            vertex_sub_buffer.size() + index_sub_buffer.size(),
+            name.map(Arc::from),
+        );
+
+        let bounds = data.vertices().iter().fold(
+            [Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)],
+            |[min, max], vertex| [min.min(vertex.position), max.max(vertex.position)],
+        );
+
+        let gpu = Arc::new(GpuModel {
+            buffers: Mutex::new((vertex_sub_buffer, index_sub_buffer)),
             data,
-        })
+        });
+        memory::register(&gpu);
+        Self {
+            gpu,
+            bounds,
+            memory,
+        }
     }
 
     /// Returns the index and vertex data of this object.
     pub fn data(&self) -> &Data {
-        &self.data
+        &self.gpu.data
+    }
+
+    /// Returns the name of this model.
+    pub fn name(&self) -> Option<&str> {
+        self.memory.label()
+    }
+
+    /// Returns the number of handles sharing the GPU side of this model, including the ones held by objects.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.memory)
+    }
+
+    /// Returns the smallest and largest vertex position of this model.
+    pub fn bounds(&self) -> [Vec2; 2] {
+        self.bounds
     }
 
     /// Returns the size of this model in number of indices.
     pub fn size(&self) -> usize {
-        self.data.indices().len()
+        self.gpu.data.indices().len()
     }
 
     //TODO(Letronix624): This along with texture updates comes in the future when we optimize.
@@ -74,12 +149,23 @@ impl ModelData {
     //     Ok(())
     // }
 
+    /// Returns true if both models use the same buffers on the GPU.
+    pub(crate) fn same_buffers(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.gpu, &other.gpu)
+    }
+
     pub(crate) fn vertex_buffer(&self) -> Subbuffer<[Vertex]> {
-        self.vertex_sub_buffer.clone()
+        self.gpu.buffers.lock().0.clone()
     }
 
     pub(crate) fn index_buffer(&self) -> Subbuffer<[u32]> {
-        self.index_sub_buffer.clone()
+        self.gpu.buffers.lock().1.clone()
+    }
+}
+
+impl PartialEq for ModelData {
+    fn eq(&self, other: &Self) -> bool {
+        self.same_buffers(other)
     }
 }
 
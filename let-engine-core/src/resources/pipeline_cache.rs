@@ -0,0 +1,155 @@
+//! Keeping the compiled pipelines on disk between runs of the game.
+//!
+//! The cache gets saved per GPU and driver in the cache directory of the platform:
+//!
+//! - Linux: `$XDG_CACHE_HOME/<game>` or `~/.cache/<game>`
+//! - Windows: `%LOCALAPPDATA%\<game>`
+//! - macOS: `~/Library/Caches/<game>`
+//!
+//! where `<game>` is the name of the executable by default.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::PathBuf,
+    sync::{Arc, LazyLock},
+};
+
+use anyhow::Result;
+use parking_lot::RwLock;
+use vulkano::{
+    device::DeviceProperties,
+    pipeline::cache::{PipelineCache, PipelineCacheCreateInfo},
+};
+
+use super::{resources, vulkan::Vulkan};
+
+const PREFIX: &str = "pipelines-";
+const EXTENSION: &str = "bin";
+
+static DIRECTORY: LazyLock<RwLock<Option<PathBuf>>> = LazyLock::new(|| {
+    let game = std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_owned()));
+    RwLock::new(
+        cache_directory()
+            .zip(game)
+            .map(|(directory, game)| directory.join(game)),
+    )
+});
+
+/// Returns the cache directory of the platform.
+fn cache_directory() -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home().map(|home| home.join(".cache")))
+    }
+}
+
+/// Sets the directory the pipeline cache gets saved in, `None` to not keep it on disk.
+///
+/// Has to be set before the engine starts to load the cache from the new directory.
+pub fn set_pipeline_cache_directory(directory: Option<PathBuf>) {
+    *DIRECTORY.write() = directory;
+}
+
+/// Returns the directory the pipeline cache gets saved in.
+pub fn pipeline_cache_directory() -> Option<PathBuf> {
+    DIRECTORY.read().clone()
+}
+
+/// Returns the file of the cache of this GPU and driver.
+fn path(properties: &DeviceProperties) -> Option<PathBuf> {
+    Some(pipeline_cache_directory()?.join(format!(
+        "{PREFIX}{:04x}-{:04x}-{:x}.{EXTENSION}",
+        properties.vendor_id, properties.device_id, properties.driver_version
+    )))
+}
+
+/// Returns true if the header of the cache data was written by this GPU and driver.
+fn compatible(data: &[u8], properties: &DeviceProperties) -> bool {
+    // The header is little endian: header size, header version, vendor ID, device ID and the cache UUID.
+    let word = |index: usize| {
+        data.get(index * 4..index * 4 + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    word(1) == Some(1)
+        && word(2) == Some(properties.vendor_id)
+        && word(3) == Some(properties.device_id)
+        && data.get(16..32) == Some(&properties.pipeline_cache_uuid[..])
+}
+
+/// Creates the pipeline cache, filled with the saved one in case it fits this GPU and driver.
+pub(crate) fn load(vulkan: &Vulkan) -> Result<Arc<PipelineCache>> {
+    let properties = vulkan.device.physical_device().properties();
+    let initial_data = path(properties)
+        .and_then(|path| fs::read(path).ok())
+        .filter(|data| compatible(data, properties))
+        .unwrap_or_default();
+    // Safety: the header got checked to come from this GPU and driver.
+    Ok(unsafe {
+        PipelineCache::new(
+            vulkan.device.clone(),
+            PipelineCacheCreateInfo {
+                initial_data,
+                ..Default::default()
+            },
+        )?
+    })
+}
+
+/// Writes the pipeline cache to the cache directory, so the materials of the next run get created faster.
+///
+/// Gets called by the engine when the game exits. Does nothing in case there is no directory.
+pub fn save_pipeline_cache() -> Result<()> {
+    let resources = resources()?;
+    let Some(path) = path(resources.vulkan().device.physical_device().properties()) else {
+        return Ok(());
+    };
+    let data = resources.loader().lock().pipeline_cache.get_data()?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    // Writing to another file first keeps the old cache intact in case writing fails.
+    let temporary = path.with_extension(format!("{EXTENSION}.tmp"));
+    fs::write(&temporary, data)?;
+    fs::rename(temporary, path)?;
+    Ok(())
+}
+
+/// Deletes the saved pipeline caches of all GPUs and starts over with an empty cache.
+pub fn clear_pipeline_cache() -> Result<()> {
+    if let Some(directory) = pipeline_cache_directory() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries.collect(),
+            Err(e) if e.kind() == ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let path = entry?.path();
+            let saved = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(PREFIX));
+            if saved {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    if let Ok(resources) = resources() {
+        resources.loader().lock().pipeline_cache = unsafe {
+            PipelineCache::new(
+                resources.vulkan().device.clone(),
+                PipelineCacheCreateInfo::default(),
+            )?
+        };
+    }
+    Ok(())
+}
@@ -0,0 +1,801 @@
+//! Loading vector graphics from SVG files into models.
+//!
+//! The paths, basic shapes, groups, transforms and solid colors of SVG files are supported.
+//! Gradients, patterns, text, masks, `use` elements and CSS style sheets get ignored.
+
+use anyhow::Result;
+use lyon::{
+    geom::{Angle, ArcFlags, Box2D},
+    math::{point, vector, Point, Transform},
+    path::{builder::BorderRadii, traits::SvgPathBuilder, Path, Polygon, Winding},
+    tessellation::{
+        BuffersBuilder, FillOptions, FillRule, FillTessellator, FillVertex, LineCap, LineJoin,
+        StrokeOptions, StrokeTessellator, StrokeVertex, TessellationError, VertexBuffers,
+    },
+};
+use thiserror::Error;
+
+use super::{
+    data::{vert, Data, Vertex},
+    Model, ModelData,
+};
+use crate::objects::Color;
+
+/// Errors that occur loading SVG files.
+#[derive(Debug, Error)]
+pub enum SvgError {
+    #[error("The SVG file is not valid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("The SVG file is not valid XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+    /// The root element has neither a `viewBox` nor a `width` and `height`.
+    #[error("The SVG file does not specify its size.")]
+    MissingSize,
+    #[error("The path data `{0}` is invalid.")]
+    PathData(String),
+    #[error("A shape could not be tessellated: {0:?}")]
+    Tessellation(TessellationError),
+}
+
+/// Settings for turning vector graphics into models.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgOptions {
+    /// The maximum distance between the curves and the triangles approximating them, in object space.
+    ///
+    /// Lower values make smoother curves using more triangles.
+    pub tolerance: f32,
+    /// Whether the fills of shapes get tessellated.
+    pub fill: bool,
+    /// Whether the strokes of shapes get tessellated.
+    pub stroke: bool,
+    /// A factor for the width of all strokes.
+    pub stroke_scale: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 0.002,
+            fill: true,
+            stroke: true,
+            stroke_scale: 1.0,
+        }
+    }
+}
+
+impl SvgOptions {
+    /// Sets the tolerance and returns the options.
+    #[inline]
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets whether fills get tessellated and returns the options.
+    #[inline]
+    pub fn fill(mut self, fill: bool) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Sets whether strokes get tessellated and returns the options.
+    #[inline]
+    pub fn stroke(mut self, stroke: bool) -> Self {
+        self.stroke = stroke;
+        self
+    }
+
+    /// Sets the factor for the width of all strokes and returns the options.
+    #[inline]
+    pub fn stroke_scale(mut self, stroke_scale: f32) -> Self {
+        self.stroke_scale = stroke_scale;
+        self
+    }
+}
+
+/// Whether a shape is the inside or the outline of an element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaintKind {
+    Fill,
+    Stroke,
+}
+
+/// A part of a vector image drawn in a single color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorShape {
+    pub data: Data,
+    pub color: Color,
+    pub kind: PaintKind,
+}
+
+/// The tessellated shapes of an SVG file in drawing order.
+///
+/// The view box of the file gets stretched over -1.0 to 1.0 on both axes like the default square,
+/// so the size of the object decides the size of the image. The aspect ratio of the file is available using
+/// [size](VectorImage::size).
+///
+/// As vertices do not have colors, every shape has its own color. Single colored icons can be drawn as one
+/// object using [model](VectorImage::model) and the color of the appearance, while images with multiple colors
+/// need an object per shape.
+///
+/// # Usage
+/// ```ignore
+/// let image = VectorImage::from_svg(&asset("icons/heart.svg").await?, SvgOptions::default())?;
+///
+/// for shape in image.shapes() {
+///     let mut object = NewObject::new();
+///     object.appearance = Appearance::new()
+///         .model(Some(Model::Custom(ModelData::new(shape.data.clone())?)))?
+///         .color(shape.color);
+///     object.init_with_parent(&icon)?;
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorImage {
+    shapes: Vec<VectorShape>,
+    size: (f32, f32),
+}
+
+impl VectorImage {
+    /// Reads and tessellates the contents of an SVG file.
+    pub fn from_svg(data: &[u8], options: SvgOptions) -> Result<Self, SvgError> {
+        let text = std::str::from_utf8(data)?;
+        let document = roxmltree::Document::parse(text)?;
+        let root = document.root_element();
+
+        let number = |name: &str| root.attribute(name).and_then(parse_number);
+        let view_box = root
+            .attribute("viewBox")
+            .map(parse_numbers)
+            .filter(|numbers| numbers.len() == 4 && numbers[2] > 0.0 && numbers[3] > 0.0);
+        let (x, y, width, height) = match (view_box, number("width"), number("height")) {
+            (Some(view_box), ..) => (view_box[0], view_box[1], view_box[2], view_box[3]),
+            (None, Some(width), Some(height)) if width > 0.0 && height > 0.0 => {
+                (0.0, 0.0, width, height)
+            }
+            _ => return Err(SvgError::MissingSize),
+        };
+        // From the view box to -1.0 to 1.0.
+        let view = Transform::translation(-x, -y)
+            .then_scale(2.0 / width, 2.0 / height)
+            .then_translate(vector(-1.0, -1.0));
+
+        let mut style = Style::default();
+        style.apply(root);
+        let mut elements = vec![];
+        collect(root, &style, &Transform::identity(), &mut elements)?;
+
+        let mut shapes = vec![];
+        let mut fill_tessellator = FillTessellator::new();
+        let mut stroke_tessellator = StrokeTessellator::new();
+        for (path, style, transform) in elements {
+            let transform = transform.then(&view);
+            let path = path.transformed(&transform);
+
+            if let Some(color) = style.fill.filter(|_| options.fill) {
+                let fill_options =
+                    FillOptions::tolerance(options.tolerance).with_fill_rule(if style.even_odd {
+                        FillRule::EvenOdd
+                    } else {
+                        FillRule::NonZero
+                    });
+                let mut buffers: VertexBuffers<Vertex, u32> = VertexBuffers::new();
+                fill_tessellator
+                    .tessellate_path(
+                        &path,
+                        &fill_options,
+                        &mut BuffersBuilder::new(&mut buffers, |vertex: FillVertex| {
+                            let position = vertex.position();
+                            vert(position.x, position.y)
+                        }),
+                    )
+                    .map_err(SvgError::Tessellation)?;
+                push_shape(
+                    &mut shapes,
+                    buffers,
+                    with_opacity(color, style.fill_opacity * style.opacity),
+                    PaintKind::Fill,
+                );
+            }
+
+            if let Some(color) = style.stroke.filter(|_| options.stroke) {
+                // Strokes get as much wider as the area of the shape grows.
+                let width = style.stroke_width
+                    * options.stroke_scale
+                    * transform.determinant().abs().sqrt();
+                if width <= 0.0 {
+                    continue;
+                }
+                let stroke_options = StrokeOptions::tolerance(options.tolerance)
+                    .with_line_width(width)
+                    .with_line_join(style.line_join)
+                    .with_line_cap(style.line_cap)
+                    .with_miter_limit(style.miter_limit.max(1.0));
+                let mut buffers: VertexBuffers<Vertex, u32> = VertexBuffers::new();
+                stroke_tessellator
+                    .tessellate_path(
+                        &path,
+                        &stroke_options,
+                        &mut BuffersBuilder::new(&mut buffers, |vertex: StrokeVertex| {
+                            let position = vertex.position();
+                            vert(position.x, position.y)
+                        }),
+                    )
+                    .map_err(SvgError::Tessellation)?;
+                push_shape(
+                    &mut shapes,
+                    buffers,
+                    with_opacity(color, style.stroke_opacity * style.opacity),
+                    PaintKind::Stroke,
+                );
+            }
+        }
+
+        Ok(Self {
+            shapes,
+            size: (width, height),
+        })
+    }
+
+    /// Returns the shapes of the image in the order they should be drawn.
+    pub fn shapes(&self) -> &[VectorShape] {
+        &self.shapes
+    }
+
+    /// Returns the width and height of the view box of the file.
+    pub fn size(&self) -> (f32, f32) {
+        self.size
+    }
+
+    /// Returns the shapes of the image combined into one, ignoring their colors.
+    pub fn data(&self) -> Data {
+        let mut vertices = vec![];
+        let mut indices = vec![];
+        for shape in &self.shapes {
+            let base = vertices.len() as u32;
+            vertices.extend_from_slice(shape.data.vertices());
+            indices.extend(shape.data.indices().iter().map(|index| base + index));
+        }
+        Data::Dynamic { vertices, indices }
+    }
+
+    /// Loads all shapes of the image combined as one model, for icons drawn in a single color.
+    pub fn model(&self) -> Result<Model> {
+        Ok(Model::Custom(ModelData::new(self.data())?))
+    }
+}
+
+fn push_shape(
+    shapes: &mut Vec<VectorShape>,
+    buffers: VertexBuffers<Vertex, u32>,
+    color: Color,
+    kind: PaintKind,
+) {
+    if buffers.indices.is_empty() || color.alpha() <= 0.0 {
+        return;
+    }
+    shapes.push(VectorShape {
+        data: Data::Dynamic {
+            vertices: buffers.vertices,
+            indices: buffers.indices,
+        },
+        color,
+        kind,
+    });
+}
+
+fn with_opacity(mut color: Color, opacity: f32) -> Color {
+    color.set_a(color.alpha() * opacity);
+    color
+}
+
+/// The presentation attributes of an element, inherited by its children.
+#[derive(Clone, Debug)]
+struct Style {
+    fill: Option<Color>,
+    stroke: Option<Color>,
+    stroke_width: f32,
+    /// The opacity of the element multiplied with the ones of its parents.
+    opacity: f32,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    even_odd: bool,
+    line_join: LineJoin,
+    line_cap: LineCap,
+    miter_limit: f32,
+    hidden: bool,
+    /// Whether the element and its children get drawn. Unlike the other properties this is not inherited.
+    displayed: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Some(Color::from_rgb(0.0, 0.0, 0.0)),
+            stroke: None,
+            stroke_width: 1.0,
+            opacity: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            even_odd: false,
+            line_join: LineJoin::Miter,
+            line_cap: LineCap::Butt,
+            miter_limit: 4.0,
+            hidden: false,
+            displayed: true,
+        }
+    }
+}
+
+impl Style {
+    /// Applies the presentation attributes and the `style` attribute of the element, which takes priority.
+    fn apply(&mut self, node: roxmltree::Node) {
+        for attribute in node.attributes() {
+            self.set(attribute.name(), attribute.value());
+        }
+        if let Some(style) = node.attribute("style") {
+            for declaration in style.split(';') {
+                if let Some((name, value)) = declaration.split_once(':') {
+                    self.set(name.trim(), value.trim());
+                }
+            }
+        }
+    }
+
+    /// Sets a property. Unsupported values keep the inherited value.
+    fn set(&mut self, name: &str, value: &str) {
+        let value = value.trim();
+        match name {
+            "fill" => {
+                if let Some(paint) = parse_paint(value) {
+                    self.fill = paint;
+                }
+            }
+            "stroke" => {
+                if let Some(paint) = parse_paint(value) {
+                    self.stroke = paint;
+                }
+            }
+            "stroke-width" => {
+                if let Some(width) = parse_number(value) {
+                    self.stroke_width = width;
+                }
+            }
+            "opacity" => {
+                if let Some(opacity) = parse_number(value) {
+                    self.opacity *= opacity.clamp(0.0, 1.0);
+                }
+            }
+            "fill-opacity" => {
+                if let Some(opacity) = parse_number(value) {
+                    self.fill_opacity = opacity.clamp(0.0, 1.0);
+                }
+            }
+            "stroke-opacity" => {
+                if let Some(opacity) = parse_number(value) {
+                    self.stroke_opacity = opacity.clamp(0.0, 1.0);
+                }
+            }
+            "fill-rule" => self.even_odd = value == "evenodd",
+            "stroke-linejoin" => match value {
+                "miter" => self.line_join = LineJoin::Miter,
+                "round" => self.line_join = LineJoin::Round,
+                "bevel" => self.line_join = LineJoin::Bevel,
+                _ => (),
+            },
+            "stroke-linecap" => match value {
+                "butt" => self.line_cap = LineCap::Butt,
+                "round" => self.line_cap = LineCap::Round,
+                "square" => self.line_cap = LineCap::Square,
+                _ => (),
+            },
+            "stroke-miterlimit" => {
+                if let Some(limit) = parse_number(value) {
+                    self.miter_limit = limit;
+                }
+            }
+            "display" => self.displayed = value != "none",
+            "visibility" => self.hidden = value == "hidden" || value == "collapse",
+            _ => (),
+        }
+    }
+}
+
+/// Collects the paths of all visible elements inside of the given one, along with their style and transform.
+fn collect(
+    node: roxmltree::Node,
+    style: &Style,
+    transform: &Transform,
+    elements: &mut Vec<(Path, Style, Transform)>,
+) -> Result<(), SvgError> {
+    for child in node.children().filter(|child| child.is_element()) {
+        let mut style = style.clone();
+        style.displayed = true;
+        style.apply(child);
+        if !style.displayed {
+            continue;
+        }
+        let transform = child
+            .attribute("transform")
+            .map(parse_transform)
+            .unwrap_or_else(Transform::identity)
+            .then(transform);
+
+        let number = |name: &str| child.attribute(name).and_then(parse_number).unwrap_or(0.0);
+        let path = match child.tag_name().name() {
+            "g" | "a" | "svg" | "switch" => {
+                // Hidden groups may still contain visible children.
+                collect(child, &style, &transform, elements)?;
+                continue;
+            }
+            "path" => {
+                let Some(data) = child.attribute("d") else {
+                    continue;
+                };
+                parse_path_data(data)?
+            }
+            "rect" => {
+                let (x, y) = (number("x"), number("y"));
+                let (width, height) = (number("width"), number("height"));
+                if width <= 0.0 || height <= 0.0 {
+                    continue;
+                }
+                let radius = child
+                    .attribute("rx")
+                    .or(child.attribute("ry"))
+                    .and_then(parse_number)
+                    .unwrap_or(0.0)
+                    .clamp(0.0, width.min(height) * 0.5);
+                let mut builder = Path::builder();
+                builder.add_rounded_rectangle(
+                    &Box2D::new(point(x, y), point(x + width, y + height)),
+                    &BorderRadii::new(radius),
+                    Winding::Positive,
+                );
+                builder.build()
+            }
+            "circle" => {
+                let mut builder = Path::builder();
+                builder.add_circle(
+                    point(number("cx"), number("cy")),
+                    number("r"),
+                    Winding::Positive,
+                );
+                builder.build()
+            }
+            "ellipse" => {
+                let mut builder = Path::builder();
+                builder.add_ellipse(
+                    point(number("cx"), number("cy")),
+                    vector(number("rx"), number("ry")),
+                    Angle::radians(0.0),
+                    Winding::Positive,
+                );
+                builder.build()
+            }
+            "line" => {
+                let mut builder = Path::builder();
+                builder.begin(point(number("x1"), number("y1")));
+                builder.line_to(point(number("x2"), number("y2")));
+                builder.end(false);
+                builder.build()
+            }
+            name @ ("polyline" | "polygon") => {
+                let numbers = parse_numbers(child.attribute("points").unwrap_or_default());
+                let points: Vec<Point> = numbers
+                    .chunks_exact(2)
+                    .map(|pair| point(pair[0], pair[1]))
+                    .collect();
+                if points.len() < 2 {
+                    continue;
+                }
+                let mut builder = Path::builder();
+                builder.add_polygon(Polygon {
+                    points: &points,
+                    closed: name == "polygon",
+                });
+                builder.build()
+            }
+            // Definitions, text, images and unknown elements.
+            _ => continue,
+        };
+        if !style.hidden {
+            elements.push((path, style, transform));
+        }
+    }
+    Ok(())
+}
+
+/// Parses a number with an optional `px` unit.
+fn parse_number(value: &str) -> Option<f32> {
+    value.trim().trim_end_matches("px").trim_end().parse().ok()
+}
+
+/// Parses a list of numbers separated by whitespace or commas.
+fn parse_numbers(value: &str) -> Vec<f32> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|number| !number.is_empty())
+        .filter_map(parse_number)
+        .collect()
+}
+
+/// Parses the value of a `transform` attribute.
+fn parse_transform(value: &str) -> Transform {
+    let mut result = Transform::identity();
+    for function in value.split(')') {
+        let Some((name, arguments)) = function.split_once('(') else {
+            continue;
+        };
+        let name = name.trim_matches(|c: char| c == ',' || c.is_whitespace());
+        let arguments = parse_numbers(arguments);
+        let transform = match (name, arguments.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Transform::new(a, b, c, d, e, f),
+            ("translate", &[x]) => Transform::translation(x, 0.0),
+            ("translate", &[x, y]) => Transform::translation(x, y),
+            ("scale", &[scale]) => Transform::scale(scale, scale),
+            ("scale", &[x, y]) => Transform::scale(x, y),
+            ("rotate", &[angle]) => Transform::rotation(Angle::degrees(angle)),
+            ("rotate", &[angle, x, y]) => Transform::translation(-x, -y)
+                .then_rotate(Angle::degrees(angle))
+                .then_translate(vector(x, y)),
+            ("skewX", &[angle]) => {
+                Transform::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+            }
+            ("skewY", &[angle]) => {
+                Transform::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+            }
+            _ => continue,
+        };
+        // The functions further right apply first.
+        result = transform.then(&result);
+    }
+    result
+}
+
+/// Parses the value of a `fill` or `stroke` attribute.
+///
+/// Returns `Some(None)` for `none` and `None` for unsupported paints like gradients.
+fn parse_paint(value: &str) -> Option<Option<Color>> {
+    let rgb = |r: u8, g: u8, b: u8| {
+        Some(Some(Color::from_rgb(
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+        )))
+    };
+    if let Some(hex) = value.strip_prefix('#') {
+        let digit = |index: usize| u8::from_str_radix(hex.get(index..index + 1)?, 16).ok();
+        let byte = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+        return match hex.len() {
+            3 => rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17),
+            6 => rgb(byte(0)?, byte(2)?, byte(4)?),
+            _ => None,
+        };
+    }
+    if let Some(arguments) = value
+        .strip_prefix("rgb(")
+        .and_then(|value| value.strip_suffix(')'))
+    {
+        let channels: Vec<u8> = arguments
+            .split(',')
+            .filter_map(|channel| {
+                let channel = channel.trim();
+                match channel.strip_suffix('%') {
+                    Some(percent) => percent.trim().parse::<f32>().ok().map(|p| p * 2.55),
+                    None => channel.parse::<f32>().ok(),
+                }
+            })
+            .map(|channel| channel.round().clamp(0.0, 255.0) as u8)
+            .collect();
+        return match channels[..] {
+            [r, g, b] => rgb(r, g, b),
+            _ => None,
+        };
+    }
+    match value {
+        "none" | "transparent" => Some(None),
+        "black" => rgb(0, 0, 0),
+        "white" => rgb(255, 255, 255),
+        "red" => rgb(255, 0, 0),
+        "lime" => rgb(0, 255, 0),
+        "green" => rgb(0, 128, 0),
+        "blue" => rgb(0, 0, 255),
+        "yellow" => rgb(255, 255, 0),
+        "cyan" | "aqua" => rgb(0, 255, 255),
+        "magenta" | "fuchsia" => rgb(255, 0, 255),
+        "orange" => rgb(255, 165, 0),
+        "purple" => rgb(128, 0, 128),
+        "gray" | "grey" => rgb(128, 128, 128),
+        "silver" => rgb(192, 192, 192),
+        "maroon" => rgb(128, 0, 0),
+        "navy" => rgb(0, 0, 128),
+        "olive" => rgb(128, 128, 0),
+        "teal" => rgb(0, 128, 128),
+        _ => None,
+    }
+}
+
+/// Reads the numbers and flags of SVG path data.
+struct PathLexer<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl PathLexer<'_> {
+    fn skip_separators(&mut self) {
+        while self
+            .bytes
+            .get(self.position)
+            .is_some_and(|byte| byte.is_ascii_whitespace() || *byte == b',')
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Returns the next command letter, in case the next token is one.
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let byte = *self.bytes.get(self.position)?;
+        if byte.is_ascii_alphabetic() && byte != b'e' && byte != b'E' {
+            self.position += 1;
+            Some(byte)
+        } else {
+            None
+        }
+    }
+
+    fn is_done(&mut self) -> bool {
+        self.skip_separators();
+        self.position >= self.bytes.len()
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.position;
+        let mut end = start;
+        let mut seen_dot = false;
+        let mut seen_exponent = false;
+        while let Some(&byte) = self.bytes.get(end) {
+            let first = end == start;
+            let after_exponent = end > start && matches!(self.bytes[end - 1], b'e' | b'E');
+            match byte {
+                b'+' | b'-' if first || after_exponent => (),
+                b'0'..=b'9' => (),
+                // A second dot starts the next number, as in `0.5.5`.
+                b'.' if !seen_dot && !seen_exponent => seen_dot = true,
+                b'e' | b'E' if !first && !seen_exponent => seen_exponent = true,
+                _ => break,
+            }
+            end += 1;
+        }
+        let number = std::str::from_utf8(&self.bytes[start..end])
+            .ok()?
+            .parse()
+            .ok()?;
+        self.position = end;
+        Some(number)
+    }
+
+    fn point(&mut self) -> Option<Point> {
+        Some(point(self.number()?, self.number()?))
+    }
+
+    /// Reads an arc flag, which may be written without a separator to the next number.
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.bytes.get(self.position)? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+}
+
+/// Parses the `d` attribute of a path element.
+fn parse_path_data(data: &str) -> Result<Path, SvgError> {
+    let error = || SvgError::PathData(data.to_string());
+    let mut lexer = PathLexer {
+        bytes: data.as_bytes(),
+        position: 0,
+    };
+    let mut builder = Path::builder().with_svg();
+    let mut command = None;
+
+    while !lexer.is_done() {
+        if let Some(letter) = lexer.command() {
+            command = Some(letter);
+        }
+        let current = command.ok_or_else(error)?;
+        (|| -> Option<()> {
+            match current {
+                b'M' => {
+                    builder.move_to(lexer.point()?);
+                    // Further coordinates are implicit lines.
+                    command = Some(b'L');
+                }
+                b'm' => {
+                    builder.relative_move_to(lexer.point()?.to_vector());
+                    command = Some(b'l');
+                }
+                b'L' => {
+                    builder.line_to(lexer.point()?);
+                }
+                b'l' => {
+                    builder.relative_line_to(lexer.point()?.to_vector());
+                }
+                b'H' => {
+                    builder.horizontal_line_to(lexer.number()?);
+                }
+                b'h' => {
+                    builder.relative_horizontal_line_to(lexer.number()?);
+                }
+                b'V' => {
+                    builder.vertical_line_to(lexer.number()?);
+                }
+                b'v' => {
+                    builder.relative_vertical_line_to(lexer.number()?);
+                }
+                b'C' => {
+                    let (first, second, to) = (lexer.point()?, lexer.point()?, lexer.point()?);
+                    builder.cubic_bezier_to(first, second, to);
+                }
+                b'c' => {
+                    let (first, second, to) = (lexer.point()?, lexer.point()?, lexer.point()?);
+                    builder.relative_cubic_bezier_to(
+                        first.to_vector(),
+                        second.to_vector(),
+                        to.to_vector(),
+                    );
+                }
+                b'S' => {
+                    let (second, to) = (lexer.point()?, lexer.point()?);
+                    builder.smooth_cubic_bezier_to(second, to);
+                }
+                b's' => {
+                    let (second, to) = (lexer.point()?, lexer.point()?);
+                    builder.smooth_relative_cubic_bezier_to(second.to_vector(), to.to_vector());
+                }
+                b'Q' => {
+                    let (control, to) = (lexer.point()?, lexer.point()?);
+                    builder.quadratic_bezier_to(control, to);
+                }
+                b'q' => {
+                    let (control, to) = (lexer.point()?, lexer.point()?);
+                    builder.relative_quadratic_bezier_to(control.to_vector(), to.to_vector());
+                }
+                b'T' => {
+                    builder.smooth_quadratic_bezier_to(lexer.point()?);
+                }
+                b't' => {
+                    builder.smooth_relative_quadratic_bezier_to(lexer.point()?.to_vector());
+                }
+                b'A' | b'a' => {
+                    let radii = lexer.point()?.to_vector();
+                    let rotation = Angle::degrees(lexer.number()?);
+                    let flags = ArcFlags {
+                        large_arc: lexer.flag()?,
+                        sweep: lexer.flag()?,
+                    };
+                    let to = lexer.point()?;
+                    if current == b'A' {
+                        builder.arc_to(radii, rotation, flags, to);
+                    } else {
+                        builder.relative_arc_to(radii, rotation, flags, to.to_vector());
+                    }
+                }
+                b'Z' | b'z' => {
+                    builder.close();
+                    // A new command has to follow.
+                    command = None;
+                }
+                _ => return None,
+            }
+            Some(())
+        })()
+        .ok_or_else(error)?;
+    }
+    Ok(builder.build())
+}
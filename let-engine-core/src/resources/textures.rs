@@ -3,15 +3,23 @@
 pub use image::ImageFormat;
 use image::{load_from_memory_with_format, DynamicImage};
 
+use anyhow::Result;
 use derive_builder::Builder;
-use std::sync::Arc;
+use parking_lot::Mutex;
+use std::{sync::Arc, time::Duration};
 use vulkano::descriptor_set::DescriptorSet;
+use vulkano::device::DeviceOwned;
 pub use vulkano::image::sampler::BorderColor;
+use vulkano::image::sampler::Sampler as vkSampler;
 use vulkano::image::sampler::{
-    Filter as vkFilter, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode,
+    Filter as vkFilter, SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode, LOD_CLAMP_NONE,
 };
+use vulkano::image::view::ImageView;
 
-use super::resources;
+use super::loader::LoadedTexture;
+use super::memory::{self, MemoryKind, Reload, TrackedMemory};
+use super::vulkan::Vulkan;
+use super::{resources, Loader};
 use crate::utils::u16tou8vec;
 
 /// Formats for the texture from raw data.
@@ -65,6 +73,28 @@ pub struct Sampler {
     /// Color for the border when the address mode is on ClampToBorder.
     #[builder(setter(into), default = "BorderColor::FloatTransparentBlack")]
     pub border_color: BorderColor,
+    /// The maximum anisotropic filtering level, sharpening textures viewed at steep angles.
+    ///
+    /// Gets clamped to the highest level the GPU supports and ignored in case it does not support it at all.
+    #[builder(setter(into, strip_option), default = "None")]
+    pub anisotropy: Option<f32>,
+    /// Offset added to the mip level the GPU chooses. Positive values make the texture blurrier.
+    ///
+    /// Gets clamped to the limit of the GPU.
+    #[builder(setter(into), default = "0.0")]
+    pub lod_bias: f32,
+}
+
+/// The way shaders see the layers of a texture.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextureKind {
+    /// A `sampler2D` for a single layer or a `sampler2DArray` for multiple layers.
+    #[default]
+    Flat,
+    /// Always a `sampler2DArray`, even with a single layer.
+    Array,
+    /// A `samplerCube` made of 6 square layers in the order +X, -X, +Y, -Y, +Z, -Z.
+    Cubemap,
 }
 
 /// The main texture settings.
@@ -74,6 +104,10 @@ pub struct TextureSettings {
     pub srgb: bool,
     /// Image sampler
     pub sampler: Sampler,
+    /// The type of the texture in shaders.
+    pub kind: TextureKind,
+    /// Generates mipmaps when loading, so the texture does not look aliased when drawn smaller than it is.
+    pub mipmaps: bool,
 }
 
 impl Default for Sampler {
@@ -84,6 +118,8 @@ impl Default for Sampler {
             mipmap_mode: Filter::Nearest,
             address_mode: [AddressMode::ClampToBorder; 3],
             border_color: BorderColor::FloatTransparentBlack,
+            anisotropy: None,
+            lod_bias: 0.0,
         }
     }
 }
@@ -95,11 +131,11 @@ impl Sampler {
                 Filter::Nearest => vkFilter::Nearest,
                 Filter::Linear => vkFilter::Linear,
             },
-            min_filter: match self.mag_filter {
+            min_filter: match self.min_filter {
                 Filter::Nearest => vkFilter::Nearest,
                 Filter::Linear => vkFilter::Linear,
             },
-            mipmap_mode: match self.mag_filter {
+            mipmap_mode: match self.mipmap_mode {
                 Filter::Nearest => SamplerMipmapMode::Nearest,
                 Filter::Linear => SamplerMipmapMode::Linear,
             },
@@ -125,6 +161,9 @@ impl Sampler {
                 },
             ],
             border_color: self.border_color,
+            anisotropy: self.anisotropy,
+            mip_lod_bias: self.lod_bias,
+            lod: 0.0..=LOD_CLAMP_NONE,
             ..Default::default()
         }
     }
@@ -135,6 +174,8 @@ impl Default for TextureSettings {
         Self {
             srgb: true,
             sampler: Sampler::default(),
+            kind: TextureKind::Flat,
+            mipmaps: false,
         }
     }
 }
@@ -148,6 +189,14 @@ impl TextureSettings {
         self.sampler = sampler;
         self
     }
+    pub fn kind(mut self, kind: TextureKind) -> Self {
+        self.kind = kind;
+        self
+    }
+    pub fn mipmaps(mut self, mipmaps: bool) -> Self {
+        self.mipmaps = mipmaps;
+        self
+    }
 }
 
 /// A texture to be used with materials.
@@ -156,134 +205,528 @@ pub struct Texture {
     data: Arc<[u8]>,
     dimensions: (u32, u32),
     layers: u32,
-    set: Arc<DescriptorSet>,
+    kind: TextureKind,
+    gpu: Arc<GpuTexture>,
+    name: Option<Arc<str>>,
+    _memory: Arc<TrackedMemory>,
+}
+
+/// The GPU side of a texture shared between its clones, along with everything needed to load it again.
+struct GpuTexture {
+    loaded: Mutex<LoadedTexture>,
+    /// The pixels including the regions written since loading, kept once the first region gets written.
+    written: Mutex<Option<Vec<u8>>>,
+    data: Arc<[u8]>,
+    dimensions: (u32, u32),
+    layers: u32,
+    format: Format,
+    settings: TextureSettings,
+    name: Option<Arc<str>>,
+}
+
+impl Reload for GpuTexture {
+    fn reload(&self, vulkan: &Vulkan, loader: &mut Loader) -> Result<()> {
+        let data = match self.written.lock().as_deref() {
+            Some(written) => Arc::from(written),
+            None => self.data.clone(),
+        };
+        *self.loaded.lock() = loader.load_texture(
+            vulkan,
+            data,
+            self.dimensions,
+            self.layers,
+            self.format,
+            self.settings.clone(),
+            self.name.as_deref(),
+        )?;
+        Ok(())
+    }
+}
+
+/// A rectangle of pixels in one layer of a texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextureRegion {
+    /// The top left corner of the region in pixels.
+    pub offset: (u32, u32),
+    /// The width and height of the region in pixels.
+    pub extent: (u32, u32),
+    pub layer: u32,
+}
+
+impl TextureRegion {
+    /// Creates a region in the first layer.
+    pub fn new(offset: (u32, u32), extent: (u32, u32)) -> Self {
+        Self {
+            offset,
+            extent,
+            layer: 0,
+        }
+    }
+
+    /// Sets the layer of the region.
+    pub fn layer(mut self, layer: u32) -> Self {
+        self.layer = layer;
+        self
+    }
 }
 
 /// Making
 impl Texture {
     /// Loads a texture to the GPU using a raw image.
+    ///
+    /// The optional name, for example the path of the texture, is used as the debug name of the GPU side objects,
+    /// so validation messages and graphics captures refer to `textures/stone.png` instead of an opaque handle.
     pub fn from_raw(
         data: &[u8],
         dimensions: (u32, u32),
         format: Format,
         layers: u32,
         settings: TextureSettings,
+        name: Option<&str>,
     ) -> Result<Texture, TextureError> {
         let data: Arc<[u8]> = Arc::from(data.to_vec().into_boxed_slice());
-        Ok(Texture {
-            data: data.clone(),
+        let resources = resources().map_err(|e| TextureError::Other(e.into()))?;
+        let loaded = resources
+            .loader()
+            .lock()
+            .load_texture(
+                &resources.vulkan(),
+                data.clone(),
+                dimensions,
+                layers,
+                format,
+                settings.clone(),
+                name,
+            )
+            .map_err(TextureError::Other)?;
+        Ok(Texture::from_set(
+            data,
             dimensions,
             layers,
-            set: resources()
-                .map_err(|e| TextureError::Other(e.into()))?
-                .loader()
-                .lock()
-                .load_texture(
-                    resources()
-                        .map_err(|e| TextureError::Other(e.into()))?
-                        .vulkan(),
-                    data,
-                    dimensions,
-                    layers,
-                    format,
-                    settings,
-                )
-                .map_err(TextureError::Other)?,
-        })
+            format,
+            settings,
+            loaded,
+            name.map(Arc::from),
+        ))
+    }
+
+    /// Loads a texture filled with transparent black pixels, to be drawn into using
+    /// [`write_region`](Texture::write_region).
+    pub fn empty(
+        dimensions: (u32, u32),
+        format: Format,
+        layers: u32,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<Texture, TextureError> {
+        let size =
+            dimensions.0 as usize * dimensions.1 as usize * format as usize * layers as usize;
+        Self::from_raw(&vec![0; size], dimensions, format, layers, settings, name)
     }
 
     /// Loads a texture to the GPU using the given image format.
     ///
+    /// Multiple layers get taken from the image as a vertical strip of equally sized frames.
+    ///
     /// Returns an error in case the given format does not work with the given bytes.
+    ///
+    /// The optional name is used as the debug name of the GPU side objects.
     pub fn from_bytes(
         data: &[u8],
         image_format: ImageFormat,
         layers: u32,
         settings: TextureSettings,
+        name: Option<&str>,
     ) -> Result<Texture, TextureError> {
-        // Turn image to a vector of u8 first.
-        let image = match load_from_memory_with_format(data, image_format) {
-            Err(_) => {
-                return Err(TextureError::InvalidFormat(format!(
-                    "Faulty format: {:?}",
-                    image_format
-                )))
-            }
-            Ok(v) => v,
-        };
+        let (image, mut dimensions, format) = decode(data, image_format)?;
 
-        let mut dimensions: (u32, u32);
+        dimensions.1 /= layers;
 
-        let mut format = Format::RGBA8;
-        let image: Vec<u8> = match image {
-            DynamicImage::ImageLuma8(image) => {
-                format = Format::R8;
-                dimensions = image.dimensions();
-                image.into_vec()
-            }
-            DynamicImage::ImageLumaA8(_) => {
-                let image = image.to_rgba8();
-                dimensions = image.dimensions();
-                image.into_vec()
-            }
-            DynamicImage::ImageLuma16(_) => {
-                let image = image.to_luma8();
-                dimensions = image.dimensions();
-                format = Format::R8;
-                image.into_vec()
-            }
-            DynamicImage::ImageLumaA16(_) => {
-                let image = image.to_rgba16();
-                dimensions = image.dimensions();
-                format = Format::RGBA16;
-                u16tou8vec(image.into_vec())
-            }
-            DynamicImage::ImageRgb8(_) => {
-                let image = image.to_rgba8();
-                dimensions = image.dimensions();
-                image.into_vec()
-            }
-            DynamicImage::ImageRgba8(image) => {
-                dimensions = image.dimensions();
-                image.into_vec()
-            }
-            DynamicImage::ImageRgb16(_) => {
-                let image = image.to_rgba16();
-                dimensions = image.dimensions();
-                format = Format::RGBA16;
-                u16tou8vec(image.into_vec())
-            }
-            DynamicImage::ImageRgba16(image) => {
-                format = Format::RGBA16;
-                dimensions = image.dimensions();
-                u16tou8vec(image.into_vec())
-            }
-            DynamicImage::ImageRgb32F(_) => {
-                let image = image.to_rgba16();
-                dimensions = image.dimensions();
-                format = Format::RGBA16;
-                u16tou8vec(image.into_vec())
+        Self::from_raw(&image, dimensions, format, layers, settings, name)
+    }
+
+    /// Loads a layered texture to the GPU, using each image as one layer.
+    ///
+    /// All images need to have the same size and format.
+    pub fn from_layers(
+        images: &[&[u8]],
+        image_format: ImageFormat,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<Texture, TextureError> {
+        let mut layers = images
+            .iter()
+            .map(|image| decode(image, image_format))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        let (mut data, dimensions, format) = layers
+            .next()
+            .ok_or_else(|| TextureError::LayerMismatch("No layers were given.".to_string()))?;
+        let mut count = 1;
+        for (layer, layer_dimensions, layer_format) in layers {
+            if layer_dimensions != dimensions || layer_format as u32 != format as u32 {
+                return Err(TextureError::LayerMismatch(format!(
+                    "Layer {count} is {layer_dimensions:?} {layer_format:?}, expected {dimensions:?} {format:?}"
+                )));
             }
-            DynamicImage::ImageRgba32F(_) => {
-                let image = image.to_rgba16();
-                dimensions = image.dimensions();
-                format = Format::RGBA16;
-                u16tou8vec(image.into_vec())
+            data.extend(layer);
+            count += 1;
+        }
+
+        Self::from_raw(&data, dimensions, format, count, settings, name)
+    }
+
+    /// Loads a cubemap from the images of its 6 faces in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn cubemap_from_faces(
+        faces: [&[u8]; 6],
+        image_format: ImageFormat,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<Texture, TextureError> {
+        Self::from_layers(
+            &faces,
+            image_format,
+            settings.kind(TextureKind::Cubemap),
+            name,
+        )
+    }
+
+    /// Loads a cubemap from a single image with all 6 faces next to or under each other
+    /// in the order +X, -X, +Y, -Y, +Z, -Z.
+    pub fn cubemap_from_strip(
+        data: &[u8],
+        image_format: ImageFormat,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<Texture, TextureError> {
+        let (image, (width, height), format) = decode(data, image_format)?;
+        let settings = settings.kind(TextureKind::Cubemap);
+
+        if height == width * 6 {
+            // A vertical strip already has the layout of the layers in memory.
+            Self::from_raw(&image, (width, width), format, 6, settings, name)
+        } else if width == height * 6 {
+            let face_row = (height * format as u32) as usize;
+            let row = face_row * 6;
+            let mut faces = Vec::with_capacity(image.len());
+            for face in 0..6 {
+                for y in 0..height as usize {
+                    let start = y * row + face * face_row;
+                    faces.extend_from_slice(&image[start..start + face_row]);
+                }
             }
+            Self::from_raw(&faces, (height, height), format, 6, settings, name)
+        } else {
+            Err(TextureError::LayerMismatch(format!(
+                "A cubemap strip needs to be 6 square faces long, but the image is {width}x{height}."
+            )))
+        }
+    }
+
+    /// Loads an animated GIF, APNG or animated WebP image, using each frame as one layer.
+    ///
+    /// Frames without a delay get shown for 100 milliseconds like in browsers.
+    /// Fails in case the image has more frames than the device allows layers in a texture.
+    pub fn animated_from_bytes(
+        data: &[u8],
+        image_format: ImageFormat,
+        settings: TextureSettings,
+        name: Option<&str>,
+    ) -> Result<AnimatedTexture, TextureError> {
+        use image::{
+            codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+            AnimationDecoder,
+        };
+
+        let invalid = |error: image::ImageError| TextureError::InvalidFormat(error.to_string());
+        let cursor = std::io::Cursor::new(data);
+        let frames = match image_format {
+            ImageFormat::Gif => GifDecoder::new(cursor).map_err(invalid)?.into_frames(),
+            ImageFormat::Png => PngDecoder::new(cursor)
+                .map_err(invalid)?
+                .apng()
+                .map_err(invalid)?
+                .into_frames(),
+            ImageFormat::WebP => WebPDecoder::new(cursor).map_err(invalid)?.into_frames(),
             _ => {
-                let image = image.to_rgba8();
-                dimensions = image.dimensions();
-                image.into_vec()
+                return Err(TextureError::InvalidFormat(format!(
+                    "{image_format:?} images can not be animated."
+                )))
             }
         };
+        let max_layers = resources()
+            .map_err(|e| TextureError::Other(e.into()))?
+            .vulkan()
+            .device
+            .physical_device()
+            .properties()
+            .max_image_array_layers;
 
-        dimensions.1 /= layers;
+        let mut delays = vec![];
+        let mut pixels = vec![];
+        let mut dimensions = None;
+        for frame in frames {
+            let frame = frame.map_err(invalid)?;
+            if delays.len() as u32 == max_layers {
+                return Err(TextureError::LayerMismatch(format!(
+                    "The image has more frames than the {max_layers} layers a texture can have on this device."
+                )));
+            }
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            delays.push(if numerator == 0 || denominator == 0 {
+                DEFAULT_FRAME_DELAY
+            } else {
+                Duration::from_secs_f64(numerator as f64 / denominator as f64 / 1000.0)
+            });
+            let buffer = frame.into_buffer();
+            dimensions.get_or_insert(buffer.dimensions());
+            pixels.extend(buffer.into_raw());
+        }
+        let dimensions = dimensions
+            .ok_or_else(|| TextureError::InvalidFormat("The image has no frames.".to_string()))?;
+
+        let texture = Self::from_raw(
+            &pixels,
+            dimensions,
+            Format::RGBA8,
+            delays.len() as u32,
+            settings,
+            name,
+        )?;
+        Ok(AnimatedTexture {
+            texture,
+            delays: delays.into(),
+        })
+    }
+}
+
+/// How long frames without a delay of animated images get shown.
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// A texture holding every frame of an animated image as a layer, along with how long each frame gets shown.
+#[derive(Clone)]
+pub struct AnimatedTexture {
+    texture: Texture,
+    delays: Arc<[Duration]>,
+}
 
-        Self::from_raw(&image, dimensions, format, layers, settings)
+impl AnimatedTexture {
+    /// Combines a layered texture with the delays of its frames.
+    ///
+    /// Returns an error in case the number of delays does not match the number of layers.
+    pub fn new(texture: Texture, delays: Arc<[Duration]>) -> Result<Self, TextureError> {
+        if delays.len() != texture.layers() as usize {
+            return Err(TextureError::LayerMismatch(format!(
+                "{} delays were given for {} layers.",
+                delays.len(),
+                texture.layers()
+            )));
+        }
+        Ok(Self { texture, delays })
+    }
+
+    /// Returns the texture with one layer per frame.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns how long each frame gets shown, in the order of the layers.
+    pub fn delays(&self) -> &Arc<[Duration]> {
+        &self.delays
+    }
+
+    /// Returns the number of frames.
+    pub fn frames(&self) -> usize {
+        self.delays.len()
+    }
+
+    /// Returns the time it takes to show all frames once.
+    pub fn duration(&self) -> Duration {
+        self.delays.iter().sum()
     }
 }
+
+/// Turns the bytes of an image file into raw pixels.
+fn decode(
+    data: &[u8],
+    image_format: ImageFormat,
+) -> Result<(Vec<u8>, (u32, u32), Format), TextureError> {
+    // Turn image to a vector of u8 first.
+    let image = match load_from_memory_with_format(data, image_format) {
+        Err(_) => {
+            return Err(TextureError::InvalidFormat(format!(
+                "Faulty format: {:?}",
+                image_format
+            )))
+        }
+        Ok(v) => v,
+    };
+
+    let dimensions: (u32, u32);
+
+    let mut format = Format::RGBA8;
+    let image: Vec<u8> = match image {
+        DynamicImage::ImageLuma8(image) => {
+            format = Format::R8;
+            dimensions = image.dimensions();
+            image.into_vec()
+        }
+        DynamicImage::ImageLumaA8(_) => {
+            let image = image.to_rgba8();
+            dimensions = image.dimensions();
+            image.into_vec()
+        }
+        DynamicImage::ImageLuma16(_) => {
+            let image = image.to_luma8();
+            dimensions = image.dimensions();
+            format = Format::R8;
+            image.into_vec()
+        }
+        DynamicImage::ImageLumaA16(_) => {
+            let image = image.to_rgba16();
+            dimensions = image.dimensions();
+            format = Format::RGBA16;
+            u16tou8vec(image.into_vec())
+        }
+        DynamicImage::ImageRgb8(_) => {
+            let image = image.to_rgba8();
+            dimensions = image.dimensions();
+            image.into_vec()
+        }
+        DynamicImage::ImageRgba8(image) => {
+            dimensions = image.dimensions();
+            image.into_vec()
+        }
+        DynamicImage::ImageRgb16(_) => {
+            let image = image.to_rgba16();
+            dimensions = image.dimensions();
+            format = Format::RGBA16;
+            u16tou8vec(image.into_vec())
+        }
+        DynamicImage::ImageRgba16(image) => {
+            format = Format::RGBA16;
+            dimensions = image.dimensions();
+            u16tou8vec(image.into_vec())
+        }
+        DynamicImage::ImageRgb32F(_) => {
+            let image = image.to_rgba16();
+            dimensions = image.dimensions();
+            format = Format::RGBA16;
+            u16tou8vec(image.into_vec())
+        }
+        DynamicImage::ImageRgba32F(_) => {
+            let image = image.to_rgba16();
+            dimensions = image.dimensions();
+            format = Format::RGBA16;
+            u16tou8vec(image.into_vec())
+        }
+        _ => {
+            let image = image.to_rgba8();
+            dimensions = image.dimensions();
+            image.into_vec()
+        }
+    };
+
+    Ok((image, dimensions, format))
+}
+
 /// Accessing
 impl Texture {
+    /// Wraps a texture already loaded to the GPU, remembering it to be loaded again in case the device gets lost.
+    pub(crate) fn from_set(
+        data: Arc<[u8]>,
+        dimensions: (u32, u32),
+        layers: u32,
+        format: Format,
+        settings: TextureSettings,
+        loaded: LoadedTexture,
+        name: Option<Arc<str>>,
+    ) -> Self {
+        let kind = settings.kind;
+        let gpu = Arc::new(GpuTexture {
+            loaded: Mutex::new(loaded),
+            written: Mutex::new(None),
+            data: data.clone(),
+            dimensions,
+            layers,
+            format,
+            settings,
+            name: name.clone(),
+        });
+        memory::register(&gpu);
+        Self {
+            _memory: TrackedMemory::new(MemoryKind::Texture, data.len() as u64, name.clone()),
+            data,
+            dimensions,
+            layers,
+            kind,
+            gpu,
+            name,
+        }
+    }
+
+    /// Overwrites a region of the texture on the GPU with the given pixels, in the format the texture got loaded with.
+    ///
+    /// Made for textures changing every frame like minimaps, paint canvases or video.
+    /// Mipmaps of the texture get regenerated after the write.
+    /// [`data`](Texture::data) keeps returning the pixels the texture got loaded with.
+    pub fn write_region(&self, region: TextureRegion, data: &[u8]) -> Result<(), TextureError> {
+        if region.layer >= self.layers {
+            return Err(TextureError::Layer(format!(
+                "Given: {}, Highest: {}",
+                region.layer,
+                self.layers - 1
+            )));
+        }
+        // Summed as u64, so regions far outside of the texture can not wrap around into it.
+        if region.offset.0 as u64 + region.extent.0 as u64 > self.dimensions.0 as u64
+            || region.offset.1 as u64 + region.extent.1 as u64 > self.dimensions.1 as u64
+        {
+            return Err(TextureError::Other(anyhow::anyhow!(
+                "The region {region:?} does not fit in a texture of {:?} pixels.",
+                self.dimensions
+            )));
+        }
+        let size = (region.extent.0 as u64)
+            .checked_mul(region.extent.1 as u64)
+            .and_then(|pixels| pixels.checked_mul(self.gpu.format as u64));
+        if size != Some(data.len() as u64) {
+            return Err(TextureError::Other(anyhow::anyhow!(
+                "The region {region:?} does not match the {} given bytes.",
+                data.len()
+            )));
+        }
+        let resources = resources().map_err(|e| TextureError::Other(e.into()))?;
+        let image = self.gpu.loaded.lock().image.clone();
+        resources
+            .loader()
+            .lock()
+            .write_texture_region(
+                &resources.vulkan(),
+                &image,
+                [region.offset.0, region.offset.1],
+                [region.extent.0, region.extent.1],
+                region.layer,
+                data,
+            )
+            .map_err(TextureError::Other)?;
+        crate::draw::contents_changed();
+
+        // Keeps the written pixels to load them again in case the device gets lost.
+        let pixel = self.gpu.format as usize;
+        let (width, height) = (self.dimensions.0 as usize, self.dimensions.1 as usize);
+        let mut written = self.gpu.written.lock();
+        let written = written.get_or_insert_with(|| self.data.to_vec());
+        let row = region.extent.0 as usize * pixel;
+        for (y, source) in data.chunks_exact(row.max(1)).enumerate() {
+            let start = ((region.layer as usize * height + region.offset.1 as usize + y) * width
+                + region.offset.0 as usize)
+                * pixel;
+            written[start..start + row].copy_from_slice(source);
+        }
+        Ok(())
+    }
+
     pub fn data(&self) -> &Arc<[u8]> {
         &self.data
     }
@@ -293,25 +736,52 @@ impl Texture {
     pub fn layers(&self) -> u32 {
         self.layers
     }
-    pub(crate) fn set(&self) -> &Arc<DescriptorSet> {
-        &self.set
+    /// Returns how shaders see this texture.
+    pub fn kind(&self) -> TextureKind {
+        self.kind
+    }
+    /// Returns the name given to this texture when loading it.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// Returns the number of handles sharing the GPU side of this texture, including the ones held by materials.
+    pub fn handle_count(&self) -> usize {
+        Arc::strong_count(&self._memory)
+    }
+    pub(crate) fn set(&self) -> Arc<DescriptorSet> {
+        self.gpu.loaded.lock().set.clone()
+    }
+    pub(crate) fn view(&self) -> Arc<ImageView> {
+        self.gpu.loaded.lock().view.clone()
+    }
+    pub(crate) fn sampler(&self) -> Arc<vkSampler> {
+        self.gpu.loaded.lock().sampler.clone()
+    }
+    /// Returns true if both textures share the same GPU side.
+    pub(crate) fn same_gpu(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.gpu, &other.gpu)
+    }
+    /// Returns true if the texture is on the device of the given Vulkan, which is only false in case it failed
+    /// to load again after the device got lost.
+    pub(crate) fn is_on(&self, vulkan: &Vulkan) -> bool {
+        Arc::ptr_eq(self.gpu.loaded.lock().image.device(), &vulkan.device)
     }
 }
 
 impl PartialEq for Texture {
     fn eq(&self, other: &Self) -> bool {
-        self.data == other.data
-            && self.dimensions == other.dimensions
-            && Arc::ptr_eq(&self.set, &other.set)
+        self.data == other.data && self.dimensions == other.dimensions && self.same_gpu(other)
     }
 }
 
 impl std::fmt::Debug for Texture {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Texture")
+            .field("name", &self.name)
             .field("size", &self.data.len())
             .field("dimensions", &self.dimensions)
             .field("frames", &self.layers)
+            .field("kind", &self.kind)
             .finish()
     }
 }
@@ -337,6 +807,9 @@ pub enum TextureError {
     /// new texture.
     #[error("The given format does not match with the bytes provided: {0}")]
     InvalidFormat(String),
+    /// This error gets returned when the layers given to a layered texture or cubemap do not fit together.
+    #[error("The layers of this texture do not fit together: {0}")]
+    LayerMismatch(String),
     /// If the texture for some reason can not be made.
     #[error("There was an error loading this texture: {0}")]
     Other(anyhow::Error),
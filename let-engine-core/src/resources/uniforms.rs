@@ -0,0 +1,336 @@
+//! Uniform blocks of materials with named, typed fields.
+//!
+//! # Usage
+//! ```ignore
+//! uniform_block! {
+//!     /// The parameters of the water shader.
+//!     pub struct WaterParams {
+//!         pub tint: Color,
+//!         pub direction: Vec2,
+//!         pub speed: f32,
+//!         pub time: f32,
+//!     }
+//! }
+//!
+//! // The water shader has no texture, so it declares `layout(set = 1, binding = 0) uniform WaterParams { ... }`.
+//! let mut water = unsafe { MaterialInstance::new(&water_material, 0, params)? };
+//! water.set("time", TIME.time() as f32)?;
+//! object.appearance.set_material(Some(water.material()?.clone()));
+//! ```
+
+use std::{any::TypeId, sync::Arc};
+
+use anyhow::Result;
+use glam::{Mat4, Vec2, Vec4};
+use thiserror::Error;
+use vulkano::{
+    buffer::Subbuffer,
+    descriptor_set::{DescriptorSet, WriteDescriptorSet},
+    DeviceSize,
+};
+
+use super::{materials::Material, resources};
+use crate::objects::Color;
+
+/// A field of a [`UniformBlock`].
+#[derive(Clone, Copy, Debug)]
+pub struct UniformField {
+    pub name: &'static str,
+    /// The offset of the field from the start of the block in bytes.
+    pub offset: usize,
+    pub size: usize,
+    pub type_id: fn() -> TypeId,
+    pub type_name: fn() -> &'static str,
+}
+
+/// A struct that can be uploaded to a shader as a uniform block.
+///
+/// Implement it using the [`uniform_block`](crate::uniform_block) macro.
+///
+/// # Safety
+///
+/// The struct must be `#[repr(C)]`, made of [`UniformValue`] fields only, contain no padding and list all of its
+/// fields in [`FIELDS`](UniformBlock::FIELDS).
+pub unsafe trait UniformBlock: Copy + Send + Sync + 'static {
+    const FIELDS: &'static [UniformField];
+
+    /// Returns the field with the given name.
+    fn field(name: &str) -> Option<&'static UniformField> {
+        Self::FIELDS.iter().find(|field| field.name == name)
+    }
+}
+
+/// A value that can be a field of a [`UniformBlock`].
+///
+/// `vec3` is left out on purpose, because its std140 alignment does not match its size.
+///
+/// # Safety
+///
+/// Every byte of the value must be initialized and it must match the memory layout of the GLSL type it represents.
+pub unsafe trait UniformValue: Copy + 'static {
+    /// The alignment the std140 layout requires for this type in bytes.
+    const ALIGN: usize;
+}
+
+unsafe impl UniformValue for f32 {
+    const ALIGN: usize = 4;
+}
+unsafe impl UniformValue for i32 {
+    const ALIGN: usize = 4;
+}
+unsafe impl UniformValue for u32 {
+    const ALIGN: usize = 4;
+}
+unsafe impl UniformValue for Vec2 {
+    const ALIGN: usize = 8;
+}
+unsafe impl UniformValue for Vec4 {
+    const ALIGN: usize = 16;
+}
+unsafe impl UniformValue for [f32; 4] {
+    const ALIGN: usize = 16;
+}
+unsafe impl UniformValue for Color {
+    const ALIGN: usize = 16;
+}
+unsafe impl UniformValue for Mat4 {
+    const ALIGN: usize = 16;
+}
+
+/// Fails compilation in case a field of a uniform block is not aligned the way std140 wants it.
+#[doc(hidden)]
+pub const fn assert_field<T: UniformValue>(offset: usize) {
+    assert!(
+        offset % T::ALIGN == 0,
+        "A field of this uniform block is misaligned for std140. Reorder the fields or add padding fields."
+    );
+}
+
+/// Declares a `#[repr(C)]` struct and implements [`UniformBlock`] for it.
+///
+/// The layout gets checked against the std140 rules at compile time.
+/// Fields can be of any type implementing [`UniformValue`].
+///
+/// ## usage:
+/// ```ignore
+/// uniform_block! {
+///     pub struct Glow {
+///         pub color: Color,
+///         pub strength: f32,
+///         pub _padding: [f32; 3],
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! uniform_block {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $ty:ty),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        $vis struct $name {
+            $($(#[$field_attr])* $field_vis $field: $ty),+
+        }
+
+        // Safety: All fields are uniform values and the assertions below rule out padding.
+        unsafe impl $crate::resources::uniforms::UniformBlock for $name {
+            const FIELDS: &'static [$crate::resources::uniforms::UniformField] = &[$(
+                $crate::resources::uniforms::UniformField {
+                    name: ::core::stringify!($field),
+                    offset: ::core::mem::offset_of!($name, $field),
+                    size: ::core::mem::size_of::<$ty>(),
+                    type_id: ::core::any::TypeId::of::<$ty>,
+                    type_name: ::core::any::type_name::<$ty>,
+                }
+            ),+];
+        }
+
+        const _: () = {
+            $($crate::resources::uniforms::assert_field::<$ty>(::core::mem::offset_of!($name, $field));)+
+            ::core::assert!(
+                0 $(+ ::core::mem::size_of::<$ty>())+ == ::core::mem::size_of::<$name>(),
+                ::core::concat!(
+                    "The uniform block `",
+                    ::core::stringify!($name),
+                    "` contains padding. Reorder the fields or add padding fields."
+                )
+            );
+        };
+    };
+}
+
+/// Errors that occur accessing the fields of a uniform block.
+#[derive(Error, Debug)]
+pub enum UniformError {
+    #[error("The uniform block has no field called `{0}`.")]
+    UnknownField(String),
+    #[error("The field `{field}` of the uniform block is a `{expected}`, not a `{given}`.")]
+    WrongType {
+        field: &'static str,
+        expected: &'static str,
+        given: &'static str,
+    },
+}
+
+/// A uniform buffer holding the block along with the descriptor set binding it.
+struct Upload {
+    buffer: Subbuffer<[u8]>,
+    set: Arc<DescriptorSet>,
+}
+
+/// A material with its own uniform block, for giving objects individual shader parameters.
+///
+/// The values get uploaded the next time [`material`](MaterialInstance::material) gets called after a change.
+/// Until then the descriptor of the last upload is kept and reused.
+///
+/// Uploads go to a buffer no frame in flight reads anymore, reusing it along with its descriptor set,
+/// so an instance keeps about one buffer per frame in flight.
+pub struct MaterialInstance<T: UniformBlock> {
+    material: Material,
+    binding: u32,
+    block: T,
+    dirty: bool,
+    uploads: Vec<Upload>,
+}
+
+/// Clones get their own buffers, so changing them does not change the values of the original.
+impl<T: UniformBlock> Clone for MaterialInstance<T> {
+    fn clone(&self) -> Self {
+        Self {
+            material: self.material.clone(),
+            binding: self.binding,
+            block: self.block,
+            dirty: true,
+            uploads: vec![],
+        }
+    }
+}
+
+impl<T: UniformBlock> MaterialInstance<T> {
+    /// Creates an instance of the material, binding the block to the given binding of the second descriptor set.
+    ///
+    /// # Safety
+    ///
+    /// The shaders of the material must declare a uniform block matching `T` at the given binding of the set
+    /// at [`Material::descriptor_set_index`].
+    pub unsafe fn new(material: &Material, binding: u32, block: T) -> Result<Self> {
+        let mut instance = Self {
+            material: material.clone(),
+            binding,
+            block,
+            dirty: true,
+            uploads: vec![],
+        };
+        instance.material()?;
+        Ok(instance)
+    }
+
+    /// Sets the field with the given name.
+    pub fn set<V: UniformValue>(&mut self, name: &str, value: V) -> Result<(), UniformError> {
+        let field = Self::typed_field::<V>(name)?;
+        // Safety: The field is a `V` inside of the block.
+        unsafe {
+            (&mut self.block as *mut T)
+                .cast::<u8>()
+                .add(field.offset)
+                .cast::<V>()
+                .write_unaligned(value);
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Returns the value of the field with the given name.
+    pub fn get<V: UniformValue>(&self, name: &str) -> Result<V, UniformError> {
+        let field = Self::typed_field::<V>(name)?;
+        // Safety: The field is a `V` inside of the block.
+        Ok(unsafe {
+            (&self.block as *const T)
+                .cast::<u8>()
+                .add(field.offset)
+                .cast::<V>()
+                .read_unaligned()
+        })
+    }
+
+    fn typed_field<V: UniformValue>(name: &str) -> Result<&'static UniformField, UniformError> {
+        let field = T::field(name).ok_or_else(|| UniformError::UnknownField(name.to_string()))?;
+        if (field.type_id)() != TypeId::of::<V>() {
+            return Err(UniformError::WrongType {
+                field: field.name,
+                expected: (field.type_name)(),
+                given: std::any::type_name::<V>(),
+            });
+        }
+        Ok(field)
+    }
+
+    /// Returns the whole block.
+    pub fn block(&self) -> &T {
+        &self.block
+    }
+
+    /// Returns the whole block to change multiple fields at once.
+    pub fn block_mut(&mut self) -> &mut T {
+        self.dirty = true;
+        &mut self.block
+    }
+
+    /// Returns the binding of the block in the descriptor set of the material.
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    /// Returns the material using the current values of the block, uploading them first in case they changed.
+    ///
+    /// Objects holding an older version of the material may show the new values once its buffer gets reused,
+    /// so set it again after changing fields.
+    pub fn material(&mut self) -> Result<&Material> {
+        if !self.dirty {
+            return Ok(&self.material);
+        }
+        // Safety: `UniformBlock` guarantees the block has no uninitialized padding bytes.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                (&self.block as *const T).cast::<u8>(),
+                std::mem::size_of::<T>(),
+            )
+        };
+        // Writing fails for buffers a frame in flight still reads.
+        let reused = self.uploads.iter().find_map(|upload| {
+            let mut contents = upload.buffer.write().ok()?;
+            contents.copy_from_slice(bytes);
+            Some(upload.set.clone())
+        });
+        let set = match reused {
+            Some(set) => set,
+            None => {
+                let mut loader = resources()?.loader().lock();
+                let buffer = loader
+                    .object_buffer_allocator
+                    .allocate_slice::<u8>(std::mem::size_of::<T>() as DeviceSize)?;
+                buffer.write()?.copy_from_slice(bytes);
+                let pipeline = self.material.get_pipeline_or_recreate(&mut loader)?;
+                // The caller of `new` guaranteed the shader declares this block.
+                let set = DescriptorSet::new(
+                    loader.descriptor_set_allocator.clone(),
+                    self.material.descriptor_layout(&pipeline)?,
+                    [WriteDescriptorSet::buffer(self.binding, buffer.clone())],
+                    [],
+                )?;
+                self.uploads.push(Upload {
+                    buffer,
+                    set: set.clone(),
+                });
+                set
+            }
+        };
+        self.material.set_descriptor(set);
+        self.dirty = false;
+        Ok(&self.material)
+    }
+}
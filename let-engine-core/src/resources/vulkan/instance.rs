@@ -22,6 +22,8 @@ pub fn create_instance(
 
     let extensions = InstanceExtensions {
         ext_debug_utils: true,
+        // Needed for HDR color spaces.
+        ext_swapchain_colorspace: library.supported_extensions().ext_swapchain_colorspace,
         ..required_extensions
     };
 
@@ -96,31 +98,87 @@ pub fn create_physical_device(
         ))
 }
 
+/// The queues of the device.
+///
+/// The transfer queue is the graphics queue in case the device has no separate queue family for it.
+pub struct Queues {
+    pub graphics: Arc<Queue>,
+    pub transfer: Arc<Queue>,
+}
+
 /// Makes the device and queues.
 pub fn create_device_and_queues(
     physical_device: &Arc<PhysicalDevice>,
     device_extensions: &DeviceExtensions,
     features: DeviceFeatures,
     queue_family_index: u32,
-) -> Result<(Arc<Device>, Arc<Queue>), EngineError> {
-    let (device, mut queues) = Device::new(
+) -> Result<(Arc<Device>, Queues), EngineError> {
+    let families = physical_device.queue_family_properties();
+    let find = |required: QueueFlags, excluded: QueueFlags| {
+        families
+            .iter()
+            .position(|family| {
+                family.queue_flags.contains(required) && !family.queue_flags.intersects(excluded)
+            })
+            .map(|index| index as u32)
+    };
+    // Prefers the DMA queues of discrete GPUs, which can copy without slowing down the graphics work.
+    let transfer_family = find(
+        QueueFlags::TRANSFER,
+        QueueFlags::GRAPHICS | QueueFlags::COMPUTE,
+    )
+    .or_else(|| find(QueueFlags::TRANSFER, QueueFlags::GRAPHICS));
+
+    // The number of queues to create of every family.
+    let mut counts = vec![(queue_family_index, 1)];
+    let mut request = |family: Option<u32>| {
+        let family = family?;
+        let available = families[family as usize].queue_count;
+        match counts.iter_mut().find(|(index, _)| *index == family) {
+            Some((_, count)) if *count < available => {
+                *count += 1;
+                Some((family, *count - 1))
+            }
+            Some(_) => None,
+            None => {
+                counts.push((family, 1));
+                Some((family, 0))
+            }
+        }
+    };
+    let transfer = request(transfer_family);
+
+    let (device, queues) = Device::new(
         physical_device.clone(),
         DeviceCreateInfo {
             enabled_extensions: *device_extensions,
             enabled_features: features,
-            queue_create_infos: vec![QueueCreateInfo {
-                queue_family_index,
-                ..Default::default()
-            }],
+            queue_create_infos: counts
+                .iter()
+                .map(|&(queue_family_index, count)| QueueCreateInfo {
+                    queue_family_index,
+                    queues: vec![0.5; count as usize],
+                    ..Default::default()
+                })
+                .collect(),
 
             ..Default::default()
         },
     )
     .map_err(|e| EngineError::RequirementError(e.to_string()))?;
-    Ok((
-        device,
-        queues.next().ok_or(EngineError::Other(anyhow!(
-            "The graphics queue has no slots.".to_string()
-        )))?,
-    ))
+
+    let queues: Vec<Arc<Queue>> = queues.collect();
+    let queue = |family: u32, index: u32| {
+        queues
+            .iter()
+            .find(|queue| queue.queue_family_index() == family && queue.queue_index() == index)
+            .cloned()
+    };
+    let graphics = queue(queue_family_index, 0).ok_or(EngineError::Other(anyhow!(
+        "The graphics queue has no slots.".to_string()
+    )))?;
+    let transfer = transfer
+        .and_then(|(family, index)| queue(family, index))
+        .unwrap_or_else(|| graphics.clone());
+    Ok((device, Queues { graphics, transfer }))
 }
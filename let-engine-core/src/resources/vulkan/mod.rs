@@ -9,21 +9,27 @@ mod debug;
 pub mod swapchain;
 pub(crate) mod window;
 
-use crate::draw::VIEWPORT;
+use crate::draw::{ColorSpace, Graphics, VIEWPORT};
 use crate::resources::data::Vertex as GameVertex;
 use anyhow::{Context, Error, Result};
 use vulkano::{
-    device::{Device, DeviceFeatures, Queue},
-    image::{view::ImageView, Image},
+    device::{Device, DeviceFeatures, DeviceOwned, Queue},
+    format::{Format, FormatFeatures},
+    image::{view::ImageView, Image, ImageCreateInfo, ImageType, ImageUsage},
+    instance::Instance,
+    memory::allocator::{AllocationCreateInfo, StandardMemoryAllocator},
     pipeline::{
         graphics::{
+            depth_stencil::StencilOp,
             input_assembly::InputAssemblyState,
-            vertex_input::{Vertex, VertexDefinition},
+            vertex_input::{Vertex, VertexDefinition, VertexInputState},
             viewport::Viewport,
         },
         GraphicsPipeline,
     },
     render_pass::{Framebuffer, FramebufferCreateInfo, RenderPass, Subpass},
+    swapchain::Surface,
+    Version, VulkanObject,
 };
 
 use std::sync::Arc;
@@ -31,14 +37,40 @@ use std::sync::Arc;
 use super::data::InstanceData;
 use super::materials::{Material, Shaders};
 
+/// The size of the texture array of the bindless instance material.
+///
+/// Has to stay the same as the size in `bindless_instance.frag`.
+pub(crate) const BINDLESS_TEXTURES: u32 = 1024;
+
 /// Just a holder of general immutable information about Vulkan.
 #[derive(Clone)]
 pub struct Vulkan {
-    pub instance: Arc<vulkano::instance::Instance>,
+    pub instance: Arc<Instance>,
     pub device: Arc<Device>,
+    /// The queue drawing the frames.
     pub queue: Arc<Queue>,
+    /// The queue resources get uploaded on, which is `queue` in case the GPU has no separate transfer queue.
+    pub transfer_queue: Arc<Queue>,
     pub render_pass: Arc<RenderPass>,
+    /// The render pass drawing frozen layers to their own image, compatible with `render_pass`.
+    pub freeze_render_pass: Arc<RenderPass>,
+    /// The subpass the scene gets drawn in, with the stencil buffer of masks as its depth stencil attachment.
     pub subpass: Subpass,
+    /// The last subpass, drawing over the final image of the window without a depth stencil attachment.
+    ///
+    /// Egui gets drawn in this subpass, after the scene got tonemapped.
+    pub overlay_subpass: Subpass,
+    /// The format and color space of the swapchain images.
+    pub surface_format: (Format, vulkano::swapchain::ColorSpace),
+    /// The pipeline mapping the HDR image of the scene to the window in the second subpass,
+    /// `None` if the window uses the sRGB color space.
+    pub tonemap: Option<Arc<GraphicsPipeline>>,
+    /// The pipeline adding the shape of a mask to the stencil buffer.
+    pub mask: Arc<GraphicsPipeline>,
+    /// The pipeline removing the shape of a mask from the stencil buffer.
+    pub unmask: Arc<GraphicsPipeline>,
+    /// The pipeline drawing the images of frozen layers over the scene.
+    pub composite: Arc<GraphicsPipeline>,
 
     pub default_shaders: Shaders,
     pub default_instance_shaders: Shaders,
@@ -48,10 +80,21 @@ pub struct Vulkan {
     pub default_instance_material: Material,
     pub textured_instance_material: Material,
     pub texture_array_instance_material: Material,
+    /// The instanced material drawing single layer textures out of one big texture array,
+    /// where the layer of each instance is the index of its texture.
+    ///
+    /// `None` in case the GPU does not support indexing textures per instance.
+    pub bindless_instance_material: Option<Material>,
+    pub msdf_material: Material,
+    pub sdf_material: Material,
+    pub lit_material: Material,
 }
 
 impl Vulkan {
-    pub fn init(event_loop: &EventLoop<()>) -> Result<(Vec<Arc<GraphicsPipeline>>, Self)> {
+    pub fn init(
+        event_loop: &EventLoop<()>,
+        graphics: &Graphics,
+    ) -> Result<(Vec<Arc<GraphicsPipeline>>, Self)> {
         let instance = instance::create_instance(event_loop)?;
 
         #[cfg(feature = "vulkan_debug_utils")]
@@ -62,6 +105,17 @@ impl Vulkan {
 
         VIEWPORT.write().extent = window.inner_size().into();
 
+        Self::new(instance, &surface, graphics)
+    }
+
+    /// Creates the device and everything made on it for drawing to the given surface.
+    ///
+    /// Also used to make a new device after the previous one got lost.
+    pub(crate) fn new(
+        instance: Arc<Instance>,
+        surface: &Arc<Surface>,
+        graphics: &Graphics,
+    ) -> Result<(Vec<Arc<GraphicsPipeline>>, Self)> {
         let device_extensions = instance::create_device_extensions();
         let features = DeviceFeatures {
             fill_mode_non_solid: true,
@@ -69,63 +123,251 @@ impl Vulkan {
             ..DeviceFeatures::empty()
         };
         let (physical_device, queue_family_index) =
-            instance::create_physical_device(&instance, device_extensions, features, &surface)?;
-        let (device, queue) = instance::create_device_and_queues(
+            instance::create_physical_device(&instance, device_extensions, features, surface)?;
+        // Optional features get enabled in case the device has them.
+        let optional_features = DeviceFeatures {
+            sampler_anisotropy: true,
+            // Only core since Vulkan 1.2, before that it needs an extension.
+            shader_sampled_image_array_non_uniform_indexing: physical_device.api_version()
+                >= Version::V1_2,
+            ..DeviceFeatures::empty()
+        };
+        let (device, queues) = instance::create_device_and_queues(
             &physical_device,
             &device_extensions,
-            features,
+            features.union(
+                &physical_device
+                    .supported_features()
+                    .intersection(&optional_features),
+            ),
             queue_family_index,
         )?;
 
-        let render_pass = vulkano::single_pass_renderpass!(
-            device.clone(),
-            attachments: {
-                color: {
-                    format: device.physical_device().surface_formats(&surface, Default::default())?[0].0,
-                    samples: 1,
-                    load_op: Clear,
-                    store_op: Store,
-                }
-            },
-            pass: {
-                color: [color],
-                depth_stencil: {}
-            }
-        )?;
+        let surface_formats = device
+            .physical_device()
+            .surface_formats(surface, Default::default())?;
+        let color_spaces: Vec<ColorSpace> = [
+            ColorSpace::Srgb,
+            ColorSpace::ExtendedSrgb,
+            ColorSpace::Hdr10,
+        ]
+        .into_iter()
+        .filter(|color_space| {
+            color_space
+                .surface_format()
+                .map_or(true, |format| surface_formats.contains(&format))
+        })
+        .collect();
+        let color_space = Some(graphics.preferred_color_space())
+            .filter(|color_space| color_spaces.contains(color_space))
+            .unwrap_or(ColorSpace::Srgb);
+        let surface_format = color_space
+            .surface_format()
+            .or_else(|| surface_formats.first().copied())
+            .ok_or(Error::msg(
+                "The window surface does not support any format.",
+            ))?;
+        *graphics.color_space.lock() = color_space;
+        graphics.available_color_spaces.get_or_init(|| color_spaces);
+
+        // The stencil buffer used by masks, the first of the formats the device can draw to.
+        let stencil_format = [
+            Format::D24_UNORM_S8_UINT,
+            Format::D32_SFLOAT_S8_UINT,
+            Format::D16_UNORM_S8_UINT,
+        ]
+        .into_iter()
+        .find(|format| {
+            physical_device
+                .format_properties(*format)
+                .is_ok_and(|properties| {
+                    properties
+                        .optimal_tiling_features
+                        .intersects(FormatFeatures::DEPTH_STENCIL_ATTACHMENT)
+                })
+        })
+        .ok_or(Error::msg(
+            "The device does not support any stencil format.",
+        ))?;
+
+        // The render pass of frozen layers only differs in keeping the image of the scene,
+        // so the pipelines of the scene can draw in both.
+        let (render_pass, freeze_render_pass) = if color_space.is_hdr() {
+            // The scene gets drawn to a floating point image, which gets tonemapped to the window in the second subpass.
+            let render_pass = vulkano::ordered_passes_renderpass!(
+                device.clone(),
+                attachments: {
+                    scene: {
+                        format: Format::R16G16B16A16_SFLOAT,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: DontCare,
+                    },
+                    color: {
+                        format: surface_format.0,
+                        samples: 1,
+                        load_op: DontCare,
+                        store_op: Store,
+                    },
+                    stencil: {
+                        format: stencil_format,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: DontCare,
+                    }
+                },
+                passes: [
+                    {
+                        color: [scene],
+                        depth_stencil: {stencil},
+                        input: []
+                    },
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: [scene]
+                    },
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: []
+                    }
+                ]
+            )?;
+            let freeze_render_pass = vulkano::ordered_passes_renderpass!(
+                device.clone(),
+                attachments: {
+                    scene: {
+                        format: Format::R16G16B16A16_SFLOAT,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: Store,
+                    },
+                    color: {
+                        format: surface_format.0,
+                        samples: 1,
+                        load_op: DontCare,
+                        store_op: DontCare,
+                    },
+                    stencil: {
+                        format: stencil_format,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: DontCare,
+                    }
+                },
+                passes: [
+                    {
+                        color: [scene],
+                        depth_stencil: {stencil},
+                        input: []
+                    },
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: [scene]
+                    },
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: []
+                    }
+                ]
+            )?;
+            (render_pass, freeze_render_pass)
+        } else {
+            let render_pass = vulkano::ordered_passes_renderpass!(
+                device.clone(),
+                attachments: {
+                    color: {
+                        format: surface_format.0,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: Store,
+                    },
+                    stencil: {
+                        format: stencil_format,
+                        samples: 1,
+                        load_op: Clear,
+                        store_op: DontCare,
+                    }
+                },
+                passes: [
+                    {
+                        color: [color],
+                        depth_stencil: {stencil},
+                        input: []
+                    },
+                    {
+                        color: [color],
+                        depth_stencil: {},
+                        input: []
+                    }
+                ]
+            )?;
+            // The window image the scene gets drawn to is kept anyway.
+            (render_pass.clone(), render_pass)
+        };
 
         let subpass = Subpass::from(render_pass.clone(), 0).ok_or(Error::msg(
             "There was a problem making a subpass from the last render pass.",
         ))?;
+        let overlay_subpass = Subpass::from(
+            render_pass.clone(),
+            render_pass.subpasses().len() as u32 - 1,
+        )
+        .ok_or(Error::msg(
+            "There was a problem making the overlay subpass.",
+        ))?;
 
         //Materials
         let vs = vertex_shader(device.clone())?;
         let fs = fragment_shader(device.clone())?;
-        let default_shaders = Shaders::from_modules(vs.clone(), fs.clone(), "main");
+        let default_shaders = Shaders::from_default(&vs, &fs);
 
         let tfs = textured_fragment_shader(device.clone())?;
-        let default_textured_shaders = Shaders::from_modules(vs.clone(), tfs.clone(), "main");
+        let default_textured_shaders = Shaders::from_default(&vs, &tfs);
 
         let tafs = texture_array_fragment_shader(device.clone())?;
-        let default_texture_array_shaders = Shaders::from_modules(vs.clone(), tafs.clone(), "main");
+        let default_texture_array_shaders = Shaders::from_default(&vs, &tafs);
+
+        let msdf_frag = msdf_fragment_shader(device.clone())?;
+        let default_msdf_shaders = Shaders::from_default(&vs, &msdf_frag);
+
+        let sdf_frag = sdf_fragment_shader(device.clone())?;
+        let default_sdf_shaders = Shaders::from_default(&vs, &sdf_frag);
+
+        let lit_vert = lit_vertex_shader(device.clone())?;
+        let lit_frag = lit_fragment_shader(device.clone())?;
+        let default_lit_shaders = Shaders::from_default(&lit_vert, &lit_frag);
 
         let instance_vert = instanced_vertex_shader(device.clone())?;
         let instance_frag = instanced_fragment_shader(device.clone())?;
-        let default_instance_shaders =
-            Shaders::from_modules(instance_vert.clone(), instance_frag.clone(), "main");
+        let default_instance_shaders = Shaders::from_default(&instance_vert, &instance_frag);
 
         let textured_instance_frag = instanced_textured_fragment_shader(device.clone())?;
-        let default_textured_instance_shaders = Shaders::from_modules(
-            instance_vert.clone(),
-            textured_instance_frag.clone(),
-            "main",
-        );
+        let default_textured_instance_shaders =
+            Shaders::from_default(&instance_vert, &textured_instance_frag);
 
         let texture_array_instance_frag = instanced_texture_array_fragment_shader(device.clone())?;
-        let default_texture_array_instance_shaders = Shaders::from_modules(
-            instance_vert.clone(),
-            texture_array_instance_frag.clone(),
-            "main",
-        );
+        let default_texture_array_instance_shaders =
+            Shaders::from_default(&instance_vert, &texture_array_instance_frag);
+
+        let properties = device.physical_device().properties();
+        let bindless = device
+            .enabled_features()
+            .shader_sampled_image_array_non_uniform_indexing
+            && [
+                properties.max_per_stage_descriptor_samplers,
+                properties.max_per_stage_descriptor_sampled_images,
+                properties.max_descriptor_set_samplers,
+                properties.max_descriptor_set_sampled_images,
+            ]
+            .iter()
+            .all(|limit| *limit >= BINDLESS_TEXTURES);
+        let bindless_instance_frag = bindless
+            .then(|| bindless_instance_fragment_shader(device.clone()))
+            .transpose()?;
 
         let vertex_buffer_description = [GameVertex::per_vertex(), InstanceData::per_instance()];
 
@@ -134,9 +376,11 @@ impl Vulkan {
         let rasterisation_state = RasterizationState::default();
 
         let vertex = vs
+            .module
             .entry_point("main")
             .expect("Main function of default vertex shader has no main function.");
         let fragment = fs
+            .module
             .entry_point("main")
             .expect("Main function of default fragment shader has no main function.");
 
@@ -153,6 +397,7 @@ impl Vulkan {
         pipelines.push(pipeline.clone());
 
         let textured_fragment = tfs
+            .module
             .entry_point("main")
             .expect("Main function not found in default textured fragment shader.");
         let textured_pipeline = pipeline::create_pipeline(
@@ -168,6 +413,7 @@ impl Vulkan {
         pipelines.push(textured_pipeline.clone());
 
         let texture_array_fragment = tafs
+            .module
             .entry_point("main")
             .expect("Main function not found in default texture array shader.");
         let texture_array_pipeline = pipeline::create_pipeline(
@@ -182,10 +428,64 @@ impl Vulkan {
         )?;
         pipelines.push(texture_array_pipeline.clone());
 
+        let msdf_fragment = msdf_frag
+            .module
+            .entry_point("main")
+            .expect("Main function not found in default MSDF fragment shader.");
+        let msdf_pipeline = pipeline::create_pipeline(
+            &device,
+            vertex.clone(),
+            msdf_fragment,
+            InputAssemblyState::default(),
+            subpass.clone(),
+            vertex_buffer_description[0].definition(&vertex)?,
+            rasterisation_state.clone(),
+            None,
+        )?;
+        pipelines.push(msdf_pipeline.clone());
+
+        let sdf_fragment = sdf_frag
+            .module
+            .entry_point("main")
+            .expect("Main function not found in default SDF fragment shader.");
+        let sdf_pipeline = pipeline::create_pipeline(
+            &device,
+            vertex.clone(),
+            sdf_fragment,
+            InputAssemblyState::default(),
+            subpass.clone(),
+            vertex_buffer_description[0].definition(&vertex)?,
+            rasterisation_state.clone(),
+            None,
+        )?;
+        pipelines.push(sdf_pipeline.clone());
+
+        let lit_vertex = lit_vert
+            .module
+            .entry_point("main")
+            .expect("Main function not found in default lit vertex shader.");
+        let lit_fragment = lit_frag
+            .module
+            .entry_point("main")
+            .expect("Main function not found in default lit fragment shader.");
+        let lit_pipeline = pipeline::create_pipeline(
+            &device,
+            lit_vertex.clone(),
+            lit_fragment,
+            InputAssemblyState::default(),
+            subpass.clone(),
+            vertex_buffer_description[0].definition(&lit_vertex)?,
+            rasterisation_state.clone(),
+            None,
+        )?;
+        pipelines.push(lit_pipeline.clone());
+
         let instance_vertex = instance_vert
+            .module
             .entry_point("main")
             .expect("Main function not found in default instanced vertex shader.");
         let instance_fragment = instance_frag
+            .module
             .entry_point("main")
             .expect("Main function not found in default instanced fragment shader.");
         let instance_pipeline = pipeline::create_pipeline(
@@ -201,6 +501,7 @@ impl Vulkan {
         pipelines.push(instance_pipeline.clone());
 
         let textured_instance_fragment = textured_instance_frag
+            .module
             .entry_point("main")
             .expect("Main function not found in default textured instanced fragment shader.");
         let textured_instance_pipeline = pipeline::create_pipeline(
@@ -216,6 +517,7 @@ impl Vulkan {
         pipelines.push(textured_instance_pipeline.clone());
 
         let texture_array_instance_fragment = texture_array_instance_frag
+            .module
             .entry_point("main")
             .expect("Main function not found in default texture array instance fragment shader.");
         let texture_array_instance_pipeline = pipeline::create_pipeline(
@@ -225,11 +527,101 @@ impl Vulkan {
             InputAssemblyState::default(),
             subpass.clone(),
             vertex_buffer_description.definition(&instance_vertex)?,
-            rasterisation_state,
+            rasterisation_state.clone(),
             None,
         )?;
         pipelines.push(texture_array_instance_pipeline.clone());
 
+        let bindless_instance_material = match bindless_instance_frag {
+            Some(bindless_instance_frag) => {
+                let fragment = bindless_instance_frag
+                    .module
+                    .entry_point("main")
+                    .expect("Main function not found in the bindless instance fragment shader.");
+                let pipeline = pipeline::create_pipeline(
+                    &device,
+                    instance_vertex.clone(),
+                    fragment,
+                    InputAssemblyState::default(),
+                    subpass.clone(),
+                    vertex_buffer_description.definition(&instance_vertex)?,
+                    rasterisation_state,
+                    None,
+                )?;
+                pipelines.push(pipeline.clone());
+                Some(Material::from_pipeline(
+                    &pipeline,
+                    true,
+                    Shaders::from_default(&instance_vert, &bindless_instance_frag),
+                ))
+            }
+            None => None,
+        };
+
+        let mask_vertex = mask_vertex_shader(device.clone())?
+            .module
+            .entry_point("main")
+            .expect("Main function not found in the mask vertex shader.");
+        let mask_fragment = mask_fragment_shader(device.clone())?
+            .module
+            .entry_point("main")
+            .expect("Main function not found in the mask fragment shader.");
+        let mask = pipeline::create_mask_pipeline(
+            &device,
+            mask_vertex.clone(),
+            mask_fragment.clone(),
+            subpass.clone(),
+            vertex_buffer_description[0].definition(&mask_vertex)?,
+            StencilOp::IncrementAndClamp,
+        )?;
+        let unmask = pipeline::create_mask_pipeline(
+            &device,
+            mask_vertex.clone(),
+            mask_fragment,
+            subpass.clone(),
+            vertex_buffer_description[0].definition(&mask_vertex)?,
+            StencilOp::DecrementAndClamp,
+        )?;
+
+        let composite = pipeline::create_composite_pipeline(
+            &device,
+            fullscreen_vertex_shader(device.clone())?
+                .module
+                .entry_point("main")
+                .expect("Main function not found in the full screen vertex shader."),
+            composite_fragment_shader(device.clone())?
+                .module
+                .entry_point("main")
+                .expect("Main function not found in the frozen layer fragment shader."),
+            subpass.clone(),
+        )?;
+
+        let tonemap = if color_space.is_hdr() {
+            let tonemap_subpass = Subpass::from(render_pass.clone(), 1).ok_or(Error::msg(
+                "There was a problem making the tonemapping subpass.",
+            ))?;
+            let vertex = fullscreen_vertex_shader(device.clone())?
+                .module
+                .entry_point("main")
+                .expect("Main function not found in the full screen vertex shader.");
+            let fragment = tonemap_fragment_shader(device.clone())?
+                .module
+                .entry_point("main")
+                .expect("Main function not found in the tonemapping fragment shader.");
+            Some(pipeline::create_pipeline(
+                &device,
+                vertex,
+                fragment,
+                InputAssemblyState::default(),
+                tonemap_subpass,
+                VertexInputState::default(),
+                RasterizationState::default(),
+                None,
+            )?)
+        } else {
+            None
+        };
+
         let default_material = Material::from_pipeline(&pipeline, false, default_shaders.clone());
         let textured_material =
             Material::from_pipeline(&textured_pipeline, false, default_textured_shaders.clone());
@@ -238,6 +630,11 @@ impl Vulkan {
             false,
             default_texture_array_shaders.clone(),
         );
+        let msdf_material =
+            Material::from_pipeline(&msdf_pipeline, false, default_msdf_shaders.clone());
+        let sdf_material =
+            Material::from_pipeline(&sdf_pipeline, false, default_sdf_shaders.clone());
+        let lit_material = Material::from_pipeline(&lit_pipeline, false, default_lit_shaders);
         let default_instance_material =
             Material::from_pipeline(&instance_pipeline, true, default_instance_shaders.clone());
 
@@ -258,9 +655,17 @@ impl Vulkan {
             Self {
                 instance,
                 device,
-                queue,
+                queue: queues.graphics,
+                transfer_queue: queues.transfer,
                 render_pass,
+                freeze_render_pass,
                 subpass,
+                overlay_subpass,
+                surface_format,
+                tonemap,
+                mask,
+                unmask,
+                composite,
                 default_shaders,
                 default_instance_shaders,
                 default_material,
@@ -268,21 +673,94 @@ impl Vulkan {
                 texture_array_material,
                 textured_instance_material,
                 texture_array_instance_material,
+                bindless_instance_material,
                 default_instance_material,
+                msdf_material,
+                sdf_material,
+                lit_material,
             },
         ))
     }
 }
 
+impl Vulkan {
+    /// Moves the pipelines and shaders of the default materials into the ones of the previous device and uses those,
+    /// so the clones the game holds keep working and counting as default materials.
+    pub(crate) fn keep_materials(&mut self, previous: &Vulkan) {
+        let materials = [
+            (&mut self.default_material, &previous.default_material),
+            (&mut self.textured_material, &previous.textured_material),
+            (
+                &mut self.texture_array_material,
+                &previous.texture_array_material,
+            ),
+            (
+                &mut self.default_instance_material,
+                &previous.default_instance_material,
+            ),
+            (
+                &mut self.textured_instance_material,
+                &previous.textured_instance_material,
+            ),
+            (
+                &mut self.texture_array_instance_material,
+                &previous.texture_array_instance_material,
+            ),
+            (&mut self.msdf_material, &previous.msdf_material),
+            (&mut self.sdf_material, &previous.sdf_material),
+            (&mut self.lit_material, &previous.lit_material),
+        ];
+        for (material, previous) in materials {
+            previous.replace_gpu(material);
+            *material = previous.clone();
+        }
+        if let (Some(material), Some(previous)) = (
+            &mut self.bindless_instance_material,
+            &previous.bindless_instance_material,
+        ) {
+            previous.replace_gpu(material);
+            *material = previous.clone();
+        }
+    }
+
+    /// Returns true if uploads run on a separate queue family, so images have to be shared between the families.
+    pub fn separate_transfer(&self) -> bool {
+        self.transfer_queue.queue_family_index() != self.queue.queue_family_index()
+    }
+
+    /// Gives the given Vulkan object a debug name, so validation messages and graphics captures refer to it by that name.
+    ///
+    /// Does nothing in case no name is given.
+    pub(crate) fn set_debug_name<T: VulkanObject + DeviceOwned>(
+        &self,
+        object: &T,
+        name: Option<&str>,
+    ) {
+        let Some(name) = name else {
+            return;
+        };
+        if let Err(error) = self.device.set_debug_utils_object_name(object, Some(name)) {
+            log::warn!("Could not set the debug name \"{name}\": {error}");
+        }
+    }
+}
+
 /// Sets the dynamic viewport up to work with the newly set resolution of the window.
 //  For games make the viewport less dynamic.
+///
+/// With an HDR render pass every framebuffer gets its own floating point image to draw the scene to.
 pub fn window_size_dependent_setup(
     images: &[Arc<Image>],
     render_pass: Arc<RenderPass>,
     viewport: &mut Viewport,
+    memory_allocator: &Arc<StandardMemoryAllocator>,
 ) -> Result<Vec<Arc<Framebuffer>>> {
     let dimensions = images[0].extent();
     viewport.extent = [dimensions[0] as f32, dimensions[1] as f32];
+    // The attachments are the HDR scene image in case there is one, the window image and the stencil buffer.
+    let attachments = render_pass.attachments();
+    let scene_format = (attachments.len() > 2).then(|| attachments[0].format);
+    let stencil_format = attachments[attachments.len() - 1].format;
 
     let framebuffers: Vec<Arc<Framebuffer>> = images
         .iter()
@@ -290,10 +768,41 @@ pub fn window_size_dependent_setup(
             let view = ImageView::new_default(image.clone())
                 .context("Could not make a frame texture.")
                 .unwrap();
+            let mut attachments = vec![view];
+            if let Some(format) = scene_format {
+                let scene = Image::new(
+                    memory_allocator.clone(),
+                    ImageCreateInfo {
+                        image_type: ImageType::Dim2d,
+                        format,
+                        extent: dimensions,
+                        usage: ImageUsage::COLOR_ATTACHMENT | ImageUsage::INPUT_ATTACHMENT,
+                        ..Default::default()
+                    },
+                    AllocationCreateInfo::default(),
+                )
+                .context("Could not make an HDR frame texture.")
+                .unwrap();
+                attachments.insert(0, ImageView::new_default(scene).unwrap());
+            }
+            let stencil = Image::new(
+                memory_allocator.clone(),
+                ImageCreateInfo {
+                    image_type: ImageType::Dim2d,
+                    format: stencil_format,
+                    extent: dimensions,
+                    usage: ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+                    ..Default::default()
+                },
+                AllocationCreateInfo::default(),
+            )
+            .context("Could not make a stencil buffer.")
+            .unwrap();
+            attachments.push(ImageView::new_default(stencil).unwrap());
             Framebuffer::new(
                 render_pass.clone(),
                 FramebufferCreateInfo {
-                    attachments: vec![view],
+                    attachments,
                     ..Default::default()
                 },
             )
@@ -304,3 +813,63 @@ pub fn window_size_dependent_setup(
 
     Ok(framebuffers)
 }
+
+/// Makes the framebuffer a frozen layer gets drawn to using the freeze render pass,
+/// returning it along with the image the scene gets drawn to.
+pub(crate) fn frozen_layer_setup(
+    render_pass: Arc<RenderPass>,
+    dimensions: [u32; 2],
+    memory_allocator: &Arc<StandardMemoryAllocator>,
+) -> Result<(Arc<Framebuffer>, Arc<ImageView>)> {
+    let image = |format, usage| -> Result<Arc<ImageView>> {
+        let image = Image::new(
+            memory_allocator.clone(),
+            ImageCreateInfo {
+                image_type: ImageType::Dim2d,
+                format,
+                extent: [dimensions[0], dimensions[1], 1],
+                usage,
+                ..Default::default()
+            },
+            AllocationCreateInfo::default(),
+        )
+        .context("Could not make the image of a frozen layer.")?;
+        Ok(ImageView::new_default(image)?)
+    };
+    // The same attachments as the framebuffers of the window, where the first one gets drawn to by the scene.
+    let formats: Vec<Format> = render_pass
+        .attachments()
+        .iter()
+        .map(|attachment| attachment.format)
+        .collect();
+    let hdr = formats.len() > 2;
+    let scene = image(
+        formats[0],
+        if hdr {
+            ImageUsage::COLOR_ATTACHMENT | ImageUsage::INPUT_ATTACHMENT | ImageUsage::SAMPLED
+        } else {
+            ImageUsage::COLOR_ATTACHMENT | ImageUsage::SAMPLED
+        },
+    )?;
+    let mut attachments = vec![scene.clone()];
+    if hdr {
+        // Only written by the tonemapping subpass, which stays empty.
+        attachments.push(image(
+            formats[1],
+            ImageUsage::COLOR_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+        )?);
+    }
+    attachments.push(image(
+        formats[formats.len() - 1],
+        ImageUsage::DEPTH_STENCIL_ATTACHMENT | ImageUsage::TRANSIENT_ATTACHMENT,
+    )?);
+    let framebuffer = Framebuffer::new(
+        render_pass,
+        FramebufferCreateInfo {
+            attachments,
+            ..Default::default()
+        },
+    )
+    .context("Could not make the framebuffer of a frozen layer.")?;
+    Ok((framebuffer, scene))
+}
@@ -4,7 +4,12 @@ use vulkano::device::Device;
 use vulkano::pipeline::cache::PipelineCache;
 use vulkano::pipeline::graphics::vertex_input::VertexInputState;
 use vulkano::pipeline::graphics::{
-    color_blend::{AttachmentBlend, ColorBlendAttachmentState, ColorBlendState},
+    color_blend::{
+        AttachmentBlend, BlendFactor, ColorBlendAttachmentState, ColorBlendState, ColorComponents,
+    },
+    depth_stencil::{
+        CompareOp, DepthStencilState, StencilOp, StencilOpState, StencilOps, StencilState,
+    },
     multisample::MultisampleState,
     rasterization::RasterizationState,
     GraphicsPipelineCreateInfo,
@@ -12,7 +17,7 @@ use vulkano::pipeline::graphics::{
 };
 
 use vulkano::pipeline::{
-    layout::PipelineDescriptorSetLayoutCreateInfo, GraphicsPipeline, PipelineLayout,
+    layout::PipelineDescriptorSetLayoutCreateInfo, DynamicState, GraphicsPipeline, PipelineLayout,
     PipelineShaderStageCreateInfo,
 };
 use vulkano::render_pass::Subpass;
@@ -21,6 +26,9 @@ use vulkano::shader::EntryPoint;
 use crate::draw::VIEWPORT;
 
 /// Creates the graphics pipeline.
+///
+/// In subpasses with a stencil buffer the pipeline only draws where the stencil value equals the stencil reference,
+/// which is dynamic state and has to be set before drawing. The engine sets it to the number of masks an object is in.
 #[allow(clippy::too_many_arguments)]
 pub fn create_pipeline(
     device: &Arc<Device>,
@@ -58,13 +66,167 @@ pub fn create_pipeline(
             color_blend_state: Some(ColorBlendState::with_attachment_states(
                 subpass.num_color_attachments(),
                 ColorBlendAttachmentState {
-                    blend: Some(AttachmentBlend::alpha()),
+                    // The alpha channel adds up the coverage, so images of frozen layers can be drawn over the others.
+                    blend: Some(AttachmentBlend {
+                        src_alpha_blend_factor: BlendFactor::One,
+                        ..AttachmentBlend::alpha()
+                    }),
                     ..Default::default()
                 },
             )),
+            depth_stencil_state: subpass
+                .has_stencil()
+                .then(|| stencil_state(StencilOp::Keep)),
+            // Layers with multiple cameras draw to multiple viewports and scissors each frame.
+            dynamic_state: if subpass.has_stencil() {
+                [
+                    DynamicState::Viewport,
+                    DynamicState::Scissor,
+                    DynamicState::StencilReference,
+                ]
+                .into_iter()
+                .collect()
+            } else {
+                [DynamicState::Viewport, DynamicState::Scissor]
+                    .into_iter()
+                    .collect()
+            },
             subpass: Some(subpass.into()),
             ..GraphicsPipelineCreateInfo::layout(layout)
         },
     )
     .context("Could not create a graphics pipeline.")
 }
+
+/// Creates a pipeline only changing the stencil buffer where the model gets drawn inside the masks around it,
+/// incrementing the values for masks and decrementing them for removing masks.
+pub fn create_mask_pipeline(
+    device: &Arc<Device>,
+    vertex: EntryPoint,
+    fragment: EntryPoint,
+    subpass: Subpass,
+    vertex_input_state: VertexInputState,
+    pass_op: StencilOp,
+) -> Result<Arc<GraphicsPipeline>> {
+    let stages = [
+        PipelineShaderStageCreateInfo::new(vertex),
+        PipelineShaderStageCreateInfo::new(fragment),
+    ];
+    let layout = PipelineLayout::new(
+        device.clone(),
+        PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+            .into_pipeline_layout_create_info(device.clone())?,
+    )?;
+
+    GraphicsPipeline::new(
+        device.clone(),
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into_iter().collect(),
+            vertex_input_state: Some(vertex_input_state),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            viewport_state: Some(ViewportState {
+                viewports: [VIEWPORT.read().clone()].into_iter().collect(),
+                ..Default::default()
+            }),
+            rasterization_state: Some(RasterizationState::default()),
+            multisample_state: Some(MultisampleState::default()),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.num_color_attachments(),
+                ColorBlendAttachmentState {
+                    color_write_mask: ColorComponents::empty(),
+                    ..Default::default()
+                },
+            )),
+            depth_stencil_state: Some(stencil_state(pass_op)),
+            dynamic_state: [
+                DynamicState::Viewport,
+                DynamicState::Scissor,
+                DynamicState::StencilReference,
+            ]
+            .into_iter()
+            .collect(),
+            subpass: Some(subpass.into()),
+            ..GraphicsPipelineCreateInfo::layout(layout)
+        },
+    )
+    .context("Could not create a mask pipeline.")
+}
+
+/// Creates the pipeline drawing the image of a frozen layer over the scene.
+///
+/// The colors of the image are already multiplied with their alpha by the blending of the pipelines drawing it.
+pub fn create_composite_pipeline(
+    device: &Arc<Device>,
+    vertex: EntryPoint,
+    fragment: EntryPoint,
+    subpass: Subpass,
+) -> Result<Arc<GraphicsPipeline>> {
+    let stages = [
+        PipelineShaderStageCreateInfo::new(vertex),
+        PipelineShaderStageCreateInfo::new(fragment),
+    ];
+    let layout = PipelineLayout::new(
+        device.clone(),
+        PipelineDescriptorSetLayoutCreateInfo::from_stages(&stages)
+            .into_pipeline_layout_create_info(device.clone())?,
+    )?;
+
+    GraphicsPipeline::new(
+        device.clone(),
+        None,
+        GraphicsPipelineCreateInfo {
+            stages: stages.into_iter().collect(),
+            vertex_input_state: Some(VertexInputState::default()),
+            input_assembly_state: Some(InputAssemblyState::default()),
+            viewport_state: Some(ViewportState {
+                viewports: [VIEWPORT.read().clone()].into_iter().collect(),
+                ..Default::default()
+            }),
+            rasterization_state: Some(RasterizationState::default()),
+            multisample_state: Some(MultisampleState::default()),
+            color_blend_state: Some(ColorBlendState::with_attachment_states(
+                subpass.num_color_attachments(),
+                ColorBlendAttachmentState {
+                    blend: Some(AttachmentBlend {
+                        src_color_blend_factor: BlendFactor::One,
+                        src_alpha_blend_factor: BlendFactor::One,
+                        ..AttachmentBlend::alpha()
+                    }),
+                    ..Default::default()
+                },
+            )),
+            depth_stencil_state: Some(stencil_state(StencilOp::Keep)),
+            dynamic_state: [
+                DynamicState::Viewport,
+                DynamicState::Scissor,
+                DynamicState::StencilReference,
+            ]
+            .into_iter()
+            .collect(),
+            subpass: Some(subpass.into()),
+            ..GraphicsPipelineCreateInfo::layout(layout)
+        },
+    )
+    .context("Could not create the frozen layer pipeline.")
+}
+
+/// Passes where the stencil value equals the reference, applying the given operation to it.
+fn stencil_state(pass_op: StencilOp) -> DepthStencilState {
+    let face = StencilOpState {
+        ops: StencilOps {
+            fail_op: StencilOp::Keep,
+            pass_op,
+            depth_fail_op: StencilOp::Keep,
+            compare_op: CompareOp::Equal,
+        },
+        ..Default::default()
+    };
+    DepthStencilState {
+        stencil: Some(StencilState {
+            front: face,
+            back: face,
+        }),
+        ..Default::default()
+    }
+}
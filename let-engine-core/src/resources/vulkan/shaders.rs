@@ -8,12 +8,20 @@ use vulkano::{
     shader::{spirv::bytes_to_words, ShaderModule, ShaderModuleCreateInfo},
 };
 
-fn from_bytes(bytes: &[u8], device: Arc<Device>) -> Result<Arc<ShaderModule>> {
-    let code = bytes_to_words(bytes)?;
-    Ok(unsafe { ShaderModule::new(device, ShaderModuleCreateInfo::new(&code))? })
+/// A default shader along with its SpirV, kept to load it again on a new device after the GPU got lost.
+#[derive(Clone)]
+pub struct Shader {
+    pub module: Arc<ShaderModule>,
+    pub code: Arc<[u32]>,
 }
 
-pub fn vertex_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+fn from_bytes(bytes: &[u8], device: Arc<Device>) -> Result<Shader> {
+    let code: Arc<[u32]> = bytes_to_words(bytes)?.into();
+    let module = unsafe { ShaderModule::new(device, ShaderModuleCreateInfo::new(&code))? };
+    Ok(Shader { module, code })
+}
+
+pub fn vertex_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/default.vert")),
         device,
@@ -21,7 +29,7 @@ pub fn vertex_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
     .context("There was a problem making the default vertex shader.")
 }
 
-pub fn fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/default.frag")),
         device,
@@ -29,7 +37,7 @@ pub fn fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
     .context("There was a problem making the default fragment shader.")
 }
 
-pub fn instanced_vertex_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn instanced_vertex_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/instance.vert")),
         device,
@@ -37,7 +45,7 @@ pub fn instanced_vertex_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>>
     .context("There was a problem making the default instanced vertex shader.")
 }
 
-pub fn instanced_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn instanced_fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/instance.frag")),
         device,
@@ -45,7 +53,7 @@ pub fn instanced_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule
     .context("There was a problem making the default instanced fragment shader.")
 }
 
-pub fn textured_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn textured_fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/textured.frag")),
         device,
@@ -53,7 +61,7 @@ pub fn textured_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>
     .context("There was a problem making the default textured fragment shader.")
 }
 
-pub fn texture_array_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn texture_array_fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/texture_array.frag")),
         device,
@@ -61,7 +69,7 @@ pub fn texture_array_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderMo
     .context("There was a problem making the default texture array fragment shader.")
 }
 
-pub fn instanced_textured_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn instanced_textured_fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/textured_instance.frag")),
         device,
@@ -69,10 +77,90 @@ pub fn instanced_textured_fragment_shader(device: Arc<Device>) -> Result<Arc<Sha
     .context("There was a problem making the default instanced texture fragment shader.")
 }
 
-pub fn instanced_texture_array_fragment_shader(device: Arc<Device>) -> Result<Arc<ShaderModule>> {
+pub fn instanced_texture_array_fragment_shader(device: Arc<Device>) -> Result<Shader> {
     from_bytes(
         include_bytes!(concat!(env!("OUT_DIR"), "/texture_array_instance.frag")),
         device,
     )
     .context("There was a problem making the default instanced texture array fragment shader.")
 }
+
+pub fn bindless_instance_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/bindless_instance.frag")),
+        device,
+    )
+    .context("There was a problem making the default bindless instance fragment shader.")
+}
+
+pub fn msdf_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/msdf.frag")),
+        device,
+    )
+    .context("There was a problem making the default MSDF fragment shader.")
+}
+
+pub fn sdf_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/sdf.frag")),
+        device,
+    )
+    .context("There was a problem making the default SDF fragment shader.")
+}
+
+pub fn lit_vertex_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/lit.vert")),
+        device,
+    )
+    .context("There was a problem making the default lit vertex shader.")
+}
+
+pub fn lit_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/lit.frag")),
+        device,
+    )
+    .context("There was a problem making the default lit fragment shader.")
+}
+
+pub fn fullscreen_vertex_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/fullscreen.vert")),
+        device,
+    )
+    .context("There was a problem making the full screen vertex shader.")
+}
+
+pub fn tonemap_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/tonemap.frag")),
+        device,
+    )
+    .context("There was a problem making the tonemapping fragment shader.")
+}
+
+pub fn composite_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/composite.frag")),
+        device,
+    )
+    .context("There was a problem making the frozen layer fragment shader.")
+}
+
+pub fn mask_vertex_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/mask.vert")),
+        device,
+    )
+    .context("There was a problem making the mask vertex shader.")
+}
+
+pub fn mask_fragment_shader(device: Arc<Device>) -> Result<Shader> {
+    from_bytes(
+        include_bytes!(concat!(env!("OUT_DIR"), "/mask.frag")),
+        device,
+    )
+    .context("There was a problem making the mask fragment shader.")
+}
@@ -3,25 +3,25 @@ extern crate vulkano;
 use anyhow::{Context, Error};
 use std::sync::Arc;
 use vulkano::device::Device;
+use vulkano::format::Format;
 use vulkano::image::{Image, ImageUsage};
-use vulkano::swapchain::{PresentMode, Surface, SurfaceInfo, Swapchain, SwapchainCreateInfo};
+use vulkano::swapchain::{
+    ColorSpace, PresentMode, Surface, SurfaceInfo, Swapchain, SwapchainCreateInfo,
+};
 use winit::window::Window;
 
 use crate::draw::Graphics;
 
-// Creates the swapchain.
+// Creates the swapchain with the format the render pass was made for.
 pub fn create_swapchain_and_images(
     device: &Arc<Device>,
     surface: &Arc<Surface>,
     graphics: &Graphics,
+    (image_format, image_color_space): (Format, ColorSpace),
 ) -> anyhow::Result<(Arc<Swapchain>, Vec<Arc<Image>>)> {
     let surface_capabilities = device
         .physical_device()
         .surface_capabilities(surface, Default::default())?;
-    let image_format = device
-        .physical_device()
-        .surface_formats(surface, Default::default())?[0]
-        .0;
     let innersize = surface
         .object()
         .ok_or(Error::msg("Failed to cast the surface to a window."))?
@@ -29,15 +29,26 @@ pub fn create_swapchain_and_images(
         .ok_or(Error::msg("Failed to cast the surface to a window."))?
         .inner_size()
         .into();
-    let present_mode = device
+    let supported_present_modes: Vec<PresentMode> = device
         .physical_device()
         .surface_present_modes(surface, SurfaceInfo::default())?
         .into_iter()
-        .min_by_key(|compare| match compare {
-            PresentMode::Mailbox => 0,
-            PresentMode::Immediate => 1,
-            PresentMode::Fifo => 2,
-            _ => 3,
+        .collect();
+    let preferred_present_mode = graphics
+        .preferred_present_mode()
+        .map(PresentMode::from)
+        .filter(|mode| supported_present_modes.contains(mode));
+    let present_mode = preferred_present_mode
+        .or_else(|| {
+            supported_present_modes
+                .iter()
+                .copied()
+                .min_by_key(|compare| match compare {
+                    PresentMode::Mailbox => 0,
+                    PresentMode::Immediate => 1,
+                    PresentMode::Fifo => 2,
+                    _ => 3,
+                })
         })
         .ok_or(Error::msg(
             "Failed to get any presentation mode on this device.",
@@ -47,9 +58,7 @@ pub fn create_swapchain_and_images(
     *graphics.present_mode.lock() = present_mode.into();
 
     // Give available present modes
-    let mut present_modes: Vec<_> = device
-        .physical_device()
-        .surface_present_modes(surface, SurfaceInfo::default())?
+    let mut present_modes: Vec<_> = supported_present_modes
         .into_iter()
         .map(|x| x.into())
         .collect();
@@ -61,6 +70,7 @@ pub fn create_swapchain_and_images(
     let create_info = SwapchainCreateInfo {
         min_image_count: surface_capabilities.min_image_count,
         image_format,
+        image_color_space,
         image_extent: innersize,
         image_usage: ImageUsage::COLOR_ATTACHMENT,
         present_mode,
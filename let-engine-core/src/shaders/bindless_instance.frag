@@ -0,0 +1,15 @@
+#version 450
+#extension GL_EXT_nonuniform_qualifier : require
+layout (location = 0) out vec4 f_color;
+
+layout (location = 1) in vec2 tex_coords;
+layout (location = 2) in vec4 color;
+// The index of the texture in the array instead of the layer.
+layout (location = 3) flat in uint layer;
+
+// Has to stay the same as `BINDLESS_TEXTURES` of the engine.
+layout (set = 0, binding = 0) uniform sampler2D textures[1024];
+
+void main() {
+    f_color = texture(textures[nonuniformEXT(layer)], tex_coords * 0.5 + 0.5) * color;
+}
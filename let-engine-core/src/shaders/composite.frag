@@ -0,0 +1,8 @@
+#version 450
+layout (location = 0) out vec4 f_color;
+layout (set = 0, binding = 0) uniform sampler2D frozen;
+
+// Copies the image of a frozen layer, which has the size of the window.
+void main() {
+    f_color = texelFetch(frozen, ivec2(gl_FragCoord.xy), 0);
+}
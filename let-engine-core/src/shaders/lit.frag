@@ -0,0 +1,48 @@
+#version 450
+#define MAX_LIGHTS 32
+
+layout (location = 0) out vec4 f_color;
+layout (location = 1) in vec2 tex_coords;
+layout (location = 2) in vec2 world_position;
+layout (location = 3) in vec4 axes;
+
+layout (set = 0, binding = 1) uniform Object {
+	vec4 color;
+	uint layer;
+} object;
+layout (set = 1, binding = 0) uniform sampler2D albedo;
+layout (set = 2, binding = 0) uniform sampler2D normal_map;
+
+struct Light {
+	// xy position, z height, w radius
+	vec4 position;
+	vec4 color;
+};
+layout (set = 3, binding = 0) uniform Lights {
+	vec4 ambient;
+	uint count;
+	Light lights[MAX_LIGHTS];
+} lights;
+
+void main() {
+    vec2 uv = tex_coords * 0.5 + 0.5;
+    vec4 color = texture(albedo, uv) * object.color;
+
+    // Normal maps have green pointing up in the image, which is the negative down axis of the object.
+    vec3 tangent_normal = texture(normal_map, uv).xyz * 2.0 - 1.0;
+    vec3 normal = normalize(vec3(
+        tangent_normal.x * axes.xy - tangent_normal.y * axes.zw,
+        tangent_normal.z
+    ));
+
+    vec3 light = lights.ambient.rgb;
+    for (uint i = 0; i < min(lights.count, MAX_LIGHTS); i++) {
+        vec4 position = lights.lights[i].position;
+        vec3 to_light = vec3(position.xy - world_position, position.z);
+        float falloff = clamp(1.0 - length(to_light.xy) / position.w, 0.0, 1.0);
+        float diffuse = max(dot(normal, normalize(to_light)), 0.0);
+        light += lights.lights[i].color.rgb * diffuse * falloff * falloff;
+    }
+
+    f_color = vec4(color.rgb * light, color.a);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout (location = 0) in vec2 position;
+layout (location = 1) in vec2 tex_position;
+layout (location = 1) out vec2 tex_coords;
+layout (location = 2) out vec2 world_position;
+// The right and down directions of the object in world space, turning the normals of the normal map.
+layout (location = 3) out vec4 axes;
+
+layout (set = 0, binding = 0) uniform Object {
+	mat4 model;
+	mat4 view;
+	mat4 proj;
+} object;
+
+void main() {
+    tex_coords = tex_position;
+
+    vec4 world = object.model * vec4(position, 0.0, 1.0);
+    world_position = world.xy;
+    axes = vec4(normalize(object.model[0].xy), normalize(object.model[1].xy));
+
+    gl_Position = object.proj * object.view * world;
+}
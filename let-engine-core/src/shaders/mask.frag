@@ -0,0 +1,5 @@
+#version 450
+
+// Masks only write to the stencil buffer.
+void main() {
+}
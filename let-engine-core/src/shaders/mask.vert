@@ -0,0 +1,11 @@
+#version 450
+
+layout (location = 0) in vec2 position;
+
+layout (push_constant) uniform Mask {
+	mat4 mvp;
+} mask;
+
+void main() {
+    gl_Position = mask.mvp * vec4(position, 0.0, 1.0);
+}
@@ -0,0 +1,27 @@
+#version 450
+layout (location = 0) out vec4 f_color;
+layout (location = 1) in vec2 tex_coords;
+layout (set = 0, binding = 1) uniform Object {
+	vec4 color;
+	uint layer;
+	float distance_range;
+} object;
+layout (set = 1, binding = 0) uniform sampler2D tex;
+
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+void main() {
+    vec2 uv = tex_coords * 0.5 + 0.5;
+    vec3 msd = texture(tex, uv).rgb;
+    float signed_distance = median(msd.r, msd.g, msd.b) - 0.5;
+
+    // The distance range of the field in screen pixels.
+    vec2 unit_range = vec2(object.distance_range) / vec2(textureSize(tex, 0));
+    vec2 screen_tex_size = vec2(1.0) / fwidth(uv);
+    float screen_px_range = max(0.5 * dot(unit_range, screen_tex_size), 1.0);
+
+    float coverage = clamp(signed_distance * screen_px_range + 0.5, 0.0, 1.0);
+    f_color = vec4(object.color.rgb, object.color.a * coverage);
+}
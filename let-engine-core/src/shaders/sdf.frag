@@ -0,0 +1,34 @@
+#version 450
+layout (location = 0) out vec4 f_color;
+layout (location = 1) in vec2 tex_coords;
+layout (set = 0, binding = 1) uniform Object {
+	vec4 color;
+	uint layer;
+	float distance_range;
+	float outline_width;
+	float glow_width;
+	vec4 outline_color;
+	vec4 glow_color;
+} object;
+layout (set = 1, binding = 0) uniform sampler2D tex;
+
+void main() {
+    vec2 uv = tex_coords * 0.5 + 0.5;
+    // The signed distance to the edge in pixels of the field, positive inside of the shape.
+    float distance = (texture(tex, uv).r - 0.5) * object.distance_range;
+
+    // Pixels of the field covered by one screen pixel.
+    float field_px = max(length(fwidth(uv) * vec2(textureSize(tex, 0))) * 0.70710678, 1e-4);
+
+    float fill = clamp(distance / field_px + 0.5, 0.0, 1.0);
+    float outline = object.outline_width > 0.0
+        ? clamp((distance + object.outline_width) / field_px + 0.5, 0.0, 1.0)
+        : 0.0;
+    float glow = object.glow_width > 0.0
+        ? clamp(1.0 + (distance + object.outline_width) / object.glow_width, 0.0, 1.0)
+        : 0.0;
+
+    vec4 color = vec4(object.glow_color.rgb, object.glow_color.a * glow);
+    color = mix(color, object.outline_color, outline);
+    f_color = mix(color, object.color, fill);
+}
@@ -0,0 +1,59 @@
+#version 450
+
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput scene;
+
+layout (push_constant) uniform Parameters {
+    // 1 for extended sRGB, 2 for HDR10.
+    uint output_space;
+    // 0 for none, 1 for Reinhard, 2 for ACES.
+    uint curve;
+    float paper_white;
+    float max_luminance;
+} parameters;
+
+layout (location = 0) out vec4 f_color;
+
+vec3 aces(vec3 x) {
+    // Narkowicz's fit of the ACES curve.
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// Compresses colors relative to paper white to the range from 0 to `white`.
+vec3 tonemap(vec3 x, float white) {
+    if (parameters.curve == 1) {
+        return x / (1.0 + x / white);
+    } else if (parameters.curve == 2) {
+        const float exposure = 2.0;
+        return white * aces(x / white * exposure) / aces(vec3(exposure));
+    }
+    return min(x, vec3(white));
+}
+
+vec3 pq(vec3 nits) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(nits / 10000.0, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+const mat3 REC709_TO_REC2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+void main() {
+    vec4 color = subpassLoad(scene);
+    float white = parameters.max_luminance / parameters.paper_white;
+    vec3 nits = tonemap(max(color.rgb, vec3(0.0)), white) * parameters.paper_white;
+
+    if (parameters.output_space == 2) {
+        f_color = vec4(pq(REC709_TO_REC2020 * nits), 1.0);
+    } else {
+        // scRGB has 1.0 at 80 nits.
+        f_color = vec4(nits / 80.0, 1.0);
+    }
+}
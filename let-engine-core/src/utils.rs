@@ -1,7 +1,10 @@
 //! General utitities used throughout the engine.
 
-use crate::camera::CameraScaling;
-use glam::{Mat4, Vec2};
+use crate::{
+    camera::{CameraScaling, CameraSettings},
+    objects::Transform,
+};
+use glam::{Mat4, Vec2, Vec3};
 
 /// Makes an orthographic projection matrix with the given information.
 pub fn ortho_maker(mode: CameraScaling, position: Vec2, zoom: f32, dimensions: Vec2) -> Mat4 {
@@ -16,6 +19,23 @@ pub fn ortho_maker(mode: CameraScaling, position: Vec2, zoom: f32, dimensions: V
     )
 }
 
+/// Returns the view and projection matrix of a camera with the given transform and settings drawing to an area of the given size in pixels.
+pub fn view_proj(camera: Transform, settings: CameraSettings, dimensions: Vec2) -> (Mat4, Mat4) {
+    let rotation = Mat4::from_rotation_z(camera.rotation);
+    let proj = ortho_maker(
+        settings.mode,
+        camera.position,
+        1.0 / settings.zoom,
+        dimensions,
+    );
+    let view = Mat4::look_at_rh(
+        camera.position.extend(1.0),
+        camera.position.extend(0.0),
+        Vec3::Y,
+    ) * rotation;
+    (view, proj)
+}
+
 /// Converts a `Vec<u16>` to a `Vec<u8>` where each number is split into it is high and low bytes.
 pub fn u16tou8vec(data: Vec<u16>) -> Vec<u8> {
     // to utils.rs in the future
@@ -27,3 +47,37 @@ pub fn u16tou8vec(data: Vec<u16>) -> Vec<u8> {
         })
         .collect()
 }
+
+/// A 64 bit FNV-1a hasher, giving the same hashes on every platform and in every version of Rust.
+///
+/// Used for checksums that get compared between machines, where the standard library hasher can not be relied on.
+#[derive(Clone, Copy, Debug)]
+pub struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Sizes differ between platforms, so they always get hashed as 64 bit numbers.
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
@@ -1,16 +1,25 @@
 //! General window stuff.
 //!
 //! Multiple structs to change the properties of a Window.
+use anyhow::Result;
 use crossbeam::atomic::AtomicCell;
 use glam::{vec2, Vec2};
+use parking_lot::Mutex;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
     Arc, OnceLock,
 };
 pub use winit::window::{CursorGrabMode, CursorIcon, Icon, UserAttentionType, WindowLevel};
-use winit::{dpi::*, error::ExternalError, window::WindowButtons};
+use winit::{
+    dpi::*,
+    error::ExternalError,
+    window::{BadIcon, WindowButtons},
+};
 
-use crate::objects::Color;
+use crate::{
+    objects::Color,
+    resources::{materials::Material, textures::Texture},
+};
 
 pub static WINDOW: OnceLock<Arc<Window>> = OnceLock::new();
 
@@ -25,9 +34,58 @@ pub struct Window {
     window: Arc<winit::window::Window>,
     clear_color: AtomicCell<Color>,
     pub(crate) initialized: (AtomicBool, AtomicBool),
+    /// The cursor grab mode requested by the game, restored after the window regains focus.
+    cursor_grab: AtomicCell<CursorGrabMode>,
+    cursor_visible: AtomicBool,
+    custom_cursor: Mutex<Option<CustomCursor>>,
+    /// The position of the cursor in pixels while it is inside the window.
+    cursor_position: AtomicCell<Option<Vec2>>,
+}
+
+/// A cursor image with a hotspot, the point of the image that clicks.
+///
+/// As winit 0.29 can not give the system cursor an image, the engine hides the system cursor while a custom one is set
+/// and draws the image over the scene at its position instead, so it moves with the frame rate of the game.
+#[derive(Clone, Debug)]
+pub struct CustomCursor {
+    texture: Texture,
+    hotspot: Vec2,
+    material: Material,
+}
+
+impl CustomCursor {
+    /// Makes a cursor from a texture, the hotspot being in pixels from the top left corner of the image.
+    ///
+    /// Texture arrays show their first layer.
+    pub fn new(texture: &Texture, hotspot: Vec2) -> Result<Self> {
+        Ok(Self {
+            texture: texture.clone(),
+            hotspot,
+            material: Material::new_default_textured_instance(texture)?,
+        })
+    }
+
+    /// Returns the image of the cursor.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the point of the image that clicks in pixels from its top left corner.
+    pub fn hotspot(&self) -> Vec2 {
+        self.hotspot
+    }
+
+    pub(crate) fn material(&self) -> &Material {
+        &self.material
+    }
 }
 
 impl Window {
+    /// Returns the winit window for creating surfaces.
+    pub(crate) fn handle(&self) -> &Arc<winit::window::Window> {
+        &self.window
+    }
+
     /// Requests the window to be redrawn.
     #[inline]
     pub fn request_redraw(&self) {
@@ -223,6 +281,31 @@ impl Window {
         self.window.set_window_icon(icon);
     }
 
+    /// Sets the window icon to the first layer of the texture.
+    ///
+    /// The pixels get taken from the copy of the texture kept in memory, so this works with textures of any format.
+    pub fn set_window_icon_from_texture(&self, texture: &Texture) -> Result<(), BadIcon> {
+        let (width, height) = texture.dimensions();
+        let pixels = width as usize * height as usize;
+        let data = texture.data();
+        let bytes_per_pixel = data.len() / (pixels * texture.layers() as usize).max(1);
+        let rgba: Vec<u8> = match bytes_per_pixel {
+            // R8 textures are grayscale.
+            1 => data[..pixels]
+                .iter()
+                .flat_map(|&value| [value, value, value, u8::MAX])
+                .collect(),
+            // RGBA16 textures store the high byte first.
+            8 => data[..pixels * 8]
+                .chunks_exact(2)
+                .map(|channel| channel[0])
+                .collect(),
+            _ => data[..pixels * 4].to_vec(),
+        };
+        self.set_window_icon(Some(Icon::from_rgba(rgba, width, height)?));
+        Ok(())
+    }
+
     /// Focuses the window.
     #[inline]
     pub fn focus(&self) {
@@ -242,22 +325,95 @@ impl Window {
     }
 
     /// Sets the cursor icon to be the given variant.
+    ///
+    /// The icon is hidden while a [custom cursor](Window::set_custom_cursor) is set.
     #[inline]
     pub fn set_cursor_icon(&self, cursor: CursorIcon) {
         self.window.set_cursor_icon(cursor);
     }
 
+    /// Replaces the cursor with an image while it is over the window, or goes back to the cursor icon with `None`.
+    pub fn set_custom_cursor(&self, cursor: Option<CustomCursor>) {
+        *self.custom_cursor.lock() = cursor;
+        self.window.set_cursor_visible(self.system_cursor_visible());
+        crate::draw::contents_changed();
+        self.window.request_redraw();
+    }
+
+    /// Returns the custom cursor in case there is one.
+    pub fn custom_cursor(&self) -> Option<CustomCursor> {
+        self.custom_cursor.lock().clone()
+    }
+
+    /// Returns the position of the cursor in pixels from the top left corner of the window while it is inside of it.
+    pub fn cursor_position(&self) -> Option<Vec2> {
+        self.cursor_position.load()
+    }
+
+    /// Tells the window where the cursor is, `None` being outside of the window.
+    ///
+    /// Gets called by the engine for every cursor event to draw the custom cursor at the right place.
+    #[doc(hidden)]
+    pub fn cursor_moved(&self, position: Option<Vec2>) {
+        self.cursor_position.store(position);
+        if self.custom_cursor.lock().is_some() {
+            crate::draw::contents_changed();
+            self.window.request_redraw();
+        }
+    }
+
+    /// Returns true if the system cursor should be shown, which is hidden behind a custom cursor.
+    fn system_cursor_visible(&self) -> bool {
+        self.cursor_visible() && self.custom_cursor.lock().is_none()
+    }
+
     /// Makes the window grab the cursor.
-    /// Some variants don't work on some platforms.
-    #[inline]
+    ///
+    /// Not every platform supports every mode, so `Locked` falls back to `Confined` and the other way around.
+    /// The grab gets restored when the window regains focus after switching to another window.
+    ///
+    /// For mouse look in first person games lock the cursor, hide it and read the raw mouse motion input events
+    /// instead of the cursor position.
     pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<(), ExternalError> {
-        self.window.set_cursor_grab(mode)
+        let fallback = match mode {
+            CursorGrabMode::Locked => CursorGrabMode::Confined,
+            CursorGrabMode::Confined => CursorGrabMode::Locked,
+            CursorGrabMode::None => CursorGrabMode::None,
+        };
+        self.window
+            .set_cursor_grab(mode)
+            .or_else(|_| self.window.set_cursor_grab(fallback))?;
+        self.cursor_grab.store(mode);
+        Ok(())
+    }
+
+    /// Returns the cursor grab mode last set.
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        self.cursor_grab.load()
     }
 
     /// Makes the cursor invisible mostly just within the confines of the window.
     #[inline]
     pub fn set_cursor_visible(&self, visible: bool) {
-        self.window.set_cursor_visible(visible)
+        self.cursor_visible.store(visible, Ordering::Release);
+        self.window.set_cursor_visible(self.system_cursor_visible());
+        crate::draw::contents_changed();
+    }
+
+    /// Returns whether the cursor is visible within the window.
+    pub fn cursor_visible(&self) -> bool {
+        self.cursor_visible.load(Ordering::Acquire)
+    }
+
+    /// Applies the cursor grab mode and visibility again, as some platforms release them when the window loses focus.
+    ///
+    /// Gets called by the engine every time the window gains focus.
+    pub fn restore_cursor(&self) {
+        let mode = self.cursor_grab();
+        if mode != CursorGrabMode::None {
+            let _ = self.set_cursor_grab(mode);
+        }
+        self.window.set_cursor_visible(self.system_cursor_visible());
     }
 
     /// Drags the window with the left mouse button until it's released.
@@ -294,11 +450,31 @@ impl Window {
     }
 
     /// Returns the current monitor where the window is inside right now if it can.
-    pub fn currect_monitor(&self) -> Option<Monitor> {
+    pub fn current_monitor(&self) -> Option<Monitor> {
         self.window
             .current_monitor()
             .map(|handle| Monitor { handle })
     }
+
+    /// Returns the current monitor where the window is inside right now if it can.
+    #[deprecated = "Use `current_monitor` instead."]
+    pub fn currect_monitor(&self) -> Option<Monitor> {
+        self.current_monitor()
+    }
+
+    /// Returns the monitor marked as primary by the system if there is one.
+    pub fn primary_monitor(&self) -> Option<Monitor> {
+        self.window
+            .primary_monitor()
+            .map(|handle| Monitor { handle })
+    }
+
+    /// Switches the display to the given video mode and shows the window fullscreen on its monitor.
+    ///
+    /// Unlike borderless fullscreen this changes the resolution and refresh rate of the monitor.
+    pub fn set_exclusive_fullscreen(&self, video_mode: VideoMode) {
+        self.set_fullscreen(Some(Fullscreen::Exclusive(video_mode)));
+    }
 }
 
 /// A builder describing the initial state of the window.
@@ -466,9 +642,12 @@ impl WindowBuilder {
     }
 }
 
+/// The ways a window can cover a whole monitor.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Fullscreen {
+    /// Takes over the monitor of the video mode, switching it to its resolution and refresh rate.
     Exclusive(VideoMode),
+    /// Covers the given monitor, or the current one on `None`, keeping its video mode.
     Borderless(Option<Monitor>),
 }
 
@@ -537,6 +716,33 @@ impl Monitor {
             .map(|video_mode| VideoMode { video_mode })
             .collect()
     }
+
+    /// Returns the video mode with the given resolution closest to the given refresh rate in millihertz.
+    ///
+    /// Picks the highest refresh rate on `None`.
+    pub fn video_mode(&self, size: Vec2, refresh_rate: Option<u32>) -> Option<VideoMode> {
+        self.video_modes()
+            .into_iter()
+            .filter(|mode| mode.size() == size)
+            .max_by_key(|mode| {
+                let rate = refresh_rate.map_or(mode.refresh_rate() as i64, |rate| {
+                    -(mode.refresh_rate() as i64 - rate as i64).abs()
+                });
+                (rate, mode.bit_depth())
+            })
+    }
+
+    /// Returns the video mode with the highest resolution, refresh rate and bit depth.
+    pub fn best_video_mode(&self) -> Option<VideoMode> {
+        self.video_modes().into_iter().max_by_key(|mode| {
+            let size = mode.video_mode.size();
+            (
+                size.width as u64 * size.height as u64,
+                mode.refresh_rate(),
+                mode.bit_depth(),
+            )
+        })
+    }
 }
 
 /// Exclusive fullscreen video modes for specific monitors.
@@ -556,6 +762,18 @@ impl VideoMode {
     pub fn refresh_rate(&self) -> u32 {
         self.video_mode.refresh_rate_millihertz()
     }
+
+    /// Returns the number of bits per pixel of this video mode.
+    pub fn bit_depth(&self) -> u16 {
+        self.video_mode.bit_depth()
+    }
+
+    /// Returns the monitor this video mode belongs to.
+    pub fn monitor(&self) -> Monitor {
+        Monitor {
+            handle: self.video_mode.monitor(),
+        }
+    }
 }
 
 impl From<WindowBuilder> for winit::window::WindowBuilder {
@@ -569,6 +787,10 @@ impl From<(Arc<winit::window::Window>, bool)> for Window {
             window: value.0,
             clear_color: AtomicCell::new(Color::BLACK),
             initialized: (AtomicBool::new(value.1), AtomicBool::new(false)),
+            cursor_grab: AtomicCell::new(CursorGrabMode::None),
+            cursor_visible: AtomicBool::new(true),
+            custom_cursor: Mutex::new(None),
+            cursor_position: AtomicCell::new(None),
         }
     }
 }
@@ -1,28 +1,35 @@
 //! Default labels given by the engine.
 
+mod sdf;
+
 use ab_glyph::FontArc;
 use glyph_brush::ab_glyph::PxScale;
 use glyph_brush::{
     ab_glyph, BrushAction, BrushError, DefaultSectionHasher, FontId, GlyphBrush, GlyphBrushBuilder,
-    HorizontalAlign, Layout, OwnedSection, OwnedText, VerticalAlign,
+    GlyphCruncher, HorizontalAlign, Layout, OwnedSection, OwnedText, SectionGlyph, VerticalAlign,
 };
 use image::{ImageBuffer, Luma};
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::{Arc, LazyLock};
 
 use anyhow::Result;
 
 use glam::{vec2, Vec2};
 use let_engine_core::{
-    objects::{scenes::Layer, Appearance, NewObject, Object, ObjectError, Transform},
+    objects::{
+        scenes::Layer, AntiAliasing, Appearance, Color, NewObject, Object, ObjectError,
+        ObjectHandle, Transform,
+    },
     resources::{
         data::{tvert, Data, Vertex},
         materials::{Material, MaterialSettingsBuilder, Shaders},
-        textures::{Format, Sampler, Texture, TextureSettings},
+        textures::{Filter, Format, Sampler, Texture, TextureSettings},
         Model, ModelData,
     },
     Direction,
 };
+use sdf::{SdfAtlas, SDF_RANGE, SDF_SIZE};
 
 pub static LABELIFIER: LazyLock<Mutex<Labelifier>> =
     LazyLock::new(|| Mutex::new(Labelifier::new().unwrap()));
@@ -108,7 +115,19 @@ pub struct Label<Object> {
     pub text: String,
     pub scale: Vec2,
     pub align: Direction,
+    /// Styled parts of the text. Replaces the plain text in case it is not empty.
+    pub spans: Vec<TextSpan>,
+    /// An outline drawn around every glyph.
+    pub outline: Option<Outline>,
+    /// A shadow drawn behind the text.
+    pub shadow: Option<Shadow>,
+    /// A glow around the text. Only drawn by SDF labels.
+    pub glow: Option<Outline>,
+    /// The way the glyphs get rendered.
+    pub rendering: TextRendering,
     section: OwnedSection<Extra>,
+    /// The colors of the spans, where color index 0 is the color of the appearance.
+    palette: Vec<Color>,
 }
 impl Label<NewObject> {
     /// Creates a new label with the given settings.
@@ -122,9 +141,50 @@ impl Label<NewObject> {
             text: create_info.text,
             scale: create_info.scale,
             align: create_info.align,
+            spans: vec![],
+            outline: None,
+            shadow: None,
+            glow: None,
+            rendering: TextRendering::default(),
             section: OwnedSection::default(),
+            palette: vec![],
         }
     }
+
+    /// Sets the styled spans of the text, replacing the plain text, and returns the label.
+    #[inline]
+    pub fn spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Sets the outline of the text and returns the label.
+    #[inline]
+    pub fn outline(mut self, outline: Option<Outline>) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    /// Sets the shadow of the text and returns the label.
+    #[inline]
+    pub fn shadow(mut self, shadow: Option<Shadow>) -> Self {
+        self.shadow = shadow;
+        self
+    }
+
+    /// Sets the glow of the text and returns the label.
+    #[inline]
+    pub fn glow(mut self, glow: Option<Outline>) -> Self {
+        self.glow = glow;
+        self
+    }
+
+    /// Sets the way the glyphs get rendered and returns the label.
+    #[inline]
+    pub fn rendering(mut self, rendering: TextRendering) -> Self {
+        self.rendering = rendering;
+        self
+    }
     pub fn init(mut self, layer: &Arc<Layer>) -> Result<Label<Object>> {
         let mut labelifier = LABELIFIER.lock();
         self.update_section(
@@ -138,7 +198,13 @@ impl Label<NewObject> {
             text: self.text,
             scale: self.scale,
             align: self.align,
+            spans: self.spans,
+            outline: self.outline,
+            shadow: self.shadow,
+            glow: self.glow,
+            rendering: self.rendering,
             section: self.section,
+            palette: self.palette,
         };
         labelifier.queue(label.clone());
         Ok(label)
@@ -156,7 +222,13 @@ impl Label<NewObject> {
             text: self.text,
             scale: self.scale,
             align: self.align,
+            spans: self.spans,
+            outline: self.outline,
+            shadow: self.shadow,
+            glow: self.glow,
+            rendering: self.rendering,
             section: self.section,
+            palette: self.palette,
         };
         labelifier.queue(label.clone());
         Ok(label)
@@ -178,7 +250,13 @@ impl Label<NewObject> {
             text: self.text,
             scale: self.scale,
             align: self.align,
+            spans: self.spans,
+            outline: self.outline,
+            shadow: self.shadow,
+            glow: self.glow,
+            rendering: self.rendering,
             section: self.section,
+            palette: self.palette,
         };
         labelifier.queue(label.clone());
         Ok(label)
@@ -189,14 +267,42 @@ impl<T> Label<T> {
     fn update_section(&mut self, id: usize, size: Vec2) {
         let dimensions: (f32, f32) = ((1000.0 * size[0]), (1000.0 * size[1]));
 
-        let text = OwnedText {
-            text: self.text.clone(),
-            scale: PxScale {
-                x: self.scale.x,
-                y: self.scale.y,
-            },
-            font_id: self.font.id(),
-            extra: Extra { id },
+        self.palette.clear();
+        let text = if self.spans.is_empty() {
+            vec![OwnedText {
+                text: self.text.clone(),
+                scale: PxScale {
+                    x: self.scale.x,
+                    y: self.scale.y,
+                },
+                font_id: self.font.id(),
+                extra: Extra { id, color: 0 },
+            }]
+        } else {
+            let mut text = Vec::with_capacity(self.spans.len());
+            for span in self.spans.iter() {
+                let color = match span.color {
+                    Some(color) => match self.palette.iter().position(|c| *c == color) {
+                        Some(index) => index + 1,
+                        None => {
+                            self.palette.push(color);
+                            self.palette.len()
+                        }
+                    },
+                    None => 0,
+                };
+                let scale = span.scale.unwrap_or(self.scale);
+                text.push(OwnedText {
+                    text: span.text.clone(),
+                    scale: PxScale {
+                        x: scale.x,
+                        y: scale.y,
+                    },
+                    font_id: span.font.as_ref().unwrap_or(&self.font).id(),
+                    extra: Extra { id, color },
+                });
+            }
+            text
         };
 
         let (h, v): (HorizontalAlign, VerticalAlign) = glyph_direction(self.align);
@@ -215,7 +321,40 @@ impl<T> Label<T> {
             .with_bounds(dimensions)
             .with_layout(Layout::default().h_align(h).v_align(v))
             .with_screen_position((x, y))
-            .add_text(text);
+            .with_text(text);
+    }
+
+    /// Returns true if this label needs more than one color to be drawn.
+    ///
+    /// Outlines of SDF labels get drawn by the shader.
+    fn is_rich(&self) -> bool {
+        !self.palette.is_empty()
+            || self.shadow.is_some()
+            || (self.outline.is_some() && self.rendering == TextRendering::Bitmap)
+    }
+
+    /// Returns the anti aliasing drawing the glyphs of this label.
+    ///
+    /// The outline and glow widths of SDF labels are relative to the scale of the label.
+    fn anti_aliasing(&self) -> AntiAliasing {
+        if self.rendering == TextRendering::Bitmap {
+            return AntiAliasing::None;
+        }
+        // Pixels of the field per pixel of the text.
+        let field = SDF_SIZE / self.scale.y.max(f32::EPSILON);
+        let none = Outline {
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.0),
+            width: 0.0,
+        };
+        let outline = self.outline.unwrap_or(none);
+        let glow = self.glow.unwrap_or(none);
+        AntiAliasing::Sdf {
+            distance_range: SDF_RANGE,
+            outline_width: outline.width * field,
+            outline_color: outline.color,
+            glow_width: glow.width * field,
+            glow_color: glow.color,
+        }
     }
 }
 impl Label<Object> {
@@ -230,6 +369,12 @@ impl Label<Object> {
         self.sync();
     }
 
+    /// Changes the styled spans of the label and immediately syncs it afterwards.
+    pub fn update_spans(&mut self, spans: Vec<TextSpan>) {
+        self.spans = spans;
+        self.sync();
+    }
+
     /// Syncs the public layer side label to be the same as the current.
     pub fn sync(&mut self) {
         let mut labelifier = LABELIFIER.lock();
@@ -256,14 +401,254 @@ impl TextVertex {
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 struct Extra {
     id: usize,
+    /// Index into the palette of the label.
+    color: usize,
 }
 
 impl std::hash::Hash for Extra {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         state.write_usize(self.id);
+        state.write_usize(self.color);
     }
 }
 
+/// A part of a label text with its own style.
+///
+/// Unset styles fall back to the font, scale and appearance color of the label.
+/// Mixing bold and italic text works by giving the span the respective font.
+#[derive(Clone, Debug, Default)]
+pub struct TextSpan {
+    /// The text of this span.
+    pub text: String,
+    /// The color of the text.
+    pub color: Option<Color>,
+    /// The font of the text.
+    pub font: Option<Font>,
+    /// The scale of the text.
+    pub scale: Option<Vec2>,
+}
+
+impl TextSpan {
+    /// Creates a new span with the given text using the style of the label.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+    /// Sets the color of the span and returns it back.
+    #[inline]
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+    /// Sets the font of the span and returns it back.
+    #[inline]
+    pub fn font(mut self, font: &Font) -> Self {
+        self.font = Some(font.clone());
+        self
+    }
+    /// Sets the scale of the span and returns it back.
+    #[inline]
+    pub fn scale(mut self, scale: impl Into<Vec2>) -> Self {
+        self.scale = Some(scale.into());
+        self
+    }
+}
+
+/// The way the glyphs of a label get rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TextRendering {
+    /// Glyphs rasterized at the scale of the text.
+    ///
+    /// Sharpest when the label is drawn at its original size, but blurry when zooming in.
+    #[default]
+    Bitmap,
+    /// Glyphs stored as signed distance fields, staying sharp at any scale and zoom.
+    ///
+    /// Outlines and glows get drawn by the shader instead of extra copies of the glyphs.
+    /// Outlines and glows wider than a few pixels of the text scale get cut off.
+    Sdf,
+}
+
+/// An outline around every glyph of a label.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outline {
+    /// The color of the outline.
+    pub color: Color,
+    /// The width of the outline in pixels of the text scale.
+    pub width: f32,
+}
+
+/// A shadow drawn behind the text of a label.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+    /// The color of the shadow.
+    pub color: Color,
+    /// The offset of the shadow to the text in pixels of the text scale.
+    pub offset: Vec2,
+}
+
+/// The regular, bold and italic fonts of a typeface for markup text.
+///
+/// Missing styles fall back to the regular font.
+#[derive(Clone, Debug)]
+pub struct FontFamily {
+    pub regular: Font,
+    pub bold: Option<Font>,
+    pub italic: Option<Font>,
+    pub bold_italic: Option<Font>,
+}
+
+impl FontFamily {
+    /// Creates a family using the given font for every style.
+    pub fn new(regular: &Font) -> Self {
+        Self {
+            regular: regular.clone(),
+            bold: None,
+            italic: None,
+            bold_italic: None,
+        }
+    }
+    /// Sets the bold font and returns the family back.
+    #[inline]
+    pub fn bold(mut self, font: &Font) -> Self {
+        self.bold = Some(font.clone());
+        self
+    }
+    /// Sets the italic font and returns the family back.
+    #[inline]
+    pub fn italic(mut self, font: &Font) -> Self {
+        self.italic = Some(font.clone());
+        self
+    }
+    /// Sets the bold italic font and returns the family back.
+    #[inline]
+    pub fn bold_italic(mut self, font: &Font) -> Self {
+        self.bold_italic = Some(font.clone());
+        self
+    }
+
+    fn get(&self, bold: bool, italic: bool) -> &Font {
+        let font = match (bold, italic) {
+            (true, true) => self.bold_italic.as_ref().or(self.bold.as_ref()),
+            (true, false) => self.bold.as_ref(),
+            (false, true) => self.italic.as_ref(),
+            (false, false) => None,
+        };
+        font.unwrap_or(&self.regular)
+    }
+}
+
+/// Parses text with markup tags into spans.
+///
+/// Supported tags are `[b]`, `[i]` and `[color=#rrggbb]` or `[color=#rrggbbaa]`, closed with
+/// `[/b]`, `[/i]` and `[/color]`. `[[` writes a single bracket. Unknown tags are kept as text.
+///
+/// # Example
+/// ```ignore
+/// let spans = parse_markup("[b]Health:[/b] [color=#ff4040]12[/color]", &family);
+/// ```
+pub fn parse_markup(markup: &str, family: &FontFamily) -> Vec<TextSpan> {
+    let mut spans = vec![];
+    let mut text = String::new();
+    let mut bold = 0usize;
+    let mut italic = 0usize;
+    let mut colors: Vec<Color> = vec![];
+
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(escaped) = rest.strip_prefix('[') {
+            text.push('[');
+            rest = escaped;
+            continue;
+        }
+        let Some(end) = rest.find(']') else {
+            text.push('[');
+            continue;
+        };
+        let tag = &rest[..end];
+
+        let known = match tag {
+            "b" | "/b" | "i" | "/i" | "/color" => true,
+            tag => tag
+                .strip_prefix("color=")
+                .and_then(parse_hex_color)
+                .is_some(),
+        };
+        if !known {
+            text.push('[');
+            continue;
+        }
+
+        if !text.is_empty() {
+            spans.push(TextSpan {
+                text: std::mem::take(&mut text),
+                color: colors.last().copied(),
+                font: Some(family.get(bold > 0, italic > 0).clone()),
+                scale: None,
+            });
+        }
+        match tag {
+            "b" => bold += 1,
+            "/b" => bold = bold.saturating_sub(1),
+            "i" => italic += 1,
+            "/i" => italic = italic.saturating_sub(1),
+            "/color" => {
+                colors.pop();
+            }
+            tag => colors.extend(tag.strip_prefix("color=").and_then(parse_hex_color)),
+        }
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        spans.push(TextSpan {
+            text,
+            color: colors.last().copied(),
+            font: Some(family.get(bold > 0, italic > 0).clone()),
+            scale: None,
+        });
+    }
+    spans
+}
+
+/// Parses a color in the `#rrggbb` or `#rrggbbaa` format.
+fn parse_hex_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|c| c as f32 / 255.0)
+    };
+    Some(Color::from_rgba(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+        if hex.len() == 8 { channel(6)? } else { 1.0 },
+    ))
+}
+
+/// Statistics of the glyph cache texture shared by all labels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GlyphCacheStats {
+    /// The current dimensions of the cache texture in pixels.
+    pub dimensions: (u32, u32),
+    /// The number of glyphs rasterized and written to the cache.
+    pub glyphs_uploaded: u64,
+    /// The number of times the cache texture grew.
+    pub resizes: u64,
+    /// The number of times cached glyphs got dropped to keep the texture below the maximum size.
+    pub evictions: u64,
+    /// The current dimensions of the distance field atlas of SDF labels in pixels.
+    pub sdf_dimensions: (u32, u32),
+}
+
 /// A label maker holding
 pub struct Labelifier {
     /// the default material,
@@ -274,8 +659,20 @@ pub struct Labelifier {
     cache_pixel_buffer: ImageBuffer<Luma<u8>, Vec<u8>>,
     /// tasks to be executed on next update,
     queued: Vec<DrawTask>,
-    /// the amount of tasks
+    /// the amount of tasks,
     tasks: usize,
+    /// the child objects drawing the colors, outlines and shadows of rich labels,
+    rich_objects: HashMap<ObjectHandle, Vec<Object>>,
+    /// the size the glyph cache should not grow beyond,
+    max_cache_size: u32,
+    /// statistics of the glyph cache,
+    stats: GlyphCacheStats,
+    /// the distance field atlas of SDF labels,
+    sdf: SdfAtlas,
+    /// the material showing the distance field atlas,
+    sdf_material: Option<Material>,
+    /// SDF labels to be laid out on next update
+    sdf_queued: Vec<Label<Object>>,
     /// and the boolean if it should update.
     ready: bool,
 }
@@ -299,6 +696,7 @@ impl Labelifier {
         let settings = TextureSettings {
             srgb: false,
             sampler: Sampler::default(),
+            ..Default::default()
         };
 
         // Make the cache a texture.
@@ -308,6 +706,7 @@ impl Labelifier {
             Format::R8,
             1,
             settings,
+            Some("label glyph cache"),
         )?;
 
         let text_shaders = unsafe {
@@ -318,7 +717,9 @@ impl Labelifier {
             )?
         };
 
-        let material_settings = MaterialSettingsBuilder::default().build()?;
+        let material_settings = MaterialSettingsBuilder::default()
+            .name("label material")
+            .build()?;
 
         let material = Material::new_with_shaders(
             material_settings,
@@ -335,9 +736,44 @@ impl Labelifier {
             queued: vec![],
             ready: false,
             tasks: 0,
+            rich_objects: HashMap::new(),
+            max_cache_size: 4096,
+            stats: GlyphCacheStats {
+                dimensions,
+                ..Default::default()
+            },
+            sdf: SdfAtlas::new(),
+            sdf_material: None,
+            sdf_queued: vec![],
         })
     }
 
+    /// Returns statistics of the glyph cache.
+    pub fn cache_stats(&self) -> GlyphCacheStats {
+        GlyphCacheStats {
+            dimensions: self.glyph_brush.texture_dimensions(),
+            sdf_dimensions: self.sdf.buffer.dimensions(),
+            ..self.stats
+        }
+    }
+
+    /// Returns the size in pixels the glyph cache texture should not grow beyond.
+    pub fn max_cache_size(&self) -> u32 {
+        self.max_cache_size
+    }
+
+    /// Sets the size in pixels the glyph cache texture should not grow beyond.
+    ///
+    /// Instead of growing beyond this size the glyphs of text not drawn this update get dropped from the cache first.
+    /// The cache still grows in case the text of a single update does not fit.
+    ///
+    /// ## Default configuration
+    ///
+    /// 4096
+    pub fn set_max_cache_size(&mut self, size: u32) {
+        self.max_cache_size = size;
+    }
+
     pub fn clear_cache(&mut self) {
         self.glyph_brush
             .to_builder()
@@ -345,6 +781,7 @@ impl Labelifier {
             .rebuild(&mut self.glyph_brush);
         let dims = self.glyph_brush.texture_dimensions();
         self.cache_pixel_buffer = ImageBuffer::from_pixel(dims.0, dims.1, image::Luma([0u8]));
+        self.sdf = SdfAtlas::new();
     }
 
     /// Increments the tasks number by one and returns the last id.
@@ -361,9 +798,11 @@ impl Labelifier {
 
         for text_vertex in text_vertices {
             let task = &mut self.queued[text_vertex.extra.id];
-            task.indices
-                .append(&mut text_vertex.indices(task.vertices.len() as u32));
-            task.vertices.extend_from_slice(&text_vertex.rect);
+            let color = text_vertex.extra.color;
+            if task.groups.len() <= color {
+                task.groups.resize_with(color + 1, Group::default);
+            }
+            task.groups[color].push(&text_vertex);
         }
 
         // Creates a new texture to be inserted into every syncing label.
@@ -373,6 +812,7 @@ impl Labelifier {
         let settings = TextureSettings {
             srgb: false,
             sampler: Sampler::default(),
+            ..Default::default()
         };
 
         // Make the cache a texture.
@@ -382,31 +822,188 @@ impl Labelifier {
             Format::R8,
             1,
             settings,
+            Some("label glyph cache"),
         )?));
 
         let queued = std::mem::take(&mut self.queued);
-
+        let material = self.material.clone();
         for task in queued.into_iter() {
-            let mut label = task.label.clone();
+            self.finish_task(task, &material)?;
+        }
+        Ok(())
+    }
 
-            // Early return if this object was removed after the update call.
-            if !label.object.is_initialized() {
-                continue;
-            }
+    /// Gives the label of the task its new model and material.
+    fn finish_task(&mut self, mut task: DrawTask, material: &Material) -> Result<()> {
+        let mut label = task.label.clone();
 
-            if !task.vertices.is_empty() {
-                let model = ModelData::new(task.into_data())?;
-                label
-                    .object
-                    .appearance
-                    .set_model(Some(Model::Custom(model)))
-                    .unwrap();
-            };
+        // Early return if this object was removed after the update call.
+        if !label.object.is_initialized() {
+            return Ok(());
+        }
+
+        let anti_aliasing = label.anti_aliasing();
+        label.object.appearance.set_anti_aliasing(anti_aliasing);
+
+        let key = label.object.handle();
+        if label.is_rich() {
+            let layers = task.into_layers();
+            label.object.appearance.set_model(None).unwrap();
+            label.object.sync()?;
+            return self.sync_rich_objects(key, &label, layers, material);
+        }
+        for object in self.rich_objects.remove(&key).into_iter().flatten() {
+            object.remove()?;
+        }
+
+        let group = if task.groups.is_empty() {
+            Group::default()
+        } else {
+            task.groups.swap_remove(0)
+        };
+        if !group.vertices.is_empty() {
+            let model = ModelData::new(group.into_data())?;
             label
                 .object
                 .appearance
-                .set_material(Some(self.material.clone()));
-            label.object.sync()?;
+                .set_model(Some(Model::Custom(model)))
+                .unwrap();
+        };
+        label.object.appearance.set_material(Some(material.clone()));
+        label.object.sync()?;
+        Ok(())
+    }
+
+    /// Lays out the queued SDF labels and builds their glyphs from the distance field atlas.
+    fn update_sdf_labels(&mut self) -> Result<()> {
+        let labels = std::mem::take(&mut self.sdf_queued);
+        if labels.is_empty() {
+            return Ok(());
+        }
+
+        let mut tasks = Vec::with_capacity(labels.len());
+        let mut needed = vec![];
+        for label in labels {
+            let glyphs: Vec<SectionGlyph> = self
+                .glyph_brush
+                .glyphs(label.section.to_borrowed())
+                .cloned()
+                .collect();
+            needed.extend(glyphs.iter().map(|glyph| (glyph.font_id, glyph.glyph.id)));
+            tasks.push((label, glyphs));
+        }
+
+        if self
+            .sdf
+            .insert_all(&needed, self.glyph_brush.fonts(), self.max_cache_size)?
+        {
+            self.stats.evictions += 1;
+        }
+
+        if self.sdf.dirty || self.sdf_material.is_none() {
+            let settings = TextureSettings {
+                srgb: false,
+                sampler: Sampler {
+                    mag_filter: Filter::Linear,
+                    min_filter: Filter::Linear,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let texture = Texture::from_raw(
+                self.sdf.buffer.as_raw(),
+                self.sdf.buffer.dimensions(),
+                Format::R8,
+                1,
+                settings,
+                Some("label sdf glyph atlas"),
+            )?;
+            self.sdf_material = Some(Material::new_default_textured(&texture)?);
+            self.sdf.dirty = false;
+        }
+        let Some(material) = self.sdf_material.clone() else {
+            return Ok(());
+        };
+
+        let (width, height) = self.sdf.buffer.dimensions();
+        let atlas = vec2(width as f32, height as f32);
+        for (label, glyphs) in tasks {
+            let bounds = vec2(label.section.bounds.0, label.section.bounds.1);
+            // Pixels to the -1.0 to 1.0 space of the model and texture.
+            let position = |pixel: Vec2| (pixel / bounds - 0.5) * 2.0;
+            let tex_position = |pixel: Vec2| pixel / atlas * 2.0 - 1.0;
+
+            let mut groups: Vec<Group> = vec![];
+            for glyph in glyphs {
+                let Some(sdf) = self.sdf.get(glyph.font_id, glyph.glyph.id) else {
+                    continue;
+                };
+                let scale = vec2(glyph.glyph.scale.x, glyph.glyph.scale.y) / SDF_SIZE;
+                let size = vec2(sdf.size[0] as f32, sdf.size[1] as f32);
+                let origin = vec2(glyph.glyph.position.x, glyph.glyph.position.y);
+
+                let min = position(origin + sdf.offset * scale);
+                let max = position(origin + (sdf.offset + size) * scale);
+                let tex_min = tex_position(vec2(sdf.min[0] as f32, sdf.min[1] as f32));
+                let tex_max = tex_position(vec2(sdf.min[0] as f32, sdf.min[1] as f32) + size);
+
+                let color = label.section.text[glyph.section_index].extra.color;
+                if groups.len() <= color {
+                    groups.resize_with(color + 1, Group::default);
+                }
+                groups[color].push(&TextVertex {
+                    rect: [
+                        tvert(min.x, min.y, tex_min.x, tex_min.y),
+                        tvert(min.x, max.y, tex_min.x, tex_max.y),
+                        tvert(max.x, min.y, tex_max.x, tex_min.y),
+                        tvert(max.x, max.y, tex_max.x, tex_max.y),
+                    ],
+                    extra: Extra { id: 0, color },
+                });
+            }
+            self.finish_task(DrawTask { label, groups }, &material)?;
+        }
+        Ok(())
+    }
+
+    /// Makes the child objects of the rich label draw the given layers in order.
+    fn sync_rich_objects(
+        &mut self,
+        key: ObjectHandle,
+        label: &Label<Object>,
+        layers: Vec<(Option<Color>, Group)>,
+        material: &Material,
+    ) -> Result<()> {
+        let objects = self.rich_objects.entry(key).or_default();
+        while objects.len() > layers.len() {
+            if let Some(object) = objects.pop() {
+                object.remove()?;
+            }
+        }
+
+        for (i, (color, group)) in layers.into_iter().enumerate() {
+            let mut appearance = Appearance::new()
+                .transform(*label.object.appearance.get_transform())
+                .visible(*label.object.appearance.get_visible())
+                .color(color.unwrap_or(*label.object.appearance.get_color()))
+                .anti_aliasing(*label.object.appearance.get_anti_aliasing())
+                .material(Some(material.clone()));
+            if !group.vertices.is_empty() {
+                appearance
+                    .set_model(Some(Model::Custom(ModelData::new(group.into_data())?)))
+                    .unwrap();
+            }
+            match objects.get_mut(i) {
+                Some(object) => {
+                    object.appearance = appearance;
+                    object.sync()?;
+                }
+                None => {
+                    let mut object = NewObject::new();
+                    object.appearance = appearance;
+                    objects.push(object.init_with_parent(&label.object)?);
+                }
+            }
         }
         Ok(())
     }
@@ -418,10 +1015,22 @@ impl Labelifier {
             return Ok(());
         }
 
-        // let dimensions = self.glyph_brush.texture_dimensions();
+        // Forget the objects of removed labels.
+        self.rich_objects
+            .retain(|_, objects| objects.iter().all(Object::is_initialized));
+
+        self.update_sdf_labels()?;
+        if self.queued.is_empty() {
+            self.tasks = 0;
+            self.ready = false;
+            return Ok(());
+        }
+
+        let mut evicted = false;
         let brush_action: glyph_brush::BrushAction<TextVertex> = loop {
             let result = self.glyph_brush.process_queued(
                 |rect, src_data| {
+                    self.stats.glyphs_uploaded += 1;
                     let width = (rect.max[0] - rect.min[0]) as usize;
                     let height = (rect.max[1] - rect.min[1]) as usize;
 
@@ -444,6 +1053,19 @@ impl Labelifier {
                     break brush_action;
                 }
                 Err(BrushError::TextureTooSmall { suggested }) => {
+                    // Drop the glyphs of text not drawn anymore before growing beyond the limit.
+                    if !evicted
+                        && (suggested.0 > self.max_cache_size || suggested.1 > self.max_cache_size)
+                    {
+                        evicted = true;
+                        self.stats.evictions += 1;
+                        let (width, height) = self.glyph_brush.texture_dimensions();
+                        self.glyph_brush.resize_texture(width, height);
+                        self.cache_pixel_buffer =
+                            ImageBuffer::from_pixel(width, height, Luma([0u8]));
+                        continue;
+                    }
+                    self.stats.resizes += 1;
                     self.glyph_brush.resize_texture(suggested.0, suggested.1);
                     let mut new_buffer =
                         ImageBuffer::from_pixel(suggested.0, suggested.1, Luma([0u8]));
@@ -465,15 +1087,24 @@ impl Labelifier {
         self.ready = false;
         Ok(())
     }
-    pub fn queue(&mut self, label: Label<Object>) {
+    pub fn queue(&mut self, mut label: Label<Object>) {
         self.ready = true;
 
+        if label.rendering == TextRendering::Sdf {
+            self.sdf_queued.push(label);
+            return;
+        }
+
+        // The id of the text is the index of its task.
+        let id = self.queued.len();
+        for text in label.section.text.iter_mut() {
+            text.extra.id = id;
+        }
         self.glyph_brush.queue(label.section.to_borrowed());
 
         self.queued.push(DrawTask {
             label,
-            vertices: vec![],
-            indices: vec![],
+            groups: vec![],
         });
     }
 }
@@ -510,12 +1141,82 @@ fn to_vertex(
 
 struct DrawTask {
     pub label: Label<Object>,
-    pub vertices: Vec<Vertex>,
-    pub indices: Vec<u32>,
+    /// The glyphs of each color index of the label.
+    pub groups: Vec<Group>,
 }
 
 impl DrawTask {
-    pub fn into_data(self) -> Data {
+    /// Returns the glyphs of a rich label in draw order with their color,
+    /// starting with the shadow and outline followed by the colored text.
+    ///
+    /// A color of `None` stands for the color of the appearance.
+    pub fn into_layers(self) -> Vec<(Option<Color>, Group)> {
+        let label = self.label;
+        let bounds = label.section.bounds;
+        // Pixels to the -1.0 to 1.0 space of the model.
+        let pixel = vec2(2.0 / bounds.0, 2.0 / bounds.1);
+
+        let mut glyphs = Group::default();
+        for group in self.groups.iter() {
+            glyphs.extend(group, Vec2::ZERO);
+        }
+
+        let mut layers = vec![];
+        if let Some(shadow) = label.shadow {
+            let mut group = Group::default();
+            group.extend(&glyphs, shadow.offset * pixel);
+            layers.push((Some(shadow.color), group));
+        }
+        if let Some(outline) = label
+            .outline
+            .filter(|_| label.rendering == TextRendering::Bitmap)
+        {
+            let mut group = Group::default();
+            for i in 0..8 {
+                let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                let offset = Vec2::from_angle(angle) * outline.width * pixel;
+                group.extend(&glyphs, offset);
+            }
+            layers.push((Some(outline.color), group));
+        }
+        for (color, group) in self.groups.into_iter().enumerate() {
+            if group.vertices.is_empty() {
+                continue;
+            }
+            let color = color.checked_sub(1).map(|index| label.palette[index]);
+            layers.push((color, group));
+        }
+        layers
+    }
+}
+
+/// Vertices and indices of glyphs drawn with the same color.
+#[derive(Default)]
+struct Group {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl Group {
+    fn push(&mut self, text_vertex: &TextVertex) {
+        self.indices
+            .append(&mut text_vertex.indices(self.vertices.len() as u32));
+        self.vertices.extend_from_slice(&text_vertex.rect);
+    }
+
+    /// Appends the glyphs of the other group moved by the offset.
+    fn extend(&mut self, other: &Group, offset: Vec2) {
+        let start = self.vertices.len() as u32;
+        self.indices
+            .extend(other.indices.iter().map(|index| index + start));
+        self.vertices
+            .extend(other.vertices.iter().map(|vertex| Vertex {
+                position: vertex.position + offset,
+                ..*vertex
+            }));
+    }
+
+    fn into_data(self) -> Data {
         Data::Dynamic {
             vertices: self.vertices,
             indices: self.indices,
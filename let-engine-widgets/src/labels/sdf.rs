@@ -0,0 +1,214 @@
+//! Glyph atlas of signed distance fields for labels that stay sharp at any scale.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use glam::{vec2, Vec2};
+use glyph_brush::{
+    ab_glyph::{Font as _, FontArc, GlyphId},
+    FontId,
+};
+use image::{ImageBuffer, Luma};
+
+/// The pixel size glyphs get rasterized at before turning them into distance fields.
+pub(super) const SDF_SIZE: f32 = 48.0;
+/// The distance in pixels of the field covered by the values from 0 to 255.
+pub(super) const SDF_RANGE: f32 = 8.0;
+/// Empty pixels around each glyph, so the field has room to fade out.
+const PADDING: u32 = SDF_RANGE as u32 / 2;
+/// Empty pixels between glyphs in the atlas, so linear filtering does not bleed.
+const SPACING: u32 = 1;
+
+/// A glyph in the atlas.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SdfGlyph {
+    /// The top left corner of the glyph in the atlas in pixels.
+    pub min: [u32; 2],
+    /// The size of the glyph including padding in pixels.
+    pub size: [u32; 2],
+    /// The position of the top left corner relative to the origin of the glyph at [`SDF_SIZE`].
+    pub offset: Vec2,
+}
+
+/// Distance fields of every glyph used by SDF labels packed in rows.
+pub(super) struct SdfAtlas {
+    pub buffer: ImageBuffer<Luma<u8>, Vec<u8>>,
+    /// Glyphs without an outline, like spaces, are stored as `None`.
+    glyphs: HashMap<(FontId, GlyphId), Option<SdfGlyph>>,
+    cursor: [u32; 2],
+    row_height: u32,
+    /// True if the buffer changed since the last texture got made.
+    pub dirty: bool,
+}
+
+impl SdfAtlas {
+    pub fn new() -> Self {
+        Self {
+            buffer: ImageBuffer::from_pixel(512, 512, Luma([0u8])),
+            glyphs: HashMap::new(),
+            cursor: [0; 2],
+            row_height: 0,
+            dirty: true,
+        }
+    }
+
+    /// Removes every glyph from the atlas.
+    pub fn clear(&mut self) {
+        let (width, height) = self.buffer.dimensions();
+        self.buffer = ImageBuffer::from_pixel(width, height, Luma([0u8]));
+        self.glyphs.clear();
+        self.cursor = [0; 2];
+        self.row_height = 0;
+        self.dirty = true;
+    }
+
+    /// Returns the glyph in case it is in the atlas and has an outline.
+    pub fn get(&self, font: FontId, glyph: GlyphId) -> Option<SdfGlyph> {
+        self.glyphs.get(&(font, glyph)).copied().flatten()
+    }
+
+    /// Makes sure all given glyphs are in the atlas.
+    ///
+    /// The atlas grows up to the maximum size. When it is full all glyphs not in the list get dropped.
+    /// Returns true if glyphs got dropped, or an error in case the glyphs do not fit even then.
+    pub fn insert_all(
+        &mut self,
+        glyphs: &[(FontId, GlyphId)],
+        fonts: &[FontArc],
+        max_size: u32,
+    ) -> Result<bool> {
+        let mut evicted = false;
+        let mut i = 0;
+        while i < glyphs.len() {
+            let (font, glyph) = glyphs[i];
+            if self.glyphs.contains_key(&(font, glyph)) {
+                i += 1;
+                continue;
+            }
+            let Some((field, width, height, offset)) = rasterize(&fonts[font.0], glyph) else {
+                self.glyphs.insert((font, glyph), None);
+                i += 1;
+                continue;
+            };
+            if width > self.buffer.width() || height > max_size.max(self.buffer.height()) {
+                bail!(
+                    "A glyph of {width}x{height} pixels does not fit into the SDF atlas. Use a smaller font size."
+                );
+            }
+            match self.allocate(width, height) {
+                Some(min) => {
+                    for y in 0..height {
+                        for x in 0..width {
+                            let value = field[(y * width + x) as usize];
+                            self.buffer.put_pixel(min[0] + x, min[1] + y, Luma([value]));
+                        }
+                    }
+                    self.glyphs.insert(
+                        (font, glyph),
+                        Some(SdfGlyph {
+                            min,
+                            size: [width, height],
+                            offset,
+                        }),
+                    );
+                    self.dirty = true;
+                    i += 1;
+                }
+                None if self.buffer.height() * 2 <= max_size => self.grow(),
+                None if evicted => {
+                    bail!("The glyphs of the SDF labels do not fit into an atlas of the maximum cache size.")
+                }
+                None => {
+                    // Start over with only the glyphs needed right now.
+                    evicted = true;
+                    self.clear();
+                    i = 0;
+                }
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Returns the position of a free area of the given size.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+        let (atlas_width, atlas_height) = self.buffer.dimensions();
+        if width > atlas_width {
+            return None;
+        }
+        if self.cursor[0] + width > atlas_width {
+            self.cursor = [0, self.cursor[1] + self.row_height + SPACING];
+            self.row_height = 0;
+        }
+        if self.cursor[1] + height > atlas_height {
+            return None;
+        }
+        let min = self.cursor;
+        self.cursor[0] += width + SPACING;
+        self.row_height = self.row_height.max(height);
+        Some(min)
+    }
+
+    /// Doubles the height of the atlas keeping its content.
+    fn grow(&mut self) {
+        let (width, height) = self.buffer.dimensions();
+        let mut buffer = ImageBuffer::from_pixel(width, height * 2, Luma([0u8]));
+        let raw: &mut [u8] = &mut buffer;
+        raw[..self.buffer.as_raw().len()].copy_from_slice(self.buffer.as_raw());
+        self.buffer = buffer;
+        self.dirty = true;
+    }
+}
+
+/// Rasterizes the glyph at [`SDF_SIZE`] and returns its distance field, size and offset to the origin.
+fn rasterize(font: &FontArc, glyph: GlyphId) -> Option<(Vec<u8>, u32, u32, Vec2)> {
+    let outlined = font.outline_glyph(glyph.with_scale(SDF_SIZE))?;
+    let bounds = outlined.px_bounds();
+    let width = bounds.width().ceil() as u32 + PADDING * 2;
+    let height = bounds.height().ceil() as u32 + PADDING * 2;
+
+    let mut coverage = vec![0.0; (width * height) as usize];
+    outlined.draw(|x, y, c| {
+        coverage[((y + PADDING) * width + x + PADDING) as usize] = c;
+    });
+
+    let offset = vec2(bounds.min.x, bounds.min.y) - Vec2::splat(PADDING as f32);
+    Some((
+        distance_field(&coverage, width, height),
+        width,
+        height,
+        offset,
+    ))
+}
+
+/// Turns the coverage of a glyph into a signed distance field by searching the closest pixel on the other side of the edge.
+fn distance_field(coverage: &[f32], width: u32, height: u32) -> Vec<u8> {
+    let radius = PADDING as i32 + 1;
+    let inside = |x: i32, y: i32| {
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && coverage[(y as u32 * width + x as u32) as usize] >= 0.5
+    };
+
+    let mut field = Vec::with_capacity(coverage.len());
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let center = inside(x, y);
+            let mut closest = radius as f32;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    if inside(x + dx, y + dy) != center {
+                        closest = closest.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+            // The edge lies between the two pixels.
+            let distance = closest - 0.5;
+            let signed = if center { distance } else { -distance };
+            let value = (0.5 + signed / SDF_RANGE).clamp(0.0, 1.0);
+            field.push((value * 255.0).round() as u8);
+        }
+    }
+    field
+}
@@ -1,6 +1,8 @@
 //! This library only works if the client feature of the let engine is active.
 
 pub mod labels;
+pub mod lines;
+pub mod ui;
 
 /// Run this at the start of every update to make sure the widgets all work correctly.
 pub fn update() {
@@ -13,3 +15,8 @@ pub fn update() {
 pub fn clear_cache() {
     labels::LABELIFIER.lock().clear_cache();
 }
+
+/// Returns statistics of the glyph cache shared by all labels.
+pub fn cache_stats() -> labels::GlyphCacheStats {
+    labels::LABELIFIER.lock().cache_stats()
+}
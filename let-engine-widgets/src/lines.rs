@@ -0,0 +1,486 @@
+//! Lines with a width and color per point, and trails following objects.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+
+use glam::Vec2;
+use let_engine_core::{
+    objects::{scenes::Layer, Appearance, Color, NewObject, Object, ObjectError},
+    resources::{
+        data::{tvert, Data},
+        materials::Material,
+        textures::{AddressMode, Filter, Format, Sampler, Texture, TextureSettings},
+        Model, ModelData,
+    },
+};
+
+/// The maximum amount of pixels of the textures holding the colors of lines.
+///
+/// Lines with more points than this get their colors resampled.
+const MAX_GRADIENT_SIZE: usize = 4096;
+
+/// The smallest cosine of the angle between a segment and the miter of a corner.
+///
+/// Sharper corners get their miter cut at four times the width of the line instead of shooting off.
+const MITER_LIMIT: f32 = 0.25;
+
+/// A point of a [LineRenderer].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinePoint {
+    pub position: Vec2,
+    /// The full width of the line at this point.
+    pub width: f32,
+    pub color: Color,
+}
+
+impl LinePoint {
+    pub fn new(position: Vec2, width: f32, color: Color) -> Self {
+        Self {
+            position,
+            width,
+            color,
+        }
+    }
+}
+
+/// A polyline drawn as a triangle strip with a width and color per point.
+///
+/// The positions of the points are in the space of the object, so a line with the default transform
+/// of size 1 uses the same units as the layer.
+///
+/// The colors get blended between the points and multiplied with the color of the appearance.
+#[derive(Clone, Debug)]
+pub struct LineRenderer<Object> {
+    pub object: Object,
+    pub points: Vec<LinePoint>,
+    /// Connects the last point back to the first one.
+    pub closed: bool,
+    /// The colors of the current gradient texture.
+    colors: Vec<Color>,
+}
+
+impl LineRenderer<NewObject> {
+    /// Creates a new line going through the given points.
+    pub fn new(points: Vec<LinePoint>) -> Self {
+        Self {
+            object: NewObject::new(),
+            points,
+            closed: false,
+            colors: vec![],
+        }
+    }
+
+    /// Sets whether the last point connects back to the first one and returns the line.
+    #[inline]
+    pub fn closed(mut self, closed: bool) -> Self {
+        self.closed = closed;
+        self
+    }
+
+    pub fn init(mut self, layer: &Arc<Layer>) -> Result<LineRenderer<Object>> {
+        build_line(
+            &mut self.object.appearance,
+            &self.points,
+            self.closed,
+            &mut self.colors,
+        )?;
+        Ok(LineRenderer {
+            object: self.object.init(layer)?,
+            points: self.points,
+            closed: self.closed,
+            colors: self.colors,
+        })
+    }
+
+    pub fn init_with_parent(mut self, parent: &Object) -> Result<LineRenderer<Object>> {
+        build_line(
+            &mut self.object.appearance,
+            &self.points,
+            self.closed,
+            &mut self.colors,
+        )?;
+        Ok(LineRenderer {
+            object: self.object.init_with_parent(parent)?,
+            points: self.points,
+            closed: self.closed,
+            colors: self.colors,
+        })
+    }
+}
+
+impl LineRenderer<Object> {
+    /// Updates the local information of this line from the layer.
+    pub fn update(&mut self) -> Result<(), ObjectError> {
+        self.object.update()
+    }
+
+    /// Changes the points of the line and immediately syncs it afterwards.
+    pub fn update_points(&mut self, points: Vec<LinePoint>) -> Result<()> {
+        self.points = points;
+        self.sync()
+    }
+
+    /// Rebuilds the mesh of the line and syncs the object with the layer.
+    ///
+    /// The color texture only gets remade in case the colors of the points changed.
+    pub fn sync(&mut self) -> Result<()> {
+        build_line(
+            &mut self.object.appearance,
+            &self.points,
+            self.closed,
+            &mut self.colors,
+        )?;
+        self.object.sync()?;
+        Ok(())
+    }
+}
+
+/// A line following an object, made of the positions it had during the lifetime of the trail.
+///
+/// The trail lives on the root of the layer of its target and has to be updated every frame
+/// using [update](TrailRenderer::update). The width and color go from the start values at the target
+/// to the end values at the oldest recorded position.
+///
+/// # Usage
+/// ```ignore
+/// let mut trail = TrailRenderer::new(&bullet)?
+///     .lifetime(Duration::from_millis(300))
+///     .width(0.05, 0.0)
+///     .color(Color::WHITE, Color::from_rgba(1.0, 1.0, 1.0, 0.0));
+///
+/// // Every frame.
+/// trail.update()?;
+/// ```
+pub struct TrailRenderer {
+    pub object: Object,
+    target: Object,
+    /// How long recorded positions stay part of the trail.
+    pub lifetime: Duration,
+    /// The distance the target has to move before a new position gets recorded.
+    pub min_distance: f32,
+    pub start_width: f32,
+    pub end_width: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+    samples: VecDeque<(Vec2, Instant)>,
+    colors: Vec<Color>,
+}
+
+impl TrailRenderer {
+    /// Creates an empty trail following the given object.
+    pub fn new(target: &Object) -> Result<Self> {
+        let object = NewObject::new().init(target.layer())?;
+        Ok(Self {
+            object,
+            target: target.clone(),
+            lifetime: Duration::from_millis(500),
+            min_distance: 0.01,
+            start_width: 0.1,
+            end_width: 0.0,
+            start_color: Color::WHITE,
+            end_color: Color::from_rgba(1.0, 1.0, 1.0, 0.0),
+            samples: VecDeque::new(),
+            colors: vec![],
+        })
+    }
+
+    /// Sets the lifetime of the recorded positions and returns the trail.
+    #[inline]
+    pub fn lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Sets the distance the target has to move to record a new position and returns the trail.
+    #[inline]
+    pub fn min_distance(mut self, min_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self
+    }
+
+    /// Sets the width at the target and at the end of the trail and returns the trail.
+    #[inline]
+    pub fn width(mut self, start: f32, end: f32) -> Self {
+        self.start_width = start;
+        self.end_width = end;
+        self
+    }
+
+    /// Sets the color at the target and at the end of the trail and returns the trail.
+    #[inline]
+    pub fn color(mut self, start: Color, end: Color) -> Self {
+        self.start_color = start;
+        self.end_color = end;
+        self
+    }
+
+    /// Returns the object this trail follows.
+    pub fn target(&self) -> &Object {
+        &self.target
+    }
+
+    /// Forgets all recorded positions, for example after teleporting the target.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Records the current position of the target, drops expired positions and rebuilds the trail.
+    pub fn update(&mut self) -> Result<()> {
+        self.target.update()?;
+        let now = Instant::now();
+        let position = self.target.public_transform().position;
+
+        if self
+            .samples
+            .front()
+            .is_none_or(|(last, _)| last.distance(position) >= self.min_distance)
+        {
+            self.samples.push_front((position, now));
+        }
+        while self
+            .samples
+            .back()
+            .is_some_and(|(_, time)| now.duration_since(*time) >= self.lifetime)
+        {
+            self.samples.pop_back();
+        }
+
+        let colors = [self.start_color, self.end_color];
+        if self.colors != colors {
+            let texture = gradient(&colors)?;
+            self.object
+                .appearance
+                .set_material(Some(Material::new_default_textured(&texture)?));
+            self.colors = colors.to_vec();
+        }
+
+        // The head of the trail always sits on the target, even if it moved less than the minimum distance.
+        let lifetime = self.lifetime.as_secs_f32().max(f32::EPSILON);
+        let points: Vec<(Vec2, f32, f32)> = std::iter::once((position, 0.0))
+            .chain(self.samples.iter().map(|(position, time)| {
+                let age = now.duration_since(*time).as_secs_f32() / lifetime;
+                (*position, age.min(1.0))
+            }))
+            .map(|(position, age)| {
+                let width = self.start_width + (self.end_width - self.start_width) * age;
+                (position, width, gradient_u(age, colors.len()))
+            })
+            .collect();
+
+        let model = match strip(&points, false) {
+            Some(data) => Some(Model::Custom(ModelData::new(data)?)),
+            None => None,
+        };
+        self.object.appearance.set_model(model)?;
+        self.object.sync()?;
+        Ok(())
+    }
+}
+
+impl Drop for TrailRenderer {
+    /// Removes the trail from its layer.
+    fn drop(&mut self) {
+        // Fails in case the trail object got removed already, together with its layer for example.
+        let _ = self.object.clone().remove();
+    }
+}
+
+/// Sets the model and, if the colors changed, the material of a line.
+///
+/// Lines without points get no model and keep their material.
+fn build_line(
+    appearance: &mut Appearance,
+    points: &[LinePoint],
+    closed: bool,
+    colors: &mut Vec<Color>,
+) -> Result<()> {
+    if points.is_empty() {
+        appearance.set_model(None)?;
+        colors.clear();
+        return Ok(());
+    }
+    let mut points: Vec<LinePoint> = points.to_vec();
+    if closed && points.len() > 2 {
+        points.push(points[0]);
+    }
+
+    let new_colors: Vec<Color> = points.iter().map(|point| point.color).collect();
+    let gradient_colors = resample(&new_colors);
+    if *colors != new_colors || appearance.get_material().is_none() {
+        let texture = gradient(&gradient_colors)?;
+        appearance.set_material(Some(Material::new_default_textured(&texture)?));
+        *colors = new_colors;
+    }
+
+    let count = points.len().saturating_sub(1).max(1) as f32;
+    let points: Vec<(Vec2, f32, f32)> = points
+        .iter()
+        .enumerate()
+        .map(|(index, point)| {
+            (
+                point.position,
+                point.width,
+                gradient_u(index as f32 / count, gradient_colors.len()),
+            )
+        })
+        .collect();
+
+    let model = match strip(&points, closed) {
+        Some(data) => Some(Model::Custom(ModelData::new(data)?)),
+        None => None,
+    };
+    appearance.set_model(model)?;
+    Ok(())
+}
+
+/// Picks at most [MAX_GRADIENT_SIZE] colors evenly spread over the given ones.
+///
+/// Lines of a single color get a texture of one pixel.
+fn resample(colors: &[Color]) -> Vec<Color> {
+    if colors.iter().all(|color| Some(color) == colors.first()) {
+        return colors.first().copied().into_iter().collect();
+    }
+    if colors.len() <= MAX_GRADIENT_SIZE {
+        return colors.to_vec();
+    }
+    let step = (colors.len() - 1) as f32 / (MAX_GRADIENT_SIZE - 1) as f32;
+    (0..MAX_GRADIENT_SIZE)
+        .map(|index| colors[(index as f32 * step).round() as usize])
+        .collect()
+}
+
+/// Makes a texture one pixel high holding the given colors from left to right.
+fn gradient(colors: &[Color]) -> Result<Texture> {
+    let pixels: Vec<u8> = colors
+        .iter()
+        .flat_map(|color| color.rgba())
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    let settings = TextureSettings {
+        // The colors stay the same as colors of appearances.
+        srgb: false,
+        sampler: Sampler {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            address_mode: [AddressMode::ClampToEdge; 3],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    Ok(Texture::from_raw(
+        &pixels,
+        (colors.len().max(1) as u32, 1),
+        Format::RGBA8,
+        1,
+        settings,
+        Some("line gradient"),
+    )?)
+}
+
+/// Returns the horizontal texture coordinate of the point at `t` from 0 to 1 along a gradient of the given width,
+/// going from the center of the first pixel to the center of the last one.
+///
+/// Texture coordinates go from -1.0 to 1.0 like the positions of the default square model.
+fn gradient_u(t: f32, width: usize) -> f32 {
+    let width = width.max(1) as f32;
+    (t * (width - 1.0) + 0.5) / width * 2.0 - 1.0
+}
+
+/// Builds the mesh of a line out of the position, width and texture coordinate of each point.
+///
+/// Every point gets two vertices on both sides of the line, and each segment is made of the two triangles
+/// between the vertices of its points. Closed lines have the first point repeated at the end.
+///
+/// Returns `None` in case there are not enough points to make a segment.
+fn strip(points: &[(Vec2, f32, f32)], closed: bool) -> Option<Data> {
+    // Points on top of each other have no direction.
+    let mut points = points.to_vec();
+    points.dedup_by(|b, a| a.0.distance_squared(b.0) < f32::EPSILON);
+    if points.len() < 2 {
+        return None;
+    }
+    let closed = closed && points.len() > 3 && points[0].0 == points[points.len() - 1].0;
+    let last = points.len() - 1;
+
+    let mut vertices = Vec::with_capacity(points.len() * 2);
+    for (index, &(position, width, u)) in points.iter().enumerate() {
+        let previous = match index {
+            0 if closed => Some(points[last - 1].0),
+            0 => None,
+            _ => Some(points[index - 1].0),
+        };
+        let next = match index {
+            _ if index == last && closed => Some(points[1].0),
+            _ if index == last => None,
+            _ => Some(points[index + 1].0),
+        };
+        let incoming = previous.map(|previous| (position - previous).normalize());
+        let outgoing = next.map(|next| (next - position).normalize());
+
+        let segment = outgoing.or(incoming).unwrap();
+        let tangent = match (incoming, outgoing) {
+            (Some(incoming), Some(outgoing)) => {
+                (incoming + outgoing).try_normalize().unwrap_or(incoming)
+            }
+            _ => segment,
+        };
+        let normal = tangent.perp();
+        // Lengthen the offset at corners so the line keeps its width on both segments.
+        let miter = normal.dot(segment.perp()).max(MITER_LIMIT);
+        let offset = normal * (width * 0.5 / miter);
+
+        vertices.push(tvert(position.x + offset.x, position.y + offset.y, u, -1.0));
+        vertices.push(tvert(position.x - offset.x, position.y - offset.y, u, 1.0));
+    }
+
+    let mut indices = Vec::with_capacity(last * 6);
+    for segment in 0..last as u32 {
+        let base = segment * 2;
+        indices.extend([base, base + 1, base + 2, base + 1, base + 3, base + 2]);
+    }
+
+    Some(Data::Dynamic { vertices, indices })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_keeps_single_colors_and_caps_gradients() {
+        assert!(resample(&[]).is_empty());
+        assert_eq!(resample(&[Color::WHITE; 3]), [Color::WHITE]);
+
+        let colors: Vec<Color> = (0..MAX_GRADIENT_SIZE * 2)
+            .map(|index| Color::from_rgba(index as f32, 0.0, 0.0, 1.0))
+            .collect();
+        let resampled = resample(&colors);
+        assert_eq!(resampled.len(), MAX_GRADIENT_SIZE);
+        assert_eq!(resampled.first(), colors.first());
+        assert_eq!(resampled.last(), colors.last());
+    }
+
+    #[test]
+    fn gradient_u_hits_the_pixel_centers() {
+        assert_eq!(gradient_u(0.0, 1), 0.0);
+        assert_eq!(gradient_u(0.0, 2), -0.5);
+        assert_eq!(gradient_u(1.0, 2), 0.5);
+    }
+
+    #[test]
+    fn strip_needs_two_distinct_points() {
+        assert!(strip(&[], false).is_none());
+        assert!(strip(&[(Vec2::ZERO, 1.0, 0.0); 2], false).is_none());
+
+        let Some(Data::Dynamic { vertices, indices }) =
+            strip(&[(Vec2::ZERO, 1.0, 0.0), (Vec2::X, 1.0, 0.0)], false)
+        else {
+            panic!("A segment makes a mesh.");
+        };
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(indices, [0, 1, 2, 1, 3, 2]);
+    }
+}
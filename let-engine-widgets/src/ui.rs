@@ -0,0 +1,758 @@
+//! A retained UI drawn on its own layer above the scene.
+//!
+//! Widgets get placed in pixels relative to the window or their parent, so the layout adapts when the window gets resized.
+//! Since this crate does not see the input of the engine, events have to be forwarded to [`Ui::handle`].
+//!
+//! # Usage
+//! ```ignore
+//! let mut ui = Ui::new(&font);
+//! let menu = ui.add(
+//!     None,
+//!     Placement::Anchored {
+//!         anchor: Direction::Center,
+//!         offset: Vec2::ZERO,
+//!         size: vec2(300.0, 200.0),
+//!     },
+//!     Panel::default().padding(10.0),
+//! );
+//! let play = ui.add(Some(menu), Placement::Docked { side: Dock::Top, size: 60.0 }, Button::new("Play"));
+//!
+//! // in the event function
+//! if let Event::Input(InputEvent::MouseInput(button, state)) = event {
+//!     ui.handle(&UiEvent::MouseButton { pressed: state == ElementState::Pressed });
+//! }
+//!
+//! // in the update function
+//! ui.update()?;
+//! for response in ui.drain_responses() {
+//!     if response == UiResponse::Clicked(play) {
+//!         start_game();
+//!     }
+//! }
+//! ```
+
+mod widgets;
+
+pub use widgets::*;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use glam::{vec2, Vec2};
+use let_engine_core::{
+    camera::{CameraScaling, CameraSettings},
+    objects::{
+        scenes::{Layer, SCENE},
+        Appearance, NewObject, Object, Transform,
+    },
+    resources::{Model, ModelData},
+    window, Direction,
+};
+
+use crate::labels::{Font, Label, LabelCreateInfo, LABELIFIER};
+
+/// Size of a pixel in world units on the UI layer, which uses expanding scaling at zoom 1.
+const PIXEL: f32 = 0.002;
+
+/// An axis aligned rectangle in pixels, with the origin at the top left corner of the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UiRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UiRect {
+    /// Creates a rectangle from its top left and bottom right corners.
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns the width and height.
+    pub fn size(&self) -> Vec2 {
+        (self.max - self.min).max(Vec2::ZERO)
+    }
+
+    /// Returns the center point.
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Returns true if the point lies within the rectangle.
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmplt(self.max).all()
+    }
+
+    /// Returns the rectangle made smaller by the amount on every side.
+    pub fn shrink(&self, amount: f32) -> Self {
+        let min = self.min + amount;
+        Self {
+            min,
+            max: (self.max - amount).max(min),
+        }
+    }
+}
+
+/// A side of the parent a widget can be docked to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dock {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// How a widget gets positioned within its parent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Placement {
+    /// A fixed size placed at a point of the parent, moved by the offset in pixels.
+    ///
+    /// An anchor of `Direction::So` keeps the widget in the bottom right corner no matter the size of the window.
+    Anchored {
+        anchor: Direction,
+        offset: Vec2,
+        size: Vec2,
+    },
+    /// Takes a strip of the given thickness from a side of the space left over by the docked siblings before it.
+    Docked { side: Dock, size: f32 },
+    /// Takes all the space left over by the docked siblings before it.
+    Fill,
+}
+
+impl Placement {
+    /// Returns the rectangle of the widget and takes docked space from the remaining area.
+    fn place(&self, parent: UiRect, remaining: &mut UiRect) -> UiRect {
+        match *self {
+            Placement::Anchored {
+                anchor,
+                offset,
+                size,
+            } => {
+                let min = parent.min + (parent.size() - size) * anchor_factor(anchor) + offset;
+                UiRect::new(min, min + size)
+            }
+            Placement::Docked { side, size } => {
+                let size = size.min(match side {
+                    Dock::Top | Dock::Bottom => remaining.size().y,
+                    Dock::Left | Dock::Right => remaining.size().x,
+                });
+                let mut rect = *remaining;
+                match side {
+                    Dock::Top => {
+                        rect.max.y = rect.min.y + size;
+                        remaining.min.y += size;
+                    }
+                    Dock::Bottom => {
+                        rect.min.y = rect.max.y - size;
+                        remaining.max.y -= size;
+                    }
+                    Dock::Left => {
+                        rect.max.x = rect.min.x + size;
+                        remaining.min.x += size;
+                    }
+                    Dock::Right => {
+                        rect.min.x = rect.max.x - size;
+                        remaining.max.x -= size;
+                    }
+                }
+                rect
+            }
+            Placement::Fill => *remaining,
+        }
+    }
+}
+
+/// Returns where the anchor lies on a rectangle, from (0, 0) at the top left to (1, 1) at the bottom right.
+fn anchor_factor(anchor: Direction) -> Vec2 {
+    match anchor {
+        Direction::Center => vec2(0.5, 0.5),
+        Direction::N => vec2(0.5, 0.0),
+        Direction::No => vec2(1.0, 0.0),
+        Direction::O => vec2(1.0, 0.5),
+        Direction::So => vec2(1.0, 1.0),
+        Direction::S => vec2(0.5, 1.0),
+        Direction::Sw => vec2(0.0, 1.0),
+        Direction::W => vec2(0.0, 0.5),
+        Direction::Nw => vec2(0.0, 0.0),
+    }
+}
+
+/// The identifier of a widget in a [`Ui`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WidgetId(usize);
+
+/// Keys used to edit text inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UiKey {
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+}
+
+/// Input forwarded to the UI by the game.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiEvent {
+    /// The cursor moved to the given position in pixels from the top left of the window.
+    CursorMoved(Vec2),
+    /// The primary mouse button got pressed or released.
+    MouseButton { pressed: bool },
+    /// Text got typed.
+    Text(String),
+    /// An editing key got pressed.
+    Key(UiKey),
+}
+
+/// Something that happened to a widget because of the forwarded input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiResponse {
+    /// A button got pressed and released while the cursor was on it.
+    Clicked(WidgetId),
+    /// A slider got moved to a new value.
+    ValueChanged(WidgetId, f32),
+    /// The text of a text input got edited.
+    TextChanged(WidgetId, String),
+    /// Enter got pressed in a text input.
+    Submitted(WidgetId, String),
+}
+
+struct Node {
+    widget: Widget,
+    placement: Placement,
+    parent: Option<WidgetId>,
+    children: Vec<WidgetId>,
+    z: i32,
+    visible: bool,
+    /// The rectangle from the last layout.
+    rect: UiRect,
+    /// The visibility of the node and all its parents from the last layout.
+    shown: bool,
+    /// True if the objects do not match the widget anymore.
+    dirty: bool,
+    objects: Vec<Object>,
+    label: Option<Label<Object>>,
+}
+
+/// A tree of widgets drawn on its own layer above the scene.
+pub struct Ui {
+    layer: Arc<Layer>,
+    font: Font,
+    nodes: Vec<Option<Node>>,
+    roots: Vec<WidgetId>,
+    window_size: Vec2,
+    cursor: Vec2,
+    hovered: Option<WidgetId>,
+    pressed: Option<WidgetId>,
+    focused: Option<WidgetId>,
+    responses: Vec<UiResponse>,
+    layout_dirty: bool,
+    order_dirty: bool,
+}
+
+impl Ui {
+    /// Creates a new UI on a new layer, using the font for the text of every widget.
+    pub fn new(font: &Font) -> Self {
+        let layer = SCENE.new_layer();
+        layer.set_camera_settings(CameraSettings::default().mode(CameraScaling::Expand));
+        Self {
+            layer,
+            font: font.clone(),
+            nodes: vec![],
+            roots: vec![],
+            window_size: Vec2::ZERO,
+            cursor: Vec2::splat(f32::NEG_INFINITY),
+            hovered: None,
+            pressed: None,
+            focused: None,
+            responses: vec![],
+            layout_dirty: true,
+            order_dirty: true,
+        }
+    }
+
+    /// Returns the layer the UI gets drawn on.
+    pub fn layer(&self) -> &Arc<Layer> {
+        &self.layer
+    }
+
+    /// Adds a widget to the parent, or to the window in case the parent is `None`.
+    pub fn add(
+        &mut self,
+        parent: Option<WidgetId>,
+        placement: Placement,
+        widget: impl Into<Widget>,
+    ) -> WidgetId {
+        let id = WidgetId(self.nodes.len());
+        self.nodes.push(Some(Node {
+            widget: widget.into(),
+            placement,
+            parent,
+            children: vec![],
+            z: 0,
+            visible: true,
+            rect: UiRect::default(),
+            shown: true,
+            dirty: true,
+            objects: vec![],
+            label: None,
+        }));
+        match parent.and_then(|parent| self.node_mut(parent)) {
+            Some(parent) => parent.children.push(id),
+            None => self.roots.push(id),
+        }
+        self.layout_dirty = true;
+        self.order_dirty = true;
+        id
+    }
+
+    /// Removes the widget and all its children.
+    pub fn remove(&mut self, id: WidgetId) -> Result<()> {
+        let Some(node) = self.nodes.get_mut(id.0).and_then(Option::take) else {
+            return Ok(());
+        };
+        match node.parent.and_then(|parent| self.node_mut(parent)) {
+            Some(parent) => parent.children.retain(|child| *child != id),
+            None => self.roots.retain(|root| *root != id),
+        }
+        for child in node.children.iter().copied() {
+            if let Some(child) = self.node_mut(child) {
+                // Keeps the child from touching the removed parent.
+                child.parent = None;
+            }
+            self.remove(child)?;
+        }
+        for object in node.objects {
+            object.remove()?;
+        }
+        if let Some(label) = node.label {
+            label.object.remove()?;
+        }
+        for state in [&mut self.hovered, &mut self.pressed, &mut self.focused] {
+            if *state == Some(id) {
+                *state = None;
+            }
+        }
+        self.layout_dirty = true;
+        Ok(())
+    }
+
+    /// Removes every widget.
+    pub fn clear(&mut self) -> Result<()> {
+        for root in std::mem::take(&mut self.roots) {
+            self.remove(root)?;
+        }
+        self.nodes.clear();
+        Ok(())
+    }
+
+    fn node(&self, id: WidgetId) -> Option<&Node> {
+        self.nodes.get(id.0).and_then(Option::as_ref)
+    }
+
+    fn node_mut(&mut self, id: WidgetId) -> Option<&mut Node> {
+        self.nodes.get_mut(id.0).and_then(Option::as_mut)
+    }
+
+    /// Returns the widget.
+    pub fn widget(&self, id: WidgetId) -> Option<&Widget> {
+        self.node(id).map(|node| &node.widget)
+    }
+
+    /// Returns the widget to change it. The change gets shown with the next update.
+    pub fn widget_mut(&mut self, id: WidgetId) -> Option<&mut Widget> {
+        let node = self.node_mut(id)?;
+        node.dirty = true;
+        Some(&mut node.widget)
+    }
+
+    /// Returns the rectangle of the widget from the last update.
+    pub fn rect(&self, id: WidgetId) -> Option<UiRect> {
+        self.node(id).map(|node| node.rect)
+    }
+
+    /// Changes how the widget is placed within its parent.
+    pub fn set_placement(&mut self, id: WidgetId, placement: Placement) {
+        if let Some(node) = self.node_mut(id) {
+            node.placement = placement;
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Shows or hides the widget along with its children.
+    pub fn set_visible(&mut self, id: WidgetId, visible: bool) {
+        if let Some(node) = self.node_mut(id) {
+            node.visible = visible;
+            self.layout_dirty = true;
+        }
+    }
+
+    /// Sets the order of the widget among its siblings. Higher values get drawn above lower ones.
+    pub fn set_z(&mut self, id: WidgetId, z: i32) {
+        if let Some(node) = self.node_mut(id) {
+            node.z = z;
+            self.order_dirty = true;
+        }
+    }
+
+    /// Returns the value of a slider or progress bar.
+    pub fn value(&self, id: WidgetId) -> Option<f32> {
+        match self.widget(id)? {
+            Widget::Slider(slider) => Some(slider.value),
+            Widget::ProgressBar(bar) => Some(bar.value),
+            _ => None,
+        }
+    }
+
+    /// Sets the value of a slider or progress bar.
+    pub fn set_value(&mut self, id: WidgetId, value: f32) {
+        match self.widget_mut(id) {
+            Some(Widget::Slider(slider)) => slider.value = value.clamp(slider.min, slider.max),
+            Some(Widget::ProgressBar(bar)) => bar.value = value,
+            _ => (),
+        }
+    }
+
+    /// Returns the text of a button or text input.
+    pub fn text(&self, id: WidgetId) -> Option<&str> {
+        match self.widget(id)? {
+            Widget::Button(button) => Some(&button.text),
+            Widget::TextInput(input) => Some(&input.text),
+            _ => None,
+        }
+    }
+
+    /// Sets the text of a button or text input.
+    pub fn set_text(&mut self, id: WidgetId, text: impl Into<String>) {
+        match self.widget_mut(id) {
+            Some(Widget::Button(button)) => button.text = text.into(),
+            Some(Widget::TextInput(input)) => *input = input.clone().text(text),
+            _ => (),
+        }
+    }
+
+    /// Returns the text input receiving typed text.
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focused
+    }
+
+    /// Gives the text input the keyboard focus, or removes the focus with `None`.
+    pub fn set_focus(&mut self, id: Option<WidgetId>) {
+        let id = id.filter(|id| matches!(self.widget(*id), Some(Widget::TextInput(_))));
+        let previous = std::mem::replace(&mut self.focused, id);
+        self.mark_dirty(previous);
+        self.mark_dirty(id);
+    }
+
+    /// Returns true if the cursor is above a widget, meaning mouse input should not reach the game.
+    pub fn hovering(&self) -> bool {
+        self.hit(self.cursor).is_some()
+    }
+
+    /// Returns the responses of all the input handled since the last call.
+    pub fn drain_responses(&mut self) -> Vec<UiResponse> {
+        std::mem::take(&mut self.responses)
+    }
+
+    fn mark_dirty(&mut self, id: Option<WidgetId>) {
+        if let Some(node) = id.and_then(|id| self.node_mut(id)) {
+            node.dirty = true;
+        }
+    }
+
+    /// Handles the input event. Returns true if the UI used it, in which case the game should ignore it.
+    pub fn handle(&mut self, event: &UiEvent) -> bool {
+        match event {
+            UiEvent::CursorMoved(position) => {
+                self.cursor = *position;
+                let hit = self.hit(self.cursor);
+                if hit != self.hovered {
+                    let previous = std::mem::replace(&mut self.hovered, hit);
+                    self.mark_dirty(previous);
+                    self.mark_dirty(hit);
+                }
+                // Keeps dragging a slider even when the cursor leaves it.
+                if let Some(id) = self.pressed {
+                    let cursor = self.cursor;
+                    if let Some(node) = self.node_mut(id) {
+                        if let Widget::Slider(slider) = &mut node.widget {
+                            let value = slider.value;
+                            slider.set_from_cursor(node.rect, cursor);
+                            if slider.value != value {
+                                node.dirty = true;
+                                self.responses
+                                    .push(UiResponse::ValueChanged(id, slider.value));
+                            }
+                        }
+                    }
+                    return true;
+                }
+                hit.is_some()
+            }
+            UiEvent::MouseButton { pressed: true } => {
+                let hit = self.hit(self.cursor);
+                let interactive =
+                    hit.filter(|id| self.widget(*id).is_some_and(|widget| widget.interactive()));
+                self.set_focus(interactive);
+                self.pressed = interactive;
+                self.mark_dirty(interactive);
+                if interactive.is_some() {
+                    // Moves the slider right away instead of waiting for the cursor to move.
+                    self.handle(&UiEvent::CursorMoved(self.cursor));
+                }
+                hit.is_some()
+            }
+            UiEvent::MouseButton { pressed: false } => {
+                let Some(pressed) = self.pressed.take() else {
+                    return self.hovering();
+                };
+                self.mark_dirty(Some(pressed));
+                if self.hit(self.cursor) == Some(pressed)
+                    && matches!(self.widget(pressed), Some(Widget::Button(_)))
+                {
+                    self.responses.push(UiResponse::Clicked(pressed));
+                }
+                true
+            }
+            UiEvent::Text(text) => {
+                let Some(id) = self.focused else {
+                    return false;
+                };
+                if let Some(node) = self.node_mut(id) {
+                    if let Widget::TextInput(input) = &mut node.widget {
+                        if input.insert(text) {
+                            node.dirty = true;
+                            self.responses
+                                .push(UiResponse::TextChanged(id, input.text.clone()));
+                        }
+                    }
+                }
+                true
+            }
+            UiEvent::Key(key) => {
+                let Some(id) = self.focused else {
+                    return false;
+                };
+                if let Some(node) = self.node_mut(id) {
+                    if let Widget::TextInput(input) = &mut node.widget {
+                        node.dirty = true;
+                        if *key == UiKey::Enter {
+                            self.responses
+                                .push(UiResponse::Submitted(id, input.text.clone()));
+                        } else if input.key(*key) {
+                            self.responses
+                                .push(UiResponse::TextChanged(id, input.text.clone()));
+                        }
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Returns the topmost visible widget at the given point.
+    fn hit(&self, point: Vec2) -> Option<WidgetId> {
+        self.draw_order().into_iter().rev().find(|id| {
+            self.node(*id)
+                .is_some_and(|node| node.shown && node.rect.contains(point))
+        })
+    }
+
+    /// Returns every widget from back to front.
+    fn draw_order(&self) -> Vec<WidgetId> {
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack: Vec<WidgetId> = self.sorted(&self.roots).into_iter().rev().collect();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            if let Some(node) = self.node(id) {
+                stack.extend(self.sorted(&node.children).into_iter().rev());
+            }
+        }
+        order
+    }
+
+    /// Returns the siblings sorted by z, keeping the order they got added in for equal z.
+    fn sorted(&self, ids: &[WidgetId]) -> Vec<WidgetId> {
+        let mut ids = ids.to_vec();
+        ids.sort_by_key(|id| self.node(*id).map_or(0, |node| node.z));
+        ids
+    }
+
+    /// Places every widget within the window.
+    fn layout(&mut self) {
+        let window = UiRect::new(Vec2::ZERO, self.window_size);
+        // Docking depends on the order of siblings, so every parent places all its children at once.
+        let mut stack = vec![(self.roots.clone(), window, true)];
+        while let Some((children, content, shown)) = stack.pop() {
+            let mut remaining = content;
+            for child in children {
+                let Some(node) = self.node_mut(child) else {
+                    continue;
+                };
+                node.rect = node.placement.place(content, &mut remaining);
+                node.shown = shown && node.visible;
+                node.dirty = true;
+                stack.push((
+                    node.children.clone(),
+                    node.widget.content(node.rect),
+                    node.shown,
+                ));
+            }
+        }
+    }
+
+    /// Lays out the widgets when the window got resized and brings all changed widgets to the screen.
+    ///
+    /// Call this every update after handling the input.
+    pub fn update(&mut self) -> Result<()> {
+        if let Some(window) = window::window() {
+            let size = window.inner_size();
+            if size != self.window_size {
+                self.window_size = size;
+                self.layout_dirty = true;
+            }
+        }
+        if self.layout_dirty {
+            self.layout();
+            self.layout_dirty = false;
+        }
+
+        let state = |id| WidgetState {
+            hovered: self.hovered == Some(id),
+            pressed: self.pressed == Some(id),
+            focused: self.focused == Some(id),
+        };
+        let states: Vec<(WidgetId, WidgetState)> = (0..self.nodes.len())
+            .map(WidgetId)
+            .filter(|id| self.node(*id).is_some_and(|node| node.dirty))
+            .map(|id| (id, state(id)))
+            .collect();
+        let window_size = self.window_size;
+        for (id, state) in states {
+            let layer = self.layer.clone();
+            let font = self.font.clone();
+            let node = self.node_mut(id).unwrap();
+            if sync_node(node, state, &layer, &font, window_size)? {
+                self.order_dirty = true;
+            }
+        }
+
+        if self.order_dirty {
+            for id in self.draw_order() {
+                let node = self.node(id).unwrap();
+                for object in &node.objects {
+                    object.move_to_top()?;
+                }
+                if let Some(label) = &node.label {
+                    label.object.move_to_top()?;
+                }
+            }
+            self.order_dirty = false;
+        }
+
+        if !SCENE
+            .layers()
+            .last()
+            .is_some_and(|layer| Arc::ptr_eq(layer, &self.layer))
+        {
+            SCENE.move_layer_to_top(&self.layer)?;
+        }
+
+        // Renders the text of the widgets right away, so it does not lag a frame behind.
+        LABELIFIER.lock().update()?;
+        Ok(())
+    }
+}
+
+/// Converts a point in pixels from the top left of the window to world space of the UI layer.
+fn to_world(point: Vec2, window_size: Vec2) -> Vec2 {
+    (point - window_size * 0.5) * PIXEL
+}
+
+/// Makes the objects of the node match its widget. Returns true if objects got added.
+fn sync_node(
+    node: &mut Node,
+    state: WidgetState,
+    layer: &Arc<Layer>,
+    font: &Font,
+    window_size: Vec2,
+) -> Result<bool> {
+    node.dirty = false;
+    let visual = node.widget.visual(node.rect, state);
+    let mut added = false;
+
+    while node.objects.len() > visual.parts.len() {
+        node.objects.pop().unwrap().remove()?;
+    }
+    for (i, part) in visual.parts.into_iter().enumerate() {
+        let transform = Transform::default().position(to_world(part.rect.center(), window_size));
+        let mut appearance = Appearance::new()
+            .transform(Transform::default().size(part.rect.size() * PIXEL * 0.5))
+            .color(part.color)
+            .material(part.material)
+            .visible(node.shown);
+        let model = match part.model {
+            Some(data) => Model::Custom(ModelData::new(data)?),
+            None => Model::Square,
+        };
+        appearance.set_model(Some(model))?;
+        match node.objects.get_mut(i) {
+            Some(object) => {
+                object.transform = transform;
+                object.appearance = appearance;
+                object.sync()?;
+            }
+            None => {
+                let mut object = NewObject::new();
+                object.transform = transform;
+                object.appearance = appearance;
+                node.objects.push(object.init(layer)?);
+                added = true;
+            }
+        }
+    }
+
+    match (visual.text, &mut node.label) {
+        (Some(text), Some(label)) => {
+            label.object.transform =
+                Transform::default().position(to_world(text.rect.center(), window_size));
+            label.object.appearance = label
+                .object
+                .appearance
+                .clone()
+                .transform(Transform::default().size(text.rect.size() * PIXEL * 0.5))
+                .color(text.color)
+                .visible(node.shown);
+            label.text = text.text;
+            label.scale = Vec2::splat(text.size);
+            label.align = text.align;
+            label.sync();
+        }
+        (Some(text), None) => {
+            let label = Label::new(
+                font,
+                LabelCreateInfo::default()
+                    .transform(
+                        Transform::default().position(to_world(text.rect.center(), window_size)),
+                    )
+                    .appearance(
+                        Appearance::new()
+                            .transform(Transform::default().size(text.rect.size() * PIXEL * 0.5))
+                            .color(text.color)
+                            .visible(node.shown),
+                    )
+                    .text(text.text)
+                    .scale(Vec2::splat(text.size))
+                    .align(text.align),
+            );
+            node.label = Some(label.init(layer)?);
+            added = true;
+        }
+        (None, Some(_)) => {
+            node.label.take().unwrap().object.remove()?;
+        }
+        (None, None) => (),
+    }
+    Ok(added)
+}
@@ -0,0 +1,611 @@
+//! The widgets available to the UI.
+
+use glam::{vec2, Vec2};
+use let_engine_core::{
+    objects::Color,
+    resources::{
+        data::{Data, Insets},
+        materials::Material,
+    },
+    Direction,
+};
+
+use super::UiRect;
+
+/// Every kind of widget a UI node can be.
+#[derive(Clone, Debug)]
+pub enum Widget {
+    Panel(Panel),
+    Image(Image),
+    Button(Button),
+    ProgressBar(ProgressBar),
+    Slider(Slider),
+    TextInput(TextInput),
+}
+
+impl Widget {
+    /// Returns true if the widget reacts to the mouse.
+    pub(super) fn interactive(&self) -> bool {
+        matches!(
+            self,
+            Widget::Button(_) | Widget::Slider(_) | Widget::TextInput(_)
+        )
+    }
+
+    /// Returns the area children get placed in.
+    pub(super) fn content(&self, rect: UiRect) -> UiRect {
+        match self {
+            Widget::Panel(panel) => rect.shrink(panel.padding),
+            _ => rect,
+        }
+    }
+
+    /// Returns the rectangles and text making up the widget in the given state.
+    pub(super) fn visual(&self, rect: UiRect, state: WidgetState) -> Visual {
+        match self {
+            Widget::Panel(panel) => match &panel.slice {
+                Some(slice) => Visual {
+                    parts: vec![Part {
+                        rect,
+                        color: panel.color,
+                        material: Some(slice.material.clone()),
+                        model: Some(slice.data(rect.size())),
+                    }],
+                    text: None,
+                },
+                None => Visual::rect(rect, panel.color, None),
+            },
+            Widget::Image(image) => Visual::rect(rect, image.color, Some(image.material.clone())),
+            Widget::Button(button) => {
+                let color = if state.pressed {
+                    button.style.pressed
+                } else if state.hovered {
+                    button.style.hovered
+                } else {
+                    button.style.normal
+                };
+                let mut visual = Visual::rect(rect, color, None);
+                visual.text = Some(TextPart {
+                    text: button.text.clone(),
+                    rect,
+                    size: button.text_size,
+                    color: button.text_color,
+                    align: Direction::Center,
+                });
+                visual
+            }
+            Widget::ProgressBar(bar) => {
+                let mut visual = Visual::rect(rect, bar.background, None);
+                let width = rect.size().x * bar.value.clamp(0.0, 1.0);
+                if width > 0.0 {
+                    visual.parts.push(Part {
+                        rect: UiRect::new(rect.min, vec2(rect.min.x + width, rect.max.y)),
+                        color: bar.fill,
+                        material: None,
+                        model: None,
+                    });
+                }
+                visual
+            }
+            Widget::Slider(slider) => {
+                let track_height = (rect.size().y * 0.25).max(1.0);
+                let center = rect.center().y;
+                let mut visual = Visual::rect(
+                    UiRect::new(
+                        vec2(rect.min.x, center - track_height * 0.5),
+                        vec2(rect.max.x, center + track_height * 0.5),
+                    ),
+                    slider.track,
+                    None,
+                );
+                let x = rect.min.x
+                    + slider.handle_width * 0.5
+                    + (rect.size().x - slider.handle_width).max(0.0) * slider.fraction();
+                visual.parts.push(Part {
+                    rect: UiRect::new(
+                        vec2(x - slider.handle_width * 0.5, rect.min.y),
+                        vec2(x + slider.handle_width * 0.5, rect.max.y),
+                    ),
+                    color: if state.pressed || state.hovered {
+                        slider.handle_hovered
+                    } else {
+                        slider.handle
+                    },
+                    material: None,
+                    model: None,
+                });
+                visual
+            }
+            Widget::TextInput(input) => {
+                let background = if state.focused {
+                    input.focused_background
+                } else {
+                    input.background
+                };
+                let mut visual = Visual::rect(rect, background, None);
+                let padding = input.text_size * 0.25;
+                let text_rect = rect.shrink(padding);
+                let (text, color) = if state.focused {
+                    // The caret is drawn as a character at the cursor position.
+                    let mut text = input.text.clone();
+                    text.insert(input.byte_index(input.cursor), '|');
+                    (text, input.text_color)
+                } else if input.text.is_empty() {
+                    let mut color = input.text_color;
+                    color.set_a(color.alpha() * 0.5);
+                    (input.placeholder.clone(), color)
+                } else {
+                    (input.text.clone(), input.text_color)
+                };
+                visual.text = Some(TextPart {
+                    text,
+                    rect: text_rect,
+                    size: input.text_size,
+                    color,
+                    align: Direction::W,
+                });
+                visual
+            }
+        }
+    }
+}
+
+/// The interaction state of a widget used to pick its look.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct WidgetState {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+}
+
+/// A single colored rectangle of a widget.
+pub(super) struct Part {
+    pub rect: UiRect,
+    pub color: Color,
+    pub material: Option<Material>,
+    /// A custom model instead of a square.
+    pub model: Option<Data>,
+}
+
+/// The text of a widget.
+pub(super) struct TextPart {
+    pub text: String,
+    pub rect: UiRect,
+    pub size: f32,
+    pub color: Color,
+    pub align: Direction,
+}
+
+/// Everything a widget draws, from back to front.
+pub(super) struct Visual {
+    pub parts: Vec<Part>,
+    pub text: Option<TextPart>,
+}
+
+impl Visual {
+    fn rect(rect: UiRect, color: Color, material: Option<Material>) -> Self {
+        Self {
+            parts: vec![Part {
+                rect,
+                color,
+                material,
+                model: None,
+            }],
+            text: None,
+        }
+    }
+}
+
+/// A colored rectangle grouping other widgets.
+#[derive(Clone, Debug)]
+pub struct Panel {
+    /// The color of the panel, or the tint of its texture in case it is sliced.
+    pub color: Color,
+    /// Space between the border of the panel and its children in pixels.
+    pub padding: f32,
+    /// A texture stretched over the panel keeping its borders intact.
+    pub slice: Option<NineSlice>,
+}
+
+impl Panel {
+    /// Creates a new panel with the given color.
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            padding: 0.0,
+            slice: None,
+        }
+    }
+
+    /// Creates a new panel showing the nine-slice texture.
+    pub fn sliced(slice: NineSlice) -> Self {
+        Self {
+            color: Color::WHITE,
+            padding: 0.0,
+            slice: Some(slice),
+        }
+    }
+
+    /// Sets the space between the border and the children in pixels.
+    pub fn padding(mut self, padding: f32) -> Self {
+        self.padding = padding;
+        self
+    }
+}
+
+impl Default for Panel {
+    fn default() -> Self {
+        Self::new(Color::from_rgba(0.1, 0.1, 0.1, 0.8))
+    }
+}
+
+/// A texture split into 9 parts, where the corners keep their size and the edges and center stretch.
+///
+/// Used for panels and dialog boxes which have to look right at any size.
+#[derive(Clone, Debug)]
+pub struct NineSlice {
+    pub material: Material,
+    /// The borders of the texture in texture pixels.
+    pub insets: Insets,
+    /// The size of a texture pixel on the screen in pixels.
+    pub scale: f32,
+    texture_size: Vec2,
+}
+
+impl NineSlice {
+    /// Creates a new nine-slice from a textured material with the given borders in pixels of its texture.
+    pub fn new(material: Material, insets: Insets) -> Self {
+        let texture_size = material.texture().map_or(Vec2::ONE, |texture| {
+            let (width, height) = texture.dimensions();
+            vec2(width as f32, height as f32)
+        });
+        Self {
+            material,
+            insets,
+            scale: 1.0,
+            texture_size,
+        }
+    }
+
+    /// Sets the size of a texture pixel on the screen, making the borders bigger or smaller.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns the model data for a rectangle of the given size in pixels.
+    ///
+    /// Can also be used for objects outside of the UI, by setting their appearance size to match the given size.
+    pub fn data(&self, size: Vec2) -> Data {
+        Data::nine_slice(
+            size / self.scale.max(f32::EPSILON),
+            self.texture_size,
+            self.insets,
+        )
+    }
+}
+
+/// A rectangle showing a material, usually a textured one.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub material: Material,
+    /// The color the material gets tinted with.
+    pub color: Color,
+}
+
+impl Image {
+    /// Creates a new image showing the given material.
+    pub fn new(material: Material) -> Self {
+        Self {
+            material,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Sets the tint of the image.
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// The colors of a button in each state.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ButtonStyle {
+    pub normal: Color,
+    pub hovered: Color,
+    pub pressed: Color,
+}
+
+impl Default for ButtonStyle {
+    fn default() -> Self {
+        Self {
+            normal: Color::from_rgb(0.25, 0.25, 0.3),
+            hovered: Color::from_rgb(0.35, 0.35, 0.42),
+            pressed: Color::from_rgb(0.18, 0.18, 0.22),
+        }
+    }
+}
+
+/// A clickable rectangle with text, reporting [`UiResponse::Clicked`](super::UiResponse::Clicked).
+#[derive(Clone, Debug)]
+pub struct Button {
+    pub text: String,
+    pub style: ButtonStyle,
+    pub text_color: Color,
+    /// The size of the text in pixels.
+    pub text_size: f32,
+}
+
+impl Button {
+    /// Creates a new button with the given text.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            style: ButtonStyle::default(),
+            text_color: Color::WHITE,
+            text_size: 24.0,
+        }
+    }
+
+    /// Sets the colors of the button.
+    pub fn style(mut self, style: ButtonStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the color of the text.
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.text_color = color;
+        self
+    }
+
+    /// Sets the size of the text in pixels.
+    pub fn text_size(mut self, size: f32) -> Self {
+        self.text_size = size;
+        self
+    }
+}
+
+/// A bar filled from left to right by its value.
+#[derive(Clone, Debug)]
+pub struct ProgressBar {
+    /// The progress from 0 to 1.
+    pub value: f32,
+    pub background: Color,
+    pub fill: Color,
+}
+
+impl ProgressBar {
+    /// Creates a new progress bar with the given value from 0 to 1.
+    pub fn new(value: f32) -> Self {
+        Self {
+            value,
+            background: Color::from_rgb(0.15, 0.15, 0.15),
+            fill: Color::from_rgb(0.3, 0.7, 0.35),
+        }
+    }
+
+    /// Sets the color of the empty part.
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the color of the filled part.
+    pub fn fill(mut self, color: Color) -> Self {
+        self.fill = color;
+        self
+    }
+}
+
+/// A handle dragged along a track, reporting [`UiResponse::ValueChanged`](super::UiResponse::ValueChanged).
+#[derive(Clone, Debug)]
+pub struct Slider {
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Snaps the value to multiples of this step from the minimum.
+    pub step: Option<f32>,
+    pub track: Color,
+    pub handle: Color,
+    pub handle_hovered: Color,
+    /// The width of the handle in pixels.
+    pub handle_width: f32,
+}
+
+impl Slider {
+    /// Creates a new slider in the given range.
+    pub fn new(value: f32, min: f32, max: f32) -> Self {
+        Self {
+            value: value.clamp(min, max),
+            min,
+            max,
+            step: None,
+            track: Color::from_rgb(0.15, 0.15, 0.15),
+            handle: Color::from_rgb(0.6, 0.6, 0.65),
+            handle_hovered: Color::from_rgb(0.8, 0.8, 0.85),
+            handle_width: 16.0,
+        }
+    }
+
+    /// Snaps the value to multiples of the given step.
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Sets the colors of the track and handle.
+    pub fn colors(mut self, track: Color, handle: Color, handle_hovered: Color) -> Self {
+        self.track = track;
+        self.handle = handle;
+        self.handle_hovered = handle_hovered;
+        self
+    }
+
+    /// Sets the width of the handle in pixels.
+    pub fn handle_width(mut self, width: f32) -> Self {
+        self.handle_width = width;
+        self
+    }
+
+    /// Returns where the value lies in the range from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        if self.max == self.min {
+            return 0.0;
+        }
+        ((self.value - self.min) / (self.max - self.min)).clamp(0.0, 1.0)
+    }
+
+    /// Sets the value from the horizontal cursor position within the given rectangle.
+    pub(super) fn set_from_cursor(&mut self, rect: UiRect, cursor: Vec2) {
+        let track = (rect.size().x - self.handle_width).max(1.0);
+        let fraction = ((cursor.x - rect.min.x - self.handle_width * 0.5) / track).clamp(0.0, 1.0);
+        let mut value = self.min + fraction * (self.max - self.min);
+        if let Some(step) = self.step.filter(|step| *step > 0.0) {
+            value = self.min + ((value - self.min) / step).round() * step;
+        }
+        self.value = value.clamp(self.min.min(self.max), self.max.max(self.min));
+    }
+}
+
+/// A single line of editable text, reporting [`UiResponse::TextChanged`](super::UiResponse::TextChanged)
+/// and [`UiResponse::Submitted`](super::UiResponse::Submitted).
+#[derive(Clone, Debug)]
+pub struct TextInput {
+    pub text: String,
+    /// Text shown while the input is empty and not focused.
+    pub placeholder: String,
+    /// The maximum number of characters.
+    pub max_length: Option<usize>,
+    pub background: Color,
+    pub focused_background: Color,
+    pub text_color: Color,
+    /// The size of the text in pixels.
+    pub text_size: f32,
+    /// The caret position in characters.
+    pub(super) cursor: usize,
+}
+
+impl TextInput {
+    /// Creates a new empty text input.
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            placeholder: String::new(),
+            max_length: None,
+            background: Color::from_rgb(0.12, 0.12, 0.14),
+            focused_background: Color::from_rgb(0.18, 0.18, 0.22),
+            text_color: Color::WHITE,
+            text_size: 24.0,
+            cursor: 0,
+        }
+    }
+
+    /// Sets the initial text.
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self.cursor = self.text.chars().count();
+        self
+    }
+
+    /// Sets the text shown while the input is empty.
+    pub fn placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = placeholder.into();
+        self
+    }
+
+    /// Limits the number of characters.
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = Some(length);
+        self
+    }
+
+    /// Sets the size of the text in pixels.
+    pub fn text_size(mut self, size: f32) -> Self {
+        self.text_size = size;
+        self
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    /// Inserts the text at the caret, ignoring control characters. Returns true if the text changed.
+    pub(super) fn insert(&mut self, text: &str) -> bool {
+        let mut changed = false;
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self
+                .max_length
+                .is_some_and(|max| self.text.chars().count() >= max)
+            {
+                break;
+            }
+            let index = self.byte_index(self.cursor);
+            self.text.insert(index, c);
+            self.cursor += 1;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Applies an editing key. Returns true if the text changed.
+    pub(super) fn key(&mut self, key: super::UiKey) -> bool {
+        use super::UiKey;
+        let length = self.text.chars().count();
+        self.cursor = self.cursor.min(length);
+        match key {
+            UiKey::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+                true
+            }
+            UiKey::Delete if self.cursor < length => {
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+                true
+            }
+            UiKey::Left => {
+                self.cursor = self.cursor.saturating_sub(1);
+                false
+            }
+            UiKey::Right => {
+                self.cursor = (self.cursor + 1).min(length);
+                false
+            }
+            UiKey::Home => {
+                self.cursor = 0;
+                false
+            }
+            UiKey::End => {
+                self.cursor = length;
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for TextInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! impl_into_widget {
+    ($($widget:ident),*) => {
+        $(
+            impl From<$widget> for Widget {
+                fn from(value: $widget) -> Self {
+                    Widget::$widget(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_widget!(Panel, Image, Button, ProgressBar, Slider, TextInput);
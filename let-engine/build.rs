@@ -0,0 +1,54 @@
+//! Finds the versions of the backends the engine got built with for `capabilities()`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// The crates whose resolved versions get reported, with the environment variable they get passed in.
+const BACKENDS: [(&str, &str); 2] = [
+    ("rapier2d", "LET_ENGINE_RAPIER_VERSION"),
+    ("kira", "LET_ENGINE_KIRA_VERSION"),
+];
+
+fn main() {
+    let lock = find_lock_file();
+    let lock_contents = lock.as_ref().and_then(|path| fs::read_to_string(path).ok());
+    if let Some(path) = &lock {
+        println!("cargo::rerun-if-changed={}", path.display());
+    }
+    for (name, variable) in BACKENDS {
+        let version = lock_contents
+            .as_deref()
+            .and_then(|contents| locked_version(contents, name))
+            .unwrap_or("unknown");
+        println!("cargo::rustc-env={variable}={version}");
+    }
+}
+
+/// Returns the lock file of the workspace building the engine,
+/// which is above the output directory unless the target directory was moved.
+fn find_lock_file() -> Option<PathBuf> {
+    let out_dir = env::var_os("OUT_DIR").map(PathBuf::from);
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    [out_dir, manifest_dir]
+        .into_iter()
+        .flatten()
+        .find_map(|directory| {
+            directory
+                .ancestors()
+                .map(|ancestor| ancestor.join("Cargo.lock"))
+                .find(|path| Path::is_file(path))
+        })
+}
+
+/// Returns the version of the first package with the given name in the lock file.
+fn locked_version<'a>(lock: &'a str, name: &str) -> Option<&'a str> {
+    let name_line = format!("name = \"{name}\"");
+    let mut lines = lock.lines().map(str::trim);
+    lines.find(|line| *line == name_line)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+}
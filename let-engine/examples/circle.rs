@@ -61,6 +61,7 @@ impl let_engine::Game for Game {
         self.main_layer.set_camera_settings(CameraSettings {
             zoom: 0.5,
             mode: CameraScaling::Expand,
+            ..Default::default()
         });
         // Makes the circle in the middle.
         let mut circle = NewObject::new();
@@ -60,6 +60,7 @@ impl let_engine::Game for Game {
         self.layer.set_camera_settings(CameraSettings {
             zoom: 0.5,
             mode: CameraScaling::Linear,
+            ..Default::default()
         });
 
         // Makes the circle in the middle.
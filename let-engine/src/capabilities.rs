@@ -0,0 +1,156 @@
+//! Runtime information about the engine build and the backends it uses.
+
+use std::fmt;
+
+/// A library or API the engine uses under the hood.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Backend {
+    /// What the backend is used for, like `graphics` or `physics`.
+    pub purpose: &'static str,
+    pub name: String,
+    pub version: String,
+}
+
+/// The version, enabled features and backends of the running engine.
+///
+/// The [`Display`](fmt::Display) implementation formats everything in a few lines fit for bug reports.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version of the engine.
+    pub version: &'static str,
+    /// The cargo features the engine was compiled with.
+    pub features: Vec<&'static str>,
+    /// The compression algorithms the asset system is able to read.
+    pub compressions: Vec<&'static str>,
+    /// The features the engine enabled on the GPU, empty before the engine got started.
+    pub device_features: Vec<&'static str>,
+    pub backends: Vec<Backend>,
+}
+
+impl Capabilities {
+    /// Returns true if the engine was compiled with the given cargo feature.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Returns true if the engine enabled the given feature on the GPU, named like the fields of vulkano's `DeviceFeatures`.
+    pub fn has_device_feature(&self, feature: &str) -> bool {
+        self.device_features.contains(&feature)
+    }
+
+    /// Returns the backend used for the given purpose.
+    pub fn backend(&self, purpose: &str) -> Option<&Backend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.purpose == purpose)
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "let-engine {}", self.version)?;
+        writeln!(f, "features: {}", self.features.join(", "))?;
+        if !self.compressions.is_empty() {
+            writeln!(f, "compressions: {}", self.compressions.join(", "))?;
+        }
+        if !self.device_features.is_empty() {
+            writeln!(f, "device features: {}", self.device_features.join(", "))?;
+        }
+        for backend in &self.backends {
+            writeln!(
+                f,
+                "{}: {} {}",
+                backend.purpose, backend.name, backend.version
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the version, enabled features and backends of the engine.
+///
+/// The graphics backend and device features are only reported after the engine got started, since they describe the GPU in use.
+/// The versions of the other backends are the ones the engine got built with.
+pub fn capabilities() -> Capabilities {
+    let features = [
+        ("client", cfg!(feature = "client")),
+        ("physics", cfg!(feature = "physics")),
+        ("audio", cfg!(feature = "audio")),
+        ("egui", cfg!(feature = "egui")),
+        ("svg", cfg!(feature = "svg")),
+        ("networking", cfg!(feature = "networking")),
+        ("encryption", cfg!(feature = "encryption")),
+        ("tracing", cfg!(feature = "tracing")),
+        ("crash_handler", cfg!(feature = "crash_handler")),
+        ("asset_system", cfg!(feature = "asset_system")),
+        ("serde", cfg!(feature = "serde")),
+        ("vulkan_debug_utils", cfg!(feature = "vulkan_debug_utils")),
+        ("rand", cfg!(feature = "rand")),
+        ("fast-math", cfg!(feature = "fast-math")),
+    ];
+    let compressions = [
+        ("deflate", cfg!(feature = "deflate")),
+        ("bzip2", cfg!(feature = "bzip2")),
+        ("zstd", cfg!(feature = "zstd")),
+        ("lzma", cfg!(feature = "lzma")),
+        ("lz4", cfg!(feature = "lz4")),
+    ];
+    let enabled = |list: &[(&'static str, bool)]| {
+        list.iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(name, _)| *name)
+            .collect()
+    };
+
+    let mut backends = vec![];
+    #[allow(unused_mut)]
+    let mut device_features = vec![];
+    #[cfg(feature = "client")]
+    if let Ok(resources) = let_engine_core::resources::resources() {
+        let vulkan = resources.vulkan();
+        let enabled_features = vulkan.device.enabled_features();
+        device_features = enabled(&[
+            ("fill_mode_non_solid", enabled_features.fill_mode_non_solid),
+            ("wide_lines", enabled_features.wide_lines),
+            ("sampler_anisotropy", enabled_features.sampler_anisotropy),
+            (
+                "shader_sampled_image_array_non_uniform_indexing",
+                enabled_features.shader_sampled_image_array_non_uniform_indexing,
+            ),
+        ]);
+        let physical_device = vulkan.device.physical_device();
+        let properties = physical_device.properties();
+        backends.push(Backend {
+            purpose: "graphics",
+            name: format!("Vulkan on {}", properties.device_name),
+            version: physical_device.api_version().to_string(),
+        });
+        if let Some(driver) = &properties.driver_name {
+            backends.push(Backend {
+                purpose: "driver",
+                name: driver.clone(),
+                version: properties.driver_info.clone().unwrap_or_default(),
+            });
+        }
+    }
+    #[cfg(feature = "physics")]
+    backends.push(Backend {
+        purpose: "physics",
+        name: "rapier2d".to_string(),
+        version: env!("LET_ENGINE_RAPIER_VERSION").to_string(),
+    });
+    #[cfg(feature = "audio")]
+    backends.push(Backend {
+        purpose: "audio",
+        name: "kira".to_string(),
+        version: env!("LET_ENGINE_KIRA_VERSION").to_string(),
+    });
+
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        features: enabled(&features),
+        compressions: enabled(&compressions),
+        device_features,
+        backends,
+    }
+}
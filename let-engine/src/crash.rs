@@ -0,0 +1,312 @@
+//! Writing crash reports and telling the player about them when the game panics.
+
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{Log, Metadata, Record};
+use parking_lot::Mutex;
+
+use crate::{settings::StoredSettings, SETTINGS};
+
+/// Keeps the last log lines to put them into crash reports, passing all records on to the logger of the game.
+struct CrashLogger {
+    lines: Mutex<VecDeque<String>>,
+    capacity: usize,
+    inner: Option<Box<dyn Log>>,
+}
+
+impl Log for CrashLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner
+            .as_ref()
+            .is_none_or(|inner| inner.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if self.capacity > 0 {
+            let mut lines = self.lines.lock();
+            if lines.len() == self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(format!(
+                "[{} {}] {}",
+                record.level(),
+                record.target(),
+                record.args()
+            ));
+        }
+        if let Some(inner) = &self.inner {
+            inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+    }
+}
+
+static LOGGER: OnceLock<CrashLogger> = OnceLock::new();
+
+/// Writes a crash report to disk and shows a message box to the player whenever the game panics.
+///
+/// A report contains the panic message and location, a backtrace, the engine capabilities including the GPU
+/// and driver, the current engine settings and the last log lines.
+///
+/// To collect the log lines, the crash handler becomes the logger of the game and passes all records on to
+/// the logger given using [`logger`](CrashHandler::logger). Crashes outside of Rust, like segmentation faults
+/// in drivers, do not get reported.
+///
+/// # Usage
+/// ```ignore
+/// CrashHandler::new("my_game")
+///     .logger(Box::new(my_logger))
+///     .install()?;
+/// ```
+pub struct CrashHandler {
+    game: String,
+    directory: Option<PathBuf>,
+    log_lines: usize,
+    dialog: bool,
+    message: Option<String>,
+    logger: Option<Box<dyn Log>>,
+}
+
+impl CrashHandler {
+    /// Creates a crash handler for the game with the given name, saving reports next to its
+    /// [stored settings](StoredSettings::path) in a `crashes` directory.
+    pub fn new(game: impl Into<String>) -> Self {
+        Self {
+            game: game.into(),
+            directory: None,
+            log_lines: 200,
+            dialog: true,
+            message: None,
+            logger: None,
+        }
+    }
+
+    /// Sets the directory to save the reports in and returns self.
+    #[inline]
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Sets the number of the last log lines put into reports and returns self.
+    #[inline]
+    pub fn log_lines(mut self, log_lines: usize) -> Self {
+        self.log_lines = log_lines;
+        self
+    }
+
+    /// Sets whether a message box tells the player about the crash and returns self.
+    #[inline]
+    pub fn dialog(mut self, dialog: bool) -> Self {
+        self.dialog = dialog;
+        self
+    }
+
+    /// Sets the text of the message box and returns self. The path of the report gets added below it.
+    #[inline]
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Sets the logger all log records get passed on to and returns self.
+    #[inline]
+    pub fn logger(mut self, logger: Box<dyn Log>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Installs the panic hook and the logger.
+    ///
+    /// Fails in case a logger was set already, leaving the panic hook uninstalled.
+    pub fn install(self) -> Result<(), log::SetLoggerError> {
+        let logger = LOGGER.get_or_init(|| CrashLogger {
+            lines: Mutex::new(VecDeque::with_capacity(self.log_lines)),
+            capacity: self.log_lines,
+            inner: self.logger,
+        });
+        log::set_logger(logger)?;
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let directory = self
+            .directory
+            .or_else(|| Some(StoredSettings::path(&self.game)?.parent()?.join("crashes")))
+            .unwrap_or_else(|| PathBuf::from("crashes"));
+        let handler = Handler {
+            game: self.game,
+            directory,
+            dialog: self.dialog,
+            message: self.message,
+        };
+
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let payload = info.payload();
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("<no message>");
+            let location = info.location().map(ToString::to_string);
+            handler.handle(message, location.as_deref());
+            previous(info);
+        }));
+        Ok(())
+    }
+}
+
+struct Handler {
+    game: String,
+    directory: PathBuf,
+    dialog: bool,
+    message: Option<String>,
+}
+
+impl Handler {
+    fn handle(&self, message: &str, location: Option<&str>) {
+        let report = report(&self.game, message, location);
+        let path = match write_report(&self.directory, &report) {
+            Ok(path) => Some(path),
+            Err(error) => {
+                eprintln!("Failed to write the crash report: {error}");
+                None
+            }
+        };
+        if self.dialog {
+            // The panicking thread can be in the middle of the event loop or hold locks the dialog needs,
+            // so the dialog gets its own thread. Joining it keeps the process alive until the player closed it.
+            let text = self.dialog_text(path.as_deref());
+            let title = format!("{} crashed", self.game);
+            let dialog = std::thread::Builder::new()
+                .name("crash dialog".to_string())
+                .spawn(move || {
+                    let _ = native_dialog::MessageDialog::new()
+                        .set_type(native_dialog::MessageType::Error)
+                        .set_title(&title)
+                        .set_text(&text)
+                        .show_alert();
+                });
+            match dialog {
+                Ok(dialog) => {
+                    let _ = dialog.join();
+                }
+                Err(error) => eprintln!("Failed to show the crash dialog: {error}"),
+            }
+        }
+    }
+
+    fn dialog_text(&self, path: Option<&Path>) -> String {
+        let mut text = self.message.clone().unwrap_or_else(|| {
+            format!(
+                "{} crashed. Sorry about that!\nPlease send the crash report to the developers.",
+                self.game
+            )
+        });
+        if let Some(path) = path {
+            let _ = write!(text, "\n\n{}", path.display());
+        }
+        text
+    }
+}
+
+/// Formats the crash report of a panic.
+fn report(game: &str, message: &str, location: Option<&str>) -> String {
+    let mut report = String::new();
+    let thread = std::thread::current();
+    let _ = writeln!(report, "{game} crashed\n");
+    let _ = writeln!(
+        report,
+        "thread '{}' panicked: {message}",
+        thread.name().unwrap_or("<unnamed>")
+    );
+    if let Some(location) = location {
+        let _ = writeln!(report, "at {location}");
+    }
+
+    let _ = writeln!(
+        report,
+        "\n# Backtrace\n{}",
+        std::backtrace::Backtrace::force_capture()
+    );
+    let _ = writeln!(report, "# Engine\n{}", crate::capabilities());
+    let _ = writeln!(report, "# Settings");
+    let _ = writeln!(report, "{:#?}", SETTINGS.tick_system.get());
+    let graphics = &SETTINGS.graphics;
+    let _ = writeln!(
+        report,
+        "present mode: {:?}\nframe pacing: {:?}\nframerate limit: {:?}",
+        graphics.present_mode(),
+        graphics.frame_pacing(),
+        graphics.framerate_limit()
+    );
+
+    if let Some(logger) = LOGGER.get() {
+        let _ = writeln!(report, "\n# Log");
+        for line in logger.lines.lock().iter() {
+            let _ = writeln!(report, "{line}");
+        }
+    }
+    report
+}
+
+/// Writes the report into a new file named after the current time and process in the given directory.
+///
+/// Never overwrites an existing report, even if several threads crash within the same millisecond.
+fn write_report(directory: &Path, report: &str) -> io::Result<PathBuf> {
+    std::fs::create_dir_all(directory)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_millis());
+    let process = std::process::id();
+    for index in 0.. {
+        let name = if index == 0 {
+            format!("crash-{millis}-{process}.txt")
+        } else {
+            format!("crash-{millis}-{process}-{index}.txt")
+        };
+        let path = directory.join(name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(report.as_bytes())?;
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(error) => return Err(error),
+        }
+    }
+    unreachable!("There is an unused file name.")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_do_not_overwrite_each_other() {
+        let directory =
+            std::env::temp_dir().join(format!("let-engine-crashes-{}", std::process::id()));
+        let first = write_report(&directory, "first").unwrap();
+        let second = write_report(&directory, "second").unwrap();
+
+        assert_ne!(first, second);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
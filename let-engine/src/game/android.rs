@@ -0,0 +1,74 @@
+//! Running the game on Android, available with the `android` feature.
+//!
+//! The game gets started from the `android_main` function of the library, which hands the activity to the engine:
+//! ```ignore
+//! #[no_mangle]
+//! fn android_main(app: AndroidApp) {
+//!     let mut engine = Engine::new(EngineSettingsBuilder::default().android_app(app).build().unwrap()).unwrap();
+//!     engine.start(Game::new());
+//! }
+//! ```
+//!
+//! Android destroys the surface of the window while the game is in the background,
+//! so drawing stops between [`Event::Suspended`](crate::events::Event::Suspended) and
+//! [`Event::Resumed`](crate::events::Event::Resumed).
+//!
+//! Settings, save files and the pipeline cache get stored in the internal data directory of the app.
+
+use let_engine_core::EngineError;
+use std::{path::PathBuf, sync::OnceLock};
+pub use winit::platform::android::activity::AndroidApp;
+use winit::{
+    event::Event,
+    event_loop::EventLoop,
+    platform::{
+        android::EventLoopBuilderExtAndroid,
+        pump_events::{EventLoopExtPumpEvents, PumpStatus},
+    },
+};
+
+/// The internal data directory of the app, known once the activity started.
+static DATA_DIRECTORY: OnceLock<PathBuf> = OnceLock::new();
+
+/// Returns the internal data directory of the app.
+pub(crate) fn data_directory() -> Option<PathBuf> {
+    DATA_DIRECTORY.get().cloned()
+}
+
+/// Builds the event loop of the activity and waits for its window to become available.
+pub(crate) fn event_loop(app: Option<AndroidApp>) -> Result<EventLoop<()>, EngineError> {
+    let app = app.ok_or(EngineError::Other(anyhow::anyhow!(
+        "The engine settings need the Android app to start on Android."
+    )))?;
+
+    #[cfg(feature = "asset_system")]
+    asset_system::android::set_asset_manager(app.asset_manager());
+    if let Some(directory) = app.internal_data_path() {
+        // There is no executable name or home directory to find the default cache directory with.
+        if let_engine_core::resources::pipeline_cache_directory().is_none() {
+            let_engine_core::resources::set_pipeline_cache_directory(Some(directory.join("cache")));
+        }
+        let _ = DATA_DIRECTORY.set(directory);
+    }
+
+    let mut event_loop = winit::event_loop::EventLoopBuilder::new()
+        .with_android_app(app)
+        .build()
+        .map_err(|e| EngineError::Other(e.into()))?;
+
+    // The native window only exists after the activity got resumed.
+    let mut resumed = false;
+    while !resumed {
+        let status = event_loop.pump_events(None, |event, _| {
+            if let Event::Resumed = event {
+                resumed = true;
+            }
+        });
+        if let PumpStatus::Exit(_) = status {
+            return Err(EngineError::Other(anyhow::anyhow!(
+                "The activity got closed before its window was created."
+            )));
+        }
+    }
+    Ok(event_loop)
+}
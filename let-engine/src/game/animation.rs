@@ -0,0 +1,769 @@
+//! Tweening of object transforms and colors, flipping through texture frames and automating sounds over game time.
+
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use glam::Vec2;
+use let_engine_core::objects::{Object, Transform};
+#[cfg(feature = "client")]
+use let_engine_core::{objects::Color, resources::textures::AnimatedTexture};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "client")]
+use crate::Gradient;
+use crate::{Curve, SCHEDULER};
+
+type Callback = Box<dyn FnMut() + Send + 'static>;
+
+/// Easing functions shaping the progress of a tween.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Ease {
+    #[default]
+    Linear,
+    InQuad,
+    OutQuad,
+    InOutQuad,
+    InCubic,
+    OutCubic,
+    InOutCubic,
+    InSine,
+    OutSine,
+    InOutSine,
+    InExpo,
+    OutExpo,
+    InOutExpo,
+    /// Overshoots slightly backwards before moving to the target.
+    InBack,
+    /// Overshoots the target slightly before settling.
+    OutBack,
+    InOutBack,
+    /// Bounces off the target a few times like a dropped ball.
+    OutBounce,
+    /// Springs around the target before settling.
+    OutElastic,
+}
+
+impl Ease {
+    /// Every easing function in the order they are declared.
+    pub const ALL: &'static [Self] = &[
+        Self::Linear,
+        Self::InQuad,
+        Self::OutQuad,
+        Self::InOutQuad,
+        Self::InCubic,
+        Self::OutCubic,
+        Self::InOutCubic,
+        Self::InSine,
+        Self::OutSine,
+        Self::InOutSine,
+        Self::InExpo,
+        Self::OutExpo,
+        Self::InOutExpo,
+        Self::InBack,
+        Self::OutBack,
+        Self::InOutBack,
+        Self::OutBounce,
+        Self::OutElastic,
+    ];
+
+    /// Maps the linear progress `t` from 0.0 to 1.0 to the eased progress.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        const BACK: f32 = 1.70158;
+        match self {
+            Ease::Linear => t,
+            Ease::InQuad => t * t,
+            Ease::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Ease::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::InCubic => t.powi(3),
+            Ease::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Ease::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Ease::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Ease::OutSine => (t * PI / 2.0).sin(),
+            Ease::InOutSine => -((t * PI).cos() - 1.0) / 2.0,
+            Ease::InExpo => {
+                if t == 0.0 {
+                    0.0
+                } else {
+                    2f32.powf(10.0 * t - 10.0)
+                }
+            }
+            Ease::OutExpo => {
+                if t == 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2f32.powf(-10.0 * t)
+                }
+            }
+            Ease::InOutExpo => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else if t < 0.5 {
+                    2f32.powf(20.0 * t - 10.0) / 2.0
+                } else {
+                    (2.0 - 2f32.powf(-20.0 * t + 10.0)) / 2.0
+                }
+            }
+            Ease::InBack => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Ease::OutBack => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Ease::InOutBack => {
+                let c = BACK * 1.525;
+                if t < 0.5 {
+                    ((2.0 * t).powi(2) * ((c + 1.0) * 2.0 * t - c)) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((c + 1.0) * (t * 2.0 - 2.0) + c) + 2.0) / 2.0
+                }
+            }
+            Ease::OutBounce => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+            Ease::OutElastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+        }
+    }
+}
+
+/// A property of an object that can be tweened towards a target value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Position(Vec2),
+    Size(Vec2),
+    Rotation(f32),
+    #[cfg(feature = "client")]
+    Color(Color),
+}
+
+/// A value of a property at the start of a step.
+#[derive(Clone, Copy)]
+enum Start {
+    Vec2(Vec2),
+    Rotation(f32),
+    #[cfg(feature = "client")]
+    Color(Color),
+}
+
+impl Target {
+    fn start(&self, object: &Object) -> Start {
+        match self {
+            Target::Position(_) => Start::Vec2(object.transform.position),
+            Target::Size(_) => Start::Vec2(object.transform.size),
+            Target::Rotation(_) => Start::Rotation(object.transform.rotation),
+            #[cfg(feature = "client")]
+            Target::Color(_) => Start::Color(*object.appearance.get_color()),
+        }
+    }
+
+    fn apply(&self, start: Start, t: f32, object: &mut Object) {
+        let transform: &mut Transform = &mut object.transform;
+        match (self, start) {
+            (Target::Position(target), Start::Vec2(start)) => {
+                transform.position = start.lerp(*target, t)
+            }
+            (Target::Size(target), Start::Vec2(start)) => transform.size = start.lerp(*target, t),
+            (Target::Rotation(target), Start::Rotation(start)) => {
+                transform.rotation = start + (target - start) * t
+            }
+            #[cfg(feature = "client")]
+            (Target::Color(target), Start::Color(start)) => {
+                object.appearance.set_color(start.lerp(*target, t))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A property of an object following a [`Curve`] or [`Gradient`] over a step of an [`Animation`]
+/// instead of easing towards a target.
+///
+/// The curves get evaluated with the progress of the step from 0 to 1.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Keyframed {
+    /// Moves the object by the values of the curves on the x and y axis from where it was at the start of the step.
+    Offset(Curve, Curve),
+    /// Multiplies the size the object had at the start of the step with the value of the curve.
+    Scale(Curve),
+    /// Adds the value of the curve to the rotation the object had at the start of the step.
+    Rotation(Curve),
+    /// Sets the color of the object to the color of the gradient.
+    #[cfg(feature = "client")]
+    Color(Gradient),
+}
+
+impl Keyframed {
+    fn start(&self, object: &Object) -> Start {
+        match self {
+            Keyframed::Offset(..) => Start::Vec2(object.transform.position),
+            Keyframed::Scale(_) => Start::Vec2(object.transform.size),
+            Keyframed::Rotation(_) => Start::Rotation(object.transform.rotation),
+            #[cfg(feature = "client")]
+            Keyframed::Color(_) => Start::Color(*object.appearance.get_color()),
+        }
+    }
+
+    fn apply(&self, start: Start, t: f32, object: &mut Object) {
+        let transform: &mut Transform = &mut object.transform;
+        match (self, start) {
+            (Keyframed::Offset(x, y), Start::Vec2(start)) => {
+                transform.position = start + Vec2::new(x.evaluate(t), y.evaluate(t))
+            }
+            (Keyframed::Scale(curve), Start::Vec2(start)) => {
+                transform.size = start * curve.evaluate(t)
+            }
+            (Keyframed::Rotation(curve), Start::Rotation(start)) => {
+                transform.rotation = start + curve.evaluate(t)
+            }
+            #[cfg(feature = "client")]
+            (Keyframed::Color(gradient), Start::Color(_)) => {
+                object.appearance.set_color(gradient.evaluate(t))
+            }
+            _ => (),
+        }
+    }
+}
+
+/// A single part of an animation, running its targets at the same time.
+struct Step {
+    duration: f64,
+    ease: Ease,
+    targets: Vec<Target>,
+    /// Properties following curves, which shape the progress themselves instead of using the easing.
+    keyframed: Vec<Keyframed>,
+    callbacks: Vec<Callback>,
+}
+
+/// A chain of tweens played one after another on an object.
+///
+/// # Usage
+/// ```ignore
+/// Animation::new()
+///     .tween(Duration::from_millis(300), Ease::OutBack, [Target::Size(vec2(2.0, 2.0))])
+///     .tween(Duration::from_millis(500), Ease::InOutSine, [Target::Position(vec2(0.0, 1.0))])
+///     .then(|| println!("Arrived"))
+///     .start(&object);
+/// ```
+#[derive(Default)]
+pub struct Animation {
+    steps: Vec<Step>,
+    looping: bool,
+}
+
+impl Animation {
+    /// Creates a new empty animation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a step tweening all the given targets from their current values at the same time.
+    pub fn tween(
+        mut self,
+        duration: Duration,
+        ease: Ease,
+        targets: impl IntoIterator<Item = Target>,
+    ) -> Self {
+        self.steps.push(Step {
+            duration: duration.as_secs_f64(),
+            ease,
+            targets: targets.into_iter().collect(),
+            keyframed: vec![],
+            callbacks: vec![],
+        });
+        self
+    }
+
+    /// Appends a step moving all the given properties along their curves and gradients at the same time.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// // a hit flashing white and squashing the object
+    /// Animation::new()
+    ///     .keyframed(Duration::from_millis(200), [
+    ///         Keyframed::Color(Gradient::between(Color::WHITE, color)),
+    ///         Keyframed::Scale(Curve::new().key(0.0, 1.0, Ease::OutQuad).key(0.3, 0.7, Ease::OutBack).key(1.0, 1.0, Ease::Linear)),
+    ///     ])
+    ///     .start(&object);
+    /// ```
+    pub fn keyframed(
+        mut self,
+        duration: Duration,
+        keyframed: impl IntoIterator<Item = Keyframed>,
+    ) -> Self {
+        self.steps.push(Step {
+            duration: duration.as_secs_f64(),
+            ease: Ease::Linear,
+            targets: vec![],
+            keyframed: keyframed.into_iter().collect(),
+            callbacks: vec![],
+        });
+        self
+    }
+
+    /// Appends a step doing nothing for the given duration.
+    pub fn wait(self, duration: Duration) -> Self {
+        self.tween(duration, Ease::Linear, [])
+    }
+
+    /// Runs the given callback once the previously appended step has finished.
+    ///
+    /// Gets called at the start in case no steps were appended yet.
+    pub fn then(mut self, callback: impl FnMut() + Send + 'static) -> Self {
+        if self.steps.is_empty() {
+            self = self.wait(Duration::ZERO);
+        }
+        if let Some(step) = self.steps.last_mut() {
+            step.callbacks.push(Box::new(callback));
+        }
+        self
+    }
+
+    /// Restarts the animation from the first step every time it finishes.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Starts playing this animation on the given object.
+    ///
+    /// The object gets synced to its layer every time the animation gets updated.
+    /// The animation stops by itself in case the object gets removed.
+    ///
+    /// It runs on the time scale of the layer of the object and halts while the layer is paused.
+    pub fn start(self, object: &Object) -> AnimationHandle {
+        let finished = Arc::new(AtomicBool::new(false));
+        crate::ANIMATIONS.playing.lock().push(Playing {
+            object: object.clone(),
+            animation: self,
+            time: 0.0,
+            last_update: SCHEDULER.time(),
+            step: 0,
+            step_start: 0.0,
+            starts: None,
+            finished: finished.clone(),
+        });
+        AnimationHandle { finished }
+    }
+}
+
+/// An animation being played on an object.
+struct Playing {
+    object: Object,
+    animation: Animation,
+    /// The time the animation has been playing for, scaled by the time scale of the layer.
+    time: f64,
+    /// The game time of the previous update.
+    last_update: f64,
+    step: usize,
+    step_start: f64,
+    starts: Option<Vec<Start>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl Playing {
+    /// Advances the animation to the given game time. Returns false once it has finished.
+    fn update(&mut self, game_time: f64) -> bool {
+        if self.finished.load(Ordering::Acquire) || self.object.update().is_err() {
+            return false;
+        }
+        self.time += (game_time - self.last_update) * self.object.layer().effective_time_scale();
+        self.last_update = game_time;
+        let time = self.time;
+        loop {
+            let Some(step) = self.animation.steps.get_mut(self.step) else {
+                if self.animation.looping && !self.animation.steps.is_empty() {
+                    self.step = 0;
+                    continue;
+                }
+                return false;
+            };
+            let starts = self.starts.get_or_insert_with(|| {
+                step.targets
+                    .iter()
+                    .map(|target| target.start(&self.object))
+                    .chain(
+                        step.keyframed
+                            .iter()
+                            .map(|keyframed| keyframed.start(&self.object)),
+                    )
+                    .collect()
+            });
+
+            let elapsed = time - self.step_start;
+            let t = if step.duration > 0.0 {
+                (elapsed / step.duration).min(1.0) as f32
+            } else {
+                1.0
+            };
+            let eased = step.ease.apply(t);
+            let (target_starts, keyframed_starts) = starts.split_at(step.targets.len());
+            for (target, start) in step.targets.iter().zip(target_starts) {
+                target.apply(*start, eased, &mut self.object);
+            }
+            for (keyframed, start) in step.keyframed.iter().zip(keyframed_starts) {
+                keyframed.apply(*start, t, &mut self.object);
+            }
+            if self.object.sync().is_err() {
+                return false;
+            }
+            if t < 1.0 {
+                return true;
+            }
+
+            // Move on to the next step, carrying over the overshot time.
+            for callback in step.callbacks.iter_mut() {
+                callback();
+            }
+            self.step_start += step.duration;
+            self.step += 1;
+            self.starts = None;
+            if self.animation.looping && self.step >= self.animation.steps.len() {
+                // Prevent endless looping of animations without any duration.
+                if self.animation.steps.iter().all(|step| step.duration <= 0.0) {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+/// Flips through the layers of the texture of an object, showing each for its own time.
+///
+/// # Usage
+/// ```ignore
+/// let explosion = RESOURCE_CACHE.animated_texture("explosion.gif", TextureSettings::default()).await?;
+/// let material = Material::new_default_textured(explosion.texture())?;
+/// // ... spawn the object with the material
+/// FrameAnimation::from_texture(&explosion).looping(true).start(&object);
+/// ```
+#[cfg(feature = "client")]
+#[derive(Clone, Debug)]
+pub struct FrameAnimation {
+    delays: Arc<[Duration]>,
+    looping: bool,
+    speed: f64,
+}
+
+#[cfg(feature = "client")]
+impl FrameAnimation {
+    /// Creates an animation showing the layers from the first one on, each for the given time.
+    pub fn new(delays: impl Into<Arc<[Duration]>>) -> Self {
+        Self {
+            delays: delays.into(),
+            looping: false,
+            speed: 1.0,
+        }
+    }
+
+    /// Creates an animation with the frame timing of the given animated texture.
+    pub fn from_texture(texture: &AnimatedTexture) -> Self {
+        Self::new(texture.delays().clone())
+    }
+
+    /// Starts over from the first layer every time the last one was shown.
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Sets how many times faster than the frame delays the animation plays.
+    pub fn speed(mut self, speed: f64) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Starts flipping through the layers of the texture of the given object.
+    ///
+    /// Without looping the last layer stays shown once the animation finished.
+    /// It stops by itself in case the object gets removed and runs on the time scale of its layer like [`Animation`].
+    pub fn start(self, object: &Object) -> AnimationHandle {
+        let finished = Arc::new(AtomicBool::new(false));
+        crate::ANIMATIONS.frames.lock().push(PlayingFrames {
+            object: object.clone(),
+            animation: self,
+            time: 0.0,
+            last_update: SCHEDULER.time(),
+            finished: finished.clone(),
+        });
+        AnimationHandle { finished }
+    }
+
+    /// Returns the frame shown at the given time, `None` after the last frame of an animation not looping.
+    fn frame(&self, time: f64) -> Option<usize> {
+        let total: f64 = self.delays.iter().map(Duration::as_secs_f64).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut time = time * self.speed;
+        if self.looping {
+            time = time.rem_euclid(total);
+        } else if time >= total {
+            return None;
+        }
+        let mut end = 0.0;
+        self.delays.iter().position(|delay| {
+            end += delay.as_secs_f64();
+            time < end
+        })
+    }
+}
+
+/// A frame animation being played on an object.
+#[cfg(feature = "client")]
+struct PlayingFrames {
+    object: Object,
+    animation: FrameAnimation,
+    time: f64,
+    last_update: f64,
+    finished: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "client")]
+impl PlayingFrames {
+    /// Shows the frame of the given game time. Returns false once the animation has finished.
+    fn update(&mut self, game_time: f64) -> bool {
+        if self.finished.load(Ordering::Acquire) || self.object.update().is_err() {
+            return false;
+        }
+        self.time += (game_time - self.last_update) * self.object.layer().effective_time_scale();
+        self.last_update = game_time;
+        let (frame, running) = match self.animation.frame(self.time) {
+            Some(frame) => (frame, true),
+            None => (self.animation.delays.len().saturating_sub(1), false),
+        };
+        if self.object.appearance.layer() != Some(frame as u32) {
+            if self.object.appearance.set_layer(frame as u32).is_err() {
+                return false;
+            }
+            if self.object.sync().is_err() {
+                return false;
+            }
+        }
+        running
+    }
+}
+
+/// A parameter of a sound that can follow a curve using [`Curve::automate`].
+#[cfg(feature = "audio")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundParameter {
+    /// The volume as an amplitude, where 1 is the volume of the sound data.
+    Volume,
+    /// The playback rate as a factor of the original speed.
+    PlaybackRate,
+    /// The panning, where 0 is left and 1 is right.
+    Panning,
+}
+
+/// The time each new value of an automated sound parameter gets faded in over, so the steps between updates do not click.
+#[cfg(feature = "audio")]
+const AUTOMATION_SMOOTHING: Duration = Duration::from_millis(20);
+
+/// A sound parameter following a curve.
+#[cfg(feature = "audio")]
+struct PlayingAutomation {
+    sound: let_engine_audio::Sound,
+    parameter: SoundParameter,
+    curve: Curve,
+    duration: f64,
+    /// The game time the automation started at.
+    start: f64,
+    finished: Arc<AtomicBool>,
+}
+
+#[cfg(feature = "audio")]
+impl PlayingAutomation {
+    /// Sets the parameter to the value of the given game time. Returns false once the automation has finished.
+    fn update(&mut self, game_time: f64) -> bool {
+        use let_engine_audio::{Easing, PlaybackRate, Tween, Volume};
+
+        if self.finished.load(Ordering::Acquire) {
+            return false;
+        }
+        let t = if self.duration > 0.0 {
+            ((game_time - self.start) / self.duration).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        let value = self.curve.evaluate(t) as f64;
+        let tween = Tween {
+            duration: AUTOMATION_SMOOTHING,
+            easing: Easing::Linear,
+        };
+        match self.parameter {
+            SoundParameter::Volume => self.sound.set_volume(Volume::Amplitude(value), tween),
+            SoundParameter::PlaybackRate => self
+                .sound
+                .set_playback_rate(PlaybackRate::Factor(value), tween),
+            SoundParameter::Panning => self.sound.set_panning(value, tween),
+        }
+        t < 1.0
+    }
+}
+
+/// A handle to a playing animation.
+///
+/// Dropping this handle does not stop the animation.
+#[derive(Clone, Debug)]
+pub struct AnimationHandle {
+    finished: Arc<AtomicBool>,
+}
+
+impl AnimationHandle {
+    /// Stops the animation, leaving the object at its current state.
+    pub fn stop(&self) {
+        self.finished.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the animation has finished or got stopped.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+}
+
+/// Plays all started animations.
+///
+/// Animations run on the game time of the [`SCHEDULER`] and get updated by the engine loop.
+pub struct Animations {
+    playing: Mutex<Vec<Playing>>,
+    #[cfg(feature = "client")]
+    frames: Mutex<Vec<PlayingFrames>>,
+    #[cfg(feature = "audio")]
+    automations: Mutex<Vec<PlayingAutomation>>,
+}
+
+impl Animations {
+    pub(crate) fn new() -> Self {
+        Self {
+            playing: Mutex::new(vec![]),
+            #[cfg(feature = "client")]
+            frames: Mutex::new(vec![]),
+            #[cfg(feature = "audio")]
+            automations: Mutex::new(vec![]),
+        }
+    }
+
+    /// Returns the number of animations currently playing.
+    pub fn len(&self) -> usize {
+        #[cfg(feature = "client")]
+        let frames = self.frames.lock().len();
+        #[cfg(not(feature = "client"))]
+        let frames = 0;
+        #[cfg(feature = "audio")]
+        let automations = self.automations.lock().len();
+        #[cfg(not(feature = "audio"))]
+        let automations = 0;
+        self.playing.lock().len() + frames + automations
+    }
+
+    /// Starts moving the parameter of the sound along the curve.
+    #[cfg(feature = "audio")]
+    pub(crate) fn automate(
+        &self,
+        sound: let_engine_audio::Sound,
+        parameter: SoundParameter,
+        curve: Curve,
+        duration: Duration,
+    ) -> AnimationHandle {
+        let finished = Arc::new(AtomicBool::new(false));
+        self.automations.lock().push(PlayingAutomation {
+            sound,
+            parameter,
+            curve,
+            duration: duration.as_secs_f64(),
+            start: SCHEDULER.time(),
+            finished: finished.clone(),
+        });
+        AnimationHandle { finished }
+    }
+
+    /// Returns true if no animations are playing.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Stops all playing animations.
+    pub fn stop_all(&self) {
+        for playing in self.playing.lock().drain(..) {
+            playing.finished.store(true, Ordering::Release);
+        }
+        #[cfg(feature = "client")]
+        for playing in self.frames.lock().drain(..) {
+            playing.finished.store(true, Ordering::Release);
+        }
+        #[cfg(feature = "audio")]
+        for playing in self.automations.lock().drain(..) {
+            playing.finished.store(true, Ordering::Release);
+        }
+    }
+
+    /// Advances all animations to the current game time.
+    pub(crate) fn update(&self) {
+        let time = SCHEDULER.time();
+        // Take the animations out, so callbacks are able to start new animations.
+        let mut playing = std::mem::take(&mut *self.playing.lock());
+        playing.retain_mut(|playing| {
+            let running = playing.update(time);
+            if !running {
+                playing.finished.store(true, Ordering::Release);
+            }
+            running
+        });
+        let mut lock = self.playing.lock();
+        playing.append(&mut lock);
+        *lock = playing;
+        drop(lock);
+
+        #[cfg(feature = "client")]
+        self.frames.lock().retain_mut(|playing| {
+            let running = playing.update(time);
+            if !running {
+                playing.finished.store(true, Ordering::Release);
+            }
+            running
+        });
+
+        #[cfg(feature = "audio")]
+        self.automations.lock().retain_mut(|playing| {
+            let running = playing.update(time);
+            if !running {
+                playing.finished.store(true, Ordering::Release);
+            }
+            running
+        });
+    }
+}
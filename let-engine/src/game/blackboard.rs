@@ -0,0 +1,143 @@
+//! A shared key value store for loosely coupling game systems.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::{Mutex, RwLock};
+
+type Watcher = Arc<dyn Fn(&str) + Send + Sync + 'static>;
+
+/// A thread safe store of typed values by name, shared between systems like AI, UI and scripts.
+///
+/// Values of any type can be stored. Reading a value with a different type than it was stored with returns `None`.
+///
+/// # Usage
+/// ```ignore
+/// BLACKBOARD.set("player_health", 100u32);
+/// BLACKBOARD.watch("player_health", |key| {
+///     let health: u32 = BLACKBOARD.get(key).unwrap_or_default();
+///     // update the health bar
+/// });
+/// ```
+pub struct Blackboard {
+    values: RwLock<HashMap<String, Box<dyn Any + Send + Sync>>>,
+    watchers: Mutex<Vec<(u64, String, Watcher)>>,
+    next_watcher: AtomicU64,
+}
+
+/// The identification of a watcher of a blackboard entry, used to stop watching.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchId(u64);
+
+impl Blackboard {
+    pub(crate) fn new() -> Self {
+        Self {
+            values: RwLock::new(HashMap::new()),
+            watchers: Mutex::new(vec![]),
+            next_watcher: AtomicU64::new(0),
+        }
+    }
+
+    /// Stores the value under the given key, replacing the old value, and notifies the watchers of the key.
+    pub fn set<T: Any + Send + Sync>(&self, key: impl Into<String>, value: T) {
+        let key = key.into();
+        self.values.write().insert(key.clone(), Box::new(value));
+        self.notify(&key);
+    }
+
+    /// Returns a copy of the value under the given key in case it exists with the requested type.
+    pub fn get<T: Any + Clone>(&self, key: &str) -> Option<T> {
+        self.with(key, T::clone)
+    }
+
+    /// Runs the closure on a reference to the value under the given key in case it exists with the requested type.
+    ///
+    /// The blackboard can not be written to inside the closure.
+    pub fn with<T: Any, R>(&self, key: &str, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.values
+            .read()
+            .get(key)
+            .and_then(|value| value.downcast_ref::<T>())
+            .map(f)
+    }
+
+    /// Modifies the value under the given key in place and notifies the watchers of the key.
+    ///
+    /// Returns `None` without notifying anyone in case there is no value with the requested type.
+    pub fn update<T: Any, R>(&self, key: &str, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let result = self
+            .values
+            .write()
+            .get_mut(key)
+            .and_then(|value| value.downcast_mut::<T>())
+            .map(f);
+        if result.is_some() {
+            self.notify(key);
+        }
+        result
+    }
+
+    /// Returns true if there is a value under the given key.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.read().contains_key(key)
+    }
+
+    /// Removes the value under the given key and notifies the watchers of the key in case there was one.
+    ///
+    /// Returns the value in case it had the requested type.
+    pub fn remove<T: Any>(&self, key: &str) -> Option<T> {
+        let value = self.values.write().remove(key)?;
+        self.notify(key);
+        value.downcast::<T>().ok().map(|value| *value)
+    }
+
+    /// Returns the keys of all stored values.
+    pub fn keys(&self) -> Vec<String> {
+        self.values.read().keys().cloned().collect()
+    }
+
+    /// Removes all values without notifying the watchers.
+    pub fn clear(&self) {
+        self.values.write().clear();
+    }
+
+    /// Calls the given closure with the key every time the value under it gets set, updated or removed.
+    ///
+    /// The closure runs on the thread changing the value, after the change got applied,
+    /// so it can read and write the blackboard.
+    pub fn watch(
+        &self,
+        key: impl Into<String>,
+        f: impl Fn(&str) + Send + Sync + 'static,
+    ) -> WatchId {
+        let id = self.next_watcher.fetch_add(1, Ordering::Relaxed);
+        self.watchers.lock().push((id, key.into(), Arc::new(f)));
+        WatchId(id)
+    }
+
+    /// Stops calling the watcher with the given ID.
+    pub fn unwatch(&self, id: WatchId) {
+        self.watchers
+            .lock()
+            .retain(|(watcher, _, _)| *watcher != id.0);
+    }
+
+    fn notify(&self, key: &str) {
+        let watchers: Vec<Watcher> = self
+            .watchers
+            .lock()
+            .iter()
+            .filter(|(_, watched, _)| watched == key)
+            .map(|(_, _, watcher)| watcher.clone())
+            .collect();
+        for watcher in watchers {
+            watcher(key);
+        }
+    }
+}
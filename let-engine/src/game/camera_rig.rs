@@ -0,0 +1,205 @@
+//! Smooth following, zooming and rotating of camera objects.
+//!
+//! # Usage
+//! ```ignore
+//! let mut rig = CameraRig::new(camera).damping(8.0).dead_zone(vec2(0.2, 0.1));
+//!
+//! // in the update function
+//! rig.follow(player.transform.position);
+//! rig.update(TIME.delta_time() as f32)?;
+//! ```
+
+use std::sync::Arc;
+
+use glam::Vec2;
+use let_engine_core::objects::{scenes::Layer, Object, ObjectError};
+
+use crate::Ease;
+
+/// A value moving from one value to another over time.
+#[derive(Clone, Copy, Debug)]
+struct Transition {
+    from: f32,
+    to: f32,
+    elapsed: f32,
+    duration: f32,
+    ease: Ease,
+}
+
+impl Transition {
+    /// Advances the transition and returns the current value along with whether it is finished.
+    fn advance(&mut self, delta: f32) -> (f32, bool) {
+        self.elapsed += delta;
+        if self.elapsed >= self.duration {
+            return (self.to, true);
+        }
+        let t = self.ease.apply(self.elapsed / self.duration);
+        (self.from + (self.to - self.from) * t, false)
+    }
+}
+
+/// Moves a camera object towards a target and tweens the zoom and rotation of its view.
+///
+/// The rig does not run on its own. Call [`update`](CameraRig::update) every frame.
+pub struct CameraRig {
+    camera: Object,
+    target: Option<Vec2>,
+    damping: f32,
+    dead_zone: Vec2,
+    offset: Vec2,
+    zoom: Option<Transition>,
+    rotation: Option<Transition>,
+}
+
+impl CameraRig {
+    /// Creates a rig controlling the given camera object.
+    ///
+    /// The object should be the camera of its layer.
+    pub fn new(camera: Object) -> Self {
+        Self {
+            camera,
+            target: None,
+            damping: 0.0,
+            dead_zone: Vec2::ZERO,
+            offset: Vec2::ZERO,
+            zoom: None,
+            rotation: None,
+        }
+    }
+
+    /// Sets how fast the camera catches up with the target. Higher values follow tighter, 0 follows instantly.
+    ///
+    /// ## Default configuration
+    ///
+    /// 0.0
+    pub fn damping(mut self, damping: f32) -> Self {
+        self.damping = damping.max(0.0);
+        self
+    }
+
+    /// Sets the half size of the area around the camera the target can move in without the camera following.
+    ///
+    /// ## Default configuration
+    ///
+    /// No dead zone.
+    pub fn dead_zone(mut self, dead_zone: Vec2) -> Self {
+        self.dead_zone = dead_zone.abs();
+        self
+    }
+
+    /// Sets an offset from the target the camera looks at, for example to show more of the way ahead.
+    pub fn offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Changes the follow speed. See [`damping`](CameraRig::damping).
+    pub fn set_damping(&mut self, damping: f32) {
+        self.damping = damping.max(0.0);
+    }
+
+    /// Changes the dead zone. See [`dead_zone`](CameraRig::dead_zone).
+    pub fn set_dead_zone(&mut self, dead_zone: Vec2) {
+        self.dead_zone = dead_zone.abs();
+    }
+
+    /// Changes the offset from the target.
+    pub fn set_offset(&mut self, offset: Vec2) {
+        self.offset = offset;
+    }
+
+    /// Returns the controlled camera object.
+    pub fn camera(&self) -> &Object {
+        &self.camera
+    }
+
+    /// Returns the layer of the camera.
+    pub fn layer(&self) -> &Arc<Layer> {
+        self.camera.layer()
+    }
+
+    /// Sets the position the camera should move to.
+    pub fn follow(&mut self, target: Vec2) {
+        self.target = Some(target);
+    }
+
+    /// Stops following the target and keeps the camera where it is.
+    pub fn stop_following(&mut self) {
+        self.target = None;
+    }
+
+    /// Moves the camera to the target immediately, skipping the damping and dead zone.
+    pub fn snap_to(&mut self, target: Vec2) -> Result<(), ObjectError> {
+        self.target = Some(target);
+        self.camera.transform.position = target + self.offset;
+        self.camera.sync()
+    }
+
+    /// Tweens the zoom of the layer to the given value over the duration in seconds.
+    pub fn zoom_to(&mut self, zoom: f32, duration: f32, ease: Ease) {
+        self.zoom = Some(Transition {
+            from: self.layer().zoom(),
+            to: zoom,
+            elapsed: 0.0,
+            duration,
+            ease,
+        });
+    }
+
+    /// Tweens the rotation of the camera to the given angle in radians over the duration in seconds.
+    pub fn rotate_to(&mut self, rotation: f32, duration: f32, ease: Ease) {
+        self.rotation = Some(Transition {
+            from: self.camera.transform.rotation,
+            to: rotation,
+            elapsed: 0.0,
+            duration,
+            ease,
+        });
+    }
+
+    /// Shakes the view of the camera with the given intensity in world units, fading out over the duration.
+    ///
+    /// Shares the shake of [`time_effects::screen_shake`](crate::time_effects::screen_shake).
+    #[cfg(feature = "client")]
+    pub fn shake(&self, intensity: f32, duration: std::time::Duration) {
+        crate::time_effects::screen_shake(self.layer(), intensity, duration);
+    }
+
+    /// Advances following and tweening by the given time in seconds and syncs the camera.
+    pub fn update(&mut self, delta: f32) -> Result<(), ObjectError> {
+        self.camera.update()?;
+        let mut position = self.camera.transform.position;
+
+        if let Some(target) = self.target {
+            let target = target + self.offset;
+            // Only move as far as needed to bring the target back to the edge of the dead zone.
+            let distance = target - position;
+            let excess = distance.signum() * (distance.abs() - self.dead_zone).max(Vec2::ZERO);
+            let goal = position + excess;
+            position = if self.damping > 0.0 {
+                position.lerp(goal, 1.0 - (-self.damping * delta).exp())
+            } else {
+                goal
+            };
+        }
+        self.camera.transform.position = position;
+
+        if let Some(rotation) = self.rotation.as_mut() {
+            let (value, finished) = rotation.advance(delta);
+            self.camera.transform.rotation = value;
+            if finished {
+                self.rotation = None;
+            }
+        }
+
+        if let Some(zoom) = self.zoom.as_mut() {
+            let (value, finished) = zoom.advance(delta);
+            self.camera.layer().set_zoom(value);
+            if finished {
+                self.zoom = None;
+            }
+        }
+
+        self.camera.sync()
+    }
+}
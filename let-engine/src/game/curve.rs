@@ -0,0 +1,512 @@
+//! Keyframed values and colors over a progress from 0 to 1.
+//!
+//! They get played on objects using [`Keyframed`](crate::Keyframed) animation steps,
+//! automate sounds using [`Curve::automate`] and can be edited at runtime in the curves window of the inspector.
+
+use anyhow::Result;
+#[cfg(feature = "egui")]
+use egui_winit_vulkano::egui;
+#[cfg(feature = "client")]
+use let_engine_core::objects::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::Ease;
+#[cfg(feature = "audio")]
+use crate::{AnimationHandle, SoundParameter};
+
+/// A value of a [`Curve`] at a point in time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CurveKey {
+    /// The time of the key, usually from 0 to 1.
+    pub t: f32,
+    pub value: f32,
+    /// How the value moves from this key to the next one.
+    pub ease: Ease,
+}
+
+/// A number changing over time, interpolated between keys with their easing.
+///
+/// Before the first key the curve has the value of the first key and after the last key the value of the last key.
+/// Curves can be stored as assets using [`to_bytes`](Curve::to_bytes) and loaded with [`from_bytes`](Curve::from_bytes).
+///
+/// # Usage
+/// ```ignore
+/// // a particle growing quickly and shrinking slowly over its lifetime
+/// let size = Curve::new()
+///     .key(0.0, 0.0, Ease::OutBack)
+///     .key(0.2, 1.0, Ease::InQuad)
+///     .key(1.0, 0.0, Ease::Linear);
+///
+/// let scale = size.evaluate(age / lifetime);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Curve {
+    /// Keys sorted by time.
+    keys: Vec<CurveKey>,
+}
+
+impl Curve {
+    /// Creates a curve without keys, being 0 everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a curve having the same value everywhere.
+    pub fn constant(value: f32) -> Self {
+        Self::new().key(0.0, value, Ease::Linear)
+    }
+
+    /// Creates a curve going from one value at 0 to another at 1 with the given easing.
+    pub fn between(from: f32, to: f32, ease: Ease) -> Self {
+        Self::new().key(0.0, from, ease).key(1.0, to, Ease::Linear)
+    }
+
+    /// Adds a key and returns self.
+    #[inline]
+    pub fn key(mut self, t: f32, value: f32, ease: Ease) -> Self {
+        self.insert(CurveKey { t, value, ease });
+        self
+    }
+
+    /// Adds a key at its place in time, after keys of the same time.
+    pub fn insert(&mut self, key: CurveKey) {
+        let index = self.keys.partition_point(|other| other.t <= key.t);
+        self.keys.insert(index, key);
+    }
+
+    /// Removes the key at the given index and returns it.
+    pub fn remove(&mut self, index: usize) -> Option<CurveKey> {
+        (index < self.keys.len()).then(|| self.keys.remove(index))
+    }
+
+    /// Returns the keys sorted by time.
+    pub fn keys(&self) -> &[CurveKey] {
+        &self.keys
+    }
+
+    /// Replaces every key, sorting them by time.
+    pub fn set_keys(&mut self, keys: Vec<CurveKey>) {
+        self.keys = keys;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    /// Returns the value at the given time.
+    pub fn evaluate(&self, t: f32) -> f32 {
+        let (a, b, progress) = match segment(&self.keys, |key| key.t, t) {
+            Segment::Empty => return 0.0,
+            Segment::Key(index) => return self.keys[index].value,
+            Segment::Between(a, b, progress) => (&self.keys[a], &self.keys[b], progress),
+        };
+        a.value + (b.value - a.value) * a.ease.apply(progress)
+    }
+
+    /// Returns the lowest and highest value of the keys, `None` without keys.
+    ///
+    /// Easings overshooting their target, like [`Ease::OutBack`], can go slightly past this range.
+    pub fn range(&self) -> Option<(f32, f32)> {
+        self.keys.iter().fold(None, |range, key| {
+            let (min, max) = range.unwrap_or((key.value, key.value));
+            Some((min.min(key.value), max.max(key.value)))
+        })
+    }
+
+    /// Loads a curve from its binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut curve: Self = bincode::deserialize(bytes)?;
+        curve.sort();
+        Ok(curve)
+    }
+
+    /// Returns the binary representation of this curve.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Loads a curve stored in the assets using [`to_bytes`](Curve::to_bytes).
+    #[cfg(feature = "asset_system")]
+    pub async fn load(path: &str) -> Result<Self> {
+        Self::from_bytes(&asset_system::asset(path).await?)
+    }
+
+    /// Moves the given parameter of the sound along this curve over the given duration of game time,
+    /// evaluating the curve from 0 at the start to 1 at the end.
+    ///
+    /// The parameter keeps the value of the end of the curve afterwards.
+    /// Only changes the sound while it is playing, played by [`ANIMATIONS`](crate::ANIMATIONS).
+    ///
+    /// # Usage
+    /// ```ignore
+    /// // a swell fading in slowly and cutting off quickly
+    /// Curve::new()
+    ///     .key(0.0, 0.0, Ease::InQuad)
+    ///     .key(0.8, 1.0, Ease::OutExpo)
+    ///     .key(1.0, 0.0, Ease::Linear)
+    ///     .automate(&sound, SoundParameter::Volume, Duration::from_secs(4));
+    /// ```
+    #[cfg(feature = "audio")]
+    pub fn automate(
+        &self,
+        sound: &let_engine_audio::Sound,
+        parameter: SoundParameter,
+        duration: std::time::Duration,
+    ) -> AnimationHandle {
+        crate::ANIMATIONS.automate(sound.clone(), parameter, self.clone(), duration)
+    }
+
+    /// Shows a graph of the curve along with fields for editing its keys.
+    ///
+    /// The response is marked as changed whenever a key got edited, added or removed.
+    #[cfg(feature = "egui")]
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let (mut response, painter) =
+            ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let (start, end) = time_range(&self.keys, |key| key.t);
+        let (min, max) = self.range().unwrap_or((0.0, 1.0));
+        let (min, max) = if max - min > f32::EPSILON {
+            (min, max)
+        } else {
+            (min - 0.5, max + 0.5)
+        };
+        let to_screen = |t: f32, value: f32| {
+            egui::pos2(
+                egui::lerp(rect.x_range(), (t - start) / (end - start)),
+                egui::lerp(rect.bottom()..=rect.top(), (value - min) / (max - min)),
+            )
+        };
+        let points = (0..=rect.width().max(2.0) as usize)
+            .map(|x| {
+                let t = start + (end - start) * x as f32 / rect.width().max(2.0);
+                to_screen(t, self.evaluate(t))
+            })
+            .collect();
+        let stroke = ui.visuals().widgets.active.fg_stroke;
+        painter.add(egui::Shape::line(points, stroke));
+        for key in &self.keys {
+            painter.circle_filled(to_screen(key.t, key.value), 3.0, stroke.color);
+        }
+
+        let mut changed = false;
+        let mut remove = None;
+        for (index, key) in self.keys.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(egui::DragValue::new(&mut key.t).speed(0.01).prefix("t: "))
+                    .changed();
+                changed |= ui
+                    .add(egui::DragValue::new(&mut key.value).speed(0.01))
+                    .changed();
+                let id = ui.id().with(index);
+                changed |= ease_ui(ui, id, &mut key.ease);
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.keys.remove(index);
+            changed = true;
+        }
+        if ui.button("Add key").clicked() {
+            let t = self.keys.last().map_or(0.0, |key| key.t + 0.1);
+            let value = self.evaluate(t);
+            self.insert(CurveKey {
+                t,
+                value,
+                ease: Ease::Linear,
+            });
+            changed = true;
+        }
+        if changed {
+            self.sort();
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// A color of a [`Gradient`] at a point in time.
+#[cfg(feature = "client")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// The time of the stop, usually from 0 to 1.
+    pub t: f32,
+    /// The color in red, green, blue and alpha.
+    pub color: [f32; 4],
+}
+
+/// A color changing over time, linearly blended between stops.
+///
+/// Before the first stop the gradient has the color of the first stop and after the last stop the color of the last stop.
+/// Gradients can be stored as assets using [`to_bytes`](Gradient::to_bytes) and loaded with [`from_bytes`](Gradient::from_bytes).
+///
+/// # Usage
+/// ```ignore
+/// // fire fading from yellow over red to transparent
+/// let fire = Gradient::new()
+///     .stop(0.0, Color::from_rgb(1.0, 0.9, 0.2))
+///     .stop(0.5, Color::RED)
+///     .stop(1.0, Color::from_rgba(0.2, 0.0, 0.0, 0.0));
+///
+/// particle.appearance.set_color(fire.evaluate(age / lifetime));
+/// ```
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    /// Stops sorted by time.
+    stops: Vec<GradientStop>,
+}
+
+#[cfg(feature = "client")]
+impl Gradient {
+    /// Creates a gradient without stops, being white everywhere.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a gradient going from one color at 0 to another at 1.
+    pub fn between(from: Color, to: Color) -> Self {
+        Self::new().stop(0.0, from).stop(1.0, to)
+    }
+
+    /// Adds a stop and returns self.
+    #[inline]
+    pub fn stop(mut self, t: f32, color: Color) -> Self {
+        self.insert(GradientStop {
+            t,
+            color: color.rgba(),
+        });
+        self
+    }
+
+    /// Adds a stop at its place in time, after stops of the same time.
+    pub fn insert(&mut self, stop: GradientStop) {
+        let index = self.stops.partition_point(|other| other.t <= stop.t);
+        self.stops.insert(index, stop);
+    }
+
+    /// Removes the stop at the given index and returns it.
+    pub fn remove(&mut self, index: usize) -> Option<GradientStop> {
+        (index < self.stops.len()).then(|| self.stops.remove(index))
+    }
+
+    /// Returns the stops sorted by time.
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+
+    /// Replaces every stop, sorting them by time.
+    pub fn set_stops(&mut self, stops: Vec<GradientStop>) {
+        self.stops = stops;
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        self.stops.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    /// Returns the color at the given time.
+    pub fn evaluate(&self, t: f32) -> Color {
+        match segment(&self.stops, |stop| stop.t, t) {
+            Segment::Empty => Color::WHITE,
+            Segment::Key(index) => Color::from(self.stops[index].color),
+            Segment::Between(a, b, progress) => {
+                Color::from(self.stops[a].color).lerp(Color::from(self.stops[b].color), progress)
+            }
+        }
+    }
+
+    /// Loads a gradient from its binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut gradient: Self = bincode::deserialize(bytes)?;
+        gradient.sort();
+        Ok(gradient)
+    }
+
+    /// Returns the binary representation of this gradient.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Loads a gradient stored in the assets using [`to_bytes`](Gradient::to_bytes).
+    #[cfg(feature = "asset_system")]
+    pub async fn load(path: &str) -> Result<Self> {
+        Self::from_bytes(&asset_system::asset(path).await?)
+    }
+
+    /// Shows a strip of the gradient along with fields for editing its stops.
+    ///
+    /// The response is marked as changed whenever a stop got edited, added or removed.
+    #[cfg(feature = "egui")]
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        let (mut response, painter) =
+            ui.allocate_painter(egui::vec2(ui.available_width(), 20.0), egui::Sense::hover());
+        let rect = response.rect;
+        let (start, end) = time_range(&self.stops, |stop| stop.t);
+        let columns = rect.width().max(1.0) as usize;
+        for column in 0..columns {
+            let x = column as f32 / columns as f32;
+            let [r, g, b, a] = self.evaluate(start + (end - start) * x).rgba();
+            let left = rect.left() + rect.width() * x;
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(
+                    left..=left + rect.width() / columns as f32 + 0.5,
+                    rect.y_range(),
+                ),
+                0.0,
+                egui::Rgba::from_rgba_unmultiplied(r, g, b, a),
+            );
+        }
+
+        let mut changed = false;
+        let mut remove = None;
+        for (index, stop) in self.stops.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(egui::DragValue::new(&mut stop.t).speed(0.01).prefix("t: "))
+                    .changed();
+                changed |= ui
+                    .color_edit_button_rgba_unmultiplied(&mut stop.color)
+                    .changed();
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.stops.remove(index);
+            changed = true;
+        }
+        if ui.button("Add stop").clicked() {
+            let t = self.stops.last().map_or(0.0, |stop| stop.t + 0.1);
+            let color = self.evaluate(t).rgba();
+            self.insert(GradientStop { t, color });
+            changed = true;
+        }
+        if changed {
+            self.sort();
+            response.mark_changed();
+        }
+        response
+    }
+}
+
+/// Where a time lies in a list of keys sorted by time.
+enum Segment {
+    Empty,
+    /// Before the first key, after the last key or exactly at a key.
+    Key(usize),
+    /// Between the keys of the two indices, with the progress from 0 to 1 from the first to the second.
+    Between(usize, usize, f32),
+}
+
+/// Finds the keys around the given time using a binary search.
+fn segment<K>(keys: &[K], time: impl Fn(&K) -> f32, t: f32) -> Segment {
+    let next = keys.partition_point(|key| time(key) <= t);
+    if keys.is_empty() {
+        Segment::Empty
+    } else if next == 0 {
+        Segment::Key(0)
+    } else if next == keys.len() {
+        Segment::Key(next - 1)
+    } else {
+        let (start, end) = (time(&keys[next - 1]), time(&keys[next]));
+        let span = end - start;
+        if span > 0.0 {
+            Segment::Between(next - 1, next, ((t - start) / span).clamp(0.0, 1.0))
+        } else {
+            Segment::Key(next)
+        }
+    }
+}
+
+/// Returns the time range shown by the editors, at least 0 to 1.
+#[cfg(feature = "egui")]
+fn time_range<K>(keys: &[K], time: impl Fn(&K) -> f32) -> (f32, f32) {
+    let start = keys.first().map_or(0.0, &time).min(0.0);
+    let end = keys.last().map_or(1.0, &time).max(1.0);
+    (start, end)
+}
+
+/// Shows a selection of every easing.
+#[cfg(feature = "egui")]
+fn ease_ui(ui: &mut egui::Ui, id: egui::Id, ease: &mut Ease) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_id_source(id)
+        .selected_text(format!("{ease:?}"))
+        .show_ui(ui, |ui| {
+            for option in Ease::ALL {
+                changed |= ui
+                    .selectable_value(ease, *option, format!("{option:?}"))
+                    .changed();
+            }
+        });
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_hold_their_ends_and_ease_between_keys() {
+        assert_eq!(Curve::new().evaluate(0.5), 0.0);
+        assert_eq!(Curve::constant(3.0).evaluate(-1.0), 3.0);
+
+        let curve = Curve::new()
+            .key(1.0, 0.0, Ease::Linear)
+            .key(0.0, 2.0, Ease::InQuad);
+        assert_eq!(curve.keys()[0].t, 0.0);
+        assert_eq!(curve.evaluate(-1.0), 2.0);
+        assert_eq!(curve.evaluate(0.5), 1.5);
+        assert_eq!(curve.evaluate(2.0), 0.0);
+        assert_eq!(curve.range(), Some((0.0, 2.0)));
+    }
+
+    #[test]
+    fn keys_at_the_same_time_jump() {
+        let curve = Curve::new()
+            .key(0.0, 0.0, Ease::Linear)
+            .key(0.5, 1.0, Ease::Linear)
+            .key(0.5, 5.0, Ease::Linear)
+            .key(1.0, 5.0, Ease::Linear);
+        assert_eq!(curve.evaluate(0.25), 0.5);
+        assert_eq!(curve.evaluate(0.5), 5.0);
+        assert_eq!(curve.evaluate(0.75), 5.0);
+    }
+
+    #[test]
+    fn loaded_curves_get_sorted() {
+        let curve = Curve {
+            keys: vec![
+                CurveKey {
+                    t: 1.0,
+                    value: 1.0,
+                    ease: Ease::Linear,
+                },
+                CurveKey::default(),
+            ],
+        };
+        let loaded = Curve::from_bytes(&curve.to_bytes().unwrap()).unwrap();
+        assert_eq!(loaded.keys()[0].t, 0.0);
+        assert_eq!(loaded.evaluate(0.5), 0.5);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn gradients_blend_between_stops() {
+        assert_eq!(Gradient::new().evaluate(0.5), Color::WHITE);
+
+        let gradient = Gradient::between(Color::BLACK, Color::WHITE);
+        assert_eq!(gradient.evaluate(-1.0), Color::BLACK);
+        assert_eq!(gradient.evaluate(2.0), Color::WHITE);
+        let [r, g, b, a] = gradient.evaluate(0.25).rgba();
+        assert_eq!([r, g, b], [0.25; 3]);
+        assert_eq!(a, 1.0);
+    }
+}
@@ -1,19 +1,26 @@
 use let_engine_core::{draw::Draw, resources::resources};
 
 use egui_winit_vulkano::{Gui, GuiConfig};
-use winit::event_loop::EventLoop;
+use winit::event_loop::EventLoopWindowTarget;
 
-pub(crate) fn init(draw: &Draw, event_loop: &EventLoop<()>) -> Gui {
+pub(crate) fn init(draw: &Draw, event_loop: &EventLoopWindowTarget<()>) -> Gui {
     let vulkan = resources().unwrap().vulkan();
-    Gui::new_with_subpass(
+    // The overlay subpass egui gets drawn in draws to the window image,
+    // even when the scene gets drawn to an HDR image first.
+    let format = vulkan.surface_format.0;
+    let gui = Gui::new_with_subpass(
         event_loop,
         draw.surface.clone(),
         vulkan.queue.clone(),
-        vulkan.subpass.clone(),
-        draw.swapchain.image_format(),
+        vulkan.overlay_subpass.clone(),
+        format,
         GuiConfig {
             allow_srgb_render_target: true,
             ..Default::default()
         },
-    )
+    );
+    // Animations and other repaints egui asks for have to wake up waiting control flows.
+    gui.context()
+        .set_request_repaint_callback(|info| super::wake::redraw_after(info.delay));
+    gui
 }
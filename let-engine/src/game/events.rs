@@ -6,7 +6,7 @@ use crate::prelude::*;
 #[cfg(feature = "egui")]
 use egui_winit_vulkano::egui::Context;
 use winit::dpi;
-pub use winit::event::{ElementState, MouseButton};
+pub use winit::event::{ElementState, MouseButton, TouchPhase};
 pub use winit::keyboard::*;
 
 /// Describes an event coming from the event loop.
@@ -24,6 +24,31 @@ pub enum Event {
     Destroyed,
     /// The application has received a low memory warning.
     LowMemory,
+    /// The application went to the background, for example on Android after switching to another app.
+    ///
+    /// Nothing gets drawn until [`Resumed`](Event::Resumed), a good moment to save the game and pause it.
+    Suspended,
+    /// The application came back to the foreground after [`Suspended`](Event::Suspended) and draws again.
+    Resumed,
+    /// The GPU stopped working, for example after a driver crash or reset, and the engine rebuilt the device.
+    ///
+    /// Textures and models got reloaded on the new device. Materials with custom written descriptors,
+    /// materials made from shader modules, uniform buffers, pipelines of render callbacks and egui textures
+    /// have to be made again and do not get drawn until then.
+    ///
+    /// In case rebuilding the device fails, the game stops without this event.
+    DeviceLost,
+    /// The mods of the mods directory set in the engine settings got loaded.
+    ///
+    /// Gets sent once right before the game starts, with an empty report in case no mods directory is set.
+    #[cfg(feature = "asset_system")]
+    ModsLoaded(std::sync::Arc<asset_system::mods::ModReport>),
+    /// The shader files of a watched material changed and got reloaded.
+    #[cfg(feature = "hot_reload")]
+    ShaderReloaded(let_engine_core::resources::hot_reload::ShaderReload),
+    /// A beat of a track played by a metronome is coming up.
+    #[cfg(feature = "audio")]
+    Beat(let_engine_audio::BeatEvent),
 }
 
 /// An event coming with window context.
@@ -48,6 +73,14 @@ pub enum WindowEvent {
     ///
     /// This event gets called once, no matter how many files were hovered over the window.
     HoveredFileCancelled,
+    /// Files are getting hovered over the window.
+    ///
+    /// Unlike [`HoveredFile`](WindowEvent::HoveredFile) this event happens once for all files dragged in together.
+    FilesHovered(FileDrop),
+    /// Files have been dropped inside the window.
+    ///
+    /// Unlike [`DroppedFile`](WindowEvent::DroppedFile) this event happens once for all files dropped together.
+    FilesDropped(FileDrop),
     /// `True` if the window was focused.
     /// `False` if it lost focus.
     Focused(bool),
@@ -61,6 +94,37 @@ pub enum WindowEvent {
     CursorMoved(dpi::PhysicalPosition<f64>),
     /// Mouse scroll event on the window.
     MouseWheel(ScrollDelta),
+    /// A monitor got connected, disconnected or changed its resolution, refresh rate, position or scaling.
+    ///
+    /// Holds all monitors available now, for rebuilding lists of monitors and video modes in settings menus.
+    /// The monitors get checked every 10 seconds and shortly after the window moved, changed its scale factor
+    /// or got focused, so the event can arrive a while after the change.
+    MonitorsChanged(Vec<Monitor>),
+}
+
+/// Files dragged into the window together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileDrop {
+    pub paths: Vec<PathBuf>,
+    /// The last known cursor position in pixels from the top left corner of the window.
+    ///
+    /// Some platforms do not report cursor movement while dragging, in which case this is where the cursor entered.
+    pub position: Vec2,
+}
+
+#[cfg(feature = "asset_system")]
+impl FileDrop {
+    /// Reads every file through the asset system cache, in the order of [`paths`](FileDrop::paths).
+    ///
+    /// Files dropped before get read from disk again, so dropping a changed file imports the new version.
+    pub async fn read(&self) -> Vec<Result<std::sync::Arc<[u8]>, asset_system::AssetError>> {
+        let mut files = Vec::with_capacity(self.paths.len());
+        for path in &self.paths {
+            let _ = asset_system::forget_file(path);
+            files.push(asset_system::file(path).await);
+        }
+        files
+    }
 }
 
 /// An event coming from device input.
@@ -81,6 +145,20 @@ pub enum InputEvent {
     ///
     /// The changes can be taken from the [INPUT](input::Input) struct.
     ModifiersChanged,
+    /// A finger touched, moved on or left a touch screen.
+    Touch(Touch),
+}
+
+/// A touch on a touch screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    /// The ID of the finger, the same from starting to ending the touch.
+    pub id: u64,
+    pub phase: TouchPhase,
+    /// The position in pixels from the top left corner of the window.
+    pub position: Vec2,
+    /// The pressure from 0 to 1, in case the device measures it.
+    pub force: Option<f32>,
 }
 
 /// The delta of a mouse scroll.
@@ -0,0 +1,89 @@
+//! Scheduling frames at an even pace.
+
+use std::time::{Duration, Instant};
+
+use let_engine_core::{
+    draw::{FramePacing, PresentMode},
+    window::window,
+};
+
+use crate::{SETTINGS, TIME};
+
+/// How often the refresh rate of the monitor gets checked in adaptive mode.
+const REFRESH_RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Waits until the next frame is due according to the frame pacing settings.
+pub(crate) struct FramePacer {
+    /// The time the next frame should start at.
+    deadline: Option<Instant>,
+    sleeper: spin_sleep::SpinSleeper,
+    /// The frame time of the refresh rate of the current monitor.
+    refresh_rate: Option<Duration>,
+    refresh_checked: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        Self {
+            deadline: None,
+            sleeper: spin_sleep::SpinSleeper::default(),
+            refresh_rate: None,
+            refresh_checked: None,
+        }
+    }
+
+    /// Sleeps until the next frame is due, spinning for the last moment to hit the target time precisely.
+    pub fn wait(&mut self) {
+        let Some(frame_time) = self.frame_time() else {
+            self.deadline = None;
+            TIME.record_frame(None, false);
+            return;
+        };
+
+        let now = Instant::now();
+        let Some(deadline) = self.deadline else {
+            self.deadline = Some(now + frame_time);
+            TIME.record_frame(Some(frame_time), false);
+            return;
+        };
+
+        // Frames starting more than half a frame late missed their slot.
+        // The schedule starts over from now, so the following frames do not rush to catch up.
+        let missed = now > deadline + frame_time / 2;
+        let start = if missed {
+            now
+        } else {
+            self.sleeper.sleep(deadline.saturating_duration_since(now));
+            deadline
+        };
+        self.deadline = Some(start + frame_time);
+        TIME.record_frame(Some(frame_time), missed);
+    }
+
+    /// Returns the target time between frames, `None` in case frames should not be paced.
+    fn frame_time(&mut self) -> Option<Duration> {
+        let graphics = &SETTINGS.graphics;
+        match graphics.frame_pacing() {
+            FramePacing::Unlimited => None,
+            FramePacing::Limit(limit) => Some(limit).filter(|limit| !limit.is_zero()),
+            FramePacing::Adaptive => {
+                if graphics.present_mode() == PresentMode::Fifo {
+                    return None;
+                }
+                // The window can move to another monitor at any time.
+                if self
+                    .refresh_checked
+                    .map_or(true, |checked| checked.elapsed() >= REFRESH_RATE_INTERVAL)
+                {
+                    self.refresh_checked = Some(Instant::now());
+                    self.refresh_rate = window()
+                        .and_then(|window| window.current_monitor())
+                        .and_then(|monitor| monitor.refresh_rate())
+                        .filter(|millihertz| *millihertz > 0)
+                        .map(|millihertz| Duration::from_secs_f64(1000.0 / millihertz as f64));
+                }
+                self.refresh_rate
+            }
+        }
+    }
+}
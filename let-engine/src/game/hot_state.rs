@@ -0,0 +1,345 @@
+//! Keeping the game state over restarts of the game while developing it.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result};
+use let_engine_core::objects::{scenes::SCENE, Object};
+use parking_lot::Mutex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// The environment variable telling the restarted process where the saved state is.
+const STATE_VARIABLE: &str = "LET_ENGINE_HOT_STATE";
+/// The environment variable telling the restarted process which build output to watch,
+/// in case it runs from a copy of the executable.
+const SOURCE_VARIABLE: &str = "LET_ENGINE_HOT_SOURCE";
+/// How long the executable has to stay unchanged before restarting, so the compiler finished writing it.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+
+type Save = Box<dyn Fn() -> Result<Vec<u8>> + Send + Sync>;
+
+#[derive(Serialize, Deserialize)]
+struct ObjectState {
+    id: usize,
+    name: Option<String>,
+    tags: Vec<String>,
+    position: [f32; 2],
+    size: [f32; 2],
+    rotation: f32,
+    visible: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LayerState {
+    zoom: f32,
+    camera_offset: [f32; 2],
+    objects: Vec<ObjectState>,
+}
+
+/// Everything saved before restarting.
+#[derive(Serialize, Deserialize, Default)]
+struct Snapshot {
+    layers: Vec<LayerState>,
+    state: HashMap<String, Vec<u8>>,
+}
+
+/// Returns the executable written by the compiler, which is not the running one in case it runs from a copy.
+fn build_output() -> std::io::Result<PathBuf> {
+    match std::env::var_os(SOURCE_VARIABLE) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => std::env::current_exe(),
+    }
+}
+
+/// Returns true if the running executable is a copy of the build output.
+fn runs_from_copy() -> bool {
+    std::env::var_os(SOURCE_VARIABLE).is_some()
+}
+
+/// The build output watched for a new build.
+struct Watch {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    /// The time the executable got changed, waiting for it to settle.
+    changed: Option<Instant>,
+}
+
+impl Watch {
+    fn new() -> Option<Self> {
+        let path = build_output().ok()?;
+        let modified = modified(&path);
+        Some(Self {
+            path,
+            modified,
+            changed: None,
+        })
+    }
+
+    /// Returns true once the executable got replaced and did not change for a moment.
+    fn poll(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified != self.modified {
+            self.modified = modified;
+            self.changed = Some(Instant::now());
+            return false;
+        }
+        // The file is missing in between the compiler removing and writing it.
+        modified.is_some()
+            && self
+                .changed
+                .is_some_and(|changed| changed.elapsed() >= SETTLE_TIME)
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// A development mode restarting the game after it got compiled again, keeping the scene and registered game state,
+/// available with the `hot_state` feature.
+///
+/// Once enabled, the engine watches the executable of the game. As soon as a new build replaces it, the engine saves
+/// the state, exits the game like closing the window does and starts the new executable with the same arguments.
+///
+/// Windows does not allow overwriting a running executable, so there the game always runs from a copy of the build
+/// output in the temporary directory. Enabling this restarts the game from a copy once in case it does not run from
+/// one yet.
+/// A restart can also be requested at any time using [`restart`](HotState::restart).
+///
+/// The new process restores the registered state as soon as it gets registered again. After the start function of
+/// the game finished, the transform, name, tags and visibility of all objects and the camera of every layer get
+/// restored. Objects get matched by layer index, ID and name, so this only works as long as the start function
+/// spawns the objects in the same order. Everything else, like resources and physics, comes from the start function.
+///
+/// # Usage
+/// ```ignore
+/// // in the start function of the game
+/// #[cfg(debug_assertions)]
+/// HOT_STATE.set_enabled(true);
+/// HOT_STATE.register(
+///     "score",
+///     || SCORE.load(Ordering::Relaxed),
+///     |score| SCORE.store(score, Ordering::Relaxed),
+/// );
+/// ```
+pub struct HotState {
+    enabled: AtomicBool,
+    requested: AtomicBool,
+    watch: Mutex<Option<Watch>>,
+    saves: Mutex<Vec<(String, Save)>>,
+    /// The state saved by the previous process, waiting to be restored.
+    restored: Mutex<Option<Snapshot>>,
+    /// The path of the state saved by this process before restarting.
+    saved: Mutex<Option<PathBuf>>,
+    /// True if the state of the previous process got read.
+    is_restored: bool,
+}
+
+impl HotState {
+    pub(crate) fn new() -> Self {
+        let restored = std::env::var_os(STATE_VARIABLE).and_then(|path| {
+            let path = PathBuf::from(path);
+            let snapshot = std::fs::read(&path)
+                .ok()
+                .and_then(|data| bincode::deserialize(&data).ok());
+            let _ = std::fs::remove_file(&path);
+            if snapshot.is_none() {
+                log::warn!("Could not read the state of the previous process.");
+            }
+            snapshot
+        });
+        let is_restored = restored.is_some();
+        Self {
+            enabled: AtomicBool::new(false),
+            requested: AtomicBool::new(false),
+            watch: Mutex::new(None),
+            saves: Mutex::new(vec![]),
+            restored: Mutex::new(restored),
+            saved: Mutex::new(None),
+            is_restored,
+        }
+    }
+
+    /// Returns true if the engine restarts the game once the executable changes.
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Enables or disables restarting the game once the executable changes.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+        *self.watch.lock() = if enabled { Watch::new() } else { None };
+        // The compiler can not replace the executable as long as it runs.
+        if enabled && cfg!(windows) && !runs_from_copy() {
+            self.restart();
+        }
+    }
+
+    /// Returns true if this process got started by a restart and the state of the previous process got read.
+    pub fn is_restored(&self) -> bool {
+        self.is_restored
+    }
+
+    /// Registers game state to save before restarting under the given name.
+    ///
+    /// In case the previous process saved state under this name, it gets restored right away.
+    pub fn register<T: Serialize + DeserializeOwned>(
+        &self,
+        name: impl Into<String>,
+        save: impl Fn() -> T + Send + Sync + 'static,
+        restore: impl FnOnce(T),
+    ) {
+        let name = name.into();
+        let data = self
+            .restored
+            .lock()
+            .as_mut()
+            .and_then(|snapshot| snapshot.state.remove(&name));
+        if let Some(data) = data {
+            match bincode::deserialize(&data) {
+                Ok(state) => restore(state),
+                // The structure of the state changed in the new build.
+                Err(error) => log::warn!("Could not restore the state {name}: {error}"),
+            }
+        }
+
+        let mut saves = self.saves.lock();
+        saves.retain(|(registered, _)| *registered != name);
+        saves.push((name, Box::new(move || Ok(bincode::serialize(&save())?))));
+    }
+
+    /// Restarts the game keeping the state in the next frame, even if the executable did not change.
+    pub fn restart(&self) {
+        self.requested.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the game should restart, checking the executable for a new build.
+    pub(crate) fn poll(&self) -> bool {
+        if self.requested.swap(false, Ordering::AcqRel) {
+            return true;
+        }
+        self.enabled() && self.watch.lock().as_mut().is_some_and(Watch::poll)
+    }
+
+    /// Saves the scene and all registered state to a temporary file for the restarted process.
+    pub(crate) fn save(&self) -> Result<()> {
+        let mut snapshot = Snapshot::default();
+        for layer in SCENE.layers().iter() {
+            let mut objects = vec![];
+            collect_objects(layer.children(), &mut objects);
+            snapshot.layers.push(LayerState {
+                zoom: layer.zoom(),
+                camera_offset: layer.camera_offset().into(),
+                objects,
+            });
+        }
+        for (name, save) in self.saves.lock().iter() {
+            snapshot.state.insert(
+                name.clone(),
+                save().with_context(|| format!("Could not save the state {name}"))?,
+            );
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("let-engine-hot-state-{}.bin", std::process::id()));
+        std::fs::write(&path, bincode::serialize(&snapshot)?)?;
+        *self.saved.lock() = Some(path);
+        Ok(())
+    }
+
+    /// Applies the saved scene of the previous process to the objects spawned by the start function.
+    pub(crate) fn restore_scene(&self) {
+        let Some(snapshot) = self.restored.lock().take() else {
+            return;
+        };
+        let layers = SCENE.layers();
+        for (layer, state) in layers.iter().zip(snapshot.layers) {
+            layer.set_zoom(state.zoom);
+            layer.set_camera_offset(state.camera_offset.into());
+
+            let mut objects = HashMap::new();
+            let mut children = layer.children();
+            while let Some(object) = children.pop() {
+                children.extend(object.children());
+                objects.insert(*object.id(), object);
+            }
+            for state in state.objects {
+                let Some(object) = objects.get_mut(&state.id) else {
+                    continue;
+                };
+                if object.name != state.name {
+                    continue;
+                }
+                object.tags = state.tags;
+                object.transform.position = state.position.into();
+                object.transform.size = state.size.into();
+                object.transform.rotation = state.rotation;
+                object.appearance.set_visible(state.visible);
+                if let Err(error) = object.sync() {
+                    log::warn!("Could not restore object #{}: {error}", state.id);
+                }
+            }
+        }
+    }
+
+    /// Starts the executable again with the path of the saved state, in case the state got saved.
+    ///
+    /// Only returns in case starting failed.
+    pub(crate) fn restart_process(&self) -> Result<()> {
+        let Some(path) = self.saved.lock().take() else {
+            return Ok(());
+        };
+        let source = build_output()?;
+        #[cfg(windows)]
+        let executable = {
+            // Every process needs its own copy, since the running ones can not be replaced either.
+            let nanos = SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            let copy = std::env::temp_dir().join(format!("let-engine-hot-{nanos}.exe"));
+            std::fs::copy(&source, &copy)
+                .with_context(|| format!("Could not copy {}", source.display()))?;
+            copy
+        };
+        #[cfg(not(windows))]
+        let executable = source;
+        let mut command = std::process::Command::new(executable);
+        command
+            .args(std::env::args_os().skip(1))
+            .env(STATE_VARIABLE, path);
+        #[cfg(windows)]
+        command.env(SOURCE_VARIABLE, source);
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            // Only returns on failure, keeping the process ID and the terminal otherwise.
+            Err(command.exec().into())
+        }
+        #[cfg(not(unix))]
+        {
+            command.spawn()?;
+            std::process::exit(0);
+        }
+    }
+}
+
+fn collect_objects(objects: Vec<Object>, states: &mut Vec<ObjectState>) {
+    for object in objects {
+        let transform = object.transform;
+        states.push(ObjectState {
+            id: *object.id(),
+            name: object.name.clone(),
+            tags: object.tags.clone(),
+            position: transform.position.into(),
+            size: transform.size.into(),
+            rotation: transform.rotation,
+            visible: *object.appearance.get_visible(),
+        });
+        collect_objects(object.children(), states);
+    }
+}
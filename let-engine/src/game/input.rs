@@ -2,11 +2,11 @@
 
 use let_engine_core::objects::scenes::Layer;
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     sync::atomic::{AtomicBool, Ordering},
 };
 pub use winit::event::MouseButton;
-use winit::event::{ElementState, Event, WindowEvent};
+use winit::event::{ElementState, Event, TouchPhase, WindowEvent};
 pub use winit::keyboard::*;
 
 use crossbeam::atomic::AtomicCell;
@@ -26,6 +26,8 @@ pub struct Input {
     //mouse position
     cursor_position: AtomicCell<Vec2>,
     cursor_inside: AtomicBool,
+    //touching fingers with their position in pixels
+    touches: Mutex<HashMap<u64, Vec2>>,
     //dimensions of the window
     dimensions: AtomicCell<Vec2>, // lazylock future
 }
@@ -38,6 +40,7 @@ impl Input {
             mouse_down: Mutex::new(HashSet::new()),
             cursor_position: AtomicCell::new(vec2(0.0, 0.0)),
             cursor_inside: AtomicBool::new(false),
+            touches: Mutex::new(HashMap::new()),
             dimensions: AtomicCell::new(vec2(0.0, 0.0)),
         }
     }
@@ -75,6 +78,17 @@ impl Input {
                 WindowEvent::CursorLeft { .. } => {
                     self.cursor_inside.store(false, Ordering::Release)
                 }
+                WindowEvent::Touch(touch) => match touch.phase {
+                    TouchPhase::Started | TouchPhase::Moved => {
+                        self.touches.lock().insert(
+                            touch.id,
+                            vec2(touch.location.x as f32, touch.location.y as f32),
+                        );
+                    }
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        self.touches.lock().remove(&touch.id);
+                    }
+                },
                 _ => (),
             }
         }
@@ -121,6 +135,18 @@ impl Input {
         )
     }
 
+    /// Returns the cursor position in pixels from the top left corner of the window.
+    pub fn cursor_pixels(&self) -> Vec2 {
+        (self.cursor_position.load() + 1.0) * 0.5 * self.dimensions.load()
+    }
+
+    /// Returns the cursor position in the world space of the layer.
+    ///
+    /// Unlike [`cursor_to_world`](Input::cursor_to_world) this respects the rotation and offset of the camera and the viewport of the layer.
+    pub fn mouse_world_position(&self, layer: &Layer) -> Vec2 {
+        layer.screen_to_world(self.cursor_pixels())
+    }
+
     /// Returns true if shift is pressed on the keyboard.
     pub fn shift(&self) -> bool {
         self.keyboard_modifiers.lock().shift_key()
@@ -143,6 +169,25 @@ impl Input {
         self.keyboard_modifiers.lock().super_key()
     }
 
+    /// Returns the IDs and positions in pixels from the top left corner of the window of all fingers touching the screen.
+    pub fn touches(&self) -> Vec<(u64, Vec2)> {
+        self.touches
+            .lock()
+            .iter()
+            .map(|(id, position)| (*id, *position))
+            .collect()
+    }
+
+    /// Returns the position of the finger with the given ID in pixels, `None` in case it does not touch the screen.
+    pub fn touch_position(&self, id: u64) -> Option<Vec2> {
+        self.touches.lock().get(&id).copied()
+    }
+
+    /// Returns the number of fingers touching the screen.
+    pub fn touch_count(&self) -> usize {
+        self.touches.lock().len()
+    }
+
     /// Returns true if the cursor is located in the window.
     pub fn cursor_inside(&self) -> bool {
         self.cursor_inside.load(Ordering::Acquire)
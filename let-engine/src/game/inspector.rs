@@ -0,0 +1,609 @@
+//! Debug windows for inspecting and tweaking the engine at runtime, drawn using egui.
+
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use egui_winit_vulkano::egui;
+#[cfg(feature = "physics")]
+use let_engine_core::objects::scenes::Layer;
+use let_engine_core::objects::{scenes::SCENE, Object, SceneCommand};
+use parking_lot::Mutex;
+
+use super::resource_cache::Bytes;
+use crate::{Curve, Gradient, PROFILER, RESOURCE_CACHE, SCENE_HISTORY, SETTINGS, TIME};
+
+/// The number of frames shown in the frame time graph.
+const FRAME_HISTORY: usize = 240;
+
+/// A debug window of the [`Inspector`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InspectorWindow {
+    /// The objects of every layer as a tree, with undoable editing of the transform of the selected object.
+    SceneTree,
+    /// The textures, buffers and sounds in memory along with the budget of the resource cache.
+    Resources,
+    /// The usage of the audio server and the output device.
+    #[cfg(feature = "audio")]
+    Audio,
+    /// The physics settings and counters of every layer.
+    #[cfg(feature = "physics")]
+    Physics,
+    /// The frame rate, render counters and the profiler.
+    Performance,
+    /// The curves and gradients listed using [`Inspector::tweak_curve`] and [`Inspector::tweak_gradient`].
+    Curves,
+}
+
+impl InspectorWindow {
+    /// Every window in the order they get listed.
+    pub const ALL: &'static [Self] = &[
+        Self::SceneTree,
+        Self::Resources,
+        #[cfg(feature = "audio")]
+        Self::Audio,
+        #[cfg(feature = "physics")]
+        Self::Physics,
+        Self::Performance,
+        Self::Curves,
+    ];
+
+    /// Returns the title of the window.
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::SceneTree => "Scene",
+            Self::Resources => "Resources",
+            #[cfg(feature = "audio")]
+            Self::Audio => "Audio",
+            #[cfg(feature = "physics")]
+            Self::Physics => "Physics",
+            Self::Performance => "Performance",
+            Self::Curves => "Curves",
+        }
+    }
+}
+
+/// Built in debug windows showing the state of the engine, available with the `egui` feature.
+///
+/// Once enabled, a small menu window lists all [`InspectorWindow`]s to open and close them.
+/// The windows can be moved and collapsed like any other egui window.
+/// The toggle key, F12 by default, enables and disables the inspector at any time.
+///
+/// # Usage
+/// ```ignore
+/// #[cfg(debug_assertions)]
+/// INSPECTOR.set_open(InspectorWindow::Performance, true);
+/// ```
+pub struct Inspector {
+    enabled: AtomicBool,
+    toggle_key: Mutex<Option<egui::Key>>,
+    open: Mutex<HashSet<InspectorWindow>>,
+    selected: Mutex<Option<Object>>,
+    frame_times: Mutex<VecDeque<f32>>,
+    tweaks: Mutex<BTreeMap<String, Tweak>>,
+}
+
+/// A curve or gradient edited in the curves window.
+enum Tweak {
+    Curve(Curve),
+    Gradient(Gradient),
+}
+
+impl Inspector {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            toggle_key: Mutex::new(Some(egui::Key::F12)),
+            open: Mutex::new(HashSet::new()),
+            selected: Mutex::new(None),
+            frame_times: Mutex::new(VecDeque::with_capacity(FRAME_HISTORY)),
+            tweaks: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns true if the inspector gets shown.
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Shows or hides the inspector.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+
+    /// Shows the inspector in case it is hidden and hides it otherwise.
+    pub fn toggle(&self) {
+        self.enabled.fetch_xor(true, Ordering::AcqRel);
+    }
+
+    /// Sets the key toggling the inspector, `None` to only toggle it from code.
+    pub fn set_toggle_key(&self, key: Option<egui::Key>) {
+        *self.toggle_key.lock() = key;
+    }
+
+    /// Returns true if the given window is open.
+    pub fn is_open(&self, window: InspectorWindow) -> bool {
+        self.open.lock().contains(&window)
+    }
+
+    /// Opens or closes the given window, enabling the inspector when opening it.
+    pub fn set_open(&self, window: InspectorWindow, open: bool) {
+        if open {
+            self.open.lock().insert(window);
+            self.set_enabled(true);
+        } else {
+            self.open.lock().remove(&window);
+        }
+    }
+
+    /// Returns the object selected in the scene tree.
+    pub fn selected(&self) -> Option<Object> {
+        self.selected.lock().clone()
+    }
+
+    /// Selects the given object in the scene tree.
+    pub fn select(&self, object: Option<Object>) {
+        *self.selected.lock() = object;
+    }
+
+    /// Returns the curve listed under the given name in the curves window, listing the default under it first.
+    ///
+    /// Getting the curve this way every time it gets used picks up the edits made in the inspector right away.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let height = INSPECTOR.tweak_curve("jump height", || Curve::between(0.0, 2.0, Ease::OutQuad));
+    /// player.transform.position.y = ground + height.evaluate(jump_progress);
+    /// ```
+    pub fn tweak_curve(&self, name: &str, default: impl FnOnce() -> Curve) -> Curve {
+        let mut tweaks = self.tweaks.lock();
+        if let Some(Tweak::Curve(curve)) = tweaks.get(name) {
+            return curve.clone();
+        }
+        let curve = default();
+        tweaks.insert(name.to_string(), Tweak::Curve(curve.clone()));
+        curve
+    }
+
+    /// Returns the gradient listed under the given name in the curves window, listing the default under it first.
+    ///
+    /// Works like [`tweak_curve`](Self::tweak_curve).
+    pub fn tweak_gradient(&self, name: &str, default: impl FnOnce() -> Gradient) -> Gradient {
+        let mut tweaks = self.tweaks.lock();
+        if let Some(Tweak::Gradient(gradient)) = tweaks.get(name) {
+            return gradient.clone();
+        }
+        let gradient = default();
+        tweaks.insert(name.to_string(), Tweak::Gradient(gradient.clone()));
+        gradient
+    }
+
+    /// Removes the curve or gradient with the given name from the curves window, returning false in case there was none.
+    pub fn remove_tweak(&self, name: &str) -> bool {
+        self.tweaks.lock().remove(name).is_some()
+    }
+
+    /// Draws the open windows. Gets called by the engine every frame before the egui event.
+    pub(crate) fn show(&self, context: &egui::Context) {
+        let toggle_key = *self.toggle_key.lock();
+        if toggle_key.is_some_and(|key| context.input(|input| input.key_pressed(key))) {
+            self.toggle();
+        }
+
+        {
+            let mut frame_times = self.frame_times.lock();
+            if frame_times.len() == FRAME_HISTORY {
+                frame_times.pop_front();
+            }
+            frame_times.push_back(TIME.unscaled_delta_time() as f32);
+        }
+
+        if !self.enabled() {
+            return;
+        }
+
+        egui::Window::new("Inspector")
+            .resizable(false)
+            .show(context, |ui| {
+                for window in InspectorWindow::ALL {
+                    let mut open = self.is_open(*window);
+                    if ui.checkbox(&mut open, window.title()).changed() {
+                        self.set_open(*window, open);
+                    }
+                }
+            });
+
+        for window in InspectorWindow::ALL {
+            let mut open = self.is_open(*window);
+            if !open {
+                continue;
+            }
+            egui::Window::new(window.title())
+                .open(&mut open)
+                .vscroll(true)
+                .show(context, |ui| match window {
+                    InspectorWindow::SceneTree => self.scene_tree(ui),
+                    InspectorWindow::Resources => resources(ui),
+                    #[cfg(feature = "audio")]
+                    InspectorWindow::Audio => audio(ui),
+                    #[cfg(feature = "physics")]
+                    InspectorWindow::Physics => physics(ui),
+                    InspectorWindow::Performance => self.performance(ui),
+                    InspectorWindow::Curves => self.curves(ui),
+                });
+            if !open {
+                self.set_open(*window, false);
+            }
+        }
+
+        if self.is_open(InspectorWindow::Performance) && PROFILER.enabled() {
+            PROFILER.show(context);
+        }
+    }
+
+    fn scene_tree(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(SCENE_HISTORY.can_undo(), egui::Button::new("Undo"))
+                .clicked()
+            {
+                if let Err(error) = SCENE_HISTORY.undo() {
+                    log::warn!("The inspector could not undo the last change: {error}");
+                }
+            }
+            if ui
+                .add_enabled(SCENE_HISTORY.can_redo(), egui::Button::new("Redo"))
+                .clicked()
+            {
+                if let Err(error) = SCENE_HISTORY.redo() {
+                    log::warn!("The inspector could not redo the last change: {error}");
+                }
+            }
+        });
+
+        let mut selected = self.selected.lock();
+        // Undoing the removal of the selected object restores it as a new object.
+        if let Some(object) = selected.as_mut() {
+            *object = SCENE_HISTORY.resolve(object);
+        }
+        for (index, layer) in SCENE.layers().iter().enumerate() {
+            let stats = layer.object_stats();
+            egui::CollapsingHeader::new(format!("Layer {index} ({} objects)", stats.objects))
+                .id_source(Arc::as_ptr(layer))
+                .default_open(index == 0)
+                .show(ui, |ui| {
+                    for object in layer.children() {
+                        object_tree(ui, &object, &mut selected);
+                    }
+                });
+        }
+
+        ui.separator();
+        let Some(object) = selected.as_mut() else {
+            ui.label("Select an object to edit it.");
+            return;
+        };
+        // The object might have been changed or removed by the game since the last frame.
+        if object.update().is_err() {
+            *selected = None;
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label(object_label(object));
+            if ui.button("Remove").clicked() {
+                if let Err(error) = SCENE_HISTORY.execute(SceneCommand::Remove(object.clone())) {
+                    log::warn!("The inspector could not remove the object: {error}");
+                }
+            }
+        });
+        if !object.is_initialized() {
+            *selected = None;
+            return;
+        }
+        let mut changed = false;
+        egui::Grid::new("let-engine inspector transform").show(ui, |ui| {
+            let transform = &mut object.transform;
+            ui.label("position");
+            changed |= ui
+                .add(egui::DragValue::new(&mut transform.position.x).speed(0.01))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut transform.position.y).speed(0.01))
+                .changed();
+            ui.end_row();
+            ui.label("size");
+            changed |= ui
+                .add(egui::DragValue::new(&mut transform.size.x).speed(0.01))
+                .changed();
+            changed |= ui
+                .add(egui::DragValue::new(&mut transform.size.y).speed(0.01))
+                .changed();
+            ui.end_row();
+            ui.label("rotation");
+            changed |= ui
+                .add(egui::DragValue::new(&mut transform.rotation).speed(0.01))
+                .changed();
+            ui.end_row();
+        });
+        let mut command =
+            changed.then(|| SceneCommand::SetTransform(object.clone(), object.transform));
+        let mut visible = *object.appearance.get_visible();
+        if ui.checkbox(&mut visible, "visible").changed() {
+            let mut appearance = object.appearance.clone();
+            appearance.set_visible(visible);
+            command = Some(SceneCommand::SetAppearance(object.clone(), appearance));
+        }
+        if let Some(command) = command {
+            if let Err(error) = SCENE_HISTORY.execute(command) {
+                log::warn!("The inspector could not change the object: {error}");
+            }
+        }
+    }
+
+    fn curves(&self, ui: &mut egui::Ui) {
+        let mut tweaks = self.tweaks.lock();
+        if tweaks.is_empty() {
+            ui.label("List curves using `Inspector::tweak_curve` to edit them here.");
+            return;
+        }
+        for (name, tweak) in tweaks.iter_mut() {
+            egui::CollapsingHeader::new(name.as_str()).show(ui, |ui| match tweak {
+                Tweak::Curve(curve) => curve.ui(ui),
+                Tweak::Gradient(gradient) => gradient.ui(ui),
+            });
+        }
+    }
+
+    fn performance(&self, ui: &mut egui::Ui) {
+        let frame_times = self.frame_times.lock();
+        let slowest = frame_times.iter().copied().fold(0.0, f32::max);
+        ui.label(format!(
+            "{:.0} fps, slowest frame {:.2} ms",
+            TIME.fps(),
+            slowest * 1000.0
+        ));
+
+        // Frame times of the last frames, scaled to the slowest one.
+        let (response, painter) =
+            ui.allocate_painter(egui::vec2(ui.available_width(), 60.0), egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(100));
+        if slowest > 0.0 {
+            let step = rect.width() / (FRAME_HISTORY - 1) as f32;
+            let points = frame_times
+                .iter()
+                .enumerate()
+                .map(|(i, time)| {
+                    egui::pos2(
+                        rect.left() + i as f32 * step,
+                        rect.bottom() - time / slowest * rect.height(),
+                    )
+                })
+                .collect();
+            painter.add(egui::Shape::line(
+                points,
+                egui::Stroke::new(1.0, egui::Color32::LIGHT_GREEN),
+            ));
+        }
+        drop(frame_times);
+
+        let stats = SETTINGS.graphics.stats();
+        egui::Grid::new("let-engine inspector render stats").show(ui, |ui| {
+            let mut row = |name: &str, value: String| {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("draw calls", stats.draw_calls.to_string());
+            row("objects", stats.objects.to_string());
+            row("culled", stats.objects_culled.to_string());
+            row("pipeline switches", stats.pipeline_switches.to_string());
+            row(
+                "swapchain recreations",
+                stats.swapchain_recreations.to_string(),
+            );
+            row("texture memory", Bytes(stats.texture_memory).to_string());
+            row("buffer memory", Bytes(stats.buffer_memory).to_string());
+        });
+
+        let mut profiling = PROFILER.enabled();
+        if ui.checkbox(&mut profiling, "profiler").changed() {
+            PROFILER.set_enabled(profiling);
+        }
+    }
+}
+
+/// Returns the name of the object or its ID in case it has none.
+fn object_label(object: &Object) -> String {
+    match &object.name {
+        Some(name) => format!("{name} #{}", object.id()),
+        None => format!("#{}", object.id()),
+    }
+}
+
+/// Returns true if both objects are the same object of the same layer.
+fn same_object(a: &Object, b: &Object) -> bool {
+    a.handle() == b.handle()
+}
+
+/// Lists the object and its children as a collapsible tree.
+fn object_tree(ui: &mut egui::Ui, object: &Object, selected: &mut Option<Object>) {
+    let is_selected = selected
+        .as_ref()
+        .is_some_and(|selected| same_object(selected, object));
+    let children = object.children();
+    if children.is_empty() {
+        if ui
+            .selectable_label(is_selected, object_label(object))
+            .clicked()
+        {
+            *selected = Some(object.clone());
+        }
+        return;
+    }
+    let id = ui.make_persistent_id(object.handle());
+    egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false)
+        .show_header(ui, |ui| {
+            if ui
+                .selectable_label(is_selected, object_label(object))
+                .clicked()
+            {
+                *selected = Some(object.clone());
+            }
+        })
+        .body(|ui| {
+            for child in children {
+                object_tree(ui, &child, selected);
+            }
+        });
+}
+
+fn resources(ui: &mut egui::Ui) {
+    use super::resource_cache::ResourceKind;
+
+    let report = RESOURCE_CACHE.report();
+    ui.label(format!(
+        "textures {}, buffers {}, sounds {}",
+        Bytes(report.total(ResourceKind::Texture)),
+        Bytes(report.total(ResourceKind::Buffer)),
+        Bytes(report.total(ResourceKind::Sound)),
+    ));
+    ui.label(format!(
+        "budget {}, {} evictions",
+        report
+            .budget
+            .map_or_else(|| "none".to_string(), |budget| Bytes(budget).to_string()),
+        report.evictions
+    ));
+    if ui.button("Trim").clicked() {
+        RESOURCE_CACHE.trim();
+    }
+    ui.separator();
+    egui::Grid::new("let-engine inspector resources")
+        .striped(true)
+        .show(ui, |ui| {
+            for resource in &report.resources {
+                ui.label(resource.kind.to_string());
+                ui.label(resource.label.as_deref().unwrap_or("unnamed"));
+                ui.label(Bytes(resource.bytes).to_string());
+                ui.label(match (resource.resident, resource.idle) {
+                    (false, _) => "evicted".to_string(),
+                    (true, Some(idle)) => format!("idle {:.0} s", idle.as_secs_f32()),
+                    (true, None) => String::new(),
+                });
+                ui.end_row();
+            }
+        });
+}
+
+#[cfg(feature = "audio")]
+fn audio(ui: &mut egui::Ui) {
+    let stats = SETTINGS.audio.stats();
+    let mut usage = |name: &str, used: u16, capacity: u16| {
+        ui.add(
+            egui::ProgressBar::new(used as f32 / capacity.max(1) as f32)
+                .text(format!("{name} {used} / {capacity}")),
+        );
+    };
+    usage("sounds", stats.sounds, stats.sound_capacity);
+    usage("emitters", stats.emitters, stats.emitter_capacity);
+    usage("listeners", stats.listeners, stats.listener_capacity);
+    usage(
+        "spatial scenes",
+        stats.spatial_scenes,
+        stats.spatial_scene_capacity,
+    );
+    usage("clocks", stats.clocks, stats.clock_capacity);
+    ui.label(format!(
+        "{} failed plays, {} unbound emitters",
+        stats.failed_plays, stats.failed_emitters
+    ));
+    egui::Grid::new("audio tracks").show(ui, |ui| {
+        for track in &stats.tracks {
+            ui.label(match track.track {
+                let_engine_audio::AudioTrack::Main => "main".to_string(),
+                let_engine_audio::AudioTrack::SharedScene => "shared scene".to_string(),
+                let_engine_audio::AudioTrack::Layer(id) => format!("layer {id}"),
+            });
+            ui.label(format!("{} voices", track.voices));
+            ui.label(format!("{} emitters", track.emitters));
+            ui.label(format!("{} listeners", track.listeners));
+            ui.end_row();
+        }
+    });
+
+    let mut settings = SETTINGS.audio.get();
+    let before = settings.output_device.clone();
+    egui::ComboBox::from_label("output device")
+        .selected_text(settings.output_device.as_deref().unwrap_or("default"))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut settings.output_device, None, "default");
+            for device in let_engine_audio::output_devices().unwrap_or_default() {
+                ui.selectable_value(&mut settings.output_device, Some(device.clone()), device);
+            }
+        });
+    if settings.output_device != before {
+        if let Err(error) = SETTINGS.audio.set(settings) {
+            log::warn!("The inspector could not change the output device: {error}");
+        }
+    }
+}
+
+#[cfg(feature = "physics")]
+fn physics(ui: &mut egui::Ui) {
+    for (index, layer) in SCENE.layers().iter().enumerate() {
+        egui::CollapsingHeader::new(format!("Layer {index}"))
+            .id_source(Arc::as_ptr(layer))
+            .default_open(index == 0)
+            .show(ui, |ui| layer_physics(ui, layer));
+    }
+}
+
+#[cfg(feature = "physics")]
+fn layer_physics(ui: &mut egui::Ui, layer: &Layer) {
+    let mut enabled = layer.physics_enabled();
+    if ui.checkbox(&mut enabled, "enabled").changed() {
+        layer.set_physics_enabled(enabled);
+    }
+
+    let mut settings = layer.physics_settings();
+    let mut solver_iterations = settings.solver_iterations.get();
+    egui::Grid::new((layer as *const Layer, "physics")).show(ui, |ui| {
+        ui.label("gravity");
+        ui.add(egui::DragValue::new(&mut settings.gravity.x).speed(0.1));
+        ui.add(egui::DragValue::new(&mut settings.gravity.y).speed(0.1));
+        ui.end_row();
+        ui.label("timestep");
+        ui.add(
+            egui::DragValue::new(&mut settings.timestep)
+                .speed(0.001)
+                .clamp_range(0.001..=1.0),
+        );
+        ui.end_row();
+        ui.label("ccd substeps");
+        ui.add(egui::DragValue::new(&mut settings.ccd_substeps));
+        ui.end_row();
+        ui.label("solver iterations");
+        ui.add(egui::DragValue::new(&mut solver_iterations).clamp_range(1..=64));
+        ui.end_row();
+    });
+    settings.solver_iterations =
+        std::num::NonZeroUsize::new(solver_iterations).unwrap_or(settings.solver_iterations);
+    if settings != layer.physics_settings() {
+        layer.set_physics_settings(settings);
+    }
+
+    let stats = layer.physics_stats();
+    ui.label(format!(
+        "{} bodies, {} active, {} colliders, {} contacts, {} islands",
+        stats.bodies, stats.active_bodies, stats.colliders, stats.contacts, stats.islands
+    ));
+    ui.label(format!(
+        "step {:.2} ms",
+        stats.step_time.as_secs_f64() * 1000.0
+    ));
+}
@@ -3,15 +3,46 @@ pub use let_engine_core::window;
 #[cfg(feature = "client")]
 use let_engine_core::{draw::Draw, resources::Resources};
 #[cfg(feature = "client")]
-use let_engine_core::{resources::RESOURCES, window::WINDOW};
+use let_engine_core::{objects::scenes::SCENE, resources::RESOURCES, window::WINDOW};
+#[cfg(all(feature = "android", target_os = "android"))]
+pub mod android;
+mod animation;
+mod blackboard;
+mod camera_rig;
+mod curve;
 #[cfg(all(feature = "egui", feature = "client"))]
 mod egui;
 #[cfg(feature = "client")]
 pub mod events;
 #[cfg(feature = "client")]
+mod frame_pacing;
+#[cfg(feature = "hot_state")]
+mod hot_state;
+#[cfg(feature = "client")]
 pub mod input;
+#[cfg(feature = "egui")]
+mod inspector;
+#[cfg(feature = "client")]
+mod monitors;
+pub mod navigation;
+pub mod noise;
+mod profiler;
+mod random;
+#[cfg(feature = "client")]
+mod resource_cache;
+pub mod savegame;
+mod scheduler;
 pub mod settings;
+pub mod skeleton;
+#[cfg(feature = "client")]
+mod splash;
 mod tick_system;
+#[cfg(feature = "client")]
+pub mod time_effects;
+#[cfg(feature = "video")]
+mod video;
+#[cfg(feature = "client")]
+mod wake;
 
 use anyhow::Result;
 use atomic_float::AtomicF64;
@@ -22,7 +53,24 @@ use self::{
     events::{InputEvent, ScrollDelta},
     window::{Window, WindowBuilder},
 };
+pub use animation::*;
+pub use blackboard::*;
+pub use camera_rig::*;
+pub use curve::*;
+#[cfg(feature = "hot_state")]
+pub use hot_state::HotState;
+#[cfg(feature = "egui")]
+pub use inspector::*;
+pub use profiler::*;
+pub use random::*;
+#[cfg(feature = "client")]
+pub use resource_cache::*;
+pub use scheduler::*;
+#[cfg(feature = "client")]
+pub use splash::{LoadingProgress, SplashScreen, LOADING};
 pub use tick_system::*;
+#[cfg(feature = "video")]
+pub use video::*;
 
 #[cfg(feature = "networking")]
 pub mod networking;
@@ -160,6 +208,18 @@ where
 
     #[cfg(feature = "client")]
     draw: Draw,
+    #[cfg(feature = "client")]
+    frame_pacer: frame_pacing::FramePacer,
+    #[cfg(feature = "client")]
+    monitor_watcher: monitors::MonitorWatcher,
+    /// Files hovered over or dropped into the window since the last update, sent as one event.
+    #[cfg(feature = "client")]
+    file_drop: (Vec<std::path::PathBuf>, Vec<std::path::PathBuf>),
+    #[cfg(feature = "client")]
+    splash_screen: Option<SplashScreen>,
+    /// The splash screen and the start function of the game while it is running.
+    #[cfg(feature = "client")]
+    startup: Option<(splash::Splash, Option<StartFuture>)>,
     server: Option<GameServer<Msg>>,
     client: Option<GameClient<Msg>>,
     _game: PhantomData<G>,
@@ -179,9 +239,25 @@ where
 
     #[cfg(feature = "client")]
     draw: Draw,
+    #[cfg(feature = "client")]
+    frame_pacer: frame_pacing::FramePacer,
+    #[cfg(feature = "client")]
+    monitor_watcher: monitors::MonitorWatcher,
+    /// Files hovered over or dropped into the window since the last update, sent as one event.
+    #[cfg(feature = "client")]
+    file_drop: (Vec<std::path::PathBuf>, Vec<std::path::PathBuf>),
+    #[cfg(feature = "client")]
+    splash_screen: Option<SplashScreen>,
+    /// The splash screen and the start function of the game while it is running.
+    #[cfg(feature = "client")]
+    startup: Option<(splash::Splash, Option<StartFuture>)>,
     _game: PhantomData<G>,
 }
 
+/// The start function of the game, polled every frame while the splash screen is shown.
+#[cfg(feature = "client")]
+type StartFuture = std::pin::Pin<Box<dyn std::future::Future<Output = ()>>>;
+
 /// Makes sure the engine struct only gets constructed a single time.
 static INIT: parking_lot::Once = parking_lot::Once::new();
 
@@ -196,22 +272,40 @@ impl_engine_features! {
         /// This function can only be called one time. Attempting to make a second one of those will return an error.
         pub fn new(settings: impl Into<settings::EngineSettings>) -> Result<Self, EngineError> {
             if INIT.state() == parking_lot::OnceState::New {
-                #[cfg(feature = "client")]
+                #[allow(unused_mut)]
+                let mut settings: settings::EngineSettings = settings.into();
+                #[cfg(all(feature = "client", not(all(feature = "android", target_os = "android"))))]
                 let event_loop = winit::event_loop::EventLoopBuilder::new()
                     .build()
                     .map_err(|e| EngineError::Other(e.into()))?;
+                #[cfg(all(feature = "android", target_os = "android"))]
+                let event_loop = android::event_loop(settings.android_app.take())?;
                 #[cfg(feature = "client")]
-                let resources = Resources::new(&event_loop)?;
+                let resources = Resources::new(&event_loop, &SETTINGS.graphics)?;
                 #[cfg(feature = "client")]
                 RESOURCES.get_or_init(|| resources);
                 INIT.call_once(|| {});
-                let settings = settings.into();
                 SETTINGS.tick_system.set(settings.tick_settings);
+                #[cfg(feature = "asset_system")]
+                if let Some(directory) = &settings.mods_directory {
+                    if let Err(error) = asset_system::mods::load_mods_blocking(directory) {
+                        log::error!("Failed to load the mods: {error}");
+                    }
+                }
                 let tick_system = Some(TickSystem::new());
 
+                #[cfg(feature = "client")]
+                let mut window_settings = settings.window_settings;
+                #[cfg(feature = "client")]
+                if let Some(game) = settings.stored_settings.as_deref() {
+                    if let Ok(stored) = SETTINGS.load(game) {
+                        window_settings = stored.apply_to(window_settings);
+                    }
+                }
+
                 #[cfg(feature = "client")]
                 let draw = Draw::setup(
-                    settings.window_settings,
+                    window_settings,
                     &event_loop,
                     SETTINGS.graphics.clone(),
                 )
@@ -221,6 +315,8 @@ impl_engine_features! {
 
                 #[cfg(all(feature = "egui", feature = "client"))]
                 let gui = egui::init(&draw, &event_loop);
+                #[cfg(feature = "client")]
+                let monitor_watcher = monitors::MonitorWatcher::new(draw.window());
 
                 Ok(Self {
                     #[cfg(all(feature = "egui", feature = "client"))]
@@ -230,6 +326,16 @@ impl_engine_features! {
                     event_loop: Some(event_loop),
                     #[cfg(feature = "client")]
                     draw,
+                    #[cfg(feature = "client")]
+                    frame_pacer: frame_pacing::FramePacer::new(),
+                    #[cfg(feature = "client")]
+                    monitor_watcher,
+                    #[cfg(feature = "client")]
+                    file_drop: (vec![], vec![]),
+                    #[cfg(feature = "client")]
+                    splash_screen: settings.splash_screen,
+                    #[cfg(feature = "client")]
+                    startup: None,
                     #[cfg(feature = "networking")]
                     server: None,
                     #[cfg(feature = "networking")]
@@ -347,18 +453,104 @@ impl_engine_features! {
             })
         }
 
+        /// Runs the tick system with the given game in the background.
+        #[cfg(feature = "client")]
+        fn start_tick_system(&mut self, game: &Arc<smol::lock::Mutex<G>>) {
+            if let Some(tick_system) = self.tick_system.take() {
+                let game = Arc::clone(game);
+                smol::spawn(async {
+                    let mut tick_system = tick_system;
+                    tick_system.run(game).await;
+                }).detach();
+            }
+        }
+
         #[cfg(feature = "client")]
         pub fn start(&mut self, game: G) {
-            use let_engine_core::draw::VulkanError;
+            use let_engine_core::draw::{ControlFlow, VulkanError};
             use winit::event::{DeviceEvent, Event, MouseScrollDelta, StartCause, WindowEvent};
             let game = Arc::new(smol::lock::Mutex::new(game));
 
             let event_loop = std::mem::take(&mut self.event_loop).unwrap();
+            wake::init(event_loop.create_proxy());
 
             event_loop
                 .run(move |event, control_flow| {
                     smol::block_on(async {
                         INPUT.update(&event, self.get_window().inner_size());
+
+                        // Only the splash screen gets drawn while the start function of the game runs.
+                        if let Some((splash, start)) = &mut self.startup {
+                            let mut started = false;
+                            match &event {
+                                Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
+                                    control_flow.exit();
+                                }
+                                Event::WindowEvent { event: WindowEvent::Resized(_), .. } => {
+                                    self.draw.mark_swapchain_outdated();
+                                    SCENE.update_anchors();
+                                }
+                                Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
+                                    self.frame_pacer.wait();
+                                    if let Some(future) = start {
+                                        if smol::future::poll_once(future.as_mut()).await.is_some() {
+                                            *start = None;
+                                        }
+                                    }
+                                    if let Err(error) = splash.update() {
+                                        log::error!("Failed to update the splash screen: {error}");
+                                    }
+                                    #[cfg(feature = "egui")]
+                                    self.gui.immediate_ui(|_| {});
+                                    match self.draw.redraw_event(
+                                        #[cfg(feature = "egui")]
+                                        &mut self.gui,
+                                    ) {
+                                        Err(VulkanError::SwapchainOutOfDate) => {
+                                            self.draw.mark_swapchain_outdated();
+                                        }
+                                        Err(VulkanError::DeviceLost) => {
+                                            log::error!("The GPU device got lost while starting.");
+                                            if let Err(error) = self.draw.recover() {
+                                                log::error!("Failed to rebuild the GPU device: {error}");
+                                                control_flow.exit();
+                                            }
+                                            #[cfg(feature = "egui")]
+                                            {
+                                                self.gui = egui::init(&self.draw, control_flow);
+                                            }
+                                        }
+                                        Err(e) => panic!("{e}"),
+                                        _ => (),
+                                    };
+                                    crate::TIME.update();
+                                    started = start.is_none() && splash.can_close();
+                                }
+                                Event::AboutToWait => {
+                                    self.draw.window().request_redraw();
+                                }
+                                Event::Suspended => self.draw.suspend(),
+                                Event::Resumed if self.draw.is_suspended() => match self.draw.resume() {
+                                    // The old gui still draws to the destroyed surface.
+                                    #[cfg(feature = "egui")]
+                                    Ok(()) => self.gui = egui::init(&self.draw, control_flow),
+                                    #[cfg(not(feature = "egui"))]
+                                    Ok(()) => (),
+                                    Err(error) => log::error!("Failed to resume drawing: {error}"),
+                                },
+                                _ => (),
+                            }
+                            if started {
+                                if let Some((splash, _)) = self.startup.take() {
+                                    splash.close();
+                                }
+                                #[cfg(feature = "hot_state")]
+                                crate::HOT_STATE.restore_scene();
+                                self.start_tick_system(&game);
+                            }
+                            return;
+                        }
+
                         if game.lock().await.exit() {
                             #[cfg(feature = "networking")]
                             if let Some(server) = &mut self.server {
@@ -387,9 +579,21 @@ impl_engine_features! {
                             Event::WindowEvent { event, .. } => {
                                 #[cfg(feature = "egui")]
                                 self.gui.update(&event);
+                                // Waiting control flows only draw after something happened.
+                                if !matches!(event, WindowEvent::RedrawRequested) {
+                                    self.get_window().request_redraw();
+                                    SETTINGS.tick_system.mark_active();
+                                }
+                                if matches!(
+                                    event,
+                                    WindowEvent::Moved(_) | WindowEvent::ScaleFactorChanged { .. } | WindowEvent::Focused(true)
+                                ) {
+                                    self.monitor_watcher.hint();
+                                }
                                 let event = match event {
                                     WindowEvent::Resized(size) => {
                                         self.draw.mark_swapchain_outdated();
+                                        SCENE.update_anchors();
                                         events::Event::Window(events::WindowEvent::Resized(size))
                                     }
                                     WindowEvent::CloseRequested => {
@@ -399,24 +603,37 @@ impl_engine_features! {
                                         events::Event::Window(events::WindowEvent::CursorEntered)
                                     }
                                     WindowEvent::CursorLeft { .. } => {
+                                        self.get_window().cursor_moved(None);
                                         events::Event::Window(events::WindowEvent::CursorLeft)
                                     }
-                                    WindowEvent::CursorMoved { position, .. } => events::Event::Window(
-                                        events::WindowEvent::CursorMoved(position),
-                                    ),
+                                    WindowEvent::CursorMoved { position, .. } => {
+                                        self.get_window().cursor_moved(Some(glam::vec2(
+                                            position.x as f32,
+                                            position.y as f32,
+                                        )));
+                                        events::Event::Window(events::WindowEvent::CursorMoved(
+                                            position,
+                                        ))
+                                    }
                                     WindowEvent::Destroyed => {
                                         events::Event::Window(events::WindowEvent::Destroyed)
                                     }
                                     WindowEvent::HoveredFile(file) => {
+                                        self.file_drop.0.push(file.clone());
                                         events::Event::Window(events::WindowEvent::HoveredFile(file))
                                     }
                                     WindowEvent::DroppedFile(file) => {
+                                        self.file_drop.1.push(file.clone());
                                         events::Event::Window(events::WindowEvent::DroppedFile(file))
                                     }
                                     WindowEvent::HoveredFileCancelled => {
+                                        self.file_drop.0.clear();
                                         events::Event::Window(events::WindowEvent::HoveredFileCancelled)
                                     }
                                     WindowEvent::Focused(focused) => {
+                                        if focused {
+                                            self.get_window().restore_cursor();
+                                        }
                                         events::Event::Window(events::WindowEvent::Focused(focused))
                                     }
                                     WindowEvent::KeyboardInput { event, .. } => {
@@ -437,6 +654,14 @@ impl_engine_features! {
                                     WindowEvent::MouseInput { state, button, .. } => {
                                         events::Event::Input(InputEvent::MouseInput(button, state))
                                     }
+                                    WindowEvent::Touch(touch) => {
+                                        events::Event::Input(InputEvent::Touch(events::Touch {
+                                            id: touch.id,
+                                            phase: touch.phase,
+                                            position: glam::vec2(touch.location.x as f32, touch.location.y as f32),
+                                            force: touch.force.map(|force| force.normalized() as f32),
+                                        }))
+                                    }
                                     WindowEvent::MouseWheel { delta, .. } => events::Event::Window(
                                         events::WindowEvent::MouseWheel(match delta {
                                             MouseScrollDelta::LineDelta(x, y) => {
@@ -449,30 +674,67 @@ impl_engine_features! {
                                     ),
                                     WindowEvent::RedrawRequested => {
 
-                                        // fps limit logic
-                                        let start_time = SystemTime::now();
+                                        self.frame_pacer.wait();
+                                        // This frame satisfies every request for one that is due already.
+                                        wake::take_due();
+                                        crate::SCENE_QUEUE.apply();
 
                                         // redraw
-                                        match self.draw.redraw_event(
+                                        let draw_start = std::time::Instant::now();
+                                        let result = self.draw.redraw_event(
                                             #[cfg(feature = "egui")]
                                             &mut self.gui,
-                                        ) {
+                                        );
+                                        crate::PROFILER.record(ProfileSection::Draw, draw_start.elapsed());
+                                        match result {
                                             Err(VulkanError::SwapchainOutOfDate) => {
                                                 self.draw.mark_swapchain_outdated();
                                             }
+                                            Err(VulkanError::DeviceLost) => {
+                                                log::error!("The GPU device got lost, rebuilding it.");
+                                                match self.draw.recover() {
+                                                    Ok(()) => {
+                                                        #[cfg(feature = "egui")]
+                                                        {
+                                                            self.gui = egui::init(&self.draw, control_flow);
+                                                        }
+                                                        game.lock().await.event(events::Event::DeviceLost).await;
+                                                    }
+                                                    Err(error) => {
+                                                        log::error!("Failed to rebuild the GPU device: {error}");
+                                                        control_flow.exit();
+                                                    }
+                                                }
+                                            }
                                             Err(e) => panic!("{e}"),
                                             _ => (),
                                         };
 
-                                        // sleeps the required time to hit the framerate limit.
-                                        spin_sleep::native_sleep(
-                                            SETTINGS
-                                                .graphics
-                                                .framerate_limit()
-                                                .saturating_sub(start_time.elapsed().unwrap() * 2),
-                                        );
+                                        // Frames skipped by damage tracking do not count as activity.
+                                        if self.draw.idle_until().is_none() {
+                                            SETTINGS.tick_system.mark_active();
+                                        }
                                         crate::TIME.update();
-                                        game.lock().await.frame_update().await;
+                                        time_effects::update();
+                                        #[cfg(feature = "hot_reload")]
+                                        for reload in let_engine_core::resources::hot_reload::SHADER_WATCHER.poll() {
+                                            game.lock().await.event(events::Event::ShaderReloaded(reload)).await;
+                                        }
+                                        #[cfg(feature = "audio")]
+                                        for beat in let_engine_audio::poll_beats() {
+                                            game.lock().await.event(events::Event::Beat(beat)).await;
+                                        }
+                                        crate::PROFILER
+                                            .measure_async(ProfileSection::FrameUpdate, async {
+                                                game.lock().await.frame_update().await;
+                                            })
+                                            .await;
+                                        // Deterministic games only advance game time in ticks.
+                                        if !SETTINGS.tick_system.tick_settings.lock().deterministic {
+                                            crate::SCHEDULER.update();
+                                            crate::ANIMATIONS.update();
+                                        }
+                                        crate::PROFILER.finish_frame();
                                         events::Event::Destroyed
                                     }
                                     _ => events::Event::Destroyed,
@@ -513,13 +775,90 @@ impl_engine_features! {
                                     self.gui.immediate_ui(|gui| {
                                         context = gui.context()
                                     });
+                                    crate::INSPECTOR.show(&context);
                                     game.lock().await.event(events::Event::Egui(context)).await;
                                 }
 
-                                game.lock().await.update().await;
+                                // Files dragged in together arrive as separate events within the same iteration.
+                                let (hovered, dropped) = std::mem::take(&mut self.file_drop);
+                                if !hovered.is_empty() {
+                                    let drop = events::FileDrop { paths: hovered, position: INPUT.cursor_pixels() };
+                                    game.lock().await
+                                        .event(events::Event::Window(events::WindowEvent::FilesHovered(drop)))
+                                        .await;
+                                }
+                                if !dropped.is_empty() {
+                                    let drop = events::FileDrop { paths: dropped, position: INPUT.cursor_pixels() };
+                                    game.lock().await
+                                        .event(events::Event::Window(events::WindowEvent::FilesDropped(drop)))
+                                        .await;
+                                }
+
+                                if let Some(monitors) = self.monitor_watcher.poll(self.draw.window()) {
+                                    game.lock().await
+                                        .event(events::Event::Window(events::WindowEvent::MonitorsChanged(monitors)))
+                                        .await;
+                                }
+
+                                crate::PROFILER
+                                    .measure_async(ProfileSection::Update, async {
+                                        game.lock().await.update().await;
+                                    })
+                                    .await;
+
+                                #[cfg(feature = "hot_state")]
+                                if crate::HOT_STATE.poll() {
+                                    match crate::HOT_STATE.save() {
+                                        Ok(()) => control_flow.exit(),
+                                        Err(error) => {
+                                            log::error!("Failed to save the state for restarting: {error:#}");
+                                            crate::HOT_STATE.set_enabled(false);
+                                        }
+                                    }
+                                }
+                                match SETTINGS.graphics.control_flow() {
+                                    // Nothing can be drawn until the application gets resumed.
+                                    _ if self.draw.is_suspended() => {
+                                        control_flow.set_control_flow(winit::event_loop::ControlFlow::Wait);
+                                    }
+                                    ControlFlow::Poll => match self.draw.idle_until() {
+                                        // Damage tracking skipped the last frame, so nothing changes until the next refresh.
+                                        Some(at) => {
+                                            let at = wake::redraw_at().map_or(at, |redraw| redraw.min(at));
+                                            control_flow.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(at));
+                                        }
+                                        None => {
+                                            control_flow.set_control_flow(winit::event_loop::ControlFlow::Poll);
+                                            self.get_window().request_redraw();
+                                        }
+                                    },
+                                    // Frames requested for later, for example by egui, end the wait early.
+                                    ControlFlow::Wait => match wake::redraw_at() {
+                                        Some(at) => {
+                                            control_flow.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(at));
+                                        }
+                                        None => {
+                                            control_flow.set_control_flow(winit::event_loop::ControlFlow::Wait);
+                                        }
+                                    },
+                                    ControlFlow::WaitUntil(interval) => {
+                                        let at = std::time::Instant::now() + interval;
+                                        let at = wake::redraw_at().map_or(at, |redraw| redraw.min(at));
+                                        control_flow.set_control_flow(winit::event_loop::ControlFlow::WaitUntil(at));
+                                    }
+                                }
+                            }
+                            Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
                                 self.get_window().request_redraw();
                             }
+                            // Sent by other threads through `wake::redraw_after`.
+                            Event::UserEvent(()) => {
+                                if wake::take_due() {
+                                    self.get_window().request_redraw();
+                                }
+                            }
                             Event::LoopExiting => {
+                                SETTINGS.tick_system.shut_down();
                                 #[cfg(feature = "networking")]
                                 {
                                     // Gracefully shutdown both server and client if open.
@@ -531,10 +870,33 @@ impl_engine_features! {
                                     }
                                 }
                                 game.lock().await.event(events::Event::Destroyed).await;
+                                if let Err(error) = let_engine_core::resources::save_pipeline_cache() {
+                                    log::warn!("Failed to save the pipeline cache: {error}");
+                                }
                             }
                             Event::MemoryWarning => {
                                 game.lock().await.event(events::Event::LowMemory).await;
                             }
+                            Event::Suspended => {
+                                self.draw.suspend();
+                                game.lock().await.event(events::Event::Suspended).await;
+                            }
+                            // Also sent once at startup, where the window can already be drawn on.
+                            Event::Resumed => {
+                                if self.draw.is_suspended() {
+                                    match self.draw.resume() {
+                                        // The old gui still draws to the destroyed surface.
+                                        #[cfg(feature = "egui")]
+                                        Ok(()) => self.gui = egui::init(&self.draw, control_flow),
+                                        #[cfg(not(feature = "egui"))]
+                                        Ok(()) => (),
+                                        Err(error) => {
+                                            log::error!("Failed to resume drawing: {error}")
+                                        }
+                                    }
+                                    game.lock().await.event(events::Event::Resumed).await;
+                                }
+                            }
                             Event::NewEvents(StartCause::Init) => {
                                 #[cfg(feature = "egui")]
                                 {
@@ -554,24 +916,43 @@ impl_engine_features! {
                                     Err(e) => panic!("{e}"),
                                     _ => (),
                                 };
+                                #[cfg(feature = "asset_system")]
+                                game.lock().await
+                                    .event(events::Event::ModsLoaded(asset_system::mods::mod_report()))
+                                    .await;
+                                if let Some(splash_screen) = self.splash_screen.take() {
+                                    match splash::Splash::show(splash_screen) {
+                                        Ok(splash) => {
+                                            let game = Arc::clone(&game);
+                                            let start: StartFuture =
+                                                Box::pin(async move {
+                                                    game.lock().await.start().await;
+                                                });
+                                            self.startup = Some((splash, Some(start)));
+                                            self.get_window().initialize();
+                                            return;
+                                        }
+                                        Err(error) => {
+                                            log::error!("Failed to show the splash screen: {error}");
+                                        }
+                                    }
+                                }
                                 game.lock().await.start().await;
+                                #[cfg(feature = "hot_state")]
+                                crate::HOT_STATE.restore_scene();
                                 self.get_window().initialize();
-
-                                let tick_system = std::mem::take(&mut self.tick_system);
-                                if let Some(tick_system) = tick_system {
-                                    let game_clone = Arc::clone(&game);
-                                    smol::spawn(async {
-                                        let mut tick_system = tick_system;
-                                        let game = game_clone;
-                                        tick_system.run(game).await;
-                                    }).detach();
-                                }
+                                self.start_tick_system(&game);
                             }
                             _ => (),
                         }
                 });
             })
             .unwrap();
+
+            #[cfg(feature = "hot_state")]
+            if let Err(error) = crate::HOT_STATE.restart_process() {
+                log::error!("Failed to restart the game: {error}");
+            }
         }
     }
 }
@@ -627,6 +1008,10 @@ pub struct Time {
     delta_instant: crossbeam::atomic::AtomicCell<SystemTime>,
     #[cfg(feature = "client")]
     delta_time: AtomicF64,
+    #[cfg(feature = "client")]
+    target_frame_time: crossbeam::atomic::AtomicCell<Option<Duration>>,
+    #[cfg(feature = "client")]
+    missed_frames: std::sync::atomic::AtomicU64,
     pub(crate) zero_cvar: (Mutex<()>, Condvar),
 }
 
@@ -639,6 +1024,10 @@ impl Default for Time {
             delta_instant: crossbeam::atomic::AtomicCell::new(SystemTime::now()),
             #[cfg(feature = "client")]
             delta_time: AtomicF64::new(0.0f64),
+            #[cfg(feature = "client")]
+            target_frame_time: crossbeam::atomic::AtomicCell::new(None),
+            #[cfg(feature = "client")]
+            missed_frames: std::sync::atomic::AtomicU64::new(0),
             zero_cvar: (Mutex::new(()), Condvar::new()),
         }
     }
@@ -663,6 +1052,15 @@ impl Time {
         self.delta_time.load(Ordering::Acquire) * self.scale()
     }
 
+    /// Returns the time it took to execute last iteration, scaled by the time scales of the game and the given layer.
+    ///
+    /// Returns 0 while the layer is paused.
+    #[inline]
+    #[cfg(feature = "client")]
+    pub fn layer_delta_time(&self, layer: &let_engine_core::objects::scenes::Layer) -> f64 {
+        self.delta_time() * layer.effective_time_scale()
+    }
+
     /// Returns the delta time of the update iteration that does not scale with the time scale.
     #[inline]
     #[cfg(feature = "client")]
@@ -677,6 +1075,32 @@ impl Time {
         1.0 / self.delta_time.load(Ordering::Acquire)
     }
 
+    /// Records the pacing outcome of the frame about to be drawn.
+    #[inline]
+    #[cfg(feature = "client")]
+    pub(crate) fn record_frame(&self, target: Option<Duration>, missed: bool) {
+        self.target_frame_time.store(target);
+        if missed {
+            self.missed_frames.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the time between frames the frame pacing aims for, `None` in case frames are not paced.
+    #[inline]
+    #[cfg(feature = "client")]
+    pub fn target_frame_time(&self) -> Option<Duration> {
+        self.target_frame_time.load()
+    }
+
+    /// Returns the number of frames that started too late to keep the target frame time since the start of the game.
+    ///
+    /// Frames only get counted while frame pacing is on.
+    #[inline]
+    #[cfg(feature = "client")]
+    pub fn missed_frames(&self) -> u64 {
+        self.missed_frames.load(Ordering::Relaxed)
+    }
+
     /// Returns the time since start of the engine game session.
     #[inline]
     pub fn time(&self) -> f64 {
@@ -0,0 +1,76 @@
+//! Noticing when monitors get connected, disconnected or change their video mode.
+
+use std::time::{Duration, Instant};
+
+use glam::Vec2;
+use let_engine_core::window::{Monitor, Window};
+
+/// How often the monitor configuration gets compared to the last one without a hint that it changed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// How long after a window event hinting at a change the monitors get checked,
+/// so events coming in quick succession like moving the window only check once.
+const HINT_DELAY: Duration = Duration::from_millis(250);
+
+/// The properties of a monitor that matter to the settings menus of games.
+#[derive(PartialEq)]
+struct MonitorState {
+    name: Option<String>,
+    size: Vec2,
+    position: Vec2,
+    refresh_rate: Option<u32>,
+    scale_factor: f64,
+}
+
+impl From<&Monitor> for MonitorState {
+    fn from(monitor: &Monitor) -> Self {
+        Self {
+            name: monitor.name(),
+            size: monitor.size(),
+            position: monitor.position(),
+            refresh_rate: monitor.refresh_rate(),
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+/// Polls the monitors of the system, as winit has no event for changes of them.
+///
+/// Enumerating monitors can be slow on some platforms, so they only get checked rarely,
+/// and shortly after window events that usually come along with monitor changes.
+pub(crate) struct MonitorWatcher {
+    /// The time of the next check.
+    due: Instant,
+    configuration: Vec<MonitorState>,
+}
+
+impl MonitorWatcher {
+    pub fn new(window: &Window) -> Self {
+        Self {
+            due: Instant::now() + CHECK_INTERVAL,
+            configuration: window.monitors().iter().map(MonitorState::from).collect(),
+        }
+    }
+
+    /// Checks the monitors soon, after a window event hinting at a change of them,
+    /// like the window moving to another monitor or its scale factor changing.
+    pub fn hint(&mut self) {
+        self.due = self.due.min(Instant::now() + HINT_DELAY);
+    }
+
+    /// Returns the monitors in case their configuration changed since the last check.
+    pub fn poll(&mut self, window: &Window) -> Option<Vec<Monitor>> {
+        let now = Instant::now();
+        if now < self.due {
+            return None;
+        }
+        self.due = now + CHECK_INTERVAL;
+
+        let monitors = window.monitors();
+        let configuration: Vec<MonitorState> = monitors.iter().map(MonitorState::from).collect();
+        if configuration == self.configuration {
+            return None;
+        }
+        self.configuration = configuration;
+        Some(monitors)
+    }
+}
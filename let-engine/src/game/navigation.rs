@@ -0,0 +1,654 @@
+//! Finding paths through the world on a navigation grid.
+//!
+//! Only grids are supported. Navigation meshes are out of scope for now, as the smoothing of grid paths
+//! already removes the staircase steps a grid would otherwise cause, and a grid can follow moving obstacles cheaply.
+//!
+//! # Usage
+//! ```ignore
+//! // A 64 by 64 grid of cells 1 unit wide, blocked wherever the layer has a collider that does not move.
+//! let mut grid = NavGrid::from_layer(&layer, vec2(-32.0, -32.0), 1.0, uvec2(64, 64), 0.4);
+//! // Crates have a dynamic body and get pushed around, so the grid has to follow them.
+//! grid.add_obstacle(&crate_object);
+//!
+//! // in the tick function
+//! grid.update_obstacles();
+//! if let Some(path) = grid.find_path(enemy.transform.position, player.transform.position).await {
+//!     // walk to path[1]
+//! }
+//! ```
+
+use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
+
+use glam::{ivec2, IVec2, UVec2, Vec2};
+#[cfg(feature = "physics")]
+use let_engine_core::objects::{
+    physics::RigidBodyType, scenes::Layer, Object, ObjectHandle, Transform,
+};
+
+/// The cost of moving to a neighbouring cell straight and diagonally, roughly 1 and the square root of 2.
+const STRAIGHT: u32 = 10;
+const DIAGONAL: u32 = 14;
+
+const NEIGHBOURS: [IVec2; 8] = [
+    ivec2(1, 0),
+    ivec2(-1, 0),
+    ivec2(0, 1),
+    ivec2(0, -1),
+    ivec2(1, 1),
+    ivec2(1, -1),
+    ivec2(-1, 1),
+    ivec2(-1, -1),
+];
+
+/// A grid of walkable and blocked cells covering a rectangular area of the world.
+///
+/// Paths move between neighbouring cells in 8 directions without cutting the corners of blocked cells,
+/// and get smoothed to skip every point that can be reached in a straight line.
+pub struct NavGrid {
+    grid: Grid,
+    /// Cells blocked by the level itself, like the walls of a tilemap.
+    walls: Vec<bool>,
+    /// How far the agents walking the grid stay away from obstacles.
+    #[cfg(feature = "physics")]
+    agent_radius: f32,
+    #[cfg(feature = "physics")]
+    obstacles: Vec<Obstacle>,
+}
+
+/// An object blocking the cells below its collider.
+#[cfg(feature = "physics")]
+struct Obstacle {
+    object: Object,
+    transform: Transform,
+    cells: Vec<usize>,
+}
+
+/// The part of the grid needed for path queries, cheap to clone for queries running on another thread.
+#[derive(Clone)]
+struct Grid {
+    origin: Vec2,
+    cell_size: f32,
+    size: UVec2,
+    /// The number of walls and obstacles covering each cell.
+    blockers: Arc<Vec<u16>>,
+}
+
+impl NavGrid {
+    /// Creates a grid with only walkable cells.
+    ///
+    /// The origin is the corner of the first cell in world space, from which the grid extends into the positive direction of both axes.
+    pub fn new(origin: Vec2, cell_size: f32, size: UVec2) -> Self {
+        let cells = (size.x * size.y) as usize;
+        Self {
+            grid: Grid {
+                origin,
+                cell_size,
+                size,
+                blockers: Arc::new(vec![0; cells]),
+            },
+            walls: vec![false; cells],
+            #[cfg(feature = "physics")]
+            agent_radius: 0.0,
+            #[cfg(feature = "physics")]
+            obstacles: vec![],
+        }
+    }
+
+    /// Creates a grid from the tiles of a tilemap stored row by row, with one cell per tile.
+    ///
+    /// The given function decides which tiles can be walked on.
+    pub fn from_tiles<T>(
+        origin: Vec2,
+        cell_size: f32,
+        width: u32,
+        tiles: &[T],
+        walkable: impl Fn(&T) -> bool,
+    ) -> Self {
+        let height = tiles.len() as u32 / width.max(1);
+        let mut grid = Self::new(origin, cell_size, UVec2::new(width, height));
+        for (index, tile) in tiles.iter().enumerate().take(grid.walls.len()) {
+            if !walkable(tile) {
+                grid.set_wall(index, true);
+            }
+        }
+        grid
+    }
+
+    /// Creates a grid blocking every cell overlapping a collider of the layer.
+    ///
+    /// Cells closer than the agent radius to a collider count as blocked as well,
+    /// so agents following the path do not get stuck on corners.
+    ///
+    /// Colliders of dynamic and kinematic bodies get skipped, as they would leave walls behind when they move.
+    /// Add those using [add_obstacle](Self::add_obstacle) instead.
+    #[cfg(feature = "physics")]
+    pub fn from_layer(
+        layer: &Arc<Layer>,
+        origin: Vec2,
+        cell_size: f32,
+        size: UVec2,
+        agent_radius: f32,
+    ) -> Self {
+        let mut grid = Self::new(origin, cell_size, size);
+        grid.agent_radius = agent_radius;
+        let half = cell_size * 0.5 + agent_radius;
+        let shape = let_engine_core::objects::physics::Shape::square(half, half);
+        for index in 0..grid.walls.len() {
+            let center = grid.cell_center(grid.grid.cell(index));
+            if layer
+                .intersections_with_shape(shape.clone(), (center, 0.0))
+                .into_iter()
+                .any(|handle| {
+                    layer
+                        .object(handle)
+                        .is_some_and(|object| is_static(&object))
+                })
+            {
+                grid.set_wall(index, true);
+            }
+        }
+        grid
+    }
+
+    /// Returns the corner of the first cell in world space.
+    pub fn origin(&self) -> Vec2 {
+        self.grid.origin
+    }
+
+    /// Returns the width and height of a single cell.
+    pub fn cell_size(&self) -> f32 {
+        self.grid.cell_size
+    }
+
+    /// Returns the number of cells in each direction.
+    pub fn size(&self) -> UVec2 {
+        self.grid.size
+    }
+
+    /// Returns the cell at the given position in world space, `None` if it is outside of the grid.
+    pub fn cell_at(&self, position: Vec2) -> Option<UVec2> {
+        let cell = self.grid.cell_at(position);
+        self.grid.contains(cell).then(|| cell.as_uvec2())
+    }
+
+    /// Returns the center of the given cell in world space.
+    pub fn cell_center(&self, cell: UVec2) -> Vec2 {
+        self.grid.center(cell.as_ivec2())
+    }
+
+    /// Returns true if the cell is inside the grid and neither a wall nor covered by an obstacle.
+    pub fn is_walkable(&self, cell: UVec2) -> bool {
+        self.grid.walkable(cell.as_ivec2())
+    }
+
+    /// Makes the cell a wall or removes the wall. Obstacles covering the cell keep blocking it.
+    pub fn set_walkable(&mut self, cell: UVec2, walkable: bool) {
+        if self.grid.contains(cell.as_ivec2()) {
+            self.set_wall(self.grid.index(cell.as_ivec2()), !walkable);
+        }
+    }
+
+    fn set_wall(&mut self, index: usize, wall: bool) {
+        if self.walls[index] == wall {
+            return;
+        }
+        self.walls[index] = wall;
+        let blockers = &mut Arc::make_mut(&mut self.grid.blockers)[index];
+        if wall {
+            *blockers += 1;
+        } else {
+            *blockers -= 1;
+        }
+    }
+
+    /// Finds a path from one position in world space to another on a background thread.
+    ///
+    /// The path starts at `from`, ends at `to` and only contains the corners in between.
+    /// Returns `None` in case the destination is blocked, outside of the grid or can not be reached.
+    ///
+    /// Changes to the grid while the query runs do not affect it.
+    pub async fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let grid = self.grid.clone();
+        smol::unblock(move || grid.find_path(from, to)).await
+    }
+
+    /// Exactly the same as [find_path](Self::find_path) but not async and blocking.
+    pub fn find_path_blocking(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        self.grid.find_path(from, to)
+    }
+}
+
+/// Returns true if the object has no rigid body or a fixed one, so only the game can move it.
+#[cfg(feature = "physics")]
+fn is_static(object: &Object) -> bool {
+    object
+        .rigid_body()
+        .is_none_or(|body| body.body_type() == RigidBodyType::Fixed)
+}
+
+/// Dynamic obstacles
+#[cfg(feature = "physics")]
+impl NavGrid {
+    /// Returns how far agents stay away from obstacles.
+    pub fn agent_radius(&self) -> f32 {
+        self.agent_radius
+    }
+
+    /// Sets how far agents stay away from obstacles, taking effect for obstacles once they move.
+    pub fn set_agent_radius(&mut self, radius: f32) {
+        self.agent_radius = radius;
+    }
+
+    /// Blocks the cells below the bounding box of the collider of the object until it gets removed.
+    ///
+    /// Moving the object moves the blocked cells along on the next [update_obstacles](Self::update_obstacles) call.
+    /// Objects without a collider do not block anything.
+    pub fn add_obstacle(&mut self, object: &Object) {
+        self.remove_obstacle(object.handle());
+        let mut obstacle = Obstacle {
+            object: object.clone(),
+            transform: object.public_transform(),
+            cells: vec![],
+        };
+        self.block(&mut obstacle);
+        self.obstacles.push(obstacle);
+    }
+
+    /// Unblocks the cells of the obstacle with the given object handle.
+    pub fn remove_obstacle(&mut self, handle: ObjectHandle) {
+        if let Some(index) = self
+            .obstacles
+            .iter()
+            .position(|obstacle| obstacle.object.handle() == handle)
+        {
+            let mut obstacle = self.obstacles.swap_remove(index);
+            self.unblock(&mut obstacle);
+        }
+    }
+
+    /// Moves the blocked cells of all obstacles to where their objects are now.
+    ///
+    /// Obstacles whose objects got removed from their layer get removed as well.
+    /// Returns true if any cell changed.
+    pub fn update_obstacles(&mut self) -> bool {
+        let mut changed = false;
+        let mut obstacles = std::mem::take(&mut self.obstacles);
+        obstacles.retain_mut(|obstacle| {
+            if obstacle.object.update().is_err() {
+                changed |= !obstacle.cells.is_empty();
+                self.unblock(obstacle);
+                return false;
+            }
+            let transform = obstacle.object.public_transform();
+            if transform != obstacle.transform {
+                obstacle.transform = transform;
+                let before = std::mem::take(&mut obstacle.cells);
+                self.release(&before);
+                self.block(obstacle);
+                changed |= before != obstacle.cells;
+            }
+            true
+        });
+        self.obstacles = obstacles;
+        changed
+    }
+
+    /// Blocks the cells below the collider of the obstacle at its current transform.
+    fn block(&mut self, obstacle: &mut Obstacle) {
+        let Some(collider) = obstacle.object.collider() else {
+            return;
+        };
+        let transform = obstacle.transform;
+        let (min, max) = collider
+            .shape()
+            .aabb(transform.position, transform.rotation);
+        let radius = Vec2::splat(self.agent_radius);
+        let min = self.grid.cell_at(min - radius).max(IVec2::ZERO);
+        let max = self
+            .grid
+            .cell_at(max + radius)
+            .min(self.grid.size.as_ivec2() - 1);
+
+        let blockers = Arc::make_mut(&mut self.grid.blockers);
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let index = (y as u32 * self.grid.size.x + x as u32) as usize;
+                blockers[index] += 1;
+                obstacle.cells.push(index);
+            }
+        }
+    }
+
+    fn unblock(&mut self, obstacle: &mut Obstacle) {
+        let cells = std::mem::take(&mut obstacle.cells);
+        self.release(&cells);
+    }
+
+    fn release(&mut self, cells: &[usize]) {
+        let blockers = Arc::make_mut(&mut self.grid.blockers);
+        for index in cells {
+            blockers[*index] -= 1;
+        }
+    }
+}
+
+impl Grid {
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size.as_ivec2()).all()
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y as u32 * self.size.x + cell.x as u32) as usize
+    }
+
+    fn cell(&self, index: usize) -> UVec2 {
+        let index = index as u32;
+        UVec2::new(index % self.size.x, index / self.size.x)
+    }
+
+    /// Returns the cell at the position, which may be outside of the grid.
+    fn cell_at(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.cell_size)
+            .floor()
+            .as_ivec2()
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * self.cell_size
+    }
+
+    fn walkable(&self, cell: IVec2) -> bool {
+        self.contains(cell) && self.blockers[self.index(cell)] == 0
+    }
+
+    fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let (start, goal) = (self.cell_at(from), self.cell_at(to));
+        // Agents standing close to an obstacle may start on a blocked cell.
+        if !self.contains(start) || !self.walkable(goal) {
+            return None;
+        }
+        let cells = self.search(start, goal)?;
+        Some(self.smooth(from, to, &cells))
+    }
+
+    /// Runs A* from the start to the goal cell, returning the cells of the path in order.
+    fn search(&self, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        let heuristic = |cell: IVec2| {
+            let distance = (cell - goal).abs();
+            let (min, max) = (distance.min_element(), distance.max_element());
+            STRAIGHT * (max - min) as u32 + DIAGONAL * min as u32
+        };
+
+        let len = self.blockers.len();
+        let mut costs = vec![u32::MAX; len];
+        let mut parents = vec![usize::MAX; len];
+        let mut open = BinaryHeap::new();
+        let (start_index, goal_index) = (self.index(start), self.index(goal));
+        costs[start_index] = 0;
+        open.push(Reverse((heuristic(start), start_index)));
+
+        while let Some(Reverse((_, current))) = open.pop() {
+            if current == goal_index {
+                let mut path = vec![goal];
+                let mut index = current;
+                while parents[index] != usize::MAX {
+                    index = parents[index];
+                    path.push(self.cell(index).as_ivec2());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let cell = self.cell(current).as_ivec2();
+            for offset in NEIGHBOURS {
+                let next = cell + offset;
+                if !self.walkable(next) {
+                    continue;
+                }
+                let diagonal = offset.x != 0 && offset.y != 0;
+                if diagonal
+                    && (!self.walkable(cell + ivec2(offset.x, 0))
+                        || !self.walkable(cell + ivec2(0, offset.y)))
+                {
+                    continue;
+                }
+                let cost = costs[current] + if diagonal { DIAGONAL } else { STRAIGHT };
+                let next_index = self.index(next);
+                if cost < costs[next_index] {
+                    costs[next_index] = cost;
+                    parents[next_index] = current;
+                    open.push(Reverse((cost + heuristic(next), next_index)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Turns the cells into world positions, leaving out every point that can be skipped by walking in a straight line.
+    fn smooth(&self, from: Vec2, to: Vec2, cells: &[IVec2]) -> Vec<Vec2> {
+        let mut points: Vec<Vec2> = cells.iter().map(|cell| self.center(*cell)).collect();
+        // Walk exactly from the start to the destination instead of the cell centers.
+        points[0] = from;
+        *points.last_mut().unwrap() = to;
+
+        let mut path = vec![from];
+        let mut anchor = from;
+        for window in points.windows(2).skip(1) {
+            if !self.line_of_sight(anchor, window[1]) {
+                anchor = window[0];
+                path.push(anchor);
+            }
+        }
+        path.push(to);
+        path
+    }
+
+    /// Checks if every cell the line between both positions passes through is walkable.
+    fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let a = (from - self.origin) / self.cell_size;
+        let b = (to - self.origin) / self.cell_size;
+        let delta = b - a;
+        let mut cell = a.floor().as_ivec2();
+        let end = b.floor().as_ivec2();
+
+        // Walk from cell to cell along the line, always crossing the nearest cell border next.
+        let step = ivec2(
+            (delta.x > 0.0) as i32 - (delta.x < 0.0) as i32,
+            (delta.y > 0.0) as i32 - (delta.y < 0.0) as i32,
+        );
+        let border_distance = |position: f32, cell: i32, delta: f32| {
+            if delta > 0.0 {
+                (cell as f32 + 1.0 - position) / delta
+            } else if delta < 0.0 {
+                (position - cell as f32) / -delta
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut next_border = Vec2::new(
+            border_distance(a.x, cell.x, delta.x),
+            border_distance(a.y, cell.y, delta.y),
+        );
+        let border_step = 1.0 / delta.abs();
+
+        let crossings = (end - cell).abs().element_sum();
+        for _ in 0..=crossings {
+            if !self.walkable(cell) {
+                return false;
+            }
+            if cell == end {
+                return true;
+            }
+            if next_border.x < next_border.y {
+                cell.x += step.x;
+                next_border.x += border_step.x;
+            } else {
+                cell.y += step.y;
+                next_border.y += border_step.y;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec2;
+
+    /// Makes a grid with cells of size 1 at the origin from rows written top to bottom, `#` being a wall.
+    fn grid(rows: &[&str]) -> NavGrid {
+        let width = rows[0].len() as u32;
+        let tiles: Vec<u8> = rows.iter().rev().flat_map(|row| row.bytes()).collect();
+        NavGrid::from_tiles(Vec2::ZERO, 1.0, width, &tiles, |tile| *tile != b'#')
+    }
+
+    /// Checks that every segment of the path only crosses walkable cells.
+    fn assert_walkable(grid: &NavGrid, path: &[Vec2]) {
+        for segment in path.windows(2) {
+            assert!(
+                grid.grid.line_of_sight(segment[0], segment[1]),
+                "{segment:?} crosses a wall"
+            );
+        }
+    }
+
+    #[test]
+    fn open_grid_walks_straight() {
+        let grid = grid(&["....", "....", "...."]);
+        let (from, to) = (vec2(0.5, 0.5), vec2(3.5, 2.5));
+
+        assert_eq!(grid.find_path_blocking(from, to), Some(vec![from, to]));
+    }
+
+    #[test]
+    fn paths_go_around_walls() {
+        let grid = grid(&[
+            ".....", //
+            ".###.", ".#...", ".#.#.", "...#.",
+        ]);
+        let (from, to) = (vec2(2.5, 2.5), vec2(0.5, 4.5));
+        let path = grid.find_path_blocking(from, to).unwrap();
+
+        assert_eq!(path.first(), Some(&from));
+        assert_eq!(path.last(), Some(&to));
+        assert!(path.len() > 2);
+        assert_walkable(&grid, &path);
+    }
+
+    #[test]
+    fn unreachable_destinations() {
+        let grid = grid(&[
+            ".....", //
+            ".###.", ".#.#.", ".###.", ".....",
+        ]);
+        // Walled in
+        assert_eq!(
+            grid.find_path_blocking(vec2(0.5, 0.5), vec2(2.5, 2.5)),
+            None
+        );
+        // On a wall
+        assert_eq!(
+            grid.find_path_blocking(vec2(0.5, 0.5), vec2(1.5, 1.5)),
+            None
+        );
+        // Outside of the grid
+        assert_eq!(
+            grid.find_path_blocking(vec2(0.5, 0.5), vec2(7.0, 0.5)),
+            None
+        );
+        assert_eq!(
+            grid.find_path_blocking(vec2(-1.0, 0.5), vec2(0.5, 0.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn corners_do_not_get_cut() {
+        let grid = grid(&[
+            "#.", //
+            ".#",
+        ]);
+        // The only connection is diagonally between two walls.
+        assert_eq!(
+            grid.find_path_blocking(vec2(0.5, 0.5), vec2(1.5, 1.5)),
+            None
+        );
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let grid = grid(&[
+            "....", //
+            ".#..", "....",
+        ]);
+        let grid = &grid.grid;
+
+        assert!(grid.line_of_sight(vec2(0.5, 0.5), vec2(3.5, 0.5)));
+        assert!(grid.line_of_sight(vec2(0.2, 0.2), vec2(0.8, 0.7)));
+        assert!(grid.line_of_sight(vec2(2.5, 0.5), vec2(3.5, 2.5)));
+        assert!(!grid.line_of_sight(vec2(0.5, 1.5), vec2(3.5, 1.5)));
+        assert!(!grid.line_of_sight(vec2(0.5, 0.5), vec2(2.5, 2.5)));
+        // Leaving the grid
+        assert!(!grid.line_of_sight(vec2(3.5, 0.5), vec2(4.5, 0.5)));
+    }
+
+    #[test]
+    fn smoothing_keeps_the_corners() {
+        let grid = grid(&[
+            "...", //
+            "##.", "...",
+        ]);
+        let path = grid
+            .find_path_blocking(vec2(0.5, 0.5), vec2(0.5, 2.5))
+            .unwrap();
+
+        assert_eq!(path.len(), 4);
+        assert_walkable(&grid, &path);
+    }
+
+    #[test]
+    fn walls_can_be_removed() {
+        let mut grid = grid(&[".#."]);
+        let (from, to) = (vec2(0.5, 0.5), vec2(2.5, 0.5));
+        assert_eq!(grid.find_path_blocking(from, to), None);
+
+        grid.set_walkable(UVec2::new(1, 0), true);
+        assert!(grid.is_walkable(UVec2::new(1, 0)));
+        assert_eq!(grid.find_path_blocking(from, to), Some(vec![from, to]));
+    }
+
+    #[cfg(feature = "physics")]
+    #[test]
+    fn moving_obstacles_leave_no_walls_behind() {
+        use let_engine_core::objects::{
+            physics::{ColliderBuilder, RigidBodyBuilder},
+            scenes::SCENE,
+            NewObject,
+        };
+
+        let layer = SCENE.new_layer();
+        let mut wall = NewObject::new();
+        wall.set_isometry(vec2(0.5, 0.5), 0.0);
+        wall.set_collider(Some(ColliderBuilder::square(0.4, 0.4).build()));
+        wall.init(&layer).unwrap();
+
+        let mut crate_object = NewObject::new();
+        crate_object.set_isometry(vec2(2.5, 0.5), 0.0);
+        crate_object.set_collider(Some(ColliderBuilder::square(0.4, 0.4).build()));
+        crate_object.set_rigid_body(Some(RigidBodyBuilder::dynamic().build()));
+        let mut crate_object = crate_object.init(&layer).unwrap();
+
+        let mut grid = NavGrid::from_layer(&layer, Vec2::ZERO, 1.0, UVec2::new(4, 1), 0.0);
+        assert!(!grid.is_walkable(UVec2::new(0, 0)));
+        assert!(grid.is_walkable(UVec2::new(2, 0)));
+
+        grid.add_obstacle(&crate_object);
+        assert!(!grid.is_walkable(UVec2::new(2, 0)));
+
+        crate_object.transform.position = vec2(3.5, 0.5);
+        crate_object.sync().unwrap();
+        assert!(grid.update_obstacles());
+        assert!(grid.is_walkable(UVec2::new(2, 0)));
+        assert!(!grid.is_walkable(UVec2::new(3, 0)));
+    }
+}
@@ -24,7 +24,10 @@ use serde::{Deserialize, Serialize};
 
 use crate::SETTINGS;
 
-use super::{serialize_tcp, Connection, Disconnected, Messages, RemoteMessage};
+use super::{
+    crypto, serialize_tcp, Channel, Cipher, Connection, Disconnected, Messages, RemoteMessage,
+    ServerInfo,
+};
 
 struct Socket {
     client: Mutex<Option<TcpStream>>,
@@ -37,6 +40,12 @@ struct Socket {
 
     ping_timestamp: AtomicCell<Option<SystemTime>>,
     ping: AtomicCell<Duration>,
+
+    last_seen: AtomicCell<SystemTime>,
+    timed_out: AtomicBool,
+
+    cipher: parking_lot::Mutex<Option<Arc<Cipher>>>,
+    auth_token: parking_lot::Mutex<Vec<u8>>,
 }
 
 impl Socket {
@@ -94,6 +103,10 @@ where
                     )),
                     ping_timestamp: AtomicCell::new(None),
                     ping: AtomicCell::new(Duration::default()),
+                    last_seen: AtomicCell::new(SystemTime::now()),
+                    timed_out: AtomicBool::new(false),
+                    cipher: parking_lot::Mutex::new(None),
+                    auth_token: parking_lot::Mutex::new(Vec::new()),
                 }),
                 messages: unbounded(),
             };
@@ -117,6 +130,18 @@ where
                 if !socket.connected.load(std::sync::atomic::Ordering::Acquire) {
                     continue;
                 }
+                // Close the connection, so the receiving task reports the timeout.
+                if socket.last_seen.load().elapsed().unwrap_or_default()
+                    > SETTINGS.networking.timeout()
+                {
+                    socket
+                        .timed_out
+                        .store(true, std::sync::atomic::Ordering::Release);
+                    if let Some(client) = socket.client.lock().await.as_ref() {
+                        let _ = client.shutdown(std::net::Shutdown::Both);
+                    }
+                    continue;
+                }
                 if let Some(timestamp) = socket.ping_timestamp.load() {
                     if timestamp.elapsed().unwrap() > Duration::from_secs(10) {
                         socket.start_ping().await;
@@ -136,6 +161,7 @@ where
             let socket = socket;
             let messages = messages;
             let connection = socket.remote_connection.load();
+            let cipher = socket.cipher.lock().clone();
 
             let disconnect_reason;
 
@@ -177,9 +203,10 @@ where
                         disconnect_reason = e.into();
                         break;
                     };
+                    socket.last_seen.store(SystemTime::now());
 
                     // Send the message if it's correctly deserialized.
-                    let _ = match bincode::deserialize::<Msg>(&buf) {
+                    let _ = match super::deserialize::<Msg>(&buf, Channel::Tcp, cipher.as_deref()) {
                         Ok(message) => {
                             messages
                                 .send((connection, RemoteMessage::Tcp(message)))
@@ -198,6 +225,14 @@ where
                     };
                 }
             }
+            let disconnect_reason = if socket
+                .timed_out
+                .swap(false, std::sync::atomic::Ordering::AcqRel)
+            {
+                Disconnected::TimedOut
+            } else {
+                disconnect_reason
+            };
             Self::disconnect_with(messages, connection, disconnect_reason, &socket.client).await;
             socket
                 .connected
@@ -223,12 +258,12 @@ where
                 let Ok(size) = socket.udp_socket.recv(&mut buf).await else {
                     continue;
                 };
+                socket.last_seen.store(SystemTime::now());
 
                 if let Some(message) = buffered_message.as_mut() {
                     if !message.outdated() {
                         if let Some(data) = message.completed(&buf[..size]) {
-                            Self::submit_udp_message(&messages, &socket.remote_connection, data)
-                                .await;
+                            Self::submit_udp_message(&messages, &socket, data).await;
                             buffered_message = None;
                         }
                         continue;
@@ -287,7 +322,7 @@ where
                 let mut buffering = super::BufferingMessage::new(len);
 
                 if let Some(data) = buffering.completed(&buf[8..]) {
-                    Self::submit_udp_message(&messages, &socket.remote_connection, data).await;
+                    Self::submit_udp_message(&messages, &socket, data).await;
                 } else {
                     buffered_message = Some(buffering);
                 }
@@ -298,12 +333,13 @@ where
 
     async fn submit_udp_message(
         messages: &Sender<(Connection, RemoteMessage<Msg>)>,
-        remote_addr: &AtomicCell<Connection>,
+        socket: &Socket,
         buf: &[u8],
     ) {
-        if let Ok(message) = bincode::deserialize::<Msg>(buf) {
+        let cipher = socket.cipher.lock().clone();
+        if let Ok(message) = super::deserialize::<Msg>(buf, Channel::Udp, cipher.as_deref()) {
             let _ = messages
-                .send((remote_addr.load(), RemoteMessage::Udp(message)))
+                .send((socket.remote_connection.load(), RemoteMessage::Udp(message)))
                 .await
                 .is_err();
         }
@@ -381,6 +417,19 @@ where
             .await
             .map_err(|_| ClientError::ServerFull)?;
 
+        let cipher = crypto::exchange_keys(&mut tcp_socket, false)
+            .await
+            .map_err(ClientError::Io)?;
+        let token = self.socket.auth_token.lock().clone();
+        if !crypto::send_token(&mut tcp_socket, &token, cipher.as_ref())
+            .await
+            .map_err(ClientError::Io)?
+        {
+            let _ = tcp_socket.shutdown(std::net::Shutdown::Both);
+            return Err(ClientError::Rejected);
+        }
+        *self.socket.cipher.lock() = cipher.map(Arc::new);
+
         let retries = SETTINGS.networking.auth_retries();
         let wait_time = SETTINGS.networking.auth_retry_wait();
 
@@ -407,12 +456,22 @@ where
             }
         }
 
+        self.socket.last_seen.store(SystemTime::now());
         self.socket
             .connected
             .store(true, std::sync::atomic::Ordering::Release);
         *self.socket.client.lock().await = Some(tcp_socket);
         self.recv_messages();
 
+        let _ = self
+            .messages
+            .0
+            .send((
+                self.socket.remote_connection.load(),
+                RemoteMessage::Connected,
+            ))
+            .await;
+
         Ok(())
     }
 
@@ -464,8 +523,11 @@ where
     ///   sending actions like pressing a button, opening a door, triggering a skill.
     pub async fn send(&self, message: &Msg) -> Result<(), ClientError> {
         if let Some(client) = self.socket.client.lock().await.as_mut() {
+            let cipher = self.socket.cipher.lock().clone();
             client
-                .write_all(&serialize_tcp(message).map_err(ClientError::Bincode)?)
+                .write_all(
+                    &serialize_tcp(message, cipher.as_deref()).map_err(ClientError::Bincode)?,
+                )
                 .await
                 .map_err(ClientError::Io)?;
         } else {
@@ -509,11 +571,13 @@ where
             return Err(ClientError::NotConnected);
         }
 
+        let cipher = self.socket.cipher.lock().clone();
         let data = super::serialize_udp(
             self.socket
                 .udp_order
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
             message,
+            cipher.as_deref(),
         )
         .map_err(ClientError::Bincode)?;
         let chunks = data.chunks(1024);
@@ -529,6 +593,44 @@ where
         Ok(())
     }
 
+    /// Searches the local network for servers running on the given port.
+    ///
+    /// Broadcasts a query and collects the info of every server answering within the wait time.
+    pub async fn discover_lan_servers(
+        &self,
+        port: u16,
+        wait: Duration,
+    ) -> Result<Vec<(SocketAddr, ServerInfo)>, ClientError> {
+        let addr = SocketAddr::from((std::net::Ipv4Addr::BROADCAST, port));
+        super::lobby::query(addr, true, wait)
+            .await
+            .map_err(ClientError::Io)
+    }
+
+    /// Asks the server at the remote address for its info without connecting to it.
+    ///
+    /// Works without being connected and retries the query like the connection auth as set in the networking settings.
+    pub async fn query_server_info(&self) -> Result<ServerInfo, ClientError> {
+        super::lobby::query(
+            self.remote_addr(),
+            false,
+            SETTINGS.networking.auth_retry_wait(),
+        )
+        .await
+        .map_err(ClientError::Io)?
+        .pop()
+        .map(|(_, info)| info)
+        .ok_or(ClientError::NoResponse)
+    }
+
+    /// Sets the token sent to the server on the next connection for authentication.
+    ///
+    /// The server decides whether to accept the token using [`GameServer::set_authenticator`](super::GameServer::set_authenticator).
+    /// Tokens can not be longer than [`MAX_TOKEN_SIZE`](super::MAX_TOKEN_SIZE) bytes.
+    pub fn set_auth_token(&self, token: impl Into<Vec<u8>>) {
+        *self.socket.auth_token.lock() = token.into();
+    }
+
     /// Returns the last calculated ping of the last running connection.
     ///
     /// May return a duration of 0 in case no calculation has been done before this function.
@@ -547,6 +649,12 @@ pub enum ClientError {
     NotConnected,
     #[error("The server you attepted to connect to is full.")]
     ServerFull,
+    /// The server did not accept the authentication token of this client.
+    #[error("The server rejected the authentication of this client.")]
+    Rejected,
+    /// The server did not answer in time.
+    #[error("The server did not respond.")]
+    NoResponse,
     /// The server sends a message invalid to the let-engine interface.
     #[error("The server is sending invalid data.")]
     InvalidResponse,
@@ -0,0 +1,327 @@
+//! Session encryption and the connection handshake.
+//!
+//! With the `encryption` feature both sides exchange ephemeral X25519 keys right after the session ID
+//! and derive a key for each direction from the shared secret using HKDF-SHA256.
+//! Every TCP and UDP message gets encrypted with ChaCha20-Poly1305 and prefixed with its 8 byte sequence number,
+//! which makes up the nonce together with the channel and is authenticated as associated data.
+//! Messages with a sequence number not higher than the last accepted one of their channel get rejected as replays.
+//!
+//! Without a [pre-shared key](super::Networking::set_pre_shared_key) the exchange is anonymous,
+//! so it protects against eavesdropping but not against a man in the middle.
+//! With one, it gets mixed into the session keys and only peers knowing it can talk to each other.
+//!
+//! Both peers have to be compiled with the same setting of the feature to understand each other.
+
+#[cfg(feature = "encryption")]
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use smol::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+#[cfg(feature = "encryption")]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The maximum size of an authentication token in bytes.
+pub const MAX_TOKEN_SIZE: usize = 4096;
+
+/// The transport a message got sent with, each counting its own sequence numbers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Channel {
+    Tcp = 0,
+    Udp = 1,
+}
+
+/// The symmetric ciphers of an encrypted session.
+#[cfg(feature = "encryption")]
+pub(crate) struct Cipher {
+    send: ChaCha20Poly1305,
+    receive: ChaCha20Poly1305,
+    /// The sequence number of the next sent message for each channel.
+    sent: [AtomicU64; 2],
+    /// The lowest sequence number accepted next for each channel.
+    received: [AtomicU64; 2],
+}
+
+/// Can not be constructed without the `encryption` feature.
+#[cfg(not(feature = "encryption"))]
+pub(crate) enum Cipher {}
+
+#[cfg(feature = "encryption")]
+const SEQUENCE_SIZE: usize = 8;
+
+#[cfg(feature = "encryption")]
+impl Cipher {
+    /// Derives the keys of both directions from the shared secret of the key exchange.
+    fn derive(
+        shared: &[u8; 32],
+        client: &[u8; 32],
+        server: &[u8; 32],
+        pre_shared_key: Option<&[u8; 32]>,
+        is_server: bool,
+    ) -> Self {
+        use hkdf::Hkdf;
+        use sha2::Sha256;
+
+        let mut salt = [0; 96];
+        salt[..32].copy_from_slice(client);
+        salt[32..64].copy_from_slice(server);
+        if let Some(key) = pre_shared_key {
+            salt[64..].copy_from_slice(key);
+        }
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), shared);
+        let mut to_server = [0; 32];
+        let mut to_client = [0; 32];
+        hkdf.expand(b"let-engine client to server", &mut to_server)
+            .expect("32 bytes are a valid HKDF output length.");
+        hkdf.expand(b"let-engine server to client", &mut to_client)
+            .expect("32 bytes are a valid HKDF output length.");
+
+        let (send, receive) = if is_server {
+            (to_client, to_server)
+        } else {
+            (to_server, to_client)
+        };
+        Self {
+            send: ChaCha20Poly1305::new(Key::from_slice(&send)),
+            receive: ChaCha20Poly1305::new(Key::from_slice(&receive)),
+            sent: Default::default(),
+            received: Default::default(),
+        }
+    }
+
+    fn nonce(channel: Channel, sequence: u64) -> [u8; 12] {
+        let mut nonce = [0; 12];
+        nonce[0] = channel as u8;
+        nonce[4..].copy_from_slice(&sequence.to_le_bytes());
+        nonce
+    }
+}
+
+/// Encrypts the data in case there is a cipher.
+pub(crate) fn seal(
+    cipher: Option<&Cipher>,
+    channel: Channel,
+    data: Vec<u8>,
+) -> bincode::Result<Vec<u8>> {
+    let Some(cipher) = cipher else {
+        return Ok(data);
+    };
+    #[cfg(feature = "encryption")]
+    {
+        let sequence = cipher.sent[channel as usize].fetch_add(1, Ordering::AcqRel);
+        let header = sequence.to_le_bytes();
+        let encrypted = cipher
+            .send
+            .encrypt(
+                Nonce::from_slice(&Cipher::nonce(channel, sequence)),
+                Payload {
+                    msg: &data,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| bincode::ErrorKind::Custom("Failed to encrypt the message.".into()))?;
+        let mut sealed = Vec::with_capacity(SEQUENCE_SIZE + encrypted.len());
+        sealed.extend_from_slice(&header);
+        sealed.extend(encrypted);
+        Ok(sealed)
+    }
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = channel;
+        match *cipher {}
+    }
+}
+
+/// Decrypts and verifies the data in case there is a cipher, rejecting replayed messages.
+pub(crate) fn open(
+    cipher: Option<&Cipher>,
+    channel: Channel,
+    data: &[u8],
+) -> bincode::Result<Vec<u8>> {
+    let Some(cipher) = cipher else {
+        return Ok(data.to_vec());
+    };
+    #[cfg(feature = "encryption")]
+    {
+        if data.len() < SEQUENCE_SIZE {
+            return Err(bincode::ErrorKind::Custom("The message is not encrypted.".into()).into());
+        }
+        let (header, encrypted) = data.split_at(SEQUENCE_SIZE);
+        let sequence = u64::from_le_bytes(header.try_into().unwrap());
+        let decrypted = cipher
+            .receive
+            .decrypt(
+                Nonce::from_slice(&Cipher::nonce(channel, sequence)),
+                Payload {
+                    msg: encrypted,
+                    aad: header,
+                },
+            )
+            .map_err(|_| bincode::ErrorKind::Custom("The message failed to decrypt.".into()))?;
+        // Only authentic messages move the window, so forged sequence numbers can not block the channel.
+        cipher.received[channel as usize]
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |next| {
+                (sequence >= next).then(|| sequence + 1)
+            })
+            .map_err(|_| bincode::ErrorKind::Custom("The message got replayed.".into()))?;
+        Ok(decrypted)
+    }
+    #[cfg(not(feature = "encryption"))]
+    {
+        let _ = (channel, data);
+        match *cipher {}
+    }
+}
+
+/// Derives the session keys from the handshake.
+///
+/// The client sends its public key first and the server answers with its own.
+#[cfg(feature = "encryption")]
+pub(crate) async fn exchange_keys(
+    stream: &mut TcpStream,
+    is_server: bool,
+) -> std::io::Result<Option<Cipher>> {
+    use x25519_dalek::{EphemeralSecret, PublicKey};
+
+    let secret = EphemeralSecret::random_from_rng(rand::rngs::OsRng);
+    let public = PublicKey::from(&secret);
+    let mut remote = [0; 32];
+    if is_server {
+        stream.read_exact(&mut remote).await?;
+        stream.write_all(public.as_bytes()).await?;
+    } else {
+        stream.write_all(public.as_bytes()).await?;
+        stream.read_exact(&mut remote).await?;
+    }
+    let remote = PublicKey::from(remote);
+    let shared = secret.diffie_hellman(&remote);
+    // An all zero secret means the peer sent a low order point to force a known key.
+    if !shared.was_contributory() {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    let (client, server) = if is_server {
+        (remote, public)
+    } else {
+        (public, remote)
+    };
+    let pre_shared_key = crate::SETTINGS.networking.pre_shared_key();
+    Ok(Some(Cipher::derive(
+        shared.as_bytes(),
+        client.as_bytes(),
+        server.as_bytes(),
+        pre_shared_key.as_ref(),
+        is_server,
+    )))
+}
+
+/// Does not exchange anything without the `encryption` feature.
+#[cfg(not(feature = "encryption"))]
+pub(crate) async fn exchange_keys(
+    _stream: &mut TcpStream,
+    _is_server: bool,
+) -> std::io::Result<Option<Cipher>> {
+    Ok(None)
+}
+
+/// Sends the authentication token of the client and returns whether the server accepted it.
+pub(crate) async fn send_token(
+    stream: &mut TcpStream,
+    token: &[u8],
+    cipher: Option<&Cipher>,
+) -> std::io::Result<bool> {
+    let token = seal(cipher, Channel::Tcp, token.to_vec()).map_err(std::io::Error::other)?;
+    stream
+        .write_all(&(token.len() as u32).to_le_bytes())
+        .await?;
+    stream.write_all(&token).await?;
+    let mut verdict = [0];
+    stream.read_exact(&mut verdict).await?;
+    Ok(verdict[0] == 1)
+}
+
+/// Receives the authentication token of a client.
+///
+/// Fails in case the client used a different pre-shared key.
+pub(crate) async fn receive_token(
+    stream: &mut TcpStream,
+    cipher: Option<&Cipher>,
+) -> std::io::Result<Vec<u8>> {
+    let mut size = [0; 4];
+    stream.read_exact(&mut size).await?;
+    let size = u32::from_le_bytes(size) as usize;
+    if size > MAX_TOKEN_SIZE + 64 {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+    let mut token = vec![0; size];
+    stream.read_exact(&mut token).await?;
+    open(cipher, Channel::Tcp, &token).map_err(std::io::Error::other)
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+
+    fn pair(client_key: Option<&[u8; 32]>, server_key: Option<&[u8; 32]>) -> (Cipher, Cipher) {
+        let shared = [7; 32];
+        let (client, server) = ([1; 32], [2; 32]);
+        (
+            Cipher::derive(&shared, &client, &server, client_key, false),
+            Cipher::derive(&shared, &client, &server, server_key, true),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let (client, server) = pair(None, None);
+        for message in [b"hello".to_vec(), vec![], vec![42; 2000]] {
+            let sealed = seal(Some(&client), Channel::Tcp, message.clone()).unwrap();
+            assert_eq!(open(Some(&server), Channel::Tcp, &sealed).unwrap(), message);
+            let sealed = seal(Some(&server), Channel::Udp, message.clone()).unwrap();
+            assert_eq!(open(Some(&client), Channel::Udp, &sealed).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn tampered_messages_get_rejected() {
+        let (client, server) = pair(None, None);
+        let sealed = seal(Some(&client), Channel::Tcp, b"hello".to_vec()).unwrap();
+        for index in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[index] ^= 1;
+            assert!(open(Some(&server), Channel::Tcp, &tampered).is_err());
+        }
+        assert!(open(Some(&server), Channel::Tcp, &sealed[..4]).is_err());
+        // The tampered messages did not move the window.
+        assert!(open(Some(&server), Channel::Tcp, &sealed).is_ok());
+    }
+
+    #[test]
+    fn replays_and_reflections_get_rejected() {
+        let (client, server) = pair(None, None);
+        let first = seal(Some(&client), Channel::Udp, b"first".to_vec()).unwrap();
+        let second = seal(Some(&client), Channel::Udp, b"second".to_vec()).unwrap();
+
+        assert!(open(Some(&server), Channel::Udp, &second).is_ok());
+        assert!(open(Some(&server), Channel::Udp, &second).is_err());
+        assert!(open(Some(&server), Channel::Udp, &first).is_err());
+        // Messages only decrypt in the direction and channel they got sent in.
+        let third = seal(Some(&client), Channel::Udp, b"third".to_vec()).unwrap();
+        assert!(open(Some(&client), Channel::Udp, &third).is_err());
+        assert!(open(Some(&server), Channel::Tcp, &third).is_err());
+    }
+
+    #[test]
+    fn different_pre_shared_keys_do_not_match() {
+        let (client, server) = pair(Some(&[3; 32]), Some(&[4; 32]));
+        let sealed = seal(Some(&client), Channel::Tcp, b"token".to_vec()).unwrap();
+        assert!(open(Some(&server), Channel::Tcp, &sealed).is_err());
+
+        let (client, server) = pair(Some(&[3; 32]), Some(&[3; 32]));
+        let sealed = seal(Some(&client), Channel::Tcp, b"token".to_vec()).unwrap();
+        assert!(open(Some(&server), Channel::Tcp, &sealed).is_ok());
+    }
+}
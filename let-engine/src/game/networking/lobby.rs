@@ -0,0 +1,107 @@
+//! Server information for server browsers, LAN discovery and master server registration.
+//!
+//! Lobby packets are sent through the UDP port of the server and start with 4 zero bytes,
+//! the same as auth messages, followed by a 4 byte tag.
+//!
+//! - `info`: A query padded with zeros to [`QUERY_SIZE`] bytes. The server answers to the sender with the same tag
+//!   followed by its bincode serialized [`ServerInfo`], but only in case the answer is not larger than the query,
+//!   so spoofed queries can not make the server flood someone else with more data than the spoofer sent.
+//! - `regs`: The registration a server sends to its master server every [`MASTER_HEARTBEAT`],
+//!   with the bincode serialized [`ServerInfo`] following the tag.
+//!   A master server can use the sender address of this packet as the address of the game server.
+
+use std::{collections::BTreeMap, net::SocketAddr, time::Duration};
+
+use futures::future::Either;
+use serde::{Deserialize, Serialize};
+use smol::{net::UdpSocket, Timer};
+
+use crate::SETTINGS;
+
+/// The header of a server info query and its answer.
+pub(crate) const QUERY: [u8; 8] = *b"\0\0\0\0info";
+/// The size of an info query, which is the largest answer a server sends.
+pub const QUERY_SIZE: usize = 1024;
+/// The header of a master server registration.
+pub(crate) const REGISTER: [u8; 8] = *b"\0\0\0\0regs";
+
+/// The time between registrations sent to the master server.
+pub const MASTER_HEARTBEAT: Duration = Duration::from_secs(30);
+
+/// Public information about a server for listing it in a server browser.
+///
+/// Servers only answer queries in case the serialized info fits in [`QUERY_SIZE`] bytes including the 8 byte header,
+/// so the name and metadata should stay short.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    /// The name of the server.
+    pub name: String,
+    /// The number of currently connected clients.
+    pub players: usize,
+    /// The maximum number of clients as set in the networking settings.
+    pub max_players: usize,
+    /// Custom information of the game, like the map or game mode.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Prefixes the serialized server info with the given header.
+pub(crate) fn packet(header: [u8; 8], info: &ServerInfo) -> bincode::Result<Vec<u8>> {
+    let mut data = header.to_vec();
+    data.extend(bincode::serialize(info)?);
+    Ok(data)
+}
+
+/// Sends an info query to the address and collects the answers until the wait time is over.
+///
+/// Direct queries return with the first answer and get retried the number of auth retries set in the networking settings.
+pub(crate) async fn query(
+    addr: SocketAddr,
+    broadcast: bool,
+    wait: Duration,
+) -> smol::io::Result<Vec<(SocketAddr, ServerInfo)>> {
+    let socket = UdpSocket::bind(if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    })
+    .await?;
+    socket.set_broadcast(broadcast)?;
+
+    let mut servers: Vec<(SocketAddr, ServerInfo)> = vec![];
+    let mut buf = vec![0; u16::MAX as usize];
+
+    let retries = if broadcast {
+        1
+    } else {
+        SETTINGS.networking.auth_retries().max(1)
+    };
+    let mut query = QUERY.to_vec();
+    query.resize(QUERY_SIZE, 0);
+    for _ in 0..retries {
+        socket.send_to(&query, addr).await?;
+
+        let mut timer = Timer::after(wait);
+        loop {
+            let recv = Box::pin(socket.recv_from(&mut buf));
+            let (size, from) = match futures::future::select(recv, &mut timer).await {
+                Either::Left((result, _)) => result?,
+                Either::Right(_) => break,
+            };
+            if size < QUERY.len() || buf[..QUERY.len()] != QUERY {
+                continue;
+            }
+            let Ok(info) = bincode::deserialize::<ServerInfo>(&buf[QUERY.len()..size]) else {
+                continue;
+            };
+            match servers.iter_mut().find(|(addr, _)| *addr == from) {
+                Some(server) => server.1 = info,
+                None => servers.push((from, info)),
+            }
+            if !broadcast {
+                return Ok(servers);
+            }
+        }
+    }
+
+    Ok(servers)
+}
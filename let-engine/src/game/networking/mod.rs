@@ -22,6 +22,8 @@
 //
 // Auth messages start with 4 bytes made only out of zeros, because zeroes are not valid order numbers
 //
+// Lobby messages for server browsers also start with 4 zero bytes, but are followed by a 4 byte tag instead of the random bytes.
+//
 // The rest of the messages have a 8 byte header with the first 4 bytes as the order number and the rest as lenght prefix.
 //
 // A Ping packet also works as the ack auth back message signalling to stop sending the auth message.
@@ -37,6 +39,8 @@
 // There is a lot of discarting here. Users have to expect that UDP is not perfect and reliable.
 
 mod client;
+mod crypto;
+mod lobby;
 mod server;
 
 use std::{
@@ -48,6 +52,9 @@ use std::{
 
 pub use client::*;
 use crossbeam::atomic::AtomicCell;
+pub use crypto::MAX_TOKEN_SIZE;
+use crypto::{Channel, Cipher};
+pub use lobby::{ServerInfo, MASTER_HEARTBEAT, QUERY_SIZE};
 use serde::Serialize;
 pub use server::*;
 use smol::channel::{Receiver, Sender};
@@ -73,6 +80,12 @@ pub struct Networking {
     ///
     /// 5 seconds
     ping_wait: AtomicCell<Duration>,
+    /// The time without any packet from the peer before the connection times out.
+    ///
+    /// ## Default configuration
+    ///
+    /// 15 seconds
+    timeout: AtomicCell<Duration>,
     /// The maximum allowed ping before sending warnings.
     ///
     /// ## Default configuration
@@ -103,6 +116,13 @@ pub struct Networking {
     ///
     /// u16::MAX bytes
     udp_size_limit: AtomicUsize,
+    /// The key mixed into the session keys of encrypted connections.
+    ///
+    /// ## Default configuration
+    ///
+    /// None
+    #[cfg(feature = "encryption")]
+    pre_shared_key: AtomicCell<Option<[u8; 32]>>,
 }
 
 impl Networking {
@@ -111,11 +131,14 @@ impl Networking {
             auth_retries: 10.into(),
             auth_retry_wait: AtomicCell::new(Duration::from_secs(2)),
             ping_wait: AtomicCell::new(Duration::from_secs(5)),
+            timeout: AtomicCell::new(Duration::from_secs(15)),
             max_ping: AtomicCell::new(Duration::from_secs(10)),
             rate_limit: AtomicCell::new(Duration::default()),
             max_connections: 20.into(),
             tcp_size_limit: 100_000_000.into(),
             udp_size_limit: (u16::MAX as usize).into(),
+            #[cfg(feature = "encryption")]
+            pre_shared_key: AtomicCell::new(None),
         }
     }
 
@@ -149,6 +172,8 @@ impl Networking {
 
     /// The time between ping requests.
     ///
+    /// The pings double as heartbeat keeping idle connections alive, so this should be well below the timeout.
+    ///
     /// ## Default configuration
     ///
     /// 5 seconds
@@ -160,6 +185,19 @@ impl Networking {
         self.ping_wait.store(duration)
     }
 
+    /// The time without any packet from the peer before the connection times out.
+    ///
+    /// ## Default configuration
+    ///
+    /// 15 seconds
+    pub fn timeout(&self) -> Duration {
+        self.timeout.load()
+    }
+
+    pub fn set_timeout(&self, duration: Duration) {
+        self.timeout.store(duration)
+    }
+
     /// The maximum allowed ping before sending warnings.
     ///
     /// ## Default configuration
@@ -230,6 +268,25 @@ impl Networking {
         self.udp_size_limit
             .store(limit, std::sync::atomic::Ordering::Release)
     }
+
+    /// The key mixed into the session keys of encrypted connections.
+    ///
+    /// Without it the key exchange is anonymous, so a man in the middle could read and change the messages.
+    /// With it only peers knowing the same key can connect, as the messages of others fail to decrypt.
+    /// Gets read while connecting, so it has to be set before starting the server or connecting the client.
+    ///
+    /// ## Default configuration
+    ///
+    /// None
+    #[cfg(feature = "encryption")]
+    pub fn pre_shared_key(&self) -> Option<[u8; 32]> {
+        self.pre_shared_key.load()
+    }
+
+    #[cfg(feature = "encryption")]
+    pub fn set_pre_shared_key(&self, key: Option<[u8; 32]>) {
+        self.pre_shared_key.store(key)
+    }
 }
 
 impl Default for Networking {
@@ -317,6 +374,8 @@ pub enum Disconnected {
     /// The peer has been disconnected for misbehaving and sending packets
     /// not according to the system.
     MisbehavingPeer,
+    /// Nothing has been received from the peer for longer than the timeout set in the networking settings.
+    TimedOut,
     /// An unexplainable error has occured.
     Other(io::Error),
 }
@@ -328,6 +387,7 @@ impl std::fmt::Display for Disconnected {
             Disconnected::ConnectionAborted => "Connection aborted",
             Disconnected::ConnectionReset => "Connection reset",
             Disconnected::MisbehavingPeer => "Peer misbehaving",
+            Disconnected::TimedOut => "Timed out",
             Disconnected::Other(e) => &format!("{e}"),
         };
 
@@ -335,6 +395,16 @@ impl std::fmt::Display for Disconnected {
     }
 }
 
+/// The state of a connection to a client of the server.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerInfo {
+    pub connection: Connection,
+    /// The last measured round trip time.
+    pub ping: Duration,
+    /// The time since the last packet received from this peer.
+    pub idle: Duration,
+}
+
 impl From<io::Error> for Disconnected {
     fn from(value: io::Error) -> Self {
         match value.kind() {
@@ -353,8 +423,8 @@ impl From<io::Error> for Disconnected {
 /// - Length prefixed with a u32
 ///
 /// \[u32data_len\](u8data)
-fn serialize_tcp(message: &impl Serialize) -> bincode::Result<Vec<u8>> {
-    let serialized_data = bincode::serialize(message)?;
+fn serialize_tcp(message: &impl Serialize, cipher: Option<&Cipher>) -> bincode::Result<Vec<u8>> {
+    let serialized_data = crypto::seal(cipher, Channel::Tcp, bincode::serialize(message)?)?;
 
     let data_len = serialized_data.len();
 
@@ -374,8 +444,12 @@ fn serialize_tcp(message: &impl Serialize) -> bincode::Result<Vec<u8>> {
 /// - Indexed and data length prefixed
 ///
 /// \[u32order_number\]\[u32data_len\])(u8data)
-fn serialize_udp(order_number: u32, message: &impl Serialize) -> bincode::Result<Vec<u8>> {
-    let serialized_data = bincode::serialize(message)?;
+fn serialize_udp(
+    order_number: u32,
+    message: &impl Serialize,
+    cipher: Option<&Cipher>,
+) -> bincode::Result<Vec<u8>> {
+    let serialized_data = crypto::seal(cipher, Channel::Udp, bincode::serialize(message)?)?;
 
     let data_len = serialized_data.len();
     let mut data: Vec<u8> = Vec::with_capacity(data_len + 8);
@@ -389,6 +463,15 @@ fn serialize_udp(order_number: u32, message: &impl Serialize) -> bincode::Result
     Ok(data)
 }
 
+/// Decrypts and deserializes a message received through the given channel.
+fn deserialize<Msg: serde::de::DeserializeOwned>(
+    data: &[u8],
+    channel: Channel,
+    cipher: Option<&Cipher>,
+) -> bincode::Result<Msg> {
+    bincode::deserialize(&crypto::open(cipher, channel, data)?)
+}
+
 struct BufferingMessage {
     bytes_left: usize,
     buf: Vec<u8>,
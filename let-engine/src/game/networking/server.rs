@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{BTreeMap, VecDeque},
     sync::{atomic::AtomicBool, Arc, LazyLock},
     time::{Duration, SystemTime},
 };
@@ -17,13 +17,18 @@ use thiserror::Error;
 
 use crate::SETTINGS;
 
-use super::{serialize_tcp, Connection, Disconnected, Messages, RemoteMessage};
+use super::{
+    crypto, lobby, serialize_tcp, Channel, Cipher, Connection, Disconnected, Messages,
+    RemoteMessage, ServerInfo,
+};
 
-type Pending = Mutex<HashMap<[u8; 128], (TcpStream, SocketAddr)>>;
+type Pending = Mutex<HashMap<[u8; 128], (TcpStream, SocketAddr, Option<Arc<Cipher>>)>>;
+type Authenticator = Arc<dyn Fn(SocketAddr, &[u8]) -> bool + Send + Sync>;
 
 #[derive(Clone)]
 struct Peer {
     tcp_stream: TcpStream,
+    cipher: Option<Arc<Cipher>>,
     order_number: u32,
     ping_timestamp: Option<SystemTime>,
     ping: Duration,
@@ -31,14 +36,16 @@ struct Peer {
     last_package: SystemTime,
     last_package_durations: VecDeque<Duration>,
     rate_average: Duration,
+    last_seen: SystemTime,
 }
 
 impl Peer {
-    pub fn new(tcp_stream: TcpStream) -> Self {
+    pub fn new(tcp_stream: TcpStream, cipher: Option<Arc<Cipher>>) -> Self {
         let mut last_package_durations = VecDeque::with_capacity(10);
         last_package_durations.extend([Duration::from_secs(600); 10]);
         Self {
             tcp_stream,
+            cipher,
             order_number: 1,
             ping_timestamp: None,
             ping: Duration::default(),
@@ -46,6 +53,7 @@ impl Peer {
             last_package: SystemTime::now(),
             last_package_durations,
             rate_average: Duration::MAX,
+            last_seen: SystemTime::now(),
         }
     }
 
@@ -55,6 +63,7 @@ impl Peer {
     }
 
     pub fn record_rate(&mut self) {
+        self.last_seen = SystemTime::now();
         self.last_package_durations
             .push_back(self.last_package.elapsed().unwrap());
         self.last_package_durations.pop_front();
@@ -83,9 +92,25 @@ struct Socket {
     connections: Mutex<HashMap<SocketAddr, Connection>>,
     connecting: Pending,
     running: AtomicBool,
+    /// The tasks of the running server, cancelled once it stops or starts again.
+    tasks: parking_lot::Mutex<Vec<smol::Task<()>>>,
+    authenticator: parking_lot::Mutex<Option<Authenticator>>,
+
+    name: parking_lot::Mutex<String>,
+    metadata: parking_lot::Mutex<BTreeMap<String, String>>,
+    master_server: parking_lot::Mutex<Option<String>>,
 }
 
 impl Socket {
+    async fn info(&self) -> ServerInfo {
+        ServerInfo {
+            name: self.name.lock().clone(),
+            players: self.connections_map.lock().await.len(),
+            max_players: SETTINGS.networking.max_connections(),
+            metadata: self.metadata.lock().clone(),
+        }
+    }
+
     /// Records the time and stops the echoing.
     ///
     /// Returns true if ping is over ping limit
@@ -95,6 +120,7 @@ impl Socket {
             return false;
         };
 
+        peer.last_seen = SystemTime::now();
         let time = std::mem::take(&mut peer.ping_timestamp);
 
         if let Some(time) = time {
@@ -137,6 +163,11 @@ where
                     connections: Mutex::new(HashMap::default()),
                     connecting: Mutex::new(HashMap::default()),
                     running: false.into(),
+                    tasks: parking_lot::Mutex::new(vec![]),
+                    authenticator: parking_lot::Mutex::new(None),
+                    name: parking_lot::Mutex::new(String::new()),
+                    metadata: parking_lot::Mutex::new(BTreeMap::new()),
+                    master_server: parking_lot::Mutex::new(None),
                 }),
                 messages: unbounded(),
             };
@@ -152,46 +183,66 @@ where
         smol::spawn(async {
             let socket = socket;
             let listener = listener;
-            while let Ok((mut stream, addr)) = listener.accept().await {
+            while let Ok((stream, addr)) = listener.accept().await {
                 if SETTINGS.networking.max_connections()
                     <= socket.connections_map.lock().await.len()
                 {
                     let _ = stream.shutdown(std::net::Shutdown::Both);
                     continue;
                 }
-                let mut buf = [0; 128];
-
-                let op = stream.read_exact(&mut buf);
-
-                use futures::future::Either;
-
-                // 3 seconds or max ping limit
-                match futures::future::select(
-                    op,
-                    smol::Timer::after(std::time::Duration::from_secs(3)),
-                )
-                .await
-                {
-                    Either::Left(result) => {
-                        if result.0.is_err() {
-                            return;
-                        }
-                    }
-                    Either::Right(_) => return,
-                };
-
-                socket.connecting.lock().await.insert(buf, (stream, addr));
+                // Slow clients do not hold up others connecting at the same time.
+                smol::spawn(Self::handshake(socket.clone(), stream, addr)).detach();
             }
         })
         .detach();
     }
 
+    /// Receives the session ID, exchanges keys and checks the authentication token of a new connection,
+    /// adding it to the connecting streams in case it got accepted.
+    async fn handshake(socket: Arc<Socket>, mut stream: TcpStream, addr: SocketAddr) {
+        let mut buf = [0; 128];
+
+        let handshake = Box::pin(async {
+            stream.read_exact(&mut buf).await?;
+            let cipher = crypto::exchange_keys(&mut stream, true).await?;
+            let token = crypto::receive_token(&mut stream, cipher.as_ref()).await?;
+            let authenticator = socket.authenticator.lock().clone();
+            let accepted = authenticator.map_or(true, |authenticate| authenticate(addr, &token));
+            stream.write_all(&[accepted as u8]).await?;
+            smol::io::Result::Ok(accepted.then_some(cipher))
+        });
+
+        use futures::future::Either;
+
+        // 3 seconds or max ping limit
+        let accepted = match futures::future::select(
+            handshake,
+            smol::Timer::after(std::time::Duration::from_secs(3)),
+        )
+        .await
+        {
+            Either::Left((result, _)) => result.ok().flatten(),
+            Either::Right(_) => None,
+        };
+        let Some(cipher) = accepted else {
+            let _ = stream.shutdown(std::net::Shutdown::Both);
+            return;
+        };
+
+        socket
+            .connecting
+            .lock()
+            .await
+            .insert(buf, (stream, addr, cipher.map(Arc::new)));
+    }
+
     async fn connect_client(
         messages: Sender<(Connection, RemoteMessage<Msg>)>,
         socket: Arc<Socket>,
         stream: TcpStream,
         tcp_addr: SocketAddr,
         udp_addr: SocketAddr,
+        cipher: Option<Arc<Cipher>>,
     ) {
         let connection = Connection::new(tcp_addr, udp_addr.port());
 
@@ -206,7 +257,7 @@ where
                 .connections_map
                 .lock()
                 .await
-                .insert(connection, Peer::new(stream.clone()));
+                .insert(connection, Peer::new(stream.clone(), cipher));
 
             {
                 let mut connections_lock = socket.connections.lock().await;
@@ -226,7 +277,7 @@ where
         }
     }
 
-    fn recv_udp_messages(&self) {
+    fn recv_udp_messages(&self) -> smol::Task<()> {
         let server = self.clone();
         smol::spawn(async {
             let server = server;
@@ -254,9 +305,17 @@ where
                         else {
                             continue;
                         };
+                        let cipher = socket
+                            .connections_map
+                            .lock()
+                            .await
+                            .get(&connection)
+                            .and_then(|peer| peer.cipher.clone());
 
                         // Send completed message
-                        if let Ok(message) = bincode::deserialize::<Msg>(message) {
+                        if let Ok(message) =
+                            super::deserialize::<Msg>(message, Channel::Udp, cipher.as_deref())
+                        {
                             if server
                                 .messages
                                 .0
@@ -272,6 +331,20 @@ where
                     }
 
                     match size {
+                        // Server browser query, which the answer can not be larger than.
+                        lobby::QUERY_SIZE if buf[..8] == lobby::QUERY => {
+                            match lobby::packet(lobby::QUERY, &socket.info().await) {
+                                Ok(data) if data.len() <= size => {
+                                    let _ = socket.udp_socket.send_to(&data, addr).await;
+                                }
+                                Ok(data) => log::warn!(
+                                    "The server info of {} bytes does not fit in an answer of {size} bytes.",
+                                    data.len()
+                                ),
+                                Err(_) => (),
+                            }
+                            continue;
+                        }
                         // 8 bytes = ping
                         8 => {
                             let Some(connection) =
@@ -337,6 +410,7 @@ where
                                 connecting.0.clone(),
                                 connecting.1,
                                 addr,
+                                connecting.2,
                             )
                             .await;
                         }
@@ -368,8 +442,10 @@ where
                         continue;
                     };
 
+                    let mut cipher = None;
                     if let Some(peer) = socket.connections_map.lock().await.get_mut(&connection) {
                         peer.record_rate();
+                        cipher = peer.cipher.clone();
                         if peer.over_rate_limit()
                             && server
                                 .messages
@@ -392,7 +468,9 @@ where
 
                     // If the packet holds the whole message don't bother buffering it.
                     if let Some(data) = buffering_message.completed(&buf[8..]) {
-                        if let Ok(message) = bincode::deserialize::<Msg>(data) {
+                        if let Ok(message) =
+                            super::deserialize::<Msg>(data, Channel::Udp, cipher.as_deref())
+                        {
                             if server
                                 .messages
                                 .0
@@ -409,7 +487,6 @@ where
                 }
             }
         })
-        .detach();
     }
 
     /// Receives messages from each TCP connection.
@@ -421,6 +498,12 @@ where
     ) {
         let disconnect_reason;
         let mut size_buf = [0u8; 4];
+        let cipher = socket
+            .connections_map
+            .lock()
+            .await
+            .get(&connection)
+            .and_then(|peer| peer.cipher.clone());
 
         let mut buf = Vec::with_capacity(1032);
         loop {
@@ -470,7 +553,7 @@ where
             };
 
             // Send the message if it's correctly deserialized.
-            let _ = match bincode::deserialize::<Msg>(&buf) {
+            let _ = match super::deserialize::<Msg>(&buf, Channel::Tcp, cipher.as_deref()) {
                 Ok(message) => {
                     messages
                         .send((connection, RemoteMessage::Tcp(message)))
@@ -523,6 +606,7 @@ where
             connection.tcp_stream.shutdown(std::net::Shutdown::Both)?;
         }
         *self.socket.connections.lock().await = HashMap::default();
+        self.socket.tasks.lock().clear();
 
         Ok(())
     }
@@ -532,7 +616,63 @@ where
         self.socket
             .running
             .store(true, std::sync::atomic::Ordering::Release);
-        self.recv_udp_messages();
+        // Replacing the tasks of a previous start cancels them, so restarting does not run them twice.
+        *self.socket.tasks.lock() = vec![
+            self.recv_udp_messages(),
+            self.watch_timeouts(),
+            self.register_master_server(),
+        ];
+    }
+
+    /// Sends the server info to the master server in a regular interval.
+    fn register_master_server(&self) -> smol::Task<()> {
+        let socket = self.socket.clone();
+        smol::spawn(async move {
+            while socket.running.load(std::sync::atomic::Ordering::Acquire) {
+                let master_server = socket.master_server.lock().clone();
+                if let Some(master_server) = master_server {
+                    let info = socket.info().await;
+                    if let (Ok(addrs), Ok(data)) = (
+                        smol::net::resolve(master_server).await,
+                        lobby::packet(lobby::REGISTER, &info),
+                    ) {
+                        if let Some(addr) = addrs.first() {
+                            let _ = socket.udp_socket.send_to(&data, addr).await;
+                        }
+                    }
+                }
+                smol::Timer::after(lobby::MASTER_HEARTBEAT).await;
+            }
+        })
+    }
+
+    /// Disconnects peers that have not sent anything for longer than the configured timeout.
+    fn watch_timeouts(&self) -> smol::Task<()> {
+        let server = self.clone();
+        smol::spawn(async move {
+            while server
+                .socket
+                .running
+                .load(std::sync::atomic::Ordering::Acquire)
+            {
+                smol::Timer::after(SETTINGS.networking.ping_wait()).await;
+                let timeout = SETTINGS.networking.timeout();
+                let timed_out: Vec<Connection> = server
+                    .socket
+                    .connections_map
+                    .lock()
+                    .await
+                    .iter()
+                    .filter(|(_, peer)| peer.last_seen.elapsed().unwrap_or_default() > timeout)
+                    .map(|(connection, _)| *connection)
+                    .collect();
+                for connection in timed_out {
+                    let _ = server
+                        .disconnect_user(connection, Disconnected::TimedOut)
+                        .await;
+                }
+            }
+        })
     }
 
     /// Broadcasts a message to every client through TCP.
@@ -543,7 +683,10 @@ where
         for (user, connection) in stream_map.clone().iter_mut() {
             let result = connection
                 .tcp_stream
-                .write_all(&serialize_tcp(&message).map_err(ServerError::SerialisationError)?)
+                .write_all(
+                    &serialize_tcp(&message, connection.cipher.as_deref())
+                        .map_err(ServerError::SerialisationError)?,
+                )
                 .await;
             if let Err(e) = result {
                 Self::disconnect_user_with(
@@ -563,16 +706,13 @@ where
     ///
     /// This function should be used to send important messages.
     pub async fn send(&self, receiver: Connection, message: &Msg) -> Result<(), ServerError> {
-        let result = self
-            .socket
-            .connections_map
-            .lock()
-            .await
-            .get_mut(&receiver)
-            .ok_or(ServerError::UserNotFound)?
-            .tcp_stream
-            .write_all(&super::serialize_tcp(message).map_err(ServerError::SerialisationError)?)
-            .await;
+        let result = {
+            let mut peers = self.socket.connections_map.lock().await;
+            let peer = peers.get_mut(&receiver).ok_or(ServerError::UserNotFound)?;
+            let data = serialize_tcp(message, peer.cipher.as_deref())
+                .map_err(ServerError::SerialisationError)?;
+            peer.tcp_stream.write_all(&data).await
+        };
         if let Err(e) = result {
             self.disconnect_user(receiver, e.into()).await?;
         }
@@ -588,7 +728,7 @@ where
         for (connection, peer) in peers.iter_mut() {
             // TODO: Optimize by not serializing for each client but only serialize once and
             //       only update the order number for each.
-            let data = super::serialize_udp(peer.order_number(), message)
+            let data = super::serialize_udp(peer.order_number(), message, peer.cipher.as_deref())
                 .map_err(ServerError::SerialisationError)?;
             let chunks = data.chunks(1024);
 
@@ -625,7 +765,7 @@ where
         let mut peers = self.socket.connections_map.lock().await;
         let peer = peers.get_mut(&receiver).ok_or(ServerError::UserNotFound)?;
 
-        let data = super::serialize_udp(peer.order_number(), message)
+        let data = super::serialize_udp(peer.order_number(), message, peer.cipher.as_deref())
             .map_err(ServerError::SerialisationError)?;
         let chunks = data.chunks(1024);
 
@@ -692,6 +832,48 @@ where
         Ok(())
     }
 
+    /// Sets the function deciding whether a connecting client gets accepted based on its address
+    /// and the authentication token it sent with [`GameClient::set_auth_token`](super::GameClient::set_auth_token).
+    ///
+    /// Rejected clients get disconnected during the handshake and never show up as connected.
+    /// All clients get accepted in case there is no authenticator.
+    pub fn set_authenticator(
+        &self,
+        authenticator: Option<impl Fn(SocketAddr, &[u8]) -> bool + Send + Sync + 'static>,
+    ) {
+        *self.socket.authenticator.lock() =
+            authenticator.map(|authenticator| Arc::new(authenticator) as Authenticator);
+    }
+
+    /// Sets the name of the server shown in server browsers.
+    pub fn set_name(&self, name: impl Into<String>) {
+        *self.socket.name.lock() = name.into();
+    }
+
+    /// Sets a custom entry of the server info, like the current map or game mode.
+    pub fn set_metadata(&self, key: impl Into<String>, value: impl Into<String>) {
+        self.socket.metadata.lock().insert(key.into(), value.into());
+    }
+
+    /// Removes a custom entry of the server info.
+    pub fn remove_metadata(&self, key: &str) -> Option<String> {
+        self.socket.metadata.lock().remove(key)
+    }
+
+    /// Returns the info clients receive when querying this server.
+    pub async fn info(&self) -> ServerInfo {
+        self.socket.info().await
+    }
+
+    /// Registers this server with the master server at the given `host:port` address, or stops registering with `None`.
+    ///
+    /// While running, the server sends its info through UDP to the master server every [`MASTER_HEARTBEAT`](super::MASTER_HEARTBEAT).
+    /// The packet starts with 4 zero bytes followed by `regs` and the bincode serialized [`ServerInfo`].
+    /// The sender address of the packet is the address clients connect to.
+    pub fn set_master_server(&self, address: Option<impl Into<String>>) {
+        *self.socket.master_server.lock() = address.map(Into::into);
+    }
+
     /// Returns a list of all connections currently initiated with the server.
     pub async fn connections(&self) -> Vec<Connection> {
         self.socket
@@ -703,6 +885,21 @@ where
             .collect()
     }
 
+    /// Returns the connections currently initiated with the server along with their ping.
+    pub async fn peers(&self) -> Vec<super::PeerInfo> {
+        self.socket
+            .connections_map
+            .lock()
+            .await
+            .iter()
+            .map(|(connection, peer)| super::PeerInfo {
+                connection: *connection,
+                ping: peer.ping,
+                idle: peer.last_seen.elapsed().unwrap_or_default(),
+            })
+            .collect()
+    }
+
     /// Requests a ping to the client to update the ping value.
     pub async fn request_repinging(&self, connection: &Connection) {
         self.socket.ping(connection).await;
@@ -0,0 +1,492 @@
+//! Seeded coherent noise for procedural generation, giving the same values on every platform for the same seed.
+//!
+//! # Usage
+//! ```ignore
+//! let height = Fbm::new(Simplex::new(seed)).octaves(5);
+//! let caves = Worley::new(seed ^ 1);
+//!
+//! for x in 0..width {
+//!     let ground = height.get1(x as f32 * 0.05) * 10.0;
+//!     for y in 0..depth {
+//!         let cave = caves.get2(vec2(x as f32, y as f32) * 0.1) < 0.3;
+//!         // place the tiles
+//!     }
+//! }
+//! ```
+
+use glam::{Vec2, Vec3};
+use rand::RngCore;
+
+use super::DeterministicRng;
+
+/// Noise sampled in 1, 2 or 3 dimensions.
+pub trait Noise {
+    fn get1(&self, x: f32) -> f32;
+    fn get2(&self, point: Vec2) -> f32;
+    fn get3(&self, point: Vec3) -> f32;
+}
+
+/// A shuffled table of the numbers from 0 to 255, repeated once to skip wrapping the indices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Permutation(Box<[u8; 512]>);
+
+impl Permutation {
+    fn new(seed: u64) -> Self {
+        let mut rng = DeterministicRng::new(seed);
+        let mut table = [0u8; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+        // Fisher-Yates by hand, as the shuffle of `rand` may change between versions.
+        for i in (1..256).rev() {
+            let j = (rng.next_u32() % (i as u32 + 1)) as usize;
+            table.swap(i, j);
+        }
+        let mut permutation = Box::new([0u8; 512]);
+        permutation[..256].copy_from_slice(&table);
+        permutation[256..].copy_from_slice(&table);
+        Self(permutation)
+    }
+
+    #[inline]
+    fn hash1(&self, x: i32) -> u8 {
+        self.0[(x & 255) as usize]
+    }
+
+    #[inline]
+    fn hash2(&self, x: i32, y: i32) -> u8 {
+        self.0[self.hash1(x) as usize + (y & 255) as usize]
+    }
+
+    #[inline]
+    fn hash3(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.0[self.hash2(x, y) as usize + (z & 255) as usize]
+    }
+}
+
+/// The smooth step of Perlin noise, having no change in slope and curvature at 0 and 1.
+#[inline]
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// A gradient from -8 to 8 of the hash, multiplied with the distance.
+#[inline]
+fn grad1(hash: u8, x: f32) -> f32 {
+    let gradient = 1.0 + (hash & 7) as f32;
+    if hash & 8 == 0 {
+        gradient * x
+    } else {
+        -gradient * x
+    }
+}
+
+/// One of the 8 directions of the hash in the dot product with the offset.
+#[inline]
+fn grad2(hash: u8, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+/// One of the 12 edge directions of a cube of the hash in the dot product with the offset.
+#[inline]
+fn grad3(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let hash = hash & 15;
+    let u = if hash < 8 { x } else { y };
+    let v = if hash < 4 {
+        y
+    } else if hash == 12 || hash == 14 {
+        x
+    } else {
+        z
+    };
+    (if hash & 1 == 0 { u } else { -u }) + (if hash & 2 == 0 { v } else { -v })
+}
+
+/// Improved Perlin noise, smooth gradient noise on a square grid ranging roughly from -1 to 1.
+///
+/// The noise is 0 at every whole number coordinate, so sample it between them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Perlin {
+    permutation: Permutation,
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn get1(&self, x: f32) -> f32 {
+        let cell = x.floor();
+        let i = cell as i32;
+        let x = x - cell;
+        let p = &self.permutation;
+        let a = grad1(p.hash1(i), x);
+        let b = grad1(p.hash1(i + 1), x - 1.0);
+        // Opposite gradients of 8 reach 4 halfway between the grid points.
+        lerp(a, b, fade(x)) * 0.25
+    }
+
+    fn get2(&self, point: Vec2) -> f32 {
+        let cell = point.floor();
+        let (i, j) = (cell.x as i32, cell.y as i32);
+        let Vec2 { x, y } = point - cell;
+        let p = &self.permutation;
+        let (u, v) = (fade(x), fade(y));
+        let bottom = lerp(
+            grad2(p.hash2(i, j), x, y),
+            grad2(p.hash2(i + 1, j), x - 1.0, y),
+            u,
+        );
+        let top = lerp(
+            grad2(p.hash2(i, j + 1), x, y - 1.0),
+            grad2(p.hash2(i + 1, j + 1), x - 1.0, y - 1.0),
+            u,
+        );
+        lerp(bottom, top, v)
+    }
+
+    fn get3(&self, point: Vec3) -> f32 {
+        let cell = point.floor();
+        let (i, j, k) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let Vec3 { x, y, z } = point - cell;
+        let p = &self.permutation;
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+        let corner = |di: i32, dj: i32, dk: i32| {
+            grad3(
+                p.hash3(i + di, j + dj, k + dk),
+                x - di as f32,
+                y - dj as f32,
+                z - dk as f32,
+            )
+        };
+        let front = lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        );
+        let back = lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        );
+        lerp(front, back, w)
+    }
+}
+
+/// Simplex noise, gradient noise on a grid of triangles ranging roughly from -1 to 1.
+///
+/// Cheaper than [`Perlin`] noise in higher dimensions and without its visible square grid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Simplex {
+    permutation: Permutation,
+}
+
+impl Simplex {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            permutation: Permutation::new(seed),
+        }
+    }
+}
+
+/// The contribution of a corner of a simplex at the given squared distance with the given gradient product.
+#[inline]
+fn falloff(radius: f32, distance: f32, gradient: f32) -> f32 {
+    let t = radius - distance;
+    if t < 0.0 {
+        0.0
+    } else {
+        let t = t * t;
+        t * t * gradient
+    }
+}
+
+impl Noise for Simplex {
+    fn get1(&self, x: f32) -> f32 {
+        let i = x.floor() as i32;
+        let x0 = x - i as f32;
+        let x1 = x0 - 1.0;
+        let p = &self.permutation;
+        let n0 = falloff(1.0, x0 * x0, grad1(p.hash1(i), x0));
+        let n1 = falloff(1.0, x1 * x1, grad1(p.hash1(i + 1), x1));
+        (n0 + n1) * 0.395
+    }
+
+    fn get2(&self, point: Vec2) -> f32 {
+        // Skews the plane so the triangles become squares with a diagonal.
+        const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+
+        let s = (point.x + point.y) * F2;
+        let i = (point.x + s).floor() as i32;
+        let j = (point.y + s).floor() as i32;
+        let t = (i + j) as f32 * G2;
+        let x0 = point.x - (i as f32 - t);
+        let y0 = point.y - (j as f32 - t);
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+        let x1 = x0 - i1 as f32 + G2;
+        let y1 = y0 - j1 as f32 + G2;
+        let x2 = x0 - 1.0 + 2.0 * G2;
+        let y2 = y0 - 1.0 + 2.0 * G2;
+
+        let p = &self.permutation;
+        let n0 = falloff(0.5, x0 * x0 + y0 * y0, grad2(p.hash2(i, j), x0, y0));
+        let n1 = falloff(
+            0.5,
+            x1 * x1 + y1 * y1,
+            grad2(p.hash2(i + i1, j + j1), x1, y1),
+        );
+        let n2 = falloff(0.5, x2 * x2 + y2 * y2, grad2(p.hash2(i + 1, j + 1), x2, y2));
+        (n0 + n1 + n2) * 70.0
+    }
+
+    fn get3(&self, point: Vec3) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (point.x + point.y + point.z) * F3;
+        let cell = (point + s).floor();
+        let (i, j, k) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let t = (i + j + k) as f32 * G3;
+        let d0 = point - (cell - t);
+
+        // The order of the axes decides which of the 6 tetrahedra of the cube the point is in.
+        let (o1, o2) = if d0.x >= d0.y {
+            if d0.y >= d0.z {
+                ((1, 0, 0), (1, 1, 0))
+            } else if d0.x >= d0.z {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if d0.y < d0.z {
+            ((0, 0, 1), (0, 1, 1))
+        } else if d0.x < d0.z {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let p = &self.permutation;
+        let corner = |(di, dj, dk): (i32, i32, i32), offset: f32| {
+            let d = d0 - Vec3::new(di as f32, dj as f32, dk as f32) + offset;
+            falloff(
+                0.6,
+                d.length_squared(),
+                grad3(p.hash3(i + di, j + dj, k + dk), d.x, d.y, d.z),
+            )
+        };
+        let sum = corner((0, 0, 0), 0.0)
+            + corner(o1, G3)
+            + corner(o2, 2.0 * G3)
+            + corner((1, 1, 1), 3.0 * G3);
+        sum * 32.0
+    }
+}
+
+/// Cellular noise, the distance to the nearest of randomly placed points with one point in every cell of the grid.
+///
+/// Ranges from 0 at the points to roughly 1 between them, forming cells like stones, scales or cracks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Worley {
+    seed: u64,
+}
+
+impl Worley {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns 3 numbers from 0 to 1 for the given cell.
+    fn point(&self, x: i32, y: i32, z: i32) -> Vec3 {
+        let mut hash = self.seed
+            ^ (x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+            ^ (y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+            ^ (z as u64).wrapping_mul(0x1656_67b1_9e37_79f9);
+        let mut next = || {
+            // splitmix64
+            hash = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = hash;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+        };
+        Vec3::new(next(), next(), next())
+    }
+}
+
+impl Noise for Worley {
+    fn get1(&self, x: f32) -> f32 {
+        let cell = x.floor() as i32;
+        (-1..=1)
+            .map(|di| {
+                let i = cell + di;
+                (i as f32 + self.point(i, 0, 0).x - x).abs()
+            })
+            .fold(f32::MAX, f32::min)
+    }
+
+    fn get2(&self, point: Vec2) -> f32 {
+        let cell = point.floor();
+        let (ci, cj) = (cell.x as i32, cell.y as i32);
+        let mut nearest = f32::MAX;
+        for dj in -1..=1 {
+            for di in -1..=1 {
+                let (i, j) = (ci + di, cj + dj);
+                let feature = Vec2::new(i as f32, j as f32) + self.point(i, j, 0).truncate();
+                nearest = nearest.min(feature.distance_squared(point));
+            }
+        }
+        nearest.sqrt()
+    }
+
+    fn get3(&self, point: Vec3) -> f32 {
+        let cell = point.floor();
+        let (ci, cj, ck) = (cell.x as i32, cell.y as i32, cell.z as i32);
+        let mut nearest = f32::MAX;
+        for dk in -1..=1 {
+            for dj in -1..=1 {
+                for di in -1..=1 {
+                    let (i, j, k) = (ci + di, cj + dj, ck + dk);
+                    let feature = Vec3::new(i as f32, j as f32, k as f32) + self.point(i, j, k);
+                    nearest = nearest.min(feature.distance_squared(point));
+                }
+            }
+        }
+        nearest.sqrt()
+    }
+}
+
+/// Fractal Brownian motion, layering octaves of a noise at rising frequencies and falling amplitudes for detail.
+///
+/// Keeps the range of the noise it is made of.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fbm<N> {
+    noise: N,
+    octaves: u32,
+    lacunarity: f32,
+    persistence: f32,
+}
+
+impl<N: Noise> Fbm<N> {
+    /// Layers 4 octaves, each with double the frequency and half the amplitude of the one before.
+    pub fn new(noise: N) -> Self {
+        Self {
+            noise,
+            octaves: 4,
+            lacunarity: 2.0,
+            persistence: 0.5,
+        }
+    }
+
+    /// Sets the number of layered octaves and returns self.
+    #[inline]
+    pub fn octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// Sets how much the frequency rises with every octave and returns self.
+    #[inline]
+    pub fn lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Sets how much the amplitude falls with every octave and returns self.
+    #[inline]
+    pub fn persistence(mut self, persistence: f32) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    /// Sums the octaves sampled by the given function with the frequency, normalized by the total amplitude.
+    fn sum(&self, sample: impl Fn(f32, u32) -> f32) -> f32 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut sum = 0.0;
+        for octave in 0..self.octaves {
+            sum += sample(frequency, octave) * amplitude;
+            total += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        sum / total
+    }
+}
+
+impl<N: Noise> Noise for Fbm<N> {
+    // Every octave gets moved a bit, so the zeros of gradient noise at whole coordinates do not line up.
+    fn get1(&self, x: f32) -> f32 {
+        self.sum(|frequency, octave| self.noise.get1(x * frequency + octave as f32 * 17.31))
+    }
+
+    fn get2(&self, point: Vec2) -> f32 {
+        self.sum(|frequency, octave| {
+            self.noise
+                .get2(point * frequency + Vec2::splat(octave as f32 * 17.31))
+        })
+    }
+
+    fn get3(&self, point: Vec3) -> f32 {
+        self.sum(|frequency, octave| {
+            self.noise
+                .get3(point * frequency + Vec3::splat(octave as f32 * 17.31))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{vec2, vec3};
+
+    /// The values must never change, as games generate their worlds from them.
+    #[test]
+    fn golden_values() {
+        let (one, two, three) = (0.37, vec2(1.3, 2.7), vec3(0.5, 1.25, 3.75));
+
+        let perlin = Perlin::new(42);
+        assert_eq!(perlin.get1(one), 0.34844634);
+        assert_eq!(perlin.get2(two), 0.4235382);
+        assert_eq!(perlin.get3(three), -0.32458162);
+
+        let simplex = Simplex::new(42);
+        assert_eq!(simplex.get1(one), 0.46896133);
+        assert_eq!(simplex.get2(two), -0.87816614);
+        assert_eq!(simplex.get3(three), 0.09684563);
+
+        let worley = Worley::new(42);
+        assert_eq!(worley.get1(one), 0.37156487);
+        assert_eq!(worley.get2(two), 0.3728886);
+        assert_eq!(worley.get3(three), 0.34350032);
+
+        assert_eq!(Fbm::new(Simplex::new(7)).octaves(4).get2(two), 0.059469696);
+    }
+
+    #[test]
+    fn seeds_change_the_noise() {
+        let point = vec2(1.3, 2.7);
+        assert_eq!(Simplex::new(1).get2(point), Simplex::new(1).get2(point));
+        assert_ne!(Simplex::new(1).get2(point), Simplex::new(2).get2(point));
+    }
+}
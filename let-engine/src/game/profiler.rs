@@ -0,0 +1,420 @@
+//! Per system timings of every frame for finding the cause of frame spikes.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "client")]
+use let_engine_core::draw::{GpuPass, GpuTimings};
+#[cfg(feature = "physics")]
+use let_engine_core::objects::physics::PhysicsStats;
+use parking_lot::Mutex;
+
+/// A system of the engine measured by the profiler.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProfileSection {
+    /// The `update` function of the game.
+    Update,
+    /// The `frame_update` function of the game.
+    FrameUpdate,
+    /// The `tick` function of the game including the scheduler and animations.
+    Tick,
+    /// Stepping the physics of every layer.
+    Physics,
+    /// Recording and submitting the frame to the GPU.
+    Draw,
+    /// Time the audio server spent handling sounds and listeners.
+    Audio,
+}
+
+impl ProfileSection {
+    /// Every section in the order they get shown.
+    pub const ALL: [ProfileSection; 6] = [
+        ProfileSection::Update,
+        ProfileSection::FrameUpdate,
+        ProfileSection::Tick,
+        ProfileSection::Physics,
+        ProfileSection::Draw,
+        ProfileSection::Audio,
+    ];
+
+    /// Returns the name of the section.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProfileSection::Update => "update",
+            ProfileSection::FrameUpdate => "frame update",
+            ProfileSection::Tick => "tick",
+            ProfileSection::Physics => "physics",
+            ProfileSection::Draw => "draw",
+            ProfileSection::Audio => "audio",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The time spent in each section during one frame.
+///
+/// Sections running multiple times a frame, like ticks, get summed up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameTimings {
+    timings: [Duration; ProfileSection::ALL.len()],
+    #[cfg(feature = "client")]
+    gpu: Option<GpuTimings>,
+    #[cfg(feature = "physics")]
+    physics: PhysicsStats,
+}
+
+impl FrameTimings {
+    /// Returns the time spent in the given section.
+    pub fn get(&self, section: ProfileSection) -> Duration {
+        self.timings[section.index()]
+    }
+
+    /// Returns the time spent in all sections combined.
+    pub fn total(&self) -> Duration {
+        self.timings.iter().sum()
+    }
+
+    /// Returns the newest GPU timings known at the end of this frame, which are a few frames behind.
+    ///
+    /// `None` in case the GPU does not support timestamps.
+    #[cfg(feature = "client")]
+    pub fn gpu(&self) -> Option<GpuTimings> {
+        self.gpu
+    }
+
+    /// Returns the counters of the latest physics step of all layers at the end of this frame.
+    #[cfg(feature = "physics")]
+    pub fn physics(&self) -> PhysicsStats {
+        self.physics
+    }
+}
+
+/// Measures the time the systems of the engine take each frame and keeps a history of the last frames.
+pub struct Profiler {
+    enabled: AtomicBool,
+    current: Mutex<FrameTimings>,
+    history: Mutex<VecDeque<FrameTimings>>,
+    history_len: Mutex<usize>,
+    #[cfg(feature = "audio")]
+    audio_busy_time: Mutex<Duration>,
+}
+
+impl Profiler {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            current: Mutex::new(FrameTimings::default()),
+            history: Mutex::new(VecDeque::new()),
+            history_len: Mutex::new(240),
+            #[cfg(feature = "audio")]
+            audio_busy_time: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    /// Returns true if the profiler is recording.
+    pub fn enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire)
+    }
+
+    /// Starts or stops recording.
+    ///
+    /// ## Default configuration
+    ///
+    /// true
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Release);
+    }
+
+    /// Returns the number of frames kept in the history.
+    pub fn history_len(&self) -> usize {
+        *self.history_len.lock()
+    }
+
+    /// Sets the number of frames kept in the history.
+    ///
+    /// ## Default configuration
+    ///
+    /// 240
+    pub fn set_history_len(&self, len: usize) {
+        *self.history_len.lock() = len;
+        let mut history = self.history.lock();
+        while history.len() > len {
+            history.pop_front();
+        }
+    }
+
+    /// Adds the given duration to the section of the current frame.
+    pub fn record(&self, section: ProfileSection, duration: Duration) {
+        if self.enabled() {
+            self.current.lock().timings[section.index()] += duration;
+        }
+    }
+
+    /// Measures the given function and records its time to the section.
+    ///
+    /// With the `tracing` feature a span with the name of the section gets emitted.
+    pub fn measure<T>(&self, section: ProfileSection, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("let-engine", section = section.name()).entered();
+        let start = Instant::now();
+        let result = f();
+        self.record(section, start.elapsed());
+        result
+    }
+
+    /// Measures the given future and records the time it took to complete to the section.
+    ///
+    /// With the `tracing` feature the future gets instrumented with a span of the section.
+    pub async fn measure_async<T>(
+        &self,
+        section: ProfileSection,
+        future: impl Future<Output = T>,
+    ) -> T {
+        #[cfg(feature = "tracing")]
+        let future = tracing::Instrument::instrument(
+            future,
+            tracing::info_span!("let-engine", section = section.name()),
+        );
+        let start = Instant::now();
+        let result = future.await;
+        self.record(section, start.elapsed());
+        result
+    }
+
+    /// Moves the timings of the current frame to the history.
+    pub(crate) fn finish_frame(&self) {
+        if !self.enabled() {
+            return;
+        }
+        #[cfg(feature = "audio")]
+        {
+            let busy_time = crate::SETTINGS.audio.stats().busy_time;
+            let last = std::mem::replace(&mut *self.audio_busy_time.lock(), busy_time);
+            self.record(ProfileSection::Audio, busy_time.saturating_sub(last));
+        }
+        #[cfg_attr(not(any(feature = "client", feature = "physics")), allow(unused_mut))]
+        let mut frame = std::mem::take(&mut *self.current.lock());
+        #[cfg(feature = "client")]
+        {
+            frame.gpu = crate::SETTINGS.graphics.stats().gpu_timings;
+        }
+        #[cfg(feature = "physics")]
+        {
+            frame.physics = let_engine_core::objects::scenes::SCENE.physics_stats();
+        }
+        let len = self.history_len();
+        let mut history = self.history.lock();
+        history.push_back(frame);
+        while history.len() > len {
+            history.pop_front();
+        }
+    }
+
+    /// Returns the timings of the last finished frame.
+    pub fn last_frame(&self) -> FrameTimings {
+        self.history.lock().back().copied().unwrap_or_default()
+    }
+
+    /// Returns the timings of the recorded frames from oldest to newest.
+    pub fn history(&self) -> Vec<FrameTimings> {
+        self.history.lock().iter().copied().collect()
+    }
+
+    /// Returns the average time of the section over the recorded frames.
+    pub fn average(&self, section: ProfileSection) -> Duration {
+        let history = self.history.lock();
+        if history.is_empty() {
+            return Duration::ZERO;
+        }
+        history
+            .iter()
+            .map(|frame| frame.get(section))
+            .sum::<Duration>()
+            / history.len() as u32
+    }
+
+    /// Returns the longest time of the section over the recorded frames.
+    pub fn max(&self, section: ProfileSection) -> Duration {
+        self.history
+            .lock()
+            .iter()
+            .map(|frame| frame.get(section))
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the average time the GPU spent on the pass over the recorded frames.
+    #[cfg(feature = "client")]
+    pub fn average_gpu(&self, pass: GpuPass) -> Duration {
+        self.average_gpu_by(|timings| timings.get(pass))
+    }
+
+    /// Returns the average time the GPU spent on whole frames over the recorded frames.
+    #[cfg(feature = "client")]
+    pub fn average_gpu_total(&self) -> Duration {
+        self.average_gpu_by(|timings| timings.total())
+    }
+
+    #[cfg(feature = "client")]
+    fn average_gpu_by(&self, f: impl Fn(&GpuTimings) -> Duration) -> Duration {
+        let history = self.history.lock();
+        let timings: Vec<Duration> = history
+            .iter()
+            .filter_map(|frame| frame.gpu.as_ref().map(&f))
+            .collect();
+        if timings.is_empty() {
+            return Duration::ZERO;
+        }
+        timings.iter().sum::<Duration>() / timings.len() as u32
+    }
+
+    /// Returns true if the GPU takes longer for a frame than the engine systems on the CPU on average,
+    /// meaning drawing less would speed up the game more than optimizing the game logic.
+    #[cfg(feature = "client")]
+    pub fn gpu_bound(&self) -> bool {
+        let cpu = ProfileSection::ALL
+            .iter()
+            .map(|section| self.average(*section))
+            .sum::<Duration>();
+        self.average_gpu_total() > cpu
+    }
+
+    /// Clears the history.
+    pub fn clear(&self) {
+        self.history.lock().clear();
+        *self.current.lock() = FrameTimings::default();
+    }
+
+    /// Shows a window with the timings of each section and a graph of the frame history.
+    #[cfg(feature = "egui")]
+    pub fn show(&self, context: &egui_winit_vulkano::egui::Context) {
+        use egui_winit_vulkano::egui;
+
+        const COLORS: [egui::Color32; ProfileSection::ALL.len()] = [
+            egui::Color32::from_rgb(0x93, 0x76, 0xe0),
+            egui::Color32::from_rgb(0xe8, 0x93, 0xcf),
+            egui::Color32::from_rgb(0x6c, 0xc4, 0xa1),
+            egui::Color32::from_rgb(0xf6, 0xc1, 0x77),
+            egui::Color32::from_rgb(0x5d, 0xa9, 0xe9),
+            egui::Color32::from_rgb(0xf3, 0x6f, 0x6f),
+        ];
+
+        let history = self.history();
+        let last = history.last().copied().unwrap_or_default();
+
+        egui::Window::new("Profiler").show(context, |ui| {
+            egui::Grid::new("let-engine profiler").show(ui, |ui| {
+                ui.label("");
+                ui.label("last");
+                ui.label("average");
+                ui.label("max");
+                ui.end_row();
+                for (section, color) in ProfileSection::ALL.iter().zip(COLORS) {
+                    ui.colored_label(color, section.name());
+                    ui.label(format!(
+                        "{:.2} ms",
+                        last.get(*section).as_secs_f64() * 1000.0
+                    ));
+                    ui.label(format!(
+                        "{:.2} ms",
+                        self.average(*section).as_secs_f64() * 1000.0
+                    ));
+                    ui.label(format!(
+                        "{:.2} ms",
+                        self.max(*section).as_secs_f64() * 1000.0
+                    ));
+                    ui.end_row();
+                }
+            });
+
+            if history.iter().any(|frame| frame.gpu.is_some()) {
+                ui.separator();
+                egui::Grid::new("let-engine gpu profiler").show(ui, |ui| {
+                    ui.label("gpu");
+                    ui.label("last");
+                    ui.label("average");
+                    ui.end_row();
+                    let last = last.gpu.unwrap_or_default();
+                    for pass in GpuPass::ALL {
+                        ui.label(pass.name());
+                        ui.label(format!("{:.2} ms", last.get(pass).as_secs_f64() * 1000.0));
+                        ui.label(format!(
+                            "{:.2} ms",
+                            self.average_gpu(pass).as_secs_f64() * 1000.0
+                        ));
+                        ui.end_row();
+                    }
+                    ui.strong("total");
+                    ui.label(format!("{:.2} ms", last.total().as_secs_f64() * 1000.0));
+                    ui.label(format!(
+                        "{:.2} ms",
+                        self.average_gpu_total().as_secs_f64() * 1000.0
+                    ));
+                    ui.end_row();
+                });
+                ui.label(if self.gpu_bound() {
+                    "GPU bound"
+                } else {
+                    "CPU bound"
+                });
+            }
+
+            #[cfg(feature = "physics")]
+            {
+                ui.separator();
+                let physics = last.physics;
+                ui.label(format!(
+                    "{} bodies, {} active, {} colliders, {} contacts, {} islands",
+                    physics.bodies,
+                    physics.active_bodies,
+                    physics.colliders,
+                    physics.contacts,
+                    physics.islands
+                ));
+                ui.label(format!(
+                    "physics step {:.2} ms",
+                    physics.step_time.as_secs_f64() * 1000.0
+                ));
+            }
+
+            // Stacked bars of every frame, scaled to the slowest one.
+            let (response, painter) =
+                ui.allocate_painter(egui::vec2(ui.available_width(), 80.0), egui::Sense::hover());
+            let rect = response.rect;
+            painter.rect_filled(rect, 0.0, egui::Color32::from_black_alpha(100));
+            let slowest = history
+                .iter()
+                .map(FrameTimings::total)
+                .max()
+                .unwrap_or_default()
+                .as_secs_f32();
+            if slowest <= 0.0 || history.is_empty() {
+                return;
+            }
+            let width = rect.width() / self.history_len().max(1) as f32;
+            for (i, frame) in history.iter().enumerate() {
+                let x = rect.left() + i as f32 * width;
+                let mut bottom = rect.bottom();
+                for (section, color) in ProfileSection::ALL.iter().zip(COLORS) {
+                    let height = frame.get(*section).as_secs_f32() / slowest * rect.height();
+                    painter.rect_filled(
+                        egui::Rect::from_min_max(
+                            egui::pos2(x, bottom - height),
+                            egui::pos2(x + width, bottom),
+                        ),
+                        0.0,
+                        color,
+                    );
+                    bottom -= height;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,396 @@
+//! Random numbers that are the same on every machine given the same seed, for lockstep networking, replays and procedural generation.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use rand::{
+    distributions::{
+        uniform::{SampleRange, SampleUniform},
+        Distribution, Standard,
+    },
+    Rng, RngCore, SeedableRng,
+};
+use serde::{Deserialize, Serialize};
+
+/// A small and fast random number generator (xoshiro256**) giving the same sequence on every platform for the same seed.
+///
+/// Unlike the generators of `rand`, the raw sequence of [`next_u64`](RngCore::next_u64) never changes between versions
+/// of the engine. Values converted by `rand`, like the ones of `gen` and `gen_range`, only stay the same as long as the
+/// version of `rand` does.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeterministicRng {
+    state: [u64; 4],
+}
+
+impl DeterministicRng {
+    /// Creates a generator starting the sequence of the given seed.
+    pub fn new(seed: u64) -> Self {
+        // Spread the seed over the whole state using splitmix64, as the state may not be all zeros.
+        let mut seed = seed;
+        let mut state = [0; 4];
+        for word in &mut state {
+            seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = seed;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *word = z ^ (z >> 31);
+        }
+        Self { state }
+    }
+
+    /// Creates the generator of the named stream of the given seed.
+    ///
+    /// Every system drawing from its own stream gets the same numbers for the same seed,
+    /// no matter how many numbers other systems draw or in which order the systems run.
+    pub fn stream(seed: u64, name: &str) -> Self {
+        Self::new(seed ^ stable_hash(name))
+    }
+}
+
+/// FNV-1a, as the hashers of the standard library are free to change between versions.
+fn stable_hash(name: &str) -> u64 {
+    name.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl RngCore for DeterministicRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for DeterministicRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        let mut state = [0; 4];
+        for (word, bytes) in state.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(bytes.try_into().unwrap());
+        }
+        if state == [0; 4] {
+            return Self::new(0);
+        }
+        Self { state }
+    }
+
+    fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+/// The engine wide random number generator, see [RANDOM](crate::RANDOM).
+///
+/// # Usage
+/// ```ignore
+/// // The host picks a seed and sends it to every peer before the match starts.
+/// RANDOM.seed(seed);
+///
+/// // In the tick function.
+/// let spawn_x = RANDOM.gen_range(-10.0..10.0);
+/// let critical = RANDOM.gen_bool(0.1);
+/// ```
+///
+/// The numbers only match between peers as long as they get drawn in the same order,
+/// so only draw from this generator in the deterministic parts of the game, like the tick function.
+/// Visual effects drawing random numbers every frame should use [fork](Random::fork) or `rand::random` instead.
+pub struct Random {
+    rng: Mutex<DeterministicRng>,
+    seed: AtomicU64,
+}
+
+impl Random {
+    /// Creates a generator with a random seed.
+    pub(crate) fn new() -> Self {
+        let seed = rand::random();
+        Self {
+            rng: Mutex::new(DeterministicRng::new(seed)),
+            seed: AtomicU64::new(seed),
+        }
+    }
+
+    /// Restarts the sequence with the given seed.
+    pub fn seed(&self, seed: u64) {
+        *self.rng.lock() = DeterministicRng::new(seed);
+        self.seed.store(seed, Ordering::Release);
+    }
+
+    /// Returns the last seed the generator got seeded with.
+    pub fn current_seed(&self) -> u64 {
+        self.seed.load(Ordering::Acquire)
+    }
+
+    /// Returns the generator of the named stream of the current seed, for systems drawing numbers on their own.
+    ///
+    /// Unlike [fork](Random::fork) the stream does not depend on the numbers drawn before,
+    /// so world generation and loot tables stay the same for a seed as the game changes around them.
+    ///
+    /// # Usage
+    /// ```ignore
+    /// let mut terrain = RANDOM.stream("terrain");
+    /// let mut loot = RANDOM.stream("loot");
+    /// let height = Fbm::new(Simplex::new(terrain.gen())).octaves(5);
+    /// let drop = loot_table.pick(&mut loot);
+    /// ```
+    pub fn stream(&self, name: &str) -> DeterministicRng {
+        DeterministicRng::stream(self.current_seed(), name)
+    }
+
+    /// Returns a random value of the type, for example a float from 0 to 1 or any integer.
+    pub fn gen<T>(&self) -> T
+    where
+        Standard: Distribution<T>,
+    {
+        self.rng.lock().gen()
+    }
+
+    /// Returns a random value in the given range.
+    pub fn gen_range<T: SampleUniform, R: SampleRange<T>>(&self, range: R) -> T {
+        self.rng.lock().gen_range(range)
+    }
+
+    /// Returns true with the given probability from 0 to 1.
+    pub fn gen_bool(&self, probability: f64) -> bool {
+        self.rng.lock().gen_bool(probability)
+    }
+
+    /// Returns a random index of the weights, each being as likely as its weight.
+    ///
+    /// `None` if there are no weights or none of them is above 0.
+    pub fn weighted_index(&self, weights: &[f64]) -> Option<usize> {
+        weighted_index(&mut *self.rng.lock(), weights.iter().copied())
+    }
+
+    /// Returns a random item of the slice, each being as likely as the weight the given function returns for it.
+    pub fn choose_weighted<'a, T>(
+        &self,
+        items: &'a [T],
+        weight: impl Fn(&T) -> f64,
+    ) -> Option<&'a T> {
+        let index = weighted_index(&mut *self.rng.lock(), items.iter().map(&weight))?;
+        items.get(index)
+    }
+
+    /// Returns a random item of the table.
+    pub fn pick<'a, T>(&self, table: &'a WeightedTable<T>) -> &'a T {
+        table.pick(&mut *self.rng.lock())
+    }
+
+    /// Returns a new generator seeded by this one, for systems drawing numbers on their own.
+    ///
+    /// Drawing from the returned generator does not move the sequence of this one.
+    pub fn fork(&self) -> DeterministicRng {
+        DeterministicRng::new(self.rng.lock().next_u64())
+    }
+
+    /// Returns the current state of the generator, to store it in save games or send it to joining peers.
+    pub fn state(&self) -> DeterministicRng {
+        self.rng.lock().clone()
+    }
+
+    /// Continues the sequence from the given state.
+    pub fn set_state(&self, state: DeterministicRng) {
+        *self.rng.lock() = state;
+    }
+}
+
+/// Returns a random index of the weights, each being as likely as its weight. Weights below 0 count as 0.
+fn weighted_index(rng: &mut impl Rng, weights: impl Iterator<Item = f64> + Clone) -> Option<usize> {
+    let total: f64 = weights.clone().map(|weight| weight.max(0.0)).sum();
+    if !(total > 0.0 && total.is_finite()) {
+        return None;
+    }
+    let mut point = rng.gen_range(0.0..total);
+    let mut last = None;
+    for (index, weight) in weights.enumerate() {
+        let weight = weight.max(0.0);
+        if weight <= 0.0 {
+            continue;
+        }
+        if point < weight {
+            return Some(index);
+        }
+        point -= weight;
+        last = Some(index);
+    }
+    // Rounding errors may leave the point slightly above the last weight.
+    last
+}
+
+/// A list of items with weights for drawing many times, like a loot table.
+///
+/// Drawing is a binary search over the summed weights, so it stays fast for long tables.
+///
+/// # Usage
+/// ```ignore
+/// let loot = WeightedTable::new([("coin", 80.0), ("potion", 15.0), ("sword", 5.0)]).unwrap();
+/// let drop = RANDOM.pick(&loot);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "WeightedTableData<T>")]
+pub struct WeightedTable<T> {
+    items: Vec<T>,
+    /// The sum of the weights up to and including each item.
+    cumulative: Vec<f64>,
+}
+
+/// The unchecked fields of a deserialized [`WeightedTable`].
+#[derive(Deserialize)]
+struct WeightedTableData<T> {
+    items: Vec<T>,
+    cumulative: Vec<f64>,
+}
+
+impl<T> TryFrom<WeightedTableData<T>> for WeightedTable<T> {
+    type Error = &'static str;
+
+    /// Makes sure the sums are as many as the items and grow with every item, which drawing relies on.
+    fn try_from(data: WeightedTableData<T>) -> Result<Self, Self::Error> {
+        if data.items.is_empty() {
+            return Err("a weighted table needs at least one item");
+        }
+        if data.items.len() != data.cumulative.len() {
+            return Err("a weighted table needs as many summed weights as items");
+        }
+        let mut previous = 0.0;
+        for &sum in &data.cumulative {
+            if !sum.is_finite() || sum <= previous {
+                return Err("the summed weights of a weighted table have to be finite and growing");
+            }
+            previous = sum;
+        }
+        Ok(Self {
+            items: data.items,
+            cumulative: data.cumulative,
+        })
+    }
+}
+
+impl<T> WeightedTable<T> {
+    /// Creates a table of the given items and weights, leaving out items with a weight of 0 or less.
+    ///
+    /// `None` if no item is left or the weights add up to more than a `f64` can hold.
+    pub fn new(items: impl IntoIterator<Item = (T, f64)>) -> Option<Self> {
+        let mut table = Self {
+            items: vec![],
+            cumulative: vec![],
+        };
+        let mut total = 0.0;
+        for (item, weight) in items {
+            if weight > 0.0 && weight.is_finite() {
+                total += weight;
+                table.items.push(item);
+                table.cumulative.push(total);
+            }
+        }
+        (!table.items.is_empty() && total.is_finite()).then_some(table)
+    }
+
+    /// Returns a random item using the given generator, each being as likely as its weight.
+    pub fn pick(&self, rng: &mut impl Rng) -> &T {
+        let total = self.total();
+        let point = rng.gen_range(0.0..total);
+        let index = self
+            .cumulative
+            .partition_point(|&sum| sum <= point)
+            .min(self.items.len() - 1);
+        &self.items[index]
+    }
+
+    /// Returns the sum of all weights.
+    pub fn total(&self) -> f64 {
+        self.cumulative.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the items in the order they were given.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weighted_table_skips_invalid_weights() {
+        let table =
+            WeightedTable::new([("a", 1.0), ("b", 0.0), ("c", f64::NAN), ("d", 3.0)]).unwrap();
+        assert_eq!(table.items(), ["a", "d"]);
+        assert_eq!(table.total(), 4.0);
+        assert!(WeightedTable::new([("a", -1.0)]).is_none());
+    }
+
+    #[test]
+    fn weighted_table_needs_a_finite_total() {
+        assert!(WeightedTable::new([("a", f64::MAX), ("b", f64::MAX)]).is_none());
+        assert!(WeightedTable::new([("a", f64::MAX), ("b", 1.0)]).is_some());
+    }
+
+    #[test]
+    fn weighted_table_picks_by_weight() {
+        let table = WeightedTable::new([(0, 1.0), (1, 3.0)]).unwrap();
+        let mut rng = DeterministicRng::new(7);
+        let mut counts = [0; 2];
+        for _ in 0..4000 {
+            counts[*table.pick(&mut rng)] += 1;
+        }
+        assert!((2800..3200).contains(&counts[1]), "{counts:?}");
+    }
+
+    #[test]
+    fn weighted_table_validates_deserialized_sums() {
+        let table = WeightedTable::new([("a", 1.0), ("b", 2.0)]).unwrap();
+        let bytes = bincode::serialize(&table).unwrap();
+        assert_eq!(
+            bincode::deserialize::<WeightedTable<String>>(&bytes)
+                .unwrap()
+                .total(),
+            3.0
+        );
+
+        for (items, sums) in [
+            (vec!["a", "b"], vec![2.0, 1.0]),
+            (vec!["a", "b"], vec![1.0]),
+            (vec![], vec![]),
+            (vec!["a"], vec![f64::INFINITY]),
+            (vec!["a"], vec![0.0]),
+        ] {
+            let bytes = bincode::serialize(&(items, sums)).unwrap();
+            assert!(bincode::deserialize::<WeightedTable<String>>(&bytes).is_err());
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! Loading textures, models and sounds through one cache, with memory reports and a GPU memory budget.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use crossbeam::atomic::AtomicCell;
+#[cfg(feature = "audio")]
+use let_engine_audio::SoundData;
+use let_engine_core::resources::{
+    allocations,
+    data::Data,
+    textures::{AnimatedTexture, Texture, TextureSettings},
+    MemoryKind, ModelData,
+};
+use parking_lot::Mutex;
+
+/// The kind of a resource in a [`ResourceReport`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    /// The pixels of a texture in GPU memory.
+    Texture,
+    /// The vertex and index buffers of a model in GPU memory.
+    Buffer,
+    /// The samples of a sound in system memory.
+    Sound,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResourceKind::Texture => "texture",
+            ResourceKind::Buffer => "buffer",
+            ResourceKind::Sound => "sound",
+        })
+    }
+}
+
+/// A resource listed in a [`ResourceReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceInfo {
+    pub kind: ResourceKind,
+    /// The name of the resource, which is the asset path for the textures and sounds loaded through [`RESOURCE_CACHE`](crate::RESOURCE_CACHE).
+    pub label: Option<Arc<str>>,
+    pub bytes: u64,
+    /// The time since the resource was last requested from the cache, `None` for resources not loaded through it.
+    pub idle: Option<Duration>,
+    /// False in case the resource got evicted and gets loaded again the next time it gets requested.
+    pub resident: bool,
+}
+
+/// A snapshot of all loaded resources for debugging memory usage.
+///
+/// Formatting it with `{}` prints a table sorted by size.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourceReport {
+    pub resources: Vec<ResourceInfo>,
+    /// The budget of GPU memory set in the cache.
+    pub budget: Option<u64>,
+    /// The number of textures and models evicted since the start of the game.
+    pub evictions: u64,
+}
+
+impl ResourceReport {
+    /// Returns the bytes used by all resident resources of the given kind.
+    pub fn total(&self, kind: ResourceKind) -> u64 {
+        self.resources
+            .iter()
+            .filter(|resource| resource.kind == kind && resource.resident)
+            .map(|resource| resource.bytes)
+            .sum()
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let gpu = self.total(ResourceKind::Texture) + self.total(ResourceKind::Buffer);
+        write!(f, "GPU memory: {}", Bytes(gpu))?;
+        if let Some(budget) = self.budget {
+            write!(f, " of {}", Bytes(budget))?;
+        }
+        writeln!(
+            f,
+            ", sounds: {}, evictions: {}",
+            Bytes(self.total(ResourceKind::Sound)),
+            self.evictions
+        )?;
+
+        let mut resources: Vec<&ResourceInfo> = self.resources.iter().collect();
+        resources.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+        for resource in resources {
+            write!(
+                f,
+                "{:<8} {:>10}  {}",
+                resource.kind,
+                Bytes(resource.bytes).to_string(),
+                resource.label.as_deref().unwrap_or("<unnamed>")
+            )?;
+            if !resource.resident {
+                write!(f, " (evicted)")?;
+            } else if let Some(idle) = resource.idle {
+                write!(f, " (used {:.1}s ago)", idle.as_secs_f32())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a byte count with a binary unit.
+pub(crate) struct Bytes(pub(crate) u64);
+
+impl fmt::Display for Bytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+        let mut size = self.0 as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            write!(f, "{} B", self.0)
+        } else {
+            write!(f, "{size:.1} {}", UNITS[unit])
+        }
+    }
+}
+
+/// How the layers of a cached texture got loaded, so the same file can be cached as both.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(not(feature = "asset_system"), allow(dead_code))]
+enum TextureLayout {
+    /// A texture with a single layer loaded using [`ResourceCache::texture`].
+    Single,
+    /// A texture with a layer per frame loaded using [`ResourceCache::animated_texture`].
+    Animated,
+}
+
+struct CachedTexture {
+    /// `None` while evicted.
+    texture: Option<Texture>,
+    /// The frame delays of animated textures.
+    #[cfg_attr(not(feature = "asset_system"), allow(dead_code))]
+    delays: Option<Arc<[Duration]>>,
+    #[cfg_attr(not(feature = "asset_system"), allow(dead_code))]
+    settings: TextureSettings,
+    bytes: u64,
+    last_used: Instant,
+}
+
+struct CachedModel {
+    /// `None` while evicted.
+    model: Option<ModelData>,
+    /// The data to load the model again after it got evicted.
+    data: Data,
+    bytes: u64,
+    last_used: Instant,
+}
+
+#[cfg(feature = "audio")]
+struct CachedSound {
+    sound: SoundData,
+    last_used: Instant,
+}
+
+/// A cache of the textures and sounds loaded from the assets, keyed by their asset path, and of named models.
+///
+/// With a budget set, textures and models nothing else uses anymore get evicted from the cache, least recently used first,
+/// whenever the GPU memory of all textures and models goes above it.
+/// Evicted textures get loaded from the assets again the next time they get requested
+/// and evicted models from the data they were made of.
+///
+/// # Usage
+/// ```ignore
+/// RESOURCE_CACHE.set_budget(Some(512 * 1024 * 1024));
+/// let stone = RESOURCE_CACHE.texture("textures/stone.png", TextureSettings::default()).await?;
+///
+/// let rock = RESOURCE_CACHE.model("rock", || Ok(rock_data()))?;
+///
+/// println!("{}", resources::report());
+/// ```
+pub struct ResourceCache {
+    textures: Mutex<HashMap<(Arc<str>, TextureLayout), CachedTexture>>,
+    models: Mutex<HashMap<Arc<str>, CachedModel>>,
+    #[cfg(feature = "audio")]
+    sounds: Mutex<HashMap<Arc<str>, CachedSound>>,
+    budget: AtomicCell<Option<u64>>,
+    evictions: AtomicU64,
+}
+
+impl ResourceCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            textures: Mutex::new(HashMap::new()),
+            models: Mutex::new(HashMap::new()),
+            #[cfg(feature = "audio")]
+            sounds: Mutex::new(HashMap::new()),
+            budget: AtomicCell::new(None),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the texture at the given asset path, loading it in case it is not in the cache.
+    ///
+    /// The image format is taken from the file extension. The settings only get used when loading,
+    /// so a cached texture keeps the settings it was loaded with first.
+    #[cfg(feature = "asset_system")]
+    pub async fn texture(&self, path: &str, settings: TextureSettings) -> Result<Texture> {
+        let key = (Arc::from(path), TextureLayout::Single);
+        let settings = match self.textures.lock().get_mut(&key) {
+            Some(cached) => {
+                cached.last_used = Instant::now();
+                if let Some(texture) = &cached.texture {
+                    return Ok(texture.clone());
+                }
+                cached.settings.clone()
+            }
+            None => settings,
+        };
+
+        let data = asset_system::asset(path).await?;
+        let format = let_engine_core::resources::textures::ImageFormat::from_path(path)?;
+        let texture = Texture::from_bytes(&data, format, 1, settings.clone(), Some(path))?;
+        self.textures.lock().insert(
+            key,
+            CachedTexture {
+                texture: Some(texture.clone()),
+                delays: None,
+                settings,
+                bytes: texture.data().len() as u64,
+                last_used: Instant::now(),
+            },
+        );
+        self.trim();
+        Ok(texture)
+    }
+
+    /// Returns the animated GIF, APNG or WebP image at the given asset path as an animated texture,
+    /// loading it in case it is not in the cache.
+    ///
+    /// It shares the budget and eviction with the other textures,
+    /// but is cached separately from the single layer texture [`texture`](Self::texture) loads from the same path.
+    #[cfg(feature = "asset_system")]
+    pub async fn animated_texture(
+        &self,
+        path: &str,
+        settings: TextureSettings,
+    ) -> Result<AnimatedTexture> {
+        let key = (Arc::from(path), TextureLayout::Animated);
+        let settings = match self.textures.lock().get_mut(&key) {
+            Some(cached) => {
+                cached.last_used = Instant::now();
+                if let (Some(texture), Some(delays)) = (&cached.texture, &cached.delays) {
+                    return Ok(AnimatedTexture::new(texture.clone(), delays.clone())?);
+                }
+                cached.settings.clone()
+            }
+            None => settings,
+        };
+
+        let data = asset_system::asset(path).await?;
+        let format = let_engine_core::resources::textures::ImageFormat::from_path(path)?;
+        let animated = Texture::animated_from_bytes(&data, format, settings.clone(), Some(path))?;
+        self.textures.lock().insert(
+            key,
+            CachedTexture {
+                texture: Some(animated.texture().clone()),
+                delays: Some(animated.delays().clone()),
+                settings,
+                bytes: animated.texture().data().len() as u64,
+                last_used: Instant::now(),
+            },
+        );
+        self.trim();
+        Ok(animated)
+    }
+
+    /// Returns the model with the given name, making it from the data `load` returns in case it is not in the cache.
+    ///
+    /// Evicted models get loaded to the GPU again from the data they were made of, without calling `load`.
+    pub fn model(&self, name: &str, load: impl FnOnce() -> Result<Data>) -> Result<ModelData> {
+        let data = match self.models.lock().get_mut(name) {
+            Some(cached) => {
+                cached.last_used = Instant::now();
+                if let Some(model) = &cached.model {
+                    return Ok(model.clone());
+                }
+                cached.data.clone()
+            }
+            None => load()?,
+        };
+
+        let model = ModelData::new_named(data.clone(), name)?;
+        let bytes = std::mem::size_of_val(data.vertices()) + std::mem::size_of_val(data.indices());
+        self.models.lock().insert(
+            Arc::from(name),
+            CachedModel {
+                model: Some(model.clone()),
+                data,
+                bytes: bytes as u64,
+                last_used: Instant::now(),
+            },
+        );
+        self.trim();
+        Ok(model)
+    }
+
+    /// Returns the sound at the given asset path, loading it in case it is not in the cache.
+    #[cfg(all(feature = "audio", feature = "asset_system"))]
+    pub async fn sound(&self, path: &str) -> Result<SoundData> {
+        if let Some(cached) = self.sounds.lock().get_mut(path) {
+            cached.last_used = Instant::now();
+            return Ok(cached.sound.clone());
+        }
+
+        let data = asset_system::asset(path).await?;
+        let sound = SoundData::from_cursor(std::io::Cursor::new(data))?;
+        self.sounds.lock().insert(
+            Arc::from(path),
+            CachedSound {
+                sound: sound.clone(),
+                last_used: Instant::now(),
+            },
+        );
+        Ok(sound)
+    }
+
+    /// Sets the GPU memory in bytes above which unused textures get evicted, `None` to never evict.
+    pub fn set_budget(&self, budget: Option<u64>) {
+        self.budget.store(budget);
+        self.trim();
+    }
+
+    /// Returns the GPU memory budget in bytes.
+    pub fn budget(&self) -> Option<u64> {
+        self.budget.load()
+    }
+
+    /// Evicts the least recently used textures nothing else uses until the GPU memory is within the budget.
+    ///
+    /// Gets called every time a texture gets loaded through the cache or the budget changes.
+    pub fn trim(&self) {
+        let Some(budget) = self.budget.load() else {
+            return;
+        };
+        let mut used: u64 = allocations()
+            .iter()
+            .map(|allocation| allocation.bytes)
+            .sum();
+        if used <= budget {
+            return;
+        }
+
+        let mut textures = self.textures.lock();
+        let mut models = self.models.lock();
+        let mut candidates: Vec<(Instant, Option<TextureLayout>, Arc<str>)> = textures
+            .iter()
+            .filter(|(_, cached)| {
+                cached
+                    .texture
+                    .as_ref()
+                    .is_some_and(|texture| texture.handle_count() == 1)
+            })
+            .map(|((path, layout), cached)| (cached.last_used, Some(*layout), path.clone()))
+            .chain(
+                models
+                    .iter()
+                    .filter(|(_, cached)| {
+                        cached
+                            .model
+                            .as_ref()
+                            .is_some_and(|model| model.handle_count() == 1)
+                    })
+                    .map(|(name, cached)| (cached.last_used, None, name.clone())),
+            )
+            .collect();
+        candidates.sort_by_key(|(last_used, _, _)| *last_used);
+        for (_, layout, name) in candidates {
+            if used <= budget {
+                break;
+            }
+            let bytes = match layout {
+                Some(layout) => textures.get_mut(&(name, layout)).map(|cached| {
+                    cached.texture = None;
+                    cached.bytes
+                }),
+                None => models.get_mut(&name).map(|cached| {
+                    cached.model = None;
+                    cached.bytes
+                }),
+            };
+            if let Some(bytes) = bytes {
+                used = used.saturating_sub(bytes);
+                self.evictions.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Evicts the texture at the given asset path or the model with the given name from the cache,
+    /// returning false in case neither was loaded.
+    ///
+    /// The GPU memory gets freed once nothing else uses the resource anymore.
+    pub fn evict(&self, name: &str) -> bool {
+        let texture = self
+            .textures
+            .lock()
+            .iter_mut()
+            .filter(|((path, _), _)| &**path == name)
+            .filter_map(|(_, cached)| cached.texture.take())
+            .count();
+        let model = self
+            .models
+            .lock()
+            .get_mut(name)
+            .and_then(|cached| cached.model.take())
+            .is_some();
+        let evicted = texture as u64 + model as u64;
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        evicted > 0
+    }
+
+    /// Removes all textures, models and sounds from the cache.
+    pub fn clear(&self) {
+        self.textures.lock().clear();
+        self.models.lock().clear();
+        #[cfg(feature = "audio")]
+        self.sounds.lock().clear();
+    }
+
+    /// Returns a report of all textures and models using GPU memory and the sounds in the cache.
+    pub fn report(&self) -> ResourceReport {
+        let now = Instant::now();
+        let textures = self.textures.lock();
+        let models = self.models.lock();
+        let mut resources: Vec<ResourceInfo> = allocations()
+            .into_iter()
+            .map(|allocation| {
+                let kind = match allocation.kind {
+                    MemoryKind::Texture => ResourceKind::Texture,
+                    MemoryKind::Buffer => ResourceKind::Buffer,
+                };
+                let idle = allocation.label.as_ref().and_then(|label| match kind {
+                    ResourceKind::Texture => textures
+                        .iter()
+                        .filter(|((path, _), _)| path == label)
+                        .map(|(_, cached)| cached.last_used)
+                        .max(),
+                    _ => models.get(label).map(|cached| cached.last_used),
+                });
+                let idle = idle.map(|last_used| now.duration_since(last_used));
+                ResourceInfo {
+                    kind,
+                    label: allocation.label,
+                    bytes: allocation.bytes,
+                    idle,
+                    resident: true,
+                }
+            })
+            .collect();
+        resources.extend(
+            textures
+                .iter()
+                .filter(|(_, cached)| cached.texture.is_none())
+                .map(|((path, _), cached)| ResourceInfo {
+                    kind: ResourceKind::Texture,
+                    label: Some(path.clone()),
+                    bytes: cached.bytes,
+                    idle: Some(now.duration_since(cached.last_used)),
+                    resident: false,
+                }),
+        );
+        resources.extend(
+            models
+                .iter()
+                .filter(|(_, cached)| cached.model.is_none())
+                .map(|(name, cached)| ResourceInfo {
+                    kind: ResourceKind::Buffer,
+                    label: Some(name.clone()),
+                    bytes: cached.bytes,
+                    idle: Some(now.duration_since(cached.last_used)),
+                    resident: false,
+                }),
+        );
+        #[cfg(feature = "audio")]
+        resources.extend(
+            self.sounds
+                .lock()
+                .iter()
+                .map(|(path, cached)| ResourceInfo {
+                    kind: ResourceKind::Sound,
+                    label: Some(path.clone()),
+                    bytes: (cached.sound.frames.len()
+                        * std::mem::size_of::<let_engine_audio::Frame>())
+                        as u64,
+                    idle: Some(now.duration_since(cached.last_used)),
+                    resident: true,
+                }),
+        );
+
+        ResourceReport {
+            resources,
+            budget: self.budget.load(),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+}
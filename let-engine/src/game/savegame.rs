@@ -0,0 +1,412 @@
+//! Saving and loading versioned game state.
+//!
+//! Save files get written to the data directory of the platform:
+//!
+//! - Linux: `$XDG_DATA_HOME/<game>` or `~/.local/share/<game>`
+//! - Windows: `%APPDATA%\<game>`
+//! - macOS: `~/Library/Application Support/<game>`
+//! - Android: `<internal data path>/<game>`
+//!
+//! # Usage
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Progress {
+//!     level: u32,
+//!     coins: u64,
+//! }
+//!
+//! impl SaveData for Progress {
+//!     const VERSION: u32 = 2;
+//!
+//!     fn migrate(version: u32, data: &[u8]) -> Result<Self, SaveError> {
+//!         match version {
+//!             // version 1 did not have coins yet.
+//!             1 => Ok(Progress { level: decode(data)?, coins: 0 }),
+//!             version => Err(SaveError::UnsupportedVersion(version)),
+//!         }
+//!     }
+//! }
+//!
+//! let saves = Saves::new("my_game")?;
+//! saves.save("slot1", &progress)?;
+//! let progress: Progress = saves.load("slot1")?;
+//! ```
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+
+const MAGIC: [u8; 8] = *b"LETSAVE\0";
+const HEADER_SIZE: usize = MAGIC.len() + 4 + 1 + 4;
+const EXTENSION: &str = "sav";
+
+/// Game state that can be written to and read from a save file.
+pub trait SaveData: Serialize + DeserializeOwned {
+    /// The version of the save format.
+    ///
+    /// Increase this every time the structure of the data changes and handle the old versions in [`migrate`](SaveData::migrate).
+    const VERSION: u32 = 1;
+
+    /// Converts the data of a save file written with an older version to the current structure.
+    ///
+    /// The data can be decoded into the old structure using [`decode`].
+    fn migrate(version: u32, data: &[u8]) -> Result<Self, SaveError> {
+        let _ = data;
+        Err(SaveError::UnsupportedVersion(version))
+    }
+}
+
+/// Decodes the raw data of a save file, used to read older versions while migrating.
+pub fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T, SaveError> {
+    Ok(bincode::deserialize(data)?)
+}
+
+/// A directory holding the save files of a game by slot name.
+#[derive(Clone, Debug)]
+pub struct Saves {
+    directory: PathBuf,
+    compress: bool,
+}
+
+impl Saves {
+    /// Uses the directory named after the game in the data directory of the platform.
+    pub fn new(game: &str) -> Result<Self, SaveError> {
+        let directory = data_directory().ok_or(SaveError::NoSaveDirectory)?;
+        Ok(Self::at(directory.join(game)))
+    }
+
+    /// Uses the given directory for the save files.
+    pub fn at(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            compress: false,
+        }
+    }
+
+    /// Compresses newly written save files using deflate.
+    ///
+    /// Requires the `deflate` feature to be enabled. Compressed files can be read regardless of this setting.
+    #[cfg(feature = "deflate")]
+    pub fn compressed(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Returns the directory of the save files.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns the path of the save file of the given slot.
+    ///
+    /// Fails with [`SaveError::InvalidSlot`] in case the name is not a plain file name,
+    /// so slots can not point outside of the save directory.
+    pub fn path(&self, slot: &str) -> Result<PathBuf, SaveError> {
+        validate_slot(slot)?;
+        Ok(self.directory.join(format!("{slot}.{EXTENSION}")))
+    }
+
+    /// Writes the data to the given slot.
+    ///
+    /// The file gets replaced atomically, so the previous save stays intact in case writing fails.
+    pub fn save<T: SaveData>(&self, slot: &str, data: &T) -> Result<(), SaveError> {
+        let path = self.path(slot)?;
+        fs::create_dir_all(&self.directory)?;
+
+        let mut payload = bincode::serialize(data)?;
+        if self.compress {
+            payload = compress(&payload)?;
+        }
+
+        let mut file = Vec::with_capacity(HEADER_SIZE + payload.len());
+        file.extend_from_slice(&MAGIC);
+        file.extend_from_slice(&T::VERSION.to_le_bytes());
+        file.push(self.compress as u8);
+        file.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+        file.extend(payload);
+
+        let temporary = path.with_extension(format!("{EXTENSION}.tmp"));
+        {
+            let mut output = fs::File::create(&temporary)?;
+            output.write_all(&file)?;
+            output.sync_all()?;
+        }
+        fs::rename(temporary, path)?;
+        Ok(())
+    }
+
+    /// Reads the data of the given slot, migrating it in case it was saved with an older version.
+    pub fn load<T: SaveData>(&self, slot: &str) -> Result<T, SaveError> {
+        let file = fs::read(self.path(slot)?)?;
+        if file.len() < HEADER_SIZE || file[..MAGIC.len()] != MAGIC {
+            return Err(SaveError::InvalidFile);
+        }
+        let (header, payload) = file.split_at(HEADER_SIZE);
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let compressed = header[12];
+        let checksum = u32::from_le_bytes(header[13..17].try_into().unwrap());
+
+        if crc32fast::hash(payload) != checksum {
+            return Err(SaveError::Corrupted);
+        }
+
+        let payload = match compressed {
+            0 => payload.to_vec(),
+            _ => decompress(payload)?,
+        };
+
+        match version {
+            version if version == T::VERSION => decode(&payload),
+            version if version < T::VERSION => T::migrate(version, &payload),
+            version => Err(SaveError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Returns true if the given slot has a save file.
+    pub fn exists(&self, slot: &str) -> bool {
+        self.path(slot).is_ok_and(|path| path.is_file())
+    }
+
+    /// Deletes the save file of the given slot.
+    pub fn delete(&self, slot: &str) -> Result<(), SaveError> {
+        Ok(fs::remove_file(self.path(slot)?)?)
+    }
+
+    /// Returns the names of all slots with a save file.
+    pub fn slots(&self) -> Result<Vec<String>, SaveError> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        let mut slots = vec![];
+        for entry in entries {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == EXTENSION)
+            {
+                if let Some(slot) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    slots.push(slot.to_string());
+                }
+            }
+        }
+        slots.sort();
+        Ok(slots)
+    }
+}
+
+/// Returns the directory of the platform for user specific application data.
+#[cfg(not(all(feature = "android", target_os = "android")))]
+pub fn data_directory() -> Option<PathBuf> {
+    platform_directory("XDG_DATA_HOME", ".local/share")
+}
+
+/// Returns the directory of the platform for user specific configuration files.
+///
+/// This is the same as the [data directory](data_directory), except for Linux,
+/// where it is `$XDG_CONFIG_HOME` or `~/.config`.
+#[cfg(not(all(feature = "android", target_os = "android")))]
+pub fn config_directory() -> Option<PathBuf> {
+    platform_directory("XDG_CONFIG_HOME", ".config")
+}
+
+/// Returns the given XDG directory on Linux, falling back to the given path in the home directory,
+/// and the application data directory on Windows and macOS.
+#[cfg(not(all(feature = "android", target_os = "android")))]
+fn platform_directory(xdg_variable: &str, fallback: &str) -> Option<PathBuf> {
+    let home = || std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os(xdg_variable)
+            .map(PathBuf::from)
+            .filter(|path| path.is_absolute())
+            .or_else(|| home().map(|home| home.join(fallback)))
+    }
+}
+
+/// Returns the internal data directory of the app.
+#[cfg(all(feature = "android", target_os = "android"))]
+pub fn data_directory() -> Option<PathBuf> {
+    crate::android::data_directory()
+}
+
+/// Returns the internal data directory of the app, as Android has no separate directory for configuration files.
+#[cfg(all(feature = "android", target_os = "android"))]
+pub fn config_directory() -> Option<PathBuf> {
+    crate::android::data_directory()
+}
+
+#[cfg(feature = "deflate")]
+fn compress(data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    let mut encoder =
+        flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(not(feature = "deflate"))]
+fn compress(_data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    Err(SaveError::CompressionUnsupported)
+}
+
+#[cfg(feature = "deflate")]
+fn decompress(data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    let mut decoder = flate2::write::DeflateDecoder::new(Vec::new());
+    decoder.write_all(data)?;
+    Ok(decoder.finish()?)
+}
+
+#[cfg(not(feature = "deflate"))]
+fn decompress(_data: &[u8]) -> Result<Vec<u8>, SaveError> {
+    Err(SaveError::CompressionUnsupported)
+}
+
+/// Makes sure the slot name is a file name that is valid on every platform and stays in the save directory.
+fn validate_slot(slot: &str) -> Result<(), SaveError> {
+    let valid = !slot.is_empty()
+        && slot.len() <= 200
+        && !slot.starts_with('.')
+        && !slot.ends_with([' ', '.'])
+        && !slot.chars().any(|c| {
+            c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+        })
+        && !is_reserved(slot);
+    if valid {
+        Ok(())
+    } else {
+        Err(SaveError::InvalidSlot(slot.to_string()))
+    }
+}
+
+/// Returns true for the names of devices on Windows, which can not be used as file names even with an extension.
+fn is_reserved(slot: &str) -> bool {
+    let stem = slot.split('.').next().unwrap_or_default().trim_end();
+    let numbered = |prefix: &str| {
+        stem.len() == 4
+            && stem.is_char_boundary(3)
+            && stem[..3].eq_ignore_ascii_case(prefix)
+            && matches!(stem.as_bytes()[3], b'1'..=b'9')
+    };
+    ["CON", "PRN", "AUX", "NUL"]
+        .iter()
+        .any(|name| stem.eq_ignore_ascii_case(name))
+        || numbered("COM")
+        || numbered("LPT")
+}
+
+/// Errors of the save game system.
+#[derive(Debug, Error)]
+pub enum SaveError {
+    /// The data directory of the platform could not be found.
+    #[error("Could not find the data directory of this platform.")]
+    NoSaveDirectory,
+    /// The slot name is empty, too long, reserved on Windows or contains characters that are not allowed in file names.
+    #[error("{0:?} is not a valid slot name.")]
+    InvalidSlot(String),
+    /// The file is not a save file.
+    #[error("The file is not a save file.")]
+    InvalidFile,
+    /// The checksum of the save file does not match its content.
+    #[error("The save file is corrupted.")]
+    Corrupted,
+    /// The save file was written with a version that can not be read.
+    #[error("Save version {0} is not supported.")]
+    UnsupportedVersion(u32),
+    /// The save file is compressed, but the `deflate` feature is not enabled.
+    #[error("Compressed save files require the `deflate` feature.")]
+    CompressionUnsupported,
+    #[error("An IO error has occured: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to serialize or deserialize the save data: {0}")]
+    Serialization(#[from] bincode::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Progress {
+        level: u32,
+        name: String,
+    }
+
+    impl SaveData for Progress {}
+
+    #[test]
+    fn slots_stay_in_the_directory() {
+        for slot in [
+            "slot1",
+            "Autosave 3",
+            "über-save",
+            "console",
+            "COM10",
+            "lpt0",
+            "auxiliary",
+        ] {
+            assert!(validate_slot(slot).is_ok(), "{slot}");
+        }
+        for slot in [
+            "",
+            ".",
+            "..",
+            "../escape",
+            "a/b",
+            "a\\b",
+            "C:",
+            ".hidden",
+            "trailing.",
+            "nul\0",
+            "CON",
+            "con.sav",
+            "Prn",
+            "aux .txt",
+            "NUL",
+            "com1",
+            "COM9.save",
+            "LPT1",
+            "lpt9",
+        ] {
+            assert!(validate_slot(slot).is_err(), "{slot}");
+        }
+        let saves = Saves::at("saves");
+        assert!(saves.path("../escape").is_err());
+        assert_eq!(
+            saves.path("slot1").unwrap(),
+            Path::new("saves").join("slot1.sav")
+        );
+    }
+
+    #[test]
+    fn round_trip_and_corruption() {
+        let directory =
+            std::env::temp_dir().join(format!("let-engine-saves-{}", std::process::id()));
+        let saves = Saves::at(&directory);
+        let progress = Progress {
+            level: 7,
+            name: String::from("progress"),
+        };
+        saves.save("slot", &progress).unwrap();
+        assert_eq!(saves.load::<Progress>("slot").unwrap(), progress);
+        assert_eq!(saves.slots().unwrap(), ["slot"]);
+
+        let path = saves.path("slot").unwrap();
+        let mut file = fs::read(&path).unwrap();
+        *file.last_mut().unwrap() ^= 1;
+        fs::write(&path, file).unwrap();
+        assert!(matches!(
+            saves.load::<Progress>("slot"),
+            Err(SaveError::Corrupted)
+        ));
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
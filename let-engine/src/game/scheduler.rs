@@ -0,0 +1,315 @@
+//! Timers and async tasks running on game time.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use let_engine_core::objects::scenes::Layer;
+use parking_lot::Mutex;
+use smol::{Executor, Task};
+
+use crate::TIME;
+
+type Callback = Box<dyn FnMut() + Send + 'static>;
+
+/// Schedules timers and async tasks that run on game time.
+///
+/// Game time advances with the time scale of the engine, so timers stop while
+/// the time scale is 0 and run faster or slower when it changes.
+///
+/// The scheduler gets updated by the tick system after every tick and by the frame loop after every frame.
+/// Timer callbacks and tasks run on the thread doing the update.
+pub struct Scheduler {
+    clock: Mutex<Clock>,
+    timers: Mutex<Vec<Timer>>,
+    sleepers: Mutex<Vec<(f64, Waker)>>,
+    executor: Executor<'static>,
+}
+
+/// The game time in seconds and the moment it was last advanced.
+#[derive(Default)]
+struct Clock {
+    time: f64,
+    last_update: Option<Instant>,
+}
+
+struct Timer {
+    /// The time the timer is due at, in the time of its layer in case it has one.
+    due: f64,
+    interval: Option<f64>,
+    callback: Callback,
+    cancelled: Arc<AtomicBool>,
+    layer: Option<LayerTime>,
+}
+
+/// The time passed on a layer since a timer bound to it got spawned.
+struct LayerTime {
+    layer: Weak<Layer>,
+    time: f64,
+}
+
+impl Timer {
+    /// Returns the current time the due time of this timer is compared against.
+    fn now(&self, game_time: f64) -> f64 {
+        self.layer
+            .as_ref()
+            .map_or(game_time, |layer_time| layer_time.time)
+    }
+}
+
+impl Scheduler {
+    pub(crate) fn new() -> Self {
+        Self {
+            clock: Mutex::new(Clock::default()),
+            timers: Mutex::new(vec![]),
+            sleepers: Mutex::new(vec![]),
+            executor: Executor::new(),
+        }
+    }
+
+    /// Returns the game time in seconds that passed since the start of the scheduler, influenced by the time scale.
+    pub fn time(&self) -> f64 {
+        self.clock.lock().time
+    }
+
+    /// Runs the given callback once after the given duration of game time.
+    pub fn spawn_timer(
+        &self,
+        duration: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        self.insert_timer(duration, None, None, Box::new(callback))
+    }
+
+    /// Runs the given callback every time the given interval of game time passes.
+    pub fn spawn_repeating(
+        &self,
+        interval: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        self.insert_timer(interval, Some(interval), None, Box::new(callback))
+    }
+
+    /// Runs the given callback once after the given duration of time passed on the layer.
+    ///
+    /// The timer runs on the time scale of the layer on top of the one of the game, halts while the layer is paused
+    /// and gets cancelled once the layer gets dropped.
+    pub fn spawn_layer_timer(
+        &self,
+        layer: &Arc<Layer>,
+        duration: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        self.insert_timer(duration, None, Some(layer), Box::new(callback))
+    }
+
+    /// Runs the given callback every time the given interval of time passes on the layer.
+    pub fn spawn_layer_repeating(
+        &self,
+        layer: &Arc<Layer>,
+        interval: Duration,
+        callback: impl FnMut() + Send + 'static,
+    ) -> TimerHandle {
+        self.insert_timer(interval, Some(interval), Some(layer), Box::new(callback))
+    }
+
+    fn insert_timer(
+        &self,
+        duration: Duration,
+        interval: Option<Duration>,
+        layer: Option<&Arc<Layer>>,
+        callback: Callback,
+    ) -> TimerHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let layer = layer.map(|layer| LayerTime {
+            layer: Arc::downgrade(layer),
+            time: 0.0,
+        });
+        let start = if layer.is_some() { 0.0 } else { self.time() };
+        self.timers.lock().push(Timer {
+            due: start + duration.as_secs_f64(),
+            interval: interval.map(|interval| interval.as_secs_f64()),
+            callback,
+            cancelled: cancelled.clone(),
+            layer,
+        });
+        TimerHandle { cancelled }
+    }
+
+    /// Spawns an async task that gets polled on the engine loop.
+    ///
+    /// Use [`sleep`](Self::sleep) inside the task to wait on game time.
+    /// Dropping the returned handle cancels the task, unless it gets detached.
+    pub fn spawn<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+    ) -> TaskHandle<T> {
+        TaskHandle {
+            task: self.executor.spawn(future),
+        }
+    }
+
+    /// Returns a future that finishes after the given duration of game time.
+    pub fn sleep(&self, duration: Duration) -> Sleep {
+        Sleep {
+            due: self.time() + duration.as_secs_f64(),
+        }
+    }
+
+    /// Advances the game time by the time passed since the last update, runs all due timers and polls the spawned tasks.
+    pub(crate) fn update(&self) {
+        self.advance(None);
+    }
+
+    /// Advances the game time by exactly the given seconds, runs all due timers and polls the spawned tasks.
+    ///
+    /// Used by the deterministic tick system, where the game time may not depend on the measured time.
+    pub(crate) fn step(&self, seconds: f64) {
+        self.advance(Some(seconds));
+    }
+
+    fn advance(&self, step: Option<f64>) {
+        let (previous, time) = {
+            let mut clock = self.clock.lock();
+            let previous = clock.time;
+            let now = Instant::now();
+            let last_update = clock.last_update.replace(now);
+            match (step, last_update) {
+                (Some(step), _) => clock.time += step,
+                (None, Some(last_update)) => {
+                    clock.time += now.duration_since(last_update).as_secs_f64() * TIME.scale();
+                }
+                (None, None) => (),
+            }
+            (previous, clock.time)
+        };
+
+        // Take the due timers out first, so callbacks are able to spawn new timers.
+        let due: Vec<Timer> = {
+            let mut timers = self.timers.lock();
+            timers.retain_mut(|timer| {
+                if timer.cancelled.load(Ordering::Acquire) {
+                    return false;
+                }
+                let Some(layer_time) = &mut timer.layer else {
+                    return true;
+                };
+                let Some(layer) = layer_time.layer.upgrade() else {
+                    timer.cancelled.store(true, Ordering::Release);
+                    return false;
+                };
+                layer_time.time += (time - previous) * layer.effective_time_scale();
+                true
+            });
+            let (due, pending) = std::mem::take(&mut *timers)
+                .into_iter()
+                .partition(|timer| timer.due <= timer.now(time));
+            *timers = pending;
+            due
+        };
+        for mut timer in due {
+            (timer.callback)();
+            if let Some(interval) = timer.interval {
+                if !timer.cancelled.load(Ordering::Acquire) {
+                    timer.due += interval.max(f64::EPSILON);
+                    self.timers.lock().push(timer);
+                }
+            } else {
+                timer.cancelled.store(true, Ordering::Release);
+            }
+        }
+
+        // Wake the tasks waiting on game time.
+        self.sleepers.lock().retain(|(due, waker)| {
+            if *due <= time {
+                waker.wake_by_ref();
+                false
+            } else {
+                true
+            }
+        });
+
+        while self.executor.try_tick() {}
+    }
+}
+
+/// A handle to a timer of the scheduler.
+///
+/// Dropping this handle does not cancel the timer.
+#[derive(Clone, Debug)]
+pub struct TimerHandle {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TimerHandle {
+    /// Stops the timer from running again.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns true if the timer got cancelled or a non repeating timer has already run.
+    pub fn is_finished(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+/// A handle to an async task of the scheduler.
+///
+/// Awaiting this handle returns the output of the task. Dropping it cancels the task.
+pub struct TaskHandle<T> {
+    task: Task<T>,
+}
+
+impl<T> TaskHandle<T> {
+    /// Cancels the task.
+    pub fn cancel(self) {
+        drop(self);
+    }
+
+    /// Lets the task run to completion without a handle.
+    pub fn detach(self) {
+        self.task.detach();
+    }
+
+    /// Returns true if the task has finished.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<T> Future for TaskHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        Pin::new(&mut self.task).poll(cx)
+    }
+}
+
+/// A future that finishes once the scheduler reached the given game time.
+#[derive(Debug)]
+pub struct Sleep {
+    due: f64,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if crate::SCHEDULER.time() >= self.due {
+            Poll::Ready(())
+        } else {
+            crate::SCHEDULER
+                .sleepers
+                .lock()
+                .push((self.due, cx.waker().clone()));
+            Poll::Pending
+        }
+    }
+}
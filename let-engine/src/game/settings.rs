@@ -6,11 +6,17 @@ use std::sync::Arc;
 use derive_builder::Builder;
 
 #[cfg(feature = "client")]
-use let_engine_core::draw::{Graphics, PresentMode};
+use let_engine_core::draw::{FramePacing, Graphics, PresentMode};
 // audio feature
 #[cfg(feature = "audio")]
 #[cfg(feature = "client")]
-use let_engine_audio::Audio;
+use let_engine_audio::{Audio, AudioSettings};
+#[cfg(feature = "client")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "client")]
+use std::path::PathBuf;
+#[cfg(feature = "client")]
+use thiserror::Error;
 
 #[cfg(feature = "networking")]
 use crate::networking::Networking;
@@ -29,9 +35,32 @@ pub struct EngineSettings {
     #[builder(setter(into, strip_option), default)]
     #[cfg(feature = "client")]
     pub window_settings: WindowBuilder,
+    /// The name of the game to load the settings stored with [`Settings::save`] from at startup.
+    ///
+    /// The stored settings get applied before the window is created. Missing or invalid files are ignored.
+    #[builder(setter(into, strip_option), default)]
+    #[cfg(feature = "client")]
+    pub stored_settings: Option<String>,
     /// The initial settings of the tick system.
     #[builder(setter(into), default)]
     pub tick_settings: TickSettings,
+    /// The directory to load mods from at startup, relative to the binary.
+    ///
+    /// The outcome is available using [`mod_report`](asset_system::mods::mod_report) and gets sent as
+    /// [`Event::ModsLoaded`](crate::events::Event::ModsLoaded) before the game starts.
+    #[builder(setter(into, strip_option), default)]
+    #[cfg(feature = "asset_system")]
+    pub mods_directory: Option<std::path::PathBuf>,
+    /// A splash screen shown while the start function of the game runs.
+    ///
+    /// Without one the window only appears after the start function finished.
+    #[builder(setter(into, strip_option), default)]
+    #[cfg(feature = "client")]
+    pub splash_screen: Option<crate::SplashScreen>,
+    /// The activity the game runs in, required on Android.
+    #[builder(setter(strip_option), default)]
+    #[cfg(all(feature = "android", target_os = "android"))]
+    pub android_app: Option<crate::android::AndroidApp>,
 }
 
 /// General in game settings built into the game engine.
@@ -71,6 +100,87 @@ impl Settings {
         }
     }
 
+    /// Writes the current graphics, audio and window settings to the settings file of the given game.
+    ///
+    /// The file is located at [`StoredSettings::path`].
+    #[cfg(feature = "client")]
+    pub fn save(&self, game: &str) -> Result<(), SettingsError> {
+        let graphics = &self.graphics;
+        let present_mode = graphics
+            .preferred_present_mode()
+            .filter(|_| graphics.get_supported_present_modes().is_empty())
+            .unwrap_or_else(|| graphics.present_mode());
+        let framerate_limit = graphics.framerate_limit().as_secs_f64();
+        let adaptive_frame_pacing = graphics.frame_pacing() == FramePacing::Adaptive;
+
+        let window = let_engine_core::window::window().map(|window| StoredWindow {
+            size: window.inner_size().into(),
+            fullscreen: window.fullscreen().is_some(),
+            maximized: window.maximized(),
+        });
+
+        let stored = StoredSettings {
+            present_mode: Some(present_mode_name(present_mode).to_string()),
+            framerate_limit: Some(framerate_limit),
+            adaptive_frame_pacing: Some(adaptive_frame_pacing),
+            #[cfg(feature = "audio")]
+            audio: Some(self.audio.get().into()),
+            window,
+        };
+
+        let path = StoredSettings::path(game).ok_or(SettingsError::NoConfigDirectory)?;
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let temporary = path.with_extension("toml.tmp");
+        std::fs::write(&temporary, toml::to_string_pretty(&stored)?)?;
+        std::fs::rename(temporary, path)?;
+        Ok(())
+    }
+
+    /// Reads the settings file of the given game and applies its values.
+    ///
+    /// Window settings get applied to the window in case it exists already.
+    /// Use [`EngineSettings::stored_settings`] to apply them before the window gets created.
+    #[cfg(feature = "client")]
+    pub fn load(&self, game: &str) -> Result<StoredSettings, SettingsError> {
+        let path = StoredSettings::path(game).ok_or(SettingsError::NoConfigDirectory)?;
+        let stored: StoredSettings = toml::from_str(&std::fs::read_to_string(path)?)?;
+
+        if let Some(mode) = stored.present_mode.as_deref().and_then(present_mode_from_name) {
+            if self.graphics.get_supported_present_modes().is_empty() {
+                self.graphics.set_preferred_present_mode(Some(mode));
+            } else {
+                let _ = self.graphics.set_present_mode(mode);
+            }
+        }
+        if let Some(limit) = stored.framerate_limit {
+            self.graphics
+                .set_framerate_limit(std::time::Duration::from_secs_f64(limit.max(0.0)));
+        }
+        if stored.adaptive_frame_pacing == Some(true) {
+            self.graphics.set_frame_pacing(FramePacing::Adaptive);
+        }
+        #[cfg(feature = "audio")]
+        if let Some(audio) = stored.audio {
+            // The settings still get stored in case the audio server is not running yet.
+            let _ = self.audio.set(audio.into());
+        }
+        if let (Some(stored_window), Some(window)) =
+            (stored.window, let_engine_core::window::window())
+        {
+            window.set_inner_size(stored_window.size.into());
+            window.set_maximized(stored_window.maximized);
+            window.set_fullscreen(
+                stored_window
+                    .fullscreen
+                    .then_some(let_engine_core::window::Fullscreen::Borderless(None)),
+            );
+        }
+
+        Ok(stored)
+    }
+
     /// Cleans all caches on both ram and vram for unused data. This decreases memory usage and may not
     /// hurt to be called between levels from time to time.
     #[cfg(feature = "client")]
@@ -99,6 +209,11 @@ impl Settings {
 pub struct TickSystem {
     pub(super) tick_settings: Mutex<TickSettings>,
     pub(super) tick_pause_lock: (Mutex<bool>, Condvar),
+    /// The time of the latest window event or drawn frame.
+    #[cfg(feature = "client")]
+    last_activity: (Mutex<std::time::Instant>, Condvar),
+    /// Set once the event loop exits, so the tick system stops waiting.
+    exiting: std::sync::atomic::AtomicBool,
 }
 
 impl TickSystem {
@@ -106,6 +221,9 @@ impl TickSystem {
         Self {
             tick_settings: Mutex::new(TickSettings::default()),
             tick_pause_lock: (Mutex::new(false), Condvar::new()),
+            #[cfg(feature = "client")]
+            last_activity: (Mutex::new(std::time::Instant::now()), Condvar::new()),
+            exiting: std::sync::atomic::AtomicBool::new(false),
         }
     }
     /// Returns the engine wide tick settings.
@@ -118,4 +236,192 @@ impl TickSystem {
         *self.tick_settings.lock() = settings;
         self.tick_pause_lock.1.notify_all();
     }
+
+    /// Records a window event or a frame that was not skipped by damage tracking,
+    /// waking up the tick system in case it waits for the window to become active.
+    #[cfg(feature = "client")]
+    pub(crate) fn mark_active(&self) {
+        *self.last_activity.0.lock() = std::time::Instant::now();
+        self.last_activity.1.notify_all();
+    }
+
+    /// Blocks while the control flow waits for events and nothing happened for the given time.
+    #[cfg(feature = "client")]
+    pub(crate) fn wait_while_idle(&self, timeout: std::time::Duration) {
+        let mut last_activity = self.last_activity.0.lock();
+        while !self.is_exiting()
+            && crate::SETTINGS.graphics.control_flow() != let_engine_core::draw::ControlFlow::Poll
+            && last_activity.elapsed() >= timeout
+        {
+            self.last_activity.1.wait(&mut last_activity);
+        }
+    }
+
+    /// Blocks while the tick system is paused.
+    pub(crate) fn wait_while_paused(&self) {
+        let mut paused = self.tick_pause_lock.0.lock();
+        while *paused && !self.is_exiting() {
+            self.tick_pause_lock.1.wait(&mut paused);
+        }
+    }
+
+    /// Stops the tick system, waking it up in case it is paused or waits for the window.
+    pub(crate) fn shut_down(&self) {
+        self.exiting
+            .store(true, std::sync::atomic::Ordering::Release);
+        // Taking the locks makes sure the tick system either sees the flag or already waits for the notification.
+        drop(self.tick_pause_lock.0.lock());
+        self.tick_pause_lock.1.notify_all();
+        #[cfg(feature = "client")]
+        {
+            drop(self.last_activity.0.lock());
+            self.last_activity.1.notify_all();
+        }
+    }
+
+    /// Returns true once the engine shuts down.
+    pub(crate) fn is_exiting(&self) -> bool {
+        self.exiting.load(std::sync::atomic::Ordering::Acquire)
+    }
+}
+
+/// The settings persisted by [`Settings::save`] in a TOML file.
+///
+/// Every value is optional, so partially written files by hand still load.
+#[cfg(feature = "client")]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StoredSettings {
+    /// The present mode, either `immediate`, `mailbox` or `fifo`.
+    pub present_mode: Option<String>,
+    /// The waiting time between frames in seconds. 0 turns off the limit.
+    pub framerate_limit: Option<f64>,
+    /// Whether frames get paced to the refresh rate of the monitor, overriding the framerate limit.
+    pub adaptive_frame_pacing: Option<bool>,
+    /// The capacities of the audio server.
+    #[cfg(feature = "audio")]
+    pub audio: Option<StoredAudio>,
+    /// The state of the window.
+    pub window: Option<StoredWindow>,
+}
+
+#[cfg(feature = "client")]
+impl StoredSettings {
+    /// Returns the path of the settings file of the given game in the config directory of the platform.
+    ///
+    /// - Linux: `$XDG_CONFIG_HOME/<game>/settings.toml` or `~/.config/<game>/settings.toml`
+    /// - Windows: `%APPDATA%\<game>\settings.toml`
+    /// - macOS: `~/Library/Application Support/<game>/settings.toml`
+    /// - Android: `<internal data path>/<game>/settings.toml`
+    pub fn path(game: &str) -> Option<PathBuf> {
+        Some(
+            super::savegame::config_directory()?
+                .join(game)
+                .join("settings.toml"),
+        )
+    }
+
+    /// Applies the stored window state to the given window builder.
+    pub fn apply_to(&self, builder: WindowBuilder) -> WindowBuilder {
+        let Some(window) = self.window else {
+            return builder;
+        };
+        builder
+            .inner_size(window.size.into())
+            .maximized(window.maximized)
+            .fullscreen(
+                window
+                    .fullscreen
+                    .then_some(let_engine_core::window::Fullscreen::Borderless(None)),
+            )
+    }
+}
+
+/// The stored window state.
+#[cfg(feature = "client")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StoredWindow {
+    /// The inner size of the window in pixels.
+    pub size: [f32; 2],
+    /// Borderless fullscreen on the current monitor.
+    pub fullscreen: bool,
+    pub maximized: bool,
+}
+
+/// The stored capacities and output device of the audio server.
+#[cfg(feature = "audio")]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredAudio {
+    pub sound_capacity: u16,
+    pub object_bound_sound_capacity: u16,
+    pub spatial_scene_capacity: u16,
+    #[serde(default = "default_listener_capacity")]
+    pub listener_capacity: u16,
+    /// The name of the selected output device.
+    #[serde(default)]
+    pub output_device: Option<String>,
+}
+
+#[cfg(feature = "audio")]
+impl From<AudioSettings> for StoredAudio {
+    fn from(value: AudioSettings) -> Self {
+        Self {
+            sound_capacity: value.sound_capacity,
+            object_bound_sound_capacity: value.object_bound_sound_capacity,
+            spatial_scene_capacity: value.spatial_scene_capacity,
+            listener_capacity: value.listener_capacity,
+            output_device: value.output_device,
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+impl From<StoredAudio> for AudioSettings {
+    fn from(value: StoredAudio) -> Self {
+        AudioSettings::new()
+            .sound_capacity(value.sound_capacity)
+            .object_bound_sound_capacity(value.object_bound_sound_capacity)
+            .spatial_scene_capacity(value.spatial_scene_capacity)
+            .listener_capacity(value.listener_capacity)
+            .output_device(value.output_device)
+    }
+}
+
+#[cfg(feature = "audio")]
+fn default_listener_capacity() -> u16 {
+    AudioSettings::default().listener_capacity
+}
+
+#[cfg(feature = "client")]
+fn present_mode_name(mode: PresentMode) -> &'static str {
+    match mode {
+        PresentMode::Immediate => "immediate",
+        PresentMode::Mailbox => "mailbox",
+        _ => "fifo",
+    }
+}
+
+#[cfg(feature = "client")]
+fn present_mode_from_name(name: &str) -> Option<PresentMode> {
+    match name {
+        "immediate" => Some(PresentMode::Immediate),
+        "mailbox" => Some(PresentMode::Mailbox),
+        "fifo" => Some(PresentMode::Fifo),
+        _ => None,
+    }
+}
+
+/// Errors of saving and loading the settings file.
+#[cfg(feature = "client")]
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    /// The config directory of the platform could not be found.
+    #[error("Could not find the config directory of this platform.")]
+    NoConfigDirectory,
+    #[error("An IO error has occured: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("The settings file is invalid: {0}")]
+    Parse(#[from] toml::de::Error),
+    #[error("Failed to write the settings: {0}")]
+    Write(#[from] toml::ser::Error),
 }
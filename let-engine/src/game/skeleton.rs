@@ -0,0 +1,262 @@
+//! Bone based cutout animation driving a hierarchy of objects with keyframed clips.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use glam::Vec2;
+use let_engine_core::objects::{Object, Transform};
+use serde::{Deserialize, Serialize};
+
+/// The local pose of a bone at a point in time of a clip.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// The time of this keyframe in seconds from the start of the clip.
+    pub time: f32,
+    /// The position of the bone relative to its parent.
+    pub position: [f32; 2],
+    /// The rotation of the bone relative to its parent in radians.
+    pub rotation: f32,
+}
+
+/// The keyframes of a single bone.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Track {
+    /// The name of the bone this track animates.
+    pub bone: String,
+    /// Keyframes sorted by time.
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Track {
+    /// Returns the linearly interpolated position and rotation at the given time.
+    pub fn sample(&self, time: f32) -> Option<(Vec2, f32)> {
+        let first = self.keyframes.first()?;
+        let next = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.time <= time);
+        let (a, b) = match next {
+            0 => (first, first),
+            n if n >= self.keyframes.len() => {
+                let last = self.keyframes.last()?;
+                (last, last)
+            }
+            n => (&self.keyframes[n - 1], &self.keyframes[n]),
+        };
+        let span = b.time - a.time;
+        let t = if span > 0.0 {
+            ((time - a.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        Some((
+            Vec2::from(a.position).lerp(Vec2::from(b.position), t),
+            a.rotation + (b.rotation - a.rotation) * t,
+        ))
+    }
+}
+
+/// A keyframed animation of multiple bones.
+///
+/// Clips can be stored as assets using [`to_bytes`](Clip::to_bytes) and loaded with [`from_bytes`](Clip::from_bytes).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Clip {
+    pub name: String,
+    /// The length of the clip in seconds.
+    pub duration: f32,
+    /// Starts over after reaching the end if true, otherwise holds the last pose.
+    pub looping: bool,
+    pub tracks: Vec<Track>,
+}
+
+impl Clip {
+    /// Loads a clip from its binary representation.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// Returns the binary representation of this clip.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Returns the pose of the given bone at the given time of this clip.
+    pub fn sample(&self, bone: &str, time: f32) -> Option<(Vec2, f32)> {
+        self.tracks
+            .iter()
+            .find(|track| track.bone == bone)
+            .and_then(|track| track.sample(time))
+    }
+
+    /// Maps the playing time to the time inside this clip.
+    fn local_time(&self, time: f32) -> f32 {
+        if self.looping && self.duration > 0.0 {
+            time.rem_euclid(self.duration)
+        } else {
+            time.min(self.duration)
+        }
+    }
+}
+
+/// A clip being played with its own playing time.
+#[derive(Clone)]
+struct Playback {
+    clip: Arc<Clip>,
+    time: f32,
+}
+
+/// A set of named bone objects posed by animation clips.
+///
+/// The bones are regular objects, so the parent child transform system of the layer
+/// positions child bones relative to their parents.
+///
+/// # Usage
+/// ```ignore
+/// let mut skeleton = Skeleton::new()
+///     .bone("torso", &torso)
+///     .bone("arm", &arm);
+/// skeleton.play(walk_clip.clone(), 0.0);
+///
+/// // every frame
+/// skeleton.update(TIME.delta_time())?;
+/// ```
+pub struct Skeleton {
+    bones: HashMap<String, Bone>,
+    current: Option<Playback>,
+    previous: Option<Playback>,
+    blend: f32,
+    blend_duration: f32,
+    speed: f32,
+}
+
+struct Bone {
+    object: Object,
+    rest: Transform,
+}
+
+impl Default for Skeleton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skeleton {
+    /// Creates a new skeleton without bones.
+    pub fn new() -> Self {
+        Self {
+            bones: HashMap::new(),
+            current: None,
+            previous: None,
+            blend: 0.0,
+            blend_duration: 0.0,
+            speed: 1.0,
+        }
+    }
+
+    /// Adds an initialized object as bone with the given name.
+    ///
+    /// The current transform of the object is used as rest pose for clips without a track for this bone.
+    pub fn bone(mut self, name: impl Into<String>, object: &Object) -> Self {
+        self.add_bone(name, object);
+        self
+    }
+
+    /// Adds an initialized object as bone with the given name.
+    pub fn add_bone(&mut self, name: impl Into<String>, object: &Object) {
+        self.bones.insert(
+            name.into(),
+            Bone {
+                object: object.clone(),
+                rest: object.transform,
+            },
+        );
+    }
+
+    /// Returns the object of the bone with the given name.
+    pub fn get_bone(&self, name: &str) -> Option<&Object> {
+        self.bones.get(name).map(|bone| &bone.object)
+    }
+
+    /// Plays the given clip from the start, blending over from the current clip for the given seconds.
+    pub fn play(&mut self, clip: Arc<Clip>, blend_duration: f32) {
+        let next = Playback { clip, time: 0.0 };
+        self.previous = if blend_duration > 0.0 {
+            self.current.replace(next)
+        } else {
+            self.current = Some(next);
+            None
+        };
+        self.blend = 0.0;
+        self.blend_duration = blend_duration;
+    }
+
+    /// Stops playing, leaving the bones at their current pose.
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+
+    /// Returns the clip currently playing.
+    pub fn clip(&self) -> Option<&Arc<Clip>> {
+        self.current.as_ref().map(|playback| &playback.clip)
+    }
+
+    /// Returns true if a non looping clip has reached its end.
+    pub fn is_finished(&self) -> bool {
+        self.current.as_ref().map_or(true, |playback| {
+            !playback.clip.looping && playback.time >= playback.clip.duration
+        })
+    }
+
+    /// Returns the playback speed multiplier.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the playback speed multiplier.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Advances the playing clips by `delta` seconds and applies the pose to the bone objects.
+    pub fn update(&mut self, delta: f64) -> Result<()> {
+        let delta = delta as f32 * self.speed;
+        let Some(current) = &mut self.current else {
+            return Ok(());
+        };
+        current.time += delta;
+
+        let blend = if let Some(previous) = &mut self.previous {
+            previous.time += delta;
+            self.blend += delta;
+            if self.blend >= self.blend_duration {
+                self.previous = None;
+                1.0
+            } else {
+                self.blend / self.blend_duration
+            }
+        } else {
+            1.0
+        };
+
+        for (name, bone) in self.bones.iter_mut() {
+            let pose = |playback: &Playback| {
+                let clip = &playback.clip;
+                clip.sample(name, clip.local_time(playback.time)).map_or(
+                    bone.rest,
+                    |(position, rotation)| Transform {
+                        position,
+                        rotation,
+                        ..bone.rest
+                    },
+                )
+            };
+            let mut transform = pose(&*current);
+            if let Some(previous) = &self.previous {
+                transform = pose(previous).lerp(transform, blend);
+            }
+            bone.object.transform = transform;
+            bone.object.sync()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+//! A splash screen shown while the game starts.
+
+use std::{
+    sync::{atomic::Ordering, Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use atomic_float::AtomicF32;
+use glam::vec2;
+use let_engine_core::{
+    camera::{CameraScaling, CameraSettings},
+    objects::{scenes::Layer, scenes::SCENE, Appearance, Color, NewObject, Object, Transform},
+    resources::{materials::Material, textures::Texture, Model},
+};
+
+/// The progress of the startup shown by the progress bar of the splash screen.
+pub static LOADING: LazyLock<LoadingProgress> = LazyLock::new(LoadingProgress::new);
+
+/// The progress of loading the game, from 0 to 1.
+///
+/// Gets set by the game in [`Game::start`](crate::Game::start) while preloading assets.
+pub struct LoadingProgress {
+    progress: AtomicF32,
+}
+
+impl LoadingProgress {
+    fn new() -> Self {
+        Self {
+            progress: AtomicF32::new(0.0),
+        }
+    }
+
+    /// Sets the progress, clamped between 0 and 1.
+    pub fn set(&self, progress: f32) {
+        self.progress
+            .store(progress.clamp(0.0, 1.0), Ordering::Relaxed);
+    }
+
+    /// Sets the progress to the share of the given loaded items.
+    pub fn set_loaded(&self, loaded: usize, total: usize) {
+        self.set(if total == 0 {
+            1.0
+        } else {
+            loaded as f32 / total as f32
+        });
+    }
+
+    /// Returns the progress from 0 to 1.
+    pub fn get(&self) -> f32 {
+        self.progress.load(Ordering::Relaxed)
+    }
+}
+
+type ProgressCallback = Arc<dyn Fn(f32) + Send + Sync>;
+
+/// A screen shown right after the window opens, while [`Game::start`](crate::Game::start) is running.
+///
+/// It shows an optional texture above a progress bar following [`LOADING`] on a plain background.
+/// The game loop, the tick system and all events other than closing the window wait until the start is done.
+///
+/// The start function gets polled once per frame, so it should wait for slow work like loading assets
+/// instead of blocking, for example using the asynchronous asset system or `smol::unblock`.
+///
+/// # Usage
+/// ```ignore
+/// let settings = EngineSettingsBuilder::default()
+///     .splash_screen(SplashScreen::new().texture(logo).background(Color::BLACK))
+///     .build()?;
+///
+/// // in the start function of the game
+/// for (index, path) in paths.iter().enumerate() {
+///     textures.push(RESOURCE_CACHE.texture(path, TextureSettings::default()).await?);
+///     LOADING.set_loaded(index + 1, paths.len());
+/// }
+/// ```
+#[derive(Clone)]
+pub struct SplashScreen {
+    /// The texture shown in the middle of the screen, fitting into a square of half the view.
+    pub texture: Option<Texture>,
+    pub background: Color,
+    pub bar_color: Color,
+    pub bar_background: Color,
+    /// The shortest time the splash screen stays, even if the game starts faster.
+    pub min_duration: Duration,
+    on_progress: Option<ProgressCallback>,
+}
+
+impl Default for SplashScreen {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            background: Color::from_rgb(0.08, 0.08, 0.1),
+            bar_color: Color::WHITE,
+            bar_background: Color::from_rgb(0.25, 0.25, 0.28),
+            min_duration: Duration::ZERO,
+            on_progress: None,
+        }
+    }
+}
+
+impl SplashScreen {
+    /// Creates a splash screen with a progress bar on a dark background.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the texture shown above the progress bar and returns self.
+    #[inline]
+    pub fn texture(mut self, texture: Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    /// Sets the background color and returns self.
+    #[inline]
+    pub fn background(mut self, background: Color) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Sets the colors of the filled and empty parts of the progress bar and returns self.
+    #[inline]
+    pub fn bar_colors(mut self, filled: Color, empty: Color) -> Self {
+        self.bar_color = filled;
+        self.bar_background = empty;
+        self
+    }
+
+    /// Sets the shortest time the splash screen stays and returns self.
+    #[inline]
+    pub fn min_duration(mut self, min_duration: Duration) -> Self {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Sets a function getting called with the progress every frame the splash screen is shown and returns self.
+    ///
+    /// Useful for driving own visuals added to the scene during the start.
+    #[inline]
+    pub fn on_progress(mut self, callback: impl Fn(f32) + Send + Sync + 'static) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+}
+
+/// The half width of the progress bar.
+const BAR_WIDTH: f32 = 0.5;
+/// The half height of the progress bar.
+const BAR_HEIGHT: f32 = 0.012;
+const BAR_Y: f32 = -0.35;
+
+/// A splash screen on its own layer above all others.
+pub(crate) struct Splash {
+    layer: Arc<Layer>,
+    bar: Object,
+    settings: SplashScreen,
+    shown: Instant,
+}
+
+impl Splash {
+    pub fn show(settings: SplashScreen) -> Result<Self> {
+        let layer = SCENE.new_layer();
+        layer.set_camera_settings(CameraSettings::default().mode(CameraScaling::Limited));
+
+        let quad = |position, size, color| -> Result<Object> {
+            let mut object = NewObject::new();
+            object.transform = Transform::default().position(position).size(size);
+            object.appearance = Appearance::new().model(Some(Model::Square))?.color(color);
+            object.init(&layer)
+        };
+        // Large enough to cover the view of any window shape.
+        quad(vec2(0.0, 0.0), vec2(100.0, 100.0), settings.background)?;
+        if let Some(texture) = &settings.texture {
+            let (width, height) = texture.dimensions();
+            let aspect = width as f32 / height.max(1) as f32;
+            let size = if aspect > 1.0 {
+                vec2(0.25, 0.25 / aspect)
+            } else {
+                vec2(0.25 * aspect, 0.25)
+            };
+            let mut logo = NewObject::new();
+            logo.transform = Transform::default().position(vec2(0.0, 0.05)).size(size);
+            logo.appearance = Appearance::new()
+                .model(Some(Model::Square))?
+                .material(Some(Material::new_default_textured(texture)?));
+            logo.init(&layer)?;
+        }
+        quad(
+            vec2(0.0, BAR_Y),
+            vec2(BAR_WIDTH, BAR_HEIGHT),
+            settings.bar_background,
+        )?;
+        let bar = quad(
+            vec2(-BAR_WIDTH, BAR_Y),
+            vec2(0.0, BAR_HEIGHT),
+            settings.bar_color,
+        )?;
+
+        Ok(Self {
+            layer,
+            bar,
+            settings,
+            shown: Instant::now(),
+        })
+    }
+
+    /// Fills the progress bar and keeps the layer above the ones the game creates while starting.
+    pub fn update(&mut self) -> Result<()> {
+        let progress = LOADING.get();
+        self.bar.transform.position = vec2(BAR_WIDTH * (progress - 1.0), BAR_Y);
+        self.bar.transform.size = vec2(BAR_WIDTH * progress, BAR_HEIGHT);
+        self.bar.sync()?;
+        SCENE.move_layer_to_top(&self.layer)?;
+        if let Some(callback) = &self.settings.on_progress {
+            callback(progress);
+        }
+        Ok(())
+    }
+
+    /// Returns true once the minimum duration has passed.
+    pub fn can_close(&self) -> bool {
+        self.shown.elapsed() >= self.settings.min_duration
+    }
+
+    /// Removes the splash screen from the scene.
+    pub fn close(self) {
+        let _ = SCENE.remove_layer(&self.layer);
+    }
+}
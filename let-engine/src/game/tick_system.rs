@@ -8,7 +8,7 @@ use crossbeam::atomic::AtomicCell;
 use derive_builder::Builder;
 use smol::{lock::Mutex, Timer};
 
-use crate::{Game, SETTINGS, TIME};
+use crate::{Game, ProfileSection, PROFILER, SETTINGS, TIME};
 
 #[cfg(not(feature = "networking"))]
 pub(crate) struct TickSystem<G: Game + Send + 'static, #[cfg(feature = "networking")] Msg> {
@@ -54,30 +54,79 @@ impl_ticksys! {
         /// Runs the games `tick` function after every iteration.
         pub async fn run(&mut self, game: Arc<Mutex<G>>) {
             let mut index: usize = 0;
+            // How far the tick system is behind schedule.
+            let mut lag = Duration::ZERO;
             let stop = self.stop.clone();
             let game = game.clone();
             loop {
                 // wait if paused
-                SETTINGS
-                    .tick_system
-                    .tick_pause_lock
-                    .1
-                    .wait_while(&mut SETTINGS.tick_system.tick_pause_lock.0.lock(), |x| *x);
+                SETTINGS.tick_system.wait_while_paused();
+                // wait while the window is idle
+                #[cfg(feature = "client")]
+                {
+                    let idle_timeout = SETTINGS.tick_system.tick_settings.lock().idle_timeout;
+                    if let Some(timeout) = idle_timeout {
+                        SETTINGS.tick_system.wait_while_idle(timeout);
+                    }
+                }
+                if SETTINGS.tick_system.is_exiting() {
+                    break;
+                }
                 let settings = SETTINGS.tick_system.get();
                 // capture tick start time.
                 let start_time = SystemTime::now();
+                #[cfg(feature = "physics")]
+                let threaded_physics =
+                    settings.update_physics && settings.threaded_physics && !settings.deterministic;
+                // Apply the physics step that ran on the worker threads since the previous tick.
+                #[cfg(feature = "physics")]
+                if threaded_physics {
+                    PROFILER.measure(ProfileSection::Physics, || {
+                        let_engine_core::objects::scenes::SCENE.sync_physics();
+                        let_engine_core::objects::scenes::SCENE.finish_tick();
+                    });
+                }
                 // Run the logic
-                game.lock().await.tick().await;
+                PROFILER
+                    .measure_async(ProfileSection::Tick, async {
+                        game.lock().await.tick().await;
+                        // Run the timers and tasks of the scheduler and advance the animations.
+                        if settings.deterministic {
+                            crate::SCHEDULER.step(settings.game_time_per_tick());
+                        } else {
+                            crate::SCHEDULER.update();
+                        }
+                        crate::ANIMATIONS.update();
+                        crate::SCENE_QUEUE.apply();
+                    })
+                    .await;
 
-                // update the physics in case they are active in the tick settings.
-                #[cfg(feature = "physics")]
-                if let_engine_core::objects::scenes::SCENE
-                    .update(settings.update_physics)
-                    .is_err()
-                {
-                    // Disable physics updating if it fails. Return running this tick system.
-                    SETTINGS.tick_system.tick_settings.lock().update_physics = false;
-                };
+                PROFILER.measure(ProfileSection::Physics, || {
+                    #[cfg(feature = "physics")]
+                    if threaded_physics {
+                        let_engine_core::objects::scenes::SCENE.update_threaded();
+                        return;
+                    }
+                    // update the physics in case they are active in the tick settings.
+                    #[cfg(feature = "physics")]
+                    if let_engine_core::objects::scenes::SCENE
+                        .update(settings.update_physics)
+                        .is_err()
+                    {
+                        // Disable physics updating if it fails. Return running this tick system.
+                        SETTINGS.tick_system.tick_settings.lock().update_physics = false;
+                    };
+                    // store the tick transforms for interpolated layers.
+                    let_engine_core::objects::scenes::SCENE.finish_tick();
+                });
+                // Without a window there are no frames, so every tick finishes one.
+                #[cfg(not(feature = "client"))]
+                PROFILER.finish_frame();
+                // Waiting event loops draw a frame after every tick, which damage tracking skips if nothing changed.
+                #[cfg(feature = "client")]
+                if SETTINGS.graphics.control_flow() != let_engine_core::draw::ControlFlow::Poll {
+                    super::wake::redraw_after(Duration::ZERO);
+                }
                 // record the elapsed time.
                 let elapsed_time = start_time.elapsed().unwrap_or_default();
 
@@ -96,9 +145,35 @@ impl_ticksys! {
 
                 // calculate waiting time
                 // ((1.0 / time_scale) * tick_wait) - elapsed_time
+                let mut skipped_ticks = 0;
                 let waiting_time = if let TimeStep::Variable = settings.timestep_mode {
+                    // Remember the time this tick took longer than it should have.
+                    lag += elapsed_time.saturating_sub(tick_wait);
+
+                    // Handle falling behind further than the catch up limit allows.
+                    let max_lag = tick_wait * settings.max_catch_up_ticks;
+                    if lag > max_lag {
+                        match settings.fall_behind {
+                            // Every peer of a lockstep game has to run every tick.
+                            FallBehind::Drop if !settings.deterministic => {
+                                skipped_ticks = lag.as_nanos() / tick_wait.as_nanos().max(1);
+                                lag = Duration::ZERO;
+                            }
+                            FallBehind::Drop | FallBehind::SlowDown => lag = max_lag,
+                            FallBehind::Panic => panic!(
+                                "The tick system fell behind by {lag:?}, which is more than {} ticks.",
+                                settings.max_catch_up_ticks
+                            ),
+                        }
+                    }
+
                     // Subtract the tick logic execution time from the waiting time to make the waiting time between ticks more consistent.
-                    tick_wait.saturating_sub(elapsed_time)
+                    let waiting_time = tick_wait.saturating_sub(elapsed_time);
+
+                    // Catch up by waiting less.
+                    let catch_up = waiting_time.min(lag);
+                    lag -= catch_up;
+                    waiting_time - catch_up
                 } else {
                     tick_wait
                 };
@@ -112,10 +187,11 @@ impl_ticksys! {
                         duration: elapsed_time,
                         waiting_time,
                         index,
+                        skipped_ticks: skipped_ticks as usize,
                     });
                 }
                 index += 1;
-                if stop.load(std::sync::atomic::Ordering::Acquire) {
+                if stop.load(std::sync::atomic::Ordering::Acquire) || SETTINGS.tick_system.is_exiting() {
                     break;
                 }
             }
@@ -166,6 +242,22 @@ pub struct TickSettings {
     #[builder(default = "true")]
     #[cfg(feature = "physics")]
     pub update_physics: bool,
+    /// Steps the physics of all layers at the same time on worker threads, overlapping with the waiting time
+    /// between ticks and the rendering.
+    ///
+    /// The outcome of a step gets applied to the objects right before the next tick,
+    /// so the transforms of physics objects seen while drawing frames are the ones from before the latest step.
+    /// [`SCENE.sync_physics`](let_engine_core::objects::scenes::Scene::sync_physics) waits for the step to finish
+    /// and applies it early.
+    ///
+    /// Ignored in deterministic mode.
+    ///
+    /// ## Default configuration:
+    ///
+    /// `false`
+    #[builder(default)]
+    #[cfg(feature = "physics")]
+    pub threaded_physics: bool,
     /// If there is some reporter it will report about the most recent tick to the given reporter.
     ///
     /// ## Default configuration:
@@ -187,6 +279,52 @@ pub struct TickSettings {
     /// `true`
     #[builder(default = "true")]
     pub time_scale_influence: bool,
+    /// The maximum amount of ticks the tick system runs back to back without waiting,
+    /// trying to catch up after ticks took longer than the tick wait.
+    ///
+    /// Only used with `TimeStep::Variable`.
+    ///
+    /// ## Default configuration:
+    ///
+    /// `5`
+    #[builder(default = "5")]
+    pub max_catch_up_ticks: u32,
+    /// What to do when the tick system falls behind further than `max_catch_up_ticks` allows.
+    ///
+    /// ## Default configuration:
+    ///
+    /// `FallBehind::SlowDown`
+    #[builder(default)]
+    pub fall_behind: FallBehind,
+    /// Makes the outcome of every tick only depend on the state before it and the input of the game,
+    /// so every peer of a lockstep networked game simulates the same.
+    ///
+    /// - Ticks always run in this order: [`Game::tick`](crate::Game::tick), the due timers of the [scheduler](crate::SCHEDULER)
+    ///   in the order they got spawned, the [animations](crate::ANIMATIONS), then the physics of every layer in the order of the layers.
+    /// - The game time advances by exactly one tick per tick instead of the measured time,
+    ///   and timers and animations do not advance between ticks in the frame loop.
+    /// - `FallBehind::Drop` slows down instead of skipping ticks.
+    ///
+    /// For the physics to match across platforms as well, enable the `determinism` feature.
+    /// Random numbers should come from [RANDOM](crate::RANDOM) seeded with the same seed on every peer.
+    ///
+    /// ## Default configuration:
+    ///
+    /// `false`
+    #[builder(default)]
+    pub deterministic: bool,
+    /// Suspends the tick system once no window event arrived and no frame got drawn for this long,
+    /// until the next one does. Frames skipped by damage tracking do not count.
+    ///
+    /// Only has an effect while the [control flow](let_engine_core::draw::Graphics::set_control_flow) waits for events,
+    /// so applications idling in the background do not run their logic either.
+    ///
+    /// ## Default configuration:
+    ///
+    /// `None`
+    #[builder(setter(strip_option), default)]
+    #[cfg(feature = "client")]
+    pub idle_timeout: Option<Duration>,
 }
 
 impl Default for TickSettings {
@@ -195,10 +333,17 @@ impl Default for TickSettings {
             tick_wait: Duration::from_secs_f64(1.0 / 62.0),
             #[cfg(feature = "physics")]
             update_physics: true,
+            #[cfg(feature = "physics")]
+            threaded_physics: false,
             timestep_mode: TimeStep::default(),
             reporter: None,
             paused: false,
             time_scale_influence: true,
+            max_catch_up_ticks: 5,
+            fall_behind: FallBehind::default(),
+            deterministic: false,
+            #[cfg(feature = "client")]
+            idle_timeout: None,
         }
     }
 }
@@ -206,6 +351,17 @@ impl TickSettings {
     pub fn into_builder(self) -> TickSettingsBuilder {
         self.into()
     }
+
+    /// Returns the game time in seconds a single tick advances in deterministic mode.
+    pub(crate) fn game_time_per_tick(&self) -> f64 {
+        let tick_wait = self.tick_wait.as_secs_f64();
+        if self.time_scale_influence {
+            // The ticks themselves already slow down or speed up with the time scale.
+            tick_wait
+        } else {
+            tick_wait * TIME.scale()
+        }
+    }
 }
 impl From<TickSettings> for TickSettingsBuilder {
     fn from(value: TickSettings) -> Self {
@@ -214,9 +370,14 @@ impl From<TickSettings> for TickSettingsBuilder {
             timestep_mode: Some(value.timestep_mode),
             #[cfg(feature = "physics")]
             update_physics: Some(value.update_physics),
+            #[cfg(feature = "physics")]
+            threaded_physics: Some(value.threaded_physics),
             reporter: Some(value.reporter),
             paused: Some(value.paused),
             time_scale_influence: Some(value.time_scale_influence),
+            max_catch_up_ticks: Some(value.max_catch_up_ticks),
+            fall_behind: Some(value.fall_behind),
+            deterministic: Some(value.deterministic),
         }
     }
 }
@@ -263,6 +424,10 @@ pub struct Tick {
     pub waiting_time: Duration,
     /// The index of this tick.
     pub index: usize,
+    /// The amount of ticks that got skipped after this tick because the tick system fell too far behind.
+    ///
+    /// Only gets set when the fall behind behaviour is `FallBehind::Drop`.
+    pub skipped_ticks: usize,
 }
 
 impl Tick {
@@ -275,6 +440,9 @@ impl Tick {
     pub fn index(&self) -> usize {
         self.index
     }
+    pub fn skipped_ticks(&self) -> usize {
+        self.skipped_ticks
+    }
     /// Returns true if the tick execution time takes longer than the expected waiting time.
     ///
     /// Because if the tick execution takes longer than the target waiting time the rate decreases making the logic behind the tick system slower.
@@ -289,6 +457,7 @@ impl std::fmt::Debug for Tick {
             .field("duration", &self.duration)
             .field("waiting time", &self.waiting_time)
             .field("index", &self.index)
+            .field("skipped ticks", &self.skipped_ticks)
             .field("has slowdown", &self.has_slowdown())
             .finish()
     }
@@ -313,3 +482,19 @@ impl Default for TimeStep {
         Self::Variable
     }
 }
+
+/// The behaviour of the tick system when it falls behind schedule further than it can catch up.
+///
+/// Set to slow down by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FallBehind {
+    /// Skip the ticks that could not be caught up with and continue on schedule.
+    ///
+    /// The amount of skipped ticks gets reported in the `Tick` report.
+    Drop,
+    /// Only keep as much delay as can be caught up with, making the game run slower for as long as the ticks are too expensive.
+    #[default]
+    SlowDown,
+    /// Panic, for cases where falling behind is a bug.
+    Panic,
+}
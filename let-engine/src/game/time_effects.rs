@@ -0,0 +1,112 @@
+//! Hit stop, slow motion and screen shake effects for game feel.
+//!
+//! The hit stop and slow motion scale the time of a single layer, so a menu on another layer keeps its pace.
+//! They are stored by the layer, see [`Layer::hit_stop`] and [`Layer::slow_motion`].
+//! All effects run on real time, so they keep working while the time of the layer is slowed down by them.
+//!
+//! # Usage
+//! ```ignore
+//! use let_engine::time_effects;
+//!
+//! // Freeze the layer for a short moment and shake its camera on a heavy hit.
+//! time_effects::impact(&layer, Duration::from_millis(80), 0.05, Duration::from_millis(300));
+//! ```
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::{Duration, Instant},
+};
+
+use glam::{vec2, Vec2};
+use let_engine_core::objects::scenes::Layer;
+use parking_lot::Mutex;
+
+static SHAKES: LazyLock<Mutex<Vec<Shake>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+struct Shake {
+    layer: Arc<Layer>,
+    start: Instant,
+    intensity: f32,
+    duration: Duration,
+}
+
+/// Freezes the time of the layer for the given duration.
+///
+/// Calling this during an active hit stop extends it in case the new one lasts longer.
+pub fn hit_stop(layer: &Layer, duration: Duration) {
+    layer.hit_stop(duration);
+}
+
+/// Slows the time of the layer down to `scale` times its time scale.
+///
+/// The time scale gets eased towards the slow motion scale for `ramp_in`, stays there for `hold` and
+/// returns back to normal for `ramp_out`. Replaces the current slow motion effect of the layer.
+pub fn slow_motion(
+    layer: &Layer,
+    scale: f64,
+    ramp_in: Duration,
+    hold: Duration,
+    ramp_out: Duration,
+) {
+    layer.slow_motion(scale, ramp_in, hold, ramp_out);
+}
+
+/// Shakes the camera of the given layer with the given intensity in world units, fading out over the duration.
+pub fn screen_shake(layer: &Arc<Layer>, intensity: f32, duration: Duration) {
+    SHAKES.lock().push(Shake {
+        layer: layer.clone(),
+        start: Instant::now(),
+        intensity,
+        duration,
+    });
+}
+
+/// A hit stop followed by a screen shake of the given layer, as used for heavy impacts.
+pub fn impact(layer: &Arc<Layer>, stop: Duration, intensity: f32, shake: Duration) {
+    screen_shake(layer, intensity, stop + shake);
+    hit_stop(layer, stop);
+}
+
+/// Stops all time effects of the layer and its screen shakes.
+pub fn clear(layer: &Arc<Layer>) {
+    layer.clear_time_effects();
+    SHAKES
+        .lock()
+        .retain(|shake| !Arc::ptr_eq(&shake.layer, layer));
+    layer.set_camera_offset(Vec2::ZERO);
+}
+
+/// Applies the active screen shakes. Gets called by the engine loop every frame.
+pub(crate) fn update() {
+    let mut shakes = SHAKES.lock();
+    let now = Instant::now();
+
+    let mut offsets: Vec<(Arc<Layer>, Vec2)> = vec![];
+    shakes.retain(|shake| {
+        let elapsed = now.duration_since(shake.start);
+        let running = elapsed < shake.duration;
+        let fade = if running {
+            1.0 - elapsed.as_secs_f32() / shake.duration.as_secs_f32()
+        } else {
+            0.0
+        };
+        // Layered sine waves as a cheap smooth noise.
+        let t = elapsed.as_secs_f32();
+        let noise = vec2(
+            (t * 47.0).sin() * 0.6 + (t * 83.0 + 1.3).sin() * 0.4,
+            (t * 53.0 + 2.1).sin() * 0.6 + (t * 71.0 + 0.7).sin() * 0.4,
+        );
+        let offset = noise * shake.intensity * fade * fade;
+        match offsets
+            .iter_mut()
+            .find(|(layer, _)| Arc::ptr_eq(layer, &shake.layer))
+        {
+            Some((_, total)) => *total += offset,
+            None => offsets.push((shake.layer.clone(), offset)),
+        }
+        running
+    });
+    for (layer, offset) in offsets {
+        layer.set_camera_offset(offset);
+    }
+}
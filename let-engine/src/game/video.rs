@@ -0,0 +1,375 @@
+//! Playing videos into textures, for cutscenes and animated menu backgrounds.
+
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "audio")]
+use let_engine_audio::{PlaybackState, Sound, SoundData, SoundSettings, Tween};
+use let_engine_core::resources::textures::{Format, Texture, TextureRegion, TextureSettings};
+
+/// A decoded picture of a video.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoFrame {
+    /// The pixels in 8 bit RGBA, row by row from the top left corner.
+    pub data: Vec<u8>,
+    /// The time from the start of the video the frame gets shown at.
+    pub time: Duration,
+}
+
+/// Decodes a video frame by frame for a [`VideoPlayer`].
+///
+/// Implement it to play any container and codec, for example VP9 or AV1 using a decoder crate,
+/// or use [`ImageSequence`] for videos exported as pictures.
+pub trait VideoDecoder: Send {
+    /// Returns the width and height of the frames in pixels.
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Decodes the next frame, `None` at the end of the video.
+    fn next_frame(&mut self) -> Result<Option<VideoFrame>>;
+
+    /// Goes back to the start of the video.
+    fn rewind(&mut self) -> Result<()>;
+
+    /// Skips the frames before the given position without decoding them,
+    /// so the next frame is the last one shown at or before it.
+    ///
+    /// Does nothing by default, making the player decode and drop the frames it fell behind on.
+    fn skip_to(&mut self, position: Duration) -> Result<()> {
+        let _ = position;
+        Ok(())
+    }
+
+    /// Returns the audio track of the video, played along with it and used as the clock of the playback.
+    #[cfg(feature = "audio")]
+    fn audio(&mut self) -> Result<Option<SoundData>> {
+        Ok(None)
+    }
+}
+
+/// A video stored as a list of image files, shown at a fixed frame rate.
+pub struct ImageSequence {
+    paths: Vec<PathBuf>,
+    frame_rate: f64,
+    dimensions: (u32, u32),
+    next: usize,
+    #[cfg(feature = "audio")]
+    audio: Option<SoundData>,
+}
+
+impl ImageSequence {
+    /// Creates a sequence of the given image files in order, reading the size of the video from the first one.
+    ///
+    /// The frame rate has to be a finite number above 0.
+    pub fn new(paths: Vec<PathBuf>, frame_rate: f64) -> Result<Self> {
+        if !(frame_rate.is_finite() && frame_rate > 0.0) {
+            return Err(anyhow!(
+                "The frame rate of an image sequence has to be above 0, not {frame_rate}."
+            ));
+        }
+        let first = paths
+            .first()
+            .ok_or(anyhow!("An image sequence needs at least one image."))?;
+        let dimensions = image::image_dimensions(first)?;
+        Ok(Self {
+            paths,
+            frame_rate,
+            dimensions,
+            next: 0,
+            #[cfg(feature = "audio")]
+            audio: None,
+        })
+    }
+
+    /// Sets the audio track played along with the sequence and returns self.
+    #[cfg(feature = "audio")]
+    pub fn audio(mut self, audio: SoundData) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+}
+
+impl VideoDecoder for ImageSequence {
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn next_frame(&mut self) -> Result<Option<VideoFrame>> {
+        let Some(path) = self.paths.get(self.next) else {
+            return Ok(None);
+        };
+        let image = image::open(path)?.to_rgba8();
+        if image.dimensions() != self.dimensions {
+            return Err(anyhow!(
+                "The frame {} does not have the size of the first frame.",
+                path.display()
+            ));
+        }
+        let time = Duration::from_secs_f64(self.next as f64 / self.frame_rate);
+        self.next += 1;
+        Ok(Some(VideoFrame {
+            data: image.into_raw(),
+            time,
+        }))
+    }
+
+    fn rewind(&mut self) -> Result<()> {
+        self.next = 0;
+        Ok(())
+    }
+
+    fn skip_to(&mut self, position: Duration) -> Result<()> {
+        let frame = (position.as_secs_f64() * self.frame_rate) as usize;
+        self.next = self.next.max(frame.min(self.paths.len()));
+        Ok(())
+    }
+
+    #[cfg(feature = "audio")]
+    fn audio(&mut self) -> Result<Option<SoundData>> {
+        Ok(self.audio.clone())
+    }
+}
+
+/// The state of a [`VideoPlayer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VideoState {
+    Stopped,
+    Playing,
+    Paused,
+    /// The last frame got shown and the video does not loop.
+    Finished,
+}
+
+/// Plays a video into a texture, available with the `video` feature.
+///
+/// The texture can be used by any material like a normal one. Call [`update`](VideoPlayer::update) every frame
+/// to show the frame of the current time. With the `audio` feature the audio track of the decoder plays along,
+/// and its position is the clock of the video, so both stay in sync. Frames the player fell behind on get skipped.
+///
+/// # Usage
+/// ```ignore
+/// let mut player = VideoPlayer::new(ImageSequence::new(frames, 30.0)?)?;
+/// let material = Material::new_default_textured(player.texture());
+/// player.set_looping(true);
+/// player.play()?;
+///
+/// // every frame
+/// player.update()?;
+/// ```
+pub struct VideoPlayer {
+    decoder: Box<dyn VideoDecoder>,
+    texture: Texture,
+    /// The next decoded frame, waiting for its time.
+    pending: Option<VideoFrame>,
+    state: VideoState,
+    looping: bool,
+    /// The time the playback started at, moved forward by pauses.
+    started: Option<Instant>,
+    /// The position the playback got paused at.
+    paused_at: Duration,
+    #[cfg(feature = "audio")]
+    sound: Option<Sound>,
+}
+
+impl VideoPlayer {
+    /// Creates a player showing the first frame of the video.
+    pub fn new(decoder: impl VideoDecoder + 'static) -> Result<Self> {
+        let mut decoder: Box<dyn VideoDecoder> = Box::new(decoder);
+        let texture = Texture::empty(
+            decoder.dimensions(),
+            Format::RGBA8,
+            1,
+            TextureSettings::default(),
+            Some("video"),
+        )?;
+        #[cfg(feature = "audio")]
+        let sound = decoder
+            .audio()?
+            .map(|data| Sound::new(data, SoundSettings::new()));
+        let mut player = Self {
+            decoder,
+            texture,
+            pending: None,
+            state: VideoState::Stopped,
+            looping: false,
+            started: None,
+            paused_at: Duration::ZERO,
+            #[cfg(feature = "audio")]
+            sound,
+        };
+        player.show_next()?;
+        Ok(player)
+    }
+
+    /// Returns the texture the video gets played into.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the audio track playing along with the video.
+    #[cfg(feature = "audio")]
+    pub fn sound(&mut self) -> Option<&mut Sound> {
+        self.sound.as_mut()
+    }
+
+    pub fn state(&self) -> VideoState {
+        self.state
+    }
+
+    /// Returns true if the video starts over after the last frame.
+    pub fn looping(&self) -> bool {
+        self.looping
+    }
+
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Starts playing the video, from the start in case it finished or got stopped.
+    pub fn play(&mut self) -> Result<()> {
+        match self.state {
+            VideoState::Playing => return Ok(()),
+            VideoState::Paused => {
+                self.resume();
+                return Ok(());
+            }
+            VideoState::Finished => self.rewind()?,
+            VideoState::Stopped => (),
+        }
+        self.started = Some(Instant::now());
+        self.state = VideoState::Playing;
+        #[cfg(feature = "audio")]
+        if let Some(sound) = &mut self.sound {
+            sound.play()?;
+        }
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        if self.state != VideoState::Playing {
+            return;
+        }
+        self.paused_at = self.position();
+        self.state = VideoState::Paused;
+        #[cfg(feature = "audio")]
+        if let Some(sound) = &mut self.sound {
+            sound.pause(Tween::default());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state != VideoState::Paused {
+            return;
+        }
+        self.started = Instant::now().checked_sub(self.paused_at);
+        self.state = VideoState::Playing;
+        #[cfg(feature = "audio")]
+        if let Some(sound) = &mut self.sound {
+            sound.resume(Tween::default());
+        }
+    }
+
+    /// Stops the video and shows its first frame.
+    pub fn stop(&mut self) -> Result<()> {
+        #[cfg(feature = "audio")]
+        if let Some(sound) = &mut self.sound {
+            sound.stop(Tween::default());
+        }
+        self.state = VideoState::Stopped;
+        self.rewind()
+    }
+
+    /// Returns the current position of the playback.
+    pub fn position(&self) -> Duration {
+        match self.state {
+            VideoState::Playing => {
+                #[cfg(feature = "audio")]
+                if let Some(sound) = self
+                    .sound
+                    .as_ref()
+                    .filter(|sound| sound.state() == PlaybackState::Playing)
+                {
+                    return Duration::from_secs_f64(sound.position());
+                }
+                self.started.map_or(Duration::ZERO, |start| start.elapsed())
+            }
+            VideoState::Paused => self.paused_at,
+            VideoState::Stopped | VideoState::Finished => Duration::ZERO,
+        }
+    }
+
+    /// Shows the newest frame of the current position, skipping the frames before it.
+    pub fn update(&mut self) -> Result<()> {
+        if self.state != VideoState::Playing {
+            return Ok(());
+        }
+        let position = self.position();
+        let mut due = None;
+        let mut ended = false;
+        loop {
+            let frame = match self.pending.take() {
+                Some(frame) => frame,
+                None => {
+                    self.decoder.skip_to(position)?;
+                    match self.decoder.next_frame()? {
+                        Some(frame) => frame,
+                        None => {
+                            ended = true;
+                            break;
+                        }
+                    }
+                }
+            };
+            if frame.time > position {
+                self.pending = Some(frame);
+                break;
+            }
+            due = Some(frame);
+        }
+        if let Some(frame) = due {
+            self.write(&frame)?;
+        }
+        if ended {
+            if self.looping {
+                self.decoder.rewind()?;
+                self.started = Some(Instant::now());
+                #[cfg(feature = "audio")]
+                if let Some(sound) = &mut self.sound {
+                    sound.seek_to(0.0);
+                    sound.play()?;
+                }
+            } else {
+                self.state = VideoState::Finished;
+            }
+        }
+        Ok(())
+    }
+
+    /// Goes back to the start and shows the first frame.
+    fn rewind(&mut self) -> Result<()> {
+        self.decoder.rewind()?;
+        self.pending = None;
+        self.started = None;
+        self.paused_at = Duration::ZERO;
+        self.show_next()
+    }
+
+    /// Shows the next frame of the decoder right away.
+    fn show_next(&mut self) -> Result<()> {
+        if let Some(frame) = self.decoder.next_frame()? {
+            self.write(&frame)?;
+        }
+        Ok(())
+    }
+
+    fn write(&self, frame: &VideoFrame) -> Result<()> {
+        let (width, height) = self.decoder.dimensions();
+        self.texture
+            .write_region(TextureRegion::new((0, 0), (width, height)), &frame.data)?;
+        // Damage tracking does not see the contents of textures change.
+        crate::SETTINGS.graphics.request_redraw();
+        Ok(())
+    }
+}
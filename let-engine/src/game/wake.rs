@@ -0,0 +1,70 @@
+//! Waking up the event loop from other threads while its control flow waits for events.
+
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use winit::event_loop::EventLoopProxy;
+
+static PROXY: OnceLock<Mutex<EventLoopProxy<()>>> = OnceLock::new();
+
+/// The earliest time a frame got requested to be drawn at, for example by egui animations.
+static REDRAW_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Stores the proxy of the event loop to wake it up with.
+pub(crate) fn init(proxy: EventLoopProxy<()>) {
+    let _ = PROXY.set(Mutex::new(proxy));
+}
+
+/// Requests a frame after the given delay, waking up the event loop to wait for it.
+pub(crate) fn redraw_after(delay: Duration) {
+    let at = Instant::now() + delay;
+    {
+        let mut redraw_at = REDRAW_AT.lock();
+        if redraw_at.is_some_and(|current| current <= at) {
+            return;
+        }
+        *redraw_at = Some(at);
+    }
+    if let Some(proxy) = PROXY.get() {
+        // Fails after the event loop exited, when nothing has to be drawn anymore.
+        let _ = proxy.lock().send_event(());
+    }
+}
+
+/// Returns the time of the next requested frame.
+pub(crate) fn redraw_at() -> Option<Instant> {
+    *REDRAW_AT.lock()
+}
+
+/// Returns true and forgets the requested frame in case it is due.
+pub(crate) fn take_due() -> bool {
+    let mut redraw_at = REDRAW_AT.lock();
+    if redraw_at.is_some_and(|at| at <= Instant::now()) {
+        *redraw_at = None;
+        true
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn earliest_request_wins() {
+        redraw_after(Duration::from_secs(60));
+        redraw_after(Duration::from_secs(30));
+        redraw_after(Duration::from_secs(90));
+        let at = redraw_at().unwrap();
+        assert!(at <= Instant::now() + Duration::from_secs(30));
+        assert!(!take_due());
+
+        redraw_after(Duration::ZERO);
+        assert!(take_due());
+        assert_eq!(redraw_at(), None);
+    }
+}
@@ -4,10 +4,16 @@
 //! [![Website](https://img.shields.io/website?up_message=Up&up_color=f6ffa6&down_message=Down&down_color=lightgrey&url=https%3A%2F%2Flet-server.net%2F&style=for-the-badge&logo=apache&color=f6ffa6&link=https%3A%2F%2Flet-server.net%2F)](https://let-server.net/)
 //!
 //! A Game engine made in Rust.
+mod capabilities;
+#[cfg(feature = "crash_handler")]
+mod crash;
 mod game;
 
 #[cfg(feature = "asset_system")]
 pub use asset_system;
+pub use capabilities::*;
+#[cfg(feature = "crash_handler")]
+pub use crash::CrashHandler;
 pub use game::*;
 pub mod prelude;
 #[cfg(feature = "audio")]
@@ -29,21 +35,59 @@ mod check_feature_dependency {
 #[cfg(feature = "egui")]
 pub use egui_winit_vulkano::egui;
 
+/// Textures, models, materials and everything else loaded to the GPU.
 #[cfg(feature = "client")]
-pub use let_engine_core::resources;
+pub mod resources {
+    pub use let_engine_core::resources::*;
+
+    /// Returns a report of all textures and models using GPU memory and the sounds in the
+    /// [`RESOURCE_CACHE`](crate::RESOURCE_CACHE) for debugging memory usage.
+    pub fn report() -> crate::ResourceReport {
+        crate::RESOURCE_CACHE.report()
+    }
+}
+#[cfg(feature = "client")]
+pub use let_engine_core::uniform_block;
 pub use let_engine_core::{camera, objects, Direction};
 
 /// Structs about drawing related things.
 #[cfg(feature = "client")]
 pub mod draw {
-    pub use let_engine_core::draw::{Graphics, PresentMode, ShaderError, VulkanError};
+    pub use let_engine_core::draw::{
+        ControlFlow, FramePacing, GpuPass, GpuTimings, Graphics, PresentMode, RenderStats,
+        ShaderError, VulkanError,
+    };
 }
 
 /// General time methods of the game engine.
 pub static TIME: LazyLock<Time> = LazyLock::new(Time::default);
+/// The scheduler running timers and async tasks on game time.
+pub static SCHEDULER: LazyLock<Scheduler> = LazyLock::new(Scheduler::new);
+/// All animations currently playing on objects.
+pub static ANIMATIONS: LazyLock<Animations> = LazyLock::new(Animations::new);
+/// Random numbers for the game logic, the same on every peer seeded with the same seed.
+pub static RANDOM: LazyLock<Random> = LazyLock::new(Random::new);
+/// Values shared between systems by name.
+pub static BLACKBOARD: LazyLock<Blackboard> = LazyLock::new(Blackboard::new);
+/// Timings of the engine systems of the last frames.
+pub static PROFILER: LazyLock<Profiler> = LazyLock::new(Profiler::new);
+/// The undo and redo history of scene changes made through commands, shared by the inspector and editor tools.
+pub static SCENE_HISTORY: LazyLock<objects::SceneHistory> =
+    LazyLock::new(objects::SceneHistory::new);
+/// Changes to the scene pushed from any thread, applied before every frame gets drawn and after the logic of every tick.
+pub static SCENE_QUEUE: LazyLock<objects::SceneQueue> = LazyLock::new(objects::SceneQueue::new);
 /// The input system holding the state of every key and the mouse position.
 #[cfg(feature = "client")]
 pub static INPUT: LazyLock<input::Input> = LazyLock::new(input::Input::new);
+/// The cache of textures and sounds loaded from the assets, with memory reports and a GPU memory budget.
+#[cfg(feature = "client")]
+pub static RESOURCE_CACHE: LazyLock<ResourceCache> = LazyLock::new(ResourceCache::new);
+/// Restarting the game after a new build while keeping its state, for development.
+#[cfg(feature = "hot_state")]
+pub static HOT_STATE: LazyLock<HotState> = LazyLock::new(HotState::new);
+/// The built in debug windows drawn with egui.
+#[cfg(feature = "egui")]
+pub static INSPECTOR: LazyLock<Inspector> = LazyLock::new(Inspector::new);
 
 /// General settings for the game engine.
 #[cfg(all(feature = "client", feature = "audio"))]
@@ -21,10 +21,19 @@ pub use data::*;
 #[cfg(feature = "client")]
 mod client {
     pub use super::materials::*;
+    pub use super::mesh::*;
+    #[cfg(feature = "svg")]
+    pub use super::svg::*;
     pub use super::textures::*;
+    pub use super::uniforms::*;
     pub use super::window::*;
     pub use crate::events::*;
-    pub use let_engine_core::draw::PresentMode;
+    pub use let_engine_core::draw::{
+        ColorSpace, ControlFlow, FramePacing, HdrSettings, PresentMode, Tonemapping,
+    };
+    pub use let_engine_core::render_callbacks::{
+        LayerView, RenderCallbackId, RenderContext, RenderStage,
+    };
 }
 #[cfg(feature = "client")]
 pub use client::*;
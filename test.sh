@@ -1,6 +1,13 @@
+# `simd` and `determinism` can not be enabled together, so instead of `--all-features`
+# every feature except `determinism` gets tested, and `determinism` in a separate run without `simd`.
+FEATURES="let-engine/egui,let-engine/vulkan_debug_utils,let-engine/hot_reload,let-engine/glsl,let-engine/svg,let-engine/asset_system,let-engine/serde,let-engine/networking,let-engine/encryption,let-engine/tracing,let-engine/hot_state,let-engine/video,let-engine/crash_handler,let-engine/android,let-engine/rand,let-engine/fast-math,let-engine/deflate,let-engine/bzip2,let-engine/zstd,let-engine/lzma,let-engine/lz4"
+DETERMINISM="let-engine/determinism,let-engine/client,let-engine/audio,let-engine/serde,let-engine/networking"
+
 cargo clippy --no-default-features
 cargo clippy
-cargo clippy --all-features
+cargo clippy --features "$FEATURES"
+cargo clippy --no-default-features --features "$DETERMINISM"
 cargo test --no-default-features
 cargo test
-cargo test --all-features
+cargo test --features "$FEATURES"
+cargo test --no-default-features --features "$DETERMINISM"